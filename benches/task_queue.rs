@@ -0,0 +1,73 @@
+//! Queue operations at the scale of the stress test. Run with `cargo bench`.
+
+use criterion::{criterion_group, criterion_main, BatchSize, Criterion};
+use functional_rust_ui_demo::queue::TaskId;
+use functional_rust_ui_demo::stress::{self, StressTask, STRESS_TASK_COUNT};
+use functional_rust_ui_demo::TaskQueue;
+
+fn filled_queue() -> (TaskQueue, Vec<TaskId>) {
+    let queue = TaskQueue::new();
+    let ids = stress::enqueue(&queue, STRESS_TASK_COUNT);
+    (queue, ids)
+}
+
+fn bench_queue(c: &mut Criterion) {
+    let mut group = c.benchmark_group("100k tasks");
+    group.sample_size(10);
+    group.bench_function("add one by one", |b| {
+        b.iter_batched(
+            TaskQueue::new,
+            |queue| {
+                for _ in 0..STRESS_TASK_COUNT {
+                    queue.add_task(StressTask::new(20));
+                }
+                queue
+            },
+            BatchSize::LargeInput,
+        )
+    });
+    group.bench_function("add batched", |b| {
+        b.iter_batched(
+            TaskQueue::new,
+            |queue| {
+                stress::enqueue(&queue, STRESS_TASK_COUNT);
+                queue
+            },
+            BatchSize::LargeInput,
+        )
+    });
+    group.bench_function("poll", |b| {
+        b.iter_batched(
+            filled_queue,
+            |(queue, ids)| {
+                for &id in &ids {
+                    let _ = queue.try_poll_task(id);
+                }
+                queue
+            },
+            BatchSize::LargeInput,
+        )
+    });
+    group.bench_function("snapshot", |b| {
+        let (queue, _) = filled_queue();
+        b.iter(|| queue.records())
+    });
+    group.bench_function("snapshot of new tasks", |b| {
+        let (queue, _) = filled_queue();
+        b.iter(|| queue.records_from(STRESS_TASK_COUNT - 100))
+    });
+    group.bench_function("cancel batched", |b| {
+        b.iter_batched(
+            filled_queue,
+            |(queue, ids)| {
+                queue.remove_tasks(&ids);
+                queue
+            },
+            BatchSize::LargeInput,
+        )
+    });
+    group.finish();
+}
+
+criterion_group!(benches, bench_queue);
+criterion_main!(benches);
@@ -0,0 +1,73 @@
+//! Minimal task-kind plugin: counts down `steps` steps of `step_ms` milliseconds each.
+//!
+//! Build it with `cargo build --example countdown_plugin --features plugins` and copy the library
+//! from `target/debug/examples/` into the app's `plugins/` directory.
+
+use std::collections::BTreeMap;
+use std::ffi::CStr;
+use std::os::raw::c_char;
+use std::time::Duration;
+
+use functional_rust_ui_demo::plugin_api::{PluginDescriptor, PluginHost, PLUGIN_ABI_VERSION};
+
+struct Descriptor(PluginDescriptor);
+
+// SAFETY: the descriptor only points at static, immutable data.
+unsafe impl Sync for Descriptor {}
+
+static DESCRIPTOR: Descriptor = Descriptor(PluginDescriptor {
+    abi_version: PLUGIN_ABI_VERSION,
+    name: b"countdown\0".as_ptr() as *const c_char,
+    param_schema: concat!(
+        r#"[{"name": "steps", "type": "number", "default": "10", "min": 1, "max": 1000000},"#,
+        r#" {"name": "step_ms", "type": "number", "default": "500", "min": 0}]"#,
+        "\0"
+    )
+    .as_ptr() as *const c_char,
+    run,
+});
+
+#[no_mangle]
+pub extern "C" fn task_queue_plugin_descriptor() -> *const PluginDescriptor {
+    &DESCRIPTOR.0
+}
+
+unsafe extern "C" fn run(
+    host: *const PluginHost,
+    params: *const c_char,
+    error: *mut c_char,
+    error_len: usize,
+) -> i32 {
+    let host = &*host;
+    let params: BTreeMap<String, String> =
+        match serde_json::from_slice(CStr::from_ptr(params).to_bytes()) {
+            Ok(params) => params,
+            Err(e) => return write_error(&e.to_string(), error, error_len),
+        };
+    let number = |name: &str| params.get(name).and_then(|value| value.parse::<u64>().ok());
+    let (steps, step_ms) = match (number("steps"), number("step_ms")) {
+        (Some(steps), Some(step_ms)) if steps > 0 => (steps, step_ms),
+        _ => {
+            return write_error(
+                "steps and step_ms must be positive integers",
+                error,
+                error_len,
+            )
+        }
+    };
+    for step in 0..steps {
+        if (host.checkpoint)(host.ctx) != 0 {
+            return 0;
+        }
+        std::thread::sleep(Duration::from_millis(step_ms));
+        (host.report_progress)(host.ctx, (step + 1) as f32 / steps as f32);
+    }
+    0
+}
+
+unsafe fn write_error(message: &str, error: *mut c_char, error_len: usize) -> i32 {
+    let len = message.len().min(error_len.saturating_sub(1));
+    std::ptr::copy_nonoverlapping(message.as_ptr() as *const c_char, error, len);
+    *error.add(len) = 0;
+    1
+}
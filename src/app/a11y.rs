@@ -0,0 +1,36 @@
+//! Screen reader names and values, through AccessKit, for widgets whose text alone says
+//! too little: rows of identical buttons, progress bars, and the line announcing changes.
+
+use egui::accesskit::{Live, Role};
+use egui::Response;
+
+use crate::app::format;
+
+/// Replaces the name egui gave a widget from its text, e.g. "Cancel" becomes "Cancel
+/// task 3".
+pub fn name(response: &Response, name: String) {
+    response.ctx.accesskit_node_builder(response.id, |node| {
+        node.set_name(name);
+    });
+}
+
+/// Describes a progress bar as one: its name, and its value as a percentage.
+pub fn progress(response: &Response, name: String, fraction: f32) {
+    let percent = f64::from(fraction.clamp(0.0, 1.0)) * 100.0;
+    response.ctx.accesskit_node_builder(response.id, |node| {
+        node.set_role(Role::ProgressIndicator);
+        node.set_name(name);
+        node.set_min_numeric_value(0.0);
+        node.set_max_numeric_value(100.0);
+        node.set_numeric_value(percent);
+        node.set_value(format::percent(fraction.clamp(0.0, 1.0), 1));
+    });
+}
+
+/// Makes a label a live region, so that screen readers read out its new text whenever it
+/// changes, without the user moving to it.
+pub fn live(response: &Response) {
+    response.ctx.accesskit_node_builder(response.id, |node| {
+        node.set_live(Live::Polite);
+    });
+}
@@ -0,0 +1,42 @@
+#[cfg(test)]
+use egui::accesskit::{Live, Role};
+
+#[cfg(test)]
+use crate::app::a11y;
+
+#[test]
+fn test_progress_and_live_nodes() {
+    let ctx = egui::Context::default();
+    ctx.enable_accesskit();
+    let output = ctx.run(egui::RawInput::default(), |ctx| {
+        egui::CentralPanel::default().show(ctx, |ui| {
+            let bar = ui.add(egui::ProgressBar::new(0.425));
+            a11y::progress(&bar, "Task 3 progress, running".to_owned(), 0.425);
+            let cancel = ui.button("Cancel");
+            a11y::name(&cancel, "Cancel task 3".to_owned());
+            let announcement = ui.label("sleep task 2 completed");
+            a11y::live(&announcement);
+        });
+    });
+    let update = output
+        .platform_output
+        .accesskit_update
+        .expect("accesskit is enabled");
+    let nodes: Vec<_> = update.nodes.iter().map(|(_, node)| node).collect();
+
+    let bar = nodes
+        .iter()
+        .find(|node| node.role() == Role::ProgressIndicator)
+        .expect("a progress indicator");
+    assert_eq!(bar.name(), Some("Task 3 progress, running"));
+    assert_eq!(bar.value(), Some("42.5%"));
+    assert_eq!(bar.max_numeric_value(), Some(100.0));
+
+    assert!(nodes
+        .iter()
+        .any(|node| node.role() == Role::Button && node.name() == Some("Cancel task 3")));
+    assert!(nodes
+        .iter()
+        .any(|node| node.live() == Some(Live::Polite)
+            && node.name() == Some("sleep task 2 completed")));
+}
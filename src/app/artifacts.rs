@@ -0,0 +1,51 @@
+//! Opening what tasks produced with the platform's own handlers, through the `opener`
+//! crate. The web build has nothing to open them with.
+
+use std::io;
+use std::path::Path;
+
+use crate::app::history::Artifact;
+
+/// Opens `path` or a URL with the platform's default handler.
+pub fn open_path(path: &Path) -> io::Result<()> {
+    #[cfg(not(target_arch = "wasm32"))]
+    return opener::open(path).map_err(|e| io::Error::new(io::ErrorKind::Other, e));
+    #[cfg(target_arch = "wasm32")]
+    {
+        let _ = path;
+        Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "cannot open files in the browser",
+        ))
+    }
+}
+
+/// Opens a file with its default application or a URL in the browser. Text has nothing
+/// to open it with; copy it instead.
+pub fn open(artifact: &Artifact) -> io::Result<()> {
+    match artifact {
+        Artifact::File { path } => open_path(path),
+        Artifact::Url { url } => open_path(Path::new(url)),
+        Artifact::Text { .. } => Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "text artifacts cannot be opened",
+        )),
+    }
+}
+
+/// Opens the folder containing `path` in the file manager.
+pub fn open_containing_folder(path: &Path) -> io::Result<()> {
+    match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => open_path(parent),
+        _ => open_path(Path::new(".")),
+    }
+}
+
+/// The first file among `artifacts`, which completion toasts and the History window's
+/// context menu offer to open.
+pub fn first_file(artifacts: &[Artifact]) -> Option<&Path> {
+    artifacts.iter().find_map(|artifact| match artifact {
+        Artifact::File { path } => Some(path.as_path()),
+        _ => None,
+    })
+}
@@ -0,0 +1,168 @@
+//! Files the app needs at runtime, built into the binary so that it runs from any
+//! directory.
+//!
+//! A file of the same name in the assets directory (`assets_dir` in `config.toml`,
+//! `assets/` in the config dir by default) is used instead of the built-in one. Fonts are
+//! not built in, since egui brings its own, but any `.ttf` or `.otf` file in that
+//! directory's `fonts/` folder is tried before egui's.
+
+use std::borrow::Cow;
+use std::path::{Path, PathBuf};
+
+use crate::app::config::AppConfig;
+
+/// The window icon, as an `.ico` or any other format the `image` crate reads.
+pub const ICON: &str = "icon.ico";
+/// Folder of the assets directory holding extra fonts.
+pub const FONTS_DIR: &str = "fonts";
+
+const EMBEDDED: &[(&str, &[u8])] = &[(
+    ICON,
+    include_bytes!("../../assets/tesseract-logo-houndstoothed-alpha.ico"),
+)];
+
+pub struct Assets {
+    dir: Option<PathBuf>,
+}
+
+impl Assets {
+    /// Assets overridden from `dir`, if given.
+    pub fn new(dir: Option<PathBuf>) -> Self {
+        Assets { dir }
+    }
+
+    pub fn from_config(config: &AppConfig) -> Self {
+        match config.resolved_assets_dir() {
+            Ok(dir) => Self::new(Some(dir)),
+            Err(e) => {
+                log::warn!("Using the built-in assets only: {}", e);
+                Self::new(None)
+            }
+        }
+    }
+
+    /// Reads `config.toml` for the assets directory; for use before the app is created.
+    pub fn from_default_config() -> Self {
+        let config = AppConfig::default_path()
+            .and_then(|path| AppConfig::load_or_default(&path))
+            .unwrap_or_else(|e| {
+                log::warn!("Cannot read the config for the assets directory: {}", e);
+                AppConfig::default()
+            });
+        Self::from_config(&config)
+    }
+
+    /// The override of `name` if there is one, else the built-in file, if any.
+    pub fn get(&self, name: &str) -> Option<Cow<'static, [u8]>> {
+        if let Some(bytes) = self.read_override(name) {
+            return Some(Cow::Owned(bytes));
+        }
+        embedded(name).map(Cow::Borrowed)
+    }
+
+    fn read_override(&self, name: &str) -> Option<Vec<u8>> {
+        let path = self.dir.as_ref()?.join(name);
+        match std::fs::read(&path) {
+            Ok(bytes) => Some(bytes),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => None,
+            Err(e) => {
+                log::warn!(
+                    "Cannot read {}, using the built-in one: {}",
+                    path.display(),
+                    e
+                );
+                None
+            }
+        }
+    }
+
+    /// The window icon, falling back to the built-in one if the override cannot be decoded.
+    pub fn window_icon(&self) -> Option<eframe::IconData> {
+        if let Some(bytes) = self.read_override(ICON) {
+            match decode_icon(&bytes) {
+                Ok(icon) => return Some(icon),
+                Err(e) => log::warn!("Cannot decode the {} override: {}", ICON, e),
+            }
+        }
+        match decode_icon(embedded(ICON)?) {
+            Ok(icon) => Some(icon),
+            Err(e) => {
+                log::error!("Cannot decode the built-in icon: {}", e);
+                None
+            }
+        }
+    }
+
+    /// egui's fonts with those in the `fonts/` folder in front, in file name order, or
+    /// `None` if there are none.
+    pub fn fonts(&self) -> Option<egui::FontDefinitions> {
+        let dir = self.dir.as_ref()?.join(FONTS_DIR);
+        let files = match font_files(&dir) {
+            Ok(files) => files,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return None,
+            Err(e) => {
+                log::warn!("Cannot list fonts in {}: {}", dir.display(), e);
+                return None;
+            }
+        };
+        let mut fonts = egui::FontDefinitions::default();
+        let mut names = Vec::new();
+        for path in files {
+            let bytes = match std::fs::read(&path) {
+                Ok(bytes) => bytes,
+                Err(e) => {
+                    log::warn!("Cannot read font {}: {}", path.display(), e);
+                    continue;
+                }
+            };
+            let name = path.to_string_lossy().into_owned();
+            fonts
+                .font_data
+                .insert(name.clone(), egui::FontData::from_owned(bytes));
+            names.push(name);
+        }
+        if names.is_empty() {
+            return None;
+        }
+        for family in [egui::FontFamily::Proportional, egui::FontFamily::Monospace] {
+            fonts
+                .families
+                .entry(family)
+                .or_default()
+                .splice(0..0, names.iter().cloned());
+        }
+        Some(fonts)
+    }
+}
+
+fn embedded(name: &str) -> Option<&'static [u8]> {
+    EMBEDDED
+        .iter()
+        .find(|(embedded_name, _)| *embedded_name == name)
+        .map(|(_, bytes)| *bytes)
+}
+
+fn font_files(dir: &Path) -> std::io::Result<Vec<PathBuf>> {
+    let mut files: Vec<PathBuf> = std::fs::read_dir(dir)?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| {
+            path.extension()
+                .and_then(|ext| ext.to_str())
+                .map_or(false, |ext| {
+                    ext.eq_ignore_ascii_case("ttf") || ext.eq_ignore_ascii_case("otf")
+                })
+        })
+        .collect();
+    files.sort();
+    Ok(files)
+}
+
+fn decode_icon(bytes: &[u8]) -> Result<eframe::IconData, image::ImageError> {
+    let icon = image::load_from_memory(bytes)?.into_rgba8();
+    let (width, height) = icon.dimensions();
+    Ok(eframe::IconData {
+        rgba: icon.into_raw(),
+        width,
+        height,
+    })
+}
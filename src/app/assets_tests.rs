@@ -0,0 +1,44 @@
+#[cfg(test)]
+use crate::app::assets::{Assets, ICON};
+
+#[cfg(test)]
+fn assets_dir(name: &str) -> std::path::PathBuf {
+    let dir = std::env::temp_dir().join(format!("{}_{}", name, std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+#[test]
+fn test_builtin_icon_decodes() {
+    let icon = Assets::new(None).window_icon().unwrap();
+    assert!(icon.width > 0 && icon.height > 0);
+    assert_eq!(icon.rgba.len(), (icon.width * icon.height * 4) as usize);
+}
+
+#[test]
+fn test_override_replaces_builtin_icon() {
+    let dir = assets_dir("assets_override");
+    image::RgbaImage::new(3, 2)
+        .save_with_format(dir.join(ICON), image::ImageFormat::Png)
+        .unwrap();
+    let assets = Assets::new(Some(dir.clone()));
+    assert_eq!(
+        assets.get(ICON).unwrap().as_ref(),
+        std::fs::read(dir.join(ICON)).unwrap().as_slice()
+    );
+    let icon = assets.window_icon().unwrap();
+    assert_eq!((icon.width, icon.height), (3, 2));
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_undecodable_override_falls_back_to_builtin_icon() {
+    let dir = assets_dir("assets_broken");
+    std::fs::write(dir.join(ICON), b"not an image").unwrap();
+    let builtin = Assets::new(None).window_icon().unwrap();
+    let icon = Assets::new(Some(dir.clone())).window_icon().unwrap();
+    assert_eq!((icon.width, icon.height), (builtin.width, builtin.height));
+    assert!(Assets::new(Some(dir.clone())).fonts().is_none());
+    std::fs::remove_dir_all(&dir).unwrap();
+}
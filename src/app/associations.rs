@@ -0,0 +1,167 @@
+use std::fmt::{Display, Formatter, Result as FmtResult};
+#[cfg(not(any(target_os = "windows", target_os = "macos")))]
+use std::path::PathBuf;
+use std::process::Command;
+
+use crate::app::job_file::JOB_FILE_EXTENSION;
+use crate::app::launch_args::URL_SCHEME;
+
+#[cfg(not(any(target_os = "windows", target_os = "macos")))]
+const JOB_FILE_MIME_TYPE: &str = "application/x-taskqueue";
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum AssociationError {
+    Io(String),
+    Command(String),
+    Unsupported(&'static str),
+}
+
+impl Display for AssociationError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        match self {
+            AssociationError::Io(e) => write!(f, "Association I/O error: {}", e),
+            AssociationError::Command(e) => write!(f, "Association registration failed: {}", e),
+            AssociationError::Unsupported(reason) => write!(f, "{}", reason),
+        }
+    }
+}
+
+/// Registers this executable as the handler for `taskqueue://` links for the current user.
+///
+/// The browser then launches the app with the link as its only argument, which the
+/// single-instance guard forwards to the running instance.
+pub fn register_url_scheme() -> Result<(), AssociationError> {
+    url_scheme_for(&current_exe()?)
+}
+
+/// Registers this executable as the program that opens `.taskqueue` job files for the
+/// current user, so double-clicking one loads it like File → Open.
+pub fn register_job_files() -> Result<(), AssociationError> {
+    job_files_for(&current_exe()?)
+}
+
+fn current_exe() -> Result<String, AssociationError> {
+    std::env::current_exe()
+        .map(|exe| exe.display().to_string())
+        .map_err(|e| AssociationError::Io(e.to_string()))
+}
+
+#[cfg(target_os = "windows")]
+fn url_scheme_for(exe: &str) -> Result<(), AssociationError> {
+    let key = format!(r"HKCU\Software\Classes\{}", URL_SCHEME);
+    let description = format!("URL:{} Protocol", URL_SCHEME);
+    run(Command::new("reg").args(["add", &key, "/ve", "/d", &description, "/f"]))?;
+    run(Command::new("reg").args(["add", &key, "/v", "URL Protocol", "/d", "", "/f"]))?;
+    register_open_command(&key, exe)
+}
+
+#[cfg(target_os = "windows")]
+fn job_files_for(exe: &str) -> Result<(), AssociationError> {
+    let prog_id = "FunctionalRustUiDemo.JobFile";
+    let extension_key = format!(r"HKCU\Software\Classes\.{}", JOB_FILE_EXTENSION);
+    let prog_id_key = format!(r"HKCU\Software\Classes\{}", prog_id);
+    run(Command::new("reg").args(["add", &extension_key, "/ve", "/d", prog_id, "/f"]))?;
+    run(Command::new("reg").args(["add", &prog_id_key, "/ve", "/d", "Task queue job", "/f"]))?;
+    register_open_command(&prog_id_key, exe)
+}
+
+#[cfg(target_os = "windows")]
+fn register_open_command(key: &str, exe: &str) -> Result<(), AssociationError> {
+    let command = format!("\"{}\" \"%1\"", exe);
+    run(Command::new("reg").args([
+        "add",
+        &format!(r"{}\shell\open\command", key),
+        "/ve",
+        "/d",
+        &command,
+        "/f",
+    ]))
+}
+
+#[cfg(target_os = "macos")]
+fn url_scheme_for(_exe: &str) -> Result<(), AssociationError> {
+    Err(AssociationError::Unsupported(
+        "On macOS the URL scheme is declared in the app bundle's Info.plist (CFBundleURLTypes)",
+    ))
+}
+
+#[cfg(target_os = "macos")]
+fn job_files_for(_exe: &str) -> Result<(), AssociationError> {
+    Err(AssociationError::Unsupported(
+        "On macOS document types are declared in the app bundle's Info.plist (CFBundleDocumentTypes)",
+    ))
+}
+
+#[cfg(not(any(target_os = "windows", target_os = "macos")))]
+fn url_scheme_for(exe: &str) -> Result<(), AssociationError> {
+    let mime_type = format!("x-scheme-handler/{}", URL_SCHEME);
+    install_desktop_entry(&format!("{}.desktop", URL_SCHEME), exe, "%u", &mime_type)
+}
+
+#[cfg(not(any(target_os = "windows", target_os = "macos")))]
+fn job_files_for(exe: &str) -> Result<(), AssociationError> {
+    let data_dir = data_dir()?;
+    let packages_dir = data_dir.join("mime").join("packages");
+    std::fs::create_dir_all(&packages_dir).map_err(|e| AssociationError::Io(e.to_string()))?;
+    let definition = format!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+         <mime-info xmlns=\"http://www.freedesktop.org/standards/shared-mime-info\">\n  \
+         <mime-type type=\"{}\">\n    \
+         <comment>Task queue job</comment>\n    \
+         <glob pattern=\"*.{}\"/>\n  \
+         </mime-type>\n\
+         </mime-info>\n",
+        JOB_FILE_MIME_TYPE, JOB_FILE_EXTENSION
+    );
+    std::fs::write(packages_dir.join("functional_rust_ui_demo.xml"), definition)
+        .map_err(|e| AssociationError::Io(e.to_string()))?;
+    run(Command::new("update-mime-database").arg(data_dir.join("mime")))?;
+    install_desktop_entry("job.desktop", exe, "%f", JOB_FILE_MIME_TYPE)
+}
+
+/// Writes a hidden desktop entry launching `exe` for `mime_type` and makes it the default.
+#[cfg(not(any(target_os = "windows", target_os = "macos")))]
+fn install_desktop_entry(
+    suffix: &str,
+    exe: &str,
+    field_code: &str,
+    mime_type: &str,
+) -> Result<(), AssociationError> {
+    let desktop_file = format!("functional_rust_ui_demo-{}", suffix);
+    let applications_dir = data_dir()?.join("applications");
+    std::fs::create_dir_all(&applications_dir).map_err(|e| AssociationError::Io(e.to_string()))?;
+    let entry = format!(
+        "[Desktop Entry]\n\
+         Type=Application\n\
+         Name=Functional Rust UI Demo\n\
+         Exec=\"{}\" {}\n\
+         NoDisplay=true\n\
+         MimeType={};\n",
+        exe, field_code, mime_type
+    );
+    std::fs::write(applications_dir.join(&desktop_file), entry)
+        .map_err(|e| AssociationError::Io(e.to_string()))?;
+    run(Command::new("xdg-mime").args(["default", &desktop_file, mime_type]))
+}
+
+#[cfg(not(any(target_os = "windows", target_os = "macos")))]
+fn data_dir() -> Result<PathBuf, AssociationError> {
+    directories_next::BaseDirs::new()
+        .map(|dirs| dirs.data_dir().to_owned())
+        .ok_or(AssociationError::Unsupported("No home directory available"))
+}
+
+#[cfg_attr(target_os = "macos", allow(dead_code))]
+fn run(command: &mut Command) -> Result<(), AssociationError> {
+    let status = command
+        .status()
+        .map_err(|e| AssociationError::Command(e.to_string()))?;
+    if status.success() {
+        Ok(())
+    } else {
+        Err(AssociationError::Command(format!(
+            "{:?} exited with {}",
+            command, status
+        )))
+    }
+}
@@ -0,0 +1,127 @@
+//! Who asked the queue to add, cancel, pause or resume each task, and through which
+//! interface: the UI, the command line, or one of the remote control surfaces.
+
+use std::fmt::{Display, Formatter, Result as FmtResult};
+
+use crate::app::history::now_millis;
+use crate::app::task_id::TaskId;
+
+/// Where a request to the queue came from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Interface {
+    Ui,
+    /// Arguments given at launch or forwarded by a later launch.
+    CommandLine,
+    Hotkey,
+    ControlPipe,
+    RpcStdio,
+    RemoteAgent,
+}
+
+impl Display for Interface {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        match self {
+            Interface::Ui => write!(f, "UI"),
+            Interface::CommandLine => write!(f, "command line"),
+            Interface::Hotkey => write!(f, "hotkey"),
+            Interface::ControlPipe => write!(f, "control pipe"),
+            Interface::RpcStdio => write!(f, "stdio RPC"),
+            Interface::RemoteAgent => write!(f, "remote agent"),
+        }
+    }
+}
+
+/// An interface and who used it.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+pub struct Origin {
+    pub interface: Interface,
+    /// A user name, or for network interfaces the peer's address.
+    pub principal: String,
+}
+
+impl Origin {
+    pub fn new(interface: Interface, principal: impl Into<String>) -> Self {
+        Origin {
+            interface,
+            principal: principal.into(),
+        }
+    }
+
+    /// For interfaces only reachable from this machine as the user running the app, which
+    /// is then who used them.
+    pub fn local(interface: Interface) -> Self {
+        Origin::new(interface, local_user())
+    }
+}
+
+impl Display for Origin {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        write!(f, "{} via {}", self.principal, self.interface)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AuditAction {
+    Add,
+    Cancel,
+    Pause,
+    Resume,
+}
+
+impl Display for AuditAction {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        match self {
+            AuditAction::Add => write!(f, "added"),
+            AuditAction::Cancel => write!(f, "cancelled"),
+            AuditAction::Pause => write!(f, "paused"),
+            AuditAction::Resume => write!(f, "resumed"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+pub struct AuditEntry {
+    /// Unix timestamp in milliseconds.
+    pub at: u64,
+    pub task_id: TaskId,
+    pub action: AuditAction,
+    #[serde(flatten)]
+    pub origin: Origin,
+}
+
+impl AuditEntry {
+    pub fn new(task_id: TaskId, action: AuditAction, origin: Origin) -> Self {
+        AuditEntry {
+            at: now_millis(),
+            task_id,
+            action,
+            origin,
+        }
+    }
+
+    /// Whether `query` is the task's id or part of its action, interface or principal,
+    /// ignoring case. An empty query matches everything.
+    pub fn matches(&self, query: &str) -> bool {
+        let query = query.trim().trim_start_matches('#').to_lowercase();
+        query.is_empty()
+            || query.parse() == Ok(self.task_id.index())
+            || [
+                self.action.to_string(),
+                self.origin.interface.to_string(),
+                self.origin.principal.clone(),
+            ]
+            .iter()
+            .any(|field| field.to_lowercase().contains(&query))
+    }
+}
+
+/// The name of the user running the app, or "unknown".
+fn local_user() -> String {
+    ["USER", "USERNAME"]
+        .iter()
+        .filter_map(|name| std::env::var(name).ok())
+        .find(|user| !user.is_empty())
+        .unwrap_or_else(|| "unknown".to_owned())
+}
@@ -0,0 +1,72 @@
+#[cfg(test)]
+use std::time::Duration;
+
+#[cfg(test)]
+use crate::app::audit::{AuditAction, AuditEntry, Interface, Origin};
+#[cfg(test)]
+use crate::app::sleep_task::SleepTask;
+#[cfg(test)]
+use crate::app::task_id::TaskId;
+#[cfg(test)]
+use crate::app::task_queue::TaskQueue;
+
+#[test]
+fn test_audit_entry_matches_query() {
+    let entry = AuditEntry::new(
+        TaskId::detached(42),
+        AuditAction::Pause,
+        Origin::new(Interface::RemoteAgent, "10.0.0.7:50123"),
+    );
+    for query in ["", "42", "#42", "paused", "Remote", "10.0.0.7"] {
+        assert!(entry.matches(query), "{:?} should match", query);
+    }
+    for query in ["4", "cancelled", "ui"] {
+        assert!(!entry.matches(query), "{:?} should not match", query);
+    }
+}
+
+#[test]
+fn test_queue_keeps_audit_log_by_task() {
+    let queue = TaskQueue::new();
+    let first = queue.add_task(SleepTask::new(None, Duration::from_secs(60)));
+    let second = queue.add_task(SleepTask::new(None, Duration::from_secs(60)));
+    let ui = Origin::new(Interface::Ui, "alice");
+    queue.audit(first, AuditAction::Add, &ui);
+    queue.audit(
+        second,
+        AuditAction::Add,
+        &Origin::new(Interface::RpcStdio, "bob"),
+    );
+    queue.audit(first, AuditAction::Cancel, &ui);
+
+    assert_eq!(queue.audit_log(None).len(), 3);
+    let first_log = queue.audit_log(Some(first));
+    assert_eq!(first_log.len(), 2);
+    assert_eq!(first_log[1].action, AuditAction::Cancel);
+    assert_eq!(first_log[1].origin, ui);
+    queue.remove_task(first).unwrap();
+    queue.remove_task(second).unwrap();
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+#[test]
+fn test_export_includes_audit_entries() {
+    let queue = TaskQueue::new();
+    let task_id = queue.add_task(SleepTask::new(None, Duration::from_secs(60)));
+    queue.remove_task(task_id).unwrap();
+    queue.audit(
+        task_id,
+        AuditAction::Cancel,
+        &Origin::new(Interface::ControlPipe, "carol"),
+    );
+    let path = std::env::temp_dir().join(format!("audit_export_{}.jsonl", std::process::id()));
+    queue.export_history_jsonl(&path).unwrap();
+    let exported = std::fs::read_to_string(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    let line: serde_json::Value = serde_json::from_str(exported.trim()).unwrap();
+    assert_eq!(line["id"], task_id.index());
+    assert_eq!(line["audit"][0]["action"], "cancel");
+    assert_eq!(line["audit"][0]["interface"], "control_pipe");
+    assert_eq!(line["audit"][0]["principal"], "carol");
+}
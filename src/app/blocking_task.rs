@@ -0,0 +1,69 @@
+//! Adapts a plain blocking closure, e.g. hashing or compressing a file, into a [`Task`].
+
+use crate::app::executor::JobHandle;
+use crate::app::job_task::JobTask;
+use crate::app::resource_usage::ResourceUsage;
+use crate::app::task_id::TaskId;
+use crate::app::task_queue::{PollResult, Task, TaskError, TaskKind};
+
+/// A task running `f` once on the runtime's blocking pool, or with
+/// [`cpu_bound`](Self::cpu_bound) on the CPU pool, so long work does not starve the
+/// threads that drive timers and I/O. It still holds one of the queue's workers while it
+/// runs, so it counts towards the concurrency limit.
+///
+/// `f` is not asked to stop part way: cancelling it, or pausing it, only takes effect if
+/// it has not started yet. For progress and cooperative pausing use a [`JobTask`] instead.
+pub struct BlockingTask(JobTask);
+
+impl BlockingTask {
+    pub fn new<F>(kind: TaskKind, f: F) -> Self
+    where
+        F: FnOnce() -> Result<(), String> + Send + 'static,
+    {
+        BlockingTask(JobTask::new(kind, Box::new(move |_| f())))
+    }
+
+    /// Runs `f` on the CPU pool, one thread per core, for work that keeps a core busy
+    /// rather than waiting, e.g. hashing or compressing.
+    pub fn cpu_bound(self) -> Self {
+        BlockingTask(self.0.cpu_bound())
+    }
+}
+
+impl Task for BlockingTask {
+    fn id(&self) -> Result<TaskId, TaskError> {
+        self.0.id()
+    }
+
+    fn set_id(&mut self, id: TaskId) {
+        self.0.set_id(id);
+    }
+
+    fn poll(&mut self) -> PollResult {
+        self.0.poll()
+    }
+
+    fn cancel(&mut self) -> Result<(), TaskError> {
+        self.0.cancel()
+    }
+
+    fn pause(&mut self) -> Result<(), TaskError> {
+        self.0.pause()
+    }
+
+    fn resume(&mut self) -> Result<(), TaskError> {
+        self.0.resume()
+    }
+
+    fn kind(&self) -> TaskKind {
+        self.0.kind()
+    }
+
+    fn take_handle(&mut self) -> Option<JobHandle> {
+        self.0.take_handle()
+    }
+
+    fn resource_usage(&self) -> Option<ResourceUsage> {
+        self.0.resource_usage()
+    }
+}
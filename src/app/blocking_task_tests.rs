@@ -0,0 +1,73 @@
+#[cfg(test)]
+use std::sync::atomic::{AtomicBool, Ordering};
+#[cfg(test)]
+use std::sync::Arc;
+#[cfg(test)]
+use std::time::Duration;
+
+#[cfg(test)]
+use crate::app::blocking_task::BlockingTask;
+#[cfg(test)]
+use crate::app::task_id::TaskId;
+#[cfg(test)]
+use crate::app::task_queue::{PollResult, TaskError, TaskKind, TaskQueue, TaskStatus};
+
+#[cfg(test)]
+fn poll_until_finished(task_queue: &TaskQueue, task_id: TaskId) -> PollResult {
+    for _ in 0..200 {
+        match task_queue.poll_task(task_id).unwrap() {
+            PollResult::Pending(_) => std::thread::sleep(Duration::from_millis(10)),
+            finished => return finished,
+        }
+    }
+    panic!("Task did not finish within the expected time");
+}
+
+#[test]
+fn test_blocking_task_runs_off_the_async_workers() {
+    let task_queue = TaskQueue::new();
+    let ran = Arc::new(AtomicBool::new(false));
+    let flag = ran.clone();
+    let task_id = task_queue.add_task(BlockingTask::new(TaskKind::Primes, move || {
+        // Blocks its thread outright, as a hashing loop would.
+        std::thread::sleep(Duration::from_millis(50));
+        flag.store(true, Ordering::SeqCst);
+        Ok(())
+    }));
+    assert_eq!(
+        poll_until_finished(&task_queue, task_id),
+        PollResult::Completed
+    );
+    assert!(ran.load(Ordering::SeqCst));
+    assert_eq!(task_queue.status(task_id), Ok(TaskStatus::Completed));
+}
+
+#[test]
+fn test_blocking_task_error_fails_the_task() {
+    let task_queue = TaskQueue::new();
+    let task_id = task_queue.add_task(BlockingTask::new(TaskKind::Primes, || {
+        Err("checksum mismatch".to_owned())
+    }));
+    assert_eq!(
+        poll_until_finished(&task_queue, task_id),
+        PollResult::Failed(TaskError::Failed {
+            reason: "checksum mismatch".to_owned()
+        })
+    );
+}
+
+#[test]
+fn test_cpu_bound_task_runs_on_the_cpu_pool() {
+    let task_queue = TaskQueue::new();
+    let task_id = task_queue.add_task(
+        BlockingTask::new(TaskKind::Primes, || match rayon::current_thread_index() {
+            Some(_) => Ok(()),
+            None => Err("not on a rayon thread".to_owned()),
+        })
+        .cpu_bound(),
+    );
+    assert_eq!(
+        poll_until_finished(&task_queue, task_id),
+        PollResult::Completed
+    );
+}
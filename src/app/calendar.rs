@@ -0,0 +1,134 @@
+//! Upcoming scheduled and recurring runs as an iCalendar (`.ics`) file, so planned heavy
+//! jobs show up in calendar apps.
+//!
+//! Each run is an event starting when the run is planned to. A recurrence's runs are
+//! listed one by one up to [`HORIZON`] ahead, as it would add them if each run finished
+//! within its period; a run that takes longer pushes the later ones back.
+
+use std::time::Duration;
+
+use crate::app::task_id::TaskId;
+
+/// How far ahead a recurrence's runs are listed.
+pub const HORIZON: Duration = Duration::from_secs(7 * 24 * 60 * 60);
+
+/// Identifies the app as the events' maker.
+const PRODID: &str = "-//functional_rust_ui_demo//Task queue//EN";
+
+/// A run the queue has planned: a scheduled task, or a recurrence's next run and those
+/// after it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PlannedRun {
+    /// The scheduled task, or the recurrence's latest run.
+    pub id: TaskId,
+    /// The task's label, or its kind if it has none.
+    pub summary: String,
+    /// Unix timestamp in milliseconds.
+    pub start: u64,
+    /// How often a recurrence runs; `None` for a task scheduled once.
+    pub every: Option<Duration>,
+}
+
+/// The calendar of `runs` as of `now`, in Unix milliseconds, and how many events are in
+/// it. A recurrence's runs after its next one are left out if they start later than
+/// `horizon` after `now`.
+pub fn ics(runs: &[PlannedRun], now: u64, horizon: Duration) -> (String, usize) {
+    let end = now.saturating_add(horizon.as_millis() as u64);
+    let mut lines = vec![
+        "BEGIN:VCALENDAR".to_owned(),
+        "VERSION:2.0".to_owned(),
+        format!("PRODID:{}", PRODID),
+    ];
+    let mut count = 0;
+    for run in runs {
+        let every = run.every.map_or(0, |every| every.as_millis() as u64);
+        let mut start = run.start;
+        // Numbers a recurrence's runs, so each event has a UID of its own.
+        for n in 0.. {
+            lines.extend([
+                "BEGIN:VEVENT".to_owned(),
+                format!(
+                    "UID:{}-{}-{}@functional_rust_ui_demo",
+                    run.id.generation(),
+                    run.id.index(),
+                    n
+                ),
+                format!("DTSTAMP:{}", utc(now)),
+                format!("DTSTART:{}", utc(start)),
+                format!("SUMMARY:{}", escape(&run.summary)),
+                "END:VEVENT".to_owned(),
+            ]);
+            count += 1;
+            start = start.saturating_add(every);
+            if every == 0 || start > end {
+                break;
+            }
+        }
+    }
+    lines.push("END:VCALENDAR".to_owned());
+    let text = lines.iter().map(|line| fold(line) + "\r\n").collect();
+    (text, count)
+}
+
+/// Unix milliseconds as an iCalendar UTC date-time, e.g. `20240102T030405Z`.
+fn utc(millis: u64) -> String {
+    let secs = millis / 1000;
+    let (year, month, day) = civil_from_days((secs / 86_400) as i64);
+    let time = secs % 86_400;
+    format!(
+        "{:04}{:02}{:02}T{:02}{:02}{:02}Z",
+        year,
+        month,
+        day,
+        time / 3600,
+        time % 3600 / 60,
+        time % 60
+    )
+}
+
+/// The proleptic Gregorian date `days` after 1970-01-01.
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = (if mp < 10 { mp + 3 } else { mp - 9 }) as u32;
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
+/// Escapes text for a property value.
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '\\' | ';' | ',' => {
+                escaped.push('\\');
+                escaped.push(c);
+            }
+            '\n' => escaped.push_str("\\n"),
+            '\r' => {}
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Splits a content line into lines of at most 75 bytes, each after the first starting
+/// with a space.
+fn fold(line: &str) -> String {
+    let mut folded = String::with_capacity(line.len());
+    let mut width = 0;
+    for c in line.chars() {
+        if width + c.len_utf8() > 75 {
+            folded.push_str("\r\n ");
+            width = 1;
+        }
+        folded.push(c);
+        width += c.len_utf8();
+    }
+    folded
+}
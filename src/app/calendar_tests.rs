@@ -0,0 +1,108 @@
+#[cfg(test)]
+use std::time::Duration;
+
+#[cfg(test)]
+use crate::app::calendar::{ics, PlannedRun, HORIZON};
+#[cfg(test)]
+use crate::app::executor::Instant;
+#[cfg(test)]
+use crate::app::sleep_task::SleepTask;
+#[cfg(test)]
+use crate::app::task_id::TaskId;
+#[cfg(test)]
+use crate::app::task_queue::TaskQueue;
+
+#[cfg(test)]
+const DAY: u64 = 24 * 60 * 60 * 1000;
+
+#[test]
+fn test_ics_lists_each_run_up_to_the_horizon() {
+    // 2023-11-14T22:13:20Z
+    let now = 1_700_000_000_000;
+    let runs = [
+        PlannedRun {
+            id: TaskId::new(3, 5),
+            summary: "Backup, nightly; full".to_owned(),
+            start: now + 3_600_000,
+            every: None,
+        },
+        PlannedRun {
+            id: TaskId::new(3, 7),
+            summary: "sleep".to_owned(),
+            start: now + DAY,
+            every: Some(Duration::from_millis(2 * DAY)),
+        },
+    ];
+    let (text, count) = ics(&runs, now, HORIZON);
+    // The recurrence runs on days 1, 3, 5 and 7.
+    assert_eq!(count, 5);
+    assert!(text.starts_with("BEGIN:VCALENDAR\r\nVERSION:2.0\r\n"));
+    assert!(text.ends_with("END:VCALENDAR\r\n"));
+    assert_eq!(text.matches("BEGIN:VEVENT\r\n").count(), 5);
+    assert_eq!(text.matches("DTSTAMP:20231114T221320Z\r\n").count(), 5);
+    assert!(text.contains(
+        "UID:3-5-0@functional_rust_ui_demo\r\n\
+         DTSTAMP:20231114T221320Z\r\n\
+         DTSTART:20231114T231320Z\r\n\
+         SUMMARY:Backup\\, nightly\\; full\r\n"
+    ));
+    for (n, start) in ["20231115", "20231117", "20231119", "20231121"]
+        .iter()
+        .enumerate()
+    {
+        assert!(text.contains(&format!(
+            "UID:3-7-{}@functional_rust_ui_demo\r\n\
+             DTSTAMP:20231114T221320Z\r\n\
+             DTSTART:{}T221320Z\r\n",
+            n, start
+        )));
+    }
+    assert!(!text.contains("UID:3-7-4@"));
+}
+
+#[test]
+fn test_ics_folds_long_lines() {
+    let runs = [PlannedRun {
+        id: TaskId::new(1, 0),
+        summary: "x".repeat(100),
+        start: 0,
+        every: None,
+    }];
+    let (text, _) = ics(&runs, 0, HORIZON);
+    assert!(text.contains("DTSTART:19700101T000000Z\r\n"));
+    assert!(text.split("\r\n").all(|line| line.len() <= 75));
+    assert!(text.contains(&format!("\r\n {}\r\n", "x".repeat(100 - 67))));
+}
+
+#[test]
+fn test_export_schedule_ics() {
+    let task_queue = TaskQueue::new();
+    let sleep = || SleepTask::new(None, Duration::from_secs(60));
+    let now = Instant::now();
+    let scheduled = task_queue.schedule_task(sleep(), now + Duration::from_secs(3600));
+    let unscheduled = task_queue.add_task(sleep());
+    let recurrence = task_queue.add_recurring_task(sleep, Duration::from_millis(2 * DAY));
+    let run = task_queue.recurrence(recurrence).unwrap().run;
+
+    let runs = task_queue.planned_runs();
+    assert_eq!(runs.len(), 2);
+    assert_eq!((runs[0].id, runs[0].every), (scheduled, None));
+    assert_eq!(runs[0].summary, "sleep");
+    assert_eq!(runs[1].id, run);
+    assert_eq!(runs[1].every, Some(Duration::from_millis(2 * DAY)));
+
+    let path = std::env::temp_dir().join(format!("schedule_{}.ics", std::process::id()));
+    // The recurrence's next run is 2 days off, then 4 and 6.
+    assert_eq!(task_queue.export_schedule_ics(&path).unwrap(), 4);
+    let text = std::fs::read_to_string(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert!(text.contains(&format!(
+        "UID:{}-{}-0@",
+        scheduled.generation(),
+        scheduled.index()
+    )));
+    assert!(text.contains(&format!("UID:{}-{}-2@", run.generation(), run.index())));
+
+    task_queue.stop_recurrence(recurrence).unwrap();
+    task_queue.remove_tasks(&[scheduled, unscheduled, run]);
+}
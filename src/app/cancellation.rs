@@ -0,0 +1,78 @@
+//! A shared signal telling a task's in-flight work to stop. The queue hands each task one
+//! when it is added and cancels it once the task ends, so work raced against it with
+//! [`CancellationToken::run`] is dropped at its next await rather than left to finish
+//! in the background.
+
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc as sync_Arc, Mutex as sync_Mutex};
+use std::task::{Context, Poll, Waker};
+
+use futures::future::{self, Either};
+
+/// Cheap to clone; every clone is cancelled together.
+#[derive(Debug, Clone, Default)]
+pub struct CancellationToken(sync_Arc<TokenState>);
+
+#[derive(Debug, Default)]
+struct TokenState {
+    cancelled: AtomicBool,
+    /// Of the [`Cancelled`] futures waiting on the token.
+    wakers: sync_Mutex<Vec<Waker>>,
+}
+
+impl CancellationToken {
+    pub fn new() -> Self {
+        CancellationToken::default()
+    }
+
+    /// Cancels the token and wakes everything waiting on it. Cancelling twice does nothing.
+    pub fn cancel(&self) {
+        self.0.cancelled.store(true, Ordering::Release);
+        for waker in self.0.wakers.lock().unwrap().drain(..) {
+            waker.wake();
+        }
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.cancelled.load(Ordering::Acquire)
+    }
+
+    /// A future that completes once the token is cancelled.
+    pub fn cancelled(&self) -> Cancelled {
+        Cancelled(self.clone())
+    }
+
+    /// Runs `work` until it finishes, or drops it as soon as the token is cancelled.
+    /// Returns its output, or `None` if it was cancelled first.
+    pub async fn run<F: Future>(&self, work: F) -> Option<F::Output> {
+        futures::pin_mut!(work);
+        // Cancellation first, so that work is not polled again once it is cancelled.
+        match future::select(self.cancelled(), work).await {
+            Either::Left(((), _)) => None,
+            Either::Right((output, _)) => Some(output),
+        }
+    }
+}
+
+/// Returned by [`CancellationToken::cancelled`].
+#[derive(Debug)]
+pub struct Cancelled(CancellationToken);
+
+impl Future for Cancelled {
+    type Output = ();
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        let state = &(self.0).0;
+        // Checked with the wakers locked, so a `cancel` in between cannot miss this one.
+        let mut wakers = state.wakers.lock().unwrap();
+        if state.cancelled.load(Ordering::Acquire) {
+            return Poll::Ready(());
+        }
+        if !wakers.iter().any(|waker| waker.will_wake(cx.waker())) {
+            wakers.push(cx.waker().clone());
+        }
+        Poll::Pending
+    }
+}
@@ -0,0 +1,24 @@
+#[cfg(test)]
+use std::time::{Duration, Instant};
+
+#[cfg(test)]
+use crate::app::cancellation::CancellationToken;
+
+#[test]
+fn test_run_returns_the_output_unless_cancelled() {
+    let token = CancellationToken::new();
+    assert_eq!(futures::executor::block_on(token.run(async { 7 })), Some(7));
+
+    let canceller = token.clone();
+    std::thread::spawn(move || {
+        std::thread::sleep(Duration::from_millis(20));
+        canceller.cancel();
+    });
+    let started = Instant::now();
+    let output = futures::executor::block_on(token.run(futures::future::pending::<()>()));
+    assert_eq!(output, None);
+    assert!(started.elapsed() < Duration::from_secs(5));
+    assert!(token.is_cancelled());
+    // Already cancelled: the work is not even started.
+    assert_eq!(futures::executor::block_on(token.run(async { 7 })), None);
+}
@@ -0,0 +1,112 @@
+//! Chaos mode: random stalls, failures and pauses of running tasks, so that the UI's error
+//! paths, retries and notifications can be exercised without anything actually going
+//! wrong. Only compiled into debug builds.
+
+use std::collections::HashMap;
+use std::sync::Mutex as sync_Mutex;
+use std::time::Duration;
+
+use crate::app::executor::Instant;
+use crate::app::history::now_millis;
+
+/// The `[chaos]` table: random trouble for running tasks, to exercise error paths. Only
+/// read by debug builds. Probabilities are per poll, from 0 to 1.
+#[derive(Debug, Clone, PartialEq, serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+pub struct ChaosConfig {
+    /// Chance that a task stops being polled for `stall_ms`, so it looks stuck.
+    pub stall: f64,
+    pub stall_ms: u64,
+    /// Chance that a task is cancelled, as if it failed.
+    pub fail: f64,
+    /// Chance that a task is paused.
+    pub pause: f64,
+    /// Makes a run repeatable; seeded from the clock when absent.
+    pub seed: Option<u64>,
+}
+
+impl Default for ChaosConfig {
+    fn default() -> Self {
+        Self {
+            stall: 0.0,
+            stall_ms: 2000,
+            fail: 0.0,
+            pause: 0.0,
+            seed: None,
+        }
+    }
+}
+
+/// What chaos mode does to a task instead of polling it.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ChaosEffect {
+    /// Skip the poll, so the task looks stuck.
+    Stall,
+    /// Cancel the task, as there is no failed state yet.
+    Fail,
+    Pause,
+}
+
+pub struct Chaos {
+    config: ChaosConfig,
+    /// State of a SplitMix64 generator.
+    rng: sync_Mutex<u64>,
+    /// Tasks stalled until the given instant.
+    stalled: sync_Mutex<HashMap<usize, Instant>>,
+}
+
+impl Chaos {
+    pub fn new(config: ChaosConfig) -> Self {
+        let seed = config.seed.unwrap_or_else(now_millis);
+        Chaos {
+            config,
+            rng: sync_Mutex::new(seed),
+            stalled: sync_Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// What, if anything, happens to running task `id` on this poll.
+    pub fn roll(&self, id: usize) -> Option<ChaosEffect> {
+        let now = Instant::now();
+        let mut stalled = self.stalled.lock().unwrap();
+        if let Some(until) = stalled.get(&id) {
+            if now < *until {
+                return Some(ChaosEffect::Stall);
+            }
+            stalled.remove(&id);
+        }
+        if self.chance(self.config.stall) {
+            log::warn!(
+                "Chaos: stalling task {} for {} ms",
+                id,
+                self.config.stall_ms
+            );
+            stalled.insert(id, now + Duration::from_millis(self.config.stall_ms));
+            return Some(ChaosEffect::Stall);
+        }
+        if self.chance(self.config.fail) {
+            log::warn!("Chaos: failing task {}", id);
+            return Some(ChaosEffect::Fail);
+        }
+        if self.chance(self.config.pause) {
+            log::warn!("Chaos: pausing task {}", id);
+            return Some(ChaosEffect::Pause);
+        }
+        None
+    }
+
+    fn chance(&self, probability: f64) -> bool {
+        probability > 0.0 && self.next_f64() < probability
+    }
+
+    /// Uniform in `[0, 1)`.
+    fn next_f64(&self) -> f64 {
+        let mut state = self.rng.lock().unwrap();
+        *state = state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = *state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^= z >> 31;
+        (z >> 11) as f64 / (1u64 << 53) as f64
+    }
+}
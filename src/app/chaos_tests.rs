@@ -0,0 +1,69 @@
+#[cfg(test)]
+use std::time::Duration;
+
+#[cfg(test)]
+use crate::app::chaos::ChaosConfig;
+#[cfg(test)]
+use crate::app::sleep_task::SleepTask;
+#[cfg(test)]
+use crate::app::task_id::TaskId;
+#[cfg(test)]
+use crate::app::task_queue::{PollResult, TaskError, TaskQueue, TaskStatus};
+
+#[cfg(test)]
+fn running_task(task_queue: &TaskQueue) -> TaskId {
+    let task_id = task_queue.add_task(SleepTask::new(None, Duration::from_secs(60)));
+    assert!(matches!(
+        task_queue.poll_task(task_id),
+        Ok(PollResult::Pending(_))
+    ));
+    task_id
+}
+
+#[test]
+fn test_chaos_fails_running_tasks() {
+    let task_queue = TaskQueue::new();
+    task_queue.set_chaos(Some(ChaosConfig {
+        fail: 1.0,
+        seed: Some(7),
+        ..ChaosConfig::default()
+    }));
+    let task_id = running_task(&task_queue);
+    assert!(matches!(
+        task_queue.poll_task(task_id),
+        Ok(PollResult::Failed(TaskError::Failed { .. }))
+    ));
+    let record = &task_queue.history()[0];
+    assert_eq!(record.status, TaskStatus::Failed);
+    assert_eq!(record.error.as_deref(), Some("Failed by chaos mode"));
+}
+
+#[test]
+fn test_chaos_pauses_and_stalls() {
+    let task_queue = TaskQueue::new();
+    task_queue.set_chaos(Some(ChaosConfig {
+        pause: 1.0,
+        ..ChaosConfig::default()
+    }));
+    let task_id = running_task(&task_queue);
+    assert!(matches!(
+        task_queue.poll_task(task_id),
+        Ok(PollResult::Paused(_))
+    ));
+
+    task_queue.set_chaos(Some(ChaosConfig {
+        stall: 1.0,
+        stall_ms: 60_000,
+        ..ChaosConfig::default()
+    }));
+    let task_id = running_task(&task_queue);
+    let stalled = task_queue.poll_task(task_id);
+    assert!(matches!(stalled, Ok(PollResult::Pending(_))));
+    assert_eq!(task_queue.poll_task(task_id), stalled);
+
+    task_queue.set_chaos(None);
+    assert!(matches!(
+        task_queue.poll_task(task_id),
+        Ok(PollResult::Pending(_))
+    ));
+}
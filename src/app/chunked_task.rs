@@ -0,0 +1,253 @@
+use std::sync::{Arc as sync_Arc, Mutex as sync_Mutex};
+use std::time::Duration;
+
+use log::{debug, info};
+
+use crate::app::cancellation::CancellationToken;
+use crate::app::executor::{self, Instant, JobHandle};
+use crate::app::progress_channel::ProgressSender;
+use crate::app::resource_usage::{CpuMeter, ResourceUsage};
+use crate::app::task_id::TaskId;
+use crate::app::task_queue::{PollResult, PollingData, Task, TaskError, TaskKind, TaskStatus};
+
+/// How long a chunked task runs steps before yielding. Short enough that the web canvas,
+/// which shares the thread, still gets a frame in between.
+const SLICE: Duration = Duration::from_millis(8);
+/// How often a paused task checks whether it was resumed.
+const PAUSED_RECHECK: Duration = Duration::from_millis(100);
+
+/// Outcome of one step of a [`ChunkedTask`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum Step {
+    /// More to do; holds the progress so far.
+    Continue(f32),
+    Done,
+}
+
+pub type StepFn = Box<dyn FnMut() -> Step + Send>;
+
+struct ChunkState {
+    status: sync_Mutex<TaskStatus>,
+    progress: sync_Mutex<f32>,
+}
+
+/// A task whose work is a series of short steps, run as a future that yields back to the
+/// executor every few milliseconds.
+///
+/// Unlike a [`JobTask`](crate::app::job_task::JobTask) it needs no thread of its own, so it
+/// also runs on the web, and pausing it frees the thread instead of parking it.
+pub struct ChunkedTask {
+    id: Option<TaskId>,
+    kind: TaskKind,
+    step: sync_Mutex<Option<StepFn>>,
+    state: sync_Arc<ChunkState>,
+    handle: Option<JobHandle>,
+    cpu: CpuMeter,
+    /// Stops the steps, paused or not, as soon as the task is cancelled.
+    cancellation: CancellationToken,
+    /// Gets the progress after every slice of steps.
+    progress_sender: Option<ProgressSender>,
+}
+
+impl ChunkedTask {
+    pub fn new(kind: TaskKind, step: StepFn) -> Self {
+        ChunkedTask {
+            id: None,
+            kind,
+            step: sync_Mutex::new(Some(step)),
+            state: sync_Arc::new(ChunkState {
+                status: sync_Mutex::new(TaskStatus::Queued),
+                progress: sync_Mutex::new(0.0),
+            }),
+            handle: None,
+            cpu: CpuMeter::default(),
+            cancellation: CancellationToken::new(),
+            progress_sender: None,
+        }
+    }
+
+    fn status(&self) -> TaskStatus {
+        self.state.status.lock().unwrap().clone()
+    }
+
+    fn set_status(&self, status: TaskStatus) {
+        *self.state.status.lock().unwrap() = status;
+    }
+
+    fn progress(&self) -> PollingData {
+        PollingData::Float(*self.state.progress.lock().unwrap())
+    }
+}
+
+async fn run_steps(
+    mut step: StepFn,
+    state: sync_Arc<ChunkState>,
+    progress_sender: Option<ProgressSender>,
+) {
+    {
+        let mut status = state.status.lock().unwrap();
+        if *status == TaskStatus::Queued {
+            *status = TaskStatus::Running;
+        }
+    }
+    loop {
+        let slice_start = Instant::now();
+        while slice_start.elapsed() < SLICE {
+            match *state.status.lock().unwrap() {
+                TaskStatus::Cancelled | TaskStatus::Interrupted => return,
+                TaskStatus::Paused => break,
+                _ => {}
+            }
+            match step() {
+                Step::Continue(progress) => {
+                    *state.progress.lock().unwrap() = progress.clamp(0.0, 1.0);
+                }
+                Step::Done => {
+                    let mut status = state.status.lock().unwrap();
+                    if *status == TaskStatus::Running {
+                        *state.progress.lock().unwrap() = 1.0;
+                        *status = TaskStatus::Completed;
+                    }
+                    return;
+                }
+            }
+        }
+        if *state.status.lock().unwrap() == TaskStatus::Paused {
+            executor::sleep(PAUSED_RECHECK).await;
+        } else {
+            if let Some(sender) = &progress_sender {
+                sender.send(PollingData::Float(*state.progress.lock().unwrap()));
+            }
+            executor::yield_now().await;
+        }
+    }
+}
+
+impl Task for ChunkedTask {
+    fn id(&self) -> Result<TaskId, TaskError> {
+        self.id.ok_or(TaskError::IdUsizeIsNone)
+    }
+
+    fn set_id(&mut self, id: TaskId) {
+        self.id = Some(id);
+    }
+
+    fn set_cancellation_token(&mut self, token: CancellationToken) {
+        self.cancellation = token;
+    }
+
+    fn set_progress_sender(&mut self, sender: ProgressSender) {
+        self.progress_sender = Some(sender);
+    }
+
+    fn poll(&mut self) -> PollResult {
+        match self.status() {
+            TaskStatus::Scheduled | TaskStatus::Queued => {
+                // Already submitted, waiting for a worker.
+                let Some(step) = self.step.lock().unwrap().take() else {
+                    return PollResult::Pending(self.progress());
+                };
+                debug!("ChunkedTask::poll() - starting {}", self.kind);
+                let steps = run_steps(step, self.state.clone(), self.progress_sender.clone());
+                let cancellation = self.cancellation.clone();
+                let steps = async move {
+                    cancellation.run(steps).await;
+                };
+                self.handle = Some(executor::submit(self.cpu.measure(steps)));
+                PollResult::Pending(PollingData::Float(0.0))
+            }
+            TaskStatus::Running => PollResult::Pending(self.progress()),
+            TaskStatus::Paused => PollResult::Paused(self.progress()),
+            TaskStatus::Completed => PollResult::Completed,
+            TaskStatus::Cancelled | TaskStatus::Failed | TaskStatus::Interrupted => {
+                PollResult::Cancelled
+            }
+        }
+    }
+
+    fn cancel(&mut self) -> Result<(), TaskError> {
+        match self.status() {
+            TaskStatus::Completed => Err(TaskError::AlreadyCompleted),
+            TaskStatus::Cancelled | TaskStatus::Failed | TaskStatus::Interrupted => {
+                Err(TaskError::AlreadyCancelled)
+            }
+            _ => {
+                self.set_status(TaskStatus::Cancelled);
+                self.cancellation.cancel();
+                Ok(())
+            }
+        }
+    }
+
+    fn pause(&mut self) -> Result<(), TaskError> {
+        match self.status() {
+            TaskStatus::Scheduled | TaskStatus::Queued | TaskStatus::Running => {
+                self.set_status(TaskStatus::Paused);
+                Ok(())
+            }
+            TaskStatus::Paused => Err(TaskError::AlreadyPaused),
+            TaskStatus::Completed => Err(TaskError::AlreadyCompleted),
+            TaskStatus::Cancelled | TaskStatus::Failed | TaskStatus::Interrupted => {
+                Err(TaskError::AlreadyCancelled)
+            }
+        }
+    }
+
+    fn resume(&mut self) -> Result<(), TaskError> {
+        match self.status() {
+            TaskStatus::Paused => {
+                // A task paused before it ever started goes back to the queue.
+                if self.step.lock().unwrap().is_some() {
+                    self.set_status(TaskStatus::Queued);
+                } else {
+                    self.set_status(TaskStatus::Running);
+                }
+                Ok(())
+            }
+            TaskStatus::Scheduled | TaskStatus::Queued | TaskStatus::Running => {
+                Err(TaskError::AlreadyRunning)
+            }
+            TaskStatus::Completed => Err(TaskError::AlreadyCompleted),
+            TaskStatus::Cancelled | TaskStatus::Failed | TaskStatus::Interrupted => {
+                Err(TaskError::AlreadyCancelled)
+            }
+        }
+    }
+
+    fn kind(&self) -> TaskKind {
+        self.kind.clone()
+    }
+
+    fn take_handle(&mut self) -> Option<JobHandle> {
+        self.handle.take()
+    }
+
+    fn resource_usage(&self) -> Option<ResourceUsage> {
+        Some(ResourceUsage {
+            cpu_time: self.cpu.total(),
+            io_bytes: None,
+        })
+    }
+}
+
+/// Body of the built-in `primes` kind: counts the primes below `below` by trial division,
+/// one candidate per step. Mostly useful for seeing how the app copes with CPU-bound work.
+pub fn count_primes(below: u64) -> StepFn {
+    let mut candidate = 2;
+    let mut found = 0u64;
+    Box::new(move || {
+        if candidate >= below {
+            info!("Found {} primes below {}", found, below);
+            return Step::Done;
+        }
+        if is_prime(candidate) {
+            found += 1;
+        }
+        candidate += 1;
+        Step::Continue(candidate as f32 / below as f32)
+    })
+}
+
+fn is_prime(n: u64) -> bool {
+    n >= 2 && (2..).take_while(|d| d * d <= n).all(|d| n % d != 0)
+}
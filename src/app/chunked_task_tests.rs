@@ -0,0 +1,62 @@
+#[cfg(test)]
+use crate::app::chunked_task::{count_primes, ChunkedTask, Step};
+#[cfg(test)]
+use crate::app::task_queue::{PollResult, PollingData, Task, TaskKind};
+#[cfg(test)]
+use std::time::Duration;
+
+#[cfg(test)]
+fn poll_until_finished(task: &mut ChunkedTask) -> PollResult {
+    for _ in 0..200 {
+        match task.poll() {
+            PollResult::Pending(_) => std::thread::sleep(std::time::Duration::from_millis(10)),
+            finished => return finished,
+        }
+    }
+    panic!("Task did not finish within the expected time");
+}
+
+#[test]
+fn test_count_primes() {
+    let mut step = count_primes(30);
+    let mut last = Step::Continue(0.0);
+    for _ in 0..100 {
+        last = step();
+        if last == Step::Done {
+            break;
+        }
+    }
+    assert_eq!(last, Step::Done);
+}
+
+#[test]
+fn test_chunked_task_completes() {
+    let mut task = ChunkedTask::new(TaskKind::Primes, count_primes(10_000));
+    assert_eq!(task.poll(), PollResult::Pending(PollingData::Float(0.0)));
+    assert_eq!(poll_until_finished(&mut task), PollResult::Completed);
+}
+
+#[test]
+fn test_pause_resume_and_cancel_chunked_task() {
+    let mut task = ChunkedTask::new(TaskKind::Primes, Box::new(|| Step::Continue(0.5)));
+    task.poll();
+    std::thread::sleep(std::time::Duration::from_millis(20));
+    task.pause().unwrap();
+    assert_eq!(task.poll(), PollResult::Paused(PollingData::Float(0.5)));
+    task.resume().unwrap();
+    assert_eq!(task.poll(), PollResult::Pending(PollingData::Float(0.5)));
+    task.cancel().unwrap();
+    assert_eq!(task.poll(), PollResult::Cancelled);
+}
+
+#[cfg(any(target_os = "linux", target_os = "macos", target_os = "windows"))]
+#[test]
+fn test_chunked_task_measures_cpu_time() {
+    let mut task = ChunkedTask::new(TaskKind::Primes, count_primes(200_000));
+    assert_eq!(task.resource_usage().unwrap().cpu_time, Duration::ZERO);
+    task.poll();
+    assert_eq!(poll_until_finished(&mut task), PollResult::Completed);
+    let usage = task.resource_usage().unwrap();
+    assert!(usage.cpu_time > Duration::ZERO);
+    assert_eq!(usage.io_bytes, None);
+}
@@ -0,0 +1,63 @@
+//! Colors the user tags tasks with, to group related work across kinds at a glance.
+
+use std::fmt::{Display, Formatter, Result as FmtResult};
+use std::str::FromStr;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, serde::Deserialize, serde::Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ColorTag {
+    Red,
+    Orange,
+    Yellow,
+    Green,
+    Blue,
+    Purple,
+}
+
+impl ColorTag {
+    /// The palette, in the order it is offered.
+    pub const ALL: [ColorTag; 6] = [
+        ColorTag::Red,
+        ColorTag::Orange,
+        ColorTag::Yellow,
+        ColorTag::Green,
+        ColorTag::Blue,
+        ColorTag::Purple,
+    ];
+
+    /// Red, green and blue, for the UI to paint the tag with.
+    pub fn rgb(&self) -> [u8; 3] {
+        match self {
+            ColorTag::Red => [0xe0, 0x4f, 0x4f],
+            ColorTag::Orange => [0xe8, 0x8a, 0x2e],
+            ColorTag::Yellow => [0xe0, 0xc2, 0x2f],
+            ColorTag::Green => [0x4c, 0xb0, 0x5a],
+            ColorTag::Blue => [0x3f, 0x8c, 0xe0],
+            ColorTag::Purple => [0x9a, 0x5c, 0xd6],
+        }
+    }
+}
+
+impl Display for ColorTag {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        match self {
+            ColorTag::Red => write!(f, "red"),
+            ColorTag::Orange => write!(f, "orange"),
+            ColorTag::Yellow => write!(f, "yellow"),
+            ColorTag::Green => write!(f, "green"),
+            ColorTag::Blue => write!(f, "blue"),
+            ColorTag::Purple => write!(f, "purple"),
+        }
+    }
+}
+
+impl FromStr for ColorTag {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        ColorTag::ALL
+            .into_iter()
+            .find(|tag| tag.to_string() == s)
+            .ok_or_else(|| format!("Unknown color tag: {}", s))
+    }
+}
@@ -0,0 +1,45 @@
+#[cfg(test)]
+use std::time::Duration;
+
+#[cfg(test)]
+use crate::app::color_tag::ColorTag;
+#[cfg(test)]
+use crate::app::history::TaskRecord;
+#[cfg(test)]
+use crate::app::sleep_task::SleepTask;
+#[cfg(test)]
+use crate::app::task_id::TaskId;
+#[cfg(test)]
+use crate::app::task_queue::TaskQueue;
+
+#[test]
+fn test_color_tag_names_round_trip() {
+    for tag in ColorTag::ALL {
+        assert_eq!(tag.to_string().parse(), Ok(tag));
+        assert_eq!(serde_json::to_string(&tag).unwrap(), format!("\"{}\"", tag));
+    }
+    assert!("teal".parse::<ColorTag>().is_err());
+}
+
+#[test]
+fn test_untagged_records_leave_the_tag_out() {
+    let json = serde_json::to_string(&TaskRecord::new(TaskId::detached(1), "sleep")).unwrap();
+    assert!(!json.contains("color_tag"));
+}
+
+#[test]
+fn test_queue_keeps_color_tag_in_record() {
+    let queue = TaskQueue::new();
+    let id = queue.add_task(SleepTask::new(None, Duration::from_secs(60)));
+    queue.set_color_tag(id, Some(ColorTag::Green)).unwrap();
+    assert_eq!(
+        queue.task_detail(id).unwrap().record.color_tag,
+        Some(ColorTag::Green)
+    );
+    queue.set_color_tag(id, None).unwrap();
+    assert_eq!(queue.task_detail(id).unwrap().record.color_tag, None);
+    assert!(queue
+        .set_color_tag(queue.task_id(id.index() + 1), None)
+        .is_err());
+    queue.remove_task(id).unwrap();
+}
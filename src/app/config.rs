@@ -0,0 +1,779 @@
+use std::collections::HashMap;
+use std::fmt::{Display, Formatter, Result as FmtResult};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::Mutex;
+use std::time::{Duration, Instant, SystemTime};
+
+use log::{debug, LevelFilter};
+
+use crate::app::artifacts;
+#[cfg(debug_assertions)]
+use crate::app::chaos::ChaosConfig;
+use crate::app::executor;
+use crate::app::history::HistoryRetention;
+use crate::app::registry::TaskParams;
+use crate::app::task_queue::{ProgressGranularity, TaskStatus};
+
+const CONFIG_FILE_NAME: &str = "config.toml";
+const RELOAD_CHECK_INTERVAL: Duration = Duration::from_secs(1);
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ConfigError {
+    NoConfigDir,
+    Io(String),
+    Parse(String),
+    InvalidLogLevel(String),
+}
+
+impl Display for ConfigError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        match self {
+            ConfigError::NoConfigDir => write!(f, "No platform config directory available"),
+            ConfigError::Io(e) => write!(f, "Config I/O error: {}", e),
+            ConfigError::Parse(e) => write!(f, "Config parse error: {}", e),
+            ConfigError::InvalidLogLevel(level) => write!(f, "Invalid log level: {}", level),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, serde::Deserialize, serde::Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Theme {
+    Dark,
+    Light,
+}
+
+#[cfg(feature = "ui")]
+impl Theme {
+    pub fn visuals(&self) -> egui::Visuals {
+        match self {
+            Theme::Dark => egui::Visuals::dark(),
+            Theme::Light => egui::Visuals::light(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, serde::Deserialize, serde::Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum StoreBackend {
+    /// History is serialized into eframe's app storage alongside the UI state.
+    Eframe,
+    /// Tasks and history live in a SQLite database (requires the `sqlite` feature).
+    Sqlite,
+}
+
+/// Maps files with one of `extensions` (without the dot, case-insensitive) to a task.
+///
+/// `{path}` in a parameter value is replaced with the new file's full path, e.g.
+/// `extensions = ["zip"]`, `kind = "extract"`, `params = { archive = "{path}" }`.
+#[derive(Debug, Clone, Default, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct WatchRule {
+    pub extensions: Vec<String>,
+    pub kind: String,
+    #[serde(default)]
+    pub params: TaskParams,
+}
+
+/// A `[[webhooks]]` entry, e.g. for a Slack incoming webhook:
+///
+/// ```toml
+/// [[webhooks]]
+/// url = "https://hooks.slack.com/services/..."
+/// payload = '{"text": "{{kind}} task {{id}} is {{status}}"}'
+/// ```
+///
+/// Without a `payload` the task record itself is sent as JSON.
+#[derive(Debug, Clone, PartialEq, serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+pub struct WebhookConfig {
+    pub url: String,
+    /// Statuses that trigger the webhook.
+    pub on: Vec<TaskStatus>,
+    /// JSON body with `{{id}}`, `{{kind}}`, `{{status}}` and `{{duration_secs}}` placeholders.
+    pub payload: Option<String>,
+    /// Extra request headers, e.g. `{ Authorization = "Bearer ..." }`.
+    pub headers: HashMap<String, String>,
+    /// Failed deliveries are retried this many times, waiting twice as long each time.
+    pub max_retries: u32,
+}
+
+impl Default for WebhookConfig {
+    fn default() -> Self {
+        Self {
+            url: String::new(),
+            on: vec![
+                TaskStatus::Completed,
+                TaskStatus::Cancelled,
+                TaskStatus::Failed,
+            ],
+            payload: None,
+            headers: HashMap::new(),
+            max_retries: 5,
+        }
+    }
+}
+
+/// The `[power]` table.
+#[derive(Debug, Clone, Default, PartialEq, serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+pub struct PowerConfig {
+    /// Stop the computer from sleeping while any task is running.
+    pub keep_awake: bool,
+    /// Pause heavy tasks while running on battery and resume them when AC power returns.
+    pub pause_on_battery: bool,
+    /// Task kinds counted as heavy; every kind is when this is empty.
+    pub heavy_kinds: Vec<String>,
+}
+
+/// The `[network]` table.
+#[derive(Debug, Clone, PartialEq, serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+pub struct NetworkConfig {
+    /// Pause network tasks while the connection is metered or roaming, and resume them
+    /// when it no longer is. Each task can be let through from its menu.
+    pub pause_on_metered: bool,
+    /// Task kinds that use the network.
+    pub network_kinds: Vec<String>,
+}
+
+impl Default for NetworkConfig {
+    fn default() -> Self {
+        Self {
+            pause_on_metered: false,
+            network_kinds: vec!["download".to_owned()],
+        }
+    }
+}
+
+/// The `[deadlines]` table: what happens when a task looks like missing its deadline.
+/// Its row is marked either way.
+#[derive(Debug, Clone, Default, PartialEq, serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+pub struct DeadlineConfig {
+    /// Raise the task's priority a level.
+    pub boost_priority: bool,
+    /// Show a desktop notification.
+    pub notify: bool,
+}
+
+/// The `[governor]` table: throttling the queue while the machine is busy or hot.
+#[derive(Debug, Clone, PartialEq, serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+pub struct GovernorConfig {
+    pub enabled: bool,
+    /// One-minute load average per core at which the machine counts as busy, e.g. `0.9`
+    /// for nine tenths of every core.
+    pub max_cpu_load: f32,
+    /// Load per core it has to drop below again before the queue speeds back up.
+    pub resume_cpu_load: f32,
+    /// Hottest sensor temperature, in °C, at which the machine counts as hot.
+    pub max_temperature: f32,
+    pub resume_temperature: f32,
+    /// Tasks run at once while throttled, or fewer if `concurrency` is lower.
+    pub throttled_concurrency: usize,
+    /// Also pause low-priority tasks while throttled, resuming them afterwards.
+    pub pause_low_priority: bool,
+}
+
+impl Default for GovernorConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            max_cpu_load: 0.9,
+            resume_cpu_load: 0.7,
+            max_temperature: 85.0,
+            resume_temperature: 75.0,
+            throttled_concurrency: 1,
+            pause_low_priority: true,
+        }
+    }
+}
+
+/// Defaults loaded from `config.toml` in the platform config directory.
+///
+/// Every field is optional in the file; anything missing falls back to [`AppConfig::default`].
+#[derive(Debug, Clone, PartialEq, serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+pub struct AppConfig {
+    /// Maximum number of tasks allowed to run at once. `None` means unlimited.
+    pub concurrency: Option<usize>,
+    /// Most tasks started per second, so that adding many at once does not start them
+    /// all in the same frame. `None` means unlimited.
+    pub start_rate: Option<f64>,
+    /// Smallest change of a task's progress fraction that is reported to progress
+    /// subscribers and the log, e.g. `0.005` for every half percent.
+    pub progress_min_delta: f32,
+    /// Fewest milliseconds between two progress reports of the same task.
+    pub progress_min_interval_ms: u64,
+    pub theme: Theme,
+    /// Global bandwidth cap for network tasks, in bytes per second. `None` means uncapped.
+    pub bandwidth_cap: Option<u64>,
+    /// One of `off`, `error`, `warn`, `info`, `debug` or `trace`.
+    pub log_level: String,
+    /// Default output directory per task kind, keyed by the kind's name (e.g. `sleep`).
+    pub output_dirs: HashMap<String, PathBuf>,
+    pub store: StoreBackend,
+    /// History records kept in memory. Older ones are paged back in from the store, or
+    /// from a spill file in the cache directory, when needed. `None` keeps all of them.
+    pub history_memory_limit: Option<usize>,
+    /// History of tasks that finished more days ago than this is deleted, from the store
+    /// too. `None` keeps it however old.
+    pub history_max_age_days: Option<u64>,
+    /// Only this many of the most recent history records are kept, in the store too.
+    /// `None` keeps all of them.
+    pub history_max_count: Option<usize>,
+    /// Keeps task parameters, which may hold paths and URLs, out of the history saved to
+    /// disk: records are written without their artifacts and annotations.
+    pub history_exclude_params: bool,
+    /// Database used by the `sqlite` store; defaults to `queue.sqlite3` in the platform data dir.
+    pub sqlite_path: Option<PathBuf>,
+    /// Directory scanned for task-kind plugins at startup; defaults to `plugins/` in the
+    /// config dir.
+    pub plugins_dir: Option<PathBuf>,
+    /// Files here replace the built-in assets of the same name; defaults to `assets/` in
+    /// the config dir.
+    pub assets_dir: Option<PathBuf>,
+    /// Host names WASM plugins may fetch from via their `http_get` host call.
+    pub wasm_http_allow_list: Vec<String>,
+    /// Files appearing in this folder are turned into tasks by `watch_rules`.
+    pub watch_folder: Option<PathBuf>,
+    /// Checked in order; the first rule listing a new file's extension creates its task.
+    pub watch_rules: Vec<WatchRule>,
+    /// Offers the `process` task kind, which runs programs. Anything able to add tasks,
+    /// such as job files, watch rules and the control pipe, can then run commands here.
+    /// Takes effect on the next start.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub process_tasks: bool,
+    /// Saves the unfinished tasks when the app closes and adds them again, paused ones
+    /// paused, on the next start. Like tasks in a session, they start over.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub restore_queue: bool,
+    /// Each `[[webhooks]]` entry is POSTed to when a task reaches one of its statuses.
+    pub webhooks: Vec<WebhookConfig>,
+    pub power: PowerConfig,
+    pub network: NetworkConfig,
+    pub governor: GovernorConfig,
+    pub deadlines: DeadlineConfig,
+    /// Broker to publish task events to; publishing is off when the `[mqtt]` table is absent.
+    #[cfg(all(feature = "mqtt", not(target_arch = "wasm32")))]
+    pub mqtt: Option<MqttConfig>,
+    /// OTLP collector to export spans and metrics to; export is off when `[otel]` is absent.
+    #[cfg(all(feature = "otel", not(target_arch = "wasm32")))]
+    pub otel: Option<OtelConfig>,
+    /// Mail server for batch summaries; no mail is sent when the `[smtp]` table is absent.
+    #[cfg(all(feature = "email", not(target_arch = "wasm32")))]
+    pub smtp: Option<SmtpConfig>,
+    /// Share this queue with, and mirror, other instances on the LAN when `[lan_sync]` is present.
+    #[cfg(all(feature = "lan-sync", not(target_arch = "wasm32")))]
+    pub lan_sync: Option<LanSyncConfig>,
+    /// Where to listen when started with `--agent`.
+    #[cfg(all(feature = "remote-agent", not(target_arch = "wasm32")))]
+    pub agent: AgentServerConfig,
+    /// Agents tasks can be dispatched to from the New task window.
+    #[cfg(all(feature = "remote-agent", not(target_arch = "wasm32")))]
+    pub remote_agents: Vec<RemoteAgentConfig>,
+    #[cfg(all(feature = "self-update", not(target_arch = "wasm32")))]
+    pub update: UpdateConfig,
+    /// Pauses all tasks from anywhere, and resumes them on the next press, e.g.
+    /// `ctrl+alt+P`. Takes effect on the next start.
+    #[cfg(all(feature = "global-hotkey", not(target_arch = "wasm32")))]
+    pub pause_hotkey: Option<String>,
+    /// Where secrets are kept when there is no OS keyring; defaults to `secrets.json` in
+    /// the platform data dir.
+    #[cfg(all(feature = "secrets", not(target_arch = "wasm32")))]
+    pub secrets_file: Option<PathBuf>,
+    /// Randomly stalls, fails and pauses tasks when `[chaos]` is present. Debug builds only.
+    #[cfg(debug_assertions)]
+    pub chaos: Option<ChaosConfig>,
+}
+
+/// The `[mqtt]` table. Changes take effect on the next start.
+#[cfg(all(feature = "mqtt", not(target_arch = "wasm32")))]
+#[derive(Debug, Clone, PartialEq, serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+pub struct MqttConfig {
+    pub host: String,
+    pub port: u16,
+    pub client_id: String,
+    pub username: Option<String>,
+    /// May be a `${secret:NAME}` reference with the `secrets` feature.
+    pub password: Option<String>,
+    /// Each lifecycle event is published to `<events_topic>/<status>` as a JSON task record.
+    pub events_topic: String,
+    /// Task counts per status are published here every `stats_interval_secs`.
+    pub stats_topic: String,
+    pub stats_interval_secs: u64,
+}
+
+#[cfg(all(feature = "mqtt", not(target_arch = "wasm32")))]
+impl Default for MqttConfig {
+    fn default() -> Self {
+        Self {
+            host: "localhost".to_owned(),
+            port: 1883,
+            client_id: "functional_rust_ui_demo".to_owned(),
+            username: None,
+            password: None,
+            events_topic: "taskqueue/events".to_owned(),
+            stats_topic: "taskqueue/stats".to_owned(),
+            stats_interval_secs: 30,
+        }
+    }
+}
+
+impl Default for AppConfig {
+    fn default() -> Self {
+        Self {
+            concurrency: None,
+            start_rate: None,
+            progress_min_delta: 0.0,
+            progress_min_interval_ms: 0,
+            theme: Theme::Dark,
+            bandwidth_cap: None,
+            log_level: "info".to_owned(),
+            output_dirs: HashMap::new(),
+            store: StoreBackend::Eframe,
+            history_memory_limit: Some(5000),
+            history_max_age_days: None,
+            history_max_count: None,
+            history_exclude_params: false,
+            sqlite_path: None,
+            plugins_dir: None,
+            assets_dir: None,
+            wasm_http_allow_list: Vec::new(),
+            watch_folder: None,
+            watch_rules: Vec::new(),
+            #[cfg(not(target_arch = "wasm32"))]
+            process_tasks: false,
+            #[cfg(not(target_arch = "wasm32"))]
+            restore_queue: true,
+            webhooks: Vec::new(),
+            power: PowerConfig::default(),
+            network: NetworkConfig::default(),
+            governor: GovernorConfig::default(),
+            deadlines: DeadlineConfig::default(),
+            #[cfg(all(feature = "mqtt", not(target_arch = "wasm32")))]
+            mqtt: None,
+            #[cfg(all(feature = "otel", not(target_arch = "wasm32")))]
+            otel: None,
+            #[cfg(all(feature = "email", not(target_arch = "wasm32")))]
+            smtp: None,
+            #[cfg(all(feature = "lan-sync", not(target_arch = "wasm32")))]
+            lan_sync: None,
+            #[cfg(all(feature = "remote-agent", not(target_arch = "wasm32")))]
+            agent: AgentServerConfig::default(),
+            #[cfg(all(feature = "remote-agent", not(target_arch = "wasm32")))]
+            remote_agents: Vec::new(),
+            #[cfg(all(feature = "self-update", not(target_arch = "wasm32")))]
+            update: UpdateConfig::default(),
+            #[cfg(all(feature = "global-hotkey", not(target_arch = "wasm32")))]
+            pause_hotkey: None,
+            #[cfg(all(feature = "secrets", not(target_arch = "wasm32")))]
+            secrets_file: None,
+            #[cfg(debug_assertions)]
+            chaos: None,
+        }
+    }
+}
+
+/// The `[otel]` table. Changes take effect on the next start.
+#[cfg(all(feature = "otel", not(target_arch = "wasm32")))]
+#[derive(Debug, Clone, PartialEq, serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+pub struct OtelConfig {
+    /// Base URL of the collector's OTLP/HTTP receiver; `/v1/traces` and `/v1/metrics` are appended.
+    pub endpoint: String,
+    pub service_name: String,
+    pub metrics_interval_secs: u64,
+}
+
+#[cfg(all(feature = "otel", not(target_arch = "wasm32")))]
+impl Default for OtelConfig {
+    fn default() -> Self {
+        Self {
+            endpoint: "http://localhost:4318".to_owned(),
+            service_name: "functional_rust_ui_demo".to_owned(),
+            metrics_interval_secs: 60,
+        }
+    }
+}
+
+/// The `[smtp]` table. Changes take effect on the next start.
+#[cfg(all(feature = "email", not(target_arch = "wasm32")))]
+#[derive(Debug, Clone, PartialEq, serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+pub struct SmtpConfig {
+    pub host: String,
+    pub port: u16,
+    pub security: SmtpSecurity,
+    pub username: Option<String>,
+    /// May be a `${secret:NAME}` reference with the `secrets` feature.
+    pub password: Option<String>,
+    pub from: String,
+    pub to: Vec<String>,
+    /// Email a summary each time the queue runs dry after working through a batch.
+    pub batch_summary: bool,
+}
+
+#[cfg(all(feature = "email", not(target_arch = "wasm32")))]
+impl Default for SmtpConfig {
+    fn default() -> Self {
+        Self {
+            host: "localhost".to_owned(),
+            port: 587,
+            security: SmtpSecurity::StartTls,
+            username: None,
+            password: None,
+            from: "functional_rust_ui_demo@localhost".to_owned(),
+            to: Vec::new(),
+            batch_summary: true,
+        }
+    }
+}
+
+#[cfg(all(feature = "email", not(target_arch = "wasm32")))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SmtpSecurity {
+    /// Upgrade a plain connection, usually on port 587.
+    StartTls,
+    /// TLS from the start, usually on port 465.
+    Tls,
+    /// No encryption; only sensible for a relay on localhost.
+    None,
+}
+
+/// The `[lan_sync]` table. Changes take effect on the next start.
+#[cfg(all(feature = "lan-sync", not(target_arch = "wasm32")))]
+#[derive(Debug, Clone, PartialEq, serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+pub struct LanSyncConfig {
+    /// How other instances list this one; must be unique on the network.
+    pub instance_name: String,
+    /// TCP port the queue is shared on; 0 picks a free one.
+    pub port: u16,
+}
+
+#[cfg(all(feature = "lan-sync", not(target_arch = "wasm32")))]
+impl Default for LanSyncConfig {
+    fn default() -> Self {
+        let host = std::env::var("HOSTNAME")
+            .or_else(|_| std::env::var("COMPUTERNAME"))
+            .unwrap_or_else(|_| "taskqueue".to_owned());
+        Self {
+            instance_name: host,
+            port: 0,
+        }
+    }
+}
+
+/// The `[agent]` table, used when this instance runs as a remote agent.
+#[cfg(all(feature = "remote-agent", not(target_arch = "wasm32")))]
+#[derive(Debug, Clone, PartialEq, serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+pub struct AgentServerConfig {
+    pub listen: String,
+    /// A secret giving controllers admin access, kept for configs written before
+    /// `tokens`. The agent refuses to start without this or a token in `tokens`.
+    pub token: String,
+    /// Each `[[agent.tokens]]` entry lets whoever presents it do what its scope allows.
+    pub tokens: Vec<ApiToken>,
+}
+
+#[cfg(all(feature = "remote-agent", not(target_arch = "wasm32")))]
+impl Default for AgentServerConfig {
+    fn default() -> Self {
+        Self {
+            listen: "0.0.0.0:7878".to_owned(),
+            token: String::new(),
+            tokens: Vec::new(),
+        }
+    }
+}
+
+#[cfg(all(feature = "remote-agent", not(target_arch = "wasm32")))]
+impl AgentServerConfig {
+    /// Every usable token, `token` included as an admin token named `default`.
+    pub fn api_tokens(&self) -> Vec<ApiToken> {
+        let legacy = ApiToken {
+            name: "default".to_owned(),
+            token: self.token.clone(),
+            scope: ApiScope::Admin,
+        };
+        std::iter::once(legacy)
+            .chain(self.tokens.iter().cloned())
+            .filter(|token| !token.token.is_empty())
+            .collect()
+    }
+}
+
+/// An `[[agent.tokens]]` entry, e.g. `name = "dashboard"`, `token = "..."`,
+/// `scope = "read"`.
+#[cfg(all(feature = "remote-agent", not(target_arch = "wasm32")))]
+#[derive(Debug, Clone, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct ApiToken {
+    /// Who the token was given to, as shown in the audit trail.
+    pub name: String,
+    pub token: String,
+    pub scope: ApiScope,
+}
+
+/// What a token allows, each scope allowing everything the ones before it do.
+#[cfg(all(feature = "remote-agent", not(target_arch = "wasm32")))]
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, serde::Deserialize, serde::Serialize,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum ApiScope {
+    /// Listing the agent's tasks.
+    Read,
+    /// Running tasks, and pausing, resuming and cancelling the ones it ran.
+    Control,
+    /// Pausing, resuming and cancelling any task on the agent, whoever ran it.
+    Admin,
+}
+
+#[cfg(all(feature = "remote-agent", not(target_arch = "wasm32")))]
+impl Display for ApiScope {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        match self {
+            ApiScope::Read => write!(f, "read-only"),
+            ApiScope::Control => write!(f, "control"),
+            ApiScope::Admin => write!(f, "admin"),
+        }
+    }
+}
+
+/// A `[[remote_agents]]` entry, e.g. `name = "desktop"`, `url = "ws://desktop.lan:7878"`.
+#[cfg(all(feature = "remote-agent", not(target_arch = "wasm32")))]
+#[derive(Debug, Clone, Default, PartialEq, serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+pub struct RemoteAgentConfig {
+    pub name: String,
+    pub url: String,
+    pub token: String,
+    /// Where files fetched back from the agent are saved; the working directory if unset.
+    pub download_dir: Option<PathBuf>,
+}
+
+/// The `[update]` table.
+#[cfg(all(feature = "self-update", not(target_arch = "wasm32")))]
+#[derive(Debug, Clone, PartialEq, serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+pub struct UpdateConfig {
+    /// GitHub repository whose latest release is checked, as `owner/name`.
+    pub repository: String,
+    pub check_on_start: bool,
+    /// Release asset holding this platform's binary, with its SHA-256 in `<asset>.sha256`.
+    /// Defaults to `functional_rust_ui_demo-<os>-<arch>`, plus `.exe` on Windows.
+    pub asset_name: Option<String>,
+}
+
+#[cfg(all(feature = "self-update", not(target_arch = "wasm32")))]
+impl Default for UpdateConfig {
+    fn default() -> Self {
+        Self {
+            repository: "xthreen/upgraded-guide".to_owned(),
+            check_on_start: true,
+            asset_name: None,
+        }
+    }
+}
+
+impl AppConfig {
+    /// Called once at startup, before any config is loaded.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn set_overrides(overrides: ConfigOverrides) {
+        *OVERRIDES
+            .lock()
+            .expect("Panicked at set_overrides: Overrides mutex poisoned") = overrides;
+    }
+
+    pub fn from_toml_str(s: &str) -> Result<Self, ConfigError> {
+        let config: AppConfig = toml::from_str(s).map_err(|e| ConfigError::Parse(e.to_string()))?;
+        config.log_level_filter()?;
+        Ok(config)
+    }
+
+    pub fn to_toml_string(&self) -> Result<String, ConfigError> {
+        toml::to_string_pretty(self).map_err(|e| ConfigError::Parse(e.to_string()))
+    }
+
+    pub fn log_level_filter(&self) -> Result<LevelFilter, ConfigError> {
+        LevelFilter::from_str(&self.log_level)
+            .map_err(|_| ConfigError::InvalidLogLevel(self.log_level.clone()))
+    }
+
+    /// Location of `config.toml` in the platform config directory,
+    /// e.g. `~/.config/functional_rust_ui_demo/config.toml` on Linux,
+    /// unless another file was given with `--config`.
+    pub fn default_path() -> Result<PathBuf, ConfigError> {
+        if let Some(path) = overrides().path {
+            return Ok(path);
+        }
+        project_dirs().map(|dirs| dirs.config_dir().join(CONFIG_FILE_NAME))
+    }
+
+    #[cfg(all(feature = "sqlite", not(target_arch = "wasm32")))]
+    pub fn resolved_sqlite_path(&self) -> Result<PathBuf, ConfigError> {
+        match &self.sqlite_path {
+            Some(path) => Ok(path.clone()),
+            None => project_dirs().map(|dirs| dirs.data_dir().join("queue.sqlite3")),
+        }
+    }
+
+    #[cfg(all(feature = "secrets", not(target_arch = "wasm32")))]
+    pub fn resolved_secrets_file(&self) -> Result<PathBuf, ConfigError> {
+        match &self.secrets_file {
+            Some(path) => Ok(path.clone()),
+            None => project_dirs().map(|dirs| dirs.data_dir().join("secrets.json")),
+        }
+    }
+
+    #[cfg(all(
+        any(feature = "plugins", feature = "wasm-plugins"),
+        not(target_arch = "wasm32")
+    ))]
+    pub fn resolved_plugins_dir(&self) -> Result<PathBuf, ConfigError> {
+        match &self.plugins_dir {
+            Some(path) => Ok(path.clone()),
+            None => project_dirs().map(|dirs| dirs.config_dir().join("plugins")),
+        }
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn resolved_assets_dir(&self) -> Result<PathBuf, ConfigError> {
+        match &self.assets_dir {
+            Some(path) => Ok(path.clone()),
+            None => project_dirs().map(|dirs| dirs.config_dir().join("assets")),
+        }
+    }
+
+    pub fn load(path: &Path) -> Result<Self, ConfigError> {
+        let contents = std::fs::read_to_string(path).map_err(|e| ConfigError::Io(e.to_string()))?;
+        Self::from_toml_str(&contents)
+    }
+
+    /// Loads the config at `path`, falling back to defaults if the file does not exist yet.
+    pub fn load_or_default(path: &Path) -> Result<Self, ConfigError> {
+        if path.exists() {
+            Self::load(path)
+        } else {
+            debug!("No config at {}, using defaults", path.display());
+            Ok(Self::default())
+        }
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), ConfigError> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent).map_err(|e| ConfigError::Io(e.to_string()))?;
+        }
+        std::fs::write(path, self.to_toml_string()?).map_err(|e| ConfigError::Io(e.to_string()))
+    }
+
+    pub fn progress_granularity(&self) -> ProgressGranularity {
+        ProgressGranularity {
+            min_delta: self.progress_min_delta.max(0.0),
+            min_interval: Duration::from_millis(self.progress_min_interval_ms),
+        }
+    }
+
+    pub fn history_retention(&self) -> HistoryRetention {
+        HistoryRetention {
+            max_age: self
+                .history_max_age_days
+                .map(|days| Duration::from_secs(days.saturating_mul(24 * 60 * 60))),
+            max_count: self.history_max_count,
+            exclude_params: self.history_exclude_params,
+        }
+    }
+
+    /// Applies the settings that live outside the task queue (concurrency, log level).
+    pub fn apply_globals(&self) {
+        executor::set_worker_limit(self.concurrency);
+        if let Some(level) = overrides().log_level {
+            log::set_max_level(level);
+            return;
+        }
+        match self.log_level_filter() {
+            Ok(level) => log::set_max_level(level),
+            Err(e) => log::error!("{}", e),
+        }
+    }
+}
+
+/// Settings given on the command line, which win over `config.toml` for this run.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ConfigOverrides {
+    pub path: Option<PathBuf>,
+    pub log_level: Option<LevelFilter>,
+}
+
+static OVERRIDES: Mutex<ConfigOverrides> = Mutex::new(ConfigOverrides {
+    path: None,
+    log_level: None,
+});
+
+fn overrides() -> ConfigOverrides {
+    OVERRIDES
+        .lock()
+        .expect("Panicked at overrides: Overrides mutex poisoned")
+        .clone()
+}
+
+/// Watches the config file's modification time so edits made in an external editor
+/// are picked up while the app is running.
+pub struct ConfigWatcher {
+    path: PathBuf,
+    last_modified: Option<SystemTime>,
+    last_check: Instant,
+}
+
+impl ConfigWatcher {
+    pub fn new(path: PathBuf) -> Self {
+        let last_modified = modified_time(&path);
+        ConfigWatcher {
+            path,
+            last_modified,
+            last_check: Instant::now(),
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Returns the freshly loaded config if the file changed since the last check.
+    /// Checks the filesystem at most once per second.
+    pub fn poll_changed(&mut self) -> Option<Result<AppConfig, ConfigError>> {
+        if self.last_check.elapsed() < RELOAD_CHECK_INTERVAL {
+            return None;
+        }
+        self.last_check = Instant::now();
+        let modified = modified_time(&self.path);
+        if modified == self.last_modified {
+            return None;
+        }
+        self.last_modified = modified;
+        debug!("Config file changed, reloading {}", self.path.display());
+        Some(AppConfig::load_or_default(&self.path))
+    }
+}
+
+fn project_dirs() -> Result<directories_next::ProjectDirs, ConfigError> {
+    directories_next::ProjectDirs::from("net", "xthreen", "functional_rust_ui_demo")
+        .ok_or(ConfigError::NoConfigDir)
+}
+
+fn modified_time(path: &Path) -> Option<SystemTime> {
+    std::fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
+/// Opens `path` with the platform's default handler, writing a default config first if needed.
+pub fn open_config_file(path: &Path) -> Result<(), ConfigError> {
+    if !path.exists() {
+        AppConfig::default().save(path)?;
+    }
+    artifacts::open_path(path).map_err(|e| ConfigError::Io(e.to_string()))
+}
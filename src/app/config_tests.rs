@@ -0,0 +1,83 @@
+#[cfg(test)]
+use crate::app::config::{AppConfig, ConfigError, ConfigWatcher, Theme};
+
+#[test]
+fn test_empty_config_uses_defaults() {
+    let config = AppConfig::from_toml_str("").unwrap();
+    assert_eq!(config, AppConfig::default());
+}
+
+#[test]
+fn test_partial_config() {
+    let config = AppConfig::from_toml_str(
+        r#"
+        concurrency = 4
+        theme = "light"
+        bandwidth_cap = 1048576
+
+        [output_dirs]
+        sleep = "/tmp/sleep"
+        "#,
+    )
+    .unwrap();
+    assert_eq!(config.concurrency, Some(4));
+    assert_eq!(config.theme, Theme::Light);
+    assert_eq!(config.bandwidth_cap, Some(1_048_576));
+    assert_eq!(config.log_level, "info");
+    assert_eq!(
+        config.output_dirs.get("sleep"),
+        Some(&std::path::PathBuf::from("/tmp/sleep"))
+    );
+}
+
+#[test]
+fn test_restore_queue_can_be_turned_off() {
+    assert!(AppConfig::default().restore_queue);
+    let config = AppConfig::from_toml_str("restore_queue = false").unwrap();
+    assert!(!config.restore_queue);
+}
+
+#[test]
+fn test_invalid_log_level() {
+    let result = AppConfig::from_toml_str(r#"log_level = "loud""#);
+    assert_eq!(
+        result.unwrap_err(),
+        ConfigError::InvalidLogLevel("loud".to_owned())
+    );
+}
+
+#[test]
+fn test_save_and_reload() {
+    let dir = std::env::temp_dir().join(format!("config_test_{}", std::process::id()));
+    let path = dir.join("config.toml");
+    let config = AppConfig {
+        concurrency: Some(2),
+        log_level: "debug".to_owned(),
+        ..Default::default()
+    };
+    config.save(&path).unwrap();
+    assert_eq!(AppConfig::load(&path).unwrap(), config);
+
+    let mut watcher = ConfigWatcher::new(path.clone());
+    assert!(watcher.poll_changed().is_none());
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn test_watch_rules_round_trip() {
+    let config = AppConfig::from_toml_str(
+        r#"
+        watch_folder = "/tmp/inbox"
+
+        [[watch_rules]]
+        extensions = ["zip"]
+        kind = "extract"
+        params = { archive = "{path}" }
+        "#,
+    )
+    .unwrap();
+    assert_eq!(config.watch_rules.len(), 1);
+    assert_eq!(config.watch_rules[0].params["archive"], "{path}");
+    let reparsed = AppConfig::from_toml_str(&config.to_toml_string().unwrap()).unwrap();
+    assert_eq!(reparsed, config);
+}
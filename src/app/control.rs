@@ -0,0 +1,159 @@
+use std::collections::BTreeMap;
+use std::sync::Arc as sync_Arc;
+
+use serde_json::{json, Value};
+
+use crate::app::audit::{AuditAction, Origin};
+use crate::app::registry::{TaskKindRegistry, TaskParams};
+use crate::app::task_id::TaskId;
+use crate::app::task_queue::{PollResult, TaskError, TaskQueue, TaskStatus};
+
+/// A command understood by the external control channels, one JSON object per line:
+/// `{"command": "add_task", "kind": "sleep", "params": {"seconds": "5"}}`.
+#[derive(Debug, Clone, PartialEq, serde::Deserialize)]
+#[serde(tag = "command", rename_all = "snake_case")]
+pub enum ControlRequest {
+    AddTask {
+        kind: String,
+        #[serde(default)]
+        params: TaskParams,
+    },
+    Poll {
+        id: usize,
+    },
+    /// The progress as of the task's last poll, without polling it.
+    Progress {
+        id: usize,
+    },
+    Pause {
+        id: usize,
+    },
+    Resume {
+        id: usize,
+    },
+    Cancel {
+        id: usize,
+    },
+    /// Sets annotations on an unfinished task; a `null` value removes the key, e.g.
+    /// `{"command": "annotate", "id": 3, "annotations": {"commit": "4f1a9c2"}}`.
+    Annotate {
+        id: usize,
+        annotations: BTreeMap<String, Option<String>>,
+    },
+    List,
+    History {
+        #[serde(default)]
+        limit: Option<usize>,
+    },
+    Kinds,
+}
+
+/// Executes [`ControlRequest`]s against the app's queue, independent of the transport
+/// they arrived on, which `origin` names in the queue's audit log.
+#[derive(Clone)]
+pub struct ControlHandler {
+    queue: sync_Arc<TaskQueue>,
+    registry: sync_Arc<TaskKindRegistry>,
+    origin: Origin,
+}
+
+impl ControlHandler {
+    pub fn new(
+        queue: sync_Arc<TaskQueue>,
+        registry: sync_Arc<TaskKindRegistry>,
+        origin: Origin,
+    ) -> Self {
+        ControlHandler {
+            queue,
+            registry,
+            origin,
+        }
+    }
+
+    pub fn handle(&self, request: ControlRequest) -> Result<Value, String> {
+        match request {
+            ControlRequest::AddTask { kind, params } => {
+                let task = self
+                    .registry
+                    .create(&kind, &params)
+                    .map_err(|e| e.to_string())?;
+                let id = self.queue.add_task(task);
+                self.queue.audit(id, AuditAction::Add, &self.origin);
+                Ok(json!({ "id": id }))
+            }
+            ControlRequest::Poll { id } => {
+                let id = self.queue.task_id(id);
+                let result = self.queue.poll_task(id).map_err(|e| e.to_string())?;
+                Ok(progress_reply(id, &result))
+            }
+            ControlRequest::Progress { id } => {
+                let id = self.queue.task_id(id);
+                let result = self.queue.progress(id).map_err(|e| e.to_string())?;
+                Ok(progress_reply(id, &result))
+            }
+            ControlRequest::Pause { id } => {
+                let id = self.queue.task_id(id);
+                self.acknowledge(id, AuditAction::Pause, self.queue.pause_task(id))
+            }
+            ControlRequest::Resume { id } => {
+                let id = self.queue.task_id(id);
+                self.acknowledge(id, AuditAction::Resume, self.queue.resume_task(id))
+            }
+            ControlRequest::Cancel { id } => {
+                let id = self.queue.task_id(id);
+                self.acknowledge(id, AuditAction::Cancel, self.queue.remove_task(id))
+            }
+            ControlRequest::Annotate { id, annotations } => {
+                let annotations = self
+                    .queue
+                    .annotate(self.queue.task_id(id), annotations)
+                    .map_err(|e| e.to_string())?;
+                Ok(json!({ "id": id, "annotations": annotations }))
+            }
+            ControlRequest::List => to_value(&self.queue.records()),
+            ControlRequest::History { limit } => {
+                to_value(&self.queue.history_page(0, limit.unwrap_or(usize::MAX)))
+            }
+            ControlRequest::Kinds => Ok(Value::Array(
+                self.registry
+                    .kinds()
+                    .iter()
+                    .map(|info| json!({ "name": info.name, "params": info.params }))
+                    .collect(),
+            )),
+        }
+    }
+
+    /// Replies to a change of task `id`'s state, auditing it if it was made.
+    fn acknowledge(
+        &self,
+        id: TaskId,
+        action: AuditAction,
+        result: Result<(), TaskError>,
+    ) -> Result<Value, String> {
+        result.map_err(|e| e.to_string())?;
+        self.queue.audit(id, action, &self.origin);
+        Ok(json!({ "id": id }))
+    }
+}
+
+fn progress_reply(id: TaskId, result: &PollResult) -> Value {
+    let progress = match result {
+        PollResult::Pending(data) | PollResult::Paused(data) => data.fraction(),
+        PollResult::Completed => 1.0,
+        PollResult::Cancelled | PollResult::Failed(_) => 0.0,
+    };
+    let mut reply = json!({
+        "id": id,
+        "status": TaskStatus::from(result),
+        "progress": progress,
+    });
+    if let PollResult::Failed(e) = result {
+        reply["error"] = json!(e.to_string());
+    }
+    reply
+}
+
+fn to_value<T: serde::Serialize + ?Sized>(value: &T) -> Result<Value, String> {
+    serde_json::to_value(value).map_err(|e| e.to_string())
+}
@@ -0,0 +1,75 @@
+//! Serves the control protocol on the named pipe `\\.\pipe\functional_rust_ui_demo.control`.
+//!
+//! Each request is one line of JSON and gets one line back. From PowerShell:
+//!
+//! ```powershell
+//! $pipe = New-Object IO.Pipes.NamedPipeClientStream('.', 'functional_rust_ui_demo.control', 'InOut')
+//! $pipe.Connect(1000)
+//! $writer = New-Object IO.StreamWriter($pipe); $writer.AutoFlush = $true
+//! $reader = New-Object IO.StreamReader($pipe)
+//! $writer.WriteLine('{"command": "add_task", "kind": "sleep", "params": {"seconds": "5"}}')
+//! $reader.ReadLine()
+//! ```
+
+use std::io::{BufRead, BufReader, Write};
+
+use interprocess::local_socket::{prelude::*, GenericNamespaced, ListenerOptions, Stream};
+use serde_json::json;
+
+use crate::app::control::ControlHandler;
+
+const PIPE_NAME: &str = "functional_rust_ui_demo.control";
+
+/// Starts accepting pipe clients on a background thread, each served on its own thread.
+pub fn serve(handler: ControlHandler) -> std::io::Result<()> {
+    let name = PIPE_NAME.to_ns_name::<GenericNamespaced>()?;
+    let listener = ListenerOptions::new().name(name).create_sync()?;
+    log::info!("Control pipe listening on \\\\.\\pipe\\{}", PIPE_NAME);
+    std::thread::spawn(move || {
+        for stream in listener.incoming() {
+            match stream {
+                Ok(stream) => {
+                    let handler = handler.clone();
+                    std::thread::spawn(move || {
+                        if let Err(e) = serve_client(stream, &handler) {
+                            log::debug!("Control pipe client disconnected: {}", e);
+                        }
+                    });
+                }
+                Err(e) => log::warn!("Control pipe accept failed: {}", e),
+            }
+        }
+    });
+    Ok(())
+}
+
+fn serve_client(stream: Stream, handler: &ControlHandler) -> std::io::Result<()> {
+    let mut reader = BufReader::new(stream);
+    let mut line = String::new();
+    loop {
+        line.clear();
+        if reader.read_line(&mut line)? == 0 {
+            return Ok(());
+        }
+        if line.trim().is_empty() {
+            continue;
+        }
+        let reply = reply_line(handler, line.trim());
+        let stream = reader.get_mut();
+        stream.write_all(reply.as_bytes())?;
+        stream.write_all(b"\n")?;
+        stream.flush()?;
+    }
+}
+
+/// Replies with `{"ok": true, "result": ...}` or `{"ok": false, "error": "..."}`.
+fn reply_line(handler: &ControlHandler, line: &str) -> String {
+    let result = serde_json::from_str(line)
+        .map_err(|e| format!("Invalid request: {}", e))
+        .and_then(|request| handler.handle(request));
+    let reply = match result {
+        Ok(result) => json!({ "ok": true, "result": result }),
+        Err(error) => json!({ "ok": false, "error": error }),
+    };
+    reply.to_string()
+}
@@ -0,0 +1,135 @@
+#[cfg(test)]
+use std::sync::Arc;
+
+#[cfg(test)]
+use serde_json::Value;
+
+#[cfg(test)]
+use crate::app::audit::{AuditAction, Interface, Origin};
+#[cfg(test)]
+use crate::app::control::ControlHandler;
+#[cfg(test)]
+use crate::app::registry::TaskKindRegistry;
+#[cfg(test)]
+use crate::app::task_queue::TaskQueue;
+
+#[cfg(test)]
+fn handler() -> ControlHandler {
+    ControlHandler::new(
+        Arc::new(TaskQueue::new()),
+        Arc::new(TaskKindRegistry::default()),
+        Origin::new(Interface::ControlPipe, "tester"),
+    )
+}
+
+#[cfg(test)]
+fn reply(handler: &ControlHandler, line: &str) -> Result<Value, String> {
+    handler.handle(serde_json::from_str(line).unwrap())
+}
+
+#[test]
+fn test_add_poll_and_cancel() {
+    let handler = handler();
+    let added = reply(
+        &handler,
+        r#"{"command": "add_task", "kind": "sleep", "params": {"seconds": "60"}}"#,
+    );
+    let id = added.unwrap()["id"].as_u64().unwrap();
+
+    let polled = reply(&handler, &format!(r#"{{"command": "poll", "id": {}}}"#, id));
+    assert_eq!(polled.unwrap()["status"], "Running");
+
+    let cancelled = reply(
+        &handler,
+        &format!(r#"{{"command": "cancel", "id": {}}}"#, id),
+    );
+    assert!(cancelled.is_ok());
+    let progress = reply(
+        &handler,
+        &format!(r#"{{"command": "progress", "id": {}}}"#, id),
+    );
+    assert_eq!(progress.unwrap()["status"], "Cancelled");
+    let history = reply(&handler, r#"{"command": "history", "limit": 10}"#);
+    assert_eq!(history.unwrap()[0]["status"], "Cancelled");
+}
+
+#[test]
+fn test_errors_are_reported() {
+    let handler = handler();
+    let unknown = reply(&handler, r#"{"command": "add_task", "kind": "teleport"}"#);
+    assert_eq!(unknown, Err("Unknown task kind: teleport".to_owned()));
+
+    let missing = reply(&handler, r#"{"command": "pause", "id": 7}"#);
+    assert_eq!(missing, Err("Task not found".to_owned()));
+}
+
+#[test]
+fn test_changes_are_audited() {
+    let queue = Arc::new(TaskQueue::new());
+    let handler = ControlHandler::new(
+        queue.clone(),
+        Arc::new(TaskKindRegistry::default()),
+        Origin::new(Interface::ControlPipe, "tester"),
+    );
+    let added = reply(
+        &handler,
+        r#"{"command": "add_task", "kind": "sleep", "params": {"seconds": "60"}}"#,
+    );
+    let id = queue.task_id(added.unwrap()["id"].as_u64().unwrap() as usize);
+    let paused = reply(
+        &handler,
+        &format!(r#"{{"command": "pause", "id": {}}}"#, id),
+    );
+    assert!(paused.is_ok());
+    let missing = reply(&handler, r#"{"command": "cancel", "id": 99}"#);
+    assert!(missing.is_err());
+
+    let actions: Vec<AuditAction> = queue
+        .audit_log(None)
+        .iter()
+        .map(|entry| entry.action)
+        .collect();
+    assert_eq!(actions, [AuditAction::Add, AuditAction::Pause]);
+    let entry = &queue.audit_log(Some(id))[0];
+    assert_eq!(entry.origin, Origin::new(Interface::ControlPipe, "tester"));
+    queue.remove_task(id).unwrap();
+}
+
+#[test]
+fn test_annotations() {
+    let handler = handler();
+    let added = reply(
+        &handler,
+        r#"{"command": "add_task", "kind": "sleep", "params": {"seconds": "60"}}"#,
+    );
+    let id = added.unwrap()["id"].as_u64().unwrap();
+    let annotate = |annotations: &str| {
+        reply(
+            &handler,
+            &format!(
+                r#"{{"command": "annotate", "id": {}, "annotations": {}}}"#,
+                id, annotations
+            ),
+        )
+    };
+
+    let annotated = annotate(r#"{"commit": "4f1a9c2", "branch": "main"}"#).unwrap();
+    assert_eq!(annotated["annotations"]["commit"], "4f1a9c2");
+    let annotated = annotate(r#"{"branch": null, "commit": "9e0b7d1"}"#).unwrap();
+    assert_eq!(
+        annotated["annotations"],
+        serde_json::json!({ "commit": "9e0b7d1" })
+    );
+    let list = reply(&handler, r#"{"command": "list"}"#).unwrap();
+    assert_eq!(list[0]["annotations"]["commit"], "9e0b7d1");
+
+    reply(
+        &handler,
+        &format!(r#"{{"command": "cancel", "id": {}}}"#, id),
+    )
+    .unwrap();
+    assert_eq!(
+        annotate(r#"{"commit": "late"}"#),
+        Err("Task is already cancelled".to_owned())
+    );
+}
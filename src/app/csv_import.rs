@@ -0,0 +1,109 @@
+use std::fmt::{Display, Formatter, Result as FmtResult};
+use std::io::Read;
+use std::path::Path;
+
+use crate::app::registry::{ParamSpec, TaskKindRegistry, TaskParams};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum CsvImportError {
+    Io(String),
+    Parse(String),
+    Empty,
+}
+
+impl Display for CsvImportError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        match self {
+            CsvImportError::Io(e) => write!(f, "Cannot read CSV file: {}", e),
+            CsvImportError::Parse(e) => write!(f, "Malformed CSV file: {}", e),
+            CsvImportError::Empty => write!(f, "The CSV file has no rows"),
+        }
+    }
+}
+
+/// For each column, the name of the task parameter it fills, or `None` to ignore it.
+pub type ColumnMapping = Vec<Option<String>>;
+
+/// A CSV file with a header row, kept as text until it is mapped to a task kind.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CsvTable {
+    pub headers: Vec<String>,
+    pub rows: Vec<Vec<String>>,
+}
+
+impl CsvTable {
+    pub fn from_reader<R: Read>(reader: R) -> Result<Self, CsvImportError> {
+        let mut reader = csv::ReaderBuilder::new()
+            .flexible(true)
+            .trim(csv::Trim::All)
+            .from_reader(reader);
+        let headers = reader
+            .headers()
+            .map_err(|e| CsvImportError::Parse(e.to_string()))?
+            .iter()
+            .map(str::to_owned)
+            .collect();
+        let mut rows = Vec::new();
+        for record in reader.records() {
+            let record = record.map_err(|e| CsvImportError::Parse(e.to_string()))?;
+            rows.push(record.iter().map(str::to_owned).collect());
+        }
+        if rows.is_empty() {
+            return Err(CsvImportError::Empty);
+        }
+        Ok(CsvTable { headers, rows })
+    }
+
+    pub fn load(path: &Path) -> Result<Self, CsvImportError> {
+        let file = std::fs::File::open(path).map_err(|e| CsvImportError::Io(e.to_string()))?;
+        Self::from_reader(file)
+    }
+
+    /// Checks every row against `kind`, returning the parameters of each valid row or
+    /// why it cannot become a task. Empty cells leave the parameter at its default.
+    pub fn validate(
+        &self,
+        registry: &TaskKindRegistry,
+        kind: &str,
+        mapping: &[Option<String>],
+    ) -> Vec<Result<TaskParams, String>> {
+        self.rows
+            .iter()
+            .map(|row| {
+                let mut params = TaskParams::new();
+                for (cell, name) in row.iter().zip(mapping) {
+                    if let Some(name) = name {
+                        if !cell.is_empty() {
+                            params.insert(name.clone(), cell.clone());
+                        }
+                    }
+                }
+                registry
+                    .create(kind, &params)
+                    .map(|_| params)
+                    .map_err(|e| e.to_string())
+            })
+            .collect()
+    }
+}
+
+/// Maps columns whose header names a parameter, ignoring case. When no header matches and
+/// the kind takes a single parameter, the first column fills it, so a single column of
+/// values works whatever its header says.
+pub fn auto_mapping(headers: &[String], params: &[ParamSpec]) -> ColumnMapping {
+    let mut mapping: ColumnMapping = headers
+        .iter()
+        .map(|header| {
+            params
+                .iter()
+                .find(|spec| spec.name.eq_ignore_ascii_case(header))
+                .map(|spec| spec.name.clone())
+        })
+        .collect();
+    if mapping.iter().all(Option::is_none) && params.len() == 1 {
+        if let Some(first) = mapping.first_mut() {
+            *first = Some(params[0].name.clone());
+        }
+    }
+    mapping
+}
@@ -0,0 +1,38 @@
+#[cfg(test)]
+use crate::app::csv_import::{auto_mapping, CsvImportError, CsvTable};
+#[cfg(test)]
+use crate::app::registry::TaskKindRegistry;
+
+#[test]
+fn test_map_and_validate_rows() {
+    let table =
+        CsvTable::from_reader("Seconds, note\n1, first\nsoon, second\n, third\n".as_bytes())
+            .unwrap();
+    assert_eq!(table.headers, vec!["Seconds", "note"]);
+    let registry = TaskKindRegistry::default();
+    let mapping = auto_mapping(&table.headers, &registry.get("sleep").unwrap().params);
+    assert_eq!(mapping, vec![Some("seconds".to_owned()), None]);
+
+    let checked = table.validate(&registry, "sleep", &mapping);
+    assert_eq!(checked[0].as_ref().unwrap()["seconds"], "1");
+    assert!(checked[1].is_err());
+    // An empty cell falls back to the parameter's default.
+    assert!(checked[2].as_ref().unwrap().is_empty());
+
+    assert!(table
+        .validate(&registry, "teleport", &mapping)
+        .iter()
+        .all(Result::is_err));
+}
+
+#[test]
+fn test_single_parameter_takes_first_column() {
+    let table = CsvTable::from_reader("duration\n5\n".as_bytes()).unwrap();
+    let registry = TaskKindRegistry::default();
+    let mapping = auto_mapping(&table.headers, &registry.get("sleep").unwrap().params);
+    assert_eq!(mapping, vec![Some("seconds".to_owned())]);
+    assert_eq!(
+        CsvTable::from_reader("duration\n".as_bytes()),
+        Err(CsvImportError::Empty)
+    );
+}
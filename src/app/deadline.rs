@@ -0,0 +1,39 @@
+//! Soft deadlines. Unlike a timeout, missing one cancels nothing: a task given a deadline
+//! is flagged as at risk once its rate so far says it will finish after it, or once the
+//! deadline passes with the task unfinished, and the queue can then raise its priority.
+//! See [`TaskQueue::set_deadline`](crate::app::task_queue::TaskQueue::set_deadline).
+
+use crate::app::forecast::MIN_PROGRESS;
+use crate::app::priority::Priority;
+use crate::app::task_id::TaskId;
+
+/// Sent to subscribers of
+/// [`TaskQueue::subscribe_deadlines`](crate::app::task_queue::TaskQueue::subscribe_deadlines)
+/// when a task is found to be at risk, once per deadline.
+#[derive(Debug, Clone, Copy, PartialEq, serde::Serialize)]
+pub struct DeadlineAtRisk {
+    pub id: TaskId,
+    /// Unix timestamps in milliseconds.
+    pub deadline: u64,
+    pub predicted_finish: u64,
+    /// After the boost, if the queue gave it one.
+    pub priority: Priority,
+}
+
+/// When a task that started at `started_at` and is `progress` done finishes if it keeps
+/// its rate so far; `None` until it has made enough progress to tell.
+pub fn predicted_finish(started_at: Option<u64>, progress: f32, now: u64) -> Option<u64> {
+    let started = started_at?;
+    if progress < MIN_PROGRESS {
+        return None;
+    }
+    let elapsed = now.saturating_sub(started) as f64;
+    Some(started + (elapsed / progress.min(1.0) as f64) as u64)
+}
+
+/// The predicted finish if it is past `deadline`. A task with no prediction yet is only
+/// at risk once the deadline has passed.
+pub fn at_risk(deadline: u64, started_at: Option<u64>, progress: f32, now: u64) -> Option<u64> {
+    let predicted = predicted_finish(started_at, progress, now).map_or(now, |t| t.max(now));
+    (predicted > deadline).then_some(predicted)
+}
@@ -0,0 +1,63 @@
+#[cfg(test)]
+use std::time::Duration;
+
+#[cfg(test)]
+use crate::app::deadline::{at_risk, predicted_finish};
+#[cfg(test)]
+use crate::app::history::now_millis;
+#[cfg(test)]
+use crate::app::priority::Priority;
+#[cfg(test)]
+use crate::app::sleep_task::SleepTask;
+#[cfg(test)]
+use crate::app::task_queue::{TaskError, TaskQueue};
+
+#[test]
+fn test_deadline_prediction() {
+    // A quarter done after 10 s: done 40 s after it started.
+    assert_eq!(predicted_finish(Some(1_000), 0.25, 11_000), Some(41_000));
+    assert_eq!(predicted_finish(Some(1_000), 0.0, 11_000), None);
+    assert_eq!(predicted_finish(None, 0.5, 11_000), None);
+
+    assert_eq!(at_risk(40_000, Some(1_000), 0.25, 11_000), Some(41_000));
+    assert_eq!(at_risk(41_000, Some(1_000), 0.25, 11_000), None);
+    // Without a prediction, only a deadline that has passed counts.
+    assert_eq!(at_risk(12_000, Some(1_000), 0.0, 11_000), None);
+    assert_eq!(at_risk(10_000, None, 0.0, 11_000), Some(11_000));
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+#[test]
+fn test_task_at_risk_is_reported_and_boosted_once() {
+    let queue = TaskQueue::new();
+    queue.set_boost_at_risk(true);
+    let deadlines = queue.subscribe_deadlines();
+    let id = queue.add_task(SleepTask::new(None, Duration::from_secs(60)));
+    let on_time = queue.add_task(SleepTask::new(None, Duration::from_secs(60)));
+    queue
+        .set_deadline(on_time, Some(now_millis() + 3_600_000))
+        .unwrap();
+    queue.set_deadline(id, Some(now_millis() - 1)).unwrap();
+    for _ in 0..2 {
+        queue.poll_task(id).unwrap();
+        queue.poll_task(on_time).unwrap();
+    }
+    assert!(queue.deadline_at_risk(id).unwrap());
+    assert!(!queue.deadline_at_risk(on_time).unwrap());
+    let event = deadlines.try_recv().unwrap();
+    assert_eq!((event.id, event.priority), (id, Priority::High));
+    assert!(deadlines.try_recv().is_err());
+    assert_eq!(
+        queue.task_detail(id).unwrap().record.priority,
+        Priority::High
+    );
+
+    // A new deadline is watched afresh.
+    queue.set_deadline(id, None).unwrap();
+    assert!(!queue.deadline_at_risk(id).unwrap());
+    queue.remove_tasks(&[id, on_time]);
+    assert_eq!(
+        queue.set_deadline(id, None),
+        Err(TaskError::AlreadyCancelled)
+    );
+}
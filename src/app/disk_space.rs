@@ -0,0 +1,31 @@
+//! Free space at a task's destination, checked before the task writes to it.
+
+use std::io;
+use std::path::Path;
+
+use crate::app::task_queue::TaskError;
+
+/// Bytes available to this user on the file system `path` is, or would be, on. A path
+/// that does not exist yet is looked up through its nearest existing ancestor.
+pub fn available_space(path: &Path) -> io::Result<u64> {
+    let existing = path
+        .ancestors()
+        .find(|ancestor| !ancestor.as_os_str().is_empty() && ancestor.exists())
+        .unwrap_or_else(|| Path::new("."));
+    fs2::available_space(existing)
+}
+
+/// Fails with [`TaskError::InsufficientSpace`] if `needed` bytes do not fit at `path`.
+/// A destination whose free space cannot be read is given the benefit of the doubt.
+pub fn ensure_space(path: &Path, needed: u64) -> Result<(), TaskError> {
+    match available_space(path) {
+        Ok(available) if available < needed => {
+            Err(TaskError::InsufficientSpace { needed, available })
+        }
+        Ok(_) => Ok(()),
+        Err(e) => {
+            log::warn!("Cannot read the free space at {}: {}", path.display(), e);
+            Ok(())
+        }
+    }
+}
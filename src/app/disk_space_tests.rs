@@ -0,0 +1,73 @@
+#[cfg(test)]
+use std::path::PathBuf;
+
+#[cfg(test)]
+use crate::app::disk_space::ensure_space;
+#[cfg(test)]
+use crate::app::task_id::TaskId;
+#[cfg(test)]
+use crate::app::task_queue::{PollResult, Task, TaskError, TaskKind, TaskQueue, TaskStatus};
+
+#[cfg(test)]
+struct HugeOutputTask {
+    id: Option<TaskId>,
+}
+
+#[cfg(test)]
+impl Task for HugeOutputTask {
+    fn id(&self) -> Result<TaskId, TaskError> {
+        self.id.ok_or(TaskError::IdUsizeIsNone)
+    }
+
+    fn set_id(&mut self, id: TaskId) {
+        self.id = Some(id);
+    }
+
+    fn poll(&mut self) -> PollResult {
+        PollResult::Completed
+    }
+
+    fn cancel(&mut self) -> Result<(), TaskError> {
+        Ok(())
+    }
+
+    fn pause(&mut self) -> Result<(), TaskError> {
+        Ok(())
+    }
+
+    fn resume(&mut self) -> Result<(), TaskError> {
+        Ok(())
+    }
+
+    fn kind(&self) -> TaskKind {
+        TaskKind::Sleep
+    }
+
+    fn required_space(&self) -> Option<(PathBuf, u64)> {
+        Some((std::env::temp_dir().join("not-yet/created.bin"), u64::MAX))
+    }
+}
+
+#[test]
+fn test_ensure_space() {
+    let dir = std::env::temp_dir();
+    assert_eq!(ensure_space(&dir, 1), Ok(()));
+    assert!(matches!(
+        ensure_space(&dir.join("missing/sub/dir"), u64::MAX),
+        Err(TaskError::InsufficientSpace {
+            needed: u64::MAX,
+            ..
+        })
+    ));
+}
+
+#[test]
+fn test_task_without_room_fails_before_it_starts() {
+    let task_queue = TaskQueue::new();
+    let task_id = task_queue.add_task(HugeOutputTask { id: None });
+    assert!(matches!(
+        task_queue.poll_task(task_id),
+        Err(TaskError::InsufficientSpace { .. })
+    ));
+    assert_eq!(task_queue.history()[0].status, TaskStatus::Failed);
+}
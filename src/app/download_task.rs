@@ -0,0 +1,328 @@
+use std::any::Any;
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc as sync_Arc, Mutex as sync_Mutex};
+use std::thread::JoinHandle;
+use std::time::Duration;
+
+use log::debug;
+
+use crate::app::disk_space;
+use crate::app::executor::{self, JobHandle};
+use crate::app::history::Artifact;
+use crate::app::resource_usage::{CpuMeter, ResourceUsage};
+use crate::app::speed_limit::SpeedLimit;
+use crate::app::task_id::TaskId;
+use crate::app::task_queue::PollingData;
+
+use super::task_queue::{PollResult, Task, TaskError, TaskKind, TaskStatus};
+
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(30);
+const CHUNK_SIZE: usize = 64 * 1024;
+/// How often a paused transfer checks whether it was resumed or cancelled.
+const PAUSE_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Streams a URL to a file over HTTP(S).
+///
+/// Progress is polled as the bytes written so far, out of the length the server reported.
+/// The body is written to `<path>.part` and renamed once complete, so `path` only ever
+/// holds a whole file.
+pub struct DownloadTask {
+    id: Option<TaskId>,
+    url: String,
+    path: PathBuf,
+    status: sync_Arc<sync_Mutex<TaskStatus>>,
+    /// Why the task failed, once it has.
+    error: sync_Arc<sync_Mutex<Option<String>>>,
+    progress: sync_Arc<Progress>,
+    speed_limit: sync_Arc<SpeedLimit>,
+    handle: Option<JoinHandle<()>>,
+}
+
+#[derive(Default)]
+struct Progress {
+    downloaded: AtomicU64,
+    /// Zero until the server reports a length.
+    total: AtomicU64,
+    /// Of the transfer thread.
+    cpu: CpuMeter,
+}
+
+impl Progress {
+    fn message(&self) -> String {
+        let downloaded = self.downloaded.load(Ordering::Relaxed);
+        match self.total.load(Ordering::Relaxed) {
+            0 => format!("{} bytes", downloaded),
+            total => format!("{} of {} bytes", downloaded, total),
+        }
+    }
+
+    fn data(&self) -> PollingData {
+        let total = self.total.load(Ordering::Relaxed);
+        PollingData::Bytes {
+            done: self.downloaded.load(Ordering::Relaxed),
+            total: (total > 0).then_some(total),
+        }
+    }
+}
+
+impl DownloadTask {
+    pub fn new(id: Option<TaskId>, url: String, path: PathBuf) -> Self {
+        debug!("DownloadTask::new() - {} -> {}", url, path.display());
+        DownloadTask {
+            id,
+            url,
+            path,
+            status: sync_Arc::new(sync_Mutex::new(TaskStatus::Queued)),
+            error: sync_Arc::new(sync_Mutex::new(None)),
+            progress: sync_Arc::new(Progress::default()),
+            speed_limit: sync_Arc::new(SpeedLimit::default()),
+            handle: None,
+        }
+    }
+
+    fn status(&self) -> TaskStatus {
+        self.status.lock().unwrap().clone()
+    }
+
+    fn set_status(&self, status: TaskStatus) {
+        *self.status.lock().unwrap() = status;
+    }
+}
+
+impl Task for DownloadTask {
+    fn id(&self) -> Result<TaskId, TaskError> {
+        self.id.ok_or(TaskError::IdUsizeIsNone)
+    }
+
+    fn set_id(&mut self, id: TaskId) {
+        self.id = Some(id);
+    }
+
+    fn poll(&mut self) -> PollResult {
+        match self.status() {
+            TaskStatus::Scheduled | TaskStatus::Queued => {
+                self.set_status(TaskStatus::Running);
+                let url = self.url.clone();
+                let path = self.path.clone();
+                let status = self.status.clone();
+                let error = self.error.clone();
+                let progress = self.progress.clone();
+                let speed_limit = self.speed_limit.clone();
+                self.handle = Some(std::thread::spawn(move || {
+                    let result = transfer(&url, &path, &status, &progress, &speed_limit);
+                    let mut status = status.lock().unwrap();
+                    match result {
+                        Ok(()) if *status != TaskStatus::Cancelled => {
+                            *status = TaskStatus::Completed;
+                        }
+                        Ok(()) => {}
+                        Err(_) if *status == TaskStatus::Cancelled => {}
+                        Err(e) => {
+                            log::error!("Download of {} failed: {}", url, e);
+                            *error.lock().unwrap() = Some(e.to_string());
+                            *status = TaskStatus::Failed;
+                        }
+                    }
+                }));
+                PollResult::Pending(self.progress.data())
+            }
+            TaskStatus::Running => PollResult::Pending(self.progress.data()),
+            TaskStatus::Paused => PollResult::Paused(self.progress.data()),
+            TaskStatus::Completed => PollResult::Completed,
+            TaskStatus::Cancelled | TaskStatus::Interrupted => PollResult::Cancelled,
+            TaskStatus::Failed => PollResult::Failed(TaskError::Failed {
+                reason: self.error.lock().unwrap().clone().unwrap_or_default(),
+            }),
+        }
+    }
+
+    fn cancel(&mut self) -> Result<(), TaskError> {
+        match self.status() {
+            TaskStatus::Completed => Err(TaskError::AlreadyCompleted),
+            TaskStatus::Cancelled | TaskStatus::Failed | TaskStatus::Interrupted => {
+                Err(TaskError::AlreadyCancelled)
+            }
+            _ => {
+                self.set_status(TaskStatus::Cancelled);
+                Ok(())
+            }
+        }
+    }
+
+    fn pause(&mut self) -> Result<(), TaskError> {
+        match self.status() {
+            TaskStatus::Scheduled | TaskStatus::Queued | TaskStatus::Running => {
+                self.set_status(TaskStatus::Paused);
+                Ok(())
+            }
+            TaskStatus::Paused => Err(TaskError::AlreadyPaused),
+            TaskStatus::Completed => Err(TaskError::AlreadyCompleted),
+            TaskStatus::Cancelled | TaskStatus::Failed | TaskStatus::Interrupted => {
+                Err(TaskError::AlreadyCancelled)
+            }
+        }
+    }
+
+    fn resume(&mut self) -> Result<(), TaskError> {
+        match self.status() {
+            TaskStatus::Scheduled | TaskStatus::Queued => Err(TaskError::NotFound),
+            TaskStatus::Running => Err(TaskError::AlreadyRunning),
+            // Paused before it ever started: back to the queue so the next poll starts it.
+            TaskStatus::Paused if self.handle.is_none() => {
+                self.set_status(TaskStatus::Queued);
+                Ok(())
+            }
+            TaskStatus::Paused => {
+                self.set_status(TaskStatus::Running);
+                Ok(())
+            }
+            TaskStatus::Completed => Err(TaskError::AlreadyCompleted),
+            TaskStatus::Cancelled | TaskStatus::Failed | TaskStatus::Interrupted => {
+                Err(TaskError::AlreadyCancelled)
+            }
+        }
+    }
+
+    fn kind(&self) -> TaskKind {
+        TaskKind::Download
+    }
+
+    fn take_handle(&mut self) -> Option<JobHandle> {
+        self.handle.take().map(executor::join_thread)
+    }
+
+    fn message(&self) -> Option<String> {
+        Some(format!("{} from {}", self.progress.message(), self.url))
+    }
+
+    fn resource_usage(&self) -> Option<ResourceUsage> {
+        Some(ResourceUsage {
+            cpu_time: self.progress.cpu.total(),
+            io_bytes: Some(self.progress.downloaded.load(Ordering::Relaxed)),
+        })
+    }
+
+    fn artifacts(&self) -> Vec<Artifact> {
+        vec![Artifact::File {
+            path: self.path.clone(),
+        }]
+    }
+
+    /// The path of the downloaded file.
+    fn take_result(&mut self) -> Option<Box<dyn Any + Send>> {
+        Some(Box::new(self.path.clone()))
+    }
+
+    fn speed_limit(&self) -> Option<sync_Arc<SpeedLimit>> {
+        Some(self.speed_limit.clone())
+    }
+
+    fn validate(&self) -> Result<(), TaskError> {
+        let invalid = |field: &str, message: String| TaskError::InvalidInput {
+            field: field.to_owned(),
+            message,
+        };
+        match url::Url::parse(&self.url) {
+            Ok(url) if matches!(url.scheme(), "http" | "https") => {}
+            _ => {
+                return Err(invalid(
+                    "url",
+                    format!("'{}' is not an http(s) URL", self.url),
+                ))
+            }
+        }
+        if self.path.is_dir() {
+            return Err(invalid(
+                "path",
+                format!("{} is a directory", self.path.display()),
+            ));
+        }
+        match self.path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+            Some(dir) if !dir.is_dir() => {
+                Err(invalid("path", format!("{} does not exist", dir.display())))
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Copies the response body to `<path>.part`, holding while paused, and renames it to
+/// `path` once the whole body has arrived. The partial file is removed on cancel or error.
+fn transfer(
+    url: &str,
+    path: &Path,
+    status: &sync_Mutex<TaskStatus>,
+    progress: &Progress,
+    speed_limit: &SpeedLimit,
+) -> Result<(), String> {
+    let mut part = path.as_os_str().to_owned();
+    part.push(".part");
+    let part = PathBuf::from(part);
+    let result = copy_body(url, &part, status, progress, speed_limit);
+    match result {
+        Ok(true) => std::fs::rename(&part, path).map_err(|e| e.to_string()),
+        Ok(false) | Err(_) => {
+            let _ = std::fs::remove_file(&part);
+            result.map(|_| ())
+        }
+    }
+}
+
+/// Returns whether the body was copied in full, i.e. the task was not cancelled.
+fn copy_body(
+    url: &str,
+    part: &Path,
+    status: &sync_Mutex<TaskStatus>,
+    progress: &Progress,
+    speed_limit: &SpeedLimit,
+) -> Result<bool, String> {
+    let mut cpu = progress.cpu.span();
+    let client = reqwest::blocking::Client::builder()
+        .connect_timeout(CONNECT_TIMEOUT)
+        .timeout(None)
+        .build()
+        .map_err(|e| e.to_string())?;
+    let mut response = client
+        .get(url)
+        .send()
+        .and_then(|response| response.error_for_status())
+        .map_err(|e| e.to_string())?;
+    progress
+        .total
+        .store(response.content_length().unwrap_or(0), Ordering::Relaxed);
+    // The size is only known from the response, so this is as early as the check can be.
+    if let Some(length) = response.content_length() {
+        disk_space::ensure_space(part, length).map_err(|e| e.to_string())?;
+    }
+    let mut file = std::io::BufWriter::new(std::fs::File::create(part).map_err(|e| e.to_string())?);
+    let mut buffer = vec![0; CHUNK_SIZE];
+    loop {
+        match status.lock().unwrap().clone() {
+            TaskStatus::Cancelled | TaskStatus::Interrupted => return Ok(false),
+            TaskStatus::Paused => {
+                std::thread::sleep(PAUSE_POLL_INTERVAL);
+                continue;
+            }
+            _ => {}
+        }
+        let chunk = speed_limit.chunk_size(CHUNK_SIZE);
+        let read = response
+            .read(&mut buffer[..chunk])
+            .map_err(|e| e.to_string())?;
+        if read == 0 {
+            break;
+        }
+        file.write_all(&buffer[..read]).map_err(|e| e.to_string())?;
+        progress
+            .downloaded
+            .fetch_add(read as u64, Ordering::Relaxed);
+        cpu.sample();
+        speed_limit.throttle(read as u64, || {
+            *status.lock().unwrap() != TaskStatus::Running
+        });
+    }
+    file.flush().map_err(|e| e.to_string())?;
+    Ok(true)
+}
@@ -0,0 +1,119 @@
+#[cfg(test)]
+use std::io::{Read, Write};
+#[cfg(test)]
+use std::net::TcpListener;
+#[cfg(test)]
+use std::path::PathBuf;
+#[cfg(test)]
+use std::sync::mpsc;
+#[cfg(test)]
+use std::time::Duration;
+
+#[cfg(test)]
+use crate::app::download_task::DownloadTask;
+#[cfg(test)]
+use crate::app::task_id::TaskId;
+#[cfg(test)]
+use crate::app::task_queue::{PollResult, PollingData, TaskQueue, TaskStatus};
+
+/// Serves `body` once, holding back all but the first `split` bytes until the returned
+/// sender is sent to or dropped.
+#[cfg(test)]
+fn serve(body: Vec<u8>, split: usize) -> (String, mpsc::Sender<()>) {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://{}/file.bin", listener.local_addr().unwrap());
+    let (release, released) = mpsc::channel();
+    std::thread::spawn(move || {
+        let (mut stream, _) = listener.accept().unwrap();
+        let mut request = Vec::new();
+        let mut byte = [0; 1];
+        while !request.ends_with(b"\r\n\r\n") && stream.read(&mut byte).unwrap() == 1 {
+            request.push(byte[0]);
+        }
+        let head = format!(
+            "HTTP/1.1 200 OK\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+            body.len()
+        );
+        stream.write_all(head.as_bytes()).unwrap();
+        stream.write_all(&body[..split]).unwrap();
+        stream.flush().unwrap();
+        let _ = released.recv();
+        let _ = stream.write_all(&body[split..]);
+    });
+    (url, release)
+}
+
+#[cfg(test)]
+fn temp_path(name: &str) -> PathBuf {
+    std::env::temp_dir().join(format!("{}_{}", name, std::process::id()))
+}
+
+/// Polls `task_id` until `done` holds for the result, which it returns.
+#[cfg(test)]
+fn poll_until(
+    queue: &TaskQueue,
+    task_id: TaskId,
+    done: impl Fn(&PollResult) -> bool,
+) -> PollResult {
+    for _ in 0..500 {
+        let result = queue.poll_task(task_id).unwrap();
+        if done(&result) {
+            return result;
+        }
+        std::thread::sleep(Duration::from_millis(10));
+    }
+    panic!("Download did not get there within the expected time");
+}
+
+#[test]
+fn test_download_reports_bytes_and_survives_a_pause() {
+    let body: Vec<u8> = (0..200_000).map(|i| i as u8).collect();
+    let (url, release) = serve(body.clone(), 50_000);
+    let path = temp_path("download_bytes");
+    let queue = TaskQueue::new();
+    let task_id = queue.add_task(DownloadTask::new(None, url, path.clone()));
+
+    let halfway = PollResult::Pending(PollingData::Bytes {
+        done: 50_000,
+        total: Some(200_000),
+    });
+    poll_until(&queue, task_id, |result| *result == halfway);
+    queue.pause_task(task_id).unwrap();
+    assert!(matches!(
+        queue.poll_task(task_id).unwrap(),
+        PollResult::Paused(PollingData::Bytes { .. })
+    ));
+    release.send(()).unwrap();
+    queue.resume_task(task_id).unwrap();
+
+    poll_until(&queue, task_id, |result| *result == PollResult::Completed);
+    assert_eq!(std::fs::read(&path).unwrap(), body);
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn test_cancelled_download_leaves_no_file() {
+    let (url, release) = serve(vec![7; 100_000], 10_000);
+    let path = temp_path("download_cancelled");
+    let queue = TaskQueue::new();
+    let task_id = queue.add_task(DownloadTask::new(None, url, path.clone()));
+    poll_until(
+        &queue,
+        task_id,
+        |result| matches!(result, PollResult::Pending(PollingData::Bytes { done, .. }) if *done > 0),
+    );
+
+    queue.remove_task(task_id).unwrap();
+    drop(release);
+    let mut part = path.clone().into_os_string();
+    part.push(".part");
+    for _ in 0..500 {
+        if !PathBuf::from(&part).exists() {
+            break;
+        }
+        std::thread::sleep(Duration::from_millis(10));
+    }
+    assert!(!PathBuf::from(&part).exists());
+    assert!(!path.exists());
+    assert_eq!(queue.status(task_id), Ok(TaskStatus::Cancelled));
+}
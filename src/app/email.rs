@@ -0,0 +1,159 @@
+use std::collections::HashSet;
+use std::fmt::{Display, Formatter, Result as FmtResult};
+use std::sync::mpsc::Receiver;
+
+use lettre::message::Mailbox;
+use lettre::transport::smtp::authentication::Credentials;
+use lettre::{Message, SmtpTransport, Transport};
+
+use crate::app::config::{SmtpConfig, SmtpSecurity};
+use crate::app::format;
+use crate::app::history::TaskRecord;
+use crate::app::task_id::TaskId;
+use crate::app::task_queue::TaskStatus;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum EmailError {
+    Address(String),
+    Message(String),
+    Smtp(String),
+}
+
+impl Display for EmailError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        match self {
+            EmailError::Address(e) => write!(f, "Invalid email address: {}", e),
+            EmailError::Message(e) => write!(f, "Cannot build email: {}", e),
+            EmailError::Smtp(e) => write!(f, "SMTP error: {}", e),
+        }
+    }
+}
+
+/// Groups task events into batches: a batch ends when no task it saw is still active.
+#[derive(Debug, Default)]
+pub struct BatchTracker {
+    active: HashSet<TaskId>,
+    finished: Vec<TaskRecord>,
+}
+
+impl BatchTracker {
+    /// Returns the finished batch when `record` was the last active task in it.
+    pub fn record(&mut self, record: &TaskRecord) -> Option<Vec<TaskRecord>> {
+        if !record.status.is_terminal() {
+            self.active.insert(record.id);
+            return None;
+        }
+        self.active.remove(&record.id);
+        self.finished.push(record.clone());
+        if self.active.is_empty() {
+            Some(std::mem::take(&mut self.finished))
+        } else {
+            None
+        }
+    }
+}
+
+/// Subject and plain-text body summarising a finished batch.
+pub fn summary(batch: &[TaskRecord]) -> (String, String) {
+    let completed = batch
+        .iter()
+        .filter(|record| record.status == TaskStatus::Completed)
+        .count();
+    let failed: Vec<&TaskRecord> = batch
+        .iter()
+        .filter(|record| record.status != TaskStatus::Completed)
+        .collect();
+    let subject = if failed.is_empty() {
+        format!(
+            "Task batch finished: {} completed",
+            format::count(completed)
+        )
+    } else {
+        format!(
+            "Task batch finished: {} completed, {} not completed",
+            format::count(completed),
+            format::count(failed.len())
+        )
+    };
+
+    let mut body = String::new();
+    if !failed.is_empty() {
+        body.push_str("Not completed:\n");
+        for record in &failed {
+            body.push_str(&format!("  {}\n", summary_line(record)));
+        }
+        body.push('\n');
+    }
+    body.push_str("All tasks:\n");
+    for record in batch {
+        body.push_str(&format!("  {}\n", summary_line(record)));
+    }
+    (subject, body)
+}
+
+fn summary_line(record: &TaskRecord) -> String {
+    let duration = match (record.started_at, record.finished_at) {
+        (Some(started), Some(finished)) => {
+            format!(" in {}", format::duration_millis(started, finished))
+        }
+        _ => String::new(),
+    };
+    format!(
+        "#{} {} {}{}",
+        record.id, record.kind, record.status, duration
+    )
+}
+
+pub fn send(config: &SmtpConfig, subject: &str, body: String) -> Result<(), EmailError> {
+    let from: Mailbox = config
+        .from
+        .parse()
+        .map_err(|e| EmailError::Address(format!("{}: {}", config.from, e)))?;
+    let mut message = Message::builder().from(from).subject(subject);
+    for to in &config.to {
+        let to: Mailbox = to
+            .parse()
+            .map_err(|e| EmailError::Address(format!("{}: {}", to, e)))?;
+        message = message.to(to);
+    }
+    let message = message
+        .body(body)
+        .map_err(|e| EmailError::Message(e.to_string()))?;
+
+    let transport = match config.security {
+        SmtpSecurity::StartTls => SmtpTransport::starttls_relay(&config.host),
+        SmtpSecurity::Tls => SmtpTransport::relay(&config.host),
+        SmtpSecurity::None => Ok(SmtpTransport::builder_dangerous(&config.host)),
+    }
+    .map_err(|e| EmailError::Smtp(e.to_string()))?;
+    let mut transport = transport.port(config.port);
+    if let Some(username) = &config.username {
+        transport = transport.credentials(Credentials::new(
+            username.clone(),
+            config.password.clone().unwrap_or_default(),
+        ));
+    }
+    transport
+        .build()
+        .send(&message)
+        .map(|_| ())
+        .map_err(|e| EmailError::Smtp(e.to_string()))
+}
+
+/// Emails a summary of each batch that finishes, until the queue drops its end of `events`.
+pub fn spawn_batch_mailer(config: &SmtpConfig, events: Receiver<TaskRecord>) {
+    let config = config.clone();
+    std::thread::spawn(move || {
+        let mut tracker = BatchTracker::default();
+        for record in events {
+            let Some(batch) = tracker.record(&record) else {
+                continue;
+            };
+            let (subject, body) = summary(&batch);
+            match send(&config, &subject, body) {
+                Ok(()) => log::info!("Sent batch summary to {}", config.to.join(", ")),
+                Err(e) => log::error!("Failed to send batch summary: {}", e),
+            }
+        }
+    });
+}
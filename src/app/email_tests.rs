@@ -0,0 +1,41 @@
+#[cfg(test)]
+use crate::app::email::{summary, BatchTracker};
+#[cfg(test)]
+use crate::app::history::TaskRecord;
+#[cfg(test)]
+use crate::app::task_id::TaskId;
+#[cfg(test)]
+use crate::app::task_queue::TaskStatus;
+
+#[cfg(test)]
+fn with_status(mut record: TaskRecord, status: TaskStatus) -> TaskRecord {
+    record.status = status;
+    record
+}
+
+#[test]
+fn test_batch_ends_when_queue_drains() {
+    let mut tracker = BatchTracker::default();
+    let first = TaskRecord::new(TaskId::detached(0), "sleep");
+    let second = TaskRecord::new(TaskId::detached(1), "download");
+    assert_eq!(tracker.record(&first), None);
+    assert_eq!(tracker.record(&second), None);
+    assert_eq!(
+        tracker.record(&with_status(first.clone(), TaskStatus::Completed)),
+        None
+    );
+    let batch = tracker
+        .record(&with_status(second.clone(), TaskStatus::Cancelled))
+        .unwrap();
+    assert_eq!(batch.len(), 2);
+
+    let (subject, body) = summary(&batch);
+    assert_eq!(subject, "Task batch finished: 1 completed, 1 not completed");
+    assert!(body.starts_with("Not completed:\n  #1 download cancelled\n"));
+
+    // The next task starts a new batch.
+    assert_eq!(
+        tracker.record(&TaskRecord::new(TaskId::detached(2), "sleep")),
+        None
+    );
+}
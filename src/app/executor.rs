@@ -0,0 +1,386 @@
+//! The async runtime that task futures and blocking jobs run on.
+//!
+//! async-std by default. With the `tokio` feature everything is spawned on the tokio
+//! runtime of the calling thread, or a shared multi-threaded one outside of any, so the
+//! queue can be embedded in a tokio application and task kinds built on tokio-based
+//! crates find the reactor they expect. Nothing outside this module names a runtime.
+//! Natively, jobs marked CPU-bound go to rayon's pool instead, whichever runtime is used.
+//!
+//! On the web there is a single thread, shared with the canvas: futures are spawned onto
+//! the page's event loop, and long-running work has to [`yield_now`] regularly so the page
+//! can draw in between.
+//!
+//! Task bodies are not spawned one future each: they are [`submit`]ted to a ready queue
+//! served by a set of worker loops, at most `concurrency` of them, which are kept around
+//! and reused for the next task. Workers take the most urgent job first, and jobs of the
+//! same priority in the order they were submitted.
+
+use std::cell::RefCell;
+use std::cmp::Reverse;
+use std::collections::BTreeMap;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc as sync_Arc, Mutex as sync_Mutex};
+use std::time::Duration;
+
+use async_std::channel::{self, Receiver, Sender};
+
+use crate::app::priority::Priority;
+
+#[cfg(not(target_arch = "wasm32"))]
+pub use std::time::Instant;
+
+/// `std::time::Instant` panics on the web, where time comes from `performance.now()`.
+#[cfg(target_arch = "wasm32")]
+pub use instant::Instant;
+
+#[cfg(not(any(feature = "tokio", target_arch = "wasm32")))]
+pub type JoinHandle<T> = async_std::task::JoinHandle<T>;
+
+#[cfg(all(feature = "tokio", not(target_arch = "wasm32")))]
+pub type JoinHandle<T> = tokio::task::JoinHandle<T>;
+
+/// Resolves to the future's output; dropping it does not stop the future.
+#[cfg(target_arch = "wasm32")]
+pub type JoinHandle<T> = futures::channel::oneshot::Receiver<T>;
+
+#[cfg(not(any(feature = "tokio", target_arch = "wasm32")))]
+pub fn spawn<F>(future: F) -> JoinHandle<F::Output>
+where
+    F: Future + Send + 'static,
+    F::Output: Send + 'static,
+{
+    async_std::task::spawn(future)
+}
+
+#[cfg(all(feature = "tokio", not(target_arch = "wasm32")))]
+pub fn spawn<F>(future: F) -> JoinHandle<F::Output>
+where
+    F: Future + Send + 'static,
+    F::Output: Send + 'static,
+{
+    runtime::handle().spawn(future)
+}
+
+#[cfg(target_arch = "wasm32")]
+pub fn spawn<F>(future: F) -> JoinHandle<F::Output>
+where
+    F: Future + 'static,
+    F::Output: 'static,
+{
+    let (sender, receiver) = futures::channel::oneshot::channel();
+    wasm_bindgen_futures::spawn_local(async move {
+        let _ = sender.send(future.await);
+    });
+    receiver
+}
+
+/// Resolves once a worker has finished the job; dropping it does not cancel the job.
+pub type JobHandle = futures::channel::oneshot::Receiver<()>;
+
+type Job = Pin<Box<dyn Future<Output = ()> + Send>>;
+
+/// Where a job waits in a [`ReadyQueue`]: most urgent first, then in submission order.
+type ReadyKey = (Reverse<Priority>, u64);
+
+/// Jobs waiting for a worker, taken most urgent first.
+pub struct ReadyQueue<J> {
+    jobs: BTreeMap<ReadyKey, (J, Option<JobTicket>)>,
+    submitted: u64,
+}
+
+impl<J> Default for ReadyQueue<J> {
+    fn default() -> Self {
+        ReadyQueue {
+            jobs: BTreeMap::new(),
+            submitted: 0,
+        }
+    }
+}
+
+impl<J> ReadyQueue<J> {
+    /// Adds `job` behind the jobs at least as urgent as its ticket, or as a normal one
+    /// without a ticket.
+    pub fn push(&mut self, job: J, ticket: Option<JobTicket>) {
+        let priority = ticket
+            .as_ref()
+            .map_or(Priority::Normal, |ticket| ticket.lock().priority);
+        let key = (Reverse(priority), self.submitted);
+        self.submitted += 1;
+        if let Some(ticket) = &ticket {
+            ticket.lock().waiting = Some(key);
+        }
+        self.jobs.insert(key, (job, ticket));
+    }
+
+    pub fn pop(&mut self) -> Option<J> {
+        let key = *self.jobs.keys().next()?;
+        let (job, ticket) = self.jobs.remove(&key)?;
+        if let Some(ticket) = ticket {
+            ticket.lock().waiting = None;
+        }
+        Some(job)
+    }
+
+    /// Sets the ticket's priority and, if its job is waiting here, moves the job behind
+    /// the others of that priority.
+    pub fn reprioritize(&mut self, ticket: &JobTicket, priority: Priority) {
+        let mut state = ticket.lock();
+        state.priority = priority;
+        let Some(key) = state.waiting else {
+            return;
+        };
+        if let Some(job) = self.jobs.remove(&key) {
+            let key = (Reverse(priority), self.submitted);
+            self.submitted += 1;
+            state.waiting = Some(key);
+            self.jobs.insert(key, job);
+        }
+    }
+}
+
+struct WorkerPool {
+    /// One message per job in `ready`, to wake a worker for it.
+    sender: Sender<()>,
+    receiver: Receiver<()>,
+    ready: ReadyQueue<Job>,
+    workers: usize,
+    /// Workers waiting for a job.
+    idle: usize,
+    limit: Option<usize>,
+}
+
+static POOL: sync_Mutex<Option<WorkerPool>> = sync_Mutex::new(None);
+
+fn with_pool<R>(f: impl FnOnce(&mut WorkerPool) -> R) -> R {
+    let mut pool = POOL
+        .lock()
+        .expect("Panicked at with_pool: Worker pool mutex poisoned");
+    let pool = pool.get_or_insert_with(|| {
+        let (sender, receiver) = channel::unbounded();
+        WorkerPool {
+            sender,
+            receiver,
+            ready: ReadyQueue::default(),
+            workers: 0,
+            idle: 0,
+            limit: None,
+        }
+    });
+    f(pool)
+}
+
+impl WorkerPool {
+    /// Starts workers until every queued job has one waiting for it, or the limit is hit.
+    fn grow(&mut self) {
+        while self.receiver.len() > self.idle && self.limit.map_or(true, |n| self.workers < n) {
+            self.workers += 1;
+            self.idle += 1;
+            spawn(work(self.receiver.clone()));
+        }
+    }
+}
+
+async fn work(receiver: Receiver<()>) {
+    while let Ok(()) = receiver.recv().await {
+        let job = with_pool(|pool| {
+            pool.idle -= 1;
+            pool.ready.pop()
+        });
+        if let Some(job) = job {
+            job.await;
+        }
+        let retire = with_pool(|pool| {
+            if pool.limit.map_or(false, |n| pool.workers > n) {
+                pool.workers -= 1;
+                true
+            } else {
+                pool.idle += 1;
+                false
+            }
+        });
+        if retire {
+            return;
+        }
+    }
+}
+
+/// A task's place in the ready queue. Its priority can change until a worker takes the
+/// job, e.g. when the user makes a task that is waiting for a worker more urgent.
+#[derive(Debug, Clone, Default)]
+pub struct JobTicket(sync_Arc<sync_Mutex<TicketState>>);
+
+#[derive(Debug, Default)]
+struct TicketState {
+    priority: Priority,
+    /// The job's key in the ready queue while it waits there.
+    waiting: Option<ReadyKey>,
+}
+
+impl JobTicket {
+    pub fn new(priority: Priority) -> Self {
+        JobTicket(sync_Arc::new(sync_Mutex::new(TicketState {
+            priority,
+            waiting: None,
+        })))
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, TicketState> {
+        self.0
+            .lock()
+            .expect("Panicked at JobTicket::lock: Ticket mutex poisoned")
+    }
+
+    /// Moves the job, if it is still waiting for a worker, among the jobs of `priority`.
+    pub fn set_priority(&self, priority: Priority) {
+        // The pool is always locked before a ticket.
+        with_pool(|pool| pool.ready.reprioritize(self, priority));
+    }
+}
+
+thread_local! {
+    static TICKET: RefCell<Option<JobTicket>> = const { RefCell::new(None) };
+}
+
+/// Runs `f` with `ticket` as the ticket of any job it [`submit`]s, which is how a task's
+/// priority reaches the job it starts on its first poll.
+pub fn with_ticket<R>(ticket: &JobTicket, f: impl FnOnce() -> R) -> R {
+    let previous = TICKET.with(|current| current.replace(Some(ticket.clone())));
+    let result = f();
+    TICKET.with(|current| *current.borrow_mut() = previous);
+    result
+}
+
+/// Queues `future` to run on the next free worker, after any more urgent jobs. Its
+/// priority is that of the ticket given to [`with_ticket`], if any, and normal otherwise.
+pub fn submit<F>(future: F) -> JobHandle
+where
+    F: Future<Output = ()> + Send + 'static,
+{
+    let (sender, receiver) = futures::channel::oneshot::channel();
+    let job: Job = Box::pin(async move {
+        future.await;
+        let _ = sender.send(());
+    });
+    let ticket = TICKET.with(|current| current.borrow().clone());
+    with_pool(|pool| {
+        pool.ready.push(job, ticket);
+        // The pool holds a receiver too, so the channel never closes.
+        let _ = pool.sender.try_send(());
+        pool.grow();
+    });
+    receiver
+}
+
+/// Caps how many submitted jobs run at once. `None` lets the pool grow with demand.
+/// Workers above a lowered limit stop once their current job is done.
+pub fn set_worker_limit(limit: Option<usize>) {
+    with_pool(|pool| {
+        pool.limit = limit.map(|n| n.max(1));
+        pool.grow();
+    });
+}
+
+/// The limit last set with [`set_worker_limit`].
+pub fn worker_limit() -> Option<usize> {
+    with_pool(|pool| pool.limit)
+}
+
+/// A handle resolving once `thread` has ended, for work run on a thread of its own.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn join_thread(thread: std::thread::JoinHandle<()>) -> JobHandle {
+    let (sender, receiver) = futures::channel::oneshot::channel();
+    std::thread::spawn(move || {
+        let _ = thread.join();
+        let _ = sender.send(());
+    });
+    receiver
+}
+
+/// Blocks until every handle resolves, or `timeout` passes; returns whether they all did.
+/// Waits on a thread of its own rather than on a runtime, so it is safe to call from
+/// outside one, or from a thread an embedding tokio runtime does not expect to block.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn wait_for_all(handles: Vec<JobHandle>, timeout: Duration) -> bool {
+    let (sender, receiver) = std::sync::mpsc::channel();
+    std::thread::spawn(move || {
+        futures::executor::block_on(futures::future::join_all(handles));
+        let _ = sender.send(());
+    });
+    receiver.recv_timeout(timeout).is_ok()
+}
+
+/// Runs `f` on the runtime's pool for blocking work, away from the async worker threads.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn spawn_blocking<F, T>(f: F) -> JoinHandle<T>
+where
+    F: FnOnce() -> T + Send + 'static,
+    T: Send + 'static,
+{
+    #[cfg(not(feature = "tokio"))]
+    return async_std::task::spawn_blocking(f);
+    #[cfg(feature = "tokio")]
+    return runtime::handle().spawn_blocking(f);
+}
+
+/// Runs CPU-bound `f` on rayon's pool, one thread per core, so long computations do not
+/// tie up the threads that drive timers and I/O.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn spawn_cpu<F>(f: F)
+where
+    F: FnOnce() + Send + 'static,
+{
+    rayon::spawn(f);
+}
+
+/// Waits for `duration` on the timer of the current runtime. Only call it from futures
+/// given to [`spawn`].
+pub async fn sleep(duration: Duration) {
+    #[cfg(not(all(feature = "tokio", not(target_arch = "wasm32"))))]
+    async_std::task::sleep(duration).await;
+    #[cfg(all(feature = "tokio", not(target_arch = "wasm32")))]
+    tokio::time::sleep(duration).await;
+}
+
+/// Lets other futures run before continuing. On the web this waits for a zero-length
+/// timer rather than a microtask, so the browser gets to handle input and draw a frame.
+pub async fn yield_now() {
+    #[cfg(not(any(feature = "tokio", target_arch = "wasm32")))]
+    async_std::task::yield_now().await;
+    #[cfg(all(feature = "tokio", not(target_arch = "wasm32")))]
+    tokio::task::yield_now().await;
+    #[cfg(target_arch = "wasm32")]
+    async_std::task::sleep(Duration::ZERO).await;
+}
+
+#[cfg(all(feature = "tokio", not(target_arch = "wasm32")))]
+mod runtime {
+    use std::sync::Mutex as sync_Mutex;
+
+    use tokio::runtime::{Builder, Handle};
+
+    static HANDLE: sync_Mutex<Option<Handle>> = sync_Mutex::new(None);
+
+    /// The runtime already driving this thread, if any, otherwise a shared one started on
+    /// first use that lives until the process exits.
+    pub fn handle() -> Handle {
+        if let Ok(handle) = Handle::try_current() {
+            return handle;
+        }
+        let mut shared = HANDLE
+            .lock()
+            .expect("Panicked at handle: Runtime mutex poisoned");
+        shared
+            .get_or_insert_with(|| {
+                let runtime = Builder::new_multi_thread()
+                    .enable_all()
+                    .thread_name("task-queue-worker")
+                    .build()
+                    .expect("Failed to start the tokio runtime");
+                let handle = runtime.handle().clone();
+                std::mem::forget(runtime);
+                handle
+            })
+            .clone()
+    }
+}
@@ -0,0 +1,60 @@
+#[cfg(test)]
+use crate::app::executor::{self, JobTicket, ReadyQueue};
+#[cfg(test)]
+use crate::app::priority::Priority;
+
+#[test]
+fn test_submitted_jobs_run_concurrently() {
+    let (tx, rx) = std::sync::mpsc::channel();
+    let handles: Vec<_> = (0..8)
+        .map(|i| {
+            let tx = tx.clone();
+            executor::submit(async move {
+                executor::sleep(std::time::Duration::from_millis(200)).await;
+                tx.send(i).unwrap();
+            })
+        })
+        .collect();
+    let started = std::time::Instant::now();
+    async_std::task::block_on(futures::future::join_all(handles));
+    assert!(started.elapsed() < std::time::Duration::from_millis(1000));
+    let mut done: Vec<_> = rx.try_iter().collect();
+    done.sort();
+    assert_eq!(done, (0..8).collect::<Vec<_>>());
+}
+
+#[test]
+fn test_ready_queue_takes_urgent_jobs_first() {
+    let mut ready = ReadyQueue::default();
+    let ticket = |priority| Some(JobTicket::new(priority));
+    ready.push("first", None);
+    ready.push("low", ticket(Priority::Low));
+    ready.push("second", ticket(Priority::Normal));
+    let urgent = JobTicket::new(Priority::Normal);
+    ready.push("urgent", Some(urgent.clone()));
+    ready.push("critical", ticket(Priority::Critical));
+    ready.reprioritize(&urgent, Priority::High);
+
+    let order: Vec<_> = std::iter::from_fn(|| ready.pop()).collect();
+    assert_eq!(order, ["critical", "urgent", "first", "second", "low"]);
+    // Taken already, so there is nothing left to move.
+    ready.reprioritize(&urgent, Priority::Low);
+    assert_eq!(ready.pop(), None);
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+#[test]
+fn test_wait_for_all_gives_up_after_its_timeout() {
+    use std::time::Duration;
+
+    let job = executor::submit(executor::sleep(Duration::from_millis(20)));
+    assert!(executor::wait_for_all(vec![job], Duration::from_secs(5)));
+
+    let (_never_sent, stuck) = futures::channel::oneshot::channel();
+    let started = std::time::Instant::now();
+    assert!(!executor::wait_for_all(
+        vec![stuck],
+        Duration::from_millis(50)
+    ));
+    assert!(started.elapsed() < Duration::from_secs(5));
+}
@@ -0,0 +1,114 @@
+//! When the queue as a whole will be done: each active task's time left, from its own
+//! progress rate or else from how long finished tasks of its kind took, laid out over
+//! the concurrency limit's worker slots.
+
+use std::collections::HashMap;
+use std::time::Duration;
+
+use crate::app::history::TaskRecord;
+use crate::app::task_queue::TaskStatus;
+
+/// Below this, a task's progress says too little about its rate to extrapolate from.
+pub const MIN_PROGRESS: f32 = 0.01;
+
+/// An active task, as far as forecasting is concerned.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ActiveTask {
+    pub kind: String,
+    pub status: TaskStatus,
+    pub progress: f32,
+    /// Since the task started; `None` if it has not.
+    pub elapsed: Option<Duration>,
+}
+
+/// Mean run time of the completed tasks of each kind.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TypicalDurations {
+    by_kind: HashMap<String, (u64, u64)>,
+    overall: (u64, u64),
+}
+
+impl TypicalDurations {
+    pub fn from_history<'a>(records: impl IntoIterator<Item = &'a TaskRecord>) -> Self {
+        let mut typical = TypicalDurations::default();
+        for record in records {
+            if record.status != TaskStatus::Completed {
+                continue;
+            }
+            let (Some(started), Some(finished)) = (record.started_at, record.finished_at) else {
+                continue;
+            };
+            let millis = finished.saturating_sub(started);
+            let (total, count) = typical.by_kind.entry(record.kind.clone()).or_default();
+            *total += millis;
+            *count += 1;
+            typical.overall.0 += millis;
+            typical.overall.1 += 1;
+        }
+        typical
+    }
+
+    /// For `kind`, or across every kind if none of it has completed yet.
+    pub fn get(&self, kind: &str) -> Option<Duration> {
+        let (total, count) = self
+            .by_kind
+            .get(kind)
+            .copied()
+            .filter(|(_, count)| *count > 0)
+            .unwrap_or(self.overall);
+        (count > 0).then(|| Duration::from_millis(total / count))
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Forecast {
+    /// Until every queued and running task is done; `None` if none of them could be
+    /// estimated. Paused tasks are left out, as if they stay paused.
+    pub remaining: Option<Duration>,
+    /// Queued and running tasks with no estimate, which `remaining` leaves out too.
+    pub unestimated: usize,
+}
+
+/// Tasks that have made progress keep the slot they are running in; the rest, in the
+/// order given, take the next slot to come free. `concurrency` of `None` is a slot each.
+pub fn forecast(
+    tasks: &[ActiveTask],
+    typical: &TypicalDurations,
+    concurrency: Option<usize>,
+) -> Forecast {
+    let slots_limit = concurrency.map_or(usize::MAX, |n| n.max(1));
+    let mut slots: Vec<Duration> = Vec::new();
+    let mut waiting: Vec<Duration> = Vec::new();
+    let mut unestimated = 0;
+    for task in tasks
+        .iter()
+        .filter(|task| task.status != TaskStatus::Paused)
+    {
+        let elapsed = task.elapsed.unwrap_or_default();
+        if task.progress >= MIN_PROGRESS {
+            let left = (1.0 - task.progress.min(1.0)) / task.progress;
+            slots.push(elapsed.mul_f32(left));
+        } else if let Some(typical) = typical.get(&task.kind) {
+            waiting.push(typical.saturating_sub(elapsed));
+        } else {
+            unestimated += 1;
+        }
+    }
+    if slots.is_empty() && waiting.is_empty() {
+        return Forecast {
+            remaining: None,
+            unestimated,
+        };
+    }
+    for duration in waiting {
+        if slots.len() < slots_limit {
+            slots.push(duration);
+        } else if let Some(soonest) = slots.iter_mut().min() {
+            *soonest += duration;
+        }
+    }
+    Forecast {
+        remaining: slots.into_iter().max(),
+        unestimated,
+    }
+}
@@ -0,0 +1,109 @@
+#[cfg(test)]
+use std::time::Duration;
+
+#[cfg(test)]
+use crate::app::forecast::{forecast, ActiveTask, TypicalDurations};
+#[cfg(test)]
+use crate::app::history::TaskRecord;
+#[cfg(test)]
+use crate::app::sleep_task::SleepTask;
+#[cfg(test)]
+use crate::app::task_id::TaskId;
+#[cfg(test)]
+use crate::app::task_queue::{TaskQueue, TaskStatus};
+
+#[cfg(test)]
+fn completed(kind: &str, seconds: u64) -> TaskRecord {
+    TaskRecord {
+        status: TaskStatus::Completed,
+        started_at: Some(1_000),
+        finished_at: Some(1_000 + seconds * 1000),
+        ..TaskRecord::new(TaskId::detached(0), kind)
+    }
+}
+
+#[cfg(test)]
+fn active(kind: &str, status: TaskStatus, progress: f32, elapsed_secs: Option<u64>) -> ActiveTask {
+    ActiveTask {
+        kind: kind.to_owned(),
+        status,
+        progress,
+        elapsed: elapsed_secs.map(Duration::from_secs),
+    }
+}
+
+#[test]
+fn test_typical_durations() {
+    let history = [
+        completed("sleep", 10),
+        completed("sleep", 20),
+        completed("download", 60),
+        TaskRecord {
+            status: TaskStatus::Cancelled,
+            ..completed("download", 1)
+        },
+    ];
+    let typical = TypicalDurations::from_history(&history);
+    assert_eq!(typical.get("sleep"), Some(Duration::from_secs(15)));
+    assert_eq!(typical.get("download"), Some(Duration::from_secs(60)));
+    assert_eq!(typical.get("extract"), Some(Duration::from_secs(30)));
+    assert_eq!(TypicalDurations::default().get("sleep"), None);
+}
+
+#[test]
+fn test_running_tasks_extrapolate_their_progress() {
+    let tasks = [
+        active("sleep", TaskStatus::Running, 0.5, Some(10)),
+        active("sleep", TaskStatus::Running, 0.25, Some(10)),
+        active("sleep", TaskStatus::Paused, 0.1, Some(10)),
+    ];
+    let forecast = forecast(&tasks, &TypicalDurations::default(), None);
+    assert_eq!(forecast.remaining, Some(Duration::from_secs(30)));
+    assert_eq!(forecast.unestimated, 0);
+}
+
+#[test]
+fn test_waiting_tasks_share_the_concurrency_limit() {
+    let typical = TypicalDurations::from_history(&[completed("sleep", 10)]);
+    let tasks = [
+        active("sleep", TaskStatus::Running, 0.5, Some(5)),
+        active("sleep", TaskStatus::Queued, 0.0, None),
+        active("sleep", TaskStatus::Queued, 0.0, None),
+        active("sleep", TaskStatus::Queued, 0.0, None),
+    ];
+    assert_eq!(
+        forecast(&tasks, &typical, None).remaining,
+        Some(Duration::from_secs(10))
+    );
+    // Two slots: 5s + 10s in one, 10s + 10s in the other.
+    assert_eq!(
+        forecast(&tasks, &typical, Some(2)).remaining,
+        Some(Duration::from_secs(20))
+    );
+    assert_eq!(
+        forecast(&tasks, &typical, Some(1)).remaining,
+        Some(Duration::from_secs(35))
+    );
+}
+
+#[test]
+fn test_tasks_without_an_estimate_are_counted() {
+    let tasks = [active("sleep", TaskStatus::Queued, 0.0, None)];
+    let forecast = forecast(&tasks, &TypicalDurations::default(), None);
+    assert_eq!(forecast.remaining, None);
+    assert_eq!(forecast.unestimated, 1);
+}
+
+#[test]
+fn test_queue_stats() {
+    let queue = TaskQueue::new();
+    let stats = queue.stats();
+    assert_eq!((stats.queued, stats.running, stats.paused), (0, 0, 0));
+    assert_eq!(stats.estimated_finish_time, None);
+
+    queue.add_task(SleepTask::new(None, Duration::from_secs(60)));
+    let stats = queue.stats();
+    assert_eq!(stats.queued, 1);
+    assert_eq!(stats.unestimated, 1);
+    assert_eq!(stats.estimated_finish_time, None);
+}
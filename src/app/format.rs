@@ -0,0 +1,166 @@
+//! Numbers, sizes and durations written for people to read, in the user's locale's
+//! separators. Machine-readable output, like the JSON Lines and trace exports, keeps
+//! plain numbers instead.
+
+use std::sync::RwLock as sync_RwLock;
+use std::time::Duration;
+
+/// Set once the app starts; English until then, and so in tests.
+static CURRENT: sync_RwLock<NumberFormat> = sync_RwLock::new(NumberFormat::ENGLISH);
+
+/// How a locale writes numbers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NumberFormat {
+    /// Between groups of three digits, e.g. `,` in `12,345`.
+    pub grouping: char,
+    pub decimal: char,
+}
+
+impl NumberFormat {
+    pub const ENGLISH: NumberFormat = NumberFormat {
+        grouping: ',',
+        decimal: '.',
+    };
+
+    /// For a POSIX locale name like `de_DE.UTF-8` or a BCP 47 tag like `fr-CA`. Locales
+    /// not known here are written like English.
+    pub fn for_locale(locale: &str) -> Self {
+        let locale = locale.split(['.', '@']).next().unwrap_or_default();
+        let mut parts = locale.split(['_', '-']);
+        let language = parts.next().unwrap_or_default().to_ascii_lowercase();
+        let region = parts.next().unwrap_or_default().to_ascii_uppercase();
+        if region == "CH" || region == "LI" {
+            return NumberFormat {
+                grouping: '\'',
+                decimal: '.',
+            };
+        }
+        match language.as_str() {
+            "da" | "de" | "el" | "es" | "hr" | "id" | "it" | "nl" | "pt" | "ro" | "sl" | "sr"
+            | "tr" | "vi" => NumberFormat {
+                grouping: '.',
+                decimal: ',',
+            },
+            "bg" | "cs" | "et" | "fi" | "fr" | "hu" | "lt" | "lv" | "nb" | "nn" | "no" | "pl"
+            | "ru" | "sk" | "sv" | "uk" => NumberFormat {
+                grouping: '\u{a0}',
+                decimal: ',',
+            },
+            _ => NumberFormat::ENGLISH,
+        }
+    }
+
+    /// The user's, from `LC_ALL`, `LC_NUMERIC` or `LANG`, whichever is set first.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn from_env() -> Self {
+        ["LC_ALL", "LC_NUMERIC", "LANG"]
+            .iter()
+            .filter_map(|name| std::env::var(name).ok())
+            .find(|value| !value.is_empty())
+            .map_or(NumberFormat::ENGLISH, |locale| Self::for_locale(&locale))
+    }
+
+    /// The one the free functions below write in.
+    pub fn current() -> Self {
+        *CURRENT
+            .read()
+            .expect("Panicked at NumberFormat::current: Lock poisoned")
+    }
+
+    pub fn set_current(format: NumberFormat) {
+        *CURRENT
+            .write()
+            .expect("Panicked at NumberFormat::set_current: Lock poisoned") = format;
+    }
+
+    /// E.g. `12,345`.
+    pub fn count(&self, n: u64) -> String {
+        let digits = n.to_string();
+        let mut grouped = String::with_capacity(digits.len() + digits.len() / 3);
+        for (i, digit) in digits.chars().enumerate() {
+            if i > 0 && (digits.len() - i) % 3 == 0 {
+                grouped.push(self.grouping);
+            }
+            grouped.push(digit);
+        }
+        grouped
+    }
+
+    /// `value` with `places` decimals and grouped whole digits, e.g. `1,234.5`.
+    pub fn decimal(&self, value: f64, places: usize) -> String {
+        let fixed = format!("{:.*}", places, value.abs());
+        let (whole, fraction) = fixed.split_once('.').unwrap_or((&fixed, ""));
+        let mut s = String::new();
+        if value.is_sign_negative() && fixed.chars().any(|c| c.is_ascii_digit() && c != '0') {
+            s.push('-');
+        }
+        s.push_str(&self.count(whole.parse().unwrap_or(0)));
+        if !fraction.is_empty() {
+            s.push(self.decimal);
+            s.push_str(fraction);
+        }
+        s
+    }
+
+    /// A progress fraction as a percentage, e.g. `42.5%`.
+    pub fn percent(&self, fraction: f32, places: usize) -> String {
+        format!("{}%", self.decimal(fraction as f64 * 100.0, places))
+    }
+
+    /// In the largest binary unit that keeps it at one or more, e.g. `1.5 GiB`.
+    pub fn bytes(&self, bytes: u64) -> String {
+        const UNITS: [&str; 5] = ["bytes", "KiB", "MiB", "GiB", "TiB"];
+        let mut value = bytes as f64;
+        let mut unit = 0;
+        while value >= 1024.0 && unit < UNITS.len() - 1 {
+            value /= 1024.0;
+            unit += 1;
+        }
+        if unit == 0 {
+            format!("{} {}", self.count(bytes), UNITS[0])
+        } else {
+            format!("{} {}", self.decimal(value, 1), UNITS[unit])
+        }
+    }
+
+    /// The two largest units, e.g. `1h 23m` or `4m 5s`; tenths of a second below a
+    /// minute, e.g. `2.5s`.
+    pub fn duration(&self, duration: Duration) -> String {
+        let seconds = duration.as_secs();
+        let (minutes, hours, days) = (seconds / 60, seconds / 3600, seconds / 86_400);
+        if days > 0 {
+            format!("{}d {}h", self.count(days), hours % 24)
+        } else if hours > 0 {
+            format!("{}h {}m", hours, minutes % 60)
+        } else if minutes > 0 {
+            format!("{}m {}s", minutes, seconds % 60)
+        } else {
+            format!("{}s", self.decimal(duration.as_secs_f64(), 1))
+        }
+    }
+}
+
+/// [`NumberFormat::count`] in the current format.
+pub fn count(n: usize) -> String {
+    NumberFormat::current().count(n as u64)
+}
+
+/// [`NumberFormat::percent`] in the current format.
+pub fn percent(fraction: f32, places: usize) -> String {
+    NumberFormat::current().percent(fraction, places)
+}
+
+/// [`NumberFormat::bytes`] in the current format.
+pub fn bytes(bytes: u64) -> String {
+    NumberFormat::current().bytes(bytes)
+}
+
+/// [`NumberFormat::duration`] in the current format.
+pub fn duration(duration: Duration) -> String {
+    NumberFormat::current().duration(duration)
+}
+
+/// Between two Unix timestamps in milliseconds, as in task records.
+pub fn duration_millis(from: u64, to: u64) -> String {
+    duration(Duration::from_millis(to.saturating_sub(from)))
+}
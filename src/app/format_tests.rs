@@ -0,0 +1,45 @@
+#[cfg(test)]
+use std::time::Duration;
+
+#[cfg(test)]
+use crate::app::format::NumberFormat;
+
+#[test]
+fn test_format_bytes() {
+    let english = NumberFormat::ENGLISH;
+    assert_eq!(english.bytes(512), "512 bytes");
+    assert_eq!(english.bytes(1536), "1.5 KiB");
+    assert_eq!(english.bytes(3 * 1024 * 1024 * 1024), "3.0 GiB");
+    assert_eq!(
+        NumberFormat::for_locale("de_DE.UTF-8").bytes(1536),
+        "1,5 KiB"
+    );
+}
+
+#[test]
+fn test_numbers_use_the_locale_separators() {
+    assert_eq!(NumberFormat::ENGLISH.count(0), "0");
+    assert_eq!(NumberFormat::ENGLISH.count(999), "999");
+    assert_eq!(NumberFormat::ENGLISH.count(1_234_567), "1,234,567");
+    let german = NumberFormat::for_locale("de_DE.UTF-8");
+    assert_eq!(german.decimal(12_345.678, 2), "12.345,68");
+    assert_eq!(german.percent(0.425, 1), "42,5%");
+    let french = NumberFormat::for_locale("fr-CA");
+    assert_eq!(french.count(100_000), "100\u{a0}000");
+    assert_eq!(NumberFormat::for_locale("de_CH").count(1000), "1'000");
+    assert_eq!(NumberFormat::for_locale("C"), NumberFormat::ENGLISH);
+    assert_eq!(NumberFormat::ENGLISH.decimal(-0.04, 1), "0.0");
+    assert_eq!(NumberFormat::ENGLISH.decimal(-1.5, 1), "-1.5");
+}
+
+#[test]
+fn test_durations_show_two_units() {
+    let english = NumberFormat::ENGLISH;
+    assert_eq!(english.duration(Duration::from_millis(2500)), "2.5s");
+    assert_eq!(english.duration(Duration::from_secs(245)), "4m 5s");
+    assert_eq!(english.duration(Duration::from_secs(4980)), "1h 23m");
+    assert_eq!(
+        english.duration(Duration::from_secs(2 * 86_400 + 7200)),
+        "2d 2h"
+    );
+}
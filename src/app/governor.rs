@@ -0,0 +1,156 @@
+//! Easing off when the machine is under pressure: fewer tasks at once, and low-priority
+//! tasks paused, while the CPU is busy or hot, until it cools down again.
+
+use std::fmt::{Display, Formatter, Result as FmtResult};
+
+use crate::app::config::GovernorConfig;
+use crate::app::history::TaskRecord;
+use crate::app::priority::Priority;
+use crate::app::task_id::TaskId;
+use crate::app::task_queue::TaskStatus;
+
+/// What the governor goes by. Either reading is `None` where the platform cannot tell.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct SystemLoad {
+    /// One-minute load average per core, so 1.0 keeps every core busy.
+    pub cpu: Option<f32>,
+    /// The hottest temperature sensor, in °C.
+    pub temperature: Option<f32>,
+}
+
+impl Display for SystemLoad {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        match (self.cpu, self.temperature) {
+            (Some(cpu), Some(temperature)) => {
+                write!(f, "CPU {:.0}% · {:.0}°C", cpu * 100.0, temperature)
+            }
+            (Some(cpu), None) => write!(f, "CPU {:.0}%", cpu * 100.0),
+            (None, Some(temperature)) => write!(f, "{:.0}°C", temperature),
+            (None, None) => write!(f, "System load unknown"),
+        }
+    }
+}
+
+#[cfg(target_os = "linux")]
+pub fn system_load() -> SystemLoad {
+    SystemLoad {
+        cpu: std::fs::read_to_string("/proc/loadavg")
+            .ok()
+            .and_then(|loadavg| parse_loadavg(&loadavg)),
+        temperature: read_thermal_zones(std::path::Path::new("/sys/class/thermal")),
+    }
+}
+
+/// The first of `/proc/loadavg`'s load averages, per core.
+#[cfg(target_os = "linux")]
+pub fn parse_loadavg(loadavg: &str) -> Option<f32> {
+    let load: f32 = loadavg.split_whitespace().next()?.parse().ok()?;
+    Some(load / cores())
+}
+
+/// The highest temperature of the sysfs `thermal` class directory's zones, which report
+/// millidegrees.
+#[cfg(target_os = "linux")]
+pub fn read_thermal_zones(dir: &std::path::Path) -> Option<f32> {
+    std::fs::read_dir(dir)
+        .ok()?
+        .flatten()
+        .filter(|entry| {
+            entry
+                .file_name()
+                .to_string_lossy()
+                .starts_with("thermal_zone")
+        })
+        .filter_map(|entry| std::fs::read_to_string(entry.path().join("temp")).ok())
+        .filter_map(|temp| temp.trim().parse::<f32>().ok())
+        .map(|millidegrees| millidegrees / 1000.0)
+        .reduce(f32::max)
+}
+
+#[cfg(target_os = "macos")]
+pub fn system_load() -> SystemLoad {
+    let cpu = std::process::Command::new("sysctl")
+        .args(["-n", "vm.loadavg"])
+        .output()
+        .ok()
+        .and_then(|output| {
+            // e.g. `{ 1.52 1.61 1.74 }`
+            String::from_utf8_lossy(&output.stdout)
+                .split_whitespace()
+                .nth(1)
+                .and_then(|load| load.parse::<f32>().ok())
+        })
+        .map(|load| load / cores());
+    SystemLoad {
+        cpu,
+        temperature: None,
+    }
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos")))]
+pub fn system_load() -> SystemLoad {
+    SystemLoad::default()
+}
+
+#[cfg(any(target_os = "linux", target_os = "macos"))]
+fn cores() -> f32 {
+    std::thread::available_parallelism().map_or(1, |n| n.get()) as f32
+}
+
+/// Whether the machine is under pressure, with some slack between the thresholds so it
+/// does not flip on every check, and which tasks it paused for that.
+#[derive(Debug, Default)]
+pub struct Governor {
+    throttled: bool,
+    paused: Vec<TaskId>,
+}
+
+impl Governor {
+    pub fn is_throttled(&self) -> bool {
+        self.throttled
+    }
+
+    /// Throttles once either reading reaches its maximum, and lets go once every reading
+    /// is below its resume threshold. Readings that are unknown do not count. Returns
+    /// whether that changed.
+    pub fn update(&mut self, load: &SystemLoad, config: &GovernorConfig) -> bool {
+        let above = |reading: Option<f32>, limit: f32| reading.map_or(false, |r| r >= limit);
+        let throttled = if self.throttled {
+            above(load.cpu, config.resume_cpu_load)
+                || above(load.temperature, config.resume_temperature)
+        } else {
+            above(load.cpu, config.max_cpu_load) || above(load.temperature, config.max_temperature)
+        };
+        let changed = throttled != self.throttled;
+        self.throttled = throttled;
+        changed
+    }
+
+    /// The worker limit to use instead of `configured` while throttled.
+    pub fn concurrency(&self, configured: Option<usize>, config: &GovernorConfig) -> Option<usize> {
+        if !self.throttled {
+            return configured;
+        }
+        let throttled = config.throttled_concurrency.max(1);
+        Some(configured.map_or(throttled, |n| n.min(throttled)))
+    }
+
+    /// Queued or running low-priority tasks that the governor has not paused before, like
+    /// [`BatteryGuard`](crate::app::power::BatteryGuard) does on battery.
+    pub fn tasks_to_pause(&mut self, records: &[TaskRecord]) -> Vec<TaskId> {
+        let ids: Vec<TaskId> = records
+            .iter()
+            .filter(|record| matches!(record.status, TaskStatus::Queued | TaskStatus::Running))
+            .filter(|record| record.priority == Priority::Low)
+            .map(|record| record.id)
+            .filter(|id| !self.paused.contains(id))
+            .collect();
+        self.paused.extend(&ids);
+        ids
+    }
+
+    /// Every task the governor paused, which it then forgets.
+    pub fn tasks_to_resume(&mut self) -> Vec<TaskId> {
+        std::mem::take(&mut self.paused)
+    }
+}
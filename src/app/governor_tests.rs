@@ -0,0 +1,77 @@
+#[cfg(test)]
+use crate::app::config::GovernorConfig;
+#[cfg(test)]
+use crate::app::governor::{Governor, SystemLoad};
+#[cfg(test)]
+use crate::app::history::TaskRecord;
+#[cfg(test)]
+use crate::app::priority::Priority;
+#[cfg(test)]
+use crate::app::task_id::TaskId;
+#[cfg(test)]
+use crate::app::task_queue::TaskStatus;
+
+#[cfg(test)]
+fn load(cpu: Option<f32>, temperature: Option<f32>) -> SystemLoad {
+    SystemLoad { cpu, temperature }
+}
+
+#[test]
+fn test_governor_throttles_until_cooled_down() {
+    let config = GovernorConfig::default();
+    let mut governor = Governor::default();
+    assert!(!governor.update(&load(Some(0.8), None), &config));
+    assert_eq!(governor.concurrency(Some(4), &config), Some(4));
+
+    assert!(governor.update(&load(Some(0.5), Some(90.0)), &config));
+    assert!(governor.is_throttled());
+    assert_eq!(governor.concurrency(Some(4), &config), Some(1));
+    assert_eq!(governor.concurrency(None, &config), Some(1));
+
+    // Between the thresholds it stays throttled.
+    assert!(!governor.update(&load(Some(0.8), Some(70.0)), &config));
+    assert!(governor.is_throttled());
+    assert!(governor.update(&load(Some(0.6), Some(70.0)), &config));
+    assert_eq!(governor.concurrency(None, &config), None);
+
+    // Unknown readings never count as pressure.
+    assert!(!governor.update(&SystemLoad::default(), &config));
+}
+
+#[test]
+fn test_governor_pauses_low_priority_tasks_once() {
+    let record = |id: usize, priority: Priority, status: TaskStatus| TaskRecord {
+        status,
+        priority,
+        ..TaskRecord::new(TaskId::detached(id), "sleep")
+    };
+    let mut governor = Governor::default();
+    let records = vec![
+        record(1, Priority::Low, TaskStatus::Running),
+        record(2, Priority::Low, TaskStatus::Paused),
+        record(3, Priority::Normal, TaskStatus::Running),
+        record(4, Priority::Low, TaskStatus::Queued),
+    ];
+    assert_eq!(
+        governor.tasks_to_pause(&records),
+        [1, 4].map(TaskId::detached)
+    );
+    assert!(governor.tasks_to_pause(&records).is_empty());
+    assert_eq!(governor.tasks_to_resume(), [1, 4].map(TaskId::detached));
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn test_read_thermal_zones() {
+    use crate::app::governor::read_thermal_zones;
+
+    let dir = std::env::temp_dir().join(format!("thermal_{}", std::process::id()));
+    for (zone, temp) in [("thermal_zone0", "45000\n"), ("thermal_zone1", "71500\n")] {
+        std::fs::create_dir_all(dir.join(zone)).unwrap();
+        std::fs::write(dir.join(zone).join("temp"), temp).unwrap();
+    }
+    std::fs::create_dir_all(dir.join("cooling_device0")).unwrap();
+    assert_eq!(read_thermal_zones(&dir), Some(71.5));
+    let _ = std::fs::remove_dir_all(&dir);
+    assert_eq!(read_thermal_zones(&dir), None);
+}
@@ -0,0 +1,180 @@
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::app::color_tag::ColorTag;
+use crate::app::priority::Priority;
+use crate::app::task_id::TaskId;
+use crate::app::task_queue::TaskStatus;
+
+/// Snapshot of a task's lifecycle, kept while the task is active and moved to the
+/// queue's history once it reaches a terminal state.
+#[derive(Debug, Clone, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct TaskRecord {
+    pub id: TaskId,
+    pub kind: String,
+    pub status: TaskStatus,
+    /// Unix timestamps in milliseconds.
+    pub created_at: u64,
+    pub started_at: Option<u64>,
+    pub finished_at: Option<u64>,
+    /// When a scheduled task is to start; see
+    /// [`TaskQueue::schedule_task`](crate::app::task_queue::TaskQueue::schedule_task).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub start_at: Option<u64>,
+    /// What the task produced, as reported by
+    /// [`Task::artifacts`](crate::app::task_queue::Task::artifacts) when it completed.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub artifacts: Vec<Artifact>,
+    /// Set by the user; see
+    /// [`TaskQueue::set_color_tag`](crate::app::task_queue::TaskQueue::set_color_tag).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub color_tag: Option<ColorTag>,
+    /// See [`TaskQueue::set_priority`](crate::app::task_queue::TaskQueue::set_priority).
+    #[serde(default, skip_serializing_if = "Priority::is_normal")]
+    pub priority: Priority,
+    /// Key/value pairs attached by automation, e.g. a CI wrapper's commit hash; see
+    /// [`TaskQueue::annotate`](crate::app::task_queue::TaskQueue::annotate).
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub annotations: BTreeMap<String, String>,
+    /// Unix timestamp in milliseconds the task should be done by; see
+    /// [`TaskQueue::set_deadline`](crate::app::task_queue::TaskQueue::set_deadline).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub deadline: Option<u64>,
+    /// Failed for running longer than its timeout; see
+    /// [`TaskQueue::add_task_with_timeout`][add_task_with_timeout].
+    ///
+    /// [add_task_with_timeout]: crate::app::task_queue::TaskQueue::add_task_with_timeout
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub timed_out: bool,
+    /// Given when the task was added, or since; see
+    /// [`TaskMeta`](crate::app::task_queue::TaskMeta).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub label: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    /// Why the task failed, if it did.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Something a task produced that the user may want to open or copy.
+#[derive(Debug, Clone, PartialEq, serde::Deserialize, serde::Serialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum Artifact {
+    File { path: PathBuf },
+    Url { url: String },
+    Text { label: String, text: String },
+}
+
+impl Artifact {
+    /// A one-line description for lists.
+    pub fn label(&self) -> String {
+        match self {
+            Artifact::File { path } => path.display().to_string(),
+            Artifact::Url { url } => url.clone(),
+            Artifact::Text { label, .. } => label.clone(),
+        }
+    }
+
+    /// What "Copy" puts on the clipboard.
+    pub fn copy_text(&self) -> String {
+        match self {
+            Artifact::File { path } => path.display().to_string(),
+            Artifact::Url { url } => url.clone(),
+            Artifact::Text { text, .. } => text.clone(),
+        }
+    }
+}
+
+impl TaskRecord {
+    /// The status as shown to the user, which tells a timeout from a cancellation.
+    /// The task's label, or its id when it has none.
+    pub fn title(&self) -> String {
+        match &self.label {
+            Some(label) => label.clone(),
+            None => format!("Task {}", self.id),
+        }
+    }
+
+    pub fn status_label(&self) -> String {
+        if self.timed_out {
+            "Timed out".to_owned()
+        } else {
+            self.status.to_string()
+        }
+    }
+
+    pub fn new(id: TaskId, kind: &str) -> Self {
+        TaskRecord {
+            id,
+            kind: kind.to_owned(),
+            status: TaskStatus::Queued,
+            created_at: now_millis(),
+            started_at: None,
+            finished_at: None,
+            start_at: None,
+            artifacts: Vec::new(),
+            color_tag: None,
+            priority: Priority::Normal,
+            annotations: BTreeMap::new(),
+            deadline: None,
+            timed_out: false,
+            label: None,
+            tags: Vec::new(),
+            error: None,
+        }
+    }
+}
+
+/// How long finished tasks are remembered, and what of them is written to disk.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct HistoryRetention {
+    /// Records of tasks that finished longer ago are deleted.
+    pub max_age: Option<Duration>,
+    /// Only this many of the most recent records are kept.
+    pub max_count: Option<usize>,
+    /// Leaves out of what is written to disk whatever of a task's parameters a record
+    /// carries: its artifacts and annotations, which may hold paths and URLs.
+    pub exclude_params: bool,
+}
+
+impl HistoryRetention {
+    pub fn is_limited(&self) -> bool {
+        self.max_age.is_some() || self.max_count.is_some()
+    }
+
+    /// Records that finished before this Unix timestamp in milliseconds are too old.
+    pub fn cutoff(&self, now: u64) -> Option<u64> {
+        self.max_age
+            .map(|age| now.saturating_sub(age.as_millis() as u64))
+    }
+
+    /// Whether `record` is still to be kept at `cutoff`; see [`cutoff`](Self::cutoff).
+    pub fn keeps(record: &TaskRecord, cutoff: Option<u64>) -> bool {
+        match (cutoff, record.finished_at) {
+            (Some(cutoff), Some(finished_at)) => finished_at >= cutoff,
+            _ => true,
+        }
+    }
+
+    /// `record` as it may be written to disk.
+    pub fn for_disk<'a>(&self, record: &'a TaskRecord) -> Cow<'a, TaskRecord> {
+        if !self.exclude_params || (record.artifacts.is_empty() && record.annotations.is_empty()) {
+            return Cow::Borrowed(record);
+        }
+        Cow::Owned(TaskRecord {
+            artifacts: Vec::new(),
+            annotations: BTreeMap::new(),
+            ..record.clone()
+        })
+    }
+}
+
+pub fn now_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
@@ -0,0 +1,128 @@
+//! On-disk overflow for task history, so a long session's history does not stay resident.
+
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, Seek, SeekFrom, Write};
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+
+use crate::app::history::TaskRecord;
+
+/// History records moved out of memory, one JSON record per line, oldest first.
+///
+/// Only the offset of each line is kept in memory. The file belongs to one session: it is
+/// emptied when opened and removed when dropped.
+pub struct HistoryFile {
+    path: PathBuf,
+    file: File,
+    /// Start of each record's line.
+    offsets: Vec<u64>,
+    end: u64,
+}
+
+impl HistoryFile {
+    /// A file in the platform cache directory, named after this process so that
+    /// instances started with `--new-instance` do not share one.
+    pub fn default_path() -> Option<PathBuf> {
+        directories_next::ProjectDirs::from("net", "xthreen", "functional_rust_ui_demo").map(
+            |dirs| {
+                dirs.cache_dir()
+                    .join(format!("history-spill-{}.jsonl", std::process::id()))
+            },
+        )
+    }
+
+    pub fn create(path: &Path) -> std::io::Result<Self> {
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        let file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .open(path)?;
+        Ok(HistoryFile {
+            path: path.to_owned(),
+            file,
+            offsets: Vec::new(),
+            end: 0,
+        })
+    }
+
+    pub fn len(&self) -> usize {
+        self.offsets.len()
+    }
+
+    pub fn append(&mut self, records: &[TaskRecord]) -> std::io::Result<()> {
+        let mut buffer = Vec::new();
+        let mut offsets = Vec::with_capacity(records.len());
+        for record in records {
+            offsets.push(self.end + buffer.len() as u64);
+            serde_json::to_writer(&mut buffer, record)?;
+            buffer.push(b'\n');
+        }
+        self.file.seek(SeekFrom::Start(self.end))?;
+        self.file.write_all(&buffer)?;
+        self.end += buffer.len() as u64;
+        self.offsets.extend(offsets);
+        Ok(())
+    }
+
+    /// The records at positions `range`, counted from the oldest.
+    pub fn read(&mut self, range: Range<usize>) -> std::io::Result<Vec<TaskRecord>> {
+        let range = range.start.min(self.len())..range.end.min(self.len());
+        let Some(&start) = self.offsets.get(range.start) else {
+            return Ok(Vec::new());
+        };
+        self.file.seek(SeekFrom::Start(start))?;
+        let mut reader = BufReader::new(&mut self.file);
+        let mut records = Vec::with_capacity(range.len());
+        let mut line = String::new();
+        for _ in range {
+            line.clear();
+            reader.read_line(&mut line)?;
+            records.push(serde_json::from_str(&line)?);
+        }
+        Ok(records)
+    }
+
+    /// How many of the oldest records finished before `cutoff`, in Unix milliseconds.
+    /// Records are in the order they finished, so reading stops at the first newer one.
+    pub fn count_finished_before(&mut self, cutoff: u64) -> std::io::Result<usize> {
+        const CHUNK: usize = 256;
+        let mut count = 0;
+        while count < self.len() {
+            let records = self.read(count..count + CHUNK)?;
+            for record in &records {
+                if record
+                    .finished_at
+                    .map_or(true, |finished_at| finished_at >= cutoff)
+                {
+                    return Ok(count);
+                }
+                count += 1;
+            }
+        }
+        Ok(count)
+    }
+
+    /// Stops listing the `count` oldest records. Their lines stay in the file until it
+    /// is cleared or removed.
+    pub fn forget_oldest(&mut self, count: usize) {
+        self.offsets.drain(..count.min(self.offsets.len()));
+    }
+
+    /// Empties the file.
+    pub fn clear(&mut self) -> std::io::Result<()> {
+        self.file.set_len(0)?;
+        self.offsets.clear();
+        self.end = 0;
+        Ok(())
+    }
+}
+
+impl Drop for HistoryFile {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.path);
+    }
+}
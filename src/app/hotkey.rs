@@ -0,0 +1,113 @@
+//! A system-wide hotkey that pauses every queued and running task, and on the next press
+//! resumes the tasks it paused, whether or not the window has focus.
+//!
+//! Presses are handled on a thread of their own rather than in the UI's update, which may
+//! not run while the window is minimized.
+
+use std::fmt::{Display, Formatter, Result as FmtResult};
+use std::str::FromStr;
+use std::sync::Arc as sync_Arc;
+
+use global_hotkey::hotkey::HotKey;
+use global_hotkey::{GlobalHotKeyEvent, GlobalHotKeyManager, HotKeyState};
+
+use crate::app::audit::{AuditAction, Interface, Origin};
+use crate::app::history::TaskRecord;
+use crate::app::task_id::TaskId;
+use crate::app::task_queue::{TaskQueue, TaskStatus};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum HotkeyError {
+    /// The `pause_hotkey` setting is not a hotkey, e.g. `ctrl+alt+P`.
+    Parse(String),
+    /// The OS refused the hotkey, e.g. because another program holds it.
+    Register(String),
+}
+
+impl Display for HotkeyError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        match self {
+            HotkeyError::Parse(e) => write!(f, "Invalid pause hotkey: {}", e),
+            HotkeyError::Register(e) => write!(f, "Cannot register the pause hotkey: {}", e),
+        }
+    }
+}
+
+/// Remembers which tasks the hotkey paused, so that it only resumes those and leaves
+/// tasks the user paused by hand alone.
+#[derive(Debug, Default)]
+pub struct PauseToggle {
+    paused: Vec<TaskId>,
+}
+
+impl PauseToggle {
+    /// The tasks to pause if nothing is paused by the hotkey yet, else the ones to resume.
+    /// Returns whether the tasks are to be paused, and which.
+    pub fn toggle(&mut self, records: &[TaskRecord]) -> (bool, Vec<TaskId>) {
+        if !self.paused.is_empty() {
+            return (false, std::mem::take(&mut self.paused));
+        }
+        self.paused = records
+            .iter()
+            .filter(|record| matches!(record.status, TaskStatus::Queued | TaskStatus::Running))
+            .map(|record| record.id)
+            .collect();
+        (true, self.paused.clone())
+    }
+}
+
+/// The registered hotkey; unregistered when dropped.
+pub struct PauseHotkey {
+    manager: GlobalHotKeyManager,
+    hotkey: HotKey,
+}
+
+impl PauseHotkey {
+    /// Registers `spec` and starts pausing and resuming the tasks in `queue` on each press.
+    pub fn register(spec: &str, queue: sync_Arc<TaskQueue>) -> Result<Self, HotkeyError> {
+        let hotkey = HotKey::from_str(spec).map_err(|e| HotkeyError::Parse(e.to_string()))?;
+        let manager =
+            GlobalHotKeyManager::new().map_err(|e| HotkeyError::Register(e.to_string()))?;
+        manager
+            .register(hotkey)
+            .map_err(|e| HotkeyError::Register(e.to_string()))?;
+        let id = hotkey.id();
+        std::thread::spawn(move || {
+            let mut toggle = PauseToggle::default();
+            while let Ok(event) = GlobalHotKeyEvent::receiver().recv() {
+                if event.id == id && event.state == HotKeyState::Pressed {
+                    toggle_all(&queue, &mut toggle);
+                }
+            }
+        });
+        Ok(PauseHotkey { manager, hotkey })
+    }
+}
+
+impl Drop for PauseHotkey {
+    fn drop(&mut self) {
+        if let Err(e) = self.manager.unregister(self.hotkey) {
+            log::warn!("Cannot unregister the pause hotkey: {}", e);
+        }
+    }
+}
+
+fn toggle_all(queue: &TaskQueue, toggle: &mut PauseToggle) {
+    let (pause, task_ids) = toggle.toggle(&queue.records());
+    if pause {
+        log::info!("Pause hotkey: pausing {} tasks", task_ids.len());
+    } else {
+        log::info!("Pause hotkey: resuming {} tasks", task_ids.len());
+    }
+    for task_id in task_ids {
+        let (result, action) = if pause {
+            (queue.pause_task(task_id), AuditAction::Pause)
+        } else {
+            (queue.resume_task(task_id), AuditAction::Resume)
+        };
+        match result {
+            Ok(()) => queue.audit(task_id, action, &Origin::local(Interface::Hotkey)),
+            Err(e) => log::debug!("Pause hotkey left task {} alone: {:?}", task_id, e),
+        }
+    }
+}
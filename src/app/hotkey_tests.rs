@@ -0,0 +1,33 @@
+#[cfg(test)]
+use crate::app::history::TaskRecord;
+#[cfg(test)]
+use crate::app::hotkey::PauseToggle;
+#[cfg(test)]
+use crate::app::task_id::TaskId;
+#[cfg(test)]
+use crate::app::task_queue::TaskStatus;
+
+#[cfg(test)]
+fn record(id: usize, status: TaskStatus) -> TaskRecord {
+    TaskRecord {
+        status,
+        ..TaskRecord::new(TaskId::detached(id), "sleep")
+    }
+}
+
+#[test]
+fn test_pause_toggle_resumes_only_what_it_paused() {
+    let mut toggle = PauseToggle::default();
+    let records = vec![
+        record(1, TaskStatus::Running),
+        record(2, TaskStatus::Queued),
+        record(3, TaskStatus::Paused),
+        record(4, TaskStatus::Completed),
+    ];
+    let paused = vec![TaskId::detached(1), TaskId::detached(2)];
+    assert_eq!(toggle.toggle(&records), (true, paused.clone()));
+    assert_eq!(toggle.toggle(&records), (false, paused.clone()));
+    assert_eq!(toggle.toggle(&[]), (true, vec![]));
+    // Nothing was paused, so the next press pauses again rather than resuming.
+    assert_eq!(toggle.toggle(&records), (true, paused.clone()));
+}
@@ -0,0 +1,93 @@
+use std::fmt::{Display, Formatter, Result as FmtResult};
+use std::path::Path;
+
+use crate::app::launch_args::TaskSpec;
+use crate::app::registry::TaskKindRegistry;
+use crate::app::task_queue::Task;
+
+/// Job files are recognised by this extension, e.g. `nightly.taskqueue`.
+pub const JOB_FILE_EXTENSION: &str = "taskqueue";
+pub const JOB_FILE_VERSION: u32 = 1;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum JobFileError {
+    Io(String),
+    Parse(String),
+    UnsupportedVersion(u32),
+    /// One message per task that could not be created.
+    Invalid(Vec<String>),
+}
+
+impl Display for JobFileError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        match self {
+            JobFileError::Io(e) => write!(f, "Cannot read job file: {}", e),
+            JobFileError::Parse(e) => write!(f, "Malformed job file: {}", e),
+            JobFileError::UnsupportedVersion(version) => write!(
+                f,
+                "Job file version {} is not supported (expected {})",
+                version, JOB_FILE_VERSION
+            ),
+            JobFileError::Invalid(errors) => write!(f, "Invalid tasks: {}", errors.join("; ")),
+        }
+    }
+}
+
+/// A batch of tasks saved as JSON:
+///
+/// ```json
+/// {"version": 1, "auto_start": true, "tasks": [{"kind": "sleep", "params": {"seconds": "5"}}]}
+/// ```
+#[derive(Debug, Clone, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct JobFile {
+    pub version: u32,
+    /// Start the tasks as soon as the file is opened instead of adding them paused.
+    #[serde(default)]
+    pub auto_start: bool,
+    pub tasks: Vec<TaskSpec>,
+}
+
+impl JobFile {
+    pub fn from_json_str(s: &str) -> Result<Self, JobFileError> {
+        let job: JobFile =
+            serde_json::from_str(s).map_err(|e| JobFileError::Parse(e.to_string()))?;
+        if job.version != JOB_FILE_VERSION {
+            return Err(JobFileError::UnsupportedVersion(job.version));
+        }
+        Ok(job)
+    }
+
+    pub fn load(path: &Path) -> Result<Self, JobFileError> {
+        let contents =
+            std::fs::read_to_string(path).map_err(|e| JobFileError::Io(e.to_string()))?;
+        Self::from_json_str(&contents)
+    }
+
+    /// Creates every task in the batch, or reports all the ones that cannot be created.
+    pub fn create_tasks(
+        &self,
+        registry: &TaskKindRegistry,
+    ) -> Result<Vec<Box<dyn Task>>, JobFileError> {
+        let mut tasks = Vec::with_capacity(self.tasks.len());
+        let mut errors = Vec::new();
+        for (index, spec) in self.tasks.iter().enumerate() {
+            match registry.create(&spec.kind, &spec.params) {
+                Ok(task) => tasks.push(task),
+                Err(e) => errors.push(format!("task {} ({}): {}", index + 1, spec.kind, e)),
+            }
+        }
+        if errors.is_empty() {
+            Ok(tasks)
+        } else {
+            Err(JobFileError::Invalid(errors))
+        }
+    }
+}
+
+pub fn is_job_file(path: &Path) -> bool {
+    path.extension()
+        .and_then(|extension| extension.to_str())
+        .map_or(false, |extension| {
+            extension.eq_ignore_ascii_case(JOB_FILE_EXTENSION)
+        })
+}
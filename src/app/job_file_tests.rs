@@ -0,0 +1,48 @@
+#[cfg(test)]
+use crate::app::job_file::{is_job_file, JobFile, JobFileError};
+#[cfg(test)]
+use crate::app::launch_args::LaunchArgs;
+#[cfg(test)]
+use crate::app::registry::TaskKindRegistry;
+
+#[test]
+fn test_parse_and_validate_job_file() {
+    let job = JobFile::from_json_str(
+        r#"{"version": 1, "tasks": [{"kind": "sleep", "params": {"seconds": "1"}}, {"kind": "sleep"}]}"#,
+    )
+    .unwrap();
+    assert!(!job.auto_start);
+    assert_eq!(job.tasks.len(), 2);
+    let registry = TaskKindRegistry::default();
+    assert_eq!(job.create_tasks(&registry).unwrap().len(), 2);
+
+    let invalid = JobFile::from_json_str(
+        r#"{"version": 1, "tasks": [{"kind": "sleep", "params": {"seconds": "soon"}}, {"kind": "teleport"}]}"#,
+    )
+    .unwrap();
+    match invalid.create_tasks(&registry) {
+        Err(JobFileError::Invalid(errors)) => assert_eq!(errors.len(), 2),
+        _ => panic!("expected both tasks to be rejected"),
+    }
+
+    assert_eq!(
+        JobFile::from_json_str(r#"{"version": 2, "tasks": []}"#),
+        Err(JobFileError::UnsupportedVersion(2))
+    );
+    assert!(matches!(
+        JobFile::from_json_str("{"),
+        Err(JobFileError::Parse(_))
+    ));
+}
+
+#[test]
+fn test_job_files_in_launch_args() {
+    assert!(is_job_file(std::path::Path::new("nightly.TaskQueue")));
+    let launch =
+        LaunchArgs::parse(&["/tmp/nightly.taskqueue".to_owned(), "sleep".to_owned()]).unwrap();
+    assert_eq!(
+        launch.job_files,
+        vec![std::path::PathBuf::from("/tmp/nightly.taskqueue")]
+    );
+    assert_eq!(launch.tasks.len(), 1);
+}
@@ -0,0 +1,283 @@
+use std::sync::{Arc as sync_Arc, Condvar, Mutex as sync_Mutex};
+
+use log::{debug, error};
+
+use crate::app::chunked_task::{Step, StepFn};
+use crate::app::executor::{self, JobHandle};
+use crate::app::resource_usage::{CpuMeter, CpuSpan, ResourceUsage};
+use crate::app::task_id::TaskId;
+use crate::app::task_queue::{PollResult, PollingData, Task, TaskError, TaskKind, TaskStatus};
+
+pub type JobBody = Box<dyn FnOnce(&JobContext) -> Result<(), String> + Send>;
+
+/// How many steps a job made with [`JobTask::from_steps`] takes between checkpoints.
+const STEPS_PER_CHECKPOINT: u32 = 1024;
+
+struct JobState {
+    status: sync_Mutex<TaskStatus>,
+    resumed: Condvar,
+    progress: sync_Mutex<f32>,
+    /// Why the body failed, once it has.
+    error: sync_Mutex<Option<String>>,
+    cpu: CpuMeter,
+    /// Open while the body runs; sampled at each checkpoint on the body's thread.
+    cpu_span: sync_Mutex<Option<CpuSpan>>,
+}
+
+/// Handle given to a job body for reporting progress and honouring pause/cancel requests.
+#[derive(Clone)]
+pub struct JobContext {
+    state: sync_Arc<JobState>,
+}
+
+impl JobContext {
+    pub fn set_progress(&self, progress: f32) {
+        *self.state.progress.lock().unwrap() = progress.clamp(0.0, 1.0);
+    }
+
+    /// Blocks while the job is paused. Returns `false` if the job was cancelled,
+    /// in which case the body should return as soon as possible.
+    pub fn checkpoint(&self) -> bool {
+        if let Some(span) = self.state.cpu_span.lock().unwrap().as_mut() {
+            span.sample();
+        }
+        let mut status = self.state.status.lock().unwrap();
+        while *status == TaskStatus::Paused {
+            status = self.state.resumed.wait(status).unwrap();
+        }
+        *status != TaskStatus::Cancelled
+    }
+}
+
+/// Which of the executor's thread pools a job's body runs on.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum JobPool {
+    /// For bodies that mostly wait, e.g. on I/O.
+    Blocking,
+    /// For bodies that keep a core busy. A paused job still holds its thread.
+    Cpu,
+}
+
+/// A task whose work is a blocking closure, run on one of the executor's thread pools.
+///
+/// The body cooperates with pause and cancel by calling [`JobContext::checkpoint`] between
+/// units of work. A body returning `Err` ends the task as failed with that error.
+pub struct JobTask {
+    id: Option<TaskId>,
+    kind: TaskKind,
+    body: sync_Mutex<Option<JobBody>>,
+    context: JobContext,
+    pool: JobPool,
+    handle: Option<JobHandle>,
+}
+
+impl JobTask {
+    pub fn new(kind: TaskKind, body: JobBody) -> Self {
+        JobTask {
+            id: None,
+            kind,
+            body: sync_Mutex::new(Some(body)),
+            context: JobContext {
+                state: sync_Arc::new(JobState {
+                    status: sync_Mutex::new(TaskStatus::Queued),
+                    resumed: Condvar::new(),
+                    progress: sync_Mutex::new(0.0),
+                    error: sync_Mutex::new(None),
+                    cpu: CpuMeter::default(),
+                    cpu_span: sync_Mutex::new(None),
+                }),
+            },
+            pool: JobPool::Blocking,
+            handle: None,
+        }
+    }
+
+    /// A job taking `step` until it is done, the way a
+    /// [`ChunkedTask`](crate::app::chunked_task::ChunkedTask) would but on a thread of its
+    /// own.
+    pub fn from_steps(kind: TaskKind, mut step: StepFn) -> Self {
+        JobTask::new(
+            kind,
+            Box::new(move |context| loop {
+                if !context.checkpoint() {
+                    return Ok(());
+                }
+                let mut progress = None;
+                for _ in 0..STEPS_PER_CHECKPOINT {
+                    match step() {
+                        Step::Continue(p) => progress = Some(p),
+                        Step::Done => return Ok(()),
+                    }
+                }
+                if let Some(progress) = progress {
+                    context.set_progress(progress);
+                }
+            }),
+        )
+    }
+
+    /// Runs the body on the CPU pool instead of the blocking pool.
+    pub fn cpu_bound(mut self) -> Self {
+        self.pool = JobPool::Cpu;
+        self
+    }
+
+    fn set_status(&self, status: TaskStatus) {
+        *self.context.state.status.lock().unwrap() = status;
+        self.context.state.resumed.notify_all();
+    }
+
+    fn progress(&self) -> PollingData {
+        PollingData::Float(*self.context.state.progress.lock().unwrap())
+    }
+}
+
+impl Task for JobTask {
+    fn id(&self) -> Result<TaskId, TaskError> {
+        self.id.ok_or(TaskError::IdUsizeIsNone)
+    }
+
+    fn set_id(&mut self, id: TaskId) {
+        self.id = Some(id);
+    }
+
+    fn poll(&mut self) -> PollResult {
+        let status = self.context.state.status.lock().unwrap().clone();
+        match status {
+            TaskStatus::Scheduled | TaskStatus::Queued => {
+                let body = match self.body.lock().unwrap().take() {
+                    Some(body) => body,
+                    // Already submitted, waiting for a worker.
+                    None => return PollResult::Pending(self.progress()),
+                };
+                debug!("JobTask::poll() - starting {}", self.kind);
+                let context = self.context.clone();
+                let kind = self.kind.clone();
+                let run = move || {
+                    {
+                        let mut status = context.state.status.lock().unwrap();
+                        match *status {
+                            TaskStatus::Queued => *status = TaskStatus::Running,
+                            TaskStatus::Cancelled
+                            | TaskStatus::Failed
+                            | TaskStatus::Interrupted => return,
+                            _ => {}
+                        }
+                    }
+                    *context.state.cpu_span.lock().unwrap() = Some(context.state.cpu.span());
+                    let result = body(&context);
+                    context.state.cpu_span.lock().unwrap().take();
+                    let mut status = context.state.status.lock().unwrap();
+                    match result {
+                        Ok(()) if *status == TaskStatus::Running => {
+                            *context.state.progress.lock().unwrap() = 1.0;
+                            *status = TaskStatus::Completed;
+                        }
+                        Ok(()) => {}
+                        Err(_) if *status == TaskStatus::Cancelled => {}
+                        Err(e) => {
+                            error!("{} failed: {}", kind, e);
+                            *context.state.error.lock().unwrap() = Some(e);
+                            *status = TaskStatus::Failed;
+                        }
+                    }
+                };
+                // Holds a worker while it runs, so it counts towards the concurrency limit.
+                self.handle = Some(match self.pool {
+                    JobPool::Blocking => executor::submit(async move {
+                        let _ = executor::spawn_blocking(run).await;
+                    }),
+                    JobPool::Cpu => executor::submit(async move {
+                        let (done, finished) = futures::channel::oneshot::channel();
+                        executor::spawn_cpu(move || {
+                            run();
+                            let _ = done.send(());
+                        });
+                        let _ = finished.await;
+                    }),
+                });
+                PollResult::Pending(PollingData::Float(0.0))
+            }
+            TaskStatus::Running => PollResult::Pending(self.progress()),
+            TaskStatus::Paused => PollResult::Paused(self.progress()),
+            TaskStatus::Completed => PollResult::Completed,
+            TaskStatus::Cancelled | TaskStatus::Interrupted => PollResult::Cancelled,
+            TaskStatus::Failed => PollResult::Failed(TaskError::Failed {
+                reason: self
+                    .context
+                    .state
+                    .error
+                    .lock()
+                    .unwrap()
+                    .clone()
+                    .unwrap_or_default(),
+            }),
+        }
+    }
+
+    fn cancel(&mut self) -> Result<(), TaskError> {
+        let status = self.context.state.status.lock().unwrap().clone();
+        match status {
+            TaskStatus::Completed => Err(TaskError::AlreadyCompleted),
+            TaskStatus::Cancelled | TaskStatus::Failed | TaskStatus::Interrupted => {
+                Err(TaskError::AlreadyCancelled)
+            }
+            _ => {
+                self.set_status(TaskStatus::Cancelled);
+                Ok(())
+            }
+        }
+    }
+
+    fn pause(&mut self) -> Result<(), TaskError> {
+        let status = self.context.state.status.lock().unwrap().clone();
+        match status {
+            TaskStatus::Scheduled | TaskStatus::Queued | TaskStatus::Running => {
+                self.set_status(TaskStatus::Paused);
+                Ok(())
+            }
+            TaskStatus::Paused => Err(TaskError::AlreadyPaused),
+            TaskStatus::Completed => Err(TaskError::AlreadyCompleted),
+            TaskStatus::Cancelled | TaskStatus::Failed | TaskStatus::Interrupted => {
+                Err(TaskError::AlreadyCancelled)
+            }
+        }
+    }
+
+    fn resume(&mut self) -> Result<(), TaskError> {
+        let status = self.context.state.status.lock().unwrap().clone();
+        match status {
+            TaskStatus::Paused => {
+                // A job paused before it ever started goes back to the queue.
+                if self.body.lock().unwrap().is_some() {
+                    self.set_status(TaskStatus::Queued);
+                } else {
+                    self.set_status(TaskStatus::Running);
+                }
+                Ok(())
+            }
+            TaskStatus::Scheduled | TaskStatus::Queued | TaskStatus::Running => {
+                Err(TaskError::AlreadyRunning)
+            }
+            TaskStatus::Completed => Err(TaskError::AlreadyCompleted),
+            TaskStatus::Cancelled | TaskStatus::Failed | TaskStatus::Interrupted => {
+                Err(TaskError::AlreadyCancelled)
+            }
+        }
+    }
+
+    fn kind(&self) -> TaskKind {
+        self.kind.clone()
+    }
+
+    fn take_handle(&mut self) -> Option<JobHandle> {
+        self.handle.take()
+    }
+
+    fn resource_usage(&self) -> Option<ResourceUsage> {
+        Some(ResourceUsage {
+            cpu_time: self.context.state.cpu.total(),
+            io_bytes: None,
+        })
+    }
+}
@@ -0,0 +1,180 @@
+//! A write-ahead journal of task status changes, so that tasks cut short by a crash can
+//! be found, and started again, on the next launch.
+
+use std::collections::BTreeMap;
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex as sync_Mutex;
+
+use crate::app::history::TaskRecord;
+use crate::app::launch_args::TaskSpec;
+use crate::app::task_id::TaskId;
+use crate::app::task_queue::TaskStatus;
+
+const JOURNAL_FILE_NAME: &str = "journal.jsonl";
+
+/// One line of the journal.
+#[derive(Debug, Clone, PartialEq, serde::Deserialize, serde::Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum JournalEntry {
+    /// The queue added a task.
+    Added {
+        id: TaskId,
+        kind: String,
+        created_at: u64,
+    },
+    /// How the task was created, for those the registry can create again.
+    Spec { id: TaskId, spec: TaskSpec },
+    /// Written before the queue applies the change.
+    Status { id: TaskId, status: TaskStatus },
+}
+
+/// A task that had started, and not finished, when the app last stopped without
+/// shutting down.
+#[derive(Debug, Clone, PartialEq)]
+pub struct InterruptedTask {
+    pub id: TaskId,
+    pub kind: String,
+    pub created_at: u64,
+    /// `None` if the task cannot be created again, e.g. one from a remote agent.
+    pub spec: Option<TaskSpec>,
+}
+
+impl InterruptedTask {
+    /// Its record for the history, finished at `now`.
+    pub fn record(&self, now: u64) -> TaskRecord {
+        TaskRecord {
+            status: TaskStatus::Interrupted,
+            created_at: self.created_at,
+            finished_at: Some(now),
+            ..TaskRecord::new(self.id, &self.kind)
+        }
+    }
+}
+
+#[derive(Default)]
+struct Replayed {
+    kind: String,
+    created_at: u64,
+    spec: Option<TaskSpec>,
+    status: Option<TaskStatus>,
+}
+
+/// The tasks in a journal whose last status was running or paused. Lines that cannot be
+/// read, such as one cut short by the crash, are skipped.
+pub fn replay(reader: impl BufRead) -> Vec<InterruptedTask> {
+    let mut tasks: BTreeMap<TaskId, Replayed> = BTreeMap::new();
+    for line in reader.lines() {
+        let Ok(line) = line else {
+            break;
+        };
+        let Ok(entry) = serde_json::from_str::<JournalEntry>(&line) else {
+            continue;
+        };
+        match entry {
+            JournalEntry::Added {
+                id,
+                kind,
+                created_at,
+            } => {
+                let task = tasks.entry(id).or_default();
+                task.kind = kind;
+                task.created_at = created_at;
+            }
+            JournalEntry::Spec { id, spec } => tasks.entry(id).or_default().spec = Some(spec),
+            JournalEntry::Status { id, status } => {
+                if status.is_terminal() {
+                    tasks.remove(&id);
+                } else {
+                    tasks.entry(id).or_default().status = Some(status);
+                }
+            }
+        }
+    }
+    tasks
+        .into_iter()
+        .filter(|(_, task)| {
+            matches!(
+                task.status,
+                Some(TaskStatus::Running) | Some(TaskStatus::Paused)
+            )
+        })
+        .map(|(id, task)| InterruptedTask {
+            id,
+            kind: task.kind,
+            created_at: task.created_at,
+            spec: task.spec,
+        })
+        .collect()
+}
+
+/// Appends [`JournalEntry`] lines to a file in the data directory, which is emptied on a
+/// clean exit. Each entry is written straight to the file, so what was written before a
+/// crash is there after it.
+pub struct Journal {
+    /// `None` once closed.
+    file: sync_Mutex<Option<File>>,
+}
+
+impl Journal {
+    pub fn default_path() -> Option<PathBuf> {
+        directories_next::ProjectDirs::from("net", "xthreen", "functional_rust_ui_demo")
+            .map(|dirs| dirs.data_dir().join(JOURNAL_FILE_NAME))
+    }
+
+    /// Opens the journal at `path`, returning the tasks interrupted according to what a
+    /// previous run left in it, and then starts it afresh.
+    pub fn open(path: &Path) -> std::io::Result<(Self, Vec<InterruptedTask>)> {
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        let mut file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(path)?;
+        let interrupted = replay(BufReader::new(&mut file));
+        file.set_len(0)?;
+        file.seek(SeekFrom::Start(0))?;
+        let journal = Journal {
+            file: sync_Mutex::new(Some(file)),
+        };
+        Ok((journal, interrupted))
+    }
+
+    pub fn write(&self, entry: &JournalEntry) {
+        let mut line = match serde_json::to_vec(entry) {
+            Ok(line) => line,
+            Err(e) => {
+                log::error!("Cannot serialize journal entry: {}", e);
+                return;
+            }
+        };
+        line.push(b'\n');
+        let mut file = self
+            .file
+            .lock()
+            .expect("Panicked at write: Journal mutex poisoned");
+        let Some(file) = file.as_mut() else {
+            return;
+        };
+        if let Err(e) = file.write_all(&line) {
+            log::error!("Cannot write to the journal: {}", e);
+        }
+    }
+
+    /// Empties the journal on a clean exit, since nothing left running then was cut
+    /// short, and ignores whatever tasks still finishing write after that.
+    pub fn close(&self) {
+        let file = self
+            .file
+            .lock()
+            .expect("Panicked at close: Journal mutex poisoned")
+            .take();
+        if let Some(Err(e)) = file.map(|file| file.set_len(0)) {
+            log::error!("Cannot clear the journal: {}", e);
+        }
+    }
+}
@@ -0,0 +1,113 @@
+#[cfg(test)]
+use std::sync::Arc;
+#[cfg(test)]
+use std::time::Duration;
+
+#[cfg(test)]
+use crate::app::journal::{replay, Journal, JournalEntry};
+#[cfg(test)]
+use crate::app::launch_args::TaskSpec;
+#[cfg(test)]
+use crate::app::sleep_task::SleepTask;
+#[cfg(test)]
+use crate::app::task_id::TaskId;
+#[cfg(test)]
+use crate::app::task_queue::{TaskQueue, TaskStatus};
+
+#[cfg(test)]
+fn lines(entries: &[JournalEntry]) -> String {
+    entries
+        .iter()
+        .map(|entry| serde_json::to_string(entry).unwrap() + "\n")
+        .collect()
+}
+
+#[cfg(test)]
+fn added(id: usize) -> JournalEntry {
+    JournalEntry::Added {
+        id: TaskId::detached(id),
+        kind: "sleep".to_owned(),
+        created_at: 1_000,
+    }
+}
+
+#[cfg(test)]
+fn status(id: usize, status: TaskStatus) -> JournalEntry {
+    JournalEntry::Status {
+        id: TaskId::detached(id),
+        status,
+    }
+}
+
+#[test]
+fn test_replay_finds_unfinished_started_tasks() {
+    let spec = TaskSpec {
+        kind: "sleep".to_owned(),
+        params: [("seconds".to_owned(), "5".to_owned())]
+            .into_iter()
+            .collect(),
+    };
+    let mut journal = lines(&[
+        added(0),
+        added(1),
+        added(2),
+        added(3),
+        JournalEntry::Spec {
+            id: TaskId::detached(1),
+            spec: spec.clone(),
+        },
+        status(0, TaskStatus::Running),
+        status(0, TaskStatus::Completed),
+        status(1, TaskStatus::Running),
+        status(2, TaskStatus::Running),
+        status(2, TaskStatus::Paused),
+    ]);
+    // Cut short by the crash.
+    journal.push_str("{\"event\":\"status\",\"id\":3,");
+
+    let interrupted = replay(journal.as_bytes());
+    assert_eq!(
+        interrupted.iter().map(|task| task.id).collect::<Vec<_>>(),
+        [1, 2].map(TaskId::detached)
+    );
+    assert_eq!(interrupted[0].spec, Some(spec));
+    assert_eq!(interrupted[1].spec, None);
+
+    let record = interrupted[0].record(2_000);
+    assert_eq!(record.status, TaskStatus::Interrupted);
+    assert_eq!(record.kind, "sleep");
+    assert_eq!(record.created_at, 1_000);
+    assert_eq!(record.finished_at, Some(2_000));
+}
+
+#[test]
+fn test_queue_writes_ahead_and_close_empties() {
+    let path = std::env::temp_dir().join(format!("journal_{}.jsonl", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+    let (journal, interrupted) = Journal::open(&path).unwrap();
+    assert!(interrupted.is_empty());
+    let journal = Arc::new(journal);
+
+    let queue = TaskQueue::new();
+    queue.set_journal(journal.clone());
+    let id = queue.add_task(SleepTask::new(None, Duration::from_secs(60)));
+    queue.poll_task(id).unwrap();
+    queue.pause_task(id).unwrap();
+    queue.poll_task(id).unwrap();
+    drop(queue);
+
+    // As if the app had crashed here.
+    let (journal_again, interrupted) = Journal::open(&path).unwrap();
+    assert_eq!(interrupted.len(), 1);
+    assert_eq!(interrupted[0].id.index(), id.index());
+    assert_eq!(interrupted[0].kind, "sleep");
+    drop(journal);
+
+    journal_again.write(&added(7));
+    journal_again.write(&status(7, TaskStatus::Running));
+    journal_again.close();
+    journal_again.write(&status(7, TaskStatus::Running));
+    let (_, interrupted) = Journal::open(&path).unwrap();
+    assert!(interrupted.is_empty());
+    let _ = std::fs::remove_file(&path);
+}
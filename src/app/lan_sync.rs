@@ -0,0 +1,203 @@
+//! Experimental read-only mirroring of other instances' queues on the local network.
+//!
+//! Each instance streams its task records as JSON lines to whoever connects to its sync
+//! port, and advertises that port over mDNS. There is no authentication: anyone on the
+//! LAN can see task kinds and statuses, but nothing can be changed remotely.
+
+use std::collections::{BTreeMap, HashMap};
+use std::io::{BufRead, BufReader, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::sync::{Arc as sync_Arc, Mutex as sync_Mutex};
+
+use mdns_sd::{ServiceDaemon, ServiceEvent, ServiceInfo};
+
+use crate::app::config::LanSyncConfig;
+use crate::app::history::TaskRecord;
+use crate::app::task_id::TaskId;
+use crate::app::task_queue::TaskQueue;
+
+const SERVICE_TYPE: &str = "_taskqueue._tcp.local.";
+/// Finished tasks sent to a new peer, and kept per peer, beyond the active ones.
+const FINISHED_TO_MIRROR: usize = 100;
+
+/// Another instance's queue as last reported.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PeerQueue {
+    pub name: String,
+    pub address: Option<SocketAddr>,
+    pub connected: bool,
+    pub tasks: BTreeMap<TaskId, TaskRecord>,
+}
+
+impl PeerQueue {
+    pub fn apply(&mut self, record: TaskRecord) {
+        self.tasks.insert(record.id, record);
+        let finished: Vec<TaskId> = self
+            .tasks
+            .values()
+            .filter(|record| record.status.is_terminal())
+            .map(|record| record.id)
+            .collect();
+        for id in finished
+            .iter()
+            .take(finished.len().saturating_sub(FINISHED_TO_MIRROR))
+        {
+            self.tasks.remove(id);
+        }
+    }
+}
+
+type Peers = sync_Arc<sync_Mutex<BTreeMap<String, PeerQueue>>>;
+
+pub struct LanSync {
+    daemon: ServiceDaemon,
+    peers: Peers,
+}
+
+impl LanSync {
+    /// Serves this queue, advertises it and starts mirroring every other instance found.
+    pub fn start(config: &LanSyncConfig, queue: sync_Arc<TaskQueue>) -> Result<Self, String> {
+        let listener = TcpListener::bind(("0.0.0.0", config.port)).map_err(|e| e.to_string())?;
+        let port = listener.local_addr().map_err(|e| e.to_string())?.port();
+        serve_records(listener, queue);
+
+        let daemon = ServiceDaemon::new().map_err(|e| e.to_string())?;
+        let host_name = format!("{}.local.", config.instance_name);
+        let service = ServiceInfo::new(
+            SERVICE_TYPE,
+            &config.instance_name,
+            &host_name,
+            "",
+            port,
+            HashMap::<String, String>::new(),
+        )
+        .map_err(|e| e.to_string())?
+        .enable_addr_auto();
+        let own_name = service.get_fullname().to_owned();
+        daemon.register(service).map_err(|e| e.to_string())?;
+        log::info!(
+            "Sharing the queue on the LAN as '{}' (port {})",
+            config.instance_name,
+            port
+        );
+
+        let events = daemon.browse(SERVICE_TYPE).map_err(|e| e.to_string())?;
+        let peers = Peers::default();
+        let discovered = peers.clone();
+        std::thread::spawn(move || {
+            while let Ok(event) = events.recv() {
+                match event {
+                    ServiceEvent::ServiceResolved(info) if info.get_fullname() != own_name => {
+                        let Some(ip) = info.get_addresses_v4().into_iter().next().copied() else {
+                            continue;
+                        };
+                        let address = SocketAddr::from((ip, info.get_port()));
+                        let name = info
+                            .get_fullname()
+                            .trim_end_matches(SERVICE_TYPE)
+                            .trim_end_matches('.')
+                            .to_owned();
+                        start_mirror(&discovered, info.get_fullname(), name, address);
+                    }
+                    ServiceEvent::ServiceRemoved(_, fullname) => {
+                        lock(&discovered).remove(&fullname);
+                    }
+                    _ => {}
+                }
+            }
+        });
+        Ok(LanSync { daemon, peers })
+    }
+
+    pub fn peers(&self) -> Vec<PeerQueue> {
+        lock(&self.peers).values().cloned().collect()
+    }
+
+    /// Withdraws the mDNS advertisement so peers drop this instance right away.
+    pub fn shutdown(&self) {
+        if let Err(e) = self.daemon.shutdown() {
+            log::warn!("Failed to stop mDNS: {}", e);
+        }
+    }
+}
+
+fn lock(peers: &Peers) -> std::sync::MutexGuard<'_, BTreeMap<String, PeerQueue>> {
+    peers
+        .lock()
+        .expect("Panicked at lan_sync: Peers mutex poisoned")
+}
+
+/// Sends each client the recent history and active tasks, then every change as it happens.
+pub fn serve_records(listener: TcpListener, queue: sync_Arc<TaskQueue>) {
+    std::thread::spawn(move || {
+        for stream in listener.incoming() {
+            let mut stream = match stream {
+                Ok(stream) => stream,
+                Err(e) => {
+                    log::warn!("LAN sync accept failed: {}", e);
+                    continue;
+                }
+            };
+            // Subscribe before the snapshot so no change falls between the two.
+            let events = queue.subscribe();
+            let snapshot: Vec<TaskRecord> = queue
+                .history_page(0, FINISHED_TO_MIRROR)
+                .into_iter()
+                .chain(queue.records())
+                .collect();
+            std::thread::spawn(move || {
+                for record in snapshot.into_iter().chain(events) {
+                    if let Err(e) = write_record(&mut stream, &record) {
+                        log::debug!("LAN sync peer disconnected: {}", e);
+                        return;
+                    }
+                }
+            });
+        }
+    });
+}
+
+fn write_record(stream: &mut TcpStream, record: &TaskRecord) -> std::io::Result<()> {
+    let line = serde_json::to_string(record)?;
+    stream.write_all(line.as_bytes())?;
+    stream.write_all(b"\n")
+}
+
+/// Connects to a discovered peer unless it is already mirrored.
+fn start_mirror(peers: &Peers, key: &str, name: String, address: SocketAddr) {
+    {
+        let mut peers = lock(peers);
+        let peer = peers.entry(key.to_owned()).or_default();
+        if peer.connected && peer.address == Some(address) {
+            return;
+        }
+        *peer = PeerQueue {
+            name,
+            address: Some(address),
+            connected: true,
+            tasks: BTreeMap::new(),
+        };
+    }
+    let peers = peers.clone();
+    let key = key.to_owned();
+    std::thread::spawn(move || {
+        if let Err(e) = mirror(&peers, &key, address) {
+            log::info!("Stopped mirroring {}: {}", address, e);
+        }
+        if let Some(peer) = lock(&peers).get_mut(&key) {
+            peer.connected = false;
+        }
+    });
+}
+
+fn mirror(peers: &Peers, key: &str, address: SocketAddr) -> std::io::Result<()> {
+    let reader = BufReader::new(TcpStream::connect(address)?);
+    for line in reader.lines() {
+        let record: TaskRecord = serde_json::from_str(&line?)?;
+        match lock(peers).get_mut(key) {
+            Some(peer) => peer.apply(record),
+            None => return Ok(()),
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,49 @@
+#[cfg(test)]
+use std::io::{BufRead, BufReader};
+#[cfg(test)]
+use std::net::{TcpListener, TcpStream};
+#[cfg(test)]
+use std::sync::Arc;
+#[cfg(test)]
+use std::time::Duration;
+
+#[cfg(test)]
+use crate::app::history::TaskRecord;
+#[cfg(test)]
+use crate::app::lan_sync::{serve_records, PeerQueue};
+#[cfg(test)]
+use crate::app::sleep_task::SleepTask;
+#[cfg(test)]
+use crate::app::task_id::TaskId;
+#[cfg(test)]
+use crate::app::task_queue::{TaskQueue, TaskStatus};
+
+#[test]
+fn test_peer_keeps_recent_finished_tasks() {
+    let mut peer = PeerQueue::default();
+    for id in 0..150 {
+        let mut record = TaskRecord::new(TaskId::detached(id), "sleep");
+        record.status = TaskStatus::Completed;
+        peer.apply(record);
+    }
+    peer.apply(TaskRecord::new(TaskId::detached(150), "sleep"));
+    assert_eq!(peer.tasks.len(), 101);
+    assert_eq!(peer.tasks.keys().next(), Some(&TaskId::detached(50)));
+}
+
+#[test]
+fn test_serve_records_streams_snapshot_and_changes() {
+    let queue = Arc::new(TaskQueue::new());
+    let first = queue.add_task(SleepTask::new(None, Duration::from_secs(60)));
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let address = listener.local_addr().unwrap();
+    serve_records(listener, queue.clone());
+
+    let mut lines = BufReader::new(TcpStream::connect(address).unwrap()).lines();
+    let snapshot: TaskRecord = serde_json::from_str(&lines.next().unwrap().unwrap()).unwrap();
+    assert_eq!(snapshot.id.index(), first.index());
+
+    queue.pause_task(first).unwrap();
+    let change: TaskRecord = serde_json::from_str(&lines.next().unwrap().unwrap()).unwrap();
+    assert_eq!(change.status, TaskStatus::Paused);
+}
@@ -0,0 +1,279 @@
+use std::fmt::{Display, Formatter, Result as FmtResult};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use clap::error::{ContextKind, ContextValue, ErrorKind};
+use clap::{CommandFactory, Parser};
+use clap_complete::Shell;
+use log::LevelFilter;
+
+use crate::app::config::{AppConfig, ConfigOverrides};
+use crate::app::job_file::is_job_file;
+use crate::app::registry::TaskParams;
+
+const BIN_NAME: &str = "functional_rust_ui_demo";
+/// Links of the form `taskqueue://<kind>?<param>=<value>&...` enqueue a task of that kind.
+pub const URL_SCHEME: &str = "taskqueue";
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum LaunchArgsError {
+    ParamWithoutKind(String),
+    UnknownFlag(String),
+    InvalidUrl {
+        url: String,
+        message: String,
+    },
+    /// Any other problem clap found, rendered with its usage hint.
+    Invalid(String),
+    /// `--help` or `--version` was given; holds the text to print.
+    Info(String),
+}
+
+impl Display for LaunchArgsError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        match self {
+            LaunchArgsError::ParamWithoutKind(param) => {
+                write!(f, "Parameter '{}' must follow a task kind", param)
+            }
+            LaunchArgsError::UnknownFlag(flag) => write!(f, "Unknown flag: {}", flag),
+            LaunchArgsError::InvalidUrl { url, message } => {
+                write!(f, "Invalid task link '{}': {}", url, message)
+            }
+            LaunchArgsError::Invalid(message) | LaunchArgsError::Info(message) => {
+                write!(f, "{}", message.trim_end())
+            }
+        }
+    }
+}
+
+/// A task to enqueue at launch, e.g. `sleep seconds=5`.
+#[derive(Debug, Clone, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct TaskSpec {
+    pub kind: String,
+    #[serde(default)]
+    pub params: TaskParams,
+}
+
+/// A change to a running task's annotations, e.g. `12:commit=4f1a9c2`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Annotation {
+    pub task_id: usize,
+    pub key: String,
+    /// `None` removes the key, as given by an empty value, e.g. `12:commit=`.
+    pub value: Option<String>,
+}
+
+/// A task queue with a desktop UI.
+///
+/// Tasks are given as a kind followed by its parameters, e.g. `sleep seconds=5 download
+/// url=https://example.com/a.iso`.
+#[derive(Parser)]
+#[command(name = BIN_NAME, version)]
+struct Cli {
+    /// Open a new window even if the app is already running.
+    #[arg(long)]
+    new_instance: bool,
+    /// Run headless, driven by JSON-RPC on stdin/stdout.
+    #[arg(long)]
+    rpc_stdio: bool,
+    /// Run headless as a remote agent, taking tasks over WebSocket.
+    #[arg(long)]
+    agent: bool,
+    /// Initial window size.
+    #[arg(long, value_name = "WIDTHxHEIGHT", value_parser = parse_window_size)]
+    window_size: Option<[f32; 2]>,
+    /// Name shown in the window title, to tell instances apart, e.g. "render box".
+    #[arg(long, value_name = "NAME")]
+    name: Option<String>,
+    /// Config file to use instead of the one in the platform config directory.
+    #[arg(long, value_name = "PATH")]
+    config: Option<PathBuf>,
+    /// Overrides `log_level` from the config file.
+    #[arg(long, value_name = "LEVEL", value_parser = parse_log_level)]
+    log_level: Option<LevelFilter>,
+    /// Enqueue a download of URL into the downloads folder. Can be repeated.
+    #[arg(long, value_name = "URL")]
+    download: Vec<String>,
+    /// Enqueue a sleep task of SECONDS. Can be repeated.
+    #[arg(long, value_name = "SECONDS")]
+    sleep: Vec<String>,
+    /// Annotate task ID of the running instance; an empty VALUE removes KEY. Can be repeated.
+    #[arg(long, value_name = "ID:KEY=VALUE", value_parser = parse_annotation)]
+    annotate: Vec<Annotation>,
+    /// Print a completion script for SHELL and exit.
+    #[arg(long, value_name = "SHELL")]
+    completions: Option<Shell>,
+    /// Time adding, snapshotting and restoring TASKS tasks (1000 by default) with the
+    /// configured store, print percentiles and exit.
+    #[arg(long, value_name = "TASKS", num_args = 0..=1, default_missing_value = "1000")]
+    bench_warm_start: Option<usize>,
+    /// `<kind> [key=value]...` tasks, taskqueue:// links and .taskqueue job files.
+    #[arg(value_name = "TASK")]
+    tasks: Vec<String>,
+}
+
+fn parse_window_size(value: &str) -> Result<[f32; 2], String> {
+    let invalid = || format!("'{}' is not a size such as 1280x720", value);
+    let (width, height) = value.split_once(['x', 'X']).ok_or_else(invalid)?;
+    match (width.trim().parse::<u32>(), height.trim().parse::<u32>()) {
+        (Ok(width), Ok(height)) if width > 0 && height > 0 => Ok([width as f32, height as f32]),
+        _ => Err(invalid()),
+    }
+}
+
+fn parse_annotation(value: &str) -> Result<Annotation, String> {
+    let invalid = || format!("'{}' is not an annotation such as 12:commit=4f1a9c2", value);
+    let (task_id, pair) = value.split_once(':').ok_or_else(invalid)?;
+    let (key, value) = pair.split_once('=').ok_or_else(invalid)?;
+    let task_id = task_id.trim().parse().map_err(|_| invalid())?;
+    if key.is_empty() {
+        return Err(invalid());
+    }
+    Ok(Annotation {
+        task_id,
+        key: key.to_owned(),
+        value: (!value.is_empty()).then(|| value.to_owned()),
+    })
+}
+
+fn parse_log_level(value: &str) -> Result<LevelFilter, String> {
+    LevelFilter::from_str(value)
+        .map_err(|_| "expected off, error, warn, info, debug or trace".to_owned())
+}
+
+/// Command-line arguments, either given to this process or forwarded from a later launch.
+///
+/// Every positional argument without an `=` starts a new task of that kind; the
+/// `key=value` arguments after it are its parameters. `taskqueue://` links, usually passed
+/// in by a browser, and `.taskqueue` job files, usually passed in by the file manager, are
+/// collected separately. Run with `--help` for the flags.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct LaunchArgs {
+    pub new_instance: bool,
+    /// Run headless, driven by JSON-RPC on stdin/stdout instead of the window.
+    pub rpc_stdio: bool,
+    /// Run headless as a remote agent, taking tasks over WebSocket.
+    pub agent: bool,
+    pub window_size: Option<[f32; 2]>,
+    /// Shown in the window title instead of the app's name.
+    pub name: Option<String>,
+    pub config: Option<PathBuf>,
+    pub log_level: Option<LevelFilter>,
+    /// Print a completion script for this shell instead of starting.
+    pub completions: Option<Shell>,
+    /// Run the warm-start benchmark with this many tasks instead of starting.
+    pub bench_warm_start: Option<usize>,
+    pub tasks: Vec<TaskSpec>,
+    /// Applied to existing tasks once the new ones are enqueued.
+    pub annotations: Vec<Annotation>,
+    /// Tasks from `taskqueue://` links; only enqueued once the user confirms them.
+    pub link_tasks: Vec<TaskSpec>,
+    /// Job files to open as if chosen with File → Open.
+    pub job_files: Vec<PathBuf>,
+}
+
+impl LaunchArgs {
+    /// Parses `args`, which exclude the program name.
+    pub fn parse(args: &[String]) -> Result<Self, LaunchArgsError> {
+        let cli =
+            Cli::try_parse_from(std::iter::once(BIN_NAME).chain(args.iter().map(String::as_str)))
+                .map_err(cli_error)?;
+        let mut launch = LaunchArgs {
+            new_instance: cli.new_instance,
+            rpc_stdio: cli.rpc_stdio,
+            agent: cli.agent,
+            window_size: cli.window_size,
+            name: cli.name,
+            config: cli.config,
+            log_level: cli.log_level,
+            completions: cli.completions,
+            bench_warm_start: cli.bench_warm_start,
+            annotations: cli.annotate,
+            ..LaunchArgs::default()
+        };
+        for url in cli.download {
+            launch.tasks.push(task_with("download", "url", url));
+        }
+        for seconds in cli.sleep {
+            launch.tasks.push(task_with("sleep", "seconds", seconds));
+        }
+        for arg in &cli.tasks {
+            if is_task_link(arg) {
+                launch.link_tasks.push(parse_task_link(arg)?);
+            } else if is_job_file(Path::new(arg)) {
+                launch.job_files.push(PathBuf::from(arg));
+            } else if let Some((key, value)) = arg.split_once('=') {
+                let task = launch
+                    .tasks
+                    .last_mut()
+                    .ok_or_else(|| LaunchArgsError::ParamWithoutKind(arg.clone()))?;
+                task.params.insert(key.to_owned(), value.to_owned());
+            } else {
+                launch.tasks.push(TaskSpec {
+                    kind: arg.clone(),
+                    params: TaskParams::new(),
+                });
+            }
+        }
+        Ok(launch)
+    }
+
+    /// Makes `--config` and `--log-level` take effect for this process.
+    pub fn apply_config_overrides(&self) {
+        AppConfig::set_overrides(ConfigOverrides {
+            path: self.config.clone(),
+            log_level: self.log_level,
+        });
+    }
+}
+
+fn task_with(kind: &str, param: &str, value: String) -> TaskSpec {
+    TaskSpec {
+        kind: kind.to_owned(),
+        params: TaskParams::from([(param.to_owned(), value)]),
+    }
+}
+
+fn cli_error(e: clap::Error) -> LaunchArgsError {
+    match e.kind() {
+        ErrorKind::DisplayHelp | ErrorKind::DisplayVersion => {
+            LaunchArgsError::Info(e.render().to_string())
+        }
+        ErrorKind::UnknownArgument => match e.get(ContextKind::InvalidArg) {
+            Some(ContextValue::String(flag)) => LaunchArgsError::UnknownFlag(flag.clone()),
+            _ => LaunchArgsError::Invalid(e.render().to_string()),
+        },
+        _ => LaunchArgsError::Invalid(e.render().to_string()),
+    }
+}
+
+/// Writes the completion script for `shell` to `out`.
+pub fn write_completions(shell: Shell, out: &mut dyn Write) {
+    clap_complete::generate(shell, &mut Cli::command(), BIN_NAME, out);
+}
+
+fn is_task_link(arg: &str) -> bool {
+    arg.split_once(':')
+        .map_or(false, |(scheme, _)| scheme.eq_ignore_ascii_case(URL_SCHEME))
+}
+
+/// Parses a link such as `taskqueue://sleep?seconds=5`, decoding percent-escapes in the query.
+pub fn parse_task_link(link: &str) -> Result<TaskSpec, LaunchArgsError> {
+    let invalid = |message: &str| LaunchArgsError::InvalidUrl {
+        url: link.to_owned(),
+        message: message.to_owned(),
+    };
+    let url = url::Url::parse(link).map_err(|e| invalid(&e.to_string()))?;
+    if url.scheme() != URL_SCHEME {
+        return Err(invalid("wrong scheme"));
+    }
+    let kind = url
+        .host_str()
+        .filter(|kind| !kind.is_empty())
+        .ok_or_else(|| invalid("missing task kind"))?;
+    Ok(TaskSpec {
+        kind: kind.to_owned(),
+        params: url.query_pairs().into_owned().collect(),
+    })
+}
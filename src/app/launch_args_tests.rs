@@ -0,0 +1,147 @@
+#[cfg(test)]
+use crate::app::launch_args::{Annotation, LaunchArgs, LaunchArgsError};
+
+#[cfg(test)]
+fn args(args: &[&str]) -> Vec<String> {
+    args.iter().map(|arg| arg.to_string()).collect()
+}
+
+#[test]
+fn test_parse_tasks_and_params() {
+    let launch =
+        LaunchArgs::parse(&args(&["sleep", "seconds=5", "--new-instance", "sleep"])).unwrap();
+    assert!(launch.new_instance);
+    assert_eq!(launch.tasks.len(), 2);
+    assert_eq!(launch.tasks[0].kind, "sleep");
+    assert_eq!(
+        launch.tasks[0].params.get("seconds").map(String::as_str),
+        Some("5")
+    );
+    assert!(launch.tasks[1].params.is_empty());
+}
+
+#[test]
+fn test_param_without_kind() {
+    assert_eq!(
+        LaunchArgs::parse(&args(&["seconds=5"])),
+        Err(LaunchArgsError::ParamWithoutKind("seconds=5".to_owned()))
+    );
+    assert_eq!(
+        LaunchArgs::parse(&args(&["--bogus"])),
+        Err(LaunchArgsError::UnknownFlag("--bogus".to_owned()))
+    );
+}
+
+#[test]
+fn test_parse_task_link() {
+    let launch = LaunchArgs::parse(&args(&[
+        "taskqueue://download?url=https%3A%2F%2Fexample.com%2Fa%20b.iso&retries=3",
+    ]))
+    .unwrap();
+    assert!(launch.tasks.is_empty());
+    assert_eq!(launch.link_tasks.len(), 1);
+    let task = &launch.link_tasks[0];
+    assert_eq!(task.kind, "download");
+    assert_eq!(
+        task.params.get("url").map(String::as_str),
+        Some("https://example.com/a b.iso")
+    );
+    assert_eq!(task.params.get("retries").map(String::as_str), Some("3"));
+    assert!(LaunchArgs::parse(&args(&["taskqueue://?seconds=1"])).is_err());
+}
+
+#[test]
+fn test_parse_flags() {
+    let launch = LaunchArgs::parse(&args(&[
+        "--window-size",
+        "1280x720",
+        "--log-level",
+        "debug",
+        "--config",
+        "/tmp/other.toml",
+        "--download",
+        "https://example.com/a.iso",
+        "--sleep",
+        "5",
+        "sleep",
+    ]))
+    .unwrap();
+    assert_eq!(launch.window_size, Some([1280.0, 720.0]));
+    assert_eq!(launch.log_level, Some(log::LevelFilter::Debug));
+    assert_eq!(
+        launch.config,
+        Some(std::path::PathBuf::from("/tmp/other.toml"))
+    );
+    let kinds: Vec<&str> = launch.tasks.iter().map(|task| task.kind.as_str()).collect();
+    assert_eq!(kinds, vec!["download", "sleep", "sleep"]);
+    assert_eq!(
+        launch.tasks[0].params.get("url").map(String::as_str),
+        Some("https://example.com/a.iso")
+    );
+    assert_eq!(
+        launch.tasks[1].params.get("seconds").map(String::as_str),
+        Some("5")
+    );
+
+    assert!(matches!(
+        LaunchArgs::parse(&args(&["--window-size", "big"])),
+        Err(LaunchArgsError::Invalid(_))
+    ));
+    assert!(matches!(
+        LaunchArgs::parse(&args(&["--help"])),
+        Err(LaunchArgsError::Info(_))
+    ));
+}
+
+#[test]
+fn test_parse_annotations() {
+    let launch = LaunchArgs::parse(&args(&[
+        "--annotate",
+        "12:commit=4f1a9c2",
+        "--annotate",
+        "12:branch=",
+    ]))
+    .unwrap();
+    assert_eq!(
+        launch.annotations,
+        vec![
+            Annotation {
+                task_id: 12,
+                key: "commit".to_owned(),
+                value: Some("4f1a9c2".to_owned()),
+            },
+            Annotation {
+                task_id: 12,
+                key: "branch".to_owned(),
+                value: None,
+            },
+        ]
+    );
+    for invalid in ["commit=4f1a9c2", "x:commit=1", "12:commit", "12:=1"] {
+        assert!(matches!(
+            LaunchArgs::parse(&args(&["--annotate", invalid])),
+            Err(LaunchArgsError::Invalid(_))
+        ));
+    }
+}
+
+#[test]
+fn test_parse_bench_warm_start() {
+    let launch = LaunchArgs::parse(&args(&["--bench-warm-start"])).unwrap();
+    assert_eq!(
+        launch.bench_warm_start,
+        Some(crate::app::warm_start::DEFAULT_TASK_COUNT)
+    );
+    let launch = LaunchArgs::parse(&args(&["--bench-warm-start", "50000"])).unwrap();
+    assert_eq!(launch.bench_warm_start, Some(50_000));
+    assert_eq!(LaunchArgs::parse(&[]).unwrap().bench_warm_start, None);
+}
+
+#[test]
+fn test_write_completions() {
+    let launch = LaunchArgs::parse(&args(&["--completions", "bash"])).unwrap();
+    let mut script = Vec::new();
+    crate::app::launch_args::write_completions(launch.completions.unwrap(), &mut script);
+    let script = String::from_utf8(script).unwrap();
+    assert!(script.contains("--download"));
+}
@@ -0,0 +1,69 @@
+//! What the main window shows and how densely, kept across runs with the rest of the app
+//! state and put back to the defaults by Options → Reset layout.
+
+use std::fmt::{Display, Formatter, Result as FmtResult};
+
+use crate::app::color_tag::ColorTag;
+
+#[derive(Debug, Clone, PartialEq, serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+pub struct Layout {
+    pub show_header: bool,
+    pub show_footer: bool,
+    pub show_settings: bool,
+    pub show_history: bool,
+    /// Only tasks tagged with this color are listed.
+    pub color_filter: Option<ColorTag>,
+    pub density: Density,
+}
+
+impl Default for Layout {
+    fn default() -> Self {
+        Layout {
+            show_header: true,
+            show_footer: false,
+            show_settings: false,
+            show_history: false,
+            color_filter: None,
+            density: Density::default(),
+        }
+    }
+}
+
+/// How much room widgets get; compact fits more task rows on screen.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+pub enum Density {
+    #[default]
+    Comfortable,
+    Compact,
+}
+
+impl Density {
+    pub const ALL: [Density; 2] = [Density::Comfortable, Density::Compact];
+
+    /// egui's default spacing, tightened when compact.
+    pub fn spacing(&self) -> egui::style::Spacing {
+        let mut spacing = egui::style::Spacing::default();
+        if *self == Density::Compact {
+            spacing.item_spacing = egui::vec2(6.0, 1.0);
+            spacing.button_padding = egui::vec2(3.0, 0.0);
+            spacing.interact_size.y = 14.0;
+        }
+        spacing
+    }
+
+    pub fn apply(&self, ctx: &egui::Context) {
+        let mut style = (*ctx.style()).clone();
+        style.spacing = self.spacing();
+        ctx.set_style(style);
+    }
+}
+
+impl Display for Density {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        match self {
+            Density::Comfortable => write!(f, "Comfortable"),
+            Density::Compact => write!(f, "Compact"),
+        }
+    }
+}
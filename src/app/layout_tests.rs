@@ -0,0 +1,39 @@
+#[cfg(test)]
+use crate::app::color_tag::ColorTag;
+#[cfg(test)]
+use crate::app::layout::{Density, Layout};
+
+#[test]
+fn test_layout_loads_flags_saved_before_it_existed() {
+    let layout: Layout =
+        serde_json::from_str(r#"{"show_header": false, "show_footer": true}"#).unwrap();
+    assert_eq!(
+        layout,
+        Layout {
+            show_header: false,
+            show_footer: true,
+            ..Layout::default()
+        }
+    );
+}
+
+#[test]
+fn test_layout_round_trip() {
+    let layout = Layout {
+        show_history: true,
+        color_filter: Some(ColorTag::Red),
+        density: Density::Compact,
+        ..Layout::default()
+    };
+    let json = serde_json::to_string(&layout).unwrap();
+    assert_eq!(serde_json::from_str::<Layout>(&json).unwrap(), layout);
+}
+
+#[test]
+fn test_compact_density_is_tighter() {
+    let comfortable = Density::Comfortable.spacing();
+    let compact = Density::Compact.spacing();
+    assert_eq!(comfortable, egui::style::Spacing::default());
+    assert!(compact.interact_size.y < comfortable.interact_size.y);
+    assert!(compact.item_spacing.y < comfortable.item_spacing.y);
+}
@@ -0,0 +1,174 @@
+//! Pausing network tasks while the connection is metered or roaming, so they do not use
+//! up a data plan, and resuming them once it is not.
+
+use std::collections::HashSet;
+use std::fmt::{Display, Formatter, Result as FmtResult};
+#[cfg(any(target_os = "linux", target_os = "windows"))]
+use std::process::Command;
+
+use crate::app::history::TaskRecord;
+use crate::app::task_id::TaskId;
+use crate::app::task_queue::TaskStatus;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum NetworkCost {
+    Unmetered,
+    /// Data is capped or charged for.
+    Metered,
+    /// On a mobile network away from home.
+    Roaming,
+    /// Offline, or the platform cannot tell.
+    #[default]
+    Unknown,
+}
+
+impl NetworkCost {
+    /// Whether network tasks should wait for another connection.
+    pub fn is_costly(&self) -> bool {
+        matches!(self, NetworkCost::Metered | NetworkCost::Roaming)
+    }
+}
+
+impl Display for NetworkCost {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        match self {
+            NetworkCost::Unmetered => write!(f, "Unmetered connection"),
+            NetworkCost::Metered => write!(f, "Metered connection"),
+            NetworkCost::Roaming => write!(f, "Roaming"),
+            NetworkCost::Unknown => write!(f, "Connection cost unknown"),
+        }
+    }
+}
+
+/// Asks NetworkManager whether its primary connection is metered. It does not tell
+/// about roaming.
+#[cfg(target_os = "linux")]
+pub fn network_cost() -> NetworkCost {
+    let output = Command::new("busctl")
+        .args([
+            "get-property",
+            "org.freedesktop.NetworkManager",
+            "/org/freedesktop/NetworkManager",
+            "org.freedesktop.NetworkManager",
+            "Metered",
+        ])
+        .output();
+    match output {
+        Ok(output) if output.status.success() => {
+            parse_nm_metered(&String::from_utf8_lossy(&output.stdout))
+        }
+        Ok(_) => NetworkCost::Unknown,
+        Err(e) => {
+            log::debug!("Cannot run busctl: {}", e);
+            NetworkCost::Unknown
+        }
+    }
+}
+
+/// Parses NetworkManager's `Metered` property as `busctl` prints it, e.g. `u 4`. Guesses
+/// count, since NetworkManager only guesses for most connections.
+#[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+pub fn parse_nm_metered(output: &str) -> NetworkCost {
+    match output.split_whitespace().nth(1) {
+        // NM_METERED_YES, NM_METERED_GUESS_YES
+        Some("1" | "3") => NetworkCost::Metered,
+        // NM_METERED_NO, NM_METERED_GUESS_NO
+        Some("2" | "4") => NetworkCost::Unmetered,
+        _ => NetworkCost::Unknown,
+    }
+}
+
+/// Asks the Windows Runtime for the cost of the internet connection.
+#[cfg(target_os = "windows")]
+pub fn network_cost() -> NetworkCost {
+    const SCRIPT: &str = "$p = [Windows.Networking.Connectivity.NetworkInformation,\
+        Windows.Networking.Connectivity,ContentType=WindowsRuntime]::\
+        GetInternetConnectionProfile(); \
+        if ($p) { $c = $p.GetConnectionCost(); \"$($c.NetworkCostType) $($c.Roaming)\" }";
+    let output = Command::new("powershell")
+        .args(["-NoProfile", "-NonInteractive", "-Command", SCRIPT])
+        .output();
+    match output {
+        Ok(output) => parse_connection_cost(&String::from_utf8_lossy(&output.stdout)),
+        Err(e) => {
+            log::debug!("Cannot run powershell: {}", e);
+            NetworkCost::Unknown
+        }
+    }
+}
+
+/// Parses a `ConnectionCost`'s network cost type and roaming flag, e.g. `Fixed False`.
+#[cfg_attr(not(target_os = "windows"), allow(dead_code))]
+pub fn parse_connection_cost(output: &str) -> NetworkCost {
+    let mut words = output.split_whitespace();
+    let cost = words.next();
+    if words.next() == Some("True") {
+        return NetworkCost::Roaming;
+    }
+    match cost {
+        Some("Unrestricted") => NetworkCost::Unmetered,
+        Some("Fixed" | "Variable") => NetworkCost::Metered,
+        _ => NetworkCost::Unknown,
+    }
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "windows")))]
+pub fn network_cost() -> NetworkCost {
+    NetworkCost::Unknown
+}
+
+/// Remembers which tasks were paused for a costly connection, so that only those are
+/// resumed when it goes, and which tasks the user lets run on one anyway.
+#[derive(Debug, Default)]
+pub struct MeteredGuard {
+    paused: Vec<TaskId>,
+    allowed: HashSet<TaskId>,
+}
+
+impl MeteredGuard {
+    pub fn is_allowed(&self, id: TaskId) -> bool {
+        self.allowed.contains(&id)
+    }
+
+    /// Lets the task run on a costly connection, or not. Returns whether the guard had
+    /// paused it, in which case it is the caller's to resume.
+    pub fn allow(&mut self, id: TaskId, allowed: bool) -> bool {
+        if !allowed {
+            self.allowed.remove(&id);
+            return false;
+        }
+        self.allowed.insert(id);
+        let paused = self.paused.contains(&id);
+        self.paused.retain(|paused| *paused != id);
+        paused
+    }
+
+    /// Queued or running tasks of a network kind, not allowed on a costly connection,
+    /// that this guard has not paused before.
+    pub fn tasks_to_pause(
+        &mut self,
+        records: &[TaskRecord],
+        network_kinds: &[String],
+    ) -> Vec<TaskId> {
+        let ids: Vec<TaskId> = records
+            .iter()
+            .filter(|record| matches!(record.status, TaskStatus::Queued | TaskStatus::Running))
+            .filter(|record| network_kinds.contains(&record.kind))
+            .map(|record| record.id)
+            .filter(|id| !self.allowed.contains(id) && !self.paused.contains(id))
+            .collect();
+        self.paused.extend(&ids);
+        ids
+    }
+
+    /// Every task the guard paused, which it then forgets.
+    pub fn tasks_to_resume(&mut self) -> Vec<TaskId> {
+        std::mem::take(&mut self.paused)
+    }
+
+    /// Drops what it knows about tasks that finished.
+    pub fn forget(&mut self, finished: &HashSet<TaskId>) {
+        self.paused.retain(|id| !finished.contains(id));
+        self.allowed.retain(|id| !finished.contains(id));
+    }
+}
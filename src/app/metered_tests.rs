@@ -0,0 +1,59 @@
+#[cfg(test)]
+use std::collections::HashSet;
+
+#[cfg(test)]
+use crate::app::history::TaskRecord;
+#[cfg(test)]
+use crate::app::metered::{parse_connection_cost, parse_nm_metered, MeteredGuard, NetworkCost};
+#[cfg(test)]
+use crate::app::task_id::TaskId;
+#[cfg(test)]
+use crate::app::task_queue::TaskStatus;
+
+#[test]
+fn test_parse_network_cost() {
+    assert_eq!(parse_nm_metered("u 1\n"), NetworkCost::Metered);
+    assert_eq!(parse_nm_metered("u 3\n"), NetworkCost::Metered);
+    assert_eq!(parse_nm_metered("u 4\n"), NetworkCost::Unmetered);
+    assert_eq!(parse_nm_metered("u 0\n"), NetworkCost::Unknown);
+    assert_eq!(parse_nm_metered(""), NetworkCost::Unknown);
+
+    assert_eq!(
+        parse_connection_cost("Unrestricted False\r\n"),
+        NetworkCost::Unmetered
+    );
+    assert_eq!(parse_connection_cost("Fixed False"), NetworkCost::Metered);
+    assert_eq!(parse_connection_cost("Variable True"), NetworkCost::Roaming);
+    assert_eq!(parse_connection_cost(""), NetworkCost::Unknown);
+    assert!(NetworkCost::Roaming.is_costly() && !NetworkCost::Unknown.is_costly());
+}
+
+#[test]
+fn test_metered_guard_skips_allowed_tasks() {
+    let record = |id, kind: &str, status| TaskRecord {
+        status,
+        ..TaskRecord::new(TaskId::detached(id), kind)
+    };
+    let records = [
+        record(0, "download", TaskStatus::Running),
+        record(1, "download", TaskStatus::Queued),
+        record(2, "download", TaskStatus::Paused),
+        record(3, "sleep", TaskStatus::Running),
+        record(4, "download", TaskStatus::Running),
+    ];
+    let kinds = ["download".to_owned()];
+    let mut guard = MeteredGuard::default();
+    assert!(!guard.allow(TaskId::detached(4), true));
+    assert_eq!(
+        guard.tasks_to_pause(&records, &kinds),
+        [0, 1].map(TaskId::detached)
+    );
+    assert!(guard.tasks_to_pause(&records, &kinds).is_empty());
+
+    // Let through after it was paused: the caller resumes it.
+    assert!(guard.allow(TaskId::detached(1), true));
+    guard.forget(&HashSet::from([TaskId::detached(4)]));
+    assert!(!guard.is_allowed(TaskId::detached(4)));
+    assert_eq!(guard.tasks_to_resume(), [TaskId::detached(0)]);
+    assert!(guard.tasks_to_resume().is_empty());
+}
@@ -1,5 +1,217 @@
+#[cfg(feature = "ui")]
+pub mod a11y;
+#[cfg_attr(not(feature = "ui"), allow(dead_code))]
+pub mod artifacts;
+#[cfg(all(feature = "ui", not(target_arch = "wasm32")))]
+pub mod assets;
+#[cfg(all(feature = "ui", not(target_arch = "wasm32")))]
+pub mod associations;
+pub mod audit;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod blocking_task;
+pub mod calendar;
+pub mod cancellation;
+#[cfg(debug_assertions)]
+pub mod chaos;
+pub mod chunked_task;
+pub mod color_tag;
+#[cfg_attr(not(feature = "ui"), allow(dead_code))]
+pub mod config;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod control;
+#[cfg(windows)]
+pub mod control_pipe;
+#[cfg(all(feature = "ui", not(target_arch = "wasm32")))]
+pub mod csv_import;
+pub mod deadline;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod disk_space;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod download_task;
+#[cfg(all(feature = "email", not(target_arch = "wasm32")))]
+pub mod email;
+pub mod executor;
+pub mod forecast;
+#[cfg_attr(not(feature = "ui"), allow(dead_code))]
+pub mod format;
+#[cfg(all(feature = "ui", not(target_arch = "wasm32")))]
+pub mod governor;
+pub mod history;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod history_spill;
+#[cfg(all(feature = "global-hotkey", not(target_arch = "wasm32")))]
+pub mod hotkey;
+#[cfg(not(target_arch = "wasm32"))]
+#[cfg_attr(not(feature = "ui"), allow(dead_code))]
+pub mod job_file;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod job_task;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod journal;
+#[cfg(all(feature = "lan-sync", not(target_arch = "wasm32")))]
+pub mod lan_sync;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod launch_args;
+#[cfg(feature = "ui")]
+pub mod layout;
+#[cfg(all(feature = "ui", not(target_arch = "wasm32")))]
+pub mod metered;
+#[cfg(all(feature = "mqtt", not(target_arch = "wasm32")))]
+pub mod mqtt;
+#[cfg(all(feature = "otel", not(target_arch = "wasm32")))]
+pub mod otel;
+pub mod pausable_timer;
+#[cfg(all(
+    any(feature = "plugins", feature = "wasm-plugins"),
+    not(target_arch = "wasm32")
+))]
+pub mod plugins;
+#[cfg(all(feature = "ui", not(target_arch = "wasm32")))]
+pub mod post_batch;
+#[cfg(all(feature = "ui", not(target_arch = "wasm32")))]
+pub mod power;
+pub mod priority;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod process_task;
+pub mod profiler;
+pub mod progress_channel;
+#[cfg(feature = "ui")]
+pub mod progress_estimate;
+#[cfg_attr(not(feature = "ui"), allow(dead_code))]
+pub mod registry;
+#[cfg(all(feature = "remote-agent", not(target_arch = "wasm32")))]
+pub mod remote_agent;
+pub mod resource_usage;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod rpc_stdio;
+pub mod scheduler;
+#[cfg(all(feature = "secrets", not(target_arch = "wasm32")))]
+pub mod secrets;
+#[cfg(all(feature = "ui", not(target_arch = "wasm32")))]
+pub mod session;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod single_instance;
 pub mod sleep_task;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod speed_limit;
+#[cfg(all(feature = "sqlite", not(target_arch = "wasm32")))]
+pub mod store;
+pub mod stress;
+pub mod task_group;
+pub mod task_id;
+pub mod task_map;
 pub mod task_queue;
+#[cfg(feature = "ui")]
+pub mod task_rows;
+#[cfg(feature = "ui")]
 pub mod template_ui;
+#[cfg(all(feature = "ui", not(target_arch = "wasm32")))]
+pub mod trace_export;
+#[cfg(all(feature = "self-update", not(target_arch = "wasm32")))]
+pub mod updater;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod warm_start;
+#[cfg(all(feature = "ui", not(target_arch = "wasm32")))]
+pub mod watch_folder;
+#[cfg(all(feature = "ui", target_arch = "wasm32"))]
+pub mod web_storage;
+#[cfg(all(feature = "ui", not(target_arch = "wasm32")))]
+pub mod webhooks;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod window_title;
 
+#[cfg(feature = "ui")]
+mod a11y_tests;
+#[cfg(all(feature = "ui", not(target_arch = "wasm32")))]
+mod assets_tests;
+mod audit_tests;
+#[cfg(not(target_arch = "wasm32"))]
+mod blocking_task_tests;
+mod calendar_tests;
+mod cancellation_tests;
+#[cfg(debug_assertions)]
+mod chaos_tests;
+mod chunked_task_tests;
+mod color_tag_tests;
+mod config_tests;
+#[cfg(not(target_arch = "wasm32"))]
+mod control_tests;
+#[cfg(all(feature = "ui", not(target_arch = "wasm32")))]
+mod csv_import_tests;
+mod deadline_tests;
+#[cfg(not(target_arch = "wasm32"))]
+mod disk_space_tests;
+#[cfg(not(target_arch = "wasm32"))]
+mod download_task_tests;
+#[cfg(all(feature = "email", not(target_arch = "wasm32")))]
+mod email_tests;
+#[cfg(not(target_arch = "wasm32"))]
+mod executor_tests;
+mod forecast_tests;
+mod format_tests;
+#[cfg(all(feature = "ui", not(target_arch = "wasm32")))]
+mod governor_tests;
+#[cfg(all(feature = "global-hotkey", not(target_arch = "wasm32")))]
+mod hotkey_tests;
+#[cfg(not(target_arch = "wasm32"))]
+mod job_file_tests;
+#[cfg(not(target_arch = "wasm32"))]
+mod journal_tests;
+#[cfg(all(feature = "lan-sync", not(target_arch = "wasm32")))]
+mod lan_sync_tests;
+#[cfg(not(target_arch = "wasm32"))]
+mod launch_args_tests;
+#[cfg(feature = "ui")]
+mod layout_tests;
+#[cfg(all(feature = "ui", not(target_arch = "wasm32")))]
+mod metered_tests;
+#[cfg(all(feature = "mqtt", not(target_arch = "wasm32")))]
+mod mqtt_tests;
+mod pausable_timer_tests;
+#[cfg(all(feature = "plugins", not(target_arch = "wasm32")))]
+mod plugins_tests;
+#[cfg(all(feature = "ui", not(target_arch = "wasm32")))]
+mod post_batch_tests;
+#[cfg(all(feature = "ui", not(target_arch = "wasm32")))]
+mod power_tests;
+mod priority_tests;
+#[cfg(not(target_arch = "wasm32"))]
+mod process_task_tests;
+#[cfg(feature = "profiling")]
+mod profiler_tests;
+mod progress_channel_tests;
+#[cfg(feature = "ui")]
+mod progress_estimate_tests;
+mod registry_tests;
+#[cfg(all(feature = "remote-agent", not(target_arch = "wasm32")))]
+mod remote_agent_tests;
+#[cfg(not(target_arch = "wasm32"))]
+mod rpc_stdio_tests;
+mod scheduler_tests;
+#[cfg(all(feature = "secrets", not(target_arch = "wasm32")))]
+mod secrets_tests;
+#[cfg(all(feature = "ui", not(target_arch = "wasm32")))]
+mod session_tests;
+#[cfg(not(target_arch = "wasm32"))]
+mod speed_limit_tests;
+#[cfg(all(feature = "sqlite", not(target_arch = "wasm32")))]
+mod store_tests;
+mod task_group_tests;
+mod task_map_tests;
 mod task_queue_tests;
+#[cfg(feature = "ui")]
+mod task_rows_tests;
+#[cfg(all(feature = "ui", not(target_arch = "wasm32")))]
+mod trace_export_tests;
+#[cfg(all(feature = "self-update", not(target_arch = "wasm32")))]
+mod updater_tests;
+#[cfg(not(target_arch = "wasm32"))]
+mod warm_start_tests;
+#[cfg(all(feature = "wasm-plugins", not(target_arch = "wasm32")))]
+mod wasm_plugins_tests;
+#[cfg(all(feature = "ui", not(target_arch = "wasm32")))]
+mod watch_folder_tests;
+#[cfg(all(feature = "ui", not(target_arch = "wasm32")))]
+mod webhooks_tests;
+#[cfg(not(target_arch = "wasm32"))]
+mod window_title_tests;
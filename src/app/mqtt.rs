@@ -0,0 +1,112 @@
+use std::collections::HashMap;
+use std::sync::mpsc::{Receiver, RecvTimeoutError};
+use std::time::{Duration, Instant};
+
+use rumqttc::{Client, MqttOptions, QoS};
+
+use crate::app::config::MqttConfig;
+use crate::app::history::TaskRecord;
+use crate::app::task_id::TaskId;
+use crate::app::task_queue::TaskStatus;
+
+const KEEP_ALIVE: Duration = Duration::from_secs(30);
+const RECONNECT_DELAY: Duration = Duration::from_secs(5);
+const REQUEST_CAPACITY: usize = 64;
+
+/// Task counts published to the stats topic. Active statuses count tasks currently in them;
+/// `completed`, `cancelled`, `failed` and `interrupted` count every task that finished
+/// since startup.
+#[derive(Debug, Default, Clone, PartialEq, serde::Serialize)]
+pub struct QueueStats {
+    pub scheduled: usize,
+    pub queued: usize,
+    pub running: usize,
+    pub paused: usize,
+    pub completed: usize,
+    pub cancelled: usize,
+    pub failed: usize,
+    pub interrupted: usize,
+    #[serde(skip)]
+    active: HashMap<TaskId, TaskStatus>,
+}
+
+impl QueueStats {
+    pub fn record(&mut self, record: &TaskRecord) {
+        if let Some(previous) = self.active.remove(&record.id) {
+            *self.count_mut(&previous) -= 1;
+        }
+        *self.count_mut(&record.status) += 1;
+        if !record.status.is_terminal() {
+            self.active.insert(record.id, record.status.clone());
+        }
+    }
+
+    fn count_mut(&mut self, status: &TaskStatus) -> &mut usize {
+        match status {
+            TaskStatus::Scheduled => &mut self.scheduled,
+            TaskStatus::Queued => &mut self.queued,
+            TaskStatus::Running => &mut self.running,
+            TaskStatus::Paused => &mut self.paused,
+            TaskStatus::Completed => &mut self.completed,
+            TaskStatus::Cancelled => &mut self.cancelled,
+            TaskStatus::Failed => &mut self.failed,
+            TaskStatus::Interrupted => &mut self.interrupted,
+        }
+    }
+}
+
+/// Connects to the broker in `config` and publishes every record received on `events`,
+/// plus periodic stats, until the queue drops its end of the channel.
+pub fn spawn_publisher(config: &MqttConfig, events: Receiver<TaskRecord>) {
+    let mut options = MqttOptions::new(&config.client_id, &config.host, config.port);
+    options.set_keep_alive(KEEP_ALIVE);
+    if let Some(username) = &config.username {
+        options.set_credentials(username, config.password.clone().unwrap_or_default());
+    }
+    let (client, mut connection) = Client::new(options, REQUEST_CAPACITY);
+
+    // rumqttc only makes progress (and reconnects) while its event loop is iterated.
+    std::thread::spawn(move || {
+        for notification in connection.iter() {
+            if let Err(e) = notification {
+                log::warn!("MQTT connection error: {}", e);
+                std::thread::sleep(RECONNECT_DELAY);
+            }
+        }
+    });
+
+    let events_topic = config.events_topic.clone();
+    let stats_topic = config.stats_topic.clone();
+    let stats_interval = Duration::from_secs(config.stats_interval_secs.max(1));
+    std::thread::spawn(move || {
+        let mut stats = QueueStats::default();
+        let mut last_stats = Instant::now();
+        loop {
+            let timeout = stats_interval.saturating_sub(last_stats.elapsed());
+            match events.recv_timeout(timeout) {
+                Ok(record) => {
+                    stats.record(&record);
+                    let topic = format!("{}/{}", events_topic, record.status);
+                    publish(&client, topic, &record);
+                }
+                Err(RecvTimeoutError::Timeout) => {}
+                Err(RecvTimeoutError::Disconnected) => break,
+            }
+            if last_stats.elapsed() >= stats_interval {
+                publish(&client, stats_topic.clone(), &stats);
+                last_stats = Instant::now();
+            }
+        }
+        let _ = client.disconnect();
+    });
+}
+
+fn publish<T: serde::Serialize>(client: &Client, topic: String, payload: &T) {
+    let payload = match serde_json::to_vec(payload) {
+        Ok(payload) => payload,
+        Err(e) => return log::error!("Failed to serialize MQTT payload: {}", e),
+    };
+    if let Err(e) = client.try_publish(topic, QoS::AtLeastOnce, false, payload) {
+        log::warn!("Dropped MQTT message: {}", e);
+    }
+}
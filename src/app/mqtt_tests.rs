@@ -0,0 +1,25 @@
+#[cfg(test)]
+use crate::app::history::TaskRecord;
+#[cfg(test)]
+use crate::app::mqtt::QueueStats;
+#[cfg(test)]
+use crate::app::task_id::TaskId;
+#[cfg(test)]
+use crate::app::task_queue::TaskStatus;
+
+#[test]
+fn test_stats_follow_transitions() {
+    let mut stats = QueueStats::default();
+    let mut record = TaskRecord::new(TaskId::detached(0), "sleep");
+    stats.record(&record);
+    stats.record(&TaskRecord::new(TaskId::detached(1), "sleep"));
+    assert_eq!((stats.queued, stats.running), (2, 0));
+
+    record.status = TaskStatus::Running;
+    stats.record(&record);
+    assert_eq!((stats.queued, stats.running), (1, 1));
+
+    record.status = TaskStatus::Completed;
+    stats.record(&record);
+    assert_eq!((stats.queued, stats.running, stats.completed), (1, 0, 1));
+}
@@ -0,0 +1,177 @@
+use std::collections::HashMap;
+use std::sync::mpsc::Receiver;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use opentelemetry::metrics::{Counter, Histogram, MeterProvider, UpDownCounter};
+use opentelemetry::trace::{Span, SpanKind, Status, Tracer, TracerProvider};
+use opentelemetry::KeyValue;
+use opentelemetry_otlp::{MetricExporter, SpanExporter, WithExportConfig};
+use opentelemetry_sdk::metrics::{PeriodicReader, SdkMeterProvider};
+use opentelemetry_sdk::trace::{SdkTracer, SdkTracerProvider};
+use opentelemetry_sdk::Resource;
+
+use crate::app::config::OtelConfig;
+use crate::app::history::TaskRecord;
+use crate::app::task_id::TaskId;
+use crate::app::task_queue::TaskStatus;
+
+const INSTRUMENTATION_NAME: &str = "functional_rust_ui_demo";
+
+/// Keeps the OTLP providers alive; [`OtelExporter::shutdown`] flushes whatever is still buffered.
+pub struct OtelExporter {
+    tracer_provider: SdkTracerProvider,
+    meter_provider: SdkMeterProvider,
+}
+
+impl OtelExporter {
+    /// Starts exporting a span per task and queue metrics for every record received on `events`.
+    pub fn start(config: &OtelConfig, events: Receiver<TaskRecord>) -> Result<Self, String> {
+        let endpoint = config.endpoint.trim_end_matches('/');
+        let resource = Resource::builder()
+            .with_service_name(config.service_name.clone())
+            .build();
+
+        let span_exporter = SpanExporter::builder()
+            .with_http()
+            .with_endpoint(format!("{}/v1/traces", endpoint))
+            .build()
+            .map_err(|e| e.to_string())?;
+        let tracer_provider = SdkTracerProvider::builder()
+            .with_batch_exporter(span_exporter)
+            .with_resource(resource.clone())
+            .build();
+
+        let metric_exporter = MetricExporter::builder()
+            .with_http()
+            .with_endpoint(format!("{}/v1/metrics", endpoint))
+            .build()
+            .map_err(|e| e.to_string())?;
+        let reader = PeriodicReader::builder(metric_exporter)
+            .with_interval(Duration::from_secs(config.metrics_interval_secs.max(1)))
+            .build();
+        let meter_provider = SdkMeterProvider::builder()
+            .with_reader(reader)
+            .with_resource(resource)
+            .build();
+
+        let mut recorder = TaskRecorder::new(
+            tracer_provider.tracer(INSTRUMENTATION_NAME),
+            &meter_provider,
+        );
+        std::thread::spawn(move || {
+            for record in events {
+                recorder.record(&record);
+            }
+        });
+
+        Ok(OtelExporter {
+            tracer_provider,
+            meter_provider,
+        })
+    }
+
+    pub fn shutdown(&self) {
+        if let Err(e) = self.tracer_provider.shutdown() {
+            log::warn!("Failed to flush OTLP spans: {}", e);
+        }
+        if let Err(e) = self.meter_provider.shutdown() {
+            log::warn!("Failed to flush OTLP metrics: {}", e);
+        }
+    }
+}
+
+/// Turns the queue's record updates into one span per task plus metrics.
+struct TaskRecorder {
+    tracer: SdkTracer,
+    spans: HashMap<TaskId, (TaskStatus, <SdkTracer as Tracer>::Span)>,
+    tasks: UpDownCounter<i64>,
+    finished: Counter<u64>,
+    duration: Histogram<f64>,
+}
+
+impl TaskRecorder {
+    fn new(tracer: SdkTracer, meter_provider: &SdkMeterProvider) -> Self {
+        let meter = meter_provider.meter(INSTRUMENTATION_NAME);
+        TaskRecorder {
+            tracer,
+            spans: HashMap::new(),
+            tasks: meter
+                .i64_up_down_counter("taskqueue.tasks")
+                .with_description("Tasks currently in the queue, by status")
+                .build(),
+            finished: meter
+                .u64_counter("taskqueue.tasks.finished")
+                .with_description("Tasks that completed or were cancelled")
+                .build(),
+            duration: meter
+                .f64_histogram("taskqueue.task.duration")
+                .with_description("Time from start to finish of a task")
+                .with_unit("s")
+                .build(),
+        }
+    }
+
+    fn record(&mut self, record: &TaskRecord) {
+        let kind = KeyValue::new("task.kind", record.kind.clone());
+        let (previous, mut span) = match self.spans.remove(&record.id) {
+            Some((previous, span)) => (Some(previous), span),
+            None => (None, self.start_span(record)),
+        };
+        if previous.as_ref() != Some(&record.status) {
+            if let Some(previous) = &previous {
+                self.tasks
+                    .add(-1, &[kind.clone(), status_attribute(previous)]);
+            }
+            if !record.status.is_terminal() {
+                self.tasks
+                    .add(1, &[kind.clone(), status_attribute(&record.status)]);
+            }
+        }
+        span.add_event(record.status.to_string(), Vec::new());
+
+        if !record.status.is_terminal() {
+            self.spans.insert(record.id, (record.status.clone(), span));
+            return;
+        }
+
+        let attributes = [kind, status_attribute(&record.status)];
+        self.finished.add(1, &attributes);
+        if let (Some(started), Some(finished)) = (record.started_at, record.finished_at) {
+            self.duration.record(
+                finished.saturating_sub(started) as f64 / 1000.0,
+                &attributes,
+            );
+        }
+        match (&record.status, &record.error) {
+            (TaskStatus::Failed, Some(error)) => span.set_status(Status::error(error.clone())),
+            (TaskStatus::Cancelled | TaskStatus::Failed, _) => {
+                span.set_status(Status::error(record.status.to_string()))
+            }
+            _ => span.set_status(Status::Ok),
+        }
+        match record.finished_at {
+            Some(finished) => span.end_with_timestamp(system_time(finished)),
+            None => span.end(),
+        }
+    }
+
+    fn start_span(&self, record: &TaskRecord) -> <SdkTracer as Tracer>::Span {
+        self.tracer
+            .span_builder(format!("task {}", record.kind))
+            .with_kind(SpanKind::Internal)
+            .with_start_time(system_time(record.created_at))
+            .with_attributes([
+                KeyValue::new("task.kind", record.kind.clone()),
+                KeyValue::new("task.id", record.id.index() as i64),
+            ])
+            .start(&self.tracer)
+    }
+}
+
+fn status_attribute(status: &TaskStatus) -> KeyValue {
+    KeyValue::new("task.status", status.to_string())
+}
+
+fn system_time(millis: u64) -> SystemTime {
+    UNIX_EPOCH + Duration::from_millis(millis)
+}
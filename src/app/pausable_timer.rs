@@ -0,0 +1,118 @@
+//! A countdown whose clock only runs while it is not paused, for tasks whose work is
+//! waiting: a plain `sleep(duration)` keeps counting through a pause and then finishes
+//! in the background.
+
+use std::sync::Mutex as sync_Mutex;
+use std::time::Duration;
+
+use crate::app::executor::{self, Instant};
+
+/// Longest [`PausableTimer::wait`] sleeps before checking whether the timer was paused
+/// or cancelled.
+const TICK: Duration = Duration::from_millis(50);
+
+/// Shared between a task, which pauses, resumes and cancels it, and the job waiting on
+/// it. Starts paused, with its clock at zero.
+#[derive(Debug)]
+pub struct PausableTimer {
+    duration: Duration,
+    state: sync_Mutex<TimerState>,
+}
+
+#[derive(Debug, Default)]
+struct TimerState {
+    /// Time run before the current stretch.
+    banked: Duration,
+    /// Start of the current stretch; `None` while paused.
+    running_since: Option<Instant>,
+    cancelled: bool,
+}
+
+impl TimerState {
+    fn elapsed(&self) -> Duration {
+        self.banked
+            + self
+                .running_since
+                .map_or(Duration::ZERO, |since| since.elapsed())
+    }
+}
+
+impl PausableTimer {
+    pub fn new(duration: Duration) -> Self {
+        PausableTimer {
+            duration,
+            state: sync_Mutex::new(TimerState::default()),
+        }
+    }
+
+    pub fn duration(&self) -> Duration {
+        self.duration
+    }
+
+    /// Starts the clock, or restarts it after a pause. Does nothing if it is running.
+    pub fn resume(&self) {
+        let mut state = self.state.lock().unwrap();
+        if state.running_since.is_none() && !state.cancelled {
+            state.running_since = Some(Instant::now());
+        }
+    }
+
+    /// Stops the clock, keeping the time run so far.
+    pub fn pause(&self) {
+        let mut state = self.state.lock().unwrap();
+        if let Some(since) = state.running_since.take() {
+            state.banked += since.elapsed();
+        }
+    }
+
+    /// Stops the clock for good and makes [`wait`](Self::wait) return early.
+    pub fn cancel(&self) {
+        self.pause();
+        self.state.lock().unwrap().cancelled = true;
+    }
+
+    /// Time run so far, at most the duration.
+    pub fn elapsed(&self) -> Duration {
+        self.state.lock().unwrap().elapsed().min(self.duration)
+    }
+
+    pub fn remaining(&self) -> Duration {
+        self.duration - self.elapsed()
+    }
+
+    /// Whether the clock has run the whole duration.
+    pub fn is_finished(&self) -> bool {
+        self.remaining().is_zero()
+    }
+
+    /// The fraction of the duration run so far, from 0 to 1.
+    pub fn progress(&self) -> f32 {
+        if self.duration.is_zero() {
+            return 1.0;
+        }
+        self.elapsed().as_secs_f32() / self.duration.as_secs_f32()
+    }
+
+    /// Sleeps until the clock has run the whole duration, in steps short enough that
+    /// pauses are waited out rather than slept through. Returns false if the timer was
+    /// cancelled first.
+    pub async fn wait(&self) -> bool {
+        loop {
+            let (remaining, running) = {
+                let state = self.state.lock().unwrap();
+                if state.cancelled {
+                    return false;
+                }
+                let elapsed = state.elapsed();
+                (
+                    self.duration.saturating_sub(elapsed),
+                    state.running_since.is_some(),
+                )
+            };
+            if remaining.is_zero() {
+                return true;
+            }
+            executor::sleep(if running { remaining.min(TICK) } else { TICK }).await;
+        }
+    }
+}
@@ -0,0 +1,94 @@
+#[cfg(test)]
+use std::sync::Arc;
+#[cfg(test)]
+use std::time::{Duration, Instant};
+
+#[cfg(test)]
+use crate::app::executor;
+#[cfg(test)]
+use crate::app::pausable_timer::PausableTimer;
+#[cfg(test)]
+use crate::app::sleep_task::SleepTask;
+#[cfg(test)]
+use crate::app::task_id::TaskId;
+#[cfg(test)]
+use crate::app::task_queue::{PollResult, PollingData, Task};
+
+#[cfg(test)]
+fn after(delay: Duration, f: impl FnOnce() + Send + 'static) {
+    std::thread::spawn(move || {
+        std::thread::sleep(delay);
+        f();
+    });
+}
+
+/// Waits on the executor, whose timer `wait` needs with either runtime.
+#[cfg(test)]
+fn wait(timer: Arc<PausableTimer>) -> bool {
+    let (sender, receiver) = std::sync::mpsc::channel();
+    let _job = executor::submit(async move {
+        let _ = sender.send(timer.wait().await);
+    });
+    receiver.recv().unwrap()
+}
+
+#[test]
+fn test_clock_only_runs_while_resumed() {
+    let timer = PausableTimer::new(Duration::from_secs(10));
+    std::thread::sleep(Duration::from_millis(20));
+    assert_eq!(timer.elapsed(), Duration::ZERO);
+
+    timer.resume();
+    std::thread::sleep(Duration::from_millis(20));
+    timer.pause();
+    let paused_at = timer.elapsed();
+    assert!(paused_at >= Duration::from_millis(20));
+    std::thread::sleep(Duration::from_millis(20));
+    assert_eq!(timer.elapsed(), paused_at);
+    assert!(!timer.is_finished());
+}
+
+#[test]
+fn test_wait_sits_out_pauses() {
+    let timer = Arc::new(PausableTimer::new(Duration::from_millis(50)));
+    let resumed = timer.clone();
+    after(Duration::from_millis(150), move || resumed.resume());
+    let started = Instant::now();
+    assert!(wait(timer.clone()));
+    assert!(started.elapsed() >= Duration::from_millis(200));
+    assert!(timer.is_finished());
+    assert_eq!(timer.progress(), 1.0);
+}
+
+#[test]
+fn test_wait_ends_on_cancel() {
+    let timer = Arc::new(PausableTimer::new(Duration::from_secs(60)));
+    timer.resume();
+    let cancelled = timer.clone();
+    after(Duration::from_millis(50), move || cancelled.cancel());
+    assert!(!wait(timer.clone()));
+    assert!(!timer.is_finished());
+}
+
+#[test]
+fn test_paused_sleep_task_does_not_finish() {
+    let mut task = SleepTask::new(Some(TaskId::detached(0)), Duration::from_millis(200));
+    task.poll();
+    let deadline = Instant::now() + Duration::from_secs(2);
+    while task.poll() == PollResult::Pending(PollingData::Float(0.0)) {
+        assert!(Instant::now() < deadline, "task did not start");
+        std::thread::sleep(Duration::from_millis(5));
+    }
+    task.pause().unwrap();
+    std::thread::sleep(Duration::from_millis(400));
+    let PollResult::Paused(PollingData::Float(progress)) = task.poll() else {
+        panic!("expected the task to stay paused");
+    };
+    assert!(progress < 1.0);
+
+    task.resume().unwrap();
+    while task.poll() != PollResult::Completed {
+        assert!(Instant::now() < deadline, "task did not finish");
+        std::thread::sleep(Duration::from_millis(10));
+    }
+}
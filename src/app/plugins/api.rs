@@ -0,0 +1,49 @@
+//! C ABI shared between the app and task-kind plugins.
+//!
+//! A plugin is a `cdylib` exporting [`DESCRIPTOR_SYMBOL`] as an
+//! `extern "C" fn() -> *const PluginDescriptor` returning a pointer that stays valid for as
+//! long as the library is loaded. See `examples/countdown_plugin.rs` for a minimal plugin.
+
+use std::ffi::c_void;
+use std::os::raw::c_char;
+
+pub use crate::app::plugins::PLUGIN_ABI_VERSION;
+
+/// Name of the exported descriptor function, including the trailing nul.
+pub const DESCRIPTOR_SYMBOL: &[u8] = b"task_queue_plugin_descriptor\0";
+
+pub type DescriptorFn = unsafe extern "C" fn() -> *const PluginDescriptor;
+
+/// Callbacks the app hands to a running plugin. `ctx` must be passed back unchanged.
+#[repr(C)]
+pub struct PluginHost {
+    pub ctx: *mut c_void,
+    /// Reports progress in `0.0..=1.0`.
+    pub report_progress: extern "C" fn(ctx: *mut c_void, progress: f32),
+    /// Blocks while the task is paused. Returns non-zero once the task has been cancelled,
+    /// after which the plugin should return from `run` promptly.
+    pub checkpoint: extern "C" fn(ctx: *mut c_void) -> i32,
+}
+
+#[repr(C)]
+pub struct PluginDescriptor {
+    /// Must equal [`PLUGIN_ABI_VERSION`]; mismatching plugins are skipped.
+    pub abi_version: u32,
+    /// Nul-terminated UTF-8 task kind name, shown in the New Task window.
+    pub name: *const c_char,
+    /// Nul-terminated JSON array of parameter specs,
+    /// e.g. `[{"name": "steps", "type": "number", "default": "10", "min": 1}]`. Besides a
+    /// name and a type, a spec may give a `default`, a `description`, whether it is
+    /// `required`, a `min` and `max` for numbers, the `choices` a string may take and
+    /// whether it is an `output` path.
+    pub param_schema: *const c_char,
+    /// Runs one task to completion on a blocking thread. `params` is a nul-terminated JSON object
+    /// of string values. Returns 0 on success; on failure returns non-zero and may write a
+    /// nul-terminated message of at most `error_len` bytes into `error`.
+    pub run: unsafe extern "C" fn(
+        host: *const PluginHost,
+        params: *const c_char,
+        error: *mut c_char,
+        error_len: usize,
+    ) -> i32,
+}
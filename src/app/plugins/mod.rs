@@ -0,0 +1,64 @@
+use std::fmt::{Display, Formatter, Result as FmtResult};
+use std::path::{Path, PathBuf};
+
+use log::debug;
+
+#[cfg(feature = "plugins")]
+pub mod api;
+#[cfg(feature = "plugins")]
+pub mod native;
+#[cfg(feature = "wasm-plugins")]
+pub mod wasm;
+
+#[cfg(feature = "plugins")]
+pub use native::load_plugins;
+
+/// Bumped whenever the native descriptor layout or the WASM host interface changes;
+/// plugins built against another version are skipped.
+pub const PLUGIN_ABI_VERSION: u32 = 1;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum PluginError {
+    Load { path: PathBuf, message: String },
+    AbiMismatch { path: PathBuf, version: u32 },
+    InvalidDescriptor { path: PathBuf, message: String },
+}
+
+impl Display for PluginError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        match self {
+            PluginError::Load { path, message } => {
+                write!(f, "Failed to load plugin {}: {}", path.display(), message)
+            }
+            PluginError::AbiMismatch { path, version } => write!(
+                f,
+                "Plugin {} uses ABI version {}, expected {}",
+                path.display(),
+                version,
+                PLUGIN_ABI_VERSION
+            ),
+            PluginError::InvalidDescriptor { path, message } => {
+                write!(
+                    f,
+                    "Plugin {} has an invalid descriptor: {}",
+                    path.display(),
+                    message
+                )
+            }
+        }
+    }
+}
+
+/// Files in `dir` with the given extension. A missing directory yields no files.
+fn plugin_files(dir: &Path, extension: &str) -> Vec<PathBuf> {
+    match std::fs::read_dir(dir) {
+        Ok(entries) => entries
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| path.extension().and_then(|ext| ext.to_str()) == Some(extension))
+            .collect(),
+        Err(_) => {
+            debug!("No plugin directory at {}", dir.display());
+            Vec::new()
+        }
+    }
+}
@@ -0,0 +1,149 @@
+use std::any::Any;
+use std::ffi::{c_void, CStr, CString};
+use std::os::raw::c_char;
+use std::path::Path;
+use std::sync::Arc as sync_Arc;
+
+use log::info;
+
+use crate::app::job_task::{JobContext, JobTask};
+use crate::app::plugins::api::{DescriptorFn, PluginDescriptor, PluginHost, DESCRIPTOR_SYMBOL};
+use crate::app::plugins::{plugin_files, PluginError, PLUGIN_ABI_VERSION};
+use crate::app::registry::{ParamSpec, TaskKindRegistry};
+use crate::app::task_queue::TaskKind;
+
+const ERROR_BUFFER_LEN: usize = 1024;
+
+/// Loads every dynamic library in `dir` and registers the task kind each one describes.
+/// A missing directory is not an error. Returns the errors of plugins that were skipped.
+pub fn load_plugins(dir: &Path, registry: &mut TaskKindRegistry) -> Vec<PluginError> {
+    let mut errors = Vec::new();
+    for path in plugin_files(dir, std::env::consts::DLL_EXTENSION) {
+        if let Err(e) = load_plugin(&path, registry) {
+            log::error!("{}", e);
+            errors.push(e);
+        }
+    }
+    errors
+}
+
+fn load_plugin(path: &Path, registry: &mut TaskKindRegistry) -> Result<(), PluginError> {
+    let load_error = |e: libloading::Error| PluginError::Load {
+        path: path.to_owned(),
+        message: e.to_string(),
+    };
+    // SAFETY: loading a library runs its initialisers; plugins in the plugin directory are
+    // trusted by the user who put them there.
+    let library = unsafe { libloading::Library::new(path) }.map_err(load_error)?;
+    let descriptor = unsafe {
+        let descriptor_fn = library
+            .get::<DescriptorFn>(DESCRIPTOR_SYMBOL)
+            .map_err(load_error)?;
+        descriptor_fn()
+    };
+    // SAFETY: the descriptor is valid for as long as `library` is loaded, which the registered
+    // factory guarantees by holding on to it.
+    unsafe { register_descriptor(path, descriptor, registry, sync_Arc::new(library)) }
+}
+
+/// Registers the task kind described by `descriptor`.
+///
+/// # Safety
+/// `descriptor` must be null or point to a [`PluginDescriptor`] whose strings and `run` function
+/// stay valid for as long as `keep_alive` is alive.
+pub unsafe fn register_descriptor(
+    path: &Path,
+    descriptor: *const PluginDescriptor,
+    registry: &mut TaskKindRegistry,
+    keep_alive: sync_Arc<dyn Any + Send + Sync>,
+) -> Result<(), PluginError> {
+    let invalid = |message: &str| PluginError::InvalidDescriptor {
+        path: path.to_owned(),
+        message: message.to_owned(),
+    };
+    let descriptor = descriptor
+        .as_ref()
+        .ok_or_else(|| invalid("null descriptor"))?;
+    if descriptor.abi_version != PLUGIN_ABI_VERSION {
+        return Err(PluginError::AbiMismatch {
+            path: path.to_owned(),
+            version: descriptor.abi_version,
+        });
+    }
+    if descriptor.name.is_null() || descriptor.param_schema.is_null() {
+        return Err(invalid("null name or parameter schema"));
+    }
+    let name = CStr::from_ptr(descriptor.name)
+        .to_str()
+        .map_err(|_| invalid("name is not UTF-8"))?
+        .to_owned();
+    let schema = CStr::from_ptr(descriptor.param_schema)
+        .to_str()
+        .map_err(|_| invalid("parameter schema is not UTF-8"))?;
+    let params: Vec<ParamSpec> =
+        serde_json::from_str(schema).map_err(|e| invalid(&e.to_string()))?;
+
+    let run = descriptor.run;
+    let kind_name = name.clone();
+    registry.register(
+        &name,
+        params,
+        Box::new(move |params| {
+            let params = serde_json::to_string(params).unwrap_or_else(|_| "{}".to_owned());
+            let keep_alive = keep_alive.clone();
+            Ok(Box::new(JobTask::new(
+                TaskKind::Plugin(kind_name.clone()),
+                Box::new(move |context| {
+                    let _library = keep_alive;
+                    run_plugin(run, context, &params)
+                }),
+            )))
+        }),
+    );
+    info!(
+        "Registered plugin task kind '{}' from {}",
+        name,
+        path.display()
+    );
+    Ok(())
+}
+
+fn run_plugin(
+    run: unsafe extern "C" fn(*const PluginHost, *const c_char, *mut c_char, usize) -> i32,
+    context: &JobContext,
+    params: &str,
+) -> Result<(), String> {
+    let params = CString::new(params).map_err(|e| e.to_string())?;
+    let host = PluginHost {
+        ctx: context as *const JobContext as *mut c_void,
+        report_progress,
+        checkpoint,
+    };
+    let mut error = vec![0 as c_char; ERROR_BUFFER_LEN];
+    // SAFETY: `host`, `params` and `error` outlive the call, and `error` is `ERROR_BUFFER_LEN`
+    // bytes long as advertised.
+    let code = unsafe { run(&host, params.as_ptr(), error.as_mut_ptr(), ERROR_BUFFER_LEN) };
+    if code == 0 {
+        return Ok(());
+    }
+    error[ERROR_BUFFER_LEN - 1] = 0;
+    // SAFETY: the buffer is nul-terminated by the line above.
+    let message = unsafe { CStr::from_ptr(error.as_ptr()) }.to_string_lossy();
+    if message.is_empty() {
+        Err(format!("plugin returned error code {}", code))
+    } else {
+        Err(message.into_owned())
+    }
+}
+
+extern "C" fn report_progress(ctx: *mut c_void, progress: f32) {
+    // SAFETY: `ctx` is the `JobContext` pointer set up by `run_plugin`, alive for the whole call.
+    let context = unsafe { &*(ctx as *const JobContext) };
+    context.set_progress(progress);
+}
+
+extern "C" fn checkpoint(ctx: *mut c_void) -> i32 {
+    // SAFETY: see `report_progress`.
+    let context = unsafe { &*(ctx as *const JobContext) };
+    i32::from(!context.checkpoint())
+}
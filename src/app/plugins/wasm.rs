@@ -0,0 +1,400 @@
+//! WASM task-kind plugins, run in a wasmtime sandbox.
+//!
+//! A plugin is a `.wasm` module in the plugins directory. It gets no WASI; everything it can do
+//! goes through the `host` imports below. Strings and buffers are `(ptr, len)` pairs in the
+//! module's exported `memory`, and "packed" values are `(ptr << 32) | len` in an `i64`.
+//!
+//! Exports:
+//! - `plugin_abi_version() -> i32`, which must equal [`PLUGIN_ABI_VERSION`]
+//! - `plugin_name() -> i64`, packed UTF-8 task kind name
+//! - `plugin_param_schema() -> i64`, packed JSON parameter specs, as for native plugins
+//! - `alloc(len: i32) -> i32`, returning a buffer the host writes the parameters into
+//! - `run(params_ptr: i32, params_len: i32) -> i32`, 0 on success
+//!
+//! Imports from `host`:
+//! - `progress(value: f32)`
+//! - `checkpoint() -> i32`, blocking while paused and returning 1 once cancelled
+//! - `set_error(ptr, len)`, the message reported when `run` returns non-zero
+//! - `temp_write(name_ptr, name_len, data_ptr, data_len) -> i32` and
+//!   `temp_read(name_ptr, name_len, buf_ptr, buf_len) -> i32`, for files in a private per-task
+//!   directory that is deleted when the task ends. Names may only contain `[A-Za-z0-9._-]`.
+//! - `http_get(url_ptr, url_len, buf_ptr, buf_len) -> i32`, allowed only for hosts in the
+//!   configured allow-list. Returns the number of body bytes written (truncated to `buf_len`).
+//!
+//! Host calls return `-1` for a refused request (bad name, host not allowed) and `-2` for a
+//! failed one (I/O or HTTP error).
+
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc as sync_Arc;
+
+use log::info;
+use wasmtime::{
+    Caller, Engine, Extern, Linker, Memory, Module, Store, StoreLimits, StoreLimitsBuilder,
+};
+
+use crate::app::job_task::{JobContext, JobTask};
+use crate::app::plugins::{plugin_files, PluginError, PLUGIN_ABI_VERSION};
+use crate::app::registry::{ParamSpec, TaskKindRegistry};
+use crate::app::task_queue::TaskKind;
+
+const MAX_MEMORY_BYTES: usize = 256 * 1024 * 1024;
+const REFUSED: i32 = -1;
+const FAILED: i32 = -2;
+
+static NEXT_TEMP_DIR: AtomicUsize = AtomicUsize::new(0);
+
+struct HostState {
+    context: Option<JobContext>,
+    limits: StoreLimits,
+    http_allow_list: sync_Arc<Vec<String>>,
+    temp_dir: Option<PathBuf>,
+    error: Option<String>,
+}
+
+impl HostState {
+    fn new(context: Option<JobContext>, http_allow_list: sync_Arc<Vec<String>>) -> Self {
+        HostState {
+            context,
+            limits: StoreLimitsBuilder::new()
+                .memory_size(MAX_MEMORY_BYTES)
+                .build(),
+            http_allow_list,
+            temp_dir: None,
+            error: None,
+        }
+    }
+
+    fn temp_dir(&mut self) -> std::io::Result<&Path> {
+        if self.temp_dir.is_none() {
+            let dir = std::env::temp_dir().join(format!(
+                "task_queue_wasm_{}_{}",
+                std::process::id(),
+                NEXT_TEMP_DIR.fetch_add(1, Ordering::SeqCst)
+            ));
+            std::fs::create_dir_all(&dir)?;
+            self.temp_dir = Some(dir);
+        }
+        Ok(self.temp_dir.as_deref().unwrap())
+    }
+}
+
+impl Drop for HostState {
+    fn drop(&mut self) {
+        if let Some(dir) = &self.temp_dir {
+            let _ = std::fs::remove_dir_all(dir);
+        }
+    }
+}
+
+/// Compiles every `.wasm` module in `dir` and registers the task kind each one describes.
+/// `http_allow_list` holds the host names plugins may fetch from.
+pub fn load_wasm_plugins(
+    dir: &Path,
+    registry: &mut TaskKindRegistry,
+    http_allow_list: &[String],
+) -> Vec<PluginError> {
+    let engine = Engine::default();
+    let linker = match host_linker(&engine) {
+        Ok(linker) => sync_Arc::new(linker),
+        Err(e) => {
+            log::error!("Failed to set up WASM host functions: {}", e);
+            return Vec::new();
+        }
+    };
+    let http_allow_list = sync_Arc::new(http_allow_list.to_vec());
+    let mut errors = Vec::new();
+    for path in plugin_files(dir, "wasm") {
+        let loaded = Module::from_file(&engine, &path)
+            .map_err(|e| PluginError::Load {
+                path: path.clone(),
+                message: e.to_string(),
+            })
+            .and_then(|module| register_module(&path, module, &linker, &http_allow_list, registry));
+        if let Err(e) = loaded {
+            log::error!("{}", e);
+            errors.push(e);
+        }
+    }
+    errors
+}
+
+fn register_module(
+    path: &Path,
+    module: Module,
+    linker: &sync_Arc<Linker<HostState>>,
+    http_allow_list: &sync_Arc<Vec<String>>,
+    registry: &mut TaskKindRegistry,
+) -> Result<(), PluginError> {
+    let invalid = |message: String| PluginError::InvalidDescriptor {
+        path: path.to_owned(),
+        message,
+    };
+    let mut store = Store::new(
+        module.engine(),
+        HostState::new(None, http_allow_list.clone()),
+    );
+    let instance = linker
+        .instantiate(&mut store, &module)
+        .map_err(|e| invalid(e.to_string()))?;
+    let version = instance
+        .get_typed_func::<(), i32>(&mut store, "plugin_abi_version")
+        .and_then(|f| f.call(&mut store, ()))
+        .map_err(|e| invalid(e.to_string()))?;
+    if version as u32 != PLUGIN_ABI_VERSION {
+        return Err(PluginError::AbiMismatch {
+            path: path.to_owned(),
+            version: version as u32,
+        });
+    }
+    let memory = instance
+        .get_memory(&mut store, "memory")
+        .ok_or_else(|| invalid("no exported memory".to_owned()))?;
+    let mut read_packed = |export: &str| -> Result<String, PluginError> {
+        let packed = instance
+            .get_typed_func::<(), i64>(&mut store, export)
+            .and_then(|f| f.call(&mut store, ()))
+            .map_err(|e| invalid(e.to_string()))?;
+        let bytes = read_memory(&memory, &store, (packed >> 32) as i32, packed as i32)
+            .map_err(|e| invalid(e.to_string()))?;
+        String::from_utf8(bytes).map_err(|_| invalid(format!("{} is not UTF-8", export)))
+    };
+    let name = read_packed("plugin_name")?;
+    let schema = read_packed("plugin_param_schema")?;
+    let params: Vec<ParamSpec> =
+        serde_json::from_str(&schema).map_err(|e| invalid(e.to_string()))?;
+
+    let linker = linker.clone();
+    let http_allow_list = http_allow_list.clone();
+    let kind_name = name.clone();
+    registry.register(
+        &name,
+        params,
+        Box::new(move |params| {
+            let params = serde_json::to_string(params).unwrap_or_else(|_| "{}".to_owned());
+            let module = module.clone();
+            let linker = linker.clone();
+            let http_allow_list = http_allow_list.clone();
+            Ok(Box::new(
+                JobTask::new(
+                    TaskKind::Plugin(kind_name.clone()),
+                    Box::new(move |context| {
+                        run_module(&module, &linker, http_allow_list, context, &params)
+                    }),
+                )
+                .cpu_bound(),
+            ))
+        }),
+    );
+    info!(
+        "Registered WASM task kind '{}' from {}",
+        name,
+        path.display()
+    );
+    Ok(())
+}
+
+fn run_module(
+    module: &Module,
+    linker: &Linker<HostState>,
+    http_allow_list: sync_Arc<Vec<String>>,
+    context: &JobContext,
+    params: &str,
+) -> Result<(), String> {
+    let mut store = Store::new(
+        module.engine(),
+        HostState::new(Some(context.clone()), http_allow_list),
+    );
+    store.limiter(|state| &mut state.limits);
+    let instance = linker
+        .instantiate(&mut store, module)
+        .map_err(|e| e.to_string())?;
+    let memory = instance
+        .get_memory(&mut store, "memory")
+        .ok_or("no exported memory")?;
+    let alloc = instance
+        .get_typed_func::<i32, i32>(&mut store, "alloc")
+        .map_err(|e| e.to_string())?;
+    let run = instance
+        .get_typed_func::<(i32, i32), i32>(&mut store, "run")
+        .map_err(|e| e.to_string())?;
+    let params_ptr = alloc
+        .call(&mut store, params.len() as i32)
+        .map_err(|e| e.to_string())?;
+    memory
+        .write(&mut store, params_ptr as usize, params.as_bytes())
+        .map_err(|e| e.to_string())?;
+    let code = run
+        .call(&mut store, (params_ptr, params.len() as i32))
+        .map_err(|e| e.to_string())?;
+    if code == 0 {
+        Ok(())
+    } else {
+        Err(store
+            .data_mut()
+            .error
+            .take()
+            .unwrap_or_else(|| format!("plugin returned error code {}", code)))
+    }
+}
+
+fn host_linker(engine: &Engine) -> wasmtime::Result<Linker<HostState>> {
+    let mut linker = Linker::new(engine);
+    linker.func_wrap(
+        "host",
+        "progress",
+        |caller: Caller<'_, HostState>, value: f32| {
+            if let Some(context) = &caller.data().context {
+                context.set_progress(value);
+            }
+        },
+    )?;
+    linker.func_wrap(
+        "host",
+        "checkpoint",
+        |caller: Caller<'_, HostState>| match &caller.data().context {
+            Some(context) => i32::from(!context.checkpoint()),
+            None => 0,
+        },
+    )?;
+    linker.func_wrap(
+        "host",
+        "set_error",
+        |mut caller: Caller<'_, HostState>, ptr: i32, len: i32| -> wasmtime::Result<()> {
+            let message = read_caller(&mut caller, ptr, len)?;
+            caller.data_mut().error = Some(String::from_utf8_lossy(&message).into_owned());
+            Ok(())
+        },
+    )?;
+    linker.func_wrap(
+        "host",
+        "temp_write",
+        |mut caller: Caller<'_, HostState>,
+         name_ptr: i32,
+         name_len: i32,
+         data_ptr: i32,
+         data_len: i32|
+         -> wasmtime::Result<i32> {
+            let name = read_caller(&mut caller, name_ptr, name_len)?;
+            let data = read_caller(&mut caller, data_ptr, data_len)?;
+            let path = match temp_path(caller.data_mut(), &name) {
+                Some(Ok(path)) => path,
+                Some(Err(_)) => return Ok(FAILED),
+                None => return Ok(REFUSED),
+            };
+            Ok(std::fs::write(path, data).map(|_| 0).unwrap_or(FAILED))
+        },
+    )?;
+    linker.func_wrap(
+        "host",
+        "temp_read",
+        |mut caller: Caller<'_, HostState>,
+         name_ptr: i32,
+         name_len: i32,
+         buf_ptr: i32,
+         buf_len: i32|
+         -> wasmtime::Result<i32> {
+            let name = read_caller(&mut caller, name_ptr, name_len)?;
+            let path = match temp_path(caller.data_mut(), &name) {
+                Some(Ok(path)) => path,
+                Some(Err(_)) => return Ok(FAILED),
+                None => return Ok(REFUSED),
+            };
+            match std::fs::read(path) {
+                Ok(data) => write_caller(&mut caller, buf_ptr, buf_len, &data),
+                Err(_) => Ok(FAILED),
+            }
+        },
+    )?;
+    linker.func_wrap(
+        "host",
+        "http_get",
+        |mut caller: Caller<'_, HostState>,
+         url_ptr: i32,
+         url_len: i32,
+         buf_ptr: i32,
+         buf_len: i32|
+         -> wasmtime::Result<i32> {
+            let url = read_caller(&mut caller, url_ptr, url_len)?;
+            let url = match std::str::from_utf8(&url)
+                .ok()
+                .and_then(|url| reqwest::Url::parse(url).ok())
+            {
+                Some(url) => url,
+                None => return Ok(REFUSED),
+            };
+            let allowed = url.host_str().map_or(false, |host| {
+                caller.data().http_allow_list.iter().any(|a| a == host)
+            });
+            if !allowed {
+                log::warn!("WASM plugin tried to fetch {} (not in allow-list)", url);
+                return Ok(REFUSED);
+            }
+            let body = reqwest::blocking::get(url)
+                .and_then(|response| response.error_for_status())
+                .and_then(|response| response.bytes());
+            match body {
+                Ok(body) => write_caller(&mut caller, buf_ptr, buf_len, &body),
+                Err(e) => {
+                    log::warn!("WASM plugin HTTP request failed: {}", e);
+                    Ok(FAILED)
+                }
+            }
+        },
+    )?;
+    Ok(linker)
+}
+
+/// Resolves `name` inside the task's private temp dir. `None` if the name is not allowed.
+fn temp_path(state: &mut HostState, name: &[u8]) -> Option<std::io::Result<PathBuf>> {
+    let name = std::str::from_utf8(name).ok()?;
+    let valid = !name.is_empty()
+        && !name.starts_with('.')
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '_' | '-'));
+    if !valid {
+        return None;
+    }
+    Some(state.temp_dir().map(|dir| dir.join(name)))
+}
+
+fn caller_memory(caller: &mut Caller<'_, HostState>) -> wasmtime::Result<Memory> {
+    match caller.get_export("memory") {
+        Some(Extern::Memory(memory)) => Ok(memory),
+        _ => Err(wasmtime::Error::msg("no exported memory")),
+    }
+}
+
+fn read_memory(
+    memory: &Memory,
+    store: impl wasmtime::AsContext,
+    ptr: i32,
+    len: i32,
+) -> wasmtime::Result<Vec<u8>> {
+    let mut buf = vec![0; len.max(0) as usize];
+    memory.read(store, ptr as u32 as usize, &mut buf)?;
+    Ok(buf)
+}
+
+fn read_caller(
+    caller: &mut Caller<'_, HostState>,
+    ptr: i32,
+    len: i32,
+) -> wasmtime::Result<Vec<u8>> {
+    let memory = caller_memory(caller)?;
+    read_memory(&memory, &*caller, ptr, len)
+}
+
+/// Copies as much of `data` as fits into the guest buffer, returning the number of bytes written.
+fn write_caller(
+    caller: &mut Caller<'_, HostState>,
+    ptr: i32,
+    len: i32,
+    data: &[u8],
+) -> wasmtime::Result<i32> {
+    let memory = caller_memory(caller)?;
+    let n = data.len().min(len.max(0) as usize);
+    memory.write(&mut *caller, ptr as u32 as usize, &data[..n])?;
+    Ok(n as i32)
+}
@@ -0,0 +1,89 @@
+#[cfg(test)]
+use std::os::raw::c_char;
+#[cfg(test)]
+use std::path::Path;
+#[cfg(test)]
+use std::sync::Arc;
+
+#[cfg(test)]
+use crate::app::plugins::api::{PluginDescriptor, PluginHost, PLUGIN_ABI_VERSION};
+#[cfg(test)]
+use crate::app::plugins::native::register_descriptor;
+#[cfg(test)]
+use crate::app::plugins::PluginError;
+#[cfg(test)]
+use crate::app::registry::{TaskKindRegistry, TaskParams};
+#[cfg(test)]
+use crate::app::task_queue::{PollResult, TaskKind, TaskQueue};
+
+#[cfg(test)]
+unsafe extern "C" fn run_half_then_done(
+    host: *const PluginHost,
+    _params: *const c_char,
+    _error: *mut c_char,
+    _error_len: usize,
+) -> i32 {
+    let host = &*host;
+    (host.report_progress)(host.ctx, 0.5);
+    if (host.checkpoint)(host.ctx) != 0 {
+        return 0;
+    }
+    0
+}
+
+#[cfg(test)]
+fn descriptor(abi_version: u32) -> PluginDescriptor {
+    PluginDescriptor {
+        abi_version,
+        name: b"half\0".as_ptr() as *const c_char,
+        param_schema: b"[{\"name\": \"n\", \"type\": \"number\", \"default\": \"1\"}]\0".as_ptr()
+            as *const c_char,
+        run: run_half_then_done,
+    }
+}
+
+#[test]
+fn test_register_and_run_plugin_task() {
+    let descriptor = descriptor(PLUGIN_ABI_VERSION);
+    let mut registry = TaskKindRegistry::default();
+    unsafe {
+        register_descriptor(
+            Path::new("in-process"),
+            &descriptor,
+            &mut registry,
+            Arc::new(()),
+        )
+        .unwrap();
+    }
+    assert_eq!(registry.get("half").unwrap().params[0].name, "n");
+
+    let task_queue = TaskQueue::new();
+    let task = registry.create("half", &TaskParams::new()).unwrap();
+    assert_eq!(task.kind(), TaskKind::Plugin("half".to_owned()));
+    let task_id = task_queue.add_task(task);
+    let mut result = task_queue.poll_task(task_id).unwrap();
+    for _ in 0..100 {
+        if result == PollResult::Completed {
+            break;
+        }
+        std::thread::sleep(std::time::Duration::from_millis(10));
+        result = task_queue.poll_task(task_id).unwrap();
+    }
+    assert_eq!(result, PollResult::Completed);
+}
+
+#[test]
+fn test_abi_mismatch_rejected() {
+    let descriptor = descriptor(PLUGIN_ABI_VERSION + 1);
+    let mut registry = TaskKindRegistry::default();
+    let result = unsafe {
+        register_descriptor(
+            Path::new("old.so"),
+            &descriptor,
+            &mut registry,
+            Arc::new(()),
+        )
+    };
+    assert!(matches!(result, Err(PluginError::AbiMismatch { .. })));
+    assert!(registry.get("half").is_none());
+}
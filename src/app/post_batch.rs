@@ -0,0 +1,237 @@
+//! What to do once every task in a batch has finished, chosen when the batch is added
+//! from a job file or a CSV import.
+
+use std::collections::{BTreeMap, HashSet};
+use std::fmt::{Display, Formatter, Result as FmtResult};
+use std::path::PathBuf;
+use std::process::Command;
+
+use crate::app::artifacts;
+use crate::app::format;
+use crate::app::process_task::split_args;
+use crate::app::task_id::TaskId;
+
+/// Seconds the user has to cancel a shutdown or sleep before it happens.
+pub const POWER_COUNTDOWN_SECONDS: f64 = 60.0;
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub enum PostBatchAction {
+    #[default]
+    Nothing,
+    /// A command line, split like the process task's arguments and run without a shell.
+    RunCommand(String),
+    OpenFolder(PathBuf),
+    /// A desktop notification with how many tasks completed.
+    Notify,
+    Shutdown,
+    Sleep,
+}
+
+impl PostBatchAction {
+    /// One of each kind, for choosing from.
+    pub fn choices() -> [PostBatchAction; 6] {
+        [
+            PostBatchAction::Nothing,
+            PostBatchAction::RunCommand(String::new()),
+            PostBatchAction::OpenFolder(PathBuf::new()),
+            PostBatchAction::Notify,
+            PostBatchAction::Shutdown,
+            PostBatchAction::Sleep,
+        ]
+    }
+
+    /// Whether this is the same kind of action as `other`, whatever their settings.
+    pub fn same_kind(&self, other: &PostBatchAction) -> bool {
+        std::mem::discriminant(self) == std::mem::discriminant(other)
+    }
+
+    /// Shutting down and sleeping are only done after a countdown the user can cancel.
+    pub fn needs_countdown(&self) -> bool {
+        matches!(self, PostBatchAction::Shutdown | PostBatchAction::Sleep)
+    }
+
+    /// Carries out the action for a finished batch, described by `summary`.
+    pub fn run(&self, summary: &BatchSummary) -> Result<(), String> {
+        match self {
+            PostBatchAction::Nothing => Ok(()),
+            PostBatchAction::RunCommand(line) => run_command(line),
+            PostBatchAction::OpenFolder(path) => {
+                artifacts::open_path(path).map_err(|e| format!("Cannot open folder: {}", e))
+            }
+            PostBatchAction::Notify => notify_rust::Notification::new()
+                .appname("Task Queue")
+                .summary("Batch finished")
+                .body(&summary.to_string())
+                .show()
+                .map(|_| ())
+                .map_err(|e| format!("Cannot show notification: {}", e)),
+            PostBatchAction::Shutdown => spawn(power_command(true)),
+            PostBatchAction::Sleep => spawn(power_command(false)),
+        }
+    }
+}
+
+impl Display for PostBatchAction {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        match self {
+            PostBatchAction::Nothing => write!(f, "Do nothing"),
+            PostBatchAction::RunCommand(_) => write!(f, "Run a command"),
+            PostBatchAction::OpenFolder(_) => write!(f, "Open a folder"),
+            PostBatchAction::Notify => write!(f, "Send a notification"),
+            PostBatchAction::Shutdown => write!(f, "Shut down the computer"),
+            PostBatchAction::Sleep => write!(f, "Put the computer to sleep"),
+        }
+    }
+}
+
+/// How a batch ended, and what to do about it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BatchSummary {
+    pub action: PostBatchAction,
+    pub completed: usize,
+    pub total: usize,
+}
+
+impl Display for BatchSummary {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        write!(
+            f,
+            "{} of {} tasks completed",
+            format::count(self.completed),
+            format::count(self.total)
+        )
+    }
+}
+
+#[derive(Debug)]
+struct PendingBatch {
+    remaining: HashSet<TaskId>,
+    completed: usize,
+    total: usize,
+    action: PostBatchAction,
+}
+
+/// Batches with unfinished tasks, each known by its first task's id.
+#[derive(Debug, Default)]
+pub struct PendingBatches {
+    batches: BTreeMap<TaskId, PendingBatch>,
+}
+
+impl PendingBatches {
+    /// Starts watching `task_ids`, returning the batch's key; `None` when there are none.
+    pub fn add(&mut self, task_ids: &[TaskId], action: PostBatchAction) -> Option<TaskId> {
+        let key = *task_ids.first()?;
+        self.batches.insert(
+            key,
+            PendingBatch {
+                remaining: task_ids.iter().copied().collect(),
+                completed: 0,
+                total: task_ids.len(),
+                action,
+            },
+        );
+        Some(key)
+    }
+
+    /// Changes what a batch does when it finishes. Returns false if it already has.
+    pub fn set_action(&mut self, key: TaskId, action: PostBatchAction) -> bool {
+        match self.batches.get_mut(&key) {
+            Some(batch) => {
+                batch.action = action;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// The unfinished tasks of each batch, in id order.
+    pub fn groups(&self) -> Vec<Vec<TaskId>> {
+        self.batches
+            .values()
+            .map(|batch| {
+                let mut ids: Vec<TaskId> = batch.remaining.iter().copied().collect();
+                ids.sort_unstable();
+                ids
+            })
+            .collect()
+    }
+
+    /// Notes that `task_id` finished, returning its batch's summary if it was the last.
+    pub fn finished(&mut self, task_id: TaskId, completed: bool) -> Option<BatchSummary> {
+        let key = self
+            .batches
+            .iter()
+            .find(|(_, batch)| batch.remaining.contains(&task_id))
+            .map(|(&key, _)| key)?;
+        let batch = self.batches.get_mut(&key)?;
+        batch.remaining.remove(&task_id);
+        if completed {
+            batch.completed += 1;
+        }
+        if !batch.remaining.is_empty() {
+            return None;
+        }
+        let batch = self.batches.remove(&key)?;
+        Some(BatchSummary {
+            action: batch.action,
+            completed: batch.completed,
+            total: batch.total,
+        })
+    }
+}
+
+fn run_command(line: &str) -> Result<(), String> {
+    let args = split_args(line).map_err(|e| format!("Invalid command: {}", e))?;
+    let Some((program, args)) = args.split_first() else {
+        return Err("No command to run".to_owned());
+    };
+    let mut command = Command::new(program);
+    command.args(args);
+    spawn(command)
+}
+
+/// Starts `command` and reaps it on a thread of its own, logging how it exited.
+fn spawn(mut command: Command) -> Result<(), String> {
+    let mut child = command
+        .spawn()
+        .map_err(|e| format!("Cannot run {:?}: {}", command.get_program(), e))?;
+    std::thread::spawn(move || match child.wait() {
+        Ok(status) if status.success() => {}
+        Ok(status) => log::warn!("Post-batch command exited with {}", status),
+        Err(e) => log::warn!("Cannot wait for post-batch command: {}", e),
+    });
+    Ok(())
+}
+
+#[cfg(target_os = "windows")]
+fn power_command(shutdown: bool) -> Command {
+    if shutdown {
+        let mut command = Command::new("shutdown");
+        command.args(["/s", "/t", "0"]);
+        command
+    } else {
+        let mut command = Command::new("rundll32.exe");
+        command.args(["powrprof.dll,SetSuspendState", "0,1,0"]);
+        command
+    }
+}
+
+#[cfg(target_os = "macos")]
+fn power_command(shutdown: bool) -> Command {
+    if shutdown {
+        let mut command = Command::new("osascript");
+        command.args(["-e", "tell application \"System Events\" to shut down"]);
+        command
+    } else {
+        let mut command = Command::new("pmset");
+        command.arg("sleepnow");
+        command
+    }
+}
+
+#[cfg(not(any(target_os = "windows", target_os = "macos")))]
+fn power_command(shutdown: bool) -> Command {
+    let mut command = Command::new("systemctl");
+    command.arg(if shutdown { "poweroff" } else { "suspend" });
+    command
+}
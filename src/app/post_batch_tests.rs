@@ -0,0 +1,51 @@
+#[cfg(test)]
+use crate::app::post_batch::{BatchSummary, PendingBatches, PostBatchAction};
+#[cfg(test)]
+use crate::app::task_id::TaskId;
+
+#[test]
+fn test_batch_finishes_with_its_last_task() {
+    let mut batches = PendingBatches::default();
+    let key = batches
+        .add(&[3, 4, 5].map(TaskId::detached), PostBatchAction::Notify)
+        .expect("batch has tasks");
+    assert_eq!(key, TaskId::detached(3));
+    assert!(batches.add(&[], PostBatchAction::Notify).is_none());
+    assert!(batches.set_action(key, PostBatchAction::Sleep));
+
+    assert_eq!(batches.finished(TaskId::detached(4), true), None);
+    assert_eq!(batches.finished(TaskId::detached(9), true), None);
+    assert_eq!(batches.finished(TaskId::detached(3), false), None);
+    assert_eq!(
+        batches.finished(TaskId::detached(5), true),
+        Some(BatchSummary {
+            action: PostBatchAction::Sleep,
+            completed: 2,
+            total: 3,
+        })
+    );
+    assert_eq!(batches.finished(TaskId::detached(5), true), None);
+    assert!(!batches.set_action(key, PostBatchAction::Notify));
+}
+
+#[test]
+fn test_only_power_actions_count_down() {
+    let countdown: Vec<bool> = PostBatchAction::choices()
+        .iter()
+        .map(PostBatchAction::needs_countdown)
+        .collect();
+    assert_eq!(countdown, [false, false, false, false, true, true]);
+    assert!(PostBatchAction::RunCommand("a".to_owned())
+        .same_kind(&PostBatchAction::RunCommand(String::new())));
+}
+
+#[test]
+fn test_run_command() {
+    assert!(PostBatchAction::RunCommand(String::new())
+        .run(&BatchSummary {
+            action: PostBatchAction::Nothing,
+            completed: 0,
+            total: 0,
+        })
+        .is_err());
+}
@@ -0,0 +1,236 @@
+//! Keeping the computer awake while tasks run, and pausing heavy tasks on battery.
+
+use std::fmt::{Display, Formatter, Result as FmtResult};
+#[cfg(not(target_os = "windows"))]
+use std::process::{Child, Command};
+
+use crate::app::history::TaskRecord;
+use crate::app::task_id::TaskId;
+use crate::app::task_queue::TaskStatus;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum PowerSource {
+    Ac,
+    Battery {
+        percent: Option<u8>,
+    },
+    /// No battery found, or the platform cannot tell.
+    #[default]
+    Unknown,
+}
+
+impl Display for PowerSource {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        match self {
+            PowerSource::Ac => write!(f, "On AC power"),
+            PowerSource::Battery {
+                percent: Some(percent),
+            } => write!(f, "On battery ({}%)", percent),
+            PowerSource::Battery { percent: None } => write!(f, "On battery"),
+            PowerSource::Unknown => write!(f, "Power source unknown"),
+        }
+    }
+}
+
+#[cfg(target_os = "linux")]
+pub fn power_source() -> PowerSource {
+    read_power_supplies(std::path::Path::new("/sys/class/power_supply"))
+}
+
+/// Reads a sysfs `power_supply` class directory: any online mains supply means AC,
+/// otherwise a battery means running on it.
+#[cfg(target_os = "linux")]
+pub fn read_power_supplies(dir: &std::path::Path) -> PowerSource {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return PowerSource::Unknown;
+    };
+    let read = |path: std::path::PathBuf| {
+        std::fs::read_to_string(path)
+            .map(|value| value.trim().to_owned())
+            .unwrap_or_default()
+    };
+    let mut battery = None;
+    for entry in entries.flatten() {
+        let supply = entry.path();
+        match read(supply.join("type")).as_str() {
+            "Mains" if read(supply.join("online")) == "1" => return PowerSource::Ac,
+            "Battery" => {
+                let percent = read(supply.join("capacity")).parse().ok();
+                battery = Some(PowerSource::Battery { percent });
+            }
+            _ => {}
+        }
+    }
+    battery.unwrap_or(PowerSource::Unknown)
+}
+
+#[cfg(target_os = "macos")]
+pub fn power_source() -> PowerSource {
+    match Command::new("pmset").args(["-g", "batt"]).output() {
+        Ok(output) => parse_pmset(&String::from_utf8_lossy(&output.stdout)),
+        Err(e) => {
+            log::debug!("Cannot run pmset: {}", e);
+            PowerSource::Unknown
+        }
+    }
+}
+
+/// Parses `pmset -g batt`, whose first line is e.g. `Now drawing from 'Battery Power'`.
+#[cfg(target_os = "macos")]
+fn parse_pmset(output: &str) -> PowerSource {
+    if output.contains("'AC Power'") {
+        PowerSource::Ac
+    } else if output.contains("'Battery Power'") {
+        let percent = output
+            .split_whitespace()
+            .find_map(|word| word.strip_suffix("%;"))
+            .and_then(|percent| percent.parse().ok());
+        PowerSource::Battery { percent }
+    } else {
+        PowerSource::Unknown
+    }
+}
+
+#[cfg(target_os = "windows")]
+mod win32 {
+    #[repr(C)]
+    #[derive(Default)]
+    pub struct SystemPowerStatus {
+        pub ac_line_status: u8,
+        pub battery_flag: u8,
+        pub battery_life_percent: u8,
+        pub system_status_flag: u8,
+        pub battery_life_time: u32,
+        pub battery_full_life_time: u32,
+    }
+
+    pub const ES_CONTINUOUS: u32 = 0x8000_0000;
+    pub const ES_SYSTEM_REQUIRED: u32 = 0x0000_0001;
+
+    #[link(name = "kernel32")]
+    extern "system" {
+        pub fn GetSystemPowerStatus(status: *mut SystemPowerStatus) -> i32;
+        pub fn SetThreadExecutionState(flags: u32) -> u32;
+    }
+}
+
+#[cfg(target_os = "windows")]
+pub fn power_source() -> PowerSource {
+    let mut status = win32::SystemPowerStatus::default();
+    // SAFETY: `status` is a valid, writable SYSTEM_POWER_STATUS.
+    if unsafe { win32::GetSystemPowerStatus(&mut status) } == 0 {
+        return PowerSource::Unknown;
+    }
+    match status.ac_line_status {
+        0 => PowerSource::Battery {
+            percent: (status.battery_life_percent <= 100).then_some(status.battery_life_percent),
+        },
+        1 => PowerSource::Ac,
+        _ => PowerSource::Unknown,
+    }
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos", target_os = "windows")))]
+pub fn power_source() -> PowerSource {
+    PowerSource::Unknown
+}
+
+/// Keeps the computer from going to sleep until dropped.
+///
+/// On Windows this holds for the thread that acquired it, so acquire and drop it on the
+/// UI thread.
+pub struct KeepAwake {
+    #[cfg(not(target_os = "windows"))]
+    inhibitor: Child,
+}
+
+impl KeepAwake {
+    #[cfg(target_os = "windows")]
+    pub fn acquire() -> Result<Self, String> {
+        // SAFETY: plain flag arguments; the previous state is returned, 0 on failure.
+        let previous = unsafe {
+            win32::SetThreadExecutionState(win32::ES_CONTINUOUS | win32::ES_SYSTEM_REQUIRED)
+        };
+        if previous == 0 {
+            return Err("SetThreadExecutionState failed".to_owned());
+        }
+        Ok(KeepAwake {})
+    }
+
+    /// Runs an inhibitor process for as long as this is held: `caffeinate` on macOS,
+    /// `systemd-inhibit` elsewhere.
+    #[cfg(not(target_os = "windows"))]
+    pub fn acquire() -> Result<Self, String> {
+        #[cfg(target_os = "macos")]
+        let mut command = {
+            let mut command = Command::new("caffeinate");
+            // `-w` also ends the assertion if this process dies without dropping it.
+            command.args(["-i", "-w", &std::process::id().to_string()]);
+            command
+        };
+        #[cfg(not(target_os = "macos"))]
+        let mut command = {
+            let mut command = Command::new("systemd-inhibit");
+            command.args([
+                "--what=sleep:idle",
+                "--who=Task Queue",
+                "--why=Tasks are running",
+                "sleep",
+                "infinity",
+            ]);
+            command
+        };
+        let inhibitor = command
+            .spawn()
+            .map_err(|e| format!("Cannot keep the computer awake: {}", e))?;
+        Ok(KeepAwake { inhibitor })
+    }
+}
+
+impl Drop for KeepAwake {
+    fn drop(&mut self) {
+        #[cfg(target_os = "windows")]
+        // SAFETY: clears the flags set in `acquire` on this thread.
+        unsafe {
+            win32::SetThreadExecutionState(win32::ES_CONTINUOUS);
+        }
+        #[cfg(not(target_os = "windows"))]
+        {
+            let _ = self.inhibitor.kill();
+            let _ = self.inhibitor.wait();
+        }
+    }
+}
+
+/// Remembers which tasks were paused for running on battery, so that only those are
+/// resumed when AC power returns.
+#[derive(Debug, Default)]
+pub struct BatteryGuard {
+    paused: Vec<TaskId>,
+}
+
+impl BatteryGuard {
+    /// Queued or running tasks of a heavy kind (any kind when `heavy_kinds` is empty)
+    /// that this guard has not paused before. A task the user resumes by hand while on
+    /// battery is therefore not paused again.
+    pub fn tasks_to_pause(
+        &mut self,
+        records: &[TaskRecord],
+        heavy_kinds: &[String],
+    ) -> Vec<TaskId> {
+        let ids: Vec<TaskId> = records
+            .iter()
+            .filter(|record| matches!(record.status, TaskStatus::Queued | TaskStatus::Running))
+            .filter(|record| heavy_kinds.is_empty() || heavy_kinds.contains(&record.kind))
+            .map(|record| record.id)
+            .filter(|id| !self.paused.contains(id))
+            .collect();
+        self.paused.extend(&ids);
+        ids
+    }
+
+    /// Every task paused by the guard, which then forgets them.
+    pub fn tasks_to_resume(&mut self) -> Vec<TaskId> {
+        std::mem::take(&mut self.paused)
+    }
+}
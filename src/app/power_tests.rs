@@ -0,0 +1,73 @@
+#[cfg(test)]
+use crate::app::history::TaskRecord;
+#[cfg(test)]
+use crate::app::power::BatteryGuard;
+#[cfg(test)]
+use crate::app::task_id::TaskId;
+#[cfg(test)]
+use crate::app::task_queue::TaskStatus;
+
+#[cfg(test)]
+fn record(id: usize, kind: &str, status: TaskStatus) -> TaskRecord {
+    TaskRecord {
+        status,
+        ..TaskRecord::new(TaskId::detached(id), kind)
+    }
+}
+
+#[test]
+fn test_battery_guard_pauses_heavy_tasks_once() {
+    let mut guard = BatteryGuard::default();
+    let records = vec![
+        record(1, "render", TaskStatus::Running),
+        record(2, "render", TaskStatus::Queued),
+        record(3, "render", TaskStatus::Paused),
+        record(4, "sleep", TaskStatus::Running),
+        record(5, "render", TaskStatus::Completed),
+    ];
+    let heavy = vec!["render".to_owned()];
+    assert_eq!(
+        guard.tasks_to_pause(&records, &heavy),
+        [1, 2].map(TaskId::detached)
+    );
+    // Task 1 was resumed by hand: it is left alone, but a new heavy task is paused.
+    let records = vec![
+        record(1, "render", TaskStatus::Running),
+        record(6, "render", TaskStatus::Queued),
+    ];
+    assert_eq!(
+        guard.tasks_to_pause(&records, &heavy),
+        [6].map(TaskId::detached)
+    );
+    assert_eq!(guard.tasks_to_resume(), [1, 2, 6].map(TaskId::detached));
+    assert!(guard.tasks_to_resume().is_empty());
+
+    assert_eq!(
+        guard.tasks_to_pause(&records, &[]),
+        [1, 6].map(TaskId::detached)
+    );
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn test_read_power_supplies() {
+    use crate::app::power::{read_power_supplies, PowerSource};
+
+    let dir = std::env::temp_dir().join(format!("power_supply_{}", std::process::id()));
+    let write = |supply: &str, file: &str, value: &str| {
+        std::fs::create_dir_all(dir.join(supply)).unwrap();
+        std::fs::write(dir.join(supply).join(file), value).unwrap();
+    };
+    assert_eq!(read_power_supplies(&dir), PowerSource::Unknown);
+    write("BAT0", "type", "Battery\n");
+    write("BAT0", "capacity", "57\n");
+    write("AC", "type", "Mains\n");
+    write("AC", "online", "0\n");
+    assert_eq!(
+        read_power_supplies(&dir),
+        PowerSource::Battery { percent: Some(57) }
+    );
+    write("AC", "online", "1\n");
+    assert_eq!(read_power_supplies(&dir), PowerSource::Ac);
+    std::fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,86 @@
+//! How urgent a task is relative to the rest of the queue. A task starts with its own
+//! [`Task::priority`](crate::app::task_queue::Task::priority), which the user can change
+//! while it is still queued; see [`TaskQueue::set_priority`]. With a concurrency limit
+//! in effect, more urgent tasks get a worker first.
+//!
+//! [`TaskQueue::set_priority`]: crate::app::task_queue::TaskQueue::set_priority
+
+use std::fmt::{Display, Formatter, Result as FmtResult};
+use std::str::FromStr;
+
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    Default,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Hash,
+    serde::Deserialize,
+    serde::Serialize,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum Priority {
+    Low,
+    #[default]
+    Normal,
+    High,
+    Critical,
+}
+
+impl Priority {
+    /// Every level, least urgent first.
+    pub const ALL: [Priority; 4] = [
+        Priority::Low,
+        Priority::Normal,
+        Priority::High,
+        Priority::Critical,
+    ];
+
+    pub fn is_normal(&self) -> bool {
+        *self == Priority::Normal
+    }
+
+    /// The next more urgent level, or critical if it is already.
+    pub fn raised(&self) -> Priority {
+        match self {
+            Priority::Low => Priority::Normal,
+            Priority::Normal => Priority::High,
+            Priority::High | Priority::Critical => Priority::Critical,
+        }
+    }
+
+    /// Levels other than normal stand out on the task's row, in this red, green and blue.
+    pub fn rgb(&self) -> Option<[u8; 3]> {
+        match self {
+            Priority::Low => None,
+            Priority::Normal => None,
+            Priority::High => Some([0xe8, 0x8a, 0x2e]),
+            Priority::Critical => Some([0xe0, 0x4f, 0x4f]),
+        }
+    }
+}
+
+impl Display for Priority {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        match self {
+            Priority::Low => write!(f, "low"),
+            Priority::Normal => write!(f, "normal"),
+            Priority::High => write!(f, "high"),
+            Priority::Critical => write!(f, "critical"),
+        }
+    }
+}
+
+impl FromStr for Priority {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Priority::ALL
+            .into_iter()
+            .find(|priority| priority.to_string() == s)
+            .ok_or_else(|| format!("Unknown priority: {}", s))
+    }
+}
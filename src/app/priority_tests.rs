@@ -0,0 +1,110 @@
+#[cfg(test)]
+use std::time::Duration;
+
+#[cfg(test)]
+use crate::app::history::TaskRecord;
+#[cfg(test)]
+use crate::app::priority::Priority;
+#[cfg(test)]
+use crate::app::sleep_task::SleepTask;
+#[cfg(test)]
+use crate::app::task_id::TaskId;
+#[cfg(test)]
+use crate::app::task_queue::{PollResult, Task, TaskError, TaskKind, TaskQueue};
+
+#[test]
+fn test_priority_names_round_trip() {
+    for priority in Priority::ALL {
+        assert_eq!(priority.to_string().parse(), Ok(priority));
+        assert_eq!(
+            serde_json::to_string(&priority).unwrap(),
+            format!("\"{}\"", priority)
+        );
+    }
+    assert!("urgent".parse::<Priority>().is_err());
+    assert!(Priority::Critical > Priority::High && Priority::Normal > Priority::Low);
+}
+
+#[test]
+fn test_normal_priority_is_left_out_of_records() {
+    let json = serde_json::to_string(&TaskRecord::new(TaskId::detached(1), "sleep")).unwrap();
+    assert!(!json.contains("priority"));
+    let record: TaskRecord = serde_json::from_str(&json).unwrap();
+    assert_eq!(record.priority, Priority::Normal);
+}
+
+#[test]
+fn test_only_queued_tasks_are_reprioritized() {
+    let queue = TaskQueue::new();
+    let id = queue.add_task(SleepTask::new(None, Duration::from_secs(60)));
+    queue.set_priority(id, Priority::High).unwrap();
+    assert_eq!(
+        queue.task_detail(id).unwrap().record.priority,
+        Priority::High
+    );
+
+    queue.poll_task(id).unwrap();
+    assert_eq!(
+        queue.set_priority(id, Priority::Low),
+        Err(TaskError::AlreadyRunning)
+    );
+    queue.remove_task(id).unwrap();
+    assert_eq!(
+        queue.set_priority(id, Priority::Low),
+        Err(TaskError::AlreadyCancelled)
+    );
+    assert_eq!(
+        queue.set_priority(queue.task_id(id.index() + 1), Priority::Low),
+        Err(TaskError::NotFound)
+    );
+}
+
+/// A sleep task that asks to go first.
+#[cfg(test)]
+struct Urgent(SleepTask);
+
+#[cfg(test)]
+impl Task for Urgent {
+    fn id(&self) -> Result<TaskId, TaskError> {
+        self.0.id()
+    }
+
+    fn set_id(&mut self, id: TaskId) {
+        self.0.set_id(id)
+    }
+
+    fn poll(&mut self) -> PollResult {
+        self.0.poll()
+    }
+
+    fn cancel(&mut self) -> Result<(), TaskError> {
+        self.0.cancel()
+    }
+
+    fn pause(&mut self) -> Result<(), TaskError> {
+        self.0.pause()
+    }
+
+    fn resume(&mut self) -> Result<(), TaskError> {
+        self.0.resume()
+    }
+
+    fn kind(&self) -> TaskKind {
+        self.0.kind()
+    }
+
+    fn priority(&self) -> Priority {
+        Priority::Critical
+    }
+}
+
+#[test]
+fn test_task_priority_is_recorded() {
+    let queue = TaskQueue::new();
+    let id = queue.add_task(Urgent(SleepTask::new(None, Duration::from_secs(60))));
+    assert_eq!(
+        queue.task_detail(id).unwrap().record.priority,
+        Priority::Critical
+    );
+    queue.remove_task(id).unwrap();
+}
@@ -0,0 +1,354 @@
+use std::any::Any;
+use std::collections::BTreeMap;
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command, Stdio};
+use std::sync::{Arc as sync_Arc, Mutex as sync_Mutex};
+use std::thread::JoinHandle;
+use std::time::Duration;
+
+use log::debug;
+
+use crate::app::executor::{self, JobHandle};
+use crate::app::history::Artifact;
+use crate::app::task_id::TaskId;
+use crate::app::task_queue::PollingData;
+
+use super::task_queue::{PollResult, Task, TaskError, TaskKind, TaskStatus};
+
+/// How often a running process is checked for having exited or been cancelled.
+const WAIT_POLL_INTERVAL: Duration = Duration::from_millis(100);
+/// Standard output kept as the task's artifact; the rest is read and dropped.
+const MAX_OUTPUT_BYTES: u64 = 64 * 1024;
+
+/// What to run, and how.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ProcessSpec {
+    pub program: String,
+    pub args: Vec<String>,
+    /// Added to, or replacing, the app's own environment.
+    pub env: BTreeMap<String, String>,
+    /// The app's working directory when `None`.
+    pub working_dir: Option<PathBuf>,
+    /// Written to standard input, which is then closed. Standard input is empty when `None`.
+    pub stdin: Option<String>,
+}
+
+impl ProcessSpec {
+    fn command(&self) -> Command {
+        let mut command = Command::new(&self.program);
+        command
+            .args(&self.args)
+            .envs(&self.env)
+            .stdin(if self.stdin.is_some() {
+                Stdio::piped()
+            } else {
+                Stdio::null()
+            })
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped());
+        if let Some(dir) = &self.working_dir {
+            command.current_dir(dir);
+        }
+        command
+    }
+}
+
+/// Runs a program to completion.
+///
+/// Progress stays at zero until the program exits, as there is no telling how far along
+/// it is. Standard output becomes a text artifact. A program that cannot be started or
+/// exits unsuccessfully ends cancelled, with its standard error logged. Only tasks that
+/// have not started can be paused.
+pub struct ProcessTask {
+    id: Option<TaskId>,
+    spec: sync_Arc<ProcessSpec>,
+    status: sync_Arc<sync_Mutex<TaskStatus>>,
+    /// Why the task failed, once it has.
+    error: sync_Arc<sync_Mutex<Option<String>>>,
+    output: sync_Arc<sync_Mutex<String>>,
+    handle: Option<JoinHandle<()>>,
+}
+
+impl ProcessTask {
+    pub fn new(id: Option<TaskId>, spec: ProcessSpec) -> Self {
+        debug!("ProcessTask::new() - {} {:?}", spec.program, spec.args);
+        ProcessTask {
+            id,
+            spec: sync_Arc::new(spec),
+            status: sync_Arc::new(sync_Mutex::new(TaskStatus::Queued)),
+            error: sync_Arc::new(sync_Mutex::new(None)),
+            output: sync_Arc::new(sync_Mutex::new(String::new())),
+            handle: None,
+        }
+    }
+
+    fn status(&self) -> TaskStatus {
+        self.status.lock().unwrap().clone()
+    }
+
+    fn set_status(&self, status: TaskStatus) {
+        *self.status.lock().unwrap() = status;
+    }
+}
+
+/// A task that runs the same command and has not started.
+impl Clone for ProcessTask {
+    fn clone(&self) -> Self {
+        ProcessTask::new(None, (*self.spec).clone())
+    }
+}
+
+impl Task for ProcessTask {
+    fn id(&self) -> Result<TaskId, TaskError> {
+        self.id.ok_or(TaskError::IdUsizeIsNone)
+    }
+
+    fn set_id(&mut self, id: TaskId) {
+        self.id = Some(id);
+    }
+
+    fn poll(&mut self) -> PollResult {
+        match self.status() {
+            TaskStatus::Scheduled | TaskStatus::Queued => {
+                self.set_status(TaskStatus::Running);
+                let spec = self.spec.clone();
+                let status = self.status.clone();
+                let error = self.error.clone();
+                let output = self.output.clone();
+                self.handle = Some(std::thread::spawn(move || {
+                    let result = run(&spec, &status, &output);
+                    let mut status = status.lock().unwrap();
+                    match result {
+                        Ok(()) if *status != TaskStatus::Cancelled => {
+                            *status = TaskStatus::Completed;
+                        }
+                        Ok(()) => {}
+                        Err(_) if *status == TaskStatus::Cancelled => {}
+                        Err(e) => {
+                            log::error!("Process {} failed: {}", spec.program, e);
+                            *error.lock().unwrap() = Some(e.to_string());
+                            *status = TaskStatus::Failed;
+                        }
+                    }
+                }));
+                PollResult::Pending(PollingData::Float(0.0))
+            }
+            TaskStatus::Running => PollResult::Pending(PollingData::Float(0.0)),
+            TaskStatus::Paused => PollResult::Paused(PollingData::Float(0.0)),
+            TaskStatus::Completed => PollResult::Completed,
+            TaskStatus::Cancelled | TaskStatus::Interrupted => PollResult::Cancelled,
+            TaskStatus::Failed => PollResult::Failed(TaskError::Failed {
+                reason: self.error.lock().unwrap().clone().unwrap_or_default(),
+            }),
+        }
+    }
+
+    fn cancel(&mut self) -> Result<(), TaskError> {
+        match self.status() {
+            TaskStatus::Completed => Err(TaskError::AlreadyCompleted),
+            TaskStatus::Cancelled | TaskStatus::Failed | TaskStatus::Interrupted => {
+                Err(TaskError::AlreadyCancelled)
+            }
+            _ => {
+                self.set_status(TaskStatus::Cancelled);
+                Ok(())
+            }
+        }
+    }
+
+    fn pause(&mut self) -> Result<(), TaskError> {
+        match self.status() {
+            TaskStatus::Scheduled | TaskStatus::Queued => {
+                self.set_status(TaskStatus::Paused);
+                Ok(())
+            }
+            TaskStatus::Running => Err(TaskError::AlreadyRunning),
+            TaskStatus::Paused => Err(TaskError::AlreadyPaused),
+            TaskStatus::Completed => Err(TaskError::AlreadyCompleted),
+            TaskStatus::Cancelled | TaskStatus::Failed | TaskStatus::Interrupted => {
+                Err(TaskError::AlreadyCancelled)
+            }
+        }
+    }
+
+    fn resume(&mut self) -> Result<(), TaskError> {
+        match self.status() {
+            TaskStatus::Scheduled | TaskStatus::Queued => Err(TaskError::NotFound),
+            TaskStatus::Running => Err(TaskError::AlreadyRunning),
+            TaskStatus::Paused => {
+                self.set_status(TaskStatus::Queued);
+                Ok(())
+            }
+            TaskStatus::Completed => Err(TaskError::AlreadyCompleted),
+            TaskStatus::Cancelled | TaskStatus::Failed | TaskStatus::Interrupted => {
+                Err(TaskError::AlreadyCancelled)
+            }
+        }
+    }
+
+    fn kind(&self) -> TaskKind {
+        TaskKind::Process
+    }
+
+    fn take_handle(&mut self) -> Option<JobHandle> {
+        self.handle.take().map(executor::join_thread)
+    }
+
+    /// A program named without a directory is looked up on PATH only when started.
+    fn validate(&self) -> Result<(), TaskError> {
+        let invalid = |field: &str, message: String| TaskError::InvalidInput {
+            field: field.to_owned(),
+            message,
+        };
+        let program = Path::new(&self.spec.program);
+        if program.components().count() > 1 && !program.is_file() {
+            return Err(invalid(
+                "command",
+                format!("{} does not exist", program.display()),
+            ));
+        }
+        match &self.spec.working_dir {
+            Some(dir) if !dir.is_dir() => Err(invalid(
+                "workdir",
+                format!("{} is not a directory", dir.display()),
+            )),
+            _ => Ok(()),
+        }
+    }
+
+    fn message(&self) -> Option<String> {
+        Some(format!(
+            "{} {}",
+            self.spec.program,
+            self.spec.args.join(" ")
+        ))
+    }
+
+    fn artifacts(&self) -> Vec<Artifact> {
+        let output = self.output.lock().unwrap();
+        if output.is_empty() {
+            return Vec::new();
+        }
+        vec![Artifact::Text {
+            label: "Standard output".to_owned(),
+            text: output.clone(),
+        }]
+    }
+
+    /// Everything the program wrote to standard output, as a `String`.
+    fn take_result(&mut self) -> Option<Box<dyn Any + Send>> {
+        Some(Box::new(std::mem::take(&mut *self.output.lock().unwrap())))
+    }
+}
+
+/// Runs the program until it exits or the task is cancelled, which kills it.
+fn run(
+    spec: &ProcessSpec,
+    status: &sync_Mutex<TaskStatus>,
+    output: &sync_Mutex<String>,
+) -> Result<(), String> {
+    let mut child = spec
+        .command()
+        .spawn()
+        .map_err(|e| format!("cannot start: {}", e))?;
+    let writer = match (child.stdin.take(), spec.stdin.clone()) {
+        (Some(mut stdin), Some(payload)) => Some(std::thread::spawn(move || {
+            // A program may exit without reading all of it.
+            let _ = stdin.write_all(payload.as_bytes());
+        })),
+        _ => None,
+    };
+    let stdout = child
+        .stdout
+        .take()
+        .map(|stdout| read_limited(stdout, MAX_OUTPUT_BYTES));
+    let stderr = child
+        .stderr
+        .take()
+        .map(|stderr| read_limited(stderr, MAX_OUTPUT_BYTES));
+    let exit = wait(&mut child, status);
+    if let Some(writer) = writer {
+        let _ = writer.join();
+    }
+    let stdout = stdout
+        .and_then(|reader| reader.join().ok())
+        .unwrap_or_default();
+    let stderr = stderr
+        .and_then(|reader| reader.join().ok())
+        .unwrap_or_default();
+    *output.lock().unwrap() = stdout;
+    match exit? {
+        Some(exit) if !exit.success() => Err(format!("{}: {}", exit, stderr.trim_end())),
+        _ => Ok(()),
+    }
+}
+
+/// The exit status, or `None` if the task was cancelled and the program killed.
+fn wait(
+    child: &mut Child,
+    status: &sync_Mutex<TaskStatus>,
+) -> Result<Option<std::process::ExitStatus>, String> {
+    loop {
+        if *status.lock().unwrap() == TaskStatus::Cancelled {
+            let _ = child.kill();
+            let _ = child.wait();
+            return Ok(None);
+        }
+        if let Some(exit) = child.try_wait().map_err(|e| e.to_string())? {
+            return Ok(Some(exit));
+        }
+        std::thread::sleep(WAIT_POLL_INTERVAL);
+    }
+}
+
+/// Reads `source` to the end on a thread of its own, keeping the first `limit` bytes.
+fn read_limited(source: impl Read + Send + 'static, limit: u64) -> JoinHandle<String> {
+    std::thread::spawn(move || {
+        let mut source = source;
+        let mut kept = Vec::new();
+        let _ = (&mut source).take(limit).read_to_end(&mut kept);
+        let _ = std::io::copy(&mut source, &mut std::io::sink());
+        String::from_utf8_lossy(&kept).into_owned()
+    })
+}
+
+/// Splits a command line into arguments at whitespace. Double quotes keep whitespace
+/// within an argument and are removed; `\"` is a literal quote.
+pub fn split_args(line: &str) -> Result<Vec<String>, String> {
+    let mut args = Vec::new();
+    let mut current = String::new();
+    let mut in_arg = false;
+    let mut quoted = false;
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' if chars.peek() == Some(&'"') => {
+                current.push('"');
+                chars.next();
+                in_arg = true;
+            }
+            '"' => {
+                quoted = !quoted;
+                in_arg = true;
+            }
+            c if c.is_whitespace() && !quoted => {
+                if in_arg {
+                    args.push(std::mem::take(&mut current));
+                    in_arg = false;
+                }
+            }
+            c => {
+                current.push(c);
+                in_arg = true;
+            }
+        }
+    }
+    if quoted {
+        return Err("unclosed quote".to_owned());
+    }
+    if in_arg {
+        args.push(current);
+    }
+    Ok(args)
+}
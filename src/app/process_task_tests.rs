@@ -0,0 +1,68 @@
+#[cfg(test)]
+use crate::app::history::Artifact;
+#[cfg(test)]
+use crate::app::process_task::{split_args, ProcessSpec, ProcessTask};
+#[cfg(test)]
+use crate::app::task_id::TaskId;
+#[cfg(test)]
+use crate::app::task_queue::{PollResult, Task, TaskQueue};
+
+#[test]
+fn test_split_args() {
+    assert_eq!(
+        split_args(r#"-o "out dir/x"  \"a\" """#).unwrap(),
+        vec!["-o", "out dir/x", "\"a\"", ""]
+    );
+    assert!(split_args(r#"echo "open"#).is_err());
+}
+
+#[cfg(unix)]
+#[test]
+fn test_process_gets_env_workdir_and_stdin() {
+    let spec = ProcessSpec {
+        program: "sh".to_owned(),
+        args: vec![
+            "-c".to_owned(),
+            r#"echo "$GREETING $(pwd)"; cat"#.to_owned(),
+        ],
+        env: [("GREETING".to_owned(), "hello".to_owned())]
+            .into_iter()
+            .collect(),
+        working_dir: Some("/".into()),
+        stdin: Some("from stdin".to_owned()),
+    };
+    let mut task = ProcessTask::new(Some(TaskId::detached(0)), spec);
+    let start = std::time::Instant::now();
+    while task.poll() != PollResult::Completed {
+        assert!(start.elapsed() < std::time::Duration::from_secs(5));
+        std::thread::sleep(std::time::Duration::from_millis(10));
+    }
+    assert_eq!(
+        task.artifacts(),
+        vec![Artifact::Text {
+            label: "Standard output".to_owned(),
+            text: "hello /\nfrom stdin".to_owned(),
+        }]
+    );
+}
+
+#[cfg(unix)]
+#[test]
+fn test_process_output_taken_as_result() {
+    let spec = ProcessSpec {
+        program: "echo".to_owned(),
+        args: vec!["done".to_owned()],
+        ..ProcessSpec::default()
+    };
+    let task_queue = TaskQueue::new();
+    let task_id = task_queue.add_task(ProcessTask::new(None, spec));
+    assert!(task_queue.take_result(task_id).unwrap().is_none());
+    let start = std::time::Instant::now();
+    while task_queue.poll_task(task_id) != Ok(PollResult::Completed) {
+        assert!(start.elapsed() < std::time::Duration::from_secs(5));
+        std::thread::sleep(std::time::Duration::from_millis(10));
+    }
+    let result = task_queue.take_result(task_id).unwrap().unwrap();
+    assert_eq!(result.downcast_ref::<String>().unwrap(), "done\n");
+    assert!(task_queue.take_result(task_id).unwrap().is_none());
+}
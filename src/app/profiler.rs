@@ -0,0 +1,159 @@
+//! Optional puffin instrumentation of the task queue and the UI's update loop.
+//!
+//! The macros below compile to nothing unless the `profiling` feature is on, so they can
+//! stay in hot paths. Even with it on, scopes are only recorded while Debug → Profiler is
+//! open.
+
+/// Records the rest of the enclosing function as a profiler scope.
+macro_rules! profile_function {
+    ($($arg:tt)*) => {
+        #[cfg(feature = "profiling")]
+        puffin::profile_function!($($arg)*);
+    };
+}
+
+/// Records the rest of the enclosing block as a profiler scope with the given name.
+#[cfg_attr(not(feature = "ui"), allow(unused_macros))]
+macro_rules! profile_scope {
+    ($($arg:tt)*) => {
+        #[cfg(feature = "profiling")]
+        puffin::profile_scope!($($arg)*);
+    };
+}
+
+pub(crate) use profile_function;
+#[cfg(feature = "ui")]
+pub(crate) use profile_scope;
+
+#[cfg(all(feature = "profiling", feature = "ui"))]
+pub use window::Profiler;
+
+#[cfg(all(feature = "profiling", feature = "ui"))]
+mod window {
+    use std::sync::Arc as sync_Arc;
+
+    use puffin::{FrameData, GlobalFrameView, MergeScope, ScopeCollection};
+
+    /// The Profiler window. Scopes are recorded for as long as one exists.
+    pub struct Profiler {
+        view: GlobalFrameView,
+        /// Show the slowest of the recent frames instead of the latest.
+        slowest: bool,
+        /// Frame kept on screen while the user reads it.
+        frozen: Option<sync_Arc<FrameData>>,
+    }
+
+    impl Profiler {
+        pub fn start() -> Self {
+            puffin::set_scopes_on(true);
+            Profiler {
+                view: GlobalFrameView::default(),
+                slowest: false,
+                frozen: None,
+            }
+        }
+
+        /// Marks the start of a UI frame; scopes recorded since the last call make up one
+        /// frame in the window.
+        pub fn new_frame(&self) {
+            puffin::GlobalProfiler::lock().new_frame();
+        }
+
+        pub fn ui(&mut self, ui: &mut egui::Ui) {
+            let view = self.view.lock();
+            ui.horizontal(|ui| {
+                ui.radio_value(&mut self.slowest, false, "Latest frame");
+                ui.radio_value(&mut self.slowest, true, "Slowest recent frame");
+                let mut frozen = self.frozen.is_some();
+                if ui.checkbox(&mut frozen, "Freeze").changed() {
+                    self.frozen = if frozen {
+                        Self::pick_frame(&view, self.slowest)
+                    } else {
+                        None
+                    };
+                }
+            });
+            ui.separator();
+            let frame = match &self.frozen {
+                Some(frame) => Some(frame.clone()),
+                None => Self::pick_frame(&view, self.slowest),
+            };
+            let Some(frame) = frame else {
+                ui.label("No frames recorded yet.");
+                return;
+            };
+            let Some(unpacked) = frame.unpacked().ok() else {
+                ui.label("Failed to read the frame.");
+                return;
+            };
+            ui.label(format!(
+                "Frame {}: {:.2} ms, {} scopes",
+                unpacked.frame_index(),
+                unpacked.duration_ns() as f64 / 1e6,
+                unpacked.meta.num_scopes
+            ));
+            let scopes = view.scope_collection();
+            let frames = [unpacked.clone()];
+            egui::ScrollArea::vertical().show(ui, |ui| {
+                for thread in unpacked.thread_streams.keys() {
+                    let merged = match puffin::merge_scopes_for_thread(scopes, &frames, thread) {
+                        Ok(merged) => merged,
+                        Err(e) => {
+                            ui.label(format!("{}: {:?}", thread.name, e));
+                            continue;
+                        }
+                    };
+                    egui::CollapsingHeader::new(&thread.name)
+                        .default_open(true)
+                        .show(ui, |ui| {
+                            for scope in &merged {
+                                ui_scope(ui, scopes, scope);
+                            }
+                        });
+                }
+            });
+        }
+
+        fn pick_frame(view: &puffin::FrameView, slowest: bool) -> Option<sync_Arc<FrameData>> {
+            if slowest {
+                view.slowest_frames_chronological()
+                    .max_by_key(|frame| frame.duration_ns())
+                    .cloned()
+            } else {
+                view.latest_frame()
+            }
+        }
+    }
+
+    impl Drop for Profiler {
+        fn drop(&mut self) {
+            puffin::set_scopes_on(false);
+        }
+    }
+
+    /// One line per scope with its total time; scopes of the same name under the same
+    /// parent, like the polls of many tasks, are merged into one line with a count.
+    fn ui_scope(ui: &mut egui::Ui, scopes: &ScopeCollection, scope: &MergeScope<'_>) {
+        let name = scopes
+            .fetch_by_id(&scope.id)
+            .map_or_else(|| "?".to_owned(), |details| details.name().to_string());
+        let mut text = format!("{} {:.3} ms", name, scope.total_duration_ns as f64 / 1e6);
+        if scope.num_pieces > 1 {
+            text.push_str(&format!(" ({}×)", scope.num_pieces));
+        }
+        if !scope.data.is_empty() {
+            text.push_str(&format!(" {}", scope.data));
+        }
+        if scope.children.is_empty() {
+            ui.label(text);
+        } else {
+            egui::CollapsingHeader::new(text)
+                .id_source(scope.id)
+                .show(ui, |ui| {
+                    for child in &scope.children {
+                        ui_scope(ui, scopes, child);
+                    }
+                });
+        }
+    }
+}
@@ -0,0 +1,29 @@
+#[cfg(test)]
+use crate::app::stress::StressTask;
+#[cfg(test)]
+use crate::app::task_queue::TaskQueue;
+
+#[test]
+fn test_queue_operations_are_profiled() {
+    let view = puffin::GlobalFrameView::default();
+    puffin::set_scopes_on(true);
+    let queue = TaskQueue::new();
+    let id = queue.add_task(StressTask::new(1));
+    queue.poll_task(id).unwrap();
+    puffin::GlobalProfiler::lock().new_frame();
+    let view = view.lock();
+    let names: Vec<String> = view
+        .scope_collection()
+        .scopes_by_name()
+        .keys()
+        .map(|name| name.to_string())
+        .collect();
+    for scope in ["TaskQueue::add_task", "TaskQueue::poll_task"] {
+        assert!(
+            names.iter().any(|name| name.ends_with(scope)),
+            "{} not in {:?}",
+            scope,
+            names
+        );
+    }
+}
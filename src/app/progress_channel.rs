@@ -0,0 +1,52 @@
+//! Progress pushed by a task as it goes, for the queue to read in place of polling the
+//! task. The channel holds one value: sending replaces whatever the queue has not read yet,
+//! so a task may send as often as it likes without the channel growing.
+
+use async_std::channel::{self, Receiver, Sender, TrySendError};
+
+use crate::app::task_queue::PollingData;
+
+/// The sending end, handed to each task by its queue; see
+/// [`Task::set_progress_sender`](crate::app::task_queue::Task::set_progress_sender). Cheap
+/// to clone, and never blocks.
+#[derive(Debug, Clone)]
+pub struct ProgressSender {
+    sender: Sender<PollingData>,
+    /// To take out a value the queue has not read yet, so the newer one fits.
+    unread: Receiver<PollingData>,
+}
+
+/// The queue's end of a task's progress channel.
+#[derive(Debug)]
+pub struct ProgressReceiver(Receiver<PollingData>);
+
+pub fn channel() -> (ProgressSender, ProgressReceiver) {
+    let (sender, receiver) = channel::bounded(1);
+    let sender = ProgressSender {
+        sender,
+        unread: receiver.clone(),
+    };
+    (sender, ProgressReceiver(receiver))
+}
+
+impl ProgressSender {
+    /// Reports `data` as the task's progress, in place of any not read yet.
+    pub fn send(&self, mut data: PollingData) {
+        loop {
+            match self.sender.try_send(data) {
+                Ok(()) | Err(TrySendError::Closed(_)) => return,
+                Err(TrySendError::Full(rejected)) => {
+                    let _ = self.unread.try_recv();
+                    data = rejected;
+                }
+            }
+        }
+    }
+}
+
+impl ProgressReceiver {
+    /// The progress sent since the last call, if any.
+    pub fn latest(&self) -> Option<PollingData> {
+        self.0.try_recv().ok()
+    }
+}
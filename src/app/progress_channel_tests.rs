@@ -0,0 +1,99 @@
+#[cfg(test)]
+use std::sync::atomic::{AtomicUsize, Ordering};
+#[cfg(test)]
+use std::sync::{Arc, Mutex};
+
+#[cfg(test)]
+use crate::app::progress_channel::{self, ProgressSender};
+#[cfg(test)]
+use crate::app::task_id::TaskId;
+#[cfg(test)]
+use crate::app::task_queue::{PollResult, PollingData, Task, TaskError, TaskKind, TaskQueue};
+
+#[test]
+fn test_channel_keeps_the_latest_progress() {
+    let (sender, receiver) = progress_channel::channel();
+    assert_eq!(receiver.latest(), None);
+    sender.send(PollingData::Float(0.25));
+    sender.clone().send(PollingData::Float(0.5));
+    assert_eq!(receiver.latest(), Some(PollingData::Float(0.5)));
+    assert_eq!(receiver.latest(), None);
+}
+
+/// Counts its polls, and hands out the sender its queue gives it.
+#[cfg(test)]
+struct PushingTask {
+    id: Option<TaskId>,
+    polls: Arc<AtomicUsize>,
+    sender: Arc<Mutex<Option<ProgressSender>>>,
+}
+
+#[cfg(test)]
+impl Task for PushingTask {
+    fn id(&self) -> Result<TaskId, TaskError> {
+        self.id.ok_or(TaskError::IdUsizeIsNone)
+    }
+
+    fn set_id(&mut self, id: TaskId) {
+        self.id = Some(id);
+    }
+
+    fn set_progress_sender(&mut self, sender: ProgressSender) {
+        *self.sender.lock().unwrap() = Some(sender);
+    }
+
+    fn poll(&mut self) -> PollResult {
+        self.polls.fetch_add(1, Ordering::SeqCst);
+        PollResult::Pending(PollingData::Float(0.0))
+    }
+
+    fn cancel(&mut self) -> Result<(), TaskError> {
+        Ok(())
+    }
+
+    fn pause(&mut self) -> Result<(), TaskError> {
+        Ok(())
+    }
+
+    fn resume(&mut self) -> Result<(), TaskError> {
+        Ok(())
+    }
+
+    fn kind(&self) -> TaskKind {
+        TaskKind::Sleep
+    }
+}
+
+#[test]
+fn test_pushed_progress_is_read_without_polling() {
+    let task_queue = TaskQueue::new();
+    let polls = Arc::new(AtomicUsize::new(0));
+    let sender = Arc::new(Mutex::new(None));
+    let task_id = task_queue.add_task(PushingTask {
+        id: None,
+        polls: polls.clone(),
+        sender: sender.clone(),
+    });
+    let sender = sender.lock().unwrap().clone().unwrap();
+    // Pushed before it started: it is still polled to start it.
+    sender.send(PollingData::Float(0.1));
+    task_queue.poll_task(task_id).unwrap();
+    assert_eq!(polls.load(Ordering::SeqCst), 1);
+
+    let steps = PollingData::Steps {
+        current: 2,
+        total: 5,
+    };
+    sender.send(steps);
+    assert_eq!(
+        task_queue.poll_task(task_id),
+        Ok(PollResult::Pending(steps))
+    );
+    assert_eq!(task_queue.progress(task_id), Ok(PollResult::Pending(steps)));
+    assert_eq!(polls.load(Ordering::SeqCst), 1);
+
+    // Nothing new: asked as usual.
+    task_queue.poll_task(task_id).unwrap();
+    assert_eq!(polls.load(Ordering::SeqCst), 2);
+    task_queue.remove_task(task_id).unwrap();
+}
@@ -0,0 +1,51 @@
+/// How far past the last report, in seconds, progress is extrapolated. Keeps a stalled
+/// task's bar from running on until the next report pulls it back.
+const MAX_LEAD: f64 = 0.5;
+
+/// Smooths a progress bar between reports by extrapolating the rate seen between the last
+/// two, so a steady task animates evenly even when it is polled only a few times a second.
+///
+/// Times are in seconds on any monotonic clock, e.g. egui's input time.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProgressEstimate {
+    reported: f32,
+    reported_at: f64,
+    /// Progress per second between the last two differing reports.
+    rate: f32,
+    /// What was last shown; the bar never moves backwards unless the task does.
+    shown: f32,
+}
+
+impl ProgressEstimate {
+    pub fn new(progress: f32, now: f64) -> Self {
+        ProgressEstimate {
+            reported: progress,
+            reported_at: now,
+            rate: 0.0,
+            shown: progress,
+        }
+    }
+
+    pub fn report(&mut self, progress: f32, now: f64) {
+        if progress == self.reported {
+            return;
+        }
+        let elapsed = now - self.reported_at;
+        if progress > self.reported && elapsed > 0.0 {
+            self.rate = ((progress - self.reported) as f64 / elapsed) as f32;
+        } else {
+            self.rate = 0.0;
+            self.shown = progress;
+        }
+        self.reported = progress;
+        self.reported_at = now;
+    }
+
+    /// The progress to draw at `now`.
+    pub fn at(&mut self, now: f64) -> f32 {
+        let lead = (now - self.reported_at).clamp(0.0, MAX_LEAD) as f32;
+        let estimate = self.reported + self.rate * lead;
+        self.shown = self.shown.max(estimate).min(1.0);
+        self.shown
+    }
+}
@@ -0,0 +1,18 @@
+#[cfg(test)]
+use crate::app::progress_estimate::ProgressEstimate;
+
+#[test]
+fn test_progress_estimate_extrapolates_steady_rate() {
+    let mut estimate = ProgressEstimate::new(0.0, 0.0);
+    estimate.report(0.1, 0.25);
+    assert!((estimate.at(0.375) - 0.15).abs() < 1e-4);
+    // Never more than half a second ahead of the last report.
+    assert!((estimate.at(5.0) - 0.3).abs() < 1e-4);
+    // A report behind what is shown does not pull the bar back...
+    estimate.report(0.2, 0.5);
+    assert!((estimate.at(0.5) - 0.3).abs() < 1e-4);
+    assert!((estimate.at(0.6) - 0.3).abs() < 1e-4);
+    // ...unless the task itself went backwards.
+    estimate.report(0.05, 2.0);
+    assert_eq!(estimate.at(3.0), 0.05);
+}
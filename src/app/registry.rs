@@ -0,0 +1,536 @@
+use std::collections::BTreeMap;
+use std::fmt::{Display, Formatter, Result as FmtResult};
+#[cfg(all(feature = "secrets", not(target_arch = "wasm32")))]
+use std::sync::Arc as sync_Arc;
+use std::time::Duration;
+
+use crate::app::chunked_task::count_primes;
+#[cfg(target_arch = "wasm32")]
+use crate::app::chunked_task::ChunkedTask;
+use crate::app::config::AppConfig;
+#[cfg(not(target_arch = "wasm32"))]
+use crate::app::download_task::DownloadTask;
+#[cfg(not(target_arch = "wasm32"))]
+use crate::app::job_task::JobTask;
+#[cfg(not(target_arch = "wasm32"))]
+use crate::app::process_task::{split_args, ProcessSpec, ProcessTask};
+#[cfg(all(feature = "secrets", not(target_arch = "wasm32")))]
+use crate::app::secrets::SecretStore;
+use crate::app::sleep_task::SleepTask;
+use crate::app::task_queue::{Task, TaskError, TaskKind};
+
+const MAX_SLEEP_SECONDS: f64 = 60.0 * 60.0 * 24.0 * 365.0;
+const MAX_PRIMES_BELOW: f64 = 1e12;
+
+/// Task parameters as entered in the New Task window, keyed by parameter name.
+pub type TaskParams = BTreeMap<String, String>;
+
+/// What two tasks have in common when they would do the same work: the kind and its
+/// parameters with defaults filled in, values trimmed and blank ones left out. See
+/// [`TaskKindRegistry::dedupe_key`].
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct DedupeKey {
+    kind: String,
+    params: TaskParams,
+}
+
+pub type TaskFactory =
+    Box<dyn Fn(&TaskParams) -> Result<Box<dyn Task>, RegistryError> + Send + Sync>;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum RegistryError {
+    UnknownKind(String),
+    InvalidParam {
+        name: String,
+        message: String,
+    },
+    /// The created task turned itself away for a reason other than its input.
+    Task(TaskError),
+}
+
+impl Display for RegistryError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        match self {
+            RegistryError::UnknownKind(kind) => write!(f, "Unknown task kind: {}", kind),
+            RegistryError::InvalidParam { name, message } => {
+                write!(f, "Invalid parameter '{}': {}", name, message)
+            }
+            RegistryError::Task(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, serde::Deserialize, serde::Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ParamType {
+    String,
+    Number,
+    Bool,
+    /// `NAME=value` lines, e.g. environment variables; see [`env_pairs`].
+    Env,
+    /// Free text over several lines.
+    Text,
+}
+
+/// One parameter of a task kind: what it is called, what values it takes and what it
+/// defaults to. [`TaskKindRegistry::create`] checks parameters against these before a task
+/// kind's factory sees them, and the New Task window builds its form from them.
+#[derive(Debug, Clone, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct ParamSpec {
+    pub name: String,
+    #[serde(rename = "type")]
+    pub param_type: ParamType,
+    #[serde(default)]
+    pub default: Option<String>,
+    #[serde(default)]
+    pub description: Option<String>,
+    /// A required parameter without a default must be given.
+    #[serde(default)]
+    pub required: bool,
+    /// Inclusive bounds of a number.
+    #[serde(default)]
+    pub min: Option<f64>,
+    #[serde(default)]
+    pub max: Option<f64>,
+    /// The values a string may take; any when empty.
+    #[serde(default)]
+    pub choices: Vec<String>,
+    /// A path the task writes its output to. The New Task window shows the free space
+    /// there.
+    #[serde(default)]
+    pub output: bool,
+}
+
+impl ParamSpec {
+    pub fn new(name: &str, param_type: ParamType) -> Self {
+        ParamSpec {
+            name: name.to_owned(),
+            param_type,
+            default: None,
+            description: None,
+            required: false,
+            min: None,
+            max: None,
+            choices: Vec::new(),
+            output: false,
+        }
+    }
+
+    pub fn with_default(mut self, default: &str) -> Self {
+        self.default = Some(default.to_owned());
+        self
+    }
+
+    pub fn with_description(mut self, description: &str) -> Self {
+        self.description = Some(description.to_owned());
+        self
+    }
+
+    pub fn required(mut self) -> Self {
+        self.required = true;
+        self
+    }
+
+    pub fn output(mut self) -> Self {
+        self.output = true;
+        self
+    }
+
+    pub fn with_range(mut self, min: f64, max: f64) -> Self {
+        self.min = Some(min);
+        self.max = Some(max);
+        self
+    }
+
+    /// Checks a value given for this parameter. A blank value counts as not given.
+    pub fn validate(&self, value: Option<&str>) -> Result<(), RegistryError> {
+        let invalid = |message: String| RegistryError::InvalidParam {
+            name: self.name.clone(),
+            message,
+        };
+        let Some(value) = value.map(str::trim).filter(|value| !value.is_empty()) else {
+            return if self.required {
+                Err(invalid("missing".to_owned()))
+            } else {
+                Ok(())
+            };
+        };
+        match self.param_type {
+            ParamType::Number => {
+                let number: f64 = value
+                    .parse()
+                    .ok()
+                    .filter(|number: &f64| number.is_finite())
+                    .ok_or_else(|| invalid(format!("'{}' is not a number", value)))?;
+                match (self.min, self.max) {
+                    (Some(min), Some(max)) if !(min..=max).contains(&number) => {
+                        Err(invalid(format!("must be between {} and {}", min, max)))
+                    }
+                    (Some(min), _) if number < min => {
+                        Err(invalid(format!("must be at least {}", min)))
+                    }
+                    (_, Some(max)) if number > max => {
+                        Err(invalid(format!("must be at most {}", max)))
+                    }
+                    _ => Ok(()),
+                }
+            }
+            ParamType::Bool => match value {
+                "true" | "false" => Ok(()),
+                _ => Err(invalid(format!("'{}' is not true or false", value))),
+            },
+            ParamType::Env => {
+                for (line, (name, _)) in env_pairs(value).iter().enumerate() {
+                    if name.is_empty() || name.contains(char::is_whitespace) {
+                        return Err(invalid(format!(
+                            "line {} does not start with a NAME=",
+                            line + 1
+                        )));
+                    }
+                }
+                Ok(())
+            }
+            ParamType::Text => Ok(()),
+            ParamType::String => {
+                if self.choices.is_empty() || self.choices.iter().any(|choice| choice == value) {
+                    Ok(())
+                } else {
+                    Err(invalid(format!(
+                        "must be one of {}",
+                        self.choices.join(", ")
+                    )))
+                }
+            }
+        }
+    }
+}
+
+pub struct TaskKindInfo {
+    pub name: String,
+    pub params: Vec<ParamSpec>,
+    factory: TaskFactory,
+}
+
+/// Every task kind the app knows how to create from parameters, built-in or loaded from plugins.
+pub struct TaskKindRegistry {
+    kinds: Vec<TaskKindInfo>,
+    /// Resolves `${secret:NAME}` in parameters as tasks are created.
+    #[cfg(all(feature = "secrets", not(target_arch = "wasm32")))]
+    secrets: Option<sync_Arc<SecretStore>>,
+}
+
+impl Default for TaskKindRegistry {
+    fn default() -> Self {
+        let mut registry = TaskKindRegistry {
+            kinds: Vec::new(),
+            #[cfg(all(feature = "secrets", not(target_arch = "wasm32")))]
+            secrets: None,
+        };
+        registry.register(
+            "sleep",
+            vec![ParamSpec::new("seconds", ParamType::Number)
+                .with_default("1")
+                .with_range(0.0, MAX_SLEEP_SECONDS)
+                .with_description("How long the task takes")],
+            Box::new(|params| {
+                let seconds = parse_number(params, "seconds")?;
+                Ok(Box::new(SleepTask::new(
+                    None,
+                    Duration::from_secs_f64(seconds),
+                )))
+            }),
+        );
+        registry.register(
+            "primes",
+            vec![ParamSpec::new("below", ParamType::Number)
+                .with_default("10000000")
+                .with_range(0.0, MAX_PRIMES_BELOW)
+                .with_description("Primes are counted up to this number")],
+            Box::new(|params| {
+                let below = parse_number(params, "below")?;
+                let steps = count_primes(below as u64);
+                // On the CPU pool natively; on the web, in slices between frames.
+                #[cfg(not(target_arch = "wasm32"))]
+                let task = JobTask::from_steps(TaskKind::Primes, steps).cpu_bound();
+                #[cfg(target_arch = "wasm32")]
+                let task = ChunkedTask::new(TaskKind::Primes, steps);
+                Ok(Box::new(task))
+            }),
+        );
+        #[cfg(not(target_arch = "wasm32"))]
+        registry.register(
+            "download",
+            vec![
+                ParamSpec::new("url", ParamType::String)
+                    .required()
+                    .with_description("An http or https URL"),
+                ParamSpec::new("path", ParamType::String)
+                    .output()
+                    .with_description("Where to save the file; your downloads folder if blank"),
+            ],
+            Box::new(|params| {
+                let url = required(params, "url")?;
+                let parsed = match url::Url::parse(url) {
+                    Ok(parsed) if matches!(parsed.scheme(), "http" | "https") => parsed,
+                    _ => {
+                        return Err(RegistryError::InvalidParam {
+                            name: "url".to_owned(),
+                            message: format!("'{}' is not an http(s) URL", url),
+                        })
+                    }
+                };
+                let path = match required(params, "path") {
+                    Ok(path) => path.into(),
+                    Err(_) => default_download_path(&parsed),
+                };
+                Ok(Box::new(DownloadTask::new(None, url.to_owned(), path)))
+            }),
+        );
+        registry
+    }
+}
+
+impl TaskKindRegistry {
+    /// The built-in kinds, the `process` kind if enabled, plus any plugins found in the
+    /// configured plugins directory.
+    pub fn with_plugins(config: &AppConfig) -> Self {
+        #[allow(unused_mut)]
+        let mut registry = TaskKindRegistry::default();
+        #[cfg(not(target_arch = "wasm32"))]
+        if config.process_tasks {
+            registry.register_process_kind();
+        }
+        #[cfg(all(
+            any(feature = "plugins", feature = "wasm-plugins"),
+            not(target_arch = "wasm32")
+        ))]
+        match config.resolved_plugins_dir() {
+            Ok(dir) => {
+                #[cfg(feature = "plugins")]
+                crate::app::plugins::load_plugins(&dir, &mut registry);
+                #[cfg(feature = "wasm-plugins")]
+                crate::app::plugins::wasm::load_wasm_plugins(
+                    &dir,
+                    &mut registry,
+                    &config.wasm_http_allow_list,
+                );
+            }
+            Err(e) => log::error!("Cannot locate plugins directory: {}", e),
+        }
+        #[cfg(not(all(
+            any(feature = "plugins", feature = "wasm-plugins"),
+            not(target_arch = "wasm32")
+        )))]
+        let _ = config;
+        registry
+    }
+
+    /// Registers `process`, which runs a program with the given arguments, environment,
+    /// working directory and standard input.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn register_process_kind(&mut self) {
+        self.register(
+            "process",
+            vec![
+                ParamSpec::new("command", ParamType::String)
+                    .required()
+                    .with_description("The program to run, looked up on PATH unless a path"),
+                ParamSpec::new("args", ParamType::String).with_description(
+                    "Arguments separated by spaces; use \"double quotes\" around spaces",
+                ),
+                ParamSpec::new("env", ParamType::Env)
+                    .with_description("Variables set for the program on top of the app's own"),
+                ParamSpec::new("workdir", ParamType::String)
+                    .with_description("Directory to run in; the app's working directory if blank"),
+                ParamSpec::new("stdin", ParamType::Text)
+                    .with_description("Text written to the program's standard input"),
+            ],
+            Box::new(|params| {
+                let invalid = |name: &str, message: String| RegistryError::InvalidParam {
+                    name: name.to_owned(),
+                    message,
+                };
+                let args = split_args(optional(params, "args").unwrap_or_default())
+                    .map_err(|e| invalid("args", e))?;
+                let spec = ProcessSpec {
+                    program: required(params, "command")?.to_owned(),
+                    args,
+                    env: optional(params, "env")
+                        .map_or_else(Default::default, |env| env_pairs(env).into_iter().collect()),
+                    working_dir: optional(params, "workdir").map(std::path::PathBuf::from),
+                    stdin: params
+                        .get("stdin")
+                        .filter(|stdin| !stdin.is_empty())
+                        .cloned(),
+                };
+                Ok(Box::new(ProcessTask::new(None, spec)))
+            }),
+        );
+    }
+
+    /// Registers a task kind, replacing any existing kind with the same name.
+    pub fn register(&mut self, name: &str, params: Vec<ParamSpec>, factory: TaskFactory) {
+        self.kinds.retain(|kind| kind.name != name);
+        self.kinds.push(TaskKindInfo {
+            name: name.to_owned(),
+            params,
+            factory,
+        });
+    }
+
+    pub fn kinds(&self) -> &[TaskKindInfo] {
+        &self.kinds
+    }
+
+    pub fn get(&self, name: &str) -> Option<&TaskKindInfo> {
+        self.kinds.iter().find(|kind| kind.name == name)
+    }
+
+    /// Checks `params` against the schema of kind `name` and returns them with defaults
+    /// filled in for parameters that are missing or blank.
+    pub fn validate(&self, name: &str, params: &TaskParams) -> Result<TaskParams, RegistryError> {
+        let info = self
+            .get(name)
+            .ok_or_else(|| RegistryError::UnknownKind(name.to_owned()))?;
+        let mut params = params.clone();
+        for spec in &info.params {
+            let given = params
+                .get(&spec.name)
+                .map_or(false, |value| !value.trim().is_empty());
+            if !given {
+                if let Some(default) = &spec.default {
+                    params.insert(spec.name.clone(), default.clone());
+                }
+            }
+            spec.validate(params.get(&spec.name).map(String::as_str))?;
+        }
+        Ok(params)
+    }
+
+    /// The key under which a task of kind `name` with `params` is the same as another, e.g.
+    /// a download of the same URL to the same place. `None` if the parameters are not
+    /// valid, as such a task would not be added.
+    pub fn dedupe_key(&self, name: &str, params: &TaskParams) -> Option<DedupeKey> {
+        let params = self
+            .validate(name, params)
+            .ok()?
+            .into_iter()
+            .map(|(param, value)| (param, value.trim().to_owned()))
+            .filter(|(_, value)| !value.is_empty())
+            .collect();
+        Some(DedupeKey {
+            kind: name.to_owned(),
+            params,
+        })
+    }
+
+    #[cfg(all(feature = "secrets", not(target_arch = "wasm32")))]
+    pub fn set_secrets(&mut self, secrets: sync_Arc<SecretStore>) {
+        self.secrets = Some(secrets);
+    }
+
+    /// Builds a task of kind `name` from validated parameters; see [`Self::validate`].
+    /// Secrets are filled in after validation, so they only ever reach the task itself.
+    /// The task then validates its own input, e.g. that paths exist, and
+    /// [`TaskError::InvalidInput`] comes back as [`RegistryError::InvalidParam`].
+    pub fn create(&self, name: &str, params: &TaskParams) -> Result<Box<dyn Task>, RegistryError> {
+        #[allow(unused_mut)]
+        let mut params = self.validate(name, params)?;
+        #[cfg(all(feature = "secrets", not(target_arch = "wasm32")))]
+        if let Some(secrets) = &self.secrets {
+            for (param, value) in params.iter_mut() {
+                *value = secrets
+                    .resolve(value)
+                    .map_err(|e| RegistryError::InvalidParam {
+                        name: param.clone(),
+                        message: e.to_string(),
+                    })?;
+            }
+        }
+        let info = self
+            .get(name)
+            .ok_or_else(|| RegistryError::UnknownKind(name.to_owned()))?;
+        let task = (info.factory)(&params)?;
+        task.validate().map_err(|e| match e {
+            TaskError::InvalidInput { field, message } => RegistryError::InvalidParam {
+                name: field,
+                message,
+            },
+            e => RegistryError::Task(e),
+        })?;
+        Ok(task)
+    }
+}
+
+/// Default parameter values for `info`, used to prefill the New Task window.
+pub fn default_params(info: &TaskKindInfo) -> TaskParams {
+    info.params
+        .iter()
+        .map(|spec| (spec.name.clone(), spec.default.clone().unwrap_or_default()))
+        .collect()
+}
+
+/// The URL's last path segment in the user's downloads folder, or the working directory
+/// when there is none.
+#[cfg(not(target_arch = "wasm32"))]
+fn default_download_path(url: &url::Url) -> std::path::PathBuf {
+    let file_name = url
+        .path_segments()
+        .and_then(|mut segments| segments.next_back())
+        .filter(|name| !name.is_empty())
+        .unwrap_or("download");
+    directories_next::UserDirs::new()
+        .and_then(|dirs| dirs.download_dir().map(|dir| dir.to_owned()))
+        .unwrap_or_default()
+        .join(file_name)
+}
+
+/// The `NAME=value` pairs of an [`ParamType::Env`] value, skipping blank lines. A line
+/// without `=` is a name with an empty value. Names and values are trimmed.
+pub fn env_pairs(value: &str) -> Vec<(String, String)> {
+    value
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| {
+            let (name, value) = line.split_once('=').unwrap_or((line, ""));
+            (name.trim().to_owned(), value.trim().to_owned())
+        })
+        .collect()
+}
+
+/// The value of `name`, or `None` if it is missing or blank.
+#[cfg(not(target_arch = "wasm32"))]
+fn optional<'a>(params: &'a TaskParams, name: &str) -> Option<&'a str> {
+    params
+        .get(name)
+        .map(|value| value.trim())
+        .filter(|value| !value.is_empty())
+}
+
+/// The value of `name`, which must be present and not blank.
+#[cfg_attr(target_arch = "wasm32", allow(dead_code))]
+fn required<'a>(params: &'a TaskParams, name: &str) -> Result<&'a str, RegistryError> {
+    match params.get(name).map(|value| value.trim()) {
+        Some(value) if !value.is_empty() => Ok(value),
+        _ => Err(RegistryError::InvalidParam {
+            name: name.to_owned(),
+            message: "missing".to_owned(),
+        }),
+    }
+}
+
+fn parse_number(params: &TaskParams, name: &str) -> Result<f64, RegistryError> {
+    let value = params
+        .get(name)
+        .ok_or_else(|| RegistryError::InvalidParam {
+            name: name.to_owned(),
+            message: "missing".to_owned(),
+        })?;
+    value
+        .trim()
+        .parse()
+        .map_err(|_| RegistryError::InvalidParam {
+            name: name.to_owned(),
+            message: format!("'{}' is not a number", value),
+        })
+}
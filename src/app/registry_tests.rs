@@ -0,0 +1,223 @@
+#[cfg(all(test, not(target_arch = "wasm32")))]
+use crate::app::download_task::DownloadTask;
+#[cfg(test)]
+use crate::app::registry::{ParamSpec, ParamType, RegistryError, TaskKindRegistry, TaskParams};
+#[cfg(test)]
+use crate::app::task_queue::TaskKind;
+#[cfg(all(test, not(target_arch = "wasm32")))]
+use crate::app::task_queue::{TaskError, TaskQueue};
+
+#[test]
+fn test_create_sleep_task_with_defaults() {
+    let registry = TaskKindRegistry::default();
+    let task = registry.create("sleep", &TaskParams::new()).unwrap();
+    assert_eq!(task.kind(), TaskKind::Sleep);
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+#[test]
+fn test_primes_task_counts_on_the_cpu_pool() {
+    use crate::app::task_queue::PollResult;
+
+    let registry = TaskKindRegistry::default();
+    let mut params = TaskParams::new();
+    params.insert("below".to_owned(), "100000".to_owned());
+    let task_queue = TaskQueue::new();
+    let task_id = task_queue.add_task(registry.create("primes", &params).unwrap());
+    for _ in 0..500 {
+        match task_queue.poll_task(task_id).unwrap() {
+            PollResult::Pending(_) => std::thread::sleep(std::time::Duration::from_millis(10)),
+            finished => return assert_eq!(finished, PollResult::Completed),
+        }
+    }
+    panic!("Primes task did not finish within the expected time");
+}
+
+#[test]
+fn test_unknown_kind() {
+    let registry = TaskKindRegistry::default();
+    let result = registry.create("teleport", &TaskParams::new());
+    assert_eq!(
+        result.err(),
+        Some(RegistryError::UnknownKind("teleport".to_owned()))
+    );
+}
+
+#[test]
+fn test_invalid_param() {
+    let registry = TaskKindRegistry::default();
+    let mut params = TaskParams::new();
+    params.insert("seconds".to_owned(), "soon".to_owned());
+    assert!(matches!(
+        registry.create("sleep", &params),
+        Err(RegistryError::InvalidParam { name, .. }) if name == "seconds"
+    ));
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+#[test]
+fn test_create_download_task() {
+    let registry = TaskKindRegistry::default();
+    let mut params = TaskParams::new();
+    params.insert("url".to_owned(), "ftp://example.com/file".to_owned());
+    params.insert("path".to_owned(), "file".to_owned());
+    assert!(matches!(
+        registry.create("download", &params),
+        Err(RegistryError::InvalidParam { name, .. }) if name == "url"
+    ));
+    params.insert("url".to_owned(), "https://example.com/file".to_owned());
+    let task = registry.create("download", &params).unwrap();
+    assert_eq!(task.kind(), TaskKind::Download);
+    params.remove("path");
+    assert!(registry.create("download", &params).is_ok());
+    params.remove("url");
+    assert!(registry.create("download", &params).is_err());
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+#[test]
+fn test_task_input_is_validated_before_queueing() {
+    let registry = TaskKindRegistry::default();
+    let missing = std::env::temp_dir().join(format!("missing_{}", std::process::id()));
+    let mut params = TaskParams::new();
+    params.insert("url".to_owned(), "https://example.com/file".to_owned());
+    params.insert(
+        "path".to_owned(),
+        missing.join("file").to_string_lossy().into_owned(),
+    );
+    assert!(matches!(
+        registry.create("download", &params),
+        Err(RegistryError::InvalidParam { name, .. }) if name == "path"
+    ));
+
+    let task = DownloadTask::new(
+        None,
+        "not a url".to_owned(),
+        std::env::temp_dir().join("file"),
+    );
+    assert!(matches!(
+        TaskQueue::new().try_add_task(task),
+        Err(TaskError::InvalidInput { field, .. }) if field == "url"
+    ));
+
+    let config = crate::app::config::AppConfig {
+        process_tasks: true,
+        ..Default::default()
+    };
+    let registry = TaskKindRegistry::with_plugins(&config);
+    let mut params = TaskParams::new();
+    params.insert("command".to_owned(), "true".to_owned());
+    params.insert("workdir".to_owned(), missing.to_string_lossy().into_owned());
+    assert!(matches!(
+        registry.create("process", &params),
+        Err(RegistryError::InvalidParam { name, .. }) if name == "workdir"
+    ));
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+#[test]
+fn test_dedupe_key() {
+    let registry = TaskKindRegistry::default();
+    let params = |url: &str, path: &str| -> TaskParams {
+        [
+            ("url".to_owned(), url.to_owned()),
+            ("path".to_owned(), path.to_owned()),
+        ]
+        .into_iter()
+        .collect()
+    };
+    let key = registry.dedupe_key("download", &params("https://example.com/a.bin", ""));
+    assert!(key.is_some());
+    assert_eq!(
+        registry.dedupe_key("download", &params(" https://example.com/a.bin ", "  ")),
+        key
+    );
+    assert_ne!(
+        registry.dedupe_key(
+            "download",
+            &params("https://example.com/a.bin", "/tmp/a.bin")
+        ),
+        key
+    );
+    assert_ne!(
+        registry.dedupe_key("download", &params("https://example.com/b.bin", "")),
+        key
+    );
+    assert_eq!(registry.dedupe_key("download", &params("", "")), None);
+
+    let mut seconds = TaskParams::new();
+    assert_eq!(
+        registry.dedupe_key("sleep", &seconds),
+        registry.dedupe_key("sleep", &[("seconds".to_owned(), "1".to_owned())].into())
+    );
+    seconds.insert("seconds".to_owned(), "2".to_owned());
+    assert_ne!(
+        registry.dedupe_key("sleep", &seconds),
+        registry.dedupe_key("sleep", &TaskParams::new())
+    );
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+#[test]
+fn test_process_kind_is_opt_in() {
+    let mut config = crate::app::config::AppConfig::default();
+    assert!(TaskKindRegistry::with_plugins(&config)
+        .get("process")
+        .is_none());
+    config.process_tasks = true;
+    let registry = TaskKindRegistry::with_plugins(&config);
+    let mut params = TaskParams::new();
+    params.insert("command".to_owned(), "true".to_owned());
+    params.insert("env".to_owned(), "A=1\nnot a name=2".to_owned());
+    assert!(matches!(
+        registry.create("process", &params),
+        Err(RegistryError::InvalidParam { name, .. }) if name == "env"
+    ));
+    params.insert("env".to_owned(), "A=1\n\nB = two words".to_owned());
+    let task = registry.create("process", &params).unwrap();
+    assert_eq!(task.kind(), TaskKind::Process);
+}
+
+#[test]
+fn test_schema_validation() {
+    let registry = TaskKindRegistry::default();
+    let mut params = TaskParams::new();
+    params.insert("seconds".to_owned(), "-1".to_owned());
+    assert_eq!(
+        registry.validate("sleep", &params).err(),
+        Some(RegistryError::InvalidParam {
+            name: "seconds".to_owned(),
+            message: "must be between 0 and 31536000".to_owned(),
+        })
+    );
+    // A blank value takes the default.
+    params.insert("seconds".to_owned(), " ".to_owned());
+    assert_eq!(registry.validate("sleep", &params).unwrap()["seconds"], "1");
+
+    let format = ParamSpec {
+        choices: vec!["png".to_owned(), "webp".to_owned()],
+        ..ParamSpec::new("format", ParamType::String).required()
+    };
+    assert!(format.validate(Some("webp")).is_ok());
+    assert!(format.validate(Some("gif")).is_err());
+    assert!(format.validate(None).is_err());
+
+    let flag = ParamSpec::new("overwrite", ParamType::Bool);
+    assert!(flag.validate(Some("true")).is_ok());
+    assert!(flag.validate(Some("yes")).is_err());
+    assert!(flag.validate(None).is_ok());
+
+    let count = ParamSpec {
+        min: Some(1.0),
+        ..ParamSpec::new("count", ParamType::Number)
+    };
+    assert!(count.validate(Some("0")).is_err());
+    assert!(count.validate(Some("inf")).is_err());
+    assert!(count.validate(Some("1e9")).is_ok());
+}
+
+#[test]
+fn test_plugin_schema_defaults() {
+    let spec: ParamSpec = serde_json::from_str(r#"{"name": "n", "type": "number"}"#).unwrap();
+    assert_eq!(spec, ParamSpec::new("n", ParamType::Number));
+}
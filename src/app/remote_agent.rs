@@ -0,0 +1,817 @@
+//! Runs tasks on a headless agent on another machine.
+//!
+//! An agent is this app started with `--agent`; it listens for WebSocket connections on
+//! `[agent].listen`. The UI connects to each `[[remote_agents]]` entry, dispatches tasks as
+//! JSON messages and gets progress streamed back. Files listed in a task's `fetch` are sent
+//! back once it completes, each as a `file` message followed by one binary message.
+//!
+//! Every connection must start with a `hello` carrying one of the agent's tokens, whose
+//! scope decides what the connection may do: list tasks with a read-only token, also run
+//! and control its own tasks with a control token, and control anyone's with an admin
+//! token. Anyone holding a control or admin token can run tasks on the agent and read
+//! any file the agent can.
+
+use std::collections::HashMap;
+use std::fmt::{Display, Formatter, Result as FmtResult};
+use std::io::ErrorKind;
+use std::net::{TcpListener, TcpStream};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::mpsc::{self, Sender};
+use std::sync::{Arc as sync_Arc, Mutex as sync_Mutex};
+use std::time::Duration;
+
+use tungstenite::{Message, WebSocket};
+
+use crate::app::audit::{AuditAction, Interface, Origin};
+use crate::app::config::{ApiScope, ApiToken, AppConfig, RemoteAgentConfig};
+use crate::app::history::TaskRecord;
+use crate::app::registry::{TaskKindRegistry, TaskParams};
+use crate::app::task_id::TaskId;
+use crate::app::task_queue::{
+    PollResult, PollingData, ProgressEvent, Task, TaskError, TaskKind, TaskQueue, TaskStatus,
+};
+
+/// How long a connection waits for a message before polling its tasks again.
+const TICK: Duration = Duration::from_millis(100);
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum AgentError {
+    InvalidUrl(String),
+    Connect(String),
+    Protocol(String),
+    Disconnected,
+}
+
+impl Display for AgentError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        match self {
+            AgentError::InvalidUrl(e) => write!(f, "Invalid agent URL: {}", e),
+            AgentError::Connect(e) => write!(f, "Cannot connect to agent: {}", e),
+            AgentError::Protocol(e) => write!(f, "Agent protocol error: {}", e),
+            AgentError::Disconnected => write!(f, "Agent disconnected"),
+        }
+    }
+}
+
+/// Messages in both directions; `task` is the controller's id for the task.
+#[derive(Debug, Clone, PartialEq, serde::Deserialize, serde::Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum AgentMessage {
+    /// First message from the controller; the agent hangs up if the token is wrong.
+    Hello {
+        token: String,
+    },
+    /// Asks for a `tasks` reply listing every task on the agent.
+    List,
+    Tasks {
+        tasks: Vec<TaskRecord>,
+    },
+    /// Changes a task by its id on the agent, as listed in `tasks`, whoever ran it.
+    Manage {
+        id: usize,
+        change: JobChange,
+    },
+    Run {
+        task: u64,
+        kind: String,
+        #[serde(default)]
+        params: TaskParams,
+        /// Paths on the agent to send back once the task completes.
+        #[serde(default)]
+        fetch: Vec<String>,
+    },
+    Pause {
+        task: u64,
+    },
+    Resume {
+        task: u64,
+    },
+    Cancel {
+        task: u64,
+    },
+    Progress {
+        task: u64,
+        status: TaskStatus,
+        progress: f32,
+        /// Why the task failed, when its status is failed.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        error: Option<String>,
+    },
+    Rejected {
+        task: u64,
+        error: String,
+    },
+    /// Refuses a request that is not about one of the controller's tasks.
+    Refused {
+        error: String,
+    },
+    /// Announces the file whose contents follow in the next binary message.
+    File {
+        task: u64,
+        name: String,
+    },
+}
+
+/// A change to a task on the agent.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum JobChange {
+    Pause,
+    Resume,
+    Cancel,
+}
+
+impl JobChange {
+    fn apply(self, queue: &TaskQueue, id: TaskId) -> Result<(), TaskError> {
+        match self {
+            JobChange::Pause => queue.pause_task(id),
+            JobChange::Resume => queue.resume_task(id),
+            JobChange::Cancel => queue.remove_task(id),
+        }
+    }
+
+    fn action(self) -> AuditAction {
+        match self {
+            JobChange::Pause => AuditAction::Pause,
+            JobChange::Resume => AuditAction::Resume,
+            JobChange::Cancel => AuditAction::Cancel,
+        }
+    }
+}
+
+fn send_message<S: std::io::Read + std::io::Write>(
+    socket: &mut WebSocket<S>,
+    message: &AgentMessage,
+) -> Result<(), AgentError> {
+    let text = serde_json::to_string(message).map_err(|e| AgentError::Protocol(e.to_string()))?;
+    socket
+        .send(Message::Text(text))
+        .map_err(|e| AgentError::Protocol(e.to_string()))
+}
+
+/// Reads the next message, or `None` when the read timed out.
+fn read_message<S: std::io::Read + std::io::Write>(
+    socket: &mut WebSocket<S>,
+) -> Result<Option<Message>, AgentError> {
+    match socket.read() {
+        Ok(Message::Close(_)) => Err(AgentError::Disconnected),
+        Ok(message) => Ok(Some(message)),
+        Err(tungstenite::Error::Io(e))
+            if matches!(e.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut) =>
+        {
+            Ok(None)
+        }
+        Err(tungstenite::Error::ConnectionClosed | tungstenite::Error::AlreadyClosed) => {
+            Err(AgentError::Disconnected)
+        }
+        Err(e) => Err(AgentError::Protocol(e.to_string())),
+    }
+}
+
+fn parse(text: &str) -> Result<AgentMessage, AgentError> {
+    serde_json::from_str(text).map_err(|e| AgentError::Protocol(e.to_string()))
+}
+
+// ---- Agent side ----
+
+/// Runs headless as an agent, serving `[agent]` from the config until the process is killed.
+pub fn run_agent() -> std::io::Result<()> {
+    let config = AppConfig::default_path()
+        .and_then(|path| AppConfig::load_or_default(&path))
+        .unwrap_or_else(|e| {
+            log::error!("Using default config: {}", e);
+            AppConfig::default()
+        });
+    config.apply_globals();
+    let tokens = config.agent.api_tokens();
+    if tokens.is_empty() {
+        return Err(std::io::Error::new(
+            ErrorKind::InvalidInput,
+            "set [agent].token or add [[agent.tokens]] to the config before running as an agent",
+        ));
+    }
+    let listener = TcpListener::bind(&config.agent.listen)?;
+    log::info!("Agent listening on {}", listener.local_addr()?);
+    let registry = sync_Arc::new(TaskKindRegistry::with_plugins(&config));
+    let queue = TaskQueue::new();
+    queue.set_progress_granularity(config.progress_granularity());
+    serve_agent(listener, tokens, sync_Arc::new(queue), registry)
+        .join()
+        .map_err(|_| std::io::Error::new(ErrorKind::Other, "agent thread panicked"))
+}
+
+/// Accepts controllers on `listener`, each served on its own thread with the scope of
+/// the token in its `hello`.
+pub fn serve_agent(
+    listener: TcpListener,
+    tokens: Vec<ApiToken>,
+    queue: sync_Arc<TaskQueue>,
+    registry: sync_Arc<TaskKindRegistry>,
+) -> std::thread::JoinHandle<()> {
+    std::thread::spawn(move || {
+        for stream in listener.incoming() {
+            let stream = match stream {
+                Ok(stream) => stream,
+                Err(e) => {
+                    log::warn!("Agent accept failed: {}", e);
+                    continue;
+                }
+            };
+            let tokens = tokens.clone();
+            let queue = queue.clone();
+            let registry = registry.clone();
+            std::thread::spawn(move || {
+                let peer = stream.peer_addr().map(|addr| addr.to_string());
+                match serve_controller(stream, &tokens, &queue, &registry) {
+                    Ok(()) | Err(AgentError::Disconnected) => {
+                        log::info!("Controller {:?} disconnected", peer)
+                    }
+                    Err(e) => log::warn!("Controller {:?}: {}", peer, e),
+                }
+            });
+        }
+    })
+}
+
+struct AgentJob {
+    local_id: TaskId,
+    fetch: Vec<String>,
+    reported: Option<(TaskStatus, f32)>,
+}
+
+fn serve_controller(
+    stream: TcpStream,
+    tokens: &[ApiToken],
+    queue: &TaskQueue,
+    registry: &TaskKindRegistry,
+) -> Result<(), AgentError> {
+    let peer = stream
+        .peer_addr()
+        .map_or_else(|_| "unknown".to_owned(), |addr| addr.to_string());
+    let mut socket =
+        tungstenite::accept(stream).map_err(|e| AgentError::Protocol(e.to_string()))?;
+    let presented = match read_message(&mut socket)? {
+        Some(Message::Text(text)) => match parse(&text)? {
+            AgentMessage::Hello { token } => token,
+            _ => return Err(AgentError::Protocol("expected hello".to_owned())),
+        },
+        _ => return Err(AgentError::Protocol("expected hello".to_owned())),
+    };
+    let token = tokens
+        .iter()
+        .find(|token| same_secret(&token.token, &presented))
+        .ok_or_else(|| AgentError::Protocol("wrong token".to_owned()))?;
+    log::info!(
+        "Controller {} connected with {} token '{}'",
+        peer,
+        token.scope,
+        token.name
+    );
+    let origin = Origin::new(Interface::RemoteAgent, format!("{} ({})", token.name, peer));
+    socket
+        .get_ref()
+        .set_read_timeout(Some(TICK))
+        .map_err(|e| AgentError::Protocol(e.to_string()))?;
+
+    let mut jobs: HashMap<u64, AgentJob> = HashMap::new();
+    let progress = queue.subscribe_progress();
+    let result = loop {
+        match read_message(&mut socket) {
+            Ok(Some(Message::Text(text))) => {
+                let reply = match parse(&text) {
+                    Ok(message) => {
+                        handle_command(message, &mut jobs, queue, registry, &origin, token.scope)
+                    }
+                    Err(e) => {
+                        log::warn!("{}", e);
+                        None
+                    }
+                };
+                if let Some(reply) = reply {
+                    if let Err(e) = send_message(&mut socket, &reply) {
+                        break Err(e);
+                    }
+                }
+            }
+            Ok(_) => {}
+            Err(e) => break Err(e),
+        }
+        if let Err(e) = report_progress(&mut socket, &mut jobs, queue, &progress) {
+            break Err(e);
+        }
+    };
+    // Nobody is left to report to, so stop what this controller started.
+    for job in jobs.values() {
+        if queue.remove_task(job.local_id).is_ok() {
+            queue.audit(job.local_id, AuditAction::Cancel, &origin);
+        }
+    }
+    result
+}
+
+/// Whether two secrets are equal, taking as long to tell as their lengths allow, so that
+/// timing replies does not reveal how much of a guess was right.
+fn same_secret(expected: &str, presented: &str) -> bool {
+    expected.len() == presented.len()
+        && expected
+            .bytes()
+            .zip(presented.bytes())
+            .fold(0, |differences, (a, b)| differences | (a ^ b))
+            == 0
+}
+
+/// The scope a controller's request needs, or `None` for messages controllers do not send.
+fn required_scope(message: &AgentMessage) -> Option<ApiScope> {
+    match message {
+        AgentMessage::List => Some(ApiScope::Read),
+        AgentMessage::Run { .. }
+        | AgentMessage::Pause { .. }
+        | AgentMessage::Resume { .. }
+        | AgentMessage::Cancel { .. } => Some(ApiScope::Control),
+        AgentMessage::Manage { .. } => Some(ApiScope::Admin),
+        _ => None,
+    }
+}
+
+fn handle_command(
+    message: AgentMessage,
+    jobs: &mut HashMap<u64, AgentJob>,
+    queue: &TaskQueue,
+    registry: &TaskKindRegistry,
+    origin: &Origin,
+    scope: ApiScope,
+) -> Option<AgentMessage> {
+    if required_scope(&message).map_or(false, |required| scope < required) {
+        let error = format!("Not allowed with a {} token", scope);
+        log::warn!("Refused {:?} from {}: {}", message, origin, error);
+        return Some(match message {
+            AgentMessage::Run { task, .. }
+            | AgentMessage::Pause { task }
+            | AgentMessage::Resume { task }
+            | AgentMessage::Cancel { task } => AgentMessage::Rejected { task, error },
+            _ => AgentMessage::Refused { error },
+        });
+    }
+    let (task, result) = match message {
+        AgentMessage::List => {
+            return Some(AgentMessage::Tasks {
+                tasks: queue.records(),
+            })
+        }
+        AgentMessage::Manage { id, change } => {
+            let id = queue.task_id(id);
+            return match change.apply(queue, id) {
+                Ok(()) => {
+                    queue.audit(id, change.action(), origin);
+                    None
+                }
+                Err(e) => Some(AgentMessage::Refused {
+                    error: e.to_string(),
+                }),
+            };
+        }
+        AgentMessage::Run {
+            task,
+            kind,
+            params,
+            fetch,
+        } => match registry.create(&kind, &params) {
+            Ok(created) => {
+                let local_id = queue.add_task(created);
+                queue.audit(local_id, AuditAction::Add, origin);
+                jobs.insert(
+                    task,
+                    AgentJob {
+                        local_id,
+                        fetch,
+                        reported: None,
+                    },
+                );
+                (task, Ok(()))
+            }
+            Err(e) => (task, Err(e.to_string())),
+        },
+        AgentMessage::Pause { task } => (
+            task,
+            change_job(jobs, task, queue, origin, JobChange::Pause),
+        ),
+        AgentMessage::Resume { task } => (
+            task,
+            change_job(jobs, task, queue, origin, JobChange::Resume),
+        ),
+        AgentMessage::Cancel { task } => (
+            task,
+            change_job(jobs, task, queue, origin, JobChange::Cancel),
+        ),
+        other => {
+            log::warn!("Ignoring unexpected message from controller: {:?}", other);
+            return None;
+        }
+    };
+    result
+        .err()
+        .map(|error| AgentMessage::Rejected { task, error })
+}
+
+/// Makes `change` to the local task running the controller's `task`, and audits it.
+fn change_job(
+    jobs: &HashMap<u64, AgentJob>,
+    task: u64,
+    queue: &TaskQueue,
+    origin: &Origin,
+    change: JobChange,
+) -> Result<(), String> {
+    let job = jobs
+        .get(&task)
+        .ok_or_else(|| TaskError::NotFound.to_string())?;
+    change
+        .apply(queue, job.local_id)
+        .map_err(|e| e.to_string())?;
+    queue.audit(job.local_id, change.action(), origin);
+    Ok(())
+}
+
+/// Polls every job and reports the ones whose status changed, or whose progress the
+/// queue reported in `progress`, sending the requested files before a completed job's
+/// final report.
+fn report_progress(
+    socket: &mut WebSocket<TcpStream>,
+    jobs: &mut HashMap<u64, AgentJob>,
+    queue: &TaskQueue,
+    progress: &mpsc::Receiver<ProgressEvent>,
+) -> Result<(), AgentError> {
+    let mut finished = Vec::new();
+    let mut polled = Vec::new();
+    for (&task, job) in jobs.iter() {
+        match queue.poll_task(job.local_id) {
+            Ok(result) => polled.push((task, result)),
+            Err(_) => finished.push(task),
+        }
+    }
+    // Progress of this connection's jobs that moved enough to be worth sending.
+    let due: HashMap<TaskId, f32> = progress
+        .try_iter()
+        .map(|event| (event.id, event.progress))
+        .collect();
+    for (task, result) in polled {
+        let Some(job) = jobs.get_mut(&task) else {
+            continue;
+        };
+        let status = TaskStatus::from(&result);
+        let status_changed = job
+            .reported
+            .as_ref()
+            .map_or(true, |(reported, _)| *reported != status);
+        let progress = match &result {
+            PollResult::Pending(data) | PollResult::Paused(data) => match due.get(&job.local_id) {
+                Some(due) => *due,
+                None if status_changed => data.fraction(),
+                None => continue,
+            },
+            PollResult::Completed => 1.0,
+            PollResult::Cancelled | PollResult::Failed(_) => {
+                job.reported.as_ref().map_or(0.0, |(_, p)| *p)
+            }
+        };
+        let current = Some((status.clone(), progress));
+        if job.reported == current {
+            continue;
+        }
+        if status == TaskStatus::Completed {
+            for path in &job.fetch {
+                send_file(socket, task, Path::new(path))?;
+            }
+        }
+        send_message(
+            socket,
+            &AgentMessage::Progress {
+                task,
+                status: status.clone(),
+                progress,
+                error: match &result {
+                    PollResult::Failed(e) => Some(e.to_string()),
+                    _ => None,
+                },
+            },
+        )?;
+        job.reported = current;
+        if status.is_terminal() {
+            finished.push(task);
+        }
+    }
+    for task in finished {
+        jobs.remove(&task);
+    }
+    Ok(())
+}
+
+fn send_file(socket: &mut WebSocket<TcpStream>, task: u64, path: &Path) -> Result<(), AgentError> {
+    let contents = match std::fs::read(path) {
+        Ok(contents) => contents,
+        Err(e) => {
+            log::warn!("Cannot send {}: {}", path.display(), e);
+            return Ok(());
+        }
+    };
+    let name = path
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_else(|| "output".to_owned());
+    send_message(socket, &AgentMessage::File { task, name })?;
+    socket
+        .send(Message::Binary(contents))
+        .map_err(|e| AgentError::Protocol(e.to_string()))
+}
+
+// ---- Controller side ----
+
+#[derive(Debug, Clone, PartialEq)]
+struct RemoteState {
+    status: TaskStatus,
+    progress: f32,
+    /// As reported by the agent once the task failed.
+    error: Option<String>,
+}
+
+type RemoteStates = sync_Arc<sync_Mutex<HashMap<u64, sync_Arc<sync_Mutex<RemoteState>>>>>;
+
+/// A connection to one agent, shared by every task dispatched to it.
+pub struct AgentClient {
+    name: String,
+    outgoing: Sender<AgentMessage>,
+    states: RemoteStates,
+    next_task: AtomicU64,
+    connected: sync_Arc<AtomicBool>,
+}
+
+impl AgentClient {
+    pub fn connect(config: &RemoteAgentConfig) -> Result<sync_Arc<Self>, AgentError> {
+        let url =
+            url::Url::parse(&config.url).map_err(|e| AgentError::InvalidUrl(e.to_string()))?;
+        if url.scheme() != "ws" {
+            return Err(AgentError::InvalidUrl(format!(
+                "{} (only ws:// is supported)",
+                config.url
+            )));
+        }
+        let host = url
+            .host_str()
+            .ok_or_else(|| AgentError::InvalidUrl(config.url.clone()))?;
+        let stream = TcpStream::connect((host, url.port().unwrap_or(80)))
+            .map_err(|e| AgentError::Connect(e.to_string()))?;
+        let (mut socket, _) = tungstenite::client(config.url.as_str(), stream)
+            .map_err(|e| AgentError::Connect(e.to_string()))?;
+        send_message(
+            &mut socket,
+            &AgentMessage::Hello {
+                token: config.token.clone(),
+            },
+        )?;
+        socket
+            .get_ref()
+            .set_read_timeout(Some(TICK))
+            .map_err(|e| AgentError::Connect(e.to_string()))?;
+
+        let (outgoing, pending) = mpsc::channel();
+        let client = sync_Arc::new(AgentClient {
+            name: config.name.clone(),
+            outgoing,
+            states: RemoteStates::default(),
+            next_task: AtomicU64::new(0),
+            connected: sync_Arc::new(AtomicBool::new(true)),
+        });
+        let states = client.states.clone();
+        let connected = client.connected.clone();
+        let download_dir = config
+            .download_dir
+            .clone()
+            .unwrap_or_else(|| PathBuf::from("."));
+        let name = config.name.clone();
+        std::thread::spawn(move || {
+            let mut incoming_file: Option<String> = None;
+            let result = loop {
+                if let Err(e) = pending
+                    .try_iter()
+                    .try_for_each(|message| send_message(&mut socket, &message))
+                {
+                    break e;
+                }
+                match read_message(&mut socket) {
+                    Ok(Some(Message::Text(text))) => match parse(&text) {
+                        Ok(AgentMessage::File { name, .. }) => incoming_file = Some(name),
+                        Ok(message) => apply_report(&states, message),
+                        Err(e) => log::warn!("{}", e),
+                    },
+                    Ok(Some(Message::Binary(contents))) => {
+                        if let Some(file_name) = incoming_file.take() {
+                            save_file(&download_dir, &file_name, &contents);
+                        }
+                    }
+                    Ok(_) => {}
+                    Err(e) => break e,
+                }
+            };
+            log::warn!("Agent '{}': {}", name, result);
+            connected.store(false, Ordering::SeqCst);
+            for state in states.lock().unwrap().values() {
+                let mut state = state.lock().unwrap();
+                if !state.status.is_terminal() {
+                    state.status = TaskStatus::Cancelled;
+                }
+            }
+        });
+        Ok(client)
+    }
+
+    pub fn is_connected(&self) -> bool {
+        self.connected.load(Ordering::SeqCst)
+    }
+
+    /// A task that runs `kind` on this agent once the queue first polls it.
+    pub fn task(
+        self: &sync_Arc<Self>,
+        kind: &str,
+        params: TaskParams,
+        fetch: Vec<String>,
+    ) -> RemoteTask {
+        let task = self.next_task.fetch_add(1, Ordering::SeqCst);
+        let state = sync_Arc::new(sync_Mutex::new(RemoteState {
+            status: TaskStatus::Queued,
+            progress: 0.0,
+            error: None,
+        }));
+        self.states.lock().unwrap().insert(task, state.clone());
+        RemoteTask {
+            id: None,
+            kind: TaskKind::Remote(format!("{}@{}", kind, self.name)),
+            client: self.clone(),
+            task,
+            request: Some(AgentMessage::Run {
+                task,
+                kind: kind.to_owned(),
+                params,
+                fetch,
+            }),
+            state,
+        }
+    }
+
+    fn send(&self, message: AgentMessage) -> Result<(), TaskError> {
+        self.outgoing.send(message).map_err(|_| TaskError::NotFound)
+    }
+}
+
+/// Applies an agent's report. The agent decides when a task starts and finishes; pausing
+/// and resuming are decided here, so a report crossing a pause request cannot undo it.
+fn apply_report(states: &RemoteStates, message: AgentMessage) {
+    let (task, status, progress, error) = match message {
+        AgentMessage::Progress {
+            task,
+            status,
+            progress,
+            error,
+        } => (task, Some(status), Some(progress), error),
+        AgentMessage::Rejected { task, error } => {
+            log::error!("Agent rejected task {}: {}", task, error);
+            (task, Some(TaskStatus::Failed), None, Some(error))
+        }
+        _ => return,
+    };
+    let Some(state) = states.lock().unwrap().get(&task).cloned() else {
+        return;
+    };
+    let mut state = state.lock().unwrap();
+    if let Some(progress) = progress {
+        state.progress = progress;
+    }
+    if let Some(status) = status {
+        if status.is_terminal() || state.status == TaskStatus::Queued {
+            state.status = status;
+            state.error = error;
+        }
+    }
+}
+
+fn save_file(download_dir: &Path, file_name: &str, contents: &[u8]) {
+    // Only the last path component, so an agent cannot write outside the download dir.
+    let Some(file_name) = Path::new(file_name).file_name() else {
+        return;
+    };
+    let path = download_dir.join(file_name);
+    match std::fs::create_dir_all(download_dir).and_then(|()| std::fs::write(&path, contents)) {
+        Ok(()) => log::info!("Received {}", path.display()),
+        Err(e) => log::error!("Cannot save {}: {}", path.display(), e),
+    }
+}
+
+/// A task running on a remote agent, mirrored into the local queue.
+pub struct RemoteTask {
+    id: Option<TaskId>,
+    kind: TaskKind,
+    client: sync_Arc<AgentClient>,
+    task: u64,
+    /// The `run` message, until it is sent on the first poll.
+    request: Option<AgentMessage>,
+    state: sync_Arc<sync_Mutex<RemoteState>>,
+}
+
+impl RemoteTask {
+    fn status(&self) -> TaskStatus {
+        self.state.lock().unwrap().status.clone()
+    }
+
+    fn set_status(&self, status: TaskStatus) {
+        self.state.lock().unwrap().status = status;
+    }
+}
+
+impl Task for RemoteTask {
+    fn id(&self) -> Result<TaskId, TaskError> {
+        self.id.ok_or(TaskError::IdUsizeIsNone)
+    }
+
+    fn set_id(&mut self, id: TaskId) {
+        self.id = Some(id);
+    }
+
+    fn poll(&mut self) -> PollResult {
+        if self.status() == TaskStatus::Queued {
+            if let Some(request) = self.request.take() {
+                if self.client.send(request).is_err() {
+                    self.set_status(TaskStatus::Cancelled);
+                }
+            }
+        }
+        let state = self.state.lock().unwrap().clone();
+        match state.status {
+            TaskStatus::Scheduled | TaskStatus::Queued | TaskStatus::Running => {
+                PollResult::Pending(PollingData::Float(state.progress))
+            }
+            TaskStatus::Paused => PollResult::Paused(PollingData::Float(state.progress)),
+            TaskStatus::Completed => PollResult::Completed,
+            TaskStatus::Cancelled | TaskStatus::Interrupted => PollResult::Cancelled,
+            TaskStatus::Failed => PollResult::Failed(TaskError::Failed {
+                reason: state.error.unwrap_or_default(),
+            }),
+        }
+    }
+
+    fn cancel(&mut self) -> Result<(), TaskError> {
+        match self.status() {
+            TaskStatus::Completed => return Err(TaskError::AlreadyCompleted),
+            TaskStatus::Cancelled | TaskStatus::Failed | TaskStatus::Interrupted => {
+                return Err(TaskError::AlreadyCancelled)
+            }
+            _ => {}
+        }
+        if self.request.take().is_none() {
+            let _ = self.client.send(AgentMessage::Cancel { task: self.task });
+        }
+        self.set_status(TaskStatus::Cancelled);
+        Ok(())
+    }
+
+    fn pause(&mut self) -> Result<(), TaskError> {
+        match self.status() {
+            TaskStatus::Scheduled | TaskStatus::Queued | TaskStatus::Running => {
+                if self.request.is_none() {
+                    self.client.send(AgentMessage::Pause { task: self.task })?;
+                }
+                self.set_status(TaskStatus::Paused);
+                Ok(())
+            }
+            TaskStatus::Paused => Err(TaskError::AlreadyPaused),
+            TaskStatus::Completed => Err(TaskError::AlreadyCompleted),
+            TaskStatus::Cancelled | TaskStatus::Failed | TaskStatus::Interrupted => {
+                Err(TaskError::AlreadyCancelled)
+            }
+        }
+    }
+
+    fn resume(&mut self) -> Result<(), TaskError> {
+        match self.status() {
+            TaskStatus::Paused => {
+                // Paused before it was dispatched: back to the queue so the next poll sends it.
+                if self.request.is_some() {
+                    self.set_status(TaskStatus::Queued);
+                } else {
+                    self.client.send(AgentMessage::Resume { task: self.task })?;
+                    self.set_status(TaskStatus::Running);
+                }
+                Ok(())
+            }
+            TaskStatus::Scheduled | TaskStatus::Queued | TaskStatus::Running => {
+                Err(TaskError::AlreadyRunning)
+            }
+            TaskStatus::Completed => Err(TaskError::AlreadyCompleted),
+            TaskStatus::Cancelled | TaskStatus::Failed | TaskStatus::Interrupted => {
+                Err(TaskError::AlreadyCancelled)
+            }
+        }
+    }
+
+    fn kind(&self) -> TaskKind {
+        self.kind.clone()
+    }
+}
@@ -0,0 +1,261 @@
+#[cfg(test)]
+use std::net::{TcpListener, TcpStream};
+#[cfg(test)]
+use std::sync::Arc;
+#[cfg(test)]
+use std::time::Duration;
+
+#[cfg(test)]
+use tungstenite::{Message, WebSocket};
+
+#[cfg(test)]
+use crate::app::config::{AgentServerConfig, ApiScope, ApiToken, RemoteAgentConfig};
+#[cfg(test)]
+use crate::app::registry::{TaskKindRegistry, TaskParams};
+#[cfg(test)]
+use crate::app::remote_agent::{serve_agent, AgentClient, AgentMessage, JobChange};
+#[cfg(test)]
+use crate::app::task_id::TaskId;
+#[cfg(test)]
+use crate::app::task_queue::{PollResult, TaskError, TaskQueue, TaskStatus};
+
+#[cfg(test)]
+fn start_agent(token: &str) -> RemoteAgentConfig {
+    let config = AgentServerConfig {
+        token: token.to_owned(),
+        ..AgentServerConfig::default()
+    };
+    start_agent_with(config.api_tokens(), Arc::new(TaskQueue::new()), token)
+}
+
+#[cfg(test)]
+fn start_agent_with(
+    tokens: Vec<ApiToken>,
+    queue: Arc<TaskQueue>,
+    token: &str,
+) -> RemoteAgentConfig {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let address = listener.local_addr().unwrap();
+    serve_agent(
+        listener,
+        tokens,
+        queue,
+        Arc::new(TaskKindRegistry::default()),
+    );
+    RemoteAgentConfig {
+        name: "test".to_owned(),
+        url: format!("ws://{}", address),
+        token: token.to_owned(),
+        download_dir: None,
+    }
+}
+
+#[cfg(test)]
+fn poll_until_done(queue: &TaskQueue, id: TaskId) -> PollResult {
+    for _ in 0..100 {
+        match queue.poll_task(id).unwrap() {
+            PollResult::Pending(_) | PollResult::Paused(_) => {
+                std::thread::sleep(Duration::from_millis(50))
+            }
+            done => return done,
+        }
+    }
+    panic!("remote task did not finish");
+}
+
+#[test]
+fn test_message_format() {
+    let message: AgentMessage =
+        serde_json::from_str(r#"{"type": "run", "task": 3, "kind": "sleep"}"#).unwrap();
+    assert_eq!(
+        message,
+        AgentMessage::Run {
+            task: 3,
+            kind: "sleep".to_owned(),
+            params: TaskParams::new(),
+            fetch: Vec::new(),
+        }
+    );
+}
+
+#[test]
+fn test_remote_task_runs_and_fetches_files() {
+    let mut config = start_agent("secret");
+    let dir = std::env::temp_dir().join(format!("remote_agent_test_{}", std::process::id()));
+    let source = dir.join("agent");
+    std::fs::create_dir_all(&source).unwrap();
+    std::fs::write(source.join("result.txt"), "done").unwrap();
+    config.download_dir = Some(dir.join("downloads"));
+
+    let client = AgentClient::connect(&config).unwrap();
+    let params = TaskParams::from([("seconds".to_owned(), "0".to_owned())]);
+    let fetch = vec![source.join("result.txt").display().to_string()];
+    let queue = TaskQueue::new();
+    let id = queue.add_task(client.task("sleep", params, fetch));
+    assert_eq!(poll_until_done(&queue, id), PollResult::Completed);
+    assert_eq!(
+        std::fs::read_to_string(dir.join("downloads").join("result.txt")).unwrap(),
+        "done"
+    );
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_rejected_and_unauthorised() {
+    let config = start_agent("secret");
+    let client = AgentClient::connect(&config).unwrap();
+    let queue = TaskQueue::new();
+    let id = queue.add_task(client.task("teleport", TaskParams::new(), Vec::new()));
+    assert_eq!(
+        poll_until_done(&queue, id),
+        PollResult::Failed(TaskError::Failed {
+            reason: "Unknown task kind: teleport".to_owned()
+        })
+    );
+
+    let wrong = RemoteAgentConfig {
+        token: "guess".to_owned(),
+        ..config
+    };
+    let client = AgentClient::connect(&wrong).unwrap();
+    let id = queue.add_task(client.task("sleep", TaskParams::new(), Vec::new()));
+    assert_eq!(poll_until_done(&queue, id), PollResult::Cancelled);
+    assert!(!client.is_connected());
+}
+
+#[cfg(test)]
+fn token(name: &str, scope: ApiScope) -> ApiToken {
+    ApiToken {
+        name: name.to_owned(),
+        token: format!("{}-secret", name),
+        scope,
+    }
+}
+
+/// A connection speaking the protocol directly, already past `hello`.
+#[cfg(test)]
+fn connect_raw(config: &RemoteAgentConfig) -> WebSocket<TcpStream> {
+    let stream = TcpStream::connect(config.url.trim_start_matches("ws://")).unwrap();
+    let (mut socket, _) = tungstenite::client(config.url.as_str(), stream).unwrap();
+    request(
+        &mut socket,
+        &AgentMessage::Hello {
+            token: config.token.clone(),
+        },
+    );
+    socket
+}
+
+#[cfg(test)]
+fn request(socket: &mut WebSocket<TcpStream>, message: &AgentMessage) {
+    let text = serde_json::to_string(message).unwrap();
+    socket.send(Message::Text(text)).unwrap();
+}
+
+/// The next message that is not a progress report.
+#[cfg(test)]
+fn reply(socket: &mut WebSocket<TcpStream>) -> AgentMessage {
+    loop {
+        if let Message::Text(text) = socket.read().unwrap() {
+            match serde_json::from_str(&text).unwrap() {
+                AgentMessage::Progress { .. } => {}
+                message => return message,
+            }
+        }
+    }
+}
+
+#[test]
+fn test_legacy_token_is_admin() {
+    let config = AgentServerConfig {
+        token: "old".to_owned(),
+        tokens: vec![token("ci", ApiScope::Control)],
+        ..AgentServerConfig::default()
+    };
+    let scopes: Vec<(String, ApiScope)> = config
+        .api_tokens()
+        .into_iter()
+        .map(|token| (token.name, token.scope))
+        .collect();
+    assert_eq!(
+        scopes,
+        vec![
+            ("default".to_owned(), ApiScope::Admin),
+            ("ci".to_owned(), ApiScope::Control)
+        ]
+    );
+    assert!(AgentServerConfig::default().api_tokens().is_empty());
+}
+
+#[test]
+fn test_read_only_token_lists_but_cannot_run() {
+    let queue = Arc::new(TaskQueue::new());
+    let reader = token("dashboard", ApiScope::Read);
+    let mut config = start_agent_with(vec![reader.clone()], queue.clone(), &reader.token);
+    config.token = reader.token;
+
+    let client = AgentClient::connect(&config).unwrap();
+    let local = TaskQueue::new();
+    let id = local.add_task(client.task("sleep", TaskParams::new(), Vec::new()));
+    assert!(matches!(poll_until_done(&local, id), PollResult::Failed(_)));
+    assert!(queue.records().is_empty());
+
+    let mut socket = connect_raw(&config);
+    request(&mut socket, &AgentMessage::List);
+    assert_eq!(
+        reply(&mut socket),
+        AgentMessage::Tasks { tasks: Vec::new() }
+    );
+}
+
+#[test]
+fn test_only_admin_tokens_manage_other_tasks() {
+    let queue = Arc::new(TaskQueue::new());
+    let operator = token("operator", ApiScope::Control);
+    let admin = token("admin", ApiScope::Admin);
+    let config = start_agent_with(
+        vec![operator.clone(), admin.clone()],
+        queue.clone(),
+        &operator.token,
+    );
+    let params = TaskParams::from([("seconds".to_owned(), "60".to_owned())]);
+    let mut socket = connect_raw(&config);
+    request(
+        &mut socket,
+        &AgentMessage::Run {
+            task: 0,
+            kind: "sleep".to_owned(),
+            params,
+            fetch: Vec::new(),
+        },
+    );
+    request(&mut socket, &AgentMessage::List);
+    let AgentMessage::Tasks { tasks } = reply(&mut socket) else {
+        panic!("expected tasks");
+    };
+    let id = tasks[0].id;
+
+    let manage = AgentMessage::Manage {
+        id: id.index(),
+        change: JobChange::Cancel,
+    };
+    let mut intruder = connect_raw(&RemoteAgentConfig {
+        token: operator.token,
+        ..config.clone()
+    });
+    request(&mut intruder, &manage);
+    assert!(matches!(reply(&mut intruder), AgentMessage::Refused { .. }));
+    assert_ne!(queue.records()[0].status, TaskStatus::Cancelled);
+
+    let mut owner = connect_raw(&RemoteAgentConfig {
+        token: admin.token,
+        ..config
+    });
+    request(&mut owner, &manage);
+    request(&mut owner, &AgentMessage::List);
+    assert!(matches!(reply(&mut owner), AgentMessage::Tasks { .. }));
+    assert_eq!(queue.records()[0].status, TaskStatus::Cancelled);
+    let audit = queue.audit_log(Some(queue.task_id(id.index())));
+    assert_eq!(audit.len(), 2);
+    assert!(audit[1].origin.principal.starts_with("admin ("));
+}
@@ -0,0 +1,169 @@
+//! CPU time, and where a task knows it, I/O attributed to individual tasks.
+//!
+//! Tasks share worker threads, so the process-wide numbers say nothing about which task
+//! is busy. Instead each task's work is bracketed: a [`CpuMeter`] adds up the CPU time
+//! its thread spent between the start and end of every poll of the task's future, or
+//! between samples of a [`CpuSpan`] for blocking work. Thread CPU time is read from the OS
+//! on Linux, macOS and Windows; elsewhere, and on the web, tasks report none.
+
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc as sync_Arc;
+use std::task::{Context, Poll};
+use std::time::Duration;
+
+/// What a task has used so far, as returned by
+/// [`Task::resource_usage`](crate::app::task_queue::Task::resource_usage).
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct ResourceUsage {
+    pub cpu_time: Duration,
+    /// Bytes read or written, for tasks that move data.
+    pub io_bytes: Option<u64>,
+}
+
+/// CPU time spent on behalf of one task, summed over the threads that ran it.
+#[derive(Debug, Clone, Default)]
+pub struct CpuMeter {
+    nanos: sync_Arc<AtomicU64>,
+}
+
+impl CpuMeter {
+    pub fn total(&self) -> Duration {
+        Duration::from_nanos(self.nanos.load(Ordering::Relaxed))
+    }
+
+    fn add(&self, time: Duration) {
+        self.nanos
+            .fetch_add(time.as_nanos() as u64, Ordering::Relaxed);
+    }
+
+    /// Wraps `future` so that the CPU time of each of its polls is added to this meter.
+    pub fn measure<F: Future>(&self, future: F) -> Measured<F> {
+        Measured {
+            future: Box::pin(future),
+            meter: self.clone(),
+        }
+    }
+
+    /// Starts adding the current thread's CPU time to this meter, up to each
+    /// [`CpuSpan::sample`] and until the span is dropped. Create, sample and drop it on
+    /// the same thread.
+    pub fn span(&self) -> CpuSpan {
+        CpuSpan {
+            meter: self.clone(),
+            last: thread_cpu_time(),
+        }
+    }
+}
+
+/// A future whose CPU time is added to a [`CpuMeter`]; see [`CpuMeter::measure`].
+pub struct Measured<F> {
+    future: Pin<Box<F>>,
+    meter: CpuMeter,
+}
+
+impl<F: Future> Future for Measured<F> {
+    type Output = F::Output;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<F::Output> {
+        let mut span = self.meter.span();
+        let result = self.future.as_mut().poll(cx);
+        span.sample();
+        result
+    }
+}
+
+/// See [`CpuMeter::span`].
+pub struct CpuSpan {
+    meter: CpuMeter,
+    last: Option<Duration>,
+}
+
+impl CpuSpan {
+    /// Adds the CPU time the thread used since the span started or was last sampled.
+    pub fn sample(&mut self) {
+        let (Some(last), Some(now)) = (self.last, thread_cpu_time()) else {
+            return;
+        };
+        self.meter.add(now.saturating_sub(last));
+        self.last = Some(now);
+    }
+}
+
+impl Drop for CpuSpan {
+    fn drop(&mut self) {
+        self.sample();
+    }
+}
+
+/// CPU time used by the calling thread so far, in user and kernel mode.
+#[cfg(any(target_os = "linux", target_os = "macos"))]
+pub fn thread_cpu_time() -> Option<Duration> {
+    use std::os::raw::{c_int, c_long};
+
+    #[repr(C)]
+    struct Timespec {
+        tv_sec: c_long,
+        tv_nsec: c_long,
+    }
+
+    #[cfg(target_os = "linux")]
+    const CLOCK_THREAD_CPUTIME_ID: c_int = 3;
+    #[cfg(target_os = "macos")]
+    const CLOCK_THREAD_CPUTIME_ID: c_int = 16;
+
+    extern "C" {
+        fn clock_gettime(clock: c_int, time: *mut Timespec) -> c_int;
+    }
+
+    let mut time = Timespec {
+        tv_sec: 0,
+        tv_nsec: 0,
+    };
+    // SAFETY: `time` is a valid, writable timespec.
+    if unsafe { clock_gettime(CLOCK_THREAD_CPUTIME_ID, &mut time) } != 0 {
+        return None;
+    }
+    Some(Duration::new(time.tv_sec as u64, time.tv_nsec as u32))
+}
+
+/// CPU time used by the calling thread so far, in user and kernel mode.
+#[cfg(target_os = "windows")]
+pub fn thread_cpu_time() -> Option<Duration> {
+    #[repr(C)]
+    #[derive(Default)]
+    struct FileTime {
+        low: u32,
+        high: u32,
+    }
+
+    #[link(name = "kernel32")]
+    extern "system" {
+        fn GetCurrentThread() -> isize;
+        fn GetThreadTimes(
+            thread: isize,
+            creation: *mut FileTime,
+            exit: *mut FileTime,
+            kernel: *mut FileTime,
+            user: *mut FileTime,
+        ) -> i32;
+    }
+
+    let mut times: [FileTime; 4] = Default::default();
+    let [creation, exit, kernel, user] = &mut times;
+    // SAFETY: the pseudo-handle of the current thread needs no closing, and all four
+    // out-parameters are valid, writable FILETIMEs.
+    if unsafe { GetThreadTimes(GetCurrentThread(), creation, exit, kernel, user) } == 0 {
+        return None;
+    }
+    let hundred_nanos = |time: &FileTime| (u64::from(time.high) << 32) | u64::from(time.low);
+    Some(Duration::from_nanos(
+        (hundred_nanos(kernel) + hundred_nanos(user)) * 100,
+    ))
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos", target_os = "windows")))]
+pub fn thread_cpu_time() -> Option<Duration> {
+    None
+}
@@ -0,0 +1,202 @@
+//! Headless mode driven by JSON-RPC 2.0 over stdin/stdout, one message per line.
+//!
+//! Methods mirror the control protocol (`add_task`, `poll`, `progress`, `pause`, `resume`,
+//! `cancel`, `annotate`, `list`, `history`, `kinds`) with the same named params. After
+//! `subscribe`, every task status change is sent as a `task_event` notification carrying
+//! the task's record, and progress as a `task_progress` notification as often as
+//! `progress_min_delta` and `progress_min_interval_ms` allow.
+//! Logs go to stderr so they never interleave with protocol messages.
+
+use std::io::{BufRead, Write};
+use std::sync::{Arc as sync_Arc, Mutex as sync_Mutex};
+use std::time::Duration;
+
+use serde_json::{json, Map, Value};
+
+use crate::app::audit::{Interface, Origin};
+use crate::app::config::AppConfig;
+use crate::app::control::{ControlHandler, ControlRequest};
+use crate::app::registry::TaskKindRegistry;
+use crate::app::task_queue::TaskQueue;
+
+const CONTROL_METHODS: [&str; 10] = [
+    "add_task", "poll", "progress", "pause", "resume", "cancel", "annotate", "list", "history",
+    "kinds",
+];
+/// How often unfinished tasks are polled; with no window, nothing else drives them.
+const DRIVE_INTERVAL: Duration = Duration::from_millis(50);
+
+const PARSE_ERROR: i64 = -32700;
+const INVALID_REQUEST: i64 = -32600;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+const SERVER_ERROR: i64 = -32000;
+
+pub struct RpcServer<W: Write + Send + 'static> {
+    queue: sync_Arc<TaskQueue>,
+    handler: ControlHandler,
+    output: sync_Arc<sync_Mutex<W>>,
+    subscribed: bool,
+}
+
+impl<W: Write + Send + 'static> RpcServer<W> {
+    pub fn new(
+        queue: sync_Arc<TaskQueue>,
+        registry: sync_Arc<TaskKindRegistry>,
+        output: sync_Arc<sync_Mutex<W>>,
+    ) -> Self {
+        RpcServer {
+            handler: ControlHandler::new(
+                queue.clone(),
+                registry,
+                Origin::local(Interface::RpcStdio),
+            ),
+            queue,
+            output,
+            subscribed: false,
+        }
+    }
+
+    /// Handles one incoming line, returning the response, or `None` for notifications.
+    pub fn handle_message(&mut self, line: &str) -> Option<Value> {
+        let message: Value = match serde_json::from_str(line) {
+            Ok(message) => message,
+            Err(e) => return Some(error_response(Value::Null, PARSE_ERROR, e.to_string())),
+        };
+        let id = message.get("id").cloned();
+        let method = match message.get("method").and_then(Value::as_str) {
+            Some(method) => method,
+            None => {
+                return Some(error_response(
+                    id.unwrap_or(Value::Null),
+                    INVALID_REQUEST,
+                    "missing method".to_owned(),
+                ))
+            }
+        };
+        let result = self.call(method, message.get("params"));
+        let id = id?;
+        Some(match result {
+            Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+            Err((code, message)) => error_response(id, code, message),
+        })
+    }
+
+    /// Reads requests from `input` until it closes, writing responses to the output.
+    pub fn serve<R: BufRead>(mut self, input: R) -> std::io::Result<()> {
+        let queue = self.queue.clone();
+        std::thread::spawn(move || loop {
+            for record in queue.records() {
+                if !record.status.is_terminal() {
+                    let _ = queue.poll_task(record.id);
+                }
+            }
+            std::thread::sleep(DRIVE_INTERVAL);
+        });
+        for line in input.lines() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            if let Some(response) = self.handle_message(&line) {
+                write_message(&self.output, &response)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn call(&mut self, method: &str, params: Option<&Value>) -> Result<Value, (i64, String)> {
+        if method == "subscribe" {
+            self.subscribe();
+            return Ok(Value::Bool(true));
+        }
+        if !CONTROL_METHODS.contains(&method) {
+            return Err((METHOD_NOT_FOUND, format!("Unknown method: {}", method)));
+        }
+        let mut request = match params {
+            Some(Value::Object(params)) => params.clone(),
+            None | Some(Value::Null) => Map::new(),
+            Some(_) => return Err((INVALID_PARAMS, "params must be an object".to_owned())),
+        };
+        request.insert("command".to_owned(), Value::String(method.to_owned()));
+        let request: ControlRequest = serde_json::from_value(Value::Object(request))
+            .map_err(|e| (INVALID_PARAMS, e.to_string()))?;
+        self.handler
+            .handle(request)
+            .map_err(|message| (SERVER_ERROR, message))
+    }
+
+    fn subscribe(&mut self) {
+        if self.subscribed {
+            return;
+        }
+        self.subscribed = true;
+        let events = self.queue.subscribe();
+        let output = self.output.clone();
+        std::thread::spawn(move || {
+            for record in events {
+                let notification = json!({
+                    "jsonrpc": "2.0",
+                    "method": "task_event",
+                    "params": record,
+                });
+                if write_message(&output, &notification).is_err() {
+                    break;
+                }
+            }
+        });
+        let progress = self.queue.subscribe_progress();
+        let output = self.output.clone();
+        std::thread::spawn(move || {
+            for event in progress {
+                let notification = json!({
+                    "jsonrpc": "2.0",
+                    "method": "task_progress",
+                    "params": event,
+                });
+                if write_message(&output, &notification).is_err() {
+                    break;
+                }
+            }
+        });
+    }
+}
+
+/// Runs the queue headless, speaking JSON-RPC on stdin/stdout until stdin closes.
+pub fn run() -> std::io::Result<()> {
+    let config = AppConfig::default_path()
+        .and_then(|path| AppConfig::load_or_default(&path))
+        .unwrap_or_else(|e| {
+            log::error!("Using default config: {}", e);
+            AppConfig::default()
+        });
+    config.apply_globals();
+    let queue = sync_Arc::new(TaskQueue::new());
+    queue.set_progress_granularity(config.progress_granularity());
+    #[cfg(feature = "email")]
+    if let Some(smtp) = config.smtp.as_ref().filter(|smtp| smtp.batch_summary) {
+        crate::app::email::spawn_batch_mailer(smtp, queue.subscribe());
+    }
+    let server = RpcServer::new(
+        queue,
+        sync_Arc::new(TaskKindRegistry::with_plugins(&config)),
+        sync_Arc::new(sync_Mutex::new(std::io::stdout())),
+    );
+    server.serve(std::io::stdin().lock())
+}
+
+fn error_response(id: Value, code: i64, message: String) -> Value {
+    json!({
+        "jsonrpc": "2.0",
+        "id": id,
+        "error": { "code": code, "message": message },
+    })
+}
+
+fn write_message<W: Write>(output: &sync_Mutex<W>, message: &Value) -> std::io::Result<()> {
+    let mut output = output
+        .lock()
+        .expect("Panicked at write_message: Output mutex poisoned");
+    writeln!(output, "{}", message)?;
+    output.flush()
+}
@@ -0,0 +1,68 @@
+#[cfg(test)]
+use std::sync::{Arc, Mutex};
+
+#[cfg(test)]
+use serde_json::Value;
+
+#[cfg(test)]
+use crate::app::registry::TaskKindRegistry;
+#[cfg(test)]
+use crate::app::rpc_stdio::RpcServer;
+#[cfg(test)]
+use crate::app::task_queue::TaskQueue;
+
+#[cfg(test)]
+fn server() -> (RpcServer<Vec<u8>>, Arc<Mutex<Vec<u8>>>) {
+    let output = Arc::new(Mutex::new(Vec::new()));
+    let server = RpcServer::new(
+        Arc::new(TaskQueue::new()),
+        Arc::new(TaskKindRegistry::default()),
+        output.clone(),
+    );
+    (server, output)
+}
+
+#[test]
+fn test_add_task_and_notifications() {
+    let (mut server, output) = server();
+    let subscribed = server
+        .handle_message(r#"{"jsonrpc": "2.0", "id": 1, "method": "subscribe"}"#)
+        .unwrap();
+    assert_eq!(subscribed["result"], true);
+
+    let added = server
+        .handle_message(
+            r#"{"jsonrpc": "2.0", "id": 2, "method": "add_task",
+                "params": {"kind": "sleep", "params": {"seconds": "0"}}}"#,
+        )
+        .unwrap();
+    assert_eq!(added["id"], 2);
+    assert_eq!(added["result"]["id"], 0);
+
+    std::thread::sleep(std::time::Duration::from_millis(100));
+    let output = String::from_utf8(output.lock().unwrap().clone()).unwrap();
+    let event: Value = serde_json::from_str(output.lines().next().unwrap()).unwrap();
+    assert_eq!(event["method"], "task_event");
+    assert_eq!(event["params"]["status"], "Queued");
+}
+
+#[test]
+fn test_rpc_errors() {
+    let (mut server, _) = server();
+    let unknown = server
+        .handle_message(r#"{"jsonrpc": "2.0", "id": 1, "method": "teleport"}"#)
+        .unwrap();
+    assert_eq!(unknown["error"]["code"], -32601);
+
+    let bad_params = server
+        .handle_message(r#"{"jsonrpc": "2.0", "id": 2, "method": "poll", "params": {}}"#)
+        .unwrap();
+    assert_eq!(bad_params["error"]["code"], -32602);
+
+    let parse_error = server.handle_message("{").unwrap();
+    assert_eq!(parse_error["error"]["code"], -32700);
+
+    assert!(server
+        .handle_message(r#"{"jsonrpc": "2.0", "method": "list"}"#)
+        .is_none());
+}
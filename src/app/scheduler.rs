@@ -0,0 +1,167 @@
+//! Which tasks a [`TaskQueue`](crate::app::task_queue::TaskQueue) lets start while a
+//! concurrency limit is in effect. A task takes a slot on the first poll that starts it
+//! and keeps it until it finishes, paused or not, so resuming never goes over the limit.
+//! The rest stay queued and are let in by priority, then by their group's place among
+//! the groups, then in the order they were added, as they are polled: a task is only let
+//! in while fewer tasks are ahead of it in line than there are slots free, so however
+//! the tasks are polled, none starts while one ahead of it is kept waiting. A start rate,
+//! if set, also spreads starts out over time.
+
+use std::cmp::Reverse;
+use std::collections::{BTreeSet, HashMap};
+
+use crate::app::executor::Instant;
+use crate::app::priority::Priority;
+use crate::app::task_id::TaskId;
+
+/// Where a task stands in line: most urgent first, then lowest id.
+pub type SlotKey = (Reverse<Priority>, TaskId);
+
+pub fn slot_key(priority: Priority, id: TaskId) -> SlotKey {
+    (Reverse(priority), id)
+}
+
+/// Where a waiting task stands in line: its key's priority, then its group's place among
+/// the groups, tasks in no group last, then its key's id.
+type Place = (Reverse<Priority>, usize, TaskId);
+
+#[derive(Debug, Default)]
+pub struct Scheduler {
+    limit: Option<usize>,
+    /// Tasks holding a slot.
+    running: usize,
+    /// Tasks added and not started yet, except paused ones.
+    waiting: BTreeSet<Place>,
+    /// The place among the groups of each grouped task's group.
+    group_ranks: HashMap<TaskId, usize>,
+    start_rate: Option<StartRate>,
+}
+
+/// A token bucket of task starts, refilled at `per_second` and holding a second's worth,
+/// or one start if that is less.
+#[derive(Debug)]
+struct StartRate {
+    per_second: f64,
+    tokens: f64,
+    refilled: Instant,
+}
+
+impl StartRate {
+    fn capacity(&self) -> f64 {
+        self.per_second.max(1.0)
+    }
+
+    /// Starts allowed now.
+    fn refill(&mut self, now: Instant) -> usize {
+        let elapsed = now.saturating_duration_since(self.refilled).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * self.per_second).min(self.capacity());
+        self.refilled = now;
+        self.tokens as usize
+    }
+}
+
+impl Scheduler {
+    pub fn limit(&self) -> Option<usize> {
+        self.limit
+    }
+
+    /// Caps how many tasks hold a slot at once; `None` for no cap. Lowering the limit
+    /// lets the tasks already running finish, and starts no others until they have.
+    pub fn set_limit(&mut self, limit: Option<usize>) {
+        self.limit = limit.map(|n| n.max(1));
+    }
+
+    pub fn start_rate(&self) -> Option<f64> {
+        self.start_rate.as_ref().map(|rate| rate.per_second)
+    }
+
+    /// Caps how many tasks start per second; `None`, zero or less for no cap. Tasks
+    /// already running are not affected.
+    pub fn set_start_rate(&mut self, per_second: Option<f64>) {
+        self.start_rate = per_second
+            .filter(|&per_second| per_second > 0.0)
+            .map(|per_second| StartRate {
+                per_second,
+                tokens: per_second.max(1.0),
+                refilled: Instant::now(),
+            });
+    }
+
+    /// Lines grouped tasks up by their group's place among the groups, as given by
+    /// `group_ranks`, tasks first in line having the lowest. Tasks not in it wait behind
+    /// those that are.
+    pub fn set_group_ranks(&mut self, group_ranks: HashMap<TaskId, usize>) {
+        self.group_ranks = group_ranks;
+        let waiting: Vec<SlotKey> = self
+            .waiting
+            .iter()
+            .map(|&(priority, _, id)| (priority, id))
+            .collect();
+        self.waiting = waiting.into_iter().map(|key| self.place(key)).collect();
+    }
+
+    fn place(&self, (priority, id): SlotKey) -> Place {
+        let rank = self.group_ranks.get(&id).copied().unwrap_or(usize::MAX);
+        (priority, rank, id)
+    }
+
+    /// Puts a task that was added, or resumed before it started, in line.
+    pub fn enqueue(&mut self, key: SlotKey) {
+        self.waiting.insert(self.place(key));
+    }
+
+    /// Takes a slot for the task at `key` if one is free for it, i.e. fewer tasks ahead of
+    /// it are waiting than there are free slots and starts the start rate allows now.
+    /// Otherwise it waits in line.
+    pub fn admit(&mut self, key: SlotKey) -> bool {
+        let key = self.place(key);
+        let slots = self.limit.map(|limit| limit.saturating_sub(self.running));
+        let starts = self
+            .start_rate
+            .as_mut()
+            .map(|rate| rate.refill(Instant::now()));
+        let free = match (slots, starts) {
+            (None, None) => {
+                self.waiting.remove(&key);
+                self.running += 1;
+                return true;
+            }
+            (Some(free), None) | (None, Some(free)) => free,
+            (Some(slots), Some(starts)) => slots.min(starts),
+        };
+        let ahead = self.waiting.range(..key).take(free).count();
+        if ahead < free {
+            self.waiting.remove(&key);
+            self.running += 1;
+            if let Some(rate) = &mut self.start_rate {
+                rate.tokens -= 1.0;
+            }
+            true
+        } else {
+            self.waiting.insert(key);
+            false
+        }
+    }
+
+    /// Ids of the tasks in line, the next to be let in first.
+    pub fn waiting_order(&self) -> Vec<TaskId> {
+        self.waiting.iter().map(|&(_, _, id)| id).collect()
+    }
+
+    /// Frees the slot of a task that finished.
+    pub fn release(&mut self) {
+        self.running = self.running.saturating_sub(1);
+    }
+
+    /// Takes a task out of line that finished, or was paused, before it could start.
+    pub fn forget(&mut self, key: SlotKey) {
+        self.waiting.remove(&self.place(key));
+    }
+
+    /// Moves a waiting task to its place in line for its new priority.
+    pub fn reprioritize(&mut self, from: SlotKey, to: SlotKey) {
+        if self.waiting.remove(&self.place(from)) {
+            self.waiting.insert(self.place(to));
+        }
+    }
+}
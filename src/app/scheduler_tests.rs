@@ -0,0 +1,134 @@
+#[cfg(test)]
+use std::time::Duration;
+
+#[cfg(test)]
+use crate::app::priority::Priority;
+#[cfg(test)]
+use crate::app::scheduler::{slot_key, Scheduler};
+#[cfg(test)]
+use crate::app::sleep_task::SleepTask;
+#[cfg(test)]
+use crate::app::task_id::TaskId;
+#[cfg(test)]
+use crate::app::task_queue::{TaskQueue, TaskStatus};
+
+#[test]
+fn test_waiting_tasks_are_let_in_by_priority_then_age() {
+    let key = |priority, index| slot_key(priority, TaskId::detached(index));
+    let mut scheduler = Scheduler::default();
+    scheduler.set_limit(Some(1));
+    assert!(scheduler.admit(key(Priority::Normal, 0)));
+    assert!(!scheduler.admit(key(Priority::Normal, 1)));
+    assert!(!scheduler.admit(key(Priority::Normal, 2)));
+    scheduler.reprioritize(key(Priority::Normal, 2), key(Priority::High, 2));
+    scheduler.release();
+    // Task 1 is behind task 2 now, even though task 2 is not polled first.
+    assert!(!scheduler.admit(key(Priority::Normal, 1)));
+    assert!(scheduler.admit(key(Priority::High, 2)));
+
+    scheduler.set_limit(Some(3));
+    assert!(scheduler.admit(key(Priority::Normal, 1)));
+    assert!(scheduler.admit(key(Priority::Low, 3)));
+    assert!(!scheduler.admit(key(Priority::Low, 4)));
+    scheduler.forget(key(Priority::Low, 4));
+    scheduler.set_limit(None);
+    assert!(scheduler.admit(key(Priority::Low, 5)));
+}
+
+#[test]
+fn test_grouped_tasks_are_let_in_by_group_rank() {
+    let key = |index| slot_key(Priority::Normal, TaskId::detached(index));
+    let mut scheduler = Scheduler::default();
+    scheduler.set_limit(Some(1));
+    for index in 0..4 {
+        scheduler.enqueue(key(index));
+    }
+    let id = TaskId::detached;
+    scheduler.set_group_ranks([(id(3), 0), (id(1), 1), (id(2), 1)].into_iter().collect());
+    assert_eq!(scheduler.waiting_order(), [id(3), id(1), id(2), id(0)]);
+    assert!(!scheduler.admit(key(0)));
+    assert!(scheduler.admit(key(3)));
+    scheduler.release();
+    scheduler.set_group_ranks([(id(2), 0), (id(1), 1)].into_iter().collect());
+    assert_eq!(scheduler.waiting_order(), [id(2), id(1), id(0)]);
+    scheduler.forget(key(2));
+    assert!(scheduler.admit(key(1)));
+}
+
+#[test]
+fn test_queue_runs_at_most_its_concurrency() {
+    let queue = TaskQueue::with_concurrency(2);
+    let ids: Vec<TaskId> = (0..4)
+        .map(|_| queue.add_task(SleepTask::new(None, Duration::from_secs(60))))
+        .collect();
+    queue.set_priority(ids[3], Priority::Critical).unwrap();
+    let statuses = |queue: &TaskQueue| -> Vec<TaskStatus> {
+        ids.iter()
+            .map(|&id| {
+                queue.poll_task(id).unwrap();
+                queue.task_detail(id).unwrap().record.status
+            })
+            .collect()
+    };
+    use TaskStatus::{Cancelled, Queued, Running};
+    assert_eq!(statuses(&queue), [Running, Queued, Queued, Running]);
+
+    // Paused tasks keep their slot.
+    queue.pause_task(ids[0]).unwrap();
+    assert_eq!(statuses(&queue)[1], Queued);
+    queue.remove_task(ids[0]).unwrap();
+    assert_eq!(statuses(&queue), [Cancelled, Running, Queued, Running]);
+
+    queue.set_concurrency(None);
+    assert_eq!(statuses(&queue), [Cancelled, Running, Running, Running]);
+    queue.remove_tasks(&ids);
+}
+
+#[test]
+fn test_start_rate_spreads_starts_out() {
+    let queue = TaskQueue::new();
+    queue.set_start_rate(Some(2.0));
+    let ids: Vec<TaskId> = (0..5)
+        .map(|_| queue.add_task(SleepTask::new(None, Duration::from_secs(60))))
+        .collect();
+    let running = |queue: &TaskQueue| -> usize {
+        ids.iter()
+            .filter(|&&id| {
+                queue.poll_task(id).unwrap();
+                queue.task_detail(id).unwrap().record.status == TaskStatus::Running
+            })
+            .count()
+    };
+    assert_eq!(running(&queue), 2);
+    assert_eq!(running(&queue), 2);
+    std::thread::sleep(Duration::from_millis(600));
+    assert_eq!(running(&queue), 3);
+
+    queue.set_start_rate(None);
+    assert_eq!(running(&queue), 5);
+    queue.remove_tasks(&ids);
+}
+
+#[test]
+fn test_queued_tasks_start_in_line_order() {
+    let queue = TaskQueue::with_concurrency(1);
+    let ids: Vec<TaskId> = (0..4)
+        .map(|_| queue.add_task(SleepTask::new(None, Duration::from_secs(60))))
+        .collect();
+    queue.set_priority(ids[2], Priority::High).unwrap();
+    assert_eq!(queue.queued_order(), [ids[2], ids[0], ids[1], ids[3]]);
+
+    let mut started = Vec::new();
+    while started.len() < ids.len() {
+        // Polled back to front, so only the line decides which starts next.
+        for &id in ids.iter().rev() {
+            queue.poll_task(id).unwrap();
+            if queue.status(id) == Ok(TaskStatus::Running) && !started.contains(&id) {
+                started.push(id);
+                queue.remove_task(id).unwrap();
+            }
+        }
+    }
+    assert_eq!(started, [ids[2], ids[0], ids[1], ids[3]]);
+    assert!(queue.queued_order().is_empty());
+}
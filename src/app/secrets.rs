@@ -0,0 +1,368 @@
+//! Named secrets, such as passwords, kept out of task parameters, config files and
+//! anything else that is saved in the clear.
+//!
+//! Secrets live in the OS keyring where there is one. Elsewhere, e.g. on a Linux machine
+//! without a Secret Service, they are kept in a file encrypted with a key derived from a
+//! passphrase, which has to be given once per session: in Settings, or in the
+//! `FUNCTIONAL_RUST_UI_DEMO_SECRETS_PASSPHRASE` environment variable.
+//!
+//! Task parameters and config refer to a secret as `${secret:NAME}`; see
+//! [`SecretStore::resolve`].
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::{Display, Formatter, Result as FmtResult};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex as sync_Mutex;
+
+use argon2::Argon2;
+use chacha20poly1305::aead::{Aead, KeyInit};
+use chacha20poly1305::{ChaCha20Poly1305, Key, Nonce};
+
+/// The keyring service secrets are filed under; the user name is the secret's name.
+const KEYRING_SERVICE: &str = "functional_rust_ui_demo";
+/// Looked up to tell whether the keyring works at all.
+const KEYRING_PROBE: &str = "functional_rust_ui_demo.probe";
+pub const PASSPHRASE_ENV_VAR: &str = "FUNCTIONAL_RUST_UI_DEMO_SECRETS_PASSPHRASE";
+const FILE_VERSION: u32 = 1;
+const SALT_LEN: usize = 16;
+const NONCE_LEN: usize = 12;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum SecretError {
+    NotFound(String),
+    /// The encrypted file has not been unlocked with its passphrase yet.
+    Locked,
+    WrongPassphrase,
+    Keyring(String),
+    Io(String),
+    /// A `${secret:` reference without its closing brace.
+    Reference(String),
+}
+
+impl Display for SecretError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        match self {
+            SecretError::NotFound(name) => write!(f, "No secret named '{}'", name),
+            SecretError::Locked => write!(f, "Secrets are locked; unlock them in Settings"),
+            SecretError::WrongPassphrase => write!(f, "Wrong secrets passphrase"),
+            SecretError::Keyring(e) => write!(f, "Keyring error: {}", e),
+            SecretError::Io(e) => write!(f, "Secrets file error: {}", e),
+            SecretError::Reference(value) => {
+                write!(f, "Unterminated secret reference in '{}'", value)
+            }
+        }
+    }
+}
+
+/// The encrypted file as stored, with its byte strings in hex.
+#[derive(serde::Deserialize, serde::Serialize)]
+struct SealedFile {
+    version: u32,
+    salt: String,
+    nonce: String,
+    ciphertext: String,
+}
+
+/// Secrets in a file of their own, decrypted in memory.
+pub struct EncryptedFile {
+    path: PathBuf,
+    salt: [u8; SALT_LEN],
+    key: [u8; 32],
+    secrets: BTreeMap<String, String>,
+}
+
+impl EncryptedFile {
+    /// Decrypts the file at `path`, or starts an empty one there if there is none yet.
+    pub fn open(path: &Path, passphrase: &str) -> Result<Self, SecretError> {
+        let sealed = match std::fs::read_to_string(path) {
+            Ok(json) => serde_json::from_str::<SealedFile>(&json)
+                .map_err(|e| SecretError::Io(format!("{}: {}", path.display(), e)))?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                let mut salt = [0; SALT_LEN];
+                getrandom::getrandom(&mut salt).map_err(|e| SecretError::Io(e.to_string()))?;
+                return Ok(EncryptedFile {
+                    path: path.to_owned(),
+                    salt,
+                    key: derive_key(passphrase, &salt)?,
+                    secrets: BTreeMap::new(),
+                });
+            }
+            Err(e) => return Err(SecretError::Io(e.to_string())),
+        };
+        if sealed.version != FILE_VERSION {
+            return Err(SecretError::Io(format!(
+                "unsupported secrets file version {}",
+                sealed.version
+            )));
+        }
+        let corrupt = || SecretError::Io(format!("{} is corrupt", path.display()));
+        let salt: [u8; SALT_LEN] = from_hex(&sealed.salt)
+            .and_then(|salt| salt.try_into().ok())
+            .ok_or_else(corrupt)?;
+        let nonce = from_hex(&sealed.nonce)
+            .filter(|nonce| nonce.len() == NONCE_LEN)
+            .ok_or_else(corrupt)?;
+        let ciphertext = from_hex(&sealed.ciphertext).ok_or_else(corrupt)?;
+        let key = derive_key(passphrase, &salt)?;
+        let plaintext = ChaCha20Poly1305::new(Key::from_slice(&key))
+            .decrypt(Nonce::from_slice(&nonce), ciphertext.as_slice())
+            .map_err(|_| SecretError::WrongPassphrase)?;
+        let secrets = serde_json::from_slice(&plaintext).map_err(|_| corrupt())?;
+        Ok(EncryptedFile {
+            path: path.to_owned(),
+            salt,
+            key,
+            secrets,
+        })
+    }
+
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.secrets.get(name).map(String::as_str)
+    }
+
+    pub fn set(&mut self, name: &str, value: &str) -> Result<(), SecretError> {
+        self.secrets.insert(name.to_owned(), value.to_owned());
+        self.save()
+    }
+
+    pub fn delete(&mut self, name: &str) -> Result<(), SecretError> {
+        if self.secrets.remove(name).is_none() {
+            return Err(SecretError::NotFound(name.to_owned()));
+        }
+        self.save()
+    }
+
+    pub fn names(&self) -> Vec<String> {
+        self.secrets.keys().cloned().collect()
+    }
+
+    /// Encrypts the secrets under a fresh nonce and replaces the file with them.
+    fn save(&self) -> Result<(), SecretError> {
+        let io = |e: std::io::Error| SecretError::Io(e.to_string());
+        let mut nonce = [0; NONCE_LEN];
+        getrandom::getrandom(&mut nonce).map_err(|e| SecretError::Io(e.to_string()))?;
+        let plaintext =
+            serde_json::to_vec(&self.secrets).map_err(|e| SecretError::Io(e.to_string()))?;
+        let ciphertext = ChaCha20Poly1305::new(Key::from_slice(&self.key))
+            .encrypt(Nonce::from_slice(&nonce), plaintext.as_slice())
+            .map_err(|_| SecretError::Io("encryption failed".to_owned()))?;
+        let sealed = SealedFile {
+            version: FILE_VERSION,
+            salt: to_hex(&self.salt),
+            nonce: to_hex(&nonce),
+            ciphertext: to_hex(&ciphertext),
+        };
+        if let Some(dir) = self.path.parent() {
+            std::fs::create_dir_all(dir).map_err(io)?;
+        }
+        let mut temp = self.path.as_os_str().to_owned();
+        temp.push(".tmp");
+        let temp = PathBuf::from(temp);
+        let mut options = std::fs::OpenOptions::new();
+        options.write(true).create(true).truncate(true);
+        #[cfg(unix)]
+        std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+        let mut file = options.open(&temp).map_err(io)?;
+        serde_json::to_writer(&mut file, &sealed).map_err(|e| SecretError::Io(e.to_string()))?;
+        file.flush().map_err(io)?;
+        drop(file);
+        std::fs::rename(&temp, &self.path).map_err(io)
+    }
+}
+
+fn derive_key(passphrase: &str, salt: &[u8]) -> Result<[u8; 32], SecretError> {
+    let mut key = [0; 32];
+    Argon2::default()
+        .hash_password_into(passphrase.as_bytes(), salt, &mut key)
+        .map_err(|e| SecretError::Io(e.to_string()))?;
+    Ok(key)
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+fn from_hex(hex: &str) -> Option<Vec<u8>> {
+    if hex.len() % 2 != 0 {
+        return None;
+    }
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok())
+        .collect()
+}
+
+enum Backend {
+    /// The keyring cannot list what it holds, so the names are kept in a file beside it.
+    Keyring {
+        names_path: PathBuf,
+        names: BTreeSet<String>,
+    },
+    /// `None` until unlocked.
+    File {
+        path: PathBuf,
+        file: Option<EncryptedFile>,
+    },
+}
+
+pub struct SecretStore {
+    backend: sync_Mutex<Backend>,
+}
+
+impl SecretStore {
+    /// The OS keyring if it works, else the encrypted file at `file_path`, unlocked with
+    /// the passphrase in [`PASSPHRASE_ENV_VAR`] if that is set.
+    pub fn open(file_path: &Path) -> Self {
+        let keyring_works = keyring::Entry::new(KEYRING_SERVICE, KEYRING_PROBE)
+            .and_then(|entry| entry.get_password())
+            .map_or_else(|e| matches!(e, keyring::Error::NoEntry), |_| true);
+        if keyring_works {
+            let names_path = file_path.with_file_name("secret_names.json");
+            let names = std::fs::read_to_string(&names_path)
+                .ok()
+                .and_then(|json| serde_json::from_str(&json).ok())
+                .unwrap_or_default();
+            return SecretStore {
+                backend: sync_Mutex::new(Backend::Keyring { names_path, names }),
+            };
+        }
+        log::info!(
+            "No OS keyring available, keeping secrets in {}",
+            file_path.display()
+        );
+        let store = Self::with_file(file_path);
+        if let Ok(passphrase) = std::env::var(PASSPHRASE_ENV_VAR) {
+            if let Err(e) = store.unlock(&passphrase) {
+                log::error!("Cannot unlock secrets from {}: {}", PASSPHRASE_ENV_VAR, e);
+            }
+        }
+        store
+    }
+
+    /// A store using only the encrypted file at `path`, locked.
+    pub fn with_file(path: &Path) -> Self {
+        SecretStore {
+            backend: sync_Mutex::new(Backend::File {
+                path: path.to_owned(),
+                file: None,
+            }),
+        }
+    }
+
+    fn backend(&self) -> std::sync::MutexGuard<'_, Backend> {
+        self.backend
+            .lock()
+            .expect("Panicked at backend: Secrets mutex poisoned")
+    }
+
+    /// Where secrets are kept, for Settings.
+    pub fn describe(&self) -> String {
+        match &*self.backend() {
+            Backend::Keyring { .. } => "OS keyring".to_owned(),
+            Backend::File { path, .. } => format!("Encrypted file {}", path.display()),
+        }
+    }
+
+    pub fn is_locked(&self) -> bool {
+        matches!(&*self.backend(), Backend::File { file: None, .. })
+    }
+
+    /// Opens the encrypted file with `passphrase`. The keyring needs no unlocking.
+    pub fn unlock(&self, passphrase: &str) -> Result<(), SecretError> {
+        if let Backend::File { path, file } = &mut *self.backend() {
+            *file = Some(EncryptedFile::open(path, passphrase)?);
+        }
+        Ok(())
+    }
+
+    pub fn get(&self, name: &str) -> Result<String, SecretError> {
+        match &*self.backend() {
+            Backend::Keyring { .. } => keyring_entry(name)?.get_password().map_err(|e| match e {
+                keyring::Error::NoEntry => SecretError::NotFound(name.to_owned()),
+                e => SecretError::Keyring(e.to_string()),
+            }),
+            Backend::File { file: None, .. } => Err(SecretError::Locked),
+            Backend::File {
+                file: Some(file), ..
+            } => file
+                .get(name)
+                .map(str::to_owned)
+                .ok_or_else(|| SecretError::NotFound(name.to_owned())),
+        }
+    }
+
+    pub fn set(&self, name: &str, value: &str) -> Result<(), SecretError> {
+        match &mut *self.backend() {
+            Backend::Keyring { names_path, names } => {
+                keyring_entry(name)?
+                    .set_password(value)
+                    .map_err(|e| SecretError::Keyring(e.to_string()))?;
+                names.insert(name.to_owned());
+                save_names(names_path, names)
+            }
+            Backend::File { file: None, .. } => Err(SecretError::Locked),
+            Backend::File {
+                file: Some(file), ..
+            } => file.set(name, value),
+        }
+    }
+
+    pub fn delete(&self, name: &str) -> Result<(), SecretError> {
+        match &mut *self.backend() {
+            Backend::Keyring { names_path, names } => {
+                match keyring_entry(name)?.delete_credential() {
+                    Ok(()) | Err(keyring::Error::NoEntry) => {}
+                    Err(e) => return Err(SecretError::Keyring(e.to_string())),
+                }
+                names.remove(name);
+                save_names(names_path, names)
+            }
+            Backend::File { file: None, .. } => Err(SecretError::Locked),
+            Backend::File {
+                file: Some(file), ..
+            } => file.delete(name),
+        }
+    }
+
+    /// The names of the stored secrets; none while locked.
+    pub fn names(&self) -> Vec<String> {
+        match &*self.backend() {
+            Backend::Keyring { names, .. } => names.iter().cloned().collect(),
+            Backend::File { file, .. } => {
+                file.as_ref().map(EncryptedFile::names).unwrap_or_default()
+            }
+        }
+    }
+
+    /// `value` with each `${secret:NAME}` replaced by that secret.
+    pub fn resolve(&self, value: &str) -> Result<String, SecretError> {
+        const OPEN: &str = "${secret:";
+        let mut resolved = String::with_capacity(value.len());
+        let mut rest = value;
+        while let Some(start) = rest.find(OPEN) {
+            resolved.push_str(&rest[..start]);
+            let after = &rest[start + OPEN.len()..];
+            let end = after
+                .find('}')
+                .ok_or_else(|| SecretError::Reference(value.to_owned()))?;
+            resolved.push_str(&self.get(&after[..end])?);
+            rest = &after[end + 1..];
+        }
+        resolved.push_str(rest);
+        Ok(resolved)
+    }
+}
+
+fn keyring_entry(name: &str) -> Result<keyring::Entry, SecretError> {
+    keyring::Entry::new(KEYRING_SERVICE, name).map_err(|e| SecretError::Keyring(e.to_string()))
+}
+
+fn save_names(path: &Path, names: &BTreeSet<String>) -> Result<(), SecretError> {
+    let io = |e: std::io::Error| SecretError::Io(e.to_string());
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir).map_err(io)?;
+    }
+    let json = serde_json::to_string(names).map_err(|e| SecretError::Io(e.to_string()))?;
+    std::fs::write(path, json).map_err(io)
+}
@@ -0,0 +1,51 @@
+#[cfg(test)]
+use crate::app::secrets::{EncryptedFile, SecretError, SecretStore};
+
+#[cfg(test)]
+fn temp_secrets_path(name: &str) -> std::path::PathBuf {
+    let path = std::env::temp_dir().join(format!("{}_{}.json", name, std::process::id()));
+    let _ = std::fs::remove_file(&path);
+    path
+}
+
+#[test]
+fn test_encrypted_file_round_trip() {
+    let path = temp_secrets_path("secrets_round_trip");
+    let mut file = EncryptedFile::open(&path, "correct horse").unwrap();
+    file.set("smtp", "hunter2").unwrap();
+    let stored = std::fs::read_to_string(&path).unwrap();
+    assert!(!stored.contains("hunter2"));
+
+    let file = EncryptedFile::open(&path, "correct horse").unwrap();
+    assert_eq!(file.get("smtp"), Some("hunter2"));
+    assert_eq!(file.names(), vec!["smtp".to_owned()]);
+    assert_eq!(
+        EncryptedFile::open(&path, "battery staple").err(),
+        Some(SecretError::WrongPassphrase)
+    );
+    std::fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn test_resolve_secret_references() {
+    let path = temp_secrets_path("secrets_resolve");
+    let store = SecretStore::with_file(&path);
+    assert_eq!(store.resolve("plain"), Ok("plain".to_owned()));
+    assert_eq!(store.resolve("${secret:token}"), Err(SecretError::Locked));
+
+    store.unlock("passphrase").unwrap();
+    store.set("token", "abc").unwrap();
+    assert_eq!(
+        store.resolve("Bearer ${secret:token}, again ${secret:token}"),
+        Ok("Bearer abc, again abc".to_owned())
+    );
+    assert_eq!(
+        store.resolve("${secret:missing}"),
+        Err(SecretError::NotFound("missing".to_owned()))
+    );
+    assert!(matches!(
+        store.resolve("${secret:token"),
+        Err(SecretError::Reference(_))
+    ));
+    std::fs::remove_file(path).unwrap();
+}
@@ -0,0 +1,120 @@
+//! Saving the whole workspace to a file and opening it again, on this machine or another.
+
+use std::fmt::{Display, Formatter, Result as FmtResult};
+use std::path::{Path, PathBuf};
+
+use crate::app::color_tag::ColorTag;
+use crate::app::launch_args::TaskSpec;
+use crate::app::priority::Priority;
+use crate::app::registry::TaskParams;
+
+/// Session files are recognised by this extension, e.g. `render-farm.tqsession`.
+pub const SESSION_FILE_EXTENSION: &str = "tqsession";
+pub const SESSION_VERSION: u32 = 1;
+/// The unfinished tasks saved on exit, in the platform data dir; see [`queue_state_path`].
+const QUEUE_STATE_FILE_NAME: &str = "unfinished.tqsession";
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum SessionError {
+    Io(String),
+    Parse(String),
+    UnsupportedVersion(u32),
+}
+
+impl Display for SessionError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        match self {
+            SessionError::Io(e) => write!(f, "Cannot access session file: {}", e),
+            SessionError::Parse(e) => write!(f, "Malformed session file: {}", e),
+            SessionError::UnsupportedVersion(version) => write!(
+                f,
+                "Session file version {} is not supported (expected {})",
+                version, SESSION_VERSION
+            ),
+        }
+    }
+}
+
+/// The queue and the windows around it, saved as JSON.
+///
+/// Tasks are saved as the kind and parameters they were created from, like job files, and
+/// start over when the session is opened; progress is not kept.
+#[derive(Debug, Clone, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct Session {
+    pub version: u32,
+    /// Unfinished tasks, in the order they were added.
+    #[serde(default)]
+    pub tasks: Vec<SessionTask>,
+    /// Batches still running, each as indices into `tasks`.
+    #[serde(default)]
+    pub groups: Vec<Vec<usize>>,
+    #[serde(default)]
+    pub layout: SessionLayout,
+}
+
+#[derive(Debug, Clone, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct SessionTask {
+    #[serde(flatten)]
+    pub spec: TaskSpec,
+    /// Opened paused instead of queued.
+    #[serde(default)]
+    pub paused: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub color_tag: Option<ColorTag>,
+    #[serde(default, skip_serializing_if = "Priority::is_normal")]
+    pub priority: Priority,
+    /// Unix timestamp in milliseconds; see
+    /// [`TaskRecord::deadline`](crate::app::history::TaskRecord::deadline).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub deadline: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub label: Option<String>,
+}
+
+/// Which windows are open and where, and the New task window's draft.
+#[derive(Debug, Clone, Default, PartialEq, serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+pub struct SessionLayout {
+    pub show_header: bool,
+    pub show_footer: bool,
+    pub show_settings: bool,
+    pub show_history: bool,
+    pub show_new_task: bool,
+    pub new_task_kind: String,
+    pub new_task_params: TaskParams,
+    /// egui's own memory, which holds the windows' positions and sizes. Left out when it
+    /// cannot be saved, and ignored when it cannot be read back, e.g. from another egui
+    /// version.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub egui_memory: Option<serde_json::Value>,
+}
+
+impl Session {
+    pub fn from_json_str(s: &str) -> Result<Self, SessionError> {
+        let session: Session =
+            serde_json::from_str(s).map_err(|e| SessionError::Parse(e.to_string()))?;
+        if session.version != SESSION_VERSION {
+            return Err(SessionError::UnsupportedVersion(session.version));
+        }
+        Ok(session)
+    }
+
+    pub fn load(path: &Path) -> Result<Self, SessionError> {
+        let contents =
+            std::fs::read_to_string(path).map_err(|e| SessionError::Io(e.to_string()))?;
+        Self::from_json_str(&contents)
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), SessionError> {
+        let json =
+            serde_json::to_string_pretty(self).map_err(|e| SessionError::Parse(e.to_string()))?;
+        std::fs::write(path, json).map_err(|e| SessionError::Io(e.to_string()))
+    }
+}
+
+/// Where the tasks left unfinished on exit are saved as a session without a layout, to be
+/// added again on the next start.
+pub fn queue_state_path() -> Option<PathBuf> {
+    directories_next::ProjectDirs::from("net", "xthreen", "functional_rust_ui_demo")
+        .map(|dirs| dirs.data_dir().join(QUEUE_STATE_FILE_NAME))
+}
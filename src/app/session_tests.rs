@@ -0,0 +1,77 @@
+#[cfg(test)]
+use crate::app::color_tag::ColorTag;
+#[cfg(test)]
+use crate::app::launch_args::TaskSpec;
+#[cfg(test)]
+use crate::app::priority::Priority;
+#[cfg(test)]
+use crate::app::session::{
+    Session, SessionError, SessionLayout, SessionTask, SESSION_FILE_EXTENSION, SESSION_VERSION,
+};
+
+#[test]
+fn test_session_round_trip() {
+    let session = Session {
+        version: SESSION_VERSION,
+        tasks: vec![
+            SessionTask {
+                spec: TaskSpec {
+                    kind: "sleep".to_owned(),
+                    params: [("seconds".to_owned(), "5".to_owned())].into(),
+                },
+                paused: true,
+                color_tag: Some(ColorTag::Blue),
+                priority: Priority::High,
+                deadline: Some(1_700_000_000_000),
+                label: Some("Nightly backup".to_owned()),
+            },
+            SessionTask {
+                spec: TaskSpec {
+                    kind: "sleep".to_owned(),
+                    params: Default::default(),
+                },
+                paused: false,
+                color_tag: None,
+                priority: Priority::Normal,
+                deadline: None,
+                label: None,
+            },
+        ],
+        groups: vec![vec![0, 1]],
+        layout: SessionLayout {
+            show_history: true,
+            new_task_kind: "download".to_owned(),
+            egui_memory: Some(serde_json::json!({"areas": {}})),
+            ..SessionLayout::default()
+        },
+    };
+    let path = std::env::temp_dir().join(format!(
+        "session-round-trip-{}.{}",
+        std::process::id(),
+        SESSION_FILE_EXTENSION
+    ));
+    session.save(&path).unwrap();
+    let loaded = Session::load(&path);
+    let _ = std::fs::remove_file(&path);
+    assert_eq!(loaded, Ok(session));
+}
+
+#[test]
+fn test_session_defaults_and_version() {
+    let session = Session::from_json_str(
+        r#"{"version": 1, "tasks": [{"kind": "sleep", "params": {"seconds": "1"}}]}"#,
+    )
+    .unwrap();
+    assert!(!session.tasks[0].paused);
+    assert!(session.groups.is_empty());
+    assert_eq!(session.layout, SessionLayout::default());
+
+    assert_eq!(
+        Session::from_json_str(r#"{"version": 2}"#),
+        Err(SessionError::UnsupportedVersion(2))
+    );
+    assert!(matches!(
+        Session::from_json_str("{"),
+        Err(SessionError::Parse(_))
+    ));
+}
@@ -0,0 +1,172 @@
+use std::fmt::{Display, Formatter, Result as FmtResult};
+use std::io::{BufRead, BufReader, Write};
+use std::net::{Ipv4Addr, TcpListener, TcpStream};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver, TryRecvError};
+use std::time::Duration;
+
+use log::{debug, info};
+
+const INSTANCE_FILE_NAME: &str = "instance.lock";
+const CONNECT_TIMEOUT: Duration = Duration::from_millis(500);
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum InstanceError {
+    NoRuntimeDir,
+    Io(String),
+}
+
+impl Display for InstanceError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        match self {
+            InstanceError::NoRuntimeDir => write!(f, "No platform runtime directory available"),
+            InstanceError::Io(e) => write!(f, "Single-instance I/O error: {}", e),
+        }
+    }
+}
+
+#[derive(serde::Deserialize, serde::Serialize)]
+struct ForwardMessage {
+    token: String,
+    args: Vec<String>,
+}
+
+/// Outcome of [`acquire`].
+pub enum InstanceRole {
+    /// No other instance is running; this process now receives forwarded arguments.
+    Primary(InstanceServer),
+    /// Another instance is running and has been handed this process's arguments.
+    Forwarded,
+}
+
+/// Receives command-line arguments forwarded by later launches of the app.
+pub struct InstanceServer {
+    receiver: Receiver<Vec<String>>,
+}
+
+impl InstanceServer {
+    /// Returns the next batch of forwarded arguments, if any arrived.
+    pub fn try_recv(&self) -> Option<Vec<String>> {
+        match self.receiver.try_recv() {
+            Ok(args) => Some(args),
+            Err(TryRecvError::Empty) | Err(TryRecvError::Disconnected) => None,
+        }
+    }
+}
+
+/// Becomes the primary instance, or forwards `args` to the already running one.
+///
+/// The primary instance listens on a loopback port published, together with a random token,
+/// in `instance.lock` in the platform runtime directory. Only processes that can read that
+/// file can forward arguments.
+pub fn acquire(args: &[String]) -> Result<InstanceRole, InstanceError> {
+    let path = instance_file_path()?;
+    if let Some((port, token)) = read_instance_file(&path) {
+        match forward(port, &token, args) {
+            Ok(()) => {
+                info!(
+                    "Forwarded arguments to the running instance on port {}",
+                    port
+                );
+                return Ok(InstanceRole::Forwarded);
+            }
+            Err(e) => debug!("No running instance answered ({}), starting a new one", e),
+        }
+    }
+    serve(&path).map(InstanceRole::Primary)
+}
+
+fn instance_file_path() -> Result<PathBuf, InstanceError> {
+    let dirs = directories_next::ProjectDirs::from("net", "xthreen", "functional_rust_ui_demo")
+        .ok_or(InstanceError::NoRuntimeDir)?;
+    let dir = dirs.runtime_dir().unwrap_or_else(|| dirs.data_local_dir());
+    Ok(dir.join(INSTANCE_FILE_NAME))
+}
+
+fn read_instance_file(path: &Path) -> Option<(u16, String)> {
+    let contents = std::fs::read_to_string(path).ok()?;
+    let (port, token) = contents.trim().split_once(' ')?;
+    Some((port.parse().ok()?, token.to_owned()))
+}
+
+fn forward(port: u16, token: &str, args: &[String]) -> std::io::Result<()> {
+    let mut stream =
+        TcpStream::connect_timeout(&(Ipv4Addr::LOCALHOST, port).into(), CONNECT_TIMEOUT)?;
+    stream.set_read_timeout(Some(CONNECT_TIMEOUT))?;
+    let message = ForwardMessage {
+        token: token.to_owned(),
+        args: args.to_vec(),
+    };
+    serde_json::to_writer(&mut stream, &message)?;
+    stream.write_all(b"\n")?;
+    let mut reply = String::new();
+    BufReader::new(stream).read_line(&mut reply)?;
+    if reply.trim() == "ok" {
+        Ok(())
+    } else {
+        Err(std::io::Error::new(
+            std::io::ErrorKind::PermissionDenied,
+            "running instance rejected the forwarded arguments",
+        ))
+    }
+}
+
+fn serve(path: &Path) -> Result<InstanceServer, InstanceError> {
+    let io_error = |e: std::io::Error| InstanceError::Io(e.to_string());
+    let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, 0)).map_err(io_error)?;
+    let port = listener.local_addr().map_err(io_error)?.port();
+    let token = random_token().map_err(|e| InstanceError::Io(e.to_string()))?;
+    write_instance_file(path, &format!("{} {}", port, token)).map_err(io_error)?;
+    debug!("Single-instance server listening on port {}", port);
+
+    let (sender, receiver) = mpsc::channel();
+    std::thread::spawn(move || {
+        for stream in listener.incoming().filter_map(Result::ok) {
+            match read_forwarded(stream, &token) {
+                Ok(Some(args)) => {
+                    if sender.send(args).is_err() {
+                        break;
+                    }
+                }
+                Ok(None) => log::warn!("Rejected forwarded arguments with a bad token"),
+                Err(e) => log::warn!("Failed to read forwarded arguments: {}", e),
+            }
+        }
+    });
+    Ok(InstanceServer { receiver })
+}
+
+fn read_forwarded(stream: TcpStream, token: &str) -> std::io::Result<Option<Vec<String>>> {
+    stream.set_read_timeout(Some(CONNECT_TIMEOUT))?;
+    let mut reader = BufReader::new(stream);
+    let mut line = String::new();
+    reader.read_line(&mut line)?;
+    let message: ForwardMessage = serde_json::from_str(&line)?;
+    let mut stream = reader.into_inner();
+    if message.token != token {
+        stream.write_all(b"denied\n")?;
+        return Ok(None);
+    }
+    stream.write_all(b"ok\n")?;
+    Ok(Some(message.args))
+}
+
+fn write_instance_file(path: &Path, contents: &str) -> std::io::Result<()> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    options.open(path)?.write_all(contents.as_bytes())
+}
+
+fn random_token() -> Result<String, getrandom::Error> {
+    let mut bytes = [0u8; 16];
+    getrandom::getrandom(&mut bytes)?;
+    Ok(bytes.iter().map(|b| format!("{:02x}", b)).collect())
+}
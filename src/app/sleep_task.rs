@@ -1,150 +1,117 @@
-use async_std::task::{self, JoinHandle};
 use log::debug;
 use std::sync::{Arc as sync_Arc, Mutex as sync_Mutex};
-use std::time::{Duration, Instant};
+use std::time::Duration;
 
+use crate::app::cancellation::CancellationToken;
+use crate::app::executor::{self, JobHandle};
+use crate::app::pausable_timer::PausableTimer;
+use crate::app::task_id::TaskId;
 use crate::app::task_queue::PollingData;
 
 use super::task_queue::{PollResult, Task, TaskError, TaskKind, TaskStatus};
 
 pub struct SleepTask {
-    id: Option<usize>,
-    duration: Duration,
+    id: Option<TaskId>,
     status: sync_Arc<sync_Mutex<TaskStatus>>,
-    handle: Option<JoinHandle<()>>,
-    start_time: sync_Arc<sync_Mutex<Option<Instant>>>,
-    elapsed_time: Duration,
-    paused_duration: sync_Arc<sync_Mutex<Duration>>,
+    handle: Option<JobHandle>,
+    /// Runs only while the task does, so a pause stops the clock.
+    timer: sync_Arc<PausableTimer>,
+    /// Ends the sleep as soon as the task is cancelled.
+    cancellation: CancellationToken,
 }
 
 impl SleepTask {
-    pub fn new(id: Option<usize>, duration: Duration) -> Self {
-        match id {
-            Some(id) => {
-                debug!("SleepTask::new() - id: {}", &id);
-                SleepTask {
-                    id: Some(id),
-                    duration,
-                    status: sync_Arc::new(sync_Mutex::new(TaskStatus::Queued)),
-                    handle: None,
-                    start_time: sync_Arc::new(sync_Mutex::new(None)),
-                    elapsed_time: Duration::from_secs(0),
-                    paused_duration: sync_Arc::new(sync_Mutex::new(Duration::from_secs(0))),
-                }
-            }
-            None => {
-                debug!("SleepTask::new() - id: None");
-                SleepTask {
-                    id: None,
-                    duration,
-                    status: sync_Arc::new(sync_Mutex::new(TaskStatus::Queued)),
-                    handle: None,
-                    start_time: sync_Arc::new(sync_Mutex::new(None)),
-                    elapsed_time: Duration::from_secs(0),
-                    paused_duration: sync_Arc::new(sync_Mutex::new(Duration::from_secs(0))),
-                }
-            }
+    pub fn new(id: Option<TaskId>, duration: Duration) -> Self {
+        debug!("SleepTask::new() - id: {:?}", id);
+        SleepTask {
+            id,
+            status: sync_Arc::new(sync_Mutex::new(TaskStatus::Queued)),
+            handle: None,
+            timer: sync_Arc::new(PausableTimer::new(duration)),
+            cancellation: CancellationToken::new(),
         }
     }
 }
 
+/// A task like this one that has not started, to sleep as long.
+impl Clone for SleepTask {
+    fn clone(&self) -> Self {
+        SleepTask::new(None, self.timer.duration())
+    }
+}
+
 impl Task for SleepTask {
-    fn id(&self) -> Result<usize, TaskError> {
+    fn id(&self) -> Result<TaskId, TaskError> {
         match self.id {
             Some(id) => Ok(id),
             None => Err(TaskError::IdUsizeIsNone),
         }
     }
 
-    fn set_id(&mut self, id: usize) {
+    fn set_id(&mut self, id: TaskId) {
         self.id = Some(id);
     }
 
+    fn set_cancellation_token(&mut self, token: CancellationToken) {
+        self.cancellation = token;
+    }
+
     fn poll(self: &mut SleepTask) -> PollResult {
         let status = self.status.lock().unwrap().clone();
         match status {
-            TaskStatus::Queued => {
+            TaskStatus::Queued if self.handle.is_some() => {
+                debug!("SleepTask::poll() - Waiting for a worker");
+                PollResult::Pending(PollingData::Float(0.0))
+            }
+            TaskStatus::Scheduled | TaskStatus::Queued => {
                 debug!("SleepTask::poll() - Queued");
-                let duration = self.duration;
                 let shared_status = self.status.clone();
-                let shared_start_time = self.start_time.clone();
-                self.handle = Some(task::spawn(async move {
-                    debug!("SleepTask::poll() - Sleeping for {:?}", duration);
-                    {
-                        let mut status_guard = shared_status.lock().unwrap();
-                        *status_guard = TaskStatus::Running;
-                    }
-                    {
-                        let mut start_time_guard = shared_start_time.lock().unwrap();
-                        *start_time_guard = Some(Instant::now());
-                    }
-                    task::sleep(duration).await;
+                let timer = self.timer.clone();
+                let cancellation = self.cancellation.clone();
+                self.handle = Some(executor::submit(async move {
                     {
                         let mut status_guard = shared_status.lock().unwrap();
                         match status_guard.clone() {
-                            TaskStatus::Running => {
-                                *status_guard = TaskStatus::Completed;
+                            TaskStatus::Queued => {
+                                *status_guard = TaskStatus::Running;
+                                timer.resume();
                             }
-                            TaskStatus::Paused => {
-                                *status_guard = TaskStatus::Paused;
-                            }
-                            _ => {}
+                            // Paused while waiting for a worker: the clock starts on resume.
+                            TaskStatus::Paused => {}
+                            _ => return,
+                        }
+                    }
+                    debug!("SleepTask::poll() - Sleeping for {:?}", timer.duration());
+                    if cancellation.run(timer.wait()).await == Some(true) {
+                        let mut status_guard = shared_status.lock().unwrap();
+                        if *status_guard == TaskStatus::Running {
+                            *status_guard = TaskStatus::Completed;
                         }
                     }
                     debug!("SleepTask::poll() - Done sleeping");
                 }));
-
                 PollResult::Pending(PollingData::Float(0.0))
             }
             TaskStatus::Running => {
-                debug!("SleepTask::poll() - Running");
-                let start_time = self.start_time.lock().unwrap();
-                let paused_duration = self.paused_duration.lock().unwrap();
-                if let Some(time) = *start_time {
-                    if *paused_duration > Duration::from_secs(0) {
-                        debug!(
-                            "{}: paused_duration: {:?}, elapsed_time: {:?}",
-                            self.kind(),
-                            paused_duration,
-                            self.elapsed_time
-                        );
-                        self.elapsed_time = time.elapsed() + *paused_duration;
-                        let progress =
-                            self.elapsed_time.as_secs_f32() / self.duration.as_secs_f32();
-                        if progress >= 1.0 {
-                            PollResult::Completed
-                        } else {
-                            PollResult::Pending(PollingData::Float(progress.min(1.0)))
-                        }
-                    } else {
-                        self.elapsed_time = time.elapsed();
-                        let progress =
-                            self.elapsed_time.as_secs_f32() / self.duration.as_secs_f32();
-                        PollResult::Pending(PollingData::Float(progress.min(1.0)))
-                    }
+                if self.timer.is_finished() {
+                    *self.status.lock().unwrap() = TaskStatus::Completed;
+                    PollResult::Completed
                 } else {
-                    PollResult::Pending(PollingData::Float(0.0))
+                    PollResult::Pending(PollingData::Float(self.timer.progress()))
                 }
             }
             TaskStatus::Paused => {
-                debug!("SleepTask::poll() - Paused");
-                let paused_duration = self.paused_duration.lock().unwrap();
                 debug!(
-                    "{}: paused task {} paused_duration: {:?}",
-                    self.kind(),
-                    self.id().expect(
-                        "SleepTask::poll() - Paused task has no id. This should never happen."
-                    ),
-                    paused_duration
+                    "SleepTask::poll() - Paused after {:?}",
+                    self.timer.elapsed()
                 );
-                let progress = paused_duration.as_secs_f32() / self.duration.as_secs_f32();
-                PollResult::Paused(PollingData::Float(progress.min(1.0)))
+                PollResult::Paused(PollingData::Float(self.timer.progress()))
             }
             TaskStatus::Completed => {
                 debug!("SleepTask::poll() - Completed");
                 PollResult::Completed
             }
-            TaskStatus::Cancelled => {
+            TaskStatus::Cancelled | TaskStatus::Failed | TaskStatus::Interrupted => {
                 debug!("SleepTask::poll() - Cancelled");
                 PollResult::Cancelled
             }
@@ -152,93 +119,69 @@ impl Task for SleepTask {
     }
 
     fn cancel(self: &mut SleepTask) -> Result<(), TaskError> {
-        let status = self.status.lock().unwrap().clone();
-        match status {
-            TaskStatus::Queued => {
-                {
-                    let mut status_guard = self.status.lock().unwrap();
-                    *status_guard = TaskStatus::Cancelled;
-                }
-                Ok(())
-            }
-            TaskStatus::Running => {
-                {
-                    let mut status_guard = self.status.lock().unwrap();
-                    *status_guard = TaskStatus::Cancelled;
-                }
-                Ok(())
-            }
-            TaskStatus::Paused => {
-                {
-                    let mut status_guard = self.status.lock().unwrap();
-                    *status_guard = TaskStatus::Cancelled;
-                }
+        let mut status = self.status.lock().unwrap();
+        match *status {
+            TaskStatus::Scheduled
+            | TaskStatus::Queued
+            | TaskStatus::Running
+            | TaskStatus::Paused => {
+                *status = TaskStatus::Cancelled;
+                // Frees the worker rather than leaving it to sleep out the duration.
+                self.cancellation.cancel();
+                self.timer.cancel();
                 Ok(())
             }
             TaskStatus::Completed => Err(TaskError::AlreadyCompleted),
-            TaskStatus::Cancelled => Err(TaskError::AlreadyCancelled),
+            TaskStatus::Cancelled | TaskStatus::Failed | TaskStatus::Interrupted => {
+                Err(TaskError::AlreadyCancelled)
+            }
         }
     }
 
     fn pause(self: &mut SleepTask) -> Result<(), TaskError> {
-        let status = self.status.lock().unwrap().clone();
-        match status {
-            TaskStatus::Queued => {
-                {
-                    let mut status_guard = self.status.lock().unwrap();
-                    *status_guard = TaskStatus::Paused;
-                }
-                Ok(())
-            }
-            TaskStatus::Running => {
-                {
-                    let mut status_guard = self.status.lock().unwrap();
-                    *status_guard = TaskStatus::Paused;
-                }
-                {
-                    let paused_at = Instant::now();
-                    let start_time = self.start_time.lock().unwrap();
-                    let diff = paused_at.duration_since(start_time.unwrap());
-                    debug!(
-                        "pausing {} task {}",
-                        self.kind(),
-                        self.id().expect(
-                            "SleepTask::pause() - Paused task has no id. This should never happen."
-                        )
-                    );
-                    let mut paused_duration_guard = self.paused_duration.lock().unwrap();
-                    *paused_duration_guard = diff;
-                }
+        let mut status = self.status.lock().unwrap();
+        match *status {
+            TaskStatus::Scheduled | TaskStatus::Queued | TaskStatus::Running => {
+                debug!("pausing {} task {:?}", self.kind(), self.id);
+                *status = TaskStatus::Paused;
+                self.timer.pause();
                 Ok(())
             }
             TaskStatus::Paused => Err(TaskError::AlreadyPaused),
             TaskStatus::Completed => Err(TaskError::AlreadyCompleted),
-            TaskStatus::Cancelled => Err(TaskError::AlreadyCancelled),
+            TaskStatus::Cancelled | TaskStatus::Failed | TaskStatus::Interrupted => {
+                Err(TaskError::AlreadyCancelled)
+            }
         }
     }
 
     fn resume(self: &mut SleepTask) -> Result<(), TaskError> {
-        let status = self.status.lock().unwrap().clone();
-        match status {
-            TaskStatus::Queued => Err(TaskError::NotFound),
+        let mut status = self.status.lock().unwrap();
+        match *status {
+            TaskStatus::Scheduled | TaskStatus::Queued => Err(TaskError::NotFound),
             TaskStatus::Running => Err(TaskError::AlreadyRunning),
+            TaskStatus::Paused if self.handle.is_none() => {
+                // Paused before it ever started: back to the queue so the next poll starts it.
+                *status = TaskStatus::Queued;
+                Ok(())
+            }
             TaskStatus::Paused => {
-                {
-                    let mut resume_time_guard = self.start_time.lock().unwrap();
-                    *resume_time_guard = Some(Instant::now());
-                }
-                {
-                    let mut status_guard = self.status.lock().unwrap();
-                    *status_guard = TaskStatus::Running;
-                }
+                *status = TaskStatus::Running;
+                self.timer.resume();
                 Ok(())
             }
             TaskStatus::Completed => Err(TaskError::AlreadyCompleted),
-            TaskStatus::Cancelled => Err(TaskError::AlreadyCancelled),
+            TaskStatus::Cancelled | TaskStatus::Failed | TaskStatus::Interrupted => {
+                Err(TaskError::AlreadyCancelled)
+            }
         }
     }
 
     fn kind(self: &SleepTask) -> TaskKind {
         TaskKind::Sleep
     }
+
+    fn take_handle(&mut self) -> Option<JobHandle> {
+        self.handle.take()
+    }
 }
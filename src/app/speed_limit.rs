@@ -0,0 +1,97 @@
+//! A cap on how fast one task moves bytes, which the user can change while it runs. It
+//! applies on top of, and independently of, the config's global `bandwidth_cap`.
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex as sync_Mutex;
+use std::time::{Duration, Instant};
+
+/// Longest a throttled transfer sleeps before checking whether it was paused or cancelled.
+const THROTTLE_SLICE: Duration = Duration::from_millis(100);
+/// Allowance left unused for longer than this is forgotten, so a transfer that stalled or
+/// was paused does not then burst above the limit.
+const MAX_CREDIT: Duration = Duration::from_secs(1);
+/// Smallest read a throttled transfer is asked to make.
+const MIN_CHUNK_SIZE: usize = 1024;
+
+/// Bytes per second, shared between a task and the UI adjusting it.
+#[derive(Debug)]
+pub struct SpeedLimit {
+    /// Zero when unlimited.
+    bytes_per_second: AtomicU64,
+    window: sync_Mutex<Window>,
+}
+
+/// Bytes moved since `started`, the last time the limit changed or credit was dropped.
+#[derive(Debug)]
+struct Window {
+    started: Instant,
+    bytes: u64,
+}
+
+impl Default for SpeedLimit {
+    fn default() -> Self {
+        SpeedLimit {
+            bytes_per_second: AtomicU64::new(0),
+            window: sync_Mutex::new(Window {
+                started: Instant::now(),
+                bytes: 0,
+            }),
+        }
+    }
+}
+
+impl SpeedLimit {
+    /// Bytes per second, or `None` when unlimited.
+    pub fn get(&self) -> Option<u64> {
+        match self.bytes_per_second.load(Ordering::Relaxed) {
+            0 => None,
+            limit => Some(limit),
+        }
+    }
+
+    pub fn set(&self, bytes_per_second: Option<u64>) {
+        self.bytes_per_second
+            .store(bytes_per_second.unwrap_or(0), Ordering::Relaxed);
+        *self.window.lock().unwrap() = Window {
+            started: Instant::now(),
+            bytes: 0,
+        };
+    }
+
+    /// How much to read at a time: `max`, or about a slice's worth when limited, so that
+    /// each read is followed by a short wait rather than a long one.
+    pub fn chunk_size(&self, max: usize) -> usize {
+        match self.get() {
+            Some(limit) => {
+                let slice = limit as f64 * THROTTLE_SLICE.as_secs_f64();
+                (slice as usize).clamp(MIN_CHUNK_SIZE, max.max(MIN_CHUNK_SIZE))
+            }
+            None => max,
+        }
+    }
+
+    /// Accounts for `bytes` just moved and waits until moving them was allowed. The wait
+    /// is cut short when `interrupted` returns true, which is checked between short
+    /// sleeps, or when the limit is changed.
+    pub fn throttle(&self, bytes: u64, interrupted: impl Fn() -> bool) {
+        let Some(limit) = self.get() else {
+            return;
+        };
+        let mut wait = {
+            let mut window = self.window.lock().unwrap();
+            window.bytes += bytes;
+            let due = Duration::from_secs_f64(window.bytes as f64 / limit as f64);
+            let elapsed = window.started.elapsed();
+            if elapsed > due + MAX_CREDIT {
+                window.started = Instant::now();
+                window.bytes = 0;
+            }
+            due.saturating_sub(elapsed)
+        };
+        while !wait.is_zero() && !interrupted() && self.get() == Some(limit) {
+            let slice = wait.min(THROTTLE_SLICE);
+            std::thread::sleep(slice);
+            wait -= slice;
+        }
+    }
+}
@@ -0,0 +1,34 @@
+#[cfg(test)]
+use std::time::{Duration, Instant};
+
+#[cfg(test)]
+use crate::app::speed_limit::SpeedLimit;
+
+#[test]
+fn test_unlimited_does_not_wait() {
+    let limit = SpeedLimit::default();
+    assert_eq!(limit.get(), None);
+    assert_eq!(limit.chunk_size(64 * 1024), 64 * 1024);
+    let started = Instant::now();
+    limit.throttle(u64::MAX / 2, || false);
+    assert!(started.elapsed() < Duration::from_millis(50));
+}
+
+#[test]
+fn test_throttle_keeps_to_the_limit() {
+    let limit = SpeedLimit::default();
+    limit.set(Some(100 * 1024));
+    assert_eq!(limit.chunk_size(64 * 1024), 10 * 1024);
+    let started = Instant::now();
+    for _ in 0..3 {
+        limit.throttle(10 * 1024, || false);
+    }
+    // 30 KiB at 100 KiB/s.
+    assert!(started.elapsed() >= Duration::from_millis(290));
+
+    let started = Instant::now();
+    limit.throttle(1024 * 1024, || true);
+    assert!(started.elapsed() < Duration::from_millis(50));
+    limit.set(None);
+    assert_eq!(limit.get(), None);
+}
@@ -0,0 +1,49 @@
+use std::fmt::{Display, Formatter, Result as FmtResult};
+
+use crate::app::history::TaskRecord;
+
+pub mod sqlite;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum StoreError {
+    Open(String),
+    Migration(String),
+    Query(String),
+}
+
+impl Display for StoreError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        match self {
+            StoreError::Open(e) => write!(f, "Failed to open store: {}", e),
+            StoreError::Migration(e) => write!(f, "Store migration failed: {}", e),
+            StoreError::Query(e) => write!(f, "Store query failed: {}", e),
+        }
+    }
+}
+
+/// Persistence backend for active tasks and history, used by `TaskQueue::with_store`.
+pub trait QueueStore: Send {
+    /// Inserts or updates an active task.
+    fn save_task(&mut self, record: &TaskRecord) -> Result<(), StoreError>;
+    /// Moves a task that reached a terminal state from the active set into history.
+    fn finish_task(&mut self, record: &TaskRecord) -> Result<(), StoreError>;
+    /// Up to `limit` history records, oldest first, after skipping the `offset` most recent.
+    fn load_history_page(
+        &mut self,
+        offset: usize,
+        limit: usize,
+    ) -> Result<Vec<TaskRecord>, StoreError>;
+    /// Deletes the history records of tasks that finished before `finished_before`, in
+    /// Unix milliseconds, then all but the `keep` most recent.
+    fn prune_history(
+        &mut self,
+        finished_before: Option<u64>,
+        keep: Option<usize>,
+    ) -> Result<(), StoreError>;
+    /// Deletes all history, leaving none of it behind in the file.
+    fn clear_history(&mut self) -> Result<(), StoreError>;
+    /// The most recent `limit` history records, oldest first.
+    fn load_history(&mut self, limit: usize) -> Result<Vec<TaskRecord>, StoreError> {
+        self.load_history_page(0, limit)
+    }
+}
@@ -0,0 +1,324 @@
+use std::path::Path;
+
+use log::debug;
+use rusqlite::{params, Connection};
+
+use crate::app::history::TaskRecord;
+use crate::app::store::{QueueStore, StoreError};
+use crate::app::task_id::TaskId;
+use crate::app::task_queue::TaskStatus;
+
+/// Schema migrations, applied in order. The index of the last applied migration + 1
+/// is kept in `PRAGMA user_version`, so only ever append to this list.
+const MIGRATIONS: &[&str] = &[
+    "CREATE TABLE tasks (
+        id INTEGER PRIMARY KEY,
+        kind TEXT NOT NULL,
+        status TEXT NOT NULL,
+        created_at INTEGER NOT NULL,
+        started_at INTEGER,
+        finished_at INTEGER
+    );
+    CREATE TABLE history (
+        row_id INTEGER PRIMARY KEY AUTOINCREMENT,
+        task_id INTEGER NOT NULL,
+        kind TEXT NOT NULL,
+        status TEXT NOT NULL,
+        created_at INTEGER NOT NULL,
+        started_at INTEGER,
+        finished_at INTEGER
+    );",
+    "CREATE INDEX history_finished_at ON history (finished_at);",
+    "ALTER TABLE tasks ADD COLUMN artifacts TEXT;
+    ALTER TABLE history ADD COLUMN artifacts TEXT;",
+    "ALTER TABLE tasks ADD COLUMN color_tag TEXT;
+    ALTER TABLE history ADD COLUMN color_tag TEXT;",
+    "ALTER TABLE tasks ADD COLUMN annotations TEXT;
+    ALTER TABLE history ADD COLUMN annotations TEXT;",
+    "ALTER TABLE tasks ADD COLUMN priority TEXT;
+    ALTER TABLE history ADD COLUMN priority TEXT;",
+    "ALTER TABLE tasks ADD COLUMN deadline INTEGER;
+    ALTER TABLE history ADD COLUMN deadline INTEGER;",
+    "ALTER TABLE tasks ADD COLUMN timed_out INTEGER NOT NULL DEFAULT 0;
+    ALTER TABLE history ADD COLUMN timed_out INTEGER NOT NULL DEFAULT 0;",
+    "ALTER TABLE tasks ADD COLUMN start_at INTEGER;
+    ALTER TABLE history ADD COLUMN start_at INTEGER;",
+    "ALTER TABLE tasks ADD COLUMN label TEXT;
+    ALTER TABLE tasks ADD COLUMN tags TEXT;
+    ALTER TABLE history ADD COLUMN label TEXT;
+    ALTER TABLE history ADD COLUMN tags TEXT;",
+    "ALTER TABLE tasks ADD COLUMN error TEXT;
+    ALTER TABLE history ADD COLUMN error TEXT;",
+    // Active tasks are keyed by their queue's generation as well as their number, since a
+    // queue swapped in on the same database numbers its tasks from 0 again. History only
+    // keeps the number, as shown to the user.
+    "CREATE TABLE tasks_by_generation (
+        generation INTEGER NOT NULL,
+        id INTEGER NOT NULL,
+        kind TEXT NOT NULL,
+        status TEXT NOT NULL,
+        created_at INTEGER NOT NULL,
+        started_at INTEGER,
+        finished_at INTEGER,
+        artifacts TEXT,
+        color_tag TEXT,
+        annotations TEXT,
+        priority TEXT,
+        deadline INTEGER,
+        timed_out INTEGER NOT NULL DEFAULT 0,
+        start_at INTEGER,
+        label TEXT,
+        tags TEXT,
+        error TEXT,
+        PRIMARY KEY (generation, id)
+    );
+    INSERT INTO tasks_by_generation (generation, id, kind, status, created_at, started_at, finished_at, artifacts, color_tag, annotations, priority, deadline, timed_out, start_at, label, tags, error)
+        SELECT 0, id, kind, status, created_at, started_at, finished_at, artifacts, color_tag, annotations, priority, deadline, timed_out, start_at, label, tags, error FROM tasks;
+    DROP TABLE tasks;
+    ALTER TABLE tasks_by_generation RENAME TO tasks;",
+];
+
+pub struct SqliteStore {
+    conn: Connection,
+}
+
+impl SqliteStore {
+    /// Opens (or creates) the database at `path` and brings its schema up to date.
+    ///
+    /// Tasks left in the active table by a previous session that never finished
+    /// (e.g. because the app crashed) are moved into history with their last known status.
+    pub fn open(path: &Path) -> Result<Self, StoreError> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent).map_err(|e| StoreError::Open(e.to_string()))?;
+        }
+        let conn = Connection::open(path).map_err(|e| StoreError::Open(e.to_string()))?;
+        Self::from_connection(conn)
+    }
+
+    fn from_connection(mut conn: Connection) -> Result<Self, StoreError> {
+        migrate(&mut conn)?;
+        conn.execute_batch(
+            "INSERT INTO history (task_id, kind, status, created_at, started_at, finished_at, artifacts, color_tag, annotations, priority, deadline, timed_out, start_at, label, tags, error)
+                SELECT id, kind, status, created_at, started_at, finished_at, artifacts, color_tag, annotations, priority, deadline, timed_out, start_at, label, tags, error FROM tasks;
+             DELETE FROM tasks;",
+        )
+        .map_err(|e| StoreError::Query(e.to_string()))?;
+        Ok(SqliteStore { conn })
+    }
+}
+
+fn schema_version(conn: &Connection) -> Result<usize, StoreError> {
+    conn.query_row("PRAGMA user_version", [], |row| row.get::<_, i64>(0))
+        .map(|v| v as usize)
+        .map_err(|e| StoreError::Migration(e.to_string()))
+}
+
+fn migrate(conn: &mut Connection) -> Result<(), StoreError> {
+    let current = schema_version(conn)?;
+    for (version, migration) in MIGRATIONS.iter().enumerate().skip(current) {
+        debug!("Applying store migration {}", version + 1);
+        let tx = conn
+            .transaction()
+            .map_err(|e| StoreError::Migration(e.to_string()))?;
+        tx.execute_batch(migration)
+            .and_then(|_| tx.pragma_update(None, "user_version", (version + 1) as i64))
+            .and_then(|_| tx.commit())
+            .map_err(|e| StoreError::Migration(format!("migration {}: {}", version + 1, e)))?;
+    }
+    Ok(())
+}
+
+fn record_from_row(row: &rusqlite::Row<'_>) -> rusqlite::Result<TaskRecord> {
+    let status: String = row.get(2)?;
+    Ok(TaskRecord {
+        id: TaskId::detached(row.get::<_, i64>(0)? as usize),
+        kind: row.get(1)?,
+        status: status.parse().unwrap_or(TaskStatus::Cancelled),
+        created_at: row.get::<_, i64>(3)? as u64,
+        started_at: row.get::<_, Option<i64>>(4)?.map(|t| t as u64),
+        finished_at: row.get::<_, Option<i64>>(5)?.map(|t| t as u64),
+        artifacts: row
+            .get::<_, Option<String>>(6)?
+            .and_then(|json| serde_json::from_str(&json).ok())
+            .unwrap_or_default(),
+        color_tag: row
+            .get::<_, Option<String>>(7)?
+            .and_then(|tag| tag.parse().ok()),
+        annotations: row
+            .get::<_, Option<String>>(8)?
+            .and_then(|json| serde_json::from_str(&json).ok())
+            .unwrap_or_default(),
+        priority: row
+            .get::<_, Option<String>>(9)?
+            .and_then(|priority| priority.parse().ok())
+            .unwrap_or_default(),
+        deadline: row.get::<_, Option<i64>>(10)?.map(|t| t as u64),
+        timed_out: row.get(11)?,
+        start_at: row.get::<_, Option<i64>>(12)?.map(|t| t as u64),
+        label: row.get(13)?,
+        tags: row
+            .get::<_, Option<String>>(14)?
+            .and_then(|json| serde_json::from_str(&json).ok())
+            .unwrap_or_default(),
+        error: row.get(15)?,
+    })
+}
+
+/// The artifacts as a JSON array, or NULL when there are none.
+fn artifacts_json(record: &TaskRecord) -> Option<String> {
+    if record.artifacts.is_empty() {
+        return None;
+    }
+    serde_json::to_string(&record.artifacts).ok()
+}
+
+/// The annotations as a JSON object, or NULL when there are none.
+fn annotations_json(record: &TaskRecord) -> Option<String> {
+    if record.annotations.is_empty() {
+        return None;
+    }
+    serde_json::to_string(&record.annotations).ok()
+}
+
+/// The tags as a JSON array, or NULL when there are none.
+fn tags_json(record: &TaskRecord) -> Option<String> {
+    if record.tags.is_empty() {
+        return None;
+    }
+    serde_json::to_string(&record.tags).ok()
+}
+
+impl QueueStore for SqliteStore {
+    fn save_task(&mut self, record: &TaskRecord) -> Result<(), StoreError> {
+        self.conn
+            .execute(
+                "INSERT INTO tasks (id, kind, status, created_at, started_at, finished_at, artifacts, color_tag, annotations, priority, deadline, timed_out, start_at, label, tags, error, generation)
+                    VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17)
+                 ON CONFLICT (generation, id) DO UPDATE SET
+                    status = excluded.status,
+                    started_at = excluded.started_at,
+                    finished_at = excluded.finished_at,
+                    artifacts = excluded.artifacts,
+                    color_tag = excluded.color_tag,
+                    annotations = excluded.annotations,
+                    priority = excluded.priority,
+                    deadline = excluded.deadline,
+                    timed_out = excluded.timed_out,
+                    start_at = excluded.start_at,
+                    label = excluded.label,
+                    tags = excluded.tags,
+                    error = excluded.error",
+                params![
+                    record.id.index() as i64,
+                    record.kind,
+                    record.status.to_string(),
+                    record.created_at as i64,
+                    record.started_at.map(|t| t as i64),
+                    record.finished_at.map(|t| t as i64),
+                    artifacts_json(record),
+                    record.color_tag.map(|tag| tag.to_string()),
+                    annotations_json(record),
+                    record.priority.to_string(),
+                    record.deadline.map(|t| t as i64),
+                    record.timed_out,
+                    record.start_at.map(|t| t as i64),
+                    record.label,
+                    tags_json(record),
+                    record.error,
+                    record.id.generation(),
+                ],
+            )
+            .map(|_| ())
+            .map_err(|e| StoreError::Query(e.to_string()))
+    }
+
+    fn finish_task(&mut self, record: &TaskRecord) -> Result<(), StoreError> {
+        let tx = self
+            .conn
+            .transaction()
+            .map_err(|e| StoreError::Query(e.to_string()))?;
+        tx.execute(
+            "DELETE FROM tasks WHERE generation = ?1 AND id = ?2",
+            params![record.id.generation(), record.id.index() as i64],
+        )
+        .and_then(|_| {
+            tx.execute(
+                "INSERT INTO history (task_id, kind, status, created_at, started_at, finished_at, artifacts, color_tag, annotations, priority, deadline, timed_out, start_at, label, tags, error)
+                    VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16)",
+                params![
+                    record.id.index() as i64,
+                    record.kind,
+                    record.status.to_string(),
+                    record.created_at as i64,
+                    record.started_at.map(|t| t as i64),
+                    record.finished_at.map(|t| t as i64),
+                    artifacts_json(record),
+                    record.color_tag.map(|tag| tag.to_string()),
+                    annotations_json(record),
+                    record.priority.to_string(),
+                    record.deadline.map(|t| t as i64),
+                    record.timed_out,
+                    record.start_at.map(|t| t as i64),
+                    record.label,
+                    tags_json(record),
+                    record.error,
+                ],
+            )
+        })
+        .and_then(|_| tx.commit())
+        .map_err(|e| StoreError::Query(e.to_string()))
+    }
+
+    fn load_history_page(
+        &mut self,
+        offset: usize,
+        limit: usize,
+    ) -> Result<Vec<TaskRecord>, StoreError> {
+        let mut stmt = self
+            .conn
+            .prepare(
+                "SELECT task_id, kind, status, created_at, started_at, finished_at, artifacts, color_tag, annotations, priority, deadline, timed_out, start_at, label, tags, error FROM (
+                    SELECT * FROM history ORDER BY row_id DESC LIMIT ?1 OFFSET ?2
+                 ) ORDER BY row_id ASC",
+            )
+            .map_err(|e| StoreError::Query(e.to_string()))?;
+        let rows = stmt
+            .query_map(params![limit as i64, offset as i64], record_from_row)
+            .map_err(|e| StoreError::Query(e.to_string()))?;
+        rows.collect::<Result<Vec<_>, _>>()
+            .map_err(|e| StoreError::Query(e.to_string()))
+    }
+
+    fn prune_history(
+        &mut self,
+        finished_before: Option<u64>,
+        keep: Option<usize>,
+    ) -> Result<(), StoreError> {
+        if let Some(cutoff) = finished_before {
+            self.conn
+                .execute(
+                    "DELETE FROM history WHERE finished_at < ?1",
+                    params![cutoff as i64],
+                )
+                .map_err(|e| StoreError::Query(e.to_string()))?;
+        }
+        if let Some(keep) = keep {
+            self.conn
+                .execute(
+                    "DELETE FROM history WHERE row_id NOT IN (
+                        SELECT row_id FROM history ORDER BY row_id DESC LIMIT ?1
+                     )",
+                    params![keep.min(i64::MAX as usize) as i64],
+                )
+                .map_err(|e| StoreError::Query(e.to_string()))?;
+        }
+        Ok(())
+    }
+
+    fn clear_history(&mut self) -> Result<(), StoreError> {
+        // Deleted rows stay readable in free pages until the file is rebuilt.
+        self.conn
+            .execute_batch("DELETE FROM history; VACUUM;")
+            .map_err(|e| StoreError::Query(e.to_string()))
+    }
+}
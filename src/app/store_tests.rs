@@ -0,0 +1,235 @@
+#[cfg(test)]
+use crate::app::color_tag::ColorTag;
+#[cfg(test)]
+use crate::app::history::{Artifact, TaskRecord};
+#[cfg(test)]
+use crate::app::store::{sqlite::SqliteStore, QueueStore};
+#[cfg(test)]
+use crate::app::task_id::TaskId;
+#[cfg(test)]
+use crate::app::task_queue::{TaskQueue, TaskStatus};
+
+#[cfg(test)]
+fn temp_db_path(name: &str) -> std::path::PathBuf {
+    let path = std::env::temp_dir().join(format!("{}_{}.sqlite3", name, std::process::id()));
+    let _ = std::fs::remove_file(&path);
+    path
+}
+
+#[test]
+fn test_finished_tasks_move_to_history() {
+    let path = temp_db_path("store_history");
+    let mut store = SqliteStore::open(&path).unwrap();
+    let mut record = TaskRecord::new(TaskId::detached(7), "sleep");
+    store.save_task(&record).unwrap();
+    assert!(store.load_history(10).unwrap().is_empty());
+
+    record.status = TaskStatus::Completed;
+    store.finish_task(&record).unwrap();
+    assert_eq!(store.load_history(10).unwrap(), vec![record]);
+    std::fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn test_artifacts_kept_in_history() {
+    let path = temp_db_path("store_artifacts");
+    let mut store = SqliteStore::open(&path).unwrap();
+    let mut record = TaskRecord::new(TaskId::detached(8), "download");
+    record.status = TaskStatus::Completed;
+    record.artifacts = vec![
+        Artifact::File {
+            path: "out/report.pdf".into(),
+        },
+        Artifact::Text {
+            label: "Summary".to_owned(),
+            text: "3 pages".to_owned(),
+        },
+    ];
+    store.finish_task(&record).unwrap();
+    assert_eq!(store.load_history(10).unwrap(), vec![record]);
+    std::fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn test_color_tag_kept_through_recovery() {
+    let path = temp_db_path("store_color_tag");
+    let mut record = TaskRecord::new(TaskId::detached(9), "render");
+    record.color_tag = Some(ColorTag::Purple);
+    SqliteStore::open(&path)
+        .unwrap()
+        .save_task(&record)
+        .unwrap();
+    let mut reopened = SqliteStore::open(&path).unwrap();
+    assert_eq!(reopened.load_history(10).unwrap(), vec![record]);
+    std::fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn test_annotations_kept_through_recovery() {
+    let path = temp_db_path("store_annotations");
+    let mut record = TaskRecord::new(TaskId::detached(4), "build");
+    record
+        .annotations
+        .insert("commit".to_owned(), "4f1a9c2".to_owned());
+    SqliteStore::open(&path)
+        .unwrap()
+        .save_task(&record)
+        .unwrap();
+    let mut reopened = SqliteStore::open(&path).unwrap();
+    assert_eq!(reopened.load_history(10).unwrap(), vec![record]);
+    std::fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn test_deadline_and_timeout_kept_in_history() {
+    let path = temp_db_path("store_deadline");
+    let mut store = SqliteStore::open(&path).unwrap();
+    let record = TaskRecord {
+        status: TaskStatus::Cancelled,
+        deadline: Some(1_700_000_000_000),
+        timed_out: true,
+        ..TaskRecord::new(TaskId::detached(5), "download")
+    };
+    store.finish_task(&record).unwrap();
+    assert_eq!(store.load_history(10).unwrap(), vec![record]);
+    std::fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn test_unfinished_tasks_recovered_on_reopen() {
+    let path = temp_db_path("store_reopen");
+    let mut record = TaskRecord::new(TaskId::detached(3), "sleep");
+    record.status = TaskStatus::Running;
+    record.label = Some("Nightly backup".to_owned());
+    record.tags = vec!["backup".to_owned(), "nightly".to_owned()];
+    {
+        let mut store = SqliteStore::open(&path).unwrap();
+        store.save_task(&record).unwrap();
+    }
+    let mut store = SqliteStore::open(&path).unwrap();
+    assert_eq!(store.load_history(10).unwrap(), vec![record]);
+    std::fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn test_same_task_number_from_another_queue_kept_apart() {
+    let path = temp_db_path("store_generations");
+    let mut earlier = TaskRecord::new(TaskId::new(1, 0), "sleep");
+    earlier.status = TaskStatus::Running;
+    let mut later = TaskRecord::new(TaskId::new(2, 0), "download");
+    later.status = TaskStatus::Running;
+    {
+        let mut store = SqliteStore::open(&path).unwrap();
+        store.save_task(&earlier).unwrap();
+        store.save_task(&later).unwrap();
+        later.status = TaskStatus::Completed;
+        store.finish_task(&later).unwrap();
+    }
+    let mut store = SqliteStore::open(&path).unwrap();
+    let statuses: Vec<(String, TaskStatus)> = store
+        .load_history(10)
+        .unwrap()
+        .into_iter()
+        .map(|record| (record.kind, record.status))
+        .collect();
+    assert_eq!(
+        statuses,
+        vec![
+            ("download".to_owned(), TaskStatus::Completed),
+            ("sleep".to_owned(), TaskStatus::Running),
+        ]
+    );
+    std::fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn test_queue_preloads_history_from_store() {
+    let path = temp_db_path("store_queue");
+    {
+        let task_queue = TaskQueue::with_store(Box::new(SqliteStore::open(&path).unwrap()));
+        let task =
+            crate::app::sleep_task::SleepTask::new(None, std::time::Duration::from_millis(100));
+        let task_id = task_queue.add_task(task);
+        task_queue.remove_task(task_id).unwrap();
+    }
+    let task_queue = TaskQueue::with_store(Box::new(SqliteStore::open(&path).unwrap()));
+    let history = task_queue.history();
+    assert_eq!(history.len(), 1);
+    assert_eq!(history[0].status, TaskStatus::Cancelled);
+    std::fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn test_queue_pages_history_dropped_from_memory() {
+    let path = temp_db_path("store_paging");
+    let task_queue = TaskQueue::with_store(Box::new(SqliteStore::open(&path).unwrap()));
+    task_queue.set_history_limit(Some(2));
+    for _ in 0..6 {
+        let task = crate::app::sleep_task::SleepTask::new(None, std::time::Duration::from_secs(60));
+        let task_id = task_queue.add_task(task);
+        task_queue.remove_task(task_id).unwrap();
+    }
+    assert!(task_queue.history().len() <= 2);
+    let ids: Vec<usize> = task_queue
+        .history_page(1, 3)
+        .iter()
+        .map(|record| record.id.index())
+        .collect();
+    assert_eq!(ids, vec![2, 3, 4]);
+    std::fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn test_history_pruned_and_cleared() {
+    let path = temp_db_path("store_retention");
+    let mut store = SqliteStore::open(&path).unwrap();
+    for id in 0..5 {
+        let mut record = TaskRecord::new(TaskId::detached(id), "sleep");
+        record.status = TaskStatus::Completed;
+        record.finished_at = Some(1_000 * (id as u64 + 1));
+        store.finish_task(&record).unwrap();
+    }
+    let ids = |store: &mut SqliteStore| -> Vec<usize> {
+        store
+            .load_history(10)
+            .unwrap()
+            .iter()
+            .map(|record| record.id.index())
+            .collect()
+    };
+
+    store.prune_history(Some(2_000), None).unwrap();
+    assert_eq!(ids(&mut store), vec![1, 2, 3, 4]);
+    store.prune_history(None, Some(2)).unwrap();
+    assert_eq!(ids(&mut store), vec![3, 4]);
+    store.clear_history().unwrap();
+    assert!(ids(&mut store).is_empty());
+    std::fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn test_queue_leaves_params_out_of_stored_history() {
+    use crate::app::history::HistoryRetention;
+
+    let path = temp_db_path("store_private");
+    let task_queue = TaskQueue::with_store(Box::new(SqliteStore::open(&path).unwrap()));
+    task_queue.set_history_retention(HistoryRetention {
+        exclude_params: true,
+        ..HistoryRetention::default()
+    });
+    let task = crate::app::sleep_task::SleepTask::new(None, std::time::Duration::from_secs(60));
+    let task_id = task_queue.add_task(task);
+    task_queue
+        .annotate(
+            task_id,
+            [("url".to_owned(), Some("https://example.com".to_owned()))].into(),
+        )
+        .unwrap();
+    task_queue.remove_task(task_id).unwrap();
+    assert!(!task_queue.history()[0].annotations.is_empty());
+    drop(task_queue);
+
+    let mut store = SqliteStore::open(&path).unwrap();
+    assert!(store.load_history(1).unwrap()[0].annotations.is_empty());
+    std::fs::remove_file(path).unwrap();
+}
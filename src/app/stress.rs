@@ -0,0 +1,116 @@
+//! A task kind with no work behind it, for loading the queue and the UI with many tasks.
+
+use crate::app::task_id::TaskId;
+use crate::app::task_queue::{
+    PollResult, PollingData, Task, TaskError, TaskKind, TaskQueue, TaskStatus,
+};
+
+/// How many tasks Options → Stress test enqueues.
+pub const STRESS_TASK_COUNT: usize = 100_000;
+
+/// Completes after being polled a fixed number of times, so it costs nothing but the
+/// queue's own bookkeeping and only advances as fast as it is polled.
+pub struct StressTask {
+    id: Option<TaskId>,
+    status: TaskStatus,
+    polls: u32,
+    polls_needed: u32,
+}
+
+impl StressTask {
+    pub fn new(polls_needed: u32) -> Self {
+        StressTask {
+            id: None,
+            status: TaskStatus::Queued,
+            polls: 0,
+            polls_needed: polls_needed.max(1),
+        }
+    }
+
+    fn progress(&self) -> PollingData {
+        PollingData::Float(self.polls as f32 / self.polls_needed as f32)
+    }
+}
+
+impl Task for StressTask {
+    fn id(&self) -> Result<TaskId, TaskError> {
+        self.id.ok_or(TaskError::IdUsizeIsNone)
+    }
+
+    fn set_id(&mut self, id: TaskId) {
+        self.id = Some(id);
+    }
+
+    fn poll(&mut self) -> PollResult {
+        match self.status {
+            TaskStatus::Scheduled | TaskStatus::Queued | TaskStatus::Running => {
+                self.polls += 1;
+                if self.polls >= self.polls_needed {
+                    self.status = TaskStatus::Completed;
+                    PollResult::Completed
+                } else {
+                    self.status = TaskStatus::Running;
+                    PollResult::Pending(self.progress())
+                }
+            }
+            TaskStatus::Paused => PollResult::Paused(self.progress()),
+            TaskStatus::Completed => PollResult::Completed,
+            TaskStatus::Cancelled | TaskStatus::Failed | TaskStatus::Interrupted => {
+                PollResult::Cancelled
+            }
+        }
+    }
+
+    fn cancel(&mut self) -> Result<(), TaskError> {
+        match self.status {
+            TaskStatus::Completed => Err(TaskError::AlreadyCompleted),
+            TaskStatus::Cancelled | TaskStatus::Failed | TaskStatus::Interrupted => {
+                Err(TaskError::AlreadyCancelled)
+            }
+            _ => {
+                self.status = TaskStatus::Cancelled;
+                Ok(())
+            }
+        }
+    }
+
+    fn pause(&mut self) -> Result<(), TaskError> {
+        match self.status {
+            TaskStatus::Scheduled | TaskStatus::Queued | TaskStatus::Running => {
+                self.status = TaskStatus::Paused;
+                Ok(())
+            }
+            TaskStatus::Paused => Err(TaskError::AlreadyPaused),
+            TaskStatus::Completed => Err(TaskError::AlreadyCompleted),
+            TaskStatus::Cancelled | TaskStatus::Failed | TaskStatus::Interrupted => {
+                Err(TaskError::AlreadyCancelled)
+            }
+        }
+    }
+
+    fn resume(&mut self) -> Result<(), TaskError> {
+        match self.status {
+            TaskStatus::Paused => {
+                self.status = TaskStatus::Running;
+                Ok(())
+            }
+            TaskStatus::Scheduled | TaskStatus::Queued | TaskStatus::Running => {
+                Err(TaskError::AlreadyRunning)
+            }
+            TaskStatus::Completed => Err(TaskError::AlreadyCompleted),
+            TaskStatus::Cancelled | TaskStatus::Failed | TaskStatus::Interrupted => {
+                Err(TaskError::AlreadyCancelled)
+            }
+        }
+    }
+
+    fn kind(&self) -> TaskKind {
+        TaskKind::Stress
+    }
+}
+
+/// Adds `count` stress tasks in one batch, each needing between 20 and 59 polls so they
+/// do not all finish on the same frame. Returns their ids.
+pub fn enqueue(queue: &TaskQueue, count: usize) -> Vec<TaskId> {
+    queue.add_tasks((0..count).map(|i| StressTask::new(20 + (i % 40) as u32)))
+}
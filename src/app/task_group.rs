@@ -0,0 +1,59 @@
+//! Tasks grouped to be followed, paused and cancelled as one, e.g. the tasks of a job
+//! file. Grouping changes nothing about how each task runs, only, under a concurrency
+//! limit, which queued tasks start first. See
+//! [`TaskQueue::create_group`](crate::app::task_queue::TaskQueue::create_group) and
+//! [`TaskQueue::move_group`](crate::app::task_queue::TaskQueue::move_group).
+
+use crate::app::task_id::TaskId;
+use crate::app::task_queue::{PollResult, PollingData};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TaskGroup {
+    pub id: usize,
+    /// In the order they were grouped.
+    pub tasks: Vec<TaskId>,
+}
+
+/// One result standing for the results of a group's tasks. Progress is the mean over the
+/// tasks not cancelled or failed, completed ones counting as done. The group is pending
+/// while any task is, paused while its unfinished tasks all are, failed with the first
+/// task's error if every task was cancelled or failed and one failed, cancelled if every
+/// task was, and completed once every task has ended with at least one completed.
+pub fn combine(results: impl IntoIterator<Item = PollResult>) -> PollResult {
+    let (mut sum, mut counted, mut pending, mut paused) = (0.0, 0, false, false);
+    let mut failure = None;
+    for result in results {
+        let progress = match result {
+            PollResult::Pending(data) => {
+                pending = true;
+                data.fraction()
+            }
+            PollResult::Paused(data) => {
+                paused = true;
+                data.fraction()
+            }
+            PollResult::Completed => 1.0,
+            PollResult::Cancelled => continue,
+            PollResult::Failed(error) => {
+                failure.get_or_insert(error);
+                continue;
+            }
+        };
+        sum += progress.clamp(0.0, 1.0);
+        counted += 1;
+    }
+    let progress = PollingData::Float(if counted == 0 {
+        0.0
+    } else {
+        sum / counted as f32
+    });
+    if pending {
+        PollResult::Pending(progress)
+    } else if paused {
+        PollResult::Paused(progress)
+    } else if counted == 0 {
+        failure.map_or(PollResult::Cancelled, PollResult::Failed)
+    } else {
+        PollResult::Completed
+    }
+}
@@ -0,0 +1,100 @@
+#[cfg(test)]
+use crate::app::sleep_task::SleepTask;
+#[cfg(test)]
+use crate::app::task_group::combine;
+#[cfg(test)]
+use crate::app::task_queue::{PollResult, PollingData, TaskError, TaskQueue, TaskStatus};
+
+#[test]
+fn test_combined_group_progress() {
+    let pending = |p| PollResult::Pending(PollingData::Float(p));
+    let paused = |p| PollResult::Paused(PollingData::Float(p));
+    assert_eq!(
+        combine([pending(0.5), PollResult::Completed, PollResult::Cancelled]),
+        pending(0.75)
+    );
+    assert_eq!(combine([paused(0.0), PollResult::Completed]), paused(0.5));
+    assert_eq!(
+        combine([PollResult::Completed, PollResult::Cancelled]),
+        PollResult::Completed
+    );
+    assert_eq!(
+        combine([PollResult::Cancelled, PollResult::Cancelled]),
+        PollResult::Cancelled
+    );
+    let failed = PollResult::Failed(TaskError::Failed {
+        reason: "Disk full".to_owned(),
+    });
+    assert_eq!(
+        combine([PollResult::Cancelled, failed.clone()]),
+        failed.clone()
+    );
+    assert_eq!(
+        combine([failed, PollResult::Completed]),
+        PollResult::Completed
+    );
+}
+
+#[test]
+fn test_group_paused_and_cancelled_together() {
+    let task_queue = TaskQueue::new();
+    let ids = task_queue
+        .add_tasks((0..3).map(|_| SleepTask::new(None, std::time::Duration::from_secs(60))));
+    let group = task_queue.create_group(&ids[..2]).unwrap();
+    assert_eq!(task_queue.group(group).unwrap().tasks, ids[..2].to_vec());
+    assert_eq!(task_queue.group_of(ids[1]), Some(group));
+    assert_eq!(task_queue.group_of(ids[2]), None);
+    assert_eq!(
+        task_queue.create_group(&[task_queue.task_id(99)]),
+        Err(TaskError::NotFound)
+    );
+    assert!(matches!(
+        task_queue.poll_group(group),
+        Ok(PollResult::Pending(_))
+    ));
+
+    task_queue.pause_group(group).unwrap();
+    assert!(matches!(
+        task_queue.group_progress(group),
+        Ok(PollResult::Paused(_))
+    ));
+    assert_eq!(task_queue.status(ids[2]), Ok(TaskStatus::Queued));
+    task_queue.resume_group(group).unwrap();
+    assert_eq!(task_queue.status(ids[0]), Ok(TaskStatus::Running));
+
+    task_queue.cancel_group(group).unwrap();
+    assert_eq!(task_queue.poll_group(group), Ok(PollResult::Cancelled));
+    assert_eq!(task_queue.status(ids[2]), Ok(TaskStatus::Queued));
+    task_queue.remove_task(ids[2]).unwrap();
+}
+
+#[test]
+fn test_moved_group_is_let_in_first() {
+    let task_queue = TaskQueue::with_concurrency(1);
+    let ids = task_queue
+        .add_tasks((0..5).map(|_| SleepTask::new(None, std::time::Duration::from_secs(60))));
+    let first = task_queue.create_group(&ids[1..3]).unwrap();
+    let second = task_queue.create_group(&ids[3..]).unwrap();
+    assert_eq!(
+        task_queue.queued_order(),
+        [ids[1], ids[2], ids[3], ids[4], ids[0]]
+    );
+
+    task_queue.move_group(second, 0).unwrap();
+    let order: Vec<usize> = task_queue.groups().iter().map(|group| group.id).collect();
+    assert_eq!(order, [second, first]);
+    assert_eq!(
+        task_queue.queued_order(),
+        [ids[3], ids[4], ids[1], ids[2], ids[0]]
+    );
+    task_queue.poll_task(ids[1]).unwrap();
+    task_queue.poll_task(ids[3]).unwrap();
+    assert_eq!(task_queue.status(ids[1]), Ok(TaskStatus::Queued));
+    assert_eq!(task_queue.status(ids[3]), Ok(TaskStatus::Running));
+
+    // Past the end goes last.
+    task_queue.move_group(second, 9).unwrap();
+    assert_eq!(task_queue.groups()[1].id, second);
+    assert_eq!(task_queue.move_group(99, 0), Err(TaskError::NotFound));
+    task_queue.remove_tasks(&ids);
+}
@@ -0,0 +1,65 @@
+//! Task ids that remember which queue handed them out.
+//!
+//! A queue numbers its tasks from 0 and never hands a number out twice, but another queue
+//! starts over: e.g. the one the app swaps in when it switches to the SQLite store, or
+//! the next run of the program. So an id also carries its queue's generation, and a
+//! queue treats an id from another generation as naming none of its tasks, rather than
+//! whichever task took that number there.
+
+use std::fmt::{Display, Formatter, Result as FmtResult};
+use std::sync::atomic::{AtomicU32, Ordering};
+
+/// Generation 0 is for ids read back from outside, which name no task of any queue.
+static NEXT_GENERATION: AtomicU32 = AtomicU32::new(1);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct TaskId {
+    generation: u32,
+    index: usize,
+}
+
+impl TaskId {
+    /// A generation no other queue in this process has.
+    pub fn new_generation() -> u32 {
+        NEXT_GENERATION.fetch_add(1, Ordering::Relaxed)
+    }
+
+    pub fn new(generation: u32, index: usize) -> Self {
+        TaskId { generation, index }
+    }
+
+    /// The id of a task numbered `index` by a queue that is gone, e.g. in a saved record.
+    pub fn detached(index: usize) -> Self {
+        TaskId::new(0, index)
+    }
+
+    /// The task's number within its queue, as shown to the user and saved.
+    pub fn index(&self) -> usize {
+        self.index
+    }
+
+    pub fn generation(&self) -> u32 {
+        self.generation
+    }
+}
+
+/// Shows the task's number only: it is unique among the tasks the user can see.
+impl Display for TaskId {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        self.index.fmt(f)
+    }
+}
+
+/// Saved as the bare number, as ids were before they had generations, and read back
+/// [`detached`](TaskId::detached).
+impl serde::Serialize for TaskId {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.index.serialize(serializer)
+    }
+}
+
+impl<'de> serde::Deserialize<'de> for TaskId {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        usize::deserialize(deserializer).map(TaskId::detached)
+    }
+}
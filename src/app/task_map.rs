@@ -0,0 +1,150 @@
+//! The queue's tasks by id, split into shards that each have a lock of their own, so
+//! threads polling different tasks do not all contend for one lock, and adding a task
+//! only holds up lookups in the shard it goes into.
+//!
+//! Ids are handed out in order by an [`Adder`], and an id is only listed once every lower
+//! one has been added, so a caller listing the tasks from some id on misses none.
+
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Mutex as sync_Mutex, MutexGuard, RwLock as sync_RwLock};
+
+const SHARDS: usize = 16;
+
+pub struct TaskMap<V> {
+    /// A task is in shard `id % SHARDS`.
+    shards: Vec<sync_RwLock<BTreeMap<usize, V>>>,
+    /// The next id to hand out; held while adding.
+    next_id: sync_Mutex<usize>,
+    /// Every id below this has been added.
+    listed: AtomicUsize,
+}
+
+impl<V> Default for TaskMap<V> {
+    fn default() -> Self {
+        TaskMap {
+            shards: (0..SHARDS)
+                .map(|_| sync_RwLock::new(BTreeMap::new()))
+                .collect(),
+            next_id: sync_Mutex::new(0),
+            listed: AtomicUsize::new(0),
+        }
+    }
+}
+
+impl<V> TaskMap<V> {
+    fn shard(&self, id: usize) -> &sync_RwLock<BTreeMap<usize, V>> {
+        &self.shards[id % SHARDS]
+    }
+}
+
+impl<V: Clone> TaskMap<V> {
+    pub fn new() -> Self {
+        TaskMap::default()
+    }
+
+    /// Locks out other adders until the returned [`Adder`] is dropped. Lookups go on.
+    pub fn adder(&self) -> Adder<'_, V> {
+        Adder {
+            next_id: self
+                .next_id
+                .lock()
+                .expect("Panicked at TaskMap::adder: Id mutex poisoned"),
+            map: self,
+        }
+    }
+
+    pub fn get(&self, id: usize) -> Option<V> {
+        self.shard(id)
+            .read()
+            .expect("Panicked at TaskMap::get: Shard lock poisoned")
+            .get(&id)
+            .cloned()
+    }
+
+    /// The task for each of `ids`, in order, with each shard locked at most once.
+    pub fn get_many(&self, ids: &[usize]) -> Vec<Option<V>> {
+        let mut values = vec![None; ids.len()];
+        for (index, shard) in self.shards.iter().enumerate() {
+            if !ids.iter().any(|id| id % SHARDS == index) {
+                continue;
+            }
+            let shard = shard
+                .read()
+                .expect("Panicked at TaskMap::get_many: Shard lock poisoned");
+            for (value, id) in values.iter_mut().zip(ids) {
+                if id % SHARDS == index {
+                    *value = shard.get(id).cloned();
+                }
+            }
+        }
+        values
+    }
+
+    /// The tasks with ids from `first_id` on, ordered by id; none if `first_id` is past
+    /// the last one listed. Each shard is only locked while its tasks are copied out.
+    pub fn values_from(&self, first_id: usize) -> Vec<(usize, V)> {
+        let listed = self.listed.load(Ordering::Acquire);
+        let mut values = Vec::new();
+        if first_id >= listed {
+            return values;
+        }
+        for shard in &self.shards {
+            let shard = shard
+                .read()
+                .expect("Panicked at TaskMap::values_from: Shard lock poisoned");
+            values.extend(
+                shard
+                    .range(first_id..listed)
+                    .map(|(&id, value)| (id, value.clone())),
+            );
+        }
+        values.sort_unstable_by_key(|&(id, _)| id);
+        values
+    }
+
+    /// Every task, ordered by id.
+    pub fn values(&self) -> Vec<(usize, V)> {
+        self.values_from(0)
+    }
+
+    /// Removes the tasks `keep` returns false for, one shard at a time. Their ids are not
+    /// handed out again. Returns how many were removed.
+    pub fn retain(&self, mut keep: impl FnMut(usize, &V) -> bool) -> usize {
+        let mut removed = 0;
+        for shard in &self.shards {
+            let mut shard = shard
+                .write()
+                .expect("Panicked at TaskMap::retain: Shard lock poisoned");
+            let before = shard.len();
+            shard.retain(|&id, value| keep(id, value));
+            removed += before - shard.len();
+        }
+        removed
+    }
+}
+
+/// Adds tasks to a [`TaskMap`], one id after another.
+pub struct Adder<'a, V> {
+    map: &'a TaskMap<V>,
+    next_id: MutexGuard<'a, usize>,
+}
+
+impl<V> Adder<'_, V> {
+    /// The id the next [`insert`](Self::insert) adds under.
+    pub fn next_id(&self) -> usize {
+        *self.next_id
+    }
+
+    /// Adds `value` under [`next_id`](Self::next_id), and lists it.
+    pub fn insert(&mut self, value: V) {
+        let id = *self.next_id;
+        self.map
+            .shard(id)
+            .write()
+            .expect("Panicked at Adder::insert: Shard lock poisoned")
+            .insert(id, value);
+        *self.next_id += 1;
+        self.map.listed.store(*self.next_id, Ordering::Release);
+    }
+}
@@ -0,0 +1,42 @@
+#[cfg(test)]
+use std::sync::Arc;
+
+#[cfg(test)]
+use crate::app::task_map::TaskMap;
+
+#[test]
+fn test_task_map_lists_in_id_order_across_shards() {
+    let map = TaskMap::new();
+    let mut adder = map.adder();
+    for id in 0..40 {
+        assert_eq!(adder.next_id(), id);
+        adder.insert(id * 10);
+    }
+    drop(adder);
+    assert_eq!(map.get(7), Some(70));
+    assert_eq!(map.get(40), None);
+    let ids: Vec<usize> = map.values_from(25).into_iter().map(|(id, _)| id).collect();
+    assert_eq!(ids, (25..40).collect::<Vec<_>>());
+    assert_eq!(map.values().len(), 40);
+    assert!(map.values_from(45).is_empty());
+}
+
+#[test]
+fn test_task_map_never_lists_an_id_before_the_lower_ones() {
+    let map = Arc::new(TaskMap::new());
+    let adders: Vec<_> = (0..4)
+        .map(|_| {
+            let map = map.clone();
+            std::thread::spawn(move || {
+                for _ in 0..250 {
+                    map.adder().insert(());
+                }
+            })
+        })
+        .collect();
+    while adders.iter().any(|adder| !adder.is_finished()) {
+        let ids: Vec<usize> = map.values().into_iter().map(|(id, _)| id).collect();
+        assert_eq!(ids, (0..ids.len()).collect::<Vec<_>>());
+    }
+    assert_eq!(map.values().len(), 1000);
+}
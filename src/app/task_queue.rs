@@ -1,23 +1,217 @@
-use std::collections::HashMap;
+use std::any::Any;
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
 use std::fmt::{Display, Formatter, Result as FmtResult};
+#[cfg(not(target_arch = "wasm32"))]
+use std::io::Write;
+#[cfg(not(target_arch = "wasm32"))]
+use std::path::Path;
+use std::path::PathBuf;
+use std::str::FromStr;
+#[cfg(not(target_arch = "wasm32"))]
+use std::sync::mpsc;
 use std::sync::{
-    atomic::{AtomicUsize, Ordering},
-    Arc as sync_Arc, Mutex as sync_Mutex,
+    atomic::{AtomicBool, AtomicU64, AtomicU8, AtomicUsize, Ordering},
+    Arc as sync_Arc, Mutex as sync_Mutex, RwLock as sync_RwLock, TryLockError,
 };
+use std::time::Duration;
 
 use async_std::channel;
 use async_std::channel::Receiver;
-use async_std::task;
 use log::debug;
 
+use crate::app::audit::{AuditAction, AuditEntry, Origin};
+use crate::app::calendar::{self, PlannedRun};
+use crate::app::cancellation::CancellationToken;
+#[cfg(debug_assertions)]
+use crate::app::chaos::{Chaos, ChaosConfig, ChaosEffect};
+use crate::app::color_tag::ColorTag;
+#[cfg(not(target_arch = "wasm32"))]
+use crate::app::deadline::{self, DeadlineAtRisk};
+use crate::app::disk_space;
+use crate::app::executor::{self, Instant, JobHandle, JobTicket};
+use crate::app::forecast::{self, ActiveTask, TypicalDurations};
+use crate::app::format;
+use crate::app::history::{now_millis, Artifact, HistoryRetention, TaskRecord};
+#[cfg(not(target_arch = "wasm32"))]
+use crate::app::history_spill::HistoryFile;
+#[cfg(not(target_arch = "wasm32"))]
+use crate::app::journal::{Journal, JournalEntry};
+use crate::app::priority::Priority;
+use crate::app::profiler::profile_function;
+use crate::app::progress_channel::{self, ProgressReceiver, ProgressSender};
+use crate::app::resource_usage::ResourceUsage;
+use crate::app::scheduler::{slot_key, Scheduler};
+#[cfg(not(target_arch = "wasm32"))]
+use crate::app::speed_limit::SpeedLimit;
+#[cfg(all(feature = "sqlite", not(target_arch = "wasm32")))]
+use crate::app::store::QueueStore;
+use crate::app::task_group::{self, TaskGroup};
+use crate::app::task_id::TaskId;
+use crate::app::task_map::{Adder, TaskMap};
+
+#[cfg(all(feature = "sqlite", not(target_arch = "wasm32")))]
+const STORE_HISTORY_PRELOAD: usize = 500;
+/// Most audit entries kept; the oldest are dropped first.
+const AUDIT_LIMIT: usize = 10_000;
+
+/// Where a task is with its [`Scheduler`] slot.
+const SLOT_NONE: u8 = 0;
+const SLOT_HELD: u8 = 1;
+const SLOT_RELEASED: u8 = 2;
+
+const NO_DEADLINE: u64 = 0;
+
 pub trait Task: Send + Sync {
-    fn id(&self) -> Result<usize, TaskError>;
-    fn set_id(&mut self, id: usize);
+    fn id(&self) -> Result<TaskId, TaskError>;
+    fn set_id(&mut self, id: TaskId);
+    /// Gives the task the token its queue cancels once the task ends, so that the work it
+    /// runs can stop on it promptly; see [`CancellationToken::run`]. Tasks that check
+    /// their status instead can ignore it.
+    fn set_cancellation_token(&mut self, _token: CancellationToken) {}
+    /// Gives the task a channel to push its progress through as it goes. While a task
+    /// keeps pushing, polling it reads the latest value instead of calling
+    /// [`Task::poll`], which is still called whenever nothing new was pushed, and so
+    /// must keep reporting the task's progress and how it ended.
+    fn set_progress_sender(&mut self, _sender: ProgressSender) {}
     fn poll(&mut self) -> PollResult;
     fn cancel(&mut self) -> Result<(), TaskError>;
     fn pause(&mut self) -> Result<(), TaskError>;
     fn resume(&mut self) -> Result<(), TaskError>;
     fn kind(&self) -> TaskKind;
+
+    /// A line about the task's progress beyond its fraction, e.g. how many bytes are
+    /// transferred. Only read by [`TaskQueue::task_detail`], never while polling.
+    fn message(&self) -> Option<String> {
+        None
+    }
+
+    /// CPU time and I/O the task's work has used so far, for tasks that measure it. Read
+    /// like [`Task::message`].
+    fn resource_usage(&self) -> Option<ResourceUsage> {
+        None
+    }
+
+    /// The job running the task's work, if it started one, for [`TaskQueue::shutdown`] to
+    /// wait on once the task is stopped. Asked at most once.
+    fn take_handle(&mut self) -> Option<JobHandle> {
+        None
+    }
+
+    /// Files, URLs or text the task produced. Asked once, when the task completes, and
+    /// kept in its record.
+    fn artifacts(&self) -> Vec<Artifact> {
+        Vec::new()
+    }
+
+    /// The value the task computed, e.g. the path it downloaded to, for
+    /// [`TaskQueue::take_result`] to hand over. Only asked once the task has completed,
+    /// and at most once, so the task may move the value out.
+    fn take_result(&mut self) -> Option<Box<dyn Any + Send>> {
+        None
+    }
+
+    /// Where the task writes its output and how many bytes it needs there, if known
+    /// before it starts. The queue checks the free space first and cancels the task with
+    /// [`TaskError::InsufficientSpace`] rather than start it without room.
+    fn required_space(&self) -> Option<(PathBuf, u64)> {
+        None
+    }
+
+    /// Checks the task's inputs before it is queued, e.g. that a directory it writes to
+    /// exists, and reports the first that cannot be used as [`TaskError::InvalidInput`].
+    /// The registry validates every task it creates, and
+    /// [`TaskQueue::try_add_task`] every task it adds.
+    fn validate(&self) -> Result<(), TaskError> {
+        Ok(())
+    }
+
+    /// How urgent the task is when added. With a concurrency limit in effect, more urgent
+    /// tasks get a worker first; [`TaskQueue::set_priority`] changes it later.
+    fn priority(&self) -> Priority {
+        Priority::Normal
+    }
+
+    /// The task's own speed limit, for kinds that move bytes at a rate that can be
+    /// throttled. The UI adjusts it while the task runs.
+    #[cfg(not(target_arch = "wasm32"))]
+    fn speed_limit(&self) -> Option<sync_Arc<SpeedLimit>> {
+        None
+    }
+}
+
+impl<T: Task + ?Sized> Task for Box<T> {
+    fn id(&self) -> Result<TaskId, TaskError> {
+        (**self).id()
+    }
+
+    fn set_id(&mut self, id: TaskId) {
+        (**self).set_id(id)
+    }
+
+    fn set_cancellation_token(&mut self, token: CancellationToken) {
+        (**self).set_cancellation_token(token)
+    }
+
+    fn set_progress_sender(&mut self, sender: ProgressSender) {
+        (**self).set_progress_sender(sender)
+    }
+
+    fn poll(&mut self) -> PollResult {
+        (**self).poll()
+    }
+
+    fn cancel(&mut self) -> Result<(), TaskError> {
+        (**self).cancel()
+    }
+
+    fn pause(&mut self) -> Result<(), TaskError> {
+        (**self).pause()
+    }
+
+    fn resume(&mut self) -> Result<(), TaskError> {
+        (**self).resume()
+    }
+
+    fn kind(&self) -> TaskKind {
+        (**self).kind()
+    }
+
+    fn message(&self) -> Option<String> {
+        (**self).message()
+    }
+
+    fn resource_usage(&self) -> Option<ResourceUsage> {
+        (**self).resource_usage()
+    }
+
+    fn take_handle(&mut self) -> Option<JobHandle> {
+        (**self).take_handle()
+    }
+
+    fn artifacts(&self) -> Vec<Artifact> {
+        (**self).artifacts()
+    }
+
+    fn take_result(&mut self) -> Option<Box<dyn Any + Send>> {
+        (**self).take_result()
+    }
+
+    fn required_space(&self) -> Option<(PathBuf, u64)> {
+        (**self).required_space()
+    }
+
+    fn validate(&self) -> Result<(), TaskError> {
+        (**self).validate()
+    }
+
+    fn priority(&self) -> Priority {
+        (**self).priority()
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    fn speed_limit(&self) -> Option<sync_Arc<SpeedLimit>> {
+        (**self).speed_limit()
+    }
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -28,179 +222,2944 @@ pub enum TaskError {
     AlreadyCancelled,
     AlreadyCompleted,
     IdUsizeIsNone,
+    /// The task's destination has less free space than it needs, in bytes.
+    InsufficientSpace {
+        needed: u64,
+        available: u64,
+    },
+    /// The task's input `field`, named as in its kind's parameters, cannot be used.
+    InvalidInput {
+        field: String,
+        message: String,
+    },
+    /// The task ran for longer than its timeout and was stopped.
+    TimedOut {
+        timeout: Duration,
+    },
+    /// The task's work went wrong, e.g. a transfer that broke off.
+    Failed {
+        reason: String,
+    },
+    /// The queue was shut down and takes no more tasks; see [`TaskQueue::shutdown`].
+    ShuttingDown,
+}
+
+impl Display for TaskError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        match self {
+            TaskError::NotFound => write!(f, "Task not found"),
+            TaskError::AlreadyRunning => write!(f, "Task is already running"),
+            TaskError::AlreadyPaused => write!(f, "Task is already paused"),
+            TaskError::AlreadyCancelled => write!(f, "Task is already cancelled"),
+            TaskError::AlreadyCompleted => write!(f, "Task is already completed"),
+            TaskError::IdUsizeIsNone => write!(f, "Task has no id"),
+            TaskError::InsufficientSpace { needed, available } => write!(
+                f,
+                "Not enough disk space: {} needed, {} available",
+                format::bytes(*needed),
+                format::bytes(*available)
+            ),
+            TaskError::InvalidInput { field, message } => {
+                write!(f, "Invalid {}: {}", field, message)
+            }
+            TaskError::TimedOut { timeout } => {
+                write!(f, "Timed out after {}", format::duration(*timeout))
+            }
+            TaskError::Failed { reason } => write!(f, "{}", reason),
+            TaskError::ShuttingDown => write!(f, "The queue is shutting down"),
+        }
+    }
 }
 
 #[derive(Debug, Clone, PartialEq)]
 pub enum TaskKind {
     Sleep,
-    // Download,
-    // Process,
+    Primes,
+    /// Placeholder work added in bulk by the stress test.
+    Stress,
+    #[cfg(all(
+        any(feature = "plugins", feature = "wasm-plugins"),
+        not(target_arch = "wasm32")
+    ))]
+    Plugin(String),
+    /// A task run by a remote agent, named `<kind>@<agent>`.
+    #[cfg(all(feature = "remote-agent", not(target_arch = "wasm32")))]
+    Remote(String),
+    #[cfg(not(target_arch = "wasm32"))]
+    Download,
+    #[cfg(not(target_arch = "wasm32"))]
+    Process,
+}
+
+impl TaskKind {
+    /// Short lowercase name used in config files and persisted records.
+    pub fn name(&self) -> &str {
+        match self {
+            TaskKind::Sleep => "sleep",
+            TaskKind::Primes => "primes",
+            TaskKind::Stress => "stress",
+            #[cfg(all(
+                any(feature = "plugins", feature = "wasm-plugins"),
+                not(target_arch = "wasm32")
+            ))]
+            TaskKind::Plugin(name) => name,
+            #[cfg(all(feature = "remote-agent", not(target_arch = "wasm32")))]
+            TaskKind::Remote(name) => name,
+            #[cfg(not(target_arch = "wasm32"))]
+            TaskKind::Download => "download",
+            #[cfg(not(target_arch = "wasm32"))]
+            TaskKind::Process => "process",
+        }
+    }
 }
 
 impl Display for TaskKind {
     fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
         match self {
             TaskKind::Sleep => write!(f, "Sleep task"),
-            // TaskKind::Download => write!(f, "Download task"),
-            // TaskKind::Process => write!(f, "Process task"),
+            TaskKind::Primes => write!(f, "Primes task"),
+            TaskKind::Stress => write!(f, "Stress task"),
+            #[cfg(all(
+                any(feature = "plugins", feature = "wasm-plugins"),
+                not(target_arch = "wasm32")
+            ))]
+            TaskKind::Plugin(name) => write!(f, "Plugin task ({})", name),
+            #[cfg(all(feature = "remote-agent", not(target_arch = "wasm32")))]
+            TaskKind::Remote(name) => write!(f, "Remote task ({})", name),
+            #[cfg(not(target_arch = "wasm32"))]
+            TaskKind::Download => write!(f, "Download task"),
+            #[cfg(not(target_arch = "wasm32"))]
+            TaskKind::Process => write!(f, "Process task"),
         }
     }
 }
 
-#[derive(Debug, Clone, PartialEq)]
+/// Progress as reported by a poll. Kept `Copy` so that polling thousands of tasks a frame
+/// allocates nothing; anything richer, such as a status message from [`Task::message`],
+/// goes through [`TaskQueue::task_detail`].
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum PollingData {
+    /// The fraction done, from 0 to 1.
     Float(f32),
-    // Tuple((usize, String, f32)),
-    // HashMap(HashMap<usize, (String, f32)>)
+    /// Bytes moved so far, out of `total` if it is known.
+    Bytes { done: u64, total: Option<u64> },
+    /// The step being worked on, counting from 1, out of `total`.
+    Steps { current: u32, total: u32 },
+}
+
+impl PollingData {
+    /// The fraction done, from 0 to 1, for progress bars and estimates. Zero while a
+    /// byte count has no known total.
+    pub fn fraction(&self) -> f32 {
+        match *self {
+            PollingData::Float(p) => p,
+            PollingData::Bytes { total: Some(0), .. } => 1.0,
+            PollingData::Bytes {
+                done,
+                total: Some(total),
+            } => (done as f64 / total as f64).min(1.0) as f32,
+            PollingData::Bytes { total: None, .. } => 0.0,
+            PollingData::Steps { total: 0, .. } => 0.0,
+            PollingData::Steps { current, total } => (current as f32 / total as f32).min(1.0),
+        }
+    }
 }
 
 impl Display for PollingData {
     fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
         match self {
             PollingData::Float(float_value) => write!(f, "{}", float_value),
-            // PollingData::Tuple((id, name, progress)) => write!(f, "({}, {}, {})", id, name, progress),
-            // PollingData::HashMap(map) => {
-            //     let mut s = String::new();
-            //     for (id, data) in map {
-            //         s.push_str(&format!("({}, ({}, {})), ", id, data.0, data.1));
-            //     }
-            //     write!(f, "{}", s)
-            // }
+            PollingData::Bytes {
+                done,
+                total: Some(total),
+            } => write!(f, "{} of {}", format::bytes(*done), format::bytes(*total)),
+            PollingData::Bytes { done, total: None } => write!(f, "{}", format::bytes(*done)),
+            PollingData::Steps { current, total } => write!(f, "step {} of {}", current, total),
         }
     }
 }
 
+/// Not `Copy`, for the error of a failed task; cloning any other result allocates nothing.
 #[derive(Debug, Clone, PartialEq)]
 pub enum PollResult {
     Pending(PollingData),
     Paused(PollingData),
     Completed,
     Cancelled,
+    /// The task ended with an error rather than being cancelled.
+    Failed(TaskError),
 }
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, serde::Deserialize, serde::Serialize)]
 pub enum TaskStatus {
+    /// Held until its start time; see [`TaskQueue::schedule_task`].
+    Scheduled,
     Queued,
     Running,
     Paused,
     Completed,
     Cancelled,
+    /// Ended with an error, kept in the record's `error`.
+    Failed,
+    /// Was running when the app last stopped without shutting down; see
+    /// [`Journal`](crate::app::journal::Journal). Only ever found in history.
+    Interrupted,
+}
+
+impl TaskStatus {
+    pub fn is_terminal(&self) -> bool {
+        matches!(
+            self,
+            TaskStatus::Completed
+                | TaskStatus::Cancelled
+                | TaskStatus::Failed
+                | TaskStatus::Interrupted
+        )
+    }
+
+    fn to_byte(&self) -> u8 {
+        match self {
+            TaskStatus::Queued => 0,
+            TaskStatus::Running => 1,
+            TaskStatus::Paused => 2,
+            TaskStatus::Completed => 3,
+            TaskStatus::Cancelled => 4,
+            TaskStatus::Interrupted => 5,
+            TaskStatus::Scheduled => 6,
+            TaskStatus::Failed => 7,
+        }
+    }
+
+    fn from_byte(byte: u8) -> Self {
+        match byte {
+            0 => TaskStatus::Queued,
+            1 => TaskStatus::Running,
+            2 => TaskStatus::Paused,
+            3 => TaskStatus::Completed,
+            5 => TaskStatus::Interrupted,
+            6 => TaskStatus::Scheduled,
+            7 => TaskStatus::Failed,
+            _ => TaskStatus::Cancelled,
+        }
+    }
+}
+
+impl Display for TaskStatus {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        match self {
+            TaskStatus::Scheduled => write!(f, "scheduled"),
+            TaskStatus::Queued => write!(f, "queued"),
+            TaskStatus::Running => write!(f, "running"),
+            TaskStatus::Paused => write!(f, "paused"),
+            TaskStatus::Completed => write!(f, "completed"),
+            TaskStatus::Cancelled => write!(f, "cancelled"),
+            TaskStatus::Failed => write!(f, "failed"),
+            TaskStatus::Interrupted => write!(f, "interrupted"),
+        }
+    }
+}
+
+impl FromStr for TaskStatus {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "scheduled" => Ok(TaskStatus::Scheduled),
+            "queued" => Ok(TaskStatus::Queued),
+            "running" => Ok(TaskStatus::Running),
+            "paused" => Ok(TaskStatus::Paused),
+            "completed" => Ok(TaskStatus::Completed),
+            "cancelled" => Ok(TaskStatus::Cancelled),
+            "failed" => Ok(TaskStatus::Failed),
+            "interrupted" => Ok(TaskStatus::Interrupted),
+            _ => Err(format!("Unknown task status: {}", s)),
+        }
+    }
+}
+
+impl From<&PollResult> for TaskStatus {
+    fn from(result: &PollResult) -> Self {
+        match result {
+            PollResult::Pending(_) => TaskStatus::Running,
+            PollResult::Paused(_) => TaskStatus::Paused,
+            PollResult::Completed => TaskStatus::Completed,
+            PollResult::Cancelled => TaskStatus::Cancelled,
+            PollResult::Failed(_) => TaskStatus::Failed,
+        }
+    }
+}
+
+/// How much a task's progress has to change before it is reported again to progress
+/// subscribers and the debug log. Both conditions must hold; the default reports every
+/// change.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct ProgressGranularity {
+    /// Smallest change of the progress fraction, e.g. `0.005` for half a percent.
+    pub min_delta: f32,
+    pub min_interval: Duration,
+}
+
+impl ProgressGranularity {
+    /// Whether `progress` at `now` is worth reporting after `last`. Reaching 1.0 always is.
+    fn is_due(&self, last: Option<(f32, Instant)>, progress: f32, now: Instant) -> bool {
+        let Some((last_progress, last_at)) = last else {
+            return true;
+        };
+        if progress == last_progress {
+            return false;
+        }
+        progress >= 1.0
+            || ((progress - last_progress).abs() >= self.min_delta
+                && now.duration_since(last_at) >= self.min_interval)
+    }
+}
+
+/// A task's progress, sent to [`TaskQueue::subscribe_progress`] subscribers.
+#[derive(Debug, Clone, Copy, PartialEq, serde::Serialize)]
+pub struct ProgressEvent {
+    pub id: TaskId,
+    pub progress: f32,
+}
+
+/// A change in a task's life; see [`TaskQueue::subscribe_events`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum TaskEvent {
+    Started {
+        id: TaskId,
+    },
+    /// Sent at most as often as the progress granularity allows.
+    Progress {
+        id: TaskId,
+        progress: f32,
+    },
+    Paused {
+        id: TaskId,
+    },
+    /// Also sent for a task paused before it started, once it is back in line.
+    Resumed {
+        id: TaskId,
+    },
+    Completed {
+        id: TaskId,
+    },
+    Cancelled {
+        id: TaskId,
+    },
+    /// The task ended with `error`, whether the task returned it or the queue cancelled
+    /// the task for it, e.g. for running out of time or disk space.
+    Failed {
+        id: TaskId,
+        error: TaskError,
+    },
+}
+
+impl TaskEvent {
+    pub fn id(&self) -> TaskId {
+        match self {
+            TaskEvent::Started { id }
+            | TaskEvent::Progress { id, .. }
+            | TaskEvent::Paused { id }
+            | TaskEvent::Resumed { id }
+            | TaskEvent::Completed { id }
+            | TaskEvent::Cancelled { id }
+            | TaskEvent::Failed { id, .. } => *id,
+        }
+    }
+
+    /// The event for task `id` going from `from` to `to`, if there is one;
+    /// `failure` is why the queue cancelled it, if it did.
+    #[cfg(not(target_arch = "wasm32"))]
+    fn of_transition(
+        id: TaskId,
+        from: &TaskStatus,
+        to: &TaskStatus,
+        failure: Option<&TaskError>,
+    ) -> Option<TaskEvent> {
+        match (from, to) {
+            (_, TaskStatus::Failed) => failure.map(|error| TaskEvent::Failed {
+                id,
+                error: error.clone(),
+            }),
+            (_, TaskStatus::Cancelled) => Some(TaskEvent::Cancelled { id }),
+            (_, TaskStatus::Completed) => Some(TaskEvent::Completed { id }),
+            (_, TaskStatus::Paused) => Some(TaskEvent::Paused { id }),
+            (TaskStatus::Paused, _) => Some(TaskEvent::Resumed { id }),
+            (_, TaskStatus::Running) => Some(TaskEvent::Started { id }),
+            _ => None,
+        }
+    }
+}
+
+/// Everything known about one task, gathered on request, e.g. for a tooltip.
+#[derive(Debug, Clone)]
+pub struct TaskDetail {
+    pub record: TaskRecord,
+    /// As of the last poll.
+    pub progress: PollResult,
+    /// See [`Task::message`]; `None` also when the task was busy and not asked.
+    pub message: Option<String>,
+    /// See [`Task::resource_usage`]; `None` when busy, like `message`.
+    pub resources: Option<ResourceUsage>,
+    /// See [`Task::speed_limit`]; `None` when busy, like `message`.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub speed_limit: Option<sync_Arc<SpeedLimit>>,
+}
+
+/// What a task is called and filed under, for people rather than the queue; see
+/// [`TaskQueue::add_task_with_meta`]. Kept in the task's record.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TaskMeta {
+    /// Shown in place of "Task 3".
+    pub label: Option<String>,
+    pub tags: Vec<String>,
+    /// Unix timestamp in milliseconds.
+    pub created_at: u64,
+}
+
+impl TaskMeta {
+    /// Created now.
+    pub fn new(label: Option<String>, tags: Vec<String>) -> Self {
+        TaskMeta {
+            label,
+            tags,
+            created_at: now_millis(),
+        }
+    }
+
+    fn of(record: &TaskRecord) -> Self {
+        TaskMeta {
+            label: record.label.clone(),
+            tags: record.tags.clone(),
+            created_at: record.created_at,
+        }
+    }
+}
+
+/// Adds a task with any of its label, tags, priority, timeout, start time and retries
+/// set in one go; see [`TaskQueue::build_task`]. Nothing is added until [`Self::submit`].
+#[must_use = "the task is only added by `submit`"]
+pub struct TaskBuilder<'q, T> {
+    queue: &'q TaskQueue,
+    task: T,
+    options: AddOptions,
+}
+
+impl<T: Task + Send + 'static> TaskBuilder<'_, T> {
+    pub fn label(mut self, label: impl Into<String>) -> Self {
+        self.meta().label = Some(label.into());
+        self
+    }
+
+    pub fn tag(mut self, tag: impl Into<String>) -> Self {
+        self.meta().tags.push(tag.into());
+        self
+    }
+
+    /// In place of the task's own [`Task::priority`].
+    pub fn priority(mut self, priority: Priority) -> Self {
+        self.options.priority = Some(priority);
+        self
+    }
+
+    /// See [`TaskQueue::add_task_with_timeout`].
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.options.timeout = Some(timeout);
+        self
+    }
+
+    /// See [`TaskQueue::schedule_task`].
+    pub fn start_at(mut self, start_at: Instant) -> Self {
+        self.options.start_at = Some(start_at);
+        self
+    }
+
+    /// Adds the task, returning its id.
+    pub fn submit(self) -> TaskId {
+        self.queue.add(self.task, self.options)
+    }
+
+    /// Adds the task if it passes [`Task::validate`]; see [`TaskQueue::try_add_task`].
+    pub fn try_submit(self) -> Result<TaskId, TaskError> {
+        if self.queue.shutting_down.load(Ordering::Acquire) {
+            return Err(TaskError::ShuttingDown);
+        }
+        self.task.validate()?;
+        Ok(self.submit())
+    }
+
+    fn meta(&mut self) -> &mut TaskMeta {
+        self.options
+            .meta
+            .get_or_insert_with(|| TaskMeta::new(None, Vec::new()))
+    }
+}
+
+impl<T: Task + Clone + Send + 'static> TaskBuilder<'_, T> {
+    /// Adds the task again each time it ends as [`TaskStatus::Failed`], up to `retries`
+    /// times, with the same options. Each try is a clone of the task as it is now, so its
+    /// `Clone` has to give a task that has not started.
+    pub fn retries(mut self, retries: u32) -> Self {
+        let template = self.task.clone();
+        self.options.retry = (retries > 0).then(|| Retry {
+            remaining: retries,
+            factory: Box::new(move || -> Box<dyn Task> { Box::new(template.clone()) }),
+        });
+        self
+    }
+}
+
+/// Where a recurring task stands; see [`TaskQueue::add_recurring_task`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RecurrenceDetail {
+    pub id: usize,
+    pub every: Duration,
+    /// The task added for the latest run, which may not have started yet.
+    pub run: TaskId,
+    /// When the next run starts, or is to start once the current one has finished, as a
+    /// Unix timestamp in milliseconds.
+    pub next_fire: u64,
+}
+
+/// How many tasks are in each active status and when they should all be done; see
+/// [`TaskQueue::stats`].
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct QueueStats {
+    pub scheduled: usize,
+    pub queued: usize,
+    pub running: usize,
+    pub paused: usize,
+    /// Unix timestamp in milliseconds; `None` when nothing is queued or running, or
+    /// nothing that is could be estimated. See [`forecast::forecast`].
+    pub estimated_finish_time: Option<u64>,
+    /// Queued and running tasks the estimate leaves out for lack of anything to go on.
+    pub unestimated: usize,
+}
+
+/// The latest progress and status of a task, readable without taking any lock. A read
+/// racing a write may mix two reports' numbers, which only shows for that one read.
+struct ProgressCell {
+    /// Which [`PollingData`] variant `done` and `total` hold.
+    kind: AtomicU8,
+    /// The bits of the `f32` fraction, the bytes done or the current step.
+    done: AtomicU64,
+    /// The total bytes, `UNKNOWN_TOTAL` if not known, or the total steps.
+    total: AtomicU64,
+    status: AtomicU8,
+    /// Why the task failed; only locked once it has.
+    failure: sync_Mutex<Option<TaskError>>,
+}
+
+const PROGRESS_FLOAT: u8 = 0;
+const PROGRESS_BYTES: u8 = 1;
+const PROGRESS_STEPS: u8 = 2;
+const UNKNOWN_TOTAL: u64 = u64::MAX;
+
+impl ProgressCell {
+    fn new() -> Self {
+        ProgressCell {
+            kind: AtomicU8::new(PROGRESS_FLOAT),
+            done: AtomicU64::new(u64::from(0.0f32.to_bits())),
+            total: AtomicU64::new(0),
+            status: AtomicU8::new(TaskStatus::Queued.to_byte()),
+            failure: sync_Mutex::new(None),
+        }
+    }
+
+    fn set_progress(&self, data: &PollingData) {
+        let (kind, done, total) = match *data {
+            PollingData::Float(p) => (PROGRESS_FLOAT, u64::from(p.to_bits()), 0),
+            PollingData::Bytes { done, total } => {
+                (PROGRESS_BYTES, done, total.unwrap_or(UNKNOWN_TOTAL))
+            }
+            PollingData::Steps { current, total } => {
+                (PROGRESS_STEPS, u64::from(current), u64::from(total))
+            }
+        };
+        self.done.store(done, Ordering::Release);
+        self.total.store(total, Ordering::Release);
+        self.kind.store(kind, Ordering::Release);
+    }
+
+    fn progress(&self) -> PollingData {
+        let kind = self.kind.load(Ordering::Acquire);
+        let done = self.done.load(Ordering::Acquire);
+        let total = self.total.load(Ordering::Acquire);
+        match kind {
+            PROGRESS_BYTES => PollingData::Bytes {
+                done,
+                total: (total != UNKNOWN_TOTAL).then_some(total),
+            },
+            PROGRESS_STEPS => PollingData::Steps {
+                current: done as u32,
+                total: total as u32,
+            },
+            _ => PollingData::Float(f32::from_bits(done as u32)),
+        }
+    }
+
+    fn set_status(&self, status: &TaskStatus) {
+        self.status.store(status.to_byte(), Ordering::Release);
+    }
+
+    fn status(&self) -> TaskStatus {
+        TaskStatus::from_byte(self.status.load(Ordering::Acquire))
+    }
+
+    fn load(&self) -> PollResult {
+        let progress = self.progress();
+        match TaskStatus::from_byte(self.status.load(Ordering::Acquire)) {
+            TaskStatus::Scheduled | TaskStatus::Queued | TaskStatus::Running => {
+                PollResult::Pending(progress)
+            }
+            TaskStatus::Paused => PollResult::Paused(progress),
+            TaskStatus::Completed => PollResult::Completed,
+            TaskStatus::Cancelled | TaskStatus::Interrupted => PollResult::Cancelled,
+            TaskStatus::Failed => PollResult::Failed(
+                self.failure
+                    .lock()
+                    .expect("Panicked at load: Failure mutex poisoned")
+                    .clone()
+                    .unwrap_or_else(|| TaskError::Failed {
+                        reason: "Unknown error".to_owned(),
+                    }),
+            ),
+        }
+    }
+}
+
+/// One task and its record, each behind its own lock so polling a task never blocks
+/// work on any other.
+struct TaskEntry {
+    task: sync_Arc<sync_Mutex<dyn Task + Send + 'static>>,
+    record: sync_Mutex<TaskRecord>,
+    /// Mirrors the last poll and the record's status; the record's mutex is only
+    /// needed to change state.
+    progress: ProgressCell,
+    reported: sync_Mutex<ProgressReport>,
+    /// Carries the record's priority to the job the task submits when it starts.
+    ticket: JobTicket,
+    /// One of the `SLOT_` constants.
+    slot: AtomicU8,
+    /// Mirrors the record's deadline, or `NO_DEADLINE`, so polls need not lock the record
+    /// to find there is none.
+    deadline: AtomicU64,
+    /// Set once the task is found to be at risk of missing its deadline.
+    at_risk: AtomicBool,
+    /// How long the task may run; see [`TaskQueue::add_task_with_timeout`].
+    timeout: Option<Duration>,
+    /// When a scheduled task is to start, and the same as a Unix timestamp in
+    /// milliseconds; see [`TaskQueue::schedule_task`].
+    start_at: Option<(Instant, u64)>,
+    /// Set once [`TaskQueue::take_result`] has asked the task for its result.
+    result_taken: AtomicBool,
+    /// Given to the task, and cancelled once it ends.
+    cancellation: CancellationToken,
+    /// Progress the task pushed since it was last read.
+    pushed: ProgressReceiver,
+    /// Taken once the task has failed.
+    retry: sync_Mutex<Option<Retry>>,
+}
+
+impl TaskEntry {
+    /// Whether the task has no start time to wait for, or it has come.
+    fn is_due(&self) -> bool {
+        self.start_at
+            .map_or(true, |(start_at, _)| Instant::now() >= start_at)
+    }
+}
+
+/// How a task is added, beyond the task itself.
+#[derive(Default)]
+struct AddOptions {
+    timeout: Option<Duration>,
+    start_at: Option<Instant>,
+    meta: Option<TaskMeta>,
+    /// In place of [`Task::priority`].
+    priority: Option<Priority>,
+    retry: Option<Retry>,
+}
+
+/// Adds a task again when it fails; see [`TaskBuilder::retries`].
+struct Retry {
+    /// Times left, at least one.
+    remaining: u32,
+    factory: TaskFactory,
+}
+
+#[derive(Default)]
+struct ProgressReport {
+    /// Overrides the queue's granularity for this task.
+    granularity: Option<ProgressGranularity>,
+    /// Progress last reported, and when.
+    last: Option<(f32, Instant)>,
+}
+
+/// The task's artifacts if `result` is the poll that completed it; empty otherwise, so
+/// they are only asked for once.
+fn completed_artifacts(entry: &TaskEntry, task: &dyn Task, result: &PollResult) -> Vec<Artifact> {
+    let completes = matches!(result, PollResult::Completed)
+        && !matches!(entry.progress.load(), PollResult::Completed);
+    if completes {
+        task.artifacts()
+    } else {
+        Vec::new()
+    }
+}
+
+type OnChange = Box<dyn Fn() + Send + Sync>;
+
+/// Makes a new task each time the queue adds one again.
+type TaskFactory = Box<dyn Fn() -> Box<dyn Task> + Send + Sync>;
+
+/// A task added again every `every`; see [`TaskQueue::add_recurring_task`].
+struct Recurrence {
+    factory: TaskFactory,
+    every: Duration,
+    /// The task added for the latest run.
+    run: TaskId,
+    /// When the latest run was added to start.
+    fired_at: Instant,
 }
 
 pub struct TaskQueue {
-    tasks: sync_Mutex<HashMap<usize, sync_Arc<sync_Mutex<dyn Task + Send + 'static>>>>,
-    next_id: AtomicUsize,
+    /// Sharded, and each shard only held long enough to look up, insert or list entries.
+    /// Keyed by [`TaskId::index`].
+    tasks: TaskMap<sync_Arc<TaskEntry>>,
+    /// Of the ids this queue hands out; see [`TaskId`].
+    generation: u32,
+    history: sync_Mutex<Vec<TaskRecord>>,
+    /// Most history records kept in `history`; see `set_history_limit`.
+    #[cfg(not(target_arch = "wasm32"))]
+    history_limit: AtomicUsize,
+    #[cfg(not(target_arch = "wasm32"))]
+    history_file: sync_Mutex<Option<HistoryFile>>,
+    history_retention: sync_RwLock<HistoryRetention>,
+    /// Where additions and status changes are written ahead; see `set_journal`.
+    #[cfg(not(target_arch = "wasm32"))]
+    journal: sync_RwLock<Option<sync_Arc<Journal>>>,
+    #[cfg(all(feature = "sqlite", not(target_arch = "wasm32")))]
+    store: Option<sync_Mutex<Box<dyn QueueStore>>>,
+    #[cfg(not(target_arch = "wasm32"))]
+    subscribers: sync_Mutex<Vec<mpsc::Sender<TaskRecord>>>,
+    progress_granularity: sync_RwLock<ProgressGranularity>,
+    #[cfg(not(target_arch = "wasm32"))]
+    progress_subscribers: sync_Mutex<Vec<mpsc::Sender<ProgressEvent>>>,
+    #[cfg(not(target_arch = "wasm32"))]
+    deadline_subscribers: sync_Mutex<Vec<mpsc::Sender<DeadlineAtRisk>>>,
+    #[cfg(not(target_arch = "wasm32"))]
+    event_subscribers: sync_Mutex<Vec<mpsc::Sender<TaskEvent>>>,
+    /// Raise the priority of tasks at risk of missing their deadline.
+    boost_at_risk: AtomicBool,
+    /// Set by `pause_all`: no task starts until `resume_all`.
+    paused: AtomicBool,
+    /// The tasks `pause_all` paused, for `resume_all` to resume.
+    paused_by_queue: sync_Mutex<Vec<TaskId>>,
+    /// Set by `shutdown`: tasks added from then on are cancelled right away.
+    shutting_down: AtomicBool,
+    /// By id; locked before any shard of the tasks map, never after.
+    recurrences: sync_Mutex<HashMap<usize, Recurrence>>,
+    next_recurrence_id: AtomicUsize,
+    /// In the order their tasks are let in; a task is in one group at most. Locked before
+    /// the scheduler, never after.
+    groups: sync_RwLock<Vec<TaskGroup>>,
+    next_group_id: AtomicUsize,
+    /// Called whenever a task is added or changes status.
+    on_change: sync_Mutex<Option<OnChange>>,
+    /// Callers of `wait_idle`, woken once no task is queued or running.
+    idle_waiters: sync_Mutex<Vec<channel::Sender<()>>>,
+    /// Who added, cancelled, paused or resumed tasks; see [`audit`](Self::audit).
+    audit: sync_Mutex<VecDeque<AuditEntry>>,
+    /// Locked after a task's record, never before.
+    scheduler: sync_Mutex<Scheduler>,
+    #[cfg(debug_assertions)]
+    chaos: sync_RwLock<Option<Chaos>>,
+}
+
+impl Default for TaskQueue {
+    fn default() -> Self {
+        TaskQueue::new()
+    }
 }
 
 impl TaskQueue {
     pub fn new() -> Self {
         TaskQueue {
-            tasks: sync_Mutex::new(HashMap::new()),
-            next_id: AtomicUsize::new(0),
+            tasks: TaskMap::new(),
+            generation: TaskId::new_generation(),
+            history: sync_Mutex::new(Vec::new()),
+            #[cfg(not(target_arch = "wasm32"))]
+            history_limit: AtomicUsize::new(usize::MAX),
+            #[cfg(not(target_arch = "wasm32"))]
+            history_file: sync_Mutex::new(None),
+            history_retention: sync_RwLock::new(HistoryRetention::default()),
+            #[cfg(not(target_arch = "wasm32"))]
+            journal: sync_RwLock::new(None),
+            #[cfg(all(feature = "sqlite", not(target_arch = "wasm32")))]
+            store: None,
+            #[cfg(not(target_arch = "wasm32"))]
+            subscribers: sync_Mutex::new(Vec::new()),
+            progress_granularity: sync_RwLock::new(ProgressGranularity::default()),
+            #[cfg(not(target_arch = "wasm32"))]
+            progress_subscribers: sync_Mutex::new(Vec::new()),
+            #[cfg(not(target_arch = "wasm32"))]
+            deadline_subscribers: sync_Mutex::new(Vec::new()),
+            #[cfg(not(target_arch = "wasm32"))]
+            event_subscribers: sync_Mutex::new(Vec::new()),
+            boost_at_risk: AtomicBool::new(false),
+            paused: AtomicBool::new(false),
+            shutting_down: AtomicBool::new(false),
+            paused_by_queue: sync_Mutex::new(Vec::new()),
+            recurrences: sync_Mutex::new(HashMap::new()),
+            next_recurrence_id: AtomicUsize::new(0),
+            groups: sync_RwLock::new(Vec::new()),
+            next_group_id: AtomicUsize::new(0),
+            on_change: sync_Mutex::new(None),
+            idle_waiters: sync_Mutex::new(Vec::new()),
+            audit: sync_Mutex::new(VecDeque::new()),
+            scheduler: sync_Mutex::new(Scheduler::default()),
+            #[cfg(debug_assertions)]
+            chaos: sync_RwLock::new(None),
         }
     }
 
-    pub fn add_task<T: Task + Send + 'static>(&self, mut task: T) -> usize {
-        let id = self.next_id.fetch_add(1, Ordering::SeqCst);
-        task.set_id(id);
-        self.tasks
+    /// Creates a queue that runs at most `n` tasks at once; see [`Self::set_concurrency`].
+    pub fn with_concurrency(n: usize) -> Self {
+        let queue = TaskQueue::new();
+        queue.set_concurrency(Some(n));
+        queue
+    }
+
+    /// Caps how many tasks run at once, paused ones included; `None` for no cap. Polling
+    /// a task that would go over the cap leaves it queued, and once a slot frees up the
+    /// next poll of the most urgent, then oldest, queued task starts it. Polling a task
+    /// further back in line never starts it ahead of that one, so tasks start in the
+    /// order of [`Self::queued_order`]. Running tasks are not stopped by a lower cap.
+    pub fn set_concurrency(&self, limit: Option<usize>) {
+        self.scheduler
             .lock()
-            .expect("Panicked at add_task: Tasks mutex poisoned")
-            .insert(id, sync_Arc::new(sync_Mutex::new(task)));
-        debug!("Added task with id: {}", id);
-        id
+            .expect("Panicked at set_concurrency: Scheduler mutex poisoned")
+            .set_limit(limit);
     }
 
-    pub fn poll_task(&self, id: usize) -> Result<PollResult, TaskError> {
-        match self
-            .tasks
+    pub fn concurrency(&self) -> Option<usize> {
+        self.scheduler
             .lock()
-            .expect("Panicked at poll_task: Tasks mutex poisoned")
-            .get_mut(&id)
-        {
-            Some(task) => {
-                let mut guard = task.lock().unwrap();
-                Ok(guard.poll())
-            }
-            None => Err(TaskError::NotFound),
-        }
+            .expect("Panicked at concurrency: Scheduler mutex poisoned")
+            .limit()
     }
 
-    pub fn remove_task(&self, id: usize) -> Result<(), TaskError> {
-        match self
-            .tasks
+    /// Ids of the queued tasks in the order they will start: most urgent first, then by
+    /// their group's place among the groups, tasks in no group last, then in the order
+    /// they were added. Tasks paused before they started are not in line, and
+    /// scheduled ones only get in line on their first poll once their time has come.
+    pub fn queued_order(&self) -> Vec<TaskId> {
+        self.scheduler
             .lock()
-            .expect("Panicked at remove_task: Tasks mutex poisoned")
-            .get_mut(&id)
-        {
-            Some(task) => {
-                let mut guard = task.lock().unwrap();
-                guard.cancel()
-            }
-            None => Err(TaskError::NotFound),
-        }
+            .expect("Panicked at queued_order: Scheduler mutex poisoned")
+            .waiting_order()
     }
 
-    pub fn pause_task(&self, id: usize) -> Result<(), TaskError> {
-        let tasks = self
-            .tasks
+    /// Caps how many tasks start per second, so that adding many at once spreads their
+    /// starts out instead of starting them all on the next poll; `None` for no cap. Up to
+    /// a second's worth of starts may happen at once. Tasks not let in yet stay queued,
+    /// in the same line as for [`Self::set_concurrency`].
+    pub fn set_start_rate(&self, per_second: Option<f64>) {
+        self.scheduler
             .lock()
-            .expect("Panicked at pause_task: Tasks mutex poisoned");
-        match tasks.get(&id) {
-            Some(task) => {
-                let mut guard = task
-                    .lock()
-                    .expect("Panicked unwrapping task to pause: Task mutex poisoned");
-                guard.pause()
-            }
-            None => {
-                log::error!("Task not found: {}", id);
-                Err(TaskError::NotFound)
-            }
+            .expect("Panicked at set_start_rate: Scheduler mutex poisoned")
+            .set_start_rate(per_second);
+    }
+
+    pub fn start_rate(&self) -> Option<f64> {
+        self.scheduler
+            .lock()
+            .expect("Panicked at start_rate: Scheduler mutex poisoned")
+            .start_rate()
+    }
+
+    /// Creates a queue that mirrors every task and history record into `store`,
+    /// preloading the most recent history from it.
+    #[cfg(all(feature = "sqlite", not(target_arch = "wasm32")))]
+    pub fn with_store(mut store: Box<dyn QueueStore>) -> Self {
+        let history = store
+            .load_history(STORE_HISTORY_PRELOAD)
+            .unwrap_or_else(|e| {
+                log::error!("Failed to load history from store: {}", e);
+                Vec::new()
+            });
+        TaskQueue {
+            history: sync_Mutex::new(history),
+            store: Some(sync_Mutex::new(store)),
+            ..TaskQueue::new()
         }
     }
 
-    pub fn resume_task(&self, id: usize) -> Result<(), TaskError> {
-        debug!("Resume requested for {}", &id);
-        let tasks = self
-            .tasks
+    /// The id this queue gives its task numbered `index`, for callers that only have the
+    /// number, e.g. from the control pipe. Whether there is such a task is up to the call
+    /// it is passed to.
+    pub fn task_id(&self, index: usize) -> TaskId {
+        TaskId::new(self.generation, index)
+    }
+
+    pub fn add_task<T: Task + Send + 'static>(&self, task: T) -> TaskId {
+        profile_function!();
+        self.add(task, AddOptions::default())
+    }
+
+    /// Starts adding `task` with options set one by one, e.g.
+    /// `queue.build_task(task).label("warmup").priority(Priority::High).submit()`.
+    pub fn build_task<T: Task + Send + 'static>(&self, task: T) -> TaskBuilder<'_, T> {
+        TaskBuilder {
+            queue: self,
+            task,
+            options: AddOptions::default(),
+        }
+    }
+
+    /// Adds `task` to be cancelled once it has run for longer than `timeout`, paused time
+    /// included, counting from when it starts. The poll that finds it has run too long
+    /// fails with [`TaskError::TimedOut`], and its record says it timed out.
+    pub fn add_task_with_timeout<T: Task + Send + 'static>(
+        &self,
+        task: T,
+        timeout: Duration,
+    ) -> TaskId {
+        self.add(
+            task,
+            AddOptions {
+                timeout: Some(timeout),
+                ..AddOptions::default()
+            },
+        )
+    }
+
+    /// Adds `task` with a label and tags. Its creation time is taken from `meta` too, so a
+    /// task added again, e.g. from a saved session, keeps the one it first had.
+    pub fn add_task_with_meta<T: Task + Send + 'static>(&self, task: T, meta: TaskMeta) -> TaskId {
+        self.add(
+            task,
+            AddOptions {
+                meta: Some(meta),
+                ..AddOptions::default()
+            },
+        )
+    }
+
+    /// Adds `task` as [`TaskStatus::Scheduled`]: polling it does nothing until `start_at`,
+    /// after which the next poll queues it like any other task. It can be paused and
+    /// resumed while it waits, and resuming it early keeps it waiting.
+    pub fn schedule_task<T: Task + Send + 'static>(&self, task: T, start_at: Instant) -> TaskId {
+        self.add(
+            task,
+            AddOptions {
+                start_at: Some(start_at),
+                ..AddOptions::default()
+            },
+        )
+    }
+
+    /// Adds a task made by `factory` now, and another every `every` after that. Each run
+    /// is a task of its own, labelled and tagged like the one before and added once that
+    /// one has ended as [`TaskStatus::Scheduled`] to start `every` after it did, or right
+    /// away if that time has passed, so runs never overlap. Cancelling a run skips it;
+    /// stopping the recurrence takes [`Self::stop_recurrence`]. Returns the recurrence's id.
+    pub fn add_recurring_task<T, F>(&self, factory: F, every: Duration) -> usize
+    where
+        T: Task + Send + 'static,
+        F: Fn() -> T + Send + Sync + 'static,
+    {
+        let id = self.next_recurrence_id.fetch_add(1, Ordering::SeqCst);
+        let fired_at = Instant::now();
+        // Added before locking: adding can end a task, which takes the lock in `recur`.
+        let run = self.add_task(factory());
+        self.recurrences
             .lock()
-            .expect("Panicked at resume_task: Tasks mutex poisoned");
-        match tasks.get(&id) {
-            Some(task) => {
-                let mut guard = task
-                    .lock()
-                    .expect("Panicked unwrapping task to resume: Task mutex poisoned");
-                debug!("Resumed task {}", &id);
-                guard.resume()
+            .expect("Panicked at add_recurring_task: Recurrences mutex poisoned")
+            .insert(
+                id,
+                Recurrence {
+                    factory: Box::new(move || -> Box<dyn Task> { Box::new(factory()) }),
+                    every,
+                    run,
+                    fired_at,
+                },
+            );
+        debug!("Added recurrence {} every {:?}", id, every);
+        id
+    }
+
+    /// Adds the next run of the recurrence whose latest run is `run`, if there is one.
+    /// Called once `run` has ended.
+    fn recur(&self, run: TaskId) {
+        // The lock is let go while the next run is added, since adding can end a task,
+        // e.g. once the queue is shutting down, which comes back here.
+        let (id, task, start_at) = {
+            let mut recurrences = self
+                .recurrences
+                .lock()
+                .expect("Panicked at recur: Recurrences mutex poisoned");
+            let Some((&id, recurrence)) = recurrences.iter_mut().find(|(_, r)| r.run == run) else {
+                return;
+            };
+            let now = Instant::now();
+            let due = recurrence.fired_at + recurrence.every;
+            recurrence.fired_at = if due < now { now } else { due };
+            (id, (recurrence.factory)(), recurrence.fired_at)
+        };
+        let meta = self
+            .task_meta(run)
+            .ok()
+            .map(|meta| TaskMeta::new(meta.label, meta.tags));
+        let next = self.add(
+            task,
+            AddOptions {
+                start_at: Some(start_at),
+                meta,
+                ..AddOptions::default()
+            },
+        );
+        let mut recurrences = self
+            .recurrences
+            .lock()
+            .expect("Panicked at recur: Recurrences mutex poisoned");
+        match recurrences.get_mut(&id) {
+            Some(recurrence) => {
+                recurrence.run = next;
+                debug!("Task {} recurs as {}", run, next);
             }
+            // Stopped in the meantime.
             None => {
-                log::error!("Task not found: {}", id);
-                Err(TaskError::NotFound)
+                drop(recurrences);
+                let _ = self.remove_task(next);
             }
         }
     }
 
-    pub fn _get_task(&self, id: usize) -> Result<Receiver<()>, TaskError> {
-        debug!("Got task with id: {}", id);
-        match self.tasks.lock().unwrap().get_mut(&id) {
-            Some(task) => {
-                debug!("matched Some(task) with id: {}", id);
-                let task_clone = task.clone();
-                let (tx, rx) = channel::bounded(1);
-                let tx_clone = tx;
-                task::spawn(async move {
-                    let mut guard = task_clone.lock().unwrap();
-                    loop {
-                        let result = guard.poll();
-                        match result {
-                            PollResult::Pending(progress) => {
+    /// Adds task `id`, which failed, again if it has retries left, with the same label,
+    /// tags, priority and timeout.
+    fn retry(&self, id: TaskId, entry: &TaskEntry) {
+        let Some(retry) = entry
+            .retry
+            .lock()
+            .expect("Panicked at retry: Retry mutex poisoned")
+            .take()
+        else {
+            return;
+        };
+        let (meta, priority) = {
+            let record = entry
+                .record
+                .lock()
+                .expect("Panicked at retry: Record mutex poisoned");
+            (
+                TaskMeta::new(record.label.clone(), record.tags.clone()),
+                record.priority,
+            )
+        };
+        let remaining = retry.remaining - 1;
+        let task = (retry.factory)();
+        let retried = self.add(
+            task,
+            AddOptions {
+                timeout: entry.timeout,
+                meta: Some(meta),
+                priority: Some(priority),
+                retry: (remaining > 0).then_some(Retry {
+                    remaining,
+                    factory: retry.factory,
+                }),
+                ..AddOptions::default()
+            },
+        );
+        log::info!(
+            "Task {} failed, retrying as task {} ({} retries left)",
+            id,
+            retried,
+            remaining
+        );
+    }
+
+    pub fn recurrence(&self, id: usize) -> Result<RecurrenceDetail, TaskError> {
+        let recurrences = self
+            .recurrences
+            .lock()
+            .expect("Panicked at recurrence: Recurrences mutex poisoned");
+        let recurrence = recurrences.get(&id).ok_or(TaskError::NotFound)?;
+        let waiting = self.entry(recurrence.run).map_or(false, |entry| {
+            entry.progress.status() == TaskStatus::Scheduled
+        });
+        let next = if waiting {
+            recurrence.fired_at
+        } else {
+            recurrence.fired_at + recurrence.every
+        };
+        let delay = next.saturating_duration_since(Instant::now());
+        Ok(RecurrenceDetail {
+            id,
+            every: recurrence.every,
+            run: recurrence.run,
+            next_fire: now_millis() + delay.as_millis() as u64,
+        })
+    }
+
+    /// The recurrence whose latest run is task `run`, if any.
+    pub fn recurrence_of(&self, run: TaskId) -> Option<usize> {
+        self.recurrences
+            .lock()
+            .expect("Panicked at recurrence_of: Recurrences mutex poisoned")
+            .iter()
+            .find_map(|(id, recurrence)| (recurrence.run == run).then_some(*id))
+    }
+
+    /// Stops adding runs of the recurrence. A run waiting for its start time is cancelled;
+    /// one already under way is left to finish.
+    pub fn stop_recurrence(&self, id: usize) -> Result<(), TaskError> {
+        let recurrence = self
+            .recurrences
+            .lock()
+            .expect("Panicked at stop_recurrence: Recurrences mutex poisoned")
+            .remove(&id)
+            .ok_or(TaskError::NotFound)?;
+        debug!("Stopped recurrence {}", id);
+        if self.status(recurrence.run)? == TaskStatus::Scheduled {
+            self.remove_task(recurrence.run)?;
+        }
+        Ok(())
+    }
+
+    fn add<T: Task + Send + 'static>(&self, task: T, options: AddOptions) -> TaskId {
+        profile_function!();
+        let mut adder = self.tasks.adder();
+        let (id, record) = self.insert(&mut adder, task, options);
+        drop(adder);
+        self.persist(&record);
+        self.notify(&record);
+        debug!("Added task with id: {}", id);
+        self.refuse_if_shutting_down(&[id]);
+        id
+    }
+
+    /// Cancels tasks just added if the queue is shutting down, which can only be checked
+    /// once they are in, so that they are not left queued with nothing to run them.
+    fn refuse_if_shutting_down(&self, ids: &[TaskId]) {
+        if !self.shutting_down.load(Ordering::Acquire) {
+            return;
+        }
+        log::warn!("Shutting down, cancelled {} tasks added", ids.len());
+        self.remove_tasks(ids);
+    }
+
+    /// Adds `task` if it passes [`Task::validate`], so a task with bad input is turned
+    /// away rather than queued to fail.
+    pub fn try_add_task<T: Task + Send + 'static>(&self, task: T) -> Result<TaskId, TaskError> {
+        if self.shutting_down.load(Ordering::Acquire) {
+            return Err(TaskError::ShuttingDown);
+        }
+        task.validate()?;
+        Ok(self.add_task(task))
+    }
+
+    /// Adds every task in `tasks` one after another, with no other task added in between,
+    /// returning their ids in order.
+    pub fn add_tasks<T, I>(&self, tasks: I) -> Vec<TaskId>
+    where
+        T: Task + Send + 'static,
+        I: IntoIterator<Item = T>,
+    {
+        profile_function!();
+        let mut adder = self.tasks.adder();
+        let ids: Vec<TaskId> = tasks
+            .into_iter()
+            .map(|task| {
+                let (id, record) = self.insert(&mut adder, task, AddOptions::default());
+                self.persist(&record);
+                self.notify(&record);
+                id
+            })
+            .collect();
+        drop(adder);
+        debug!("Added {} tasks", ids.len());
+        self.refuse_if_shutting_down(&ids);
+        ids
+    }
+
+    /// Ids are handed out by the adder, so once a task is listed every task with a lower
+    /// id is too.
+    fn insert<T: Task + Send + 'static>(
+        &self,
+        adder: &mut Adder<'_, sync_Arc<TaskEntry>>,
+        mut task: T,
+        options: AddOptions,
+    ) -> (TaskId, TaskRecord) {
+        let id = TaskId::new(self.generation, adder.next_id());
+        task.set_id(id);
+        let cancellation = CancellationToken::new();
+        task.set_cancellation_token(cancellation.clone());
+        let (progress_sender, pushed) = progress_channel::channel();
+        task.set_progress_sender(progress_sender);
+        let start_at = options.start_at.map(|at| {
+            let delay = at.saturating_duration_since(Instant::now());
+            (at, now_millis() + delay.as_millis() as u64)
+        });
+        let mut record = TaskRecord {
+            status: match start_at {
+                Some(_) => TaskStatus::Scheduled,
+                None => TaskStatus::Queued,
+            },
+            priority: options.priority.unwrap_or_else(|| task.priority()),
+            start_at: start_at.map(|(_, millis)| millis),
+            ..TaskRecord::new(id, task.kind().name())
+        };
+        if let Some(meta) = options.meta {
+            record.label = meta.label;
+            record.tags = meta.tags;
+            record.created_at = meta.created_at;
+        }
+        #[cfg(not(target_arch = "wasm32"))]
+        self.write_journal(&JournalEntry::Added {
+            id,
+            kind: record.kind.clone(),
+            created_at: record.created_at,
+        });
+        let entry = sync_Arc::new(TaskEntry {
+            task: sync_Arc::new(sync_Mutex::new(task)),
+            record: sync_Mutex::new(record.clone()),
+            progress: ProgressCell::new(),
+            reported: sync_Mutex::new(ProgressReport::default()),
+            ticket: JobTicket::new(record.priority),
+            slot: AtomicU8::new(SLOT_NONE),
+            deadline: AtomicU64::new(NO_DEADLINE),
+            at_risk: AtomicBool::new(false),
+            timeout: options.timeout,
+            start_at,
+            result_taken: AtomicBool::new(false),
+            cancellation,
+            pushed,
+            retry: sync_Mutex::new(options.retry),
+        });
+        entry.progress.set_status(&record.status);
+        // Scheduled tasks get in line once their time comes.
+        if start_at.is_none() {
+            self.scheduler
+                .lock()
+                .expect("Panicked at insert: Scheduler mutex poisoned")
+                .enqueue(slot_key(record.priority, id));
+        }
+        adder.insert(entry);
+        (id, record)
+    }
+
+    /// The entry for `id`, cloned out so its shard is unlocked before the task is used.
+    fn entry(&self, id: TaskId) -> Result<sync_Arc<TaskEntry>, TaskError> {
+        if id.generation() != self.generation {
+            return Err(TaskError::NotFound);
+        }
+        self.tasks.get(id.index()).ok_or(TaskError::NotFound)
+    }
+
+    /// The entry for each of `ids`, looked up with each shard locked once.
+    fn entries(&self, ids: &[TaskId]) -> Vec<Option<sync_Arc<TaskEntry>>> {
+        let indices: Vec<usize> = ids.iter().map(TaskId::index).collect();
+        self.tasks
+            .get_many(&indices)
+            .into_iter()
+            .zip(ids)
+            .map(|(entry, id)| entry.filter(|_| id.generation() == self.generation))
+            .collect()
+    }
+
+    /// Every entry with its id, ordered by id.
+    fn all_entries(&self) -> Vec<(TaskId, sync_Arc<TaskEntry>)> {
+        self.tasks
+            .values()
+            .into_iter()
+            .map(|(index, entry)| (self.task_id(index), entry))
+            .collect()
+    }
+
+    pub fn poll_task(&self, id: TaskId) -> Result<PollResult, TaskError> {
+        profile_function!();
+        let entry = self.entry(id)?;
+        self.watch_deadline(&entry);
+        if let Some(result) = self.read_pushed(id, &entry) {
+            return Ok(result);
+        }
+        let (result, artifacts) = {
+            let mut task = entry
+                .task
+                .lock()
+                .expect("Panicked unwrapping task to poll: Task mutex poisoned");
+            if !self.admit(&entry) {
+                return Ok(entry.progress.load());
+            }
+            if let Err(e) = self
+                .preflight(id, &entry, &mut *task)
+                .and_then(|_| self.check_timeout(id, &entry, &mut *task))
+            {
+                drop(task);
+                self.fail(&entry, &e);
+                return Err(e);
+            }
+            let result = self.poll_once(id, &entry, &mut *task);
+            let artifacts = completed_artifacts(&entry, &*task, &result);
+            (result, artifacts)
+        };
+        self.polled(id, &entry, &result, artifacts);
+        Ok(result)
+    }
+
+    /// Polls the task unless another thread is using it, in which case the result of
+    /// its last poll is returned instead of waiting. Meant for the UI thread.
+    pub fn try_poll_task(&self, id: TaskId) -> Result<PollResult, TaskError> {
+        profile_function!();
+        let entry = self.entry(id)?;
+        self.try_poll_entry(id, &entry)
+    }
+
+    /// Polls each of `ids` like [`Self::try_poll_task`], looking them all up with each
+    /// shard of the map locked once. Returns one result per id, in order. Meant for the UI
+    /// thread, to poll the tasks it shows in one call a frame.
+    pub fn poll_many(&self, ids: &[TaskId]) -> Vec<Result<PollResult, TaskError>> {
+        profile_function!();
+        self.entries(ids)
+            .into_iter()
+            .zip(ids)
+            .map(|(entry, &id)| {
+                let entry = entry.ok_or(TaskError::NotFound)?;
+                self.try_poll_entry(id, &entry)
+            })
+            .collect()
+    }
+
+    /// Polls every unfinished task like [`Self::try_poll_task`], and gives each finished
+    /// one's result without polling it. Ordered by id. A task whose poll fails, e.g. by
+    /// timing out, gives the result it ended with.
+    pub fn poll_all(&self) -> Vec<(TaskId, PollResult)> {
+        profile_function!();
+        self.all_entries()
+            .into_iter()
+            .map(|(id, entry)| {
+                let result = if entry.progress.status().is_terminal() {
+                    entry.progress.load()
+                } else {
+                    self.try_poll_entry(id, &entry)
+                        .unwrap_or_else(|_| entry.progress.load())
+                };
+                (id, result)
+            })
+            .collect()
+    }
+
+    fn try_poll_entry(&self, id: TaskId, entry: &TaskEntry) -> Result<PollResult, TaskError> {
+        self.watch_deadline(entry);
+        if let Some(result) = self.read_pushed(id, entry) {
+            return Ok(result);
+        }
+        let (result, artifacts) = match entry.task.try_lock() {
+            Ok(mut task) => {
+                if !self.admit(entry) {
+                    return Ok(entry.progress.load());
+                }
+                if let Err(e) = self
+                    .preflight(id, entry, &mut *task)
+                    .and_then(|_| self.check_timeout(id, entry, &mut *task))
+                {
+                    drop(task);
+                    self.fail(entry, &e);
+                    return Err(e);
+                }
+                let result = self.poll_once(id, entry, &mut *task);
+                let artifacts = completed_artifacts(entry, &*task, &result);
+                (result, artifacts)
+            }
+            Err(TryLockError::WouldBlock) => return Ok(entry.progress.load()),
+            Err(TryLockError::Poisoned(_)) => {
+                panic!("Panicked unwrapping task to poll: Task mutex poisoned")
+            }
+        };
+        self.polled(id, entry, &result, artifacts);
+        Ok(result)
+    }
+
+    /// The progress a running task pushed since its last poll, taken in place of polling
+    /// it, so the task is not locked. `None` if it pushed nothing new, or it is not
+    /// running or has a timeout, for it to be polled as usual.
+    fn read_pushed(&self, id: TaskId, entry: &TaskEntry) -> Option<PollResult> {
+        if entry.timeout.is_some() || entry.progress.status() != TaskStatus::Running {
+            return None;
+        }
+        let data = entry.pushed.latest()?;
+        entry.progress.set_progress(&data);
+        self.report_progress(id, entry, data.fraction());
+        Some(PollResult::Pending(data))
+    }
+
+    /// Whether the task may be polled: it has started already, or it is not waiting for
+    /// its start time and the scheduler has a slot for it now. Called with the task
+    /// locked, so only once at a time per task.
+    fn admit(&self, entry: &TaskEntry) -> bool {
+        if entry.slot.load(Ordering::Acquire) != SLOT_NONE {
+            return true;
+        }
+        if entry.progress.status() == TaskStatus::Scheduled {
+            if !entry.is_due() {
+                return false;
+            }
+            self.transition(entry, TaskStatus::Queued);
+        }
+        if self.paused.load(Ordering::Acquire) && !entry.progress.status().is_terminal() {
+            return false;
+        }
+        let (key, status) = {
+            let record = entry
+                .record
+                .lock()
+                .expect("Panicked at admit: Record mutex poisoned");
+            // Polled once more to report how it ended.
+            if record.status.is_terminal() {
+                return true;
+            }
+            (slot_key(record.priority, record.id), record.status.clone())
+        };
+        let mut scheduler = self
+            .scheduler
+            .lock()
+            .expect("Panicked at admit: Scheduler mutex poisoned");
+        // Paused before it started: out of line until resumed.
+        if status == TaskStatus::Paused {
+            scheduler.forget(key);
+            return false;
+        }
+        let admitted = scheduler.admit(key);
+        if admitted {
+            entry.slot.store(SLOT_HELD, Ordering::Release);
+        }
+        admitted
+    }
+
+    /// Polls `task`, unless chaos mode has something else in store for it.
+    fn poll_once(&self, id: TaskId, entry: &TaskEntry, task: &mut (dyn Task + Send)) -> PollResult {
+        #[cfg(debug_assertions)]
+        if let Some(result) = self.inject_chaos(id, entry, task) {
+            return result;
+        }
+        #[cfg(not(debug_assertions))]
+        let _ = id;
+        executor::with_ticket(&entry.ticket, || task.poll())
+    }
+
+    /// The result chaos mode makes up for a running task in place of polling it, if any.
+    #[cfg(debug_assertions)]
+    fn inject_chaos(
+        &self,
+        id: TaskId,
+        entry: &TaskEntry,
+        task: &mut (dyn Task + Send),
+    ) -> Option<PollResult> {
+        if entry.progress.status() != TaskStatus::Running {
+            return None;
+        }
+        let effect = self
+            .chaos
+            .read()
+            .expect("Panicked at inject_chaos: Chaos lock poisoned")
+            .as_ref()?
+            .roll(id.index())?;
+        let last = entry.progress.load();
+        match effect {
+            ChaosEffect::Stall => Some(last),
+            ChaosEffect::Fail => task.cancel().ok().map(|_| {
+                PollResult::Failed(TaskError::Failed {
+                    reason: "Failed by chaos mode".to_owned(),
+                })
+            }),
+            ChaosEffect::Pause => {
+                let PollResult::Pending(data) = last else {
+                    return None;
+                };
+                task.pause().ok().map(|_| PollResult::Paused(data))
+            }
+        }
+    }
+
+    /// Turns chaos mode on with the given odds, or off for `None`. Debug builds only.
+    #[cfg(debug_assertions)]
+    pub fn set_chaos(&self, config: Option<ChaosConfig>) {
+        *self
+            .chaos
+            .write()
+            .expect("Panicked at set_chaos: Chaos lock poisoned") = config.map(Chaos::new);
+    }
+
+    /// Checks, before a task's first poll starts it, that its destination has room for
+    /// its output, and cancels it if not.
+    fn preflight(
+        &self,
+        id: TaskId,
+        entry: &TaskEntry,
+        task: &mut (dyn Task + Send),
+    ) -> Result<(), TaskError> {
+        if entry.progress.status() != TaskStatus::Queued {
+            return Ok(());
+        }
+        #[cfg(not(target_arch = "wasm32"))]
+        if let Some((path, needed)) = task.required_space() {
+            if let Err(e) = disk_space::ensure_space(&path, needed) {
+                log::error!("Task {} not started: {}", id, e);
+                let _ = task.cancel();
+                return Err(e);
+            }
+        }
+        #[cfg(target_arch = "wasm32")]
+        let _ = (id, task);
+        Ok(())
+    }
+
+    /// Cancels a task that has run for longer than its timeout, marking its record.
+    fn check_timeout(
+        &self,
+        id: TaskId,
+        entry: &TaskEntry,
+        task: &mut (dyn Task + Send),
+    ) -> Result<(), TaskError> {
+        let Some(timeout) = entry.timeout else {
+            return Ok(());
+        };
+        {
+            let mut record = entry
+                .record
+                .lock()
+                .expect("Panicked at check_timeout: Record mutex poisoned");
+            let Some(started_at) = record.started_at else {
+                return Ok(());
+            };
+            if record.status.is_terminal()
+                || now_millis().saturating_sub(started_at) <= timeout.as_millis() as u64
+            {
+                return Ok(());
+            }
+            record.timed_out = true;
+        }
+        log::warn!("Task {} timed out after {}", id, format::duration(timeout));
+        let _ = task.cancel();
+        Err(TaskError::TimedOut { timeout })
+    }
+
+    /// The task's record, progress, message and resource usage. The task is only asked
+    /// for the last two if it is not in use, so this never waits on a poll.
+    pub fn task_detail(&self, id: TaskId) -> Result<TaskDetail, TaskError> {
+        let entry = self.entry(id)?;
+        #[cfg(not(target_arch = "wasm32"))]
+        let (message, resources, speed_limit) = match entry.task.try_lock() {
+            Ok(task) => (task.message(), task.resource_usage(), task.speed_limit()),
+            Err(_) => (None, None, None),
+        };
+        #[cfg(target_arch = "wasm32")]
+        let (message, resources) = match entry.task.try_lock() {
+            Ok(task) => (task.message(), task.resource_usage()),
+            Err(_) => (None, None),
+        };
+        let record = entry
+            .record
+            .lock()
+            .expect("Panicked at task_detail: Record mutex poisoned")
+            .clone();
+        Ok(TaskDetail {
+            record,
+            progress: entry.progress.load(),
+            message,
+            resources,
+            #[cfg(not(target_arch = "wasm32"))]
+            speed_limit,
+        })
+    }
+
+    /// The task's progress as of its last poll, and its current status, read without
+    /// locking the task or its record.
+    pub fn progress(&self, id: TaskId) -> Result<PollResult, TaskError> {
+        Ok(self.entry(id)?.progress.load())
+    }
+
+    /// The task's status as of its last poll, read without locking the task or its record.
+    pub fn status(&self, id: TaskId) -> Result<TaskStatus, TaskError> {
+        Ok(self.entry(id)?.progress.status())
+    }
+
+    fn polled(&self, id: TaskId, entry: &TaskEntry, result: &PollResult, artifacts: Vec<Artifact>) {
+        if let PollResult::Pending(data) | PollResult::Paused(data) = result {
+            entry.progress.set_progress(data);
+            self.report_progress(id, entry, data.fraction());
+        }
+        if !artifacts.is_empty() {
+            entry
+                .record
+                .lock()
+                .expect("Panicked at polled: Record mutex poisoned")
+                .artifacts = artifacts;
+        }
+        match result {
+            PollResult::Failed(error) => self.fail(entry, error),
+            result => self.transition(entry, TaskStatus::from(result)),
+        }
+    }
+
+    /// Logs and sends `progress` to the progress subscribers if it moved far enough, and
+    /// long enough ago, from what was last reported for the task.
+    fn report_progress(&self, id: TaskId, entry: &TaskEntry, progress: f32) {
+        let now = Instant::now();
+        {
+            let mut reported = entry
+                .reported
+                .lock()
+                .expect("Panicked at report_progress: Report mutex poisoned");
+            let granularity = reported.granularity.unwrap_or_else(|| {
+                *self
+                    .progress_granularity
+                    .read()
+                    .expect("Panicked at report_progress: Granularity lock poisoned")
+            });
+            if !granularity.is_due(reported.last, progress, now) {
+                return;
+            }
+            reported.last = Some((progress, now));
+        }
+        debug!("Task {} at {:.1}%", id, progress * 100.0);
+        #[cfg(not(target_arch = "wasm32"))]
+        self.progress_subscribers
+            .lock()
+            .expect("Panicked at report_progress: Subscribers mutex poisoned")
+            .retain(|sender| sender.send(ProgressEvent { id, progress }).is_ok());
+        #[cfg(not(target_arch = "wasm32"))]
+        self.emit(TaskEvent::Progress { id, progress });
+    }
+
+    /// Sets how much progress has to change before it is reported, for tasks without a
+    /// granularity of their own.
+    pub fn set_progress_granularity(&self, granularity: ProgressGranularity) {
+        *self
+            .progress_granularity
+            .write()
+            .expect("Panicked at set_progress_granularity: Granularity lock poisoned") =
+            granularity;
+    }
+
+    /// Overrides the queue's progress granularity for task `id`, or goes back to it for
+    /// `None`.
+    pub fn set_task_progress_granularity(
+        &self,
+        id: TaskId,
+        granularity: Option<ProgressGranularity>,
+    ) -> Result<(), TaskError> {
+        self.entry(id)?
+            .reported
+            .lock()
+            .expect("Panicked at set_task_progress_granularity: Report mutex poisoned")
+            .granularity = granularity;
+        Ok(())
+    }
+
+    pub fn remove_task(&self, id: TaskId) -> Result<(), TaskError> {
+        profile_function!();
+        let entry = self.entry(id)?;
+        entry
+            .task
+            .lock()
+            .expect("Panicked unwrapping task to cancel: Task mutex poisoned")
+            .cancel()?;
+        self.transition(&entry, TaskStatus::Cancelled);
+        Ok(())
+    }
+
+    /// Cancels each of `ids`, looking them all up with each shard of the map locked once.
+    /// Returns one result per id, in order.
+    pub fn remove_tasks(&self, ids: &[TaskId]) -> Vec<Result<(), TaskError>> {
+        profile_function!();
+        self.entries(ids)
+            .into_iter()
+            .map(|entry| {
+                let entry = entry.ok_or(TaskError::NotFound)?;
+                entry
+                    .task
+                    .lock()
+                    .expect("Panicked unwrapping task to cancel: Task mutex poisoned")
+                    .cancel()?;
+                self.transition(&entry, TaskStatus::Cancelled);
+                Ok(())
+            })
+            .collect()
+    }
+
+    /// Drops the finished tasks a frontend no longer holds an id for: those not in `keep`,
+    /// in no group and not the latest run of a recurrence. Their tasks, and whatever they
+    /// hold, are freed once no poll is using them; their records stay in the history, and
+    /// their ids are [`TaskError::NotFound`] from now on. Unfinished tasks are kept either
+    /// way. Returns how many were dropped.
+    pub fn retain_ids(&self, keep: &[TaskId]) -> usize {
+        profile_function!();
+        let mut held: HashSet<TaskId> = keep.iter().copied().collect();
+        held.extend(
+            self.recurrences
+                .lock()
+                .expect("Panicked at retain_ids: Recurrences mutex poisoned")
+                .values()
+                .map(|recurrence| recurrence.run),
+        );
+        held.extend(
+            self.groups
+                .read()
+                .expect("Panicked at retain_ids: Groups lock poisoned")
+                .iter()
+                .flat_map(|group| group.tasks.iter().copied()),
+        );
+        let dropped = self.tasks.retain(|index, entry| {
+            !entry.progress.status().is_terminal() || held.contains(&self.task_id(index))
+        });
+        if dropped > 0 {
+            debug!("Dropped {} finished tasks no longer held", dropped);
+        }
+        dropped
+    }
+
+    pub fn pause_task(&self, id: TaskId) -> Result<(), TaskError> {
+        profile_function!();
+        let Ok(entry) = self.entry(id) else {
+            log::error!("Task not found: {}", id);
+            return Err(TaskError::NotFound);
+        };
+        entry
+            .task
+            .lock()
+            .expect("Panicked unwrapping task to pause: Task mutex poisoned")
+            .pause()?;
+        self.transition(&entry, TaskStatus::Paused);
+        Ok(())
+    }
+
+    pub fn resume_task(&self, id: TaskId) -> Result<(), TaskError> {
+        profile_function!();
+        debug!("Resume requested for {}", &id);
+        let Ok(entry) = self.entry(id) else {
+            log::error!("Task not found: {}", id);
+            return Err(TaskError::NotFound);
+        };
+        entry
+            .task
+            .lock()
+            .expect("Panicked unwrapping task to resume: Task mutex poisoned")
+            .resume()?;
+        debug!("Resumed task {}", &id);
+        // Paused before it got a slot, so it goes back in line, or back to waiting for
+        // its start time.
+        let status = if entry.slot.load(Ordering::Acquire) != SLOT_NONE {
+            TaskStatus::Running
+        } else if entry.is_due() {
+            TaskStatus::Queued
+        } else {
+            TaskStatus::Scheduled
+        };
+        self.transition(&entry, status);
+        Ok(())
+    }
+
+    /// Pauses every running task and keeps queued ones, including any added meanwhile,
+    /// from starting until [`Self::resume_all`]. Returns the tasks it paused; tasks that
+    /// cannot be paused keep running.
+    pub fn pause_all(&self) -> Vec<TaskId> {
+        profile_function!();
+        // Set first, so that no task starts while the running ones are being paused.
+        self.paused.store(true, Ordering::Release);
+        let entries = self.all_entries();
+        let mut paused = Vec::new();
+        for (id, entry) in entries {
+            let mut task = entry
+                .task
+                .lock()
+                .expect("Panicked unwrapping task to pause: Task mutex poisoned");
+            // Tasks take their slot with the task locked, so any poll that started one
+            // has finished by now.
+            let status = entry.progress.status();
+            if entry.slot.load(Ordering::Acquire) == SLOT_NONE
+                || status.is_terminal()
+                || status == TaskStatus::Paused
+            {
+                continue;
+            }
+            match task.pause() {
+                Ok(()) => {
+                    drop(task);
+                    self.transition(&entry, TaskStatus::Paused);
+                    paused.push(id);
+                }
+                Err(e) => log::error!("Cannot pause task {}: {}", id, e),
+            }
+        }
+        self.paused_by_queue
+            .lock()
+            .expect("Panicked at pause_all: Paused mutex poisoned")
+            .extend(paused.iter().copied());
+        self.changed();
+        paused
+    }
+
+    /// Lets queued tasks start again and resumes the tasks [`Self::pause_all`] paused,
+    /// unless they were resumed or removed meanwhile. Tasks paused one by one stay
+    /// paused. Returns the tasks it resumed.
+    pub fn resume_all(&self) -> Vec<TaskId> {
+        profile_function!();
+        self.paused.store(false, Ordering::Release);
+        let ids = std::mem::take(
+            &mut *self
+                .paused_by_queue
+                .lock()
+                .expect("Panicked at resume_all: Paused mutex poisoned"),
+        );
+        let resumed = ids
+            .into_iter()
+            .filter(|&id| matches!(self.status(id), Ok(TaskStatus::Paused)))
+            .filter(|&id| match self.resume_task(id) {
+                Ok(()) => true,
+                Err(e) => {
+                    log::error!("Cannot resume task {}: {}", id, e);
+                    false
+                }
+            })
+            .collect();
+        self.changed();
+        resumed
+    }
+
+    /// Whether [`Self::pause_all`] is in effect.
+    pub fn is_paused(&self) -> bool {
+        self.paused.load(Ordering::Acquire)
+    }
+
+    /// Groups `ids` to be polled, paused and cancelled as one, taking them out of any
+    /// group they were in. The group goes last among the groups; see
+    /// [`Self::move_group`]. Returns the group's id.
+    pub fn create_group(&self, ids: &[TaskId]) -> Result<usize, TaskError> {
+        if !ids.iter().all(|&id| self.entry(id).is_ok()) {
+            return Err(TaskError::NotFound);
+        }
+        let id = self.next_group_id.fetch_add(1, Ordering::SeqCst);
+        let mut groups = self
+            .groups
+            .write()
+            .expect("Panicked at create_group: Groups lock poisoned");
+        for group in groups.iter_mut() {
+            group.tasks.retain(|task| !ids.contains(task));
+        }
+        groups.retain(|group| !group.tasks.is_empty());
+        groups.push(TaskGroup {
+            id,
+            tasks: ids.to_vec(),
+        });
+        self.rank_groups(&groups);
+        debug!("Grouped {} tasks as group {}", ids.len(), id);
+        Ok(id)
+    }
+
+    /// Moves the group to `position` among the groups, or last if that is past the end.
+    /// While a concurrency limit or start rate keeps tasks queued, those of groups
+    /// earlier on are let in first among tasks of the same priority, and tasks in no
+    /// group after all of them.
+    pub fn move_group(&self, id: usize, position: usize) -> Result<(), TaskError> {
+        let mut groups = self
+            .groups
+            .write()
+            .expect("Panicked at move_group: Groups lock poisoned");
+        let from = groups
+            .iter()
+            .position(|group| group.id == id)
+            .ok_or(TaskError::NotFound)?;
+        let group = groups.remove(from);
+        let position = position.min(groups.len());
+        groups.insert(position, group);
+        self.rank_groups(&groups);
+        debug!("Moved group {} to {}", id, position);
+        Ok(())
+    }
+
+    /// Lines the queued tasks up again for the groups' order.
+    fn rank_groups(&self, groups: &[TaskGroup]) {
+        let ranks = groups
+            .iter()
+            .enumerate()
+            .flat_map(|(rank, group)| group.tasks.iter().map(move |&task_id| (task_id, rank)))
+            .collect();
+        self.scheduler
+            .lock()
+            .expect("Panicked at rank_groups: Scheduler mutex poisoned")
+            .set_group_ranks(ranks);
+    }
+
+    pub fn group(&self, id: usize) -> Result<TaskGroup, TaskError> {
+        self.groups
+            .read()
+            .expect("Panicked at group: Groups lock poisoned")
+            .iter()
+            .find(|group| group.id == id)
+            .cloned()
+            .ok_or(TaskError::NotFound)
+    }
+
+    /// Every group, in the order their tasks are let in; see [`Self::move_group`].
+    pub fn groups(&self) -> Vec<TaskGroup> {
+        self.groups
+            .read()
+            .expect("Panicked at groups: Groups lock poisoned")
+            .clone()
+    }
+
+    pub fn group_of(&self, task_id: TaskId) -> Option<usize> {
+        self.groups
+            .read()
+            .expect("Panicked at group_of: Groups lock poisoned")
+            .iter()
+            .find_map(|group| group.tasks.contains(&task_id).then_some(group.id))
+    }
+
+    /// Polls every task in the group and combines the results; see
+    /// [`task_group::combine`]. A task whose poll fails counts as it was left.
+    pub fn poll_group(&self, id: usize) -> Result<PollResult, TaskError> {
+        let group = self.group(id)?;
+        let results: Vec<PollResult> = group
+            .tasks
+            .iter()
+            .map(|&task_id| {
+                self.poll_task(task_id).or_else(|e| {
+                    debug!("Task {} in group {} failed to poll: {}", task_id, id, e);
+                    self.progress(task_id)
+                })
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(task_group::combine(results))
+    }
+
+    /// The group's results as of the last poll of each task, combined like
+    /// [`Self::poll_group`] but without polling or locking any task.
+    pub fn group_progress(&self, id: usize) -> Result<PollResult, TaskError> {
+        let group = self.group(id)?;
+        let results: Vec<PollResult> = group
+            .tasks
+            .iter()
+            .map(|&task_id| self.progress(task_id))
+            .collect::<Result<_, _>>()?;
+        Ok(task_group::combine(results))
+    }
+
+    /// Pauses the group's tasks that are neither paused nor finished. All of them are
+    /// tried; the first error, if any, is returned.
+    pub fn pause_group(&self, id: usize) -> Result<(), TaskError> {
+        self.for_group(
+            id,
+            |status| !status.is_terminal() && *status != TaskStatus::Paused,
+            |task_id| self.pause_task(task_id),
+        )
+    }
+
+    /// Resumes the group's paused tasks, returning the first error like
+    /// [`Self::pause_group`].
+    pub fn resume_group(&self, id: usize) -> Result<(), TaskError> {
+        self.for_group(
+            id,
+            |status| *status == TaskStatus::Paused,
+            |task_id| self.resume_task(task_id),
+        )
+    }
+
+    /// Cancels the group's unfinished tasks, returning the first error like
+    /// [`Self::pause_group`].
+    pub fn cancel_group(&self, id: usize) -> Result<(), TaskError> {
+        self.for_group(
+            id,
+            |status| !status.is_terminal(),
+            |task_id| self.remove_task(task_id),
+        )
+    }
+
+    /// Applies `action` to each task in the group whose status passes `filter`.
+    fn for_group(
+        &self,
+        id: usize,
+        filter: impl Fn(&TaskStatus) -> bool,
+        action: impl Fn(TaskId) -> Result<(), TaskError>,
+    ) -> Result<(), TaskError> {
+        let mut first_error = None;
+        for task_id in self.group(id)?.tasks {
+            if !filter(&self.status(task_id)?) {
+                continue;
+            }
+            if let Err(e) = action(task_id) {
+                log::warn!("Task {} in group {}: {}", task_id, id, e);
+                first_error.get_or_insert(e);
+            }
+        }
+        first_error.map_or(Ok(()), Err)
+    }
+
+    /// Hands over the value the completed task computed; see [`Task::take_result`]. `None`
+    /// until the task has completed, when it computes nothing, and once taken.
+    pub fn take_result(&self, id: TaskId) -> Result<Option<Box<dyn Any + Send>>, TaskError> {
+        let entry = self.entry(id)?;
+        if entry.progress.status() != TaskStatus::Completed
+            || entry.result_taken.swap(true, Ordering::AcqRel)
+        {
+            return Ok(None);
+        }
+        let result = entry
+            .task
+            .lock()
+            .expect("Panicked at take_result: Task mutex poisoned")
+            .take_result();
+        Ok(result)
+    }
+
+    /// When the task is to start, as a Unix timestamp in milliseconds, while it is
+    /// waiting to; read without locking the task or its record.
+    pub fn scheduled_start(&self, id: TaskId) -> Result<Option<u64>, TaskError> {
+        let entry = self.entry(id)?;
+        if entry.progress.status() != TaskStatus::Scheduled {
+            return Ok(None);
+        }
+        Ok(entry.start_at.map(|(_, millis)| millis))
+    }
+
+    pub fn task_meta(&self, id: TaskId) -> Result<TaskMeta, TaskError> {
+        let entry = self.entry(id)?;
+        let record = entry
+            .record
+            .lock()
+            .expect("Panicked at task_meta: Record mutex poisoned");
+        Ok(TaskMeta::of(&record))
+    }
+
+    /// Gives the task a label, or takes it away. Kept in the record like a color tag.
+    pub fn set_label(&self, id: TaskId, label: Option<String>) -> Result<(), TaskError> {
+        let entry = self.entry(id)?;
+        let mut record = entry
+            .record
+            .lock()
+            .expect("Panicked at set_label: Record mutex poisoned");
+        record.label = label;
+        if !record.status.is_terminal() {
+            self.persist(&record);
+        }
+        Ok(())
+    }
+
+    /// Tags the task with a color, or clears its tag. The tag is kept in the task's record,
+    /// and so in the store and history, but is not reported to subscribers.
+    pub fn set_color_tag(&self, id: TaskId, tag: Option<ColorTag>) -> Result<(), TaskError> {
+        let entry = self.entry(id)?;
+        let mut record = entry
+            .record
+            .lock()
+            .expect("Panicked at set_color_tag: Record mutex poisoned");
+        record.color_tag = tag;
+        // Finished tasks are already in the store's history.
+        if !record.status.is_terminal() {
+            self.persist(&record);
+        }
+        Ok(())
+    }
+
+    /// Changes how urgent the task is. Only tasks that have not started yet can be
+    /// reprioritized; like color tags, the priority is kept in the record.
+    pub fn set_priority(&self, id: TaskId, priority: Priority) -> Result<(), TaskError> {
+        let entry = self.entry(id)?;
+        let mut record = entry
+            .record
+            .lock()
+            .expect("Panicked at set_priority: Record mutex poisoned");
+        match record.status {
+            TaskStatus::Scheduled | TaskStatus::Queued => {}
+            TaskStatus::Running | TaskStatus::Paused => return Err(TaskError::AlreadyRunning),
+            TaskStatus::Completed => return Err(TaskError::AlreadyCompleted),
+            TaskStatus::Cancelled | TaskStatus::Failed | TaskStatus::Interrupted => {
+                return Err(TaskError::AlreadyCancelled)
+            }
+        }
+        self.reprioritize(&entry, &mut record, priority);
+        Ok(())
+    }
+
+    /// Moves the task to its place in line for `priority`, and its jobs to theirs.
+    fn reprioritize(&self, entry: &TaskEntry, record: &mut TaskRecord, priority: Priority) {
+        self.scheduler
+            .lock()
+            .expect("Panicked at reprioritize: Scheduler mutex poisoned")
+            .reprioritize(
+                slot_key(record.priority, record.id),
+                slot_key(priority, record.id),
+            );
+        record.priority = priority;
+        entry.ticket.set_priority(priority);
+        self.persist(record);
+    }
+
+    /// Sets when the task should be done by, in Unix milliseconds, or takes its deadline
+    /// away for `None`. Missing it cancels nothing; see
+    /// [`subscribe_deadlines`](Self::subscribe_deadlines). A new deadline is watched afresh.
+    pub fn set_deadline(&self, id: TaskId, deadline: Option<u64>) -> Result<(), TaskError> {
+        let entry = self.entry(id)?;
+        let mut record = entry
+            .record
+            .lock()
+            .expect("Panicked at set_deadline: Record mutex poisoned");
+        match record.status {
+            TaskStatus::Scheduled
+            | TaskStatus::Queued
+            | TaskStatus::Running
+            | TaskStatus::Paused => {}
+            TaskStatus::Completed => return Err(TaskError::AlreadyCompleted),
+            TaskStatus::Cancelled | TaskStatus::Failed | TaskStatus::Interrupted => {
+                return Err(TaskError::AlreadyCancelled)
+            }
+        }
+        record.deadline = deadline;
+        entry
+            .deadline
+            .store(deadline.unwrap_or(NO_DEADLINE), Ordering::Release);
+        entry.at_risk.store(false, Ordering::Release);
+        self.persist(&record);
+        Ok(())
+    }
+
+    /// Whether the task was found to be at risk of missing its deadline.
+    pub fn deadline_at_risk(&self, id: TaskId) -> Result<bool, TaskError> {
+        Ok(self.entry(id)?.at_risk.load(Ordering::Acquire))
+    }
+
+    /// Raises the priority of each task found to be at risk of missing its deadline by a
+    /// level, whether it has started or not.
+    pub fn set_boost_at_risk(&self, boost: bool) {
+        self.boost_at_risk.store(boost, Ordering::Relaxed);
+    }
+
+    /// Checks, as of its last poll, whether an unfinished task with a deadline is going
+    /// to miss it, and if it newly is flags it, boosts it if asked to and reports it.
+    fn watch_deadline(&self, entry: &TaskEntry) {
+        let deadline = entry.deadline.load(Ordering::Acquire);
+        if deadline == NO_DEADLINE || entry.at_risk.load(Ordering::Acquire) {
+            return;
+        }
+        let progress = match entry.progress.load() {
+            PollResult::Pending(data) | PollResult::Paused(data) => data.fraction(),
+            PollResult::Completed | PollResult::Cancelled | PollResult::Failed(_) => return,
+        };
+        let mut record = entry
+            .record
+            .lock()
+            .expect("Panicked at watch_deadline: Record mutex poisoned");
+        if record.status.is_terminal() {
+            return;
+        }
+        let Some(predicted_finish) =
+            deadline::at_risk(deadline, record.started_at, progress, now_millis())
+        else {
+            return;
+        };
+        if entry.at_risk.swap(true, Ordering::AcqRel) {
+            return;
+        }
+        if self.boost_at_risk.load(Ordering::Relaxed) {
+            let raised = record.priority.raised();
+            if raised != record.priority {
+                self.reprioritize(entry, &mut record, raised);
+            }
+        }
+        let event = DeadlineAtRisk {
+            id: record.id,
+            deadline,
+            predicted_finish,
+            priority: record.priority,
+        };
+        drop(record);
+        debug!("Task {} may miss its deadline", event.id);
+        #[cfg(not(target_arch = "wasm32"))]
+        self.deadline_subscribers
+            .lock()
+            .expect("Panicked at watch_deadline: Subscribers mutex poisoned")
+            .retain(|sender| sender.send(event).is_ok());
+        self.changed();
+    }
+
+    /// Sets the task's annotations in `changes`, removing those whose value is `None`,
+    /// and returns all of them. Like color tags they are kept in the record but not
+    /// reported to subscribers. Finished tasks can no longer be annotated.
+    pub fn annotate(
+        &self,
+        id: TaskId,
+        changes: BTreeMap<String, Option<String>>,
+    ) -> Result<BTreeMap<String, String>, TaskError> {
+        let entry = self.entry(id)?;
+        let mut record = entry
+            .record
+            .lock()
+            .expect("Panicked at annotate: Record mutex poisoned");
+        if record.status == TaskStatus::Completed {
+            return Err(TaskError::AlreadyCompleted);
+        }
+        if record.status.is_terminal() {
+            return Err(TaskError::AlreadyCancelled);
+        }
+        for (key, value) in changes {
+            match value {
+                Some(value) => record.annotations.insert(key, value),
+                None => record.annotations.remove(&key),
+            };
+        }
+        self.persist(&record);
+        Ok(record.annotations.clone())
+    }
+
+    /// Current records of every task in the queue, including finished ones, ordered by id.
+    pub fn records(&self) -> Vec<TaskRecord> {
+        self.records_from(0)
+    }
+
+    /// Current records of the tasks with ids from `first_id` on, ordered by id.
+    pub fn records_from(&self, first_id: usize) -> Vec<TaskRecord> {
+        profile_function!();
+        self.tasks
+            .values_from(first_id)
+            .iter()
+            .map(|(_, entry)| {
+                entry
+                    .record
+                    .lock()
+                    .expect("Panicked at records: Record mutex poisoned")
+                    .clone()
+            })
+            .collect()
+    }
+
+    /// Counts of the active tasks, and a forecast of when they will all be done from
+    /// their progress so far, how long finished tasks of their kinds took and the
+    /// concurrency limit.
+    pub fn stats(&self) -> QueueStats {
+        profile_function!();
+        let now = now_millis();
+        let mut stats = QueueStats::default();
+        let mut active = Vec::new();
+        for (_, entry) in self.tasks.values() {
+            let status = entry.progress.status();
+            match status {
+                TaskStatus::Scheduled => {
+                    stats.scheduled += 1;
+                    continue;
+                }
+                TaskStatus::Queued => stats.queued += 1,
+                TaskStatus::Running => stats.running += 1,
+                TaskStatus::Paused => stats.paused += 1,
+                TaskStatus::Completed
+                | TaskStatus::Cancelled
+                | TaskStatus::Failed
+                | TaskStatus::Interrupted => continue,
+            }
+            let progress = match entry.progress.load() {
+                PollResult::Pending(data) | PollResult::Paused(data) => data.fraction(),
+                _ => 0.0,
+            };
+            let record = entry
+                .record
+                .lock()
+                .expect("Panicked at stats: Record mutex poisoned");
+            active.push(ActiveTask {
+                kind: record.kind.clone(),
+                status,
+                progress,
+                elapsed: record
+                    .started_at
+                    .map(|started| Duration::from_millis(now.saturating_sub(started))),
+            });
+        }
+        let typical = TypicalDurations::from_history(
+            self.history
+                .lock()
+                .expect("Panicked at stats: History mutex poisoned")
+                .iter(),
+        );
+        let concurrency = match (self.concurrency(), executor::worker_limit()) {
+            (Some(queue), Some(workers)) => Some(queue.min(workers)),
+            (queue, workers) => queue.or(workers),
+        };
+        let forecast = forecast::forecast(&active, &typical, concurrency);
+        stats.estimated_finish_time = forecast
+            .remaining
+            .map(|remaining| now + remaining.as_millis() as u64);
+        stats.unestimated = forecast.unestimated;
+        stats
+    }
+
+    /// Records of tasks that reached a terminal state, oldest first. Only those still in
+    /// memory are returned; see [`history_page`](Self::history_page).
+    pub fn history(&self) -> Vec<TaskRecord> {
+        self.history
+            .lock()
+            .expect("Panicked at history: History mutex poisoned")
+            .clone()
+    }
+
+    /// Up to `limit` history records, oldest first, after skipping the `offset` most recent.
+    /// Records no longer in memory are read back from the store or the spill file.
+    pub fn history_page(&self, offset: usize, limit: usize) -> Vec<TaskRecord> {
+        profile_function!();
+        #[cfg(all(feature = "sqlite", not(target_arch = "wasm32")))]
+        if let Some(store) = &self.store {
+            let loaded = store
+                .lock()
+                .expect("Panicked at history_page: Store mutex poisoned")
+                .load_history_page(offset, limit);
+            match loaded {
+                Ok(records) => return records,
+                Err(e) => log::error!("Paging in-memory history only: {}", e),
+            }
+        }
+        let history = self
+            .history
+            .lock()
+            .expect("Panicked at history_page: History mutex poisoned");
+        #[cfg(not(target_arch = "wasm32"))]
+        let mut file = self
+            .history_file
+            .lock()
+            .expect("Panicked at history_page: History file mutex poisoned");
+        #[cfg(not(target_arch = "wasm32"))]
+        let spilled = file.as_ref().map_or(0, HistoryFile::len);
+        #[cfg(target_arch = "wasm32")]
+        let spilled = 0;
+        let end = (spilled + history.len()).saturating_sub(offset);
+        let start = end.saturating_sub(limit);
+        let mut page = Vec::new();
+        #[cfg(not(target_arch = "wasm32"))]
+        if let Some(file) = file.as_mut().filter(|_| start < spilled) {
+            match file.read(start..end.min(spilled)) {
+                Ok(records) => page = records,
+                Err(e) => log::error!("Cannot read spilled history: {}", e),
+            }
+        }
+        page.extend_from_slice(&history[start.max(spilled) - spilled..end.max(spilled) - spilled]);
+        page
+    }
+
+    /// Keeps at most `limit` history records in memory, or all of them for `None`. Older
+    /// records are dropped from memory when a store holds them, and otherwise moved to the
+    /// file given to [`set_history_file`](Self::set_history_file); without one they stay.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn set_history_limit(&self, limit: Option<usize>) {
+        self.history_limit
+            .store(limit.unwrap_or(usize::MAX), Ordering::Relaxed);
+        let mut history = self
+            .history
+            .lock()
+            .expect("Panicked at set_history_limit: History mutex poisoned");
+        self.spill_history(&mut history);
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn set_history_file(&self, file: HistoryFile) {
+        *self
+            .history_file
+            .lock()
+            .expect("Panicked at set_history_file: History file mutex poisoned") = Some(file);
+    }
+
+    /// Writes every task added from now on, and each status change before it is made, to
+    /// `journal`, so the tasks a crash cuts short can be told apart on the next launch.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn set_journal(&self, journal: sync_Arc<Journal>) {
+        *self
+            .journal
+            .write()
+            .expect("Panicked at set_journal: Journal lock poisoned") = Some(journal);
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    fn write_journal(&self, entry: &JournalEntry) {
+        if let Some(journal) = &*self
+            .journal
+            .read()
+            .expect("Panicked at write_journal: Journal lock poisoned")
+        {
+            journal.write(entry);
+        }
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    fn spill_history(&self, history: &mut Vec<TaskRecord>) {
+        let limit = self.history_limit.load(Ordering::Relaxed);
+        if history.len() <= limit {
+            return;
+        }
+        // A quarter of the limit more than needed, so that not every finish spills.
+        let count = (history.len() - limit)
+            .saturating_add(limit / 4)
+            .min(history.len());
+        #[cfg(feature = "sqlite")]
+        if self.store.is_some() {
+            history.drain(..count);
+            return;
+        }
+        let mut file = self
+            .history_file
+            .lock()
+            .expect("Panicked at spill_history: History file mutex poisoned");
+        if let Some(file) = file.as_mut() {
+            let retention = self.history_retention();
+            let appended = if retention.exclude_params {
+                let records: Vec<TaskRecord> = history[..count]
+                    .iter()
+                    .map(|record| retention.for_disk(record).into_owned())
+                    .collect();
+                file.append(&records)
+            } else {
+                file.append(&history[..count])
+            };
+            match appended {
+                Ok(()) => {
+                    history.drain(..count);
+                }
+                Err(e) => log::error!("Cannot spill history to disk: {}", e),
+            }
+        }
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    fn spill_history(&self, _history: &mut Vec<TaskRecord>) {}
+
+    /// Limits how long history is kept, and what of it is written to disk, and drops what
+    /// is already past the limits.
+    pub fn set_history_retention(&self, retention: HistoryRetention) {
+        *self
+            .history_retention
+            .write()
+            .expect("Panicked at set_history_retention: Retention lock poisoned") = retention;
+        self.prune_history();
+    }
+
+    fn history_retention(&self) -> HistoryRetention {
+        self.history_retention
+            .read()
+            .expect("Panicked at history_retention: Retention lock poisoned")
+            .clone()
+    }
+
+    /// Drops the history the retention limits no longer keep, from memory, the spill file
+    /// and the store alike.
+    fn prune_history(&self) {
+        let retention = self.history_retention();
+        if !retention.is_limited() {
+            return;
+        }
+        let cutoff = retention.cutoff(now_millis());
+        let mut history = self
+            .history
+            .lock()
+            .expect("Panicked at prune_history: History mutex poisoned");
+        history.retain(|record| HistoryRetention::keeps(record, cutoff));
+        #[cfg(not(target_arch = "wasm32"))]
+        if let Some(file) = self
+            .history_file
+            .lock()
+            .expect("Panicked at prune_history: History file mutex poisoned")
+            .as_mut()
+        {
+            if let Some(cutoff) = cutoff {
+                match file.count_finished_before(cutoff) {
+                    Ok(count) => file.forget_oldest(count),
+                    Err(e) => log::error!("Cannot read spilled history: {}", e),
+                }
+            }
+            if let Some(max_count) = retention.max_count {
+                file.forget_oldest((file.len() + history.len()).saturating_sub(max_count));
+            }
+        }
+        if let Some(max_count) = retention.max_count {
+            let excess = history.len().saturating_sub(max_count);
+            history.drain(..excess);
+        }
+        drop(history);
+        #[cfg(all(feature = "sqlite", not(target_arch = "wasm32")))]
+        if let Some(store) = &self.store {
+            let pruned = store
+                .lock()
+                .expect("Panicked at prune_history: Store mutex poisoned")
+                .prune_history(cutoff, retention.max_count);
+            if let Err(e) = pruned {
+                log::error!("Failed to prune stored history: {}", e);
+            }
+        }
+    }
+
+    /// Forgets every finished task, in memory and on disk: the spill file is emptied and
+    /// the store's history deleted. Unfinished tasks are kept.
+    pub fn clear_history(&self) -> Result<(), String> {
+        self.history
+            .lock()
+            .expect("Panicked at clear_history: History mutex poisoned")
+            .clear();
+        #[cfg(not(target_arch = "wasm32"))]
+        if let Some(file) = self
+            .history_file
+            .lock()
+            .expect("Panicked at clear_history: History file mutex poisoned")
+            .as_mut()
+        {
+            file.clear()
+                .map_err(|e| format!("Cannot empty the history spill file: {}", e))?;
+        }
+        #[cfg(all(feature = "sqlite", not(target_arch = "wasm32")))]
+        if let Some(store) = &self.store {
+            store
+                .lock()
+                .expect("Panicked at clear_history: Store mutex poisoned")
+                .clear_history()
+                .map_err(|e| e.to_string())?;
+        }
+        Ok(())
+    }
+
+    /// Notes that `origin` asked for `action` on task `task_id`. The queue's own methods
+    /// do not, as only their callers know who they act for.
+    pub fn audit(&self, task_id: TaskId, action: AuditAction, origin: &Origin) {
+        log::info!("Task {} {} by {}", task_id, action, origin);
+        let mut audit = self
+            .audit
+            .lock()
+            .expect("Panicked at audit: Audit mutex poisoned");
+        if audit.len() == AUDIT_LIMIT {
+            audit.pop_front();
+        }
+        audit.push_back(AuditEntry::new(task_id, action, origin.clone()));
+    }
+
+    /// Audit entries, oldest first, for one task or, with `None`, for all.
+    pub fn audit_log(&self, task_id: Option<TaskId>) -> Vec<AuditEntry> {
+        self.audit
+            .lock()
+            .expect("Panicked at audit_log: Audit mutex poisoned")
+            .iter()
+            .filter(|entry| task_id.map_or(true, |id| entry.task_id == id))
+            .cloned()
+            .collect()
+    }
+
+    /// Writes the history to `path` as JSON Lines, one task record per line, oldest first,
+    /// and returns the number of records written. Records spilled from memory or only in
+    /// the store are included, each with its task's audit entries under `audit`.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn export_history_jsonl(&self, path: &Path) -> std::io::Result<usize> {
+        let records = self.history_page(0, usize::MAX);
+        // By number: records read back from the store or the spill file are detached.
+        let mut audit: std::collections::HashMap<usize, Vec<AuditEntry>> =
+            std::collections::HashMap::new();
+        for entry in self.audit_log(None) {
+            audit.entry(entry.task_id.index()).or_default().push(entry);
+        }
+        let mut writer = std::io::BufWriter::new(std::fs::File::create(path)?);
+        for record in &records {
+            let mut line = serde_json::to_value(record)?;
+            if let (Some(entries), Some(object)) =
+                (audit.remove(&record.id.index()), line.as_object_mut())
+            {
+                object.insert("audit".to_owned(), serde_json::to_value(entries)?);
+            }
+            serde_json::to_writer(&mut writer, &line)?;
+            writer.write_all(b"\n")?;
+        }
+        writer.flush()?;
+        Ok(records.len())
+    }
+
+    /// The runs planned so far: each scheduled task still waiting for its start time, and
+    /// each recurrence's next run, which stands for those after it. See [`calendar`].
+    pub fn planned_runs(&self) -> Vec<PlannedRun> {
+        let summary = |entry: &TaskEntry| {
+            let record = entry
+                .record
+                .lock()
+                .expect("Panicked at planned_runs: Record mutex poisoned");
+            record.label.clone().unwrap_or_else(|| record.kind.clone())
+        };
+        let recurrence_ids: Vec<usize> = self
+            .recurrences
+            .lock()
+            .expect("Panicked at planned_runs: Recurrences mutex poisoned")
+            .keys()
+            .copied()
+            .collect();
+        let mut runs: Vec<PlannedRun> = recurrence_ids
+            .into_iter()
+            .filter_map(|id| self.recurrence(id).ok())
+            .filter_map(|recurrence| {
+                let entry = self.entry(recurrence.run).ok()?;
+                Some(PlannedRun {
+                    id: recurrence.run,
+                    summary: summary(&entry),
+                    start: recurrence.next_fire,
+                    every: Some(recurrence.every),
+                })
+            })
+            .collect();
+        for (index, entry) in self.tasks.values() {
+            let id = self.task_id(index);
+            let Some((_, start)) = entry.start_at else {
+                continue;
+            };
+            if entry.progress.status() != TaskStatus::Scheduled
+                || runs.iter().any(|run| run.id == id)
+            {
+                continue;
+            }
+            runs.push(PlannedRun {
+                id,
+                summary: summary(&entry),
+                start,
+                every: None,
+            });
+        }
+        runs.sort_by_key(|run| run.start);
+        runs
+    }
+
+    /// Writes the runs planned within [`calendar::HORIZON`] to `path` as an iCalendar
+    /// file, and returns the number of events in it.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn export_schedule_ics(&self, path: &Path) -> std::io::Result<usize> {
+        let (text, count) = calendar::ics(&self.planned_runs(), now_millis(), calendar::HORIZON);
+        std::fs::write(path, text)?;
+        Ok(count)
+    }
+
+    /// Prepends records from a previous session to the history.
+    pub fn restore_history(&self, mut records: Vec<TaskRecord>) {
+        let mut history = self
+            .history
+            .lock()
+            .expect("Panicked at restore_history: History mutex poisoned");
+        records.append(&mut history);
+        *history = records;
+        self.spill_history(&mut history);
+        drop(history);
+        self.prune_history();
+    }
+
+    /// Adds the records of tasks that a previous run left unfinished to the history, and
+    /// moves them to the store's history, where it still lists them as unfinished.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn restore_interrupted(&self, records: Vec<TaskRecord>) {
+        for record in &records {
+            self.persist(record);
+        }
+        let mut history = self
+            .history
+            .lock()
+            .expect("Panicked at restore_interrupted: History mutex poisoned");
+        history.extend(records);
+        self.spill_history(&mut history);
+        drop(history);
+        self.prune_history();
+    }
+
+    fn transition(&self, entry: &TaskEntry, status: TaskStatus) {
+        self.change_status(entry, status, None);
+    }
+
+    /// Cancels the task for `error`, which event subscribers hear of as a failure.
+    fn fail(&self, entry: &TaskEntry, error: &TaskError) {
+        self.change_status(entry, TaskStatus::Failed, Some(error));
+    }
+
+    fn change_status(&self, entry: &TaskEntry, status: TaskStatus, failure: Option<&TaskError>) {
+        profile_function!();
+        let mut record = entry
+            .record
+            .lock()
+            .expect("Panicked at transition: Record mutex poisoned");
+        if record.status == status || record.status.is_terminal() {
+            return;
+        }
+        #[cfg(not(target_arch = "wasm32"))]
+        let event = TaskEvent::of_transition(record.id, &record.status, &status, failure);
+        debug!(
+            "Task {} transition {:?} -> {:?}",
+            record.id, record.status, status
+        );
+        #[cfg(not(target_arch = "wasm32"))]
+        self.write_journal(&JournalEntry::Status {
+            id: record.id,
+            status: status.clone(),
+        });
+        if let Some(error) = failure {
+            record.error = Some(error.to_string());
+            *entry
+                .progress
+                .failure
+                .lock()
+                .expect("Panicked at transition: Failure mutex poisoned") = Some(error.clone());
+        }
+        record.status = status;
+        entry.progress.set_status(&record.status);
+        let now = now_millis();
+        if record.started_at.is_none()
+            && !matches!(record.status, TaskStatus::Scheduled | TaskStatus::Queued)
+        {
+            record.started_at = Some(now);
+        }
+        if record.status == TaskStatus::Queued && entry.slot.load(Ordering::Acquire) == SLOT_NONE {
+            self.scheduler
+                .lock()
+                .expect("Panicked at transition: Scheduler mutex poisoned")
+                .enqueue(slot_key(record.priority, record.id));
+        }
+        if record.status.is_terminal() {
+            record.finished_at = Some(now);
+            entry.cancellation.cancel();
+            let mut scheduler = self
+                .scheduler
+                .lock()
+                .expect("Panicked at transition: Scheduler mutex poisoned");
+            match entry.slot.swap(SLOT_RELEASED, Ordering::AcqRel) {
+                SLOT_HELD => scheduler.release(),
+                SLOT_NONE => scheduler.forget(slot_key(record.priority, record.id)),
+                _ => {}
+            }
+            drop(scheduler);
+            let mut history = self
+                .history
+                .lock()
+                .expect("Panicked at transition: History mutex poisoned");
+            history.push(record.clone());
+            self.spill_history(&mut history);
+        }
+        self.persist(&record);
+        self.notify(&record);
+        #[cfg(not(target_arch = "wasm32"))]
+        if let Some(event) = event {
+            self.emit(event);
+        }
+        let finished = record.status.is_terminal();
+        let failed = record.status == TaskStatus::Failed;
+        let id = record.id;
+        drop(record);
+        if finished {
+            self.prune_history();
+            self.recur(id);
+        }
+        if failed {
+            self.retry(id, entry);
+        }
+        // Adding a task never makes the queue idle, so only status changes wake waiters.
+        self.wake_idle_waiters();
+    }
+
+    /// Returns a channel receiving a copy of each task's record whenever it is added
+    /// or changes status. The subscription ends when the receiver is dropped.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn subscribe(&self) -> mpsc::Receiver<TaskRecord> {
+        let (sender, receiver) = mpsc::channel();
+        self.subscribers
+            .lock()
+            .expect("Panicked at subscribe: Subscribers mutex poisoned")
+            .push(sender);
+        receiver
+    }
+
+    /// Returns a channel receiving tasks' progress as they are polled, at most as often as
+    /// the progress granularity allows. The subscription ends when the receiver is dropped.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn subscribe_progress(&self) -> mpsc::Receiver<ProgressEvent> {
+        let (sender, receiver) = mpsc::channel();
+        self.progress_subscribers
+            .lock()
+            .expect("Panicked at subscribe_progress: Subscribers mutex poisoned")
+            .push(sender);
+        receiver
+    }
+
+    /// Returns a channel receiving each task's lifecycle events as they happen: when it
+    /// starts, progresses, is paused or resumed, and how it ends. Tasks only change when
+    /// polled, so events are sent by whoever polls them. The subscription ends when the
+    /// receiver is dropped.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn subscribe_events(&self) -> mpsc::Receiver<TaskEvent> {
+        let (sender, receiver) = mpsc::channel();
+        self.event_subscribers
+            .lock()
+            .expect("Panicked at subscribe_events: Subscribers mutex poisoned")
+            .push(sender);
+        receiver
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    fn emit(&self, event: TaskEvent) {
+        self.event_subscribers
+            .lock()
+            .expect("Panicked at emit: Subscribers mutex poisoned")
+            .retain(|sender| sender.send(event.clone()).is_ok());
+    }
+
+    /// Returns a channel receiving each task found to be at risk of missing its deadline.
+    /// The subscription ends when the receiver is dropped.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn subscribe_deadlines(&self) -> mpsc::Receiver<DeadlineAtRisk> {
+        let (sender, receiver) = mpsc::channel();
+        self.deadline_subscribers
+            .lock()
+            .expect("Panicked at subscribe_deadlines: Subscribers mutex poisoned")
+            .push(sender);
+        receiver
+    }
+
+    /// Calls `on_change` whenever a task is added, changes status or is found to be at
+    /// risk of missing its deadline, or the queue is paused or resumed, from whichever
+    /// thread made the change, e.g. to wake a UI that idles between changes.
+    pub fn set_on_change(&self, on_change: impl Fn() + Send + Sync + 'static) {
+        *self
+            .on_change
+            .lock()
+            .expect("Panicked at set_on_change: Callback mutex poisoned") =
+            Some(Box::new(on_change));
+    }
+
+    fn notify(&self, _record: &TaskRecord) {
+        #[cfg(not(target_arch = "wasm32"))]
+        self.subscribers
+            .lock()
+            .expect("Panicked at notify: Subscribers mutex poisoned")
+            .retain(|sender| sender.send(_record.clone()).is_ok());
+        self.changed();
+    }
+
+    fn changed(&self) {
+        if let Some(on_change) = &*self
+            .on_change
+            .lock()
+            .expect("Panicked at changed: Callback mutex poisoned")
+        {
+            on_change();
+        }
+    }
+
+    /// Whether no task is scheduled, queued or running. Paused tasks do not count, since
+    /// they only move on when someone resumes them. Tasks only change status when polled, so a
+    /// queue nobody polls does not become idle.
+    pub fn is_idle(&self) -> bool {
+        self.tasks
+            .values()
+            .iter()
+            .all(|(_, entry)| !matches!(entry.progress.load(), PollResult::Pending(_)))
+    }
+
+    /// Stops the queue for good, e.g. as the app closes: tasks added from now on are
+    /// cancelled right away, recurrences add no more runs, and every unfinished task is
+    /// cancelled. [`TaskStatus::Interrupted`] is left for tasks a crash cut short. Then
+    /// waits up to `grace` for the work of every task to end, so none is left running
+    /// unseen. Returns whether it all did in time.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn shutdown(&self, grace: Duration) -> bool {
+        profile_function!();
+        self.shutting_down.store(true, Ordering::Release);
+        self.recurrences
+            .lock()
+            .expect("Panicked at shutdown: Recurrences mutex poisoned")
+            .clear();
+        let mut handles = Vec::new();
+        let mut cancelled = 0;
+        for (_, entry) in self.tasks.values() {
+            let mut task = entry
+                .task
+                .lock()
+                .expect("Panicked at shutdown: Task mutex poisoned");
+            // Finished tasks may still be winding down after a cancel.
+            handles.extend(task.take_handle());
+            if entry.progress.status().is_terminal() {
+                continue;
+            }
+            let _ = task.cancel();
+            drop(task);
+            self.transition(&entry, TaskStatus::Cancelled);
+            cancelled += 1;
+        }
+        log::info!(
+            "Shutting down: {} tasks cancelled, waiting for {} jobs",
+            cancelled,
+            handles.len()
+        );
+        executor::wait_for_all(handles, grace)
+    }
+
+    /// Resolves once [`TaskQueue::is_idle`] holds, right away if it already does.
+    pub async fn wait_idle(&self) {
+        loop {
+            let (sender, receiver) = channel::bounded(1);
+            self.idle_waiters
+                .lock()
+                .expect("Panicked at wait_idle: Waiters mutex poisoned")
+                .push(sender);
+            // Checked after registering, so a change in between still wakes us.
+            if self.is_idle() {
+                return;
+            }
+            let _ = receiver.recv().await;
+        }
+    }
+
+    fn wake_idle_waiters(&self) {
+        let mut waiters = self
+            .idle_waiters
+            .lock()
+            .expect("Panicked at wake_idle_waiters: Waiters mutex poisoned");
+        if waiters.is_empty() || !self.is_idle() {
+            return;
+        }
+        for waiter in waiters.drain(..) {
+            let _ = waiter.try_send(());
+        }
+    }
+
+    #[cfg(all(feature = "sqlite", not(target_arch = "wasm32")))]
+    fn persist(&self, record: &TaskRecord) {
+        profile_function!();
+        if let Some(store) = &self.store {
+            let mut store = store
+                .lock()
+                .expect("Panicked at persist: Store mutex poisoned");
+            let record = self.history_retention().for_disk(record);
+            let result = if record.status.is_terminal() {
+                store.finish_task(&record)
+            } else {
+                store.save_task(&record)
+            };
+            if let Err(e) = result {
+                log::error!("Failed to persist task {}: {}", record.id, e);
+            }
+        }
+    }
+
+    #[cfg(not(all(feature = "sqlite", not(target_arch = "wasm32"))))]
+    fn persist(&self, _record: &TaskRecord) {}
+
+    pub fn _get_task(&self, id: TaskId) -> Result<Receiver<()>, TaskError> {
+        debug!("Got task with id: {}", id);
+        match self.entry(id) {
+            Ok(entry) => {
+                debug!("matched Some(task) with id: {}", id);
+                let task_clone = entry.task.clone();
+                let (tx, rx) = channel::bounded(1);
+                let tx_clone = tx;
+                executor::spawn(async move {
+                    loop {
+                        let result = task_clone.lock().unwrap().poll();
+                        match result {
+                            PollResult::Pending(progress) => {
                                 debug!("PollResult::Pending: {}", progress);
-                                continue;
+                                executor::sleep(Duration::from_millis(10)).await;
                             }
                             PollResult::Paused(p) => {
                                 debug!("PollResult::Paused at {}", p);
-                                continue;
+                                executor::sleep(Duration::from_millis(10)).await;
                             }
                             PollResult::Completed => {
                                 debug!("PollResult::Completed");
@@ -210,6 +3169,10 @@ impl TaskQueue {
                                 debug!("PollResult::Cancelled");
                                 break;
                             }
+                            PollResult::Failed(e) => {
+                                debug!("PollResult::Failed: {}", e);
+                                break;
+                            }
                         }
                     }
                     let send = tx_clone.send(());
@@ -218,7 +3181,7 @@ impl TaskQueue {
 
                 Ok(rx)
             }
-            None => Err(TaskError::NotFound),
+            Err(e) => Err(e),
         }
     }
 }
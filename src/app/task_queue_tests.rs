@@ -1,5 +1,7 @@
 #[cfg(test)]
-use crate::app::task_queue::{PollResult, PollingData, TaskError, TaskQueue};
+use crate::app::task_id::TaskId;
+#[cfg(test)]
+use crate::app::task_queue::{PollResult, PollingData, TaskError, TaskQueue, TaskStatus};
 
 fn _setup_logging() {
     let _ = env_logger::Builder::new()
@@ -10,10 +12,12 @@ fn _setup_logging() {
 #[test]
 fn test_add_task() {
     let task_queue = TaskQueue::new();
-    let task =
-        crate::app::sleep_task::SleepTask::new(Some(0), std::time::Duration::from_millis(100));
+    let task = crate::app::sleep_task::SleepTask::new(
+        Some(TaskId::detached(0)),
+        std::time::Duration::from_millis(100),
+    );
     let task_id = task_queue.add_task(task);
-    assert_eq!(task_id, 0);
+    assert_eq!(task_id.index(), 0);
 }
 
 #[test]
@@ -21,8 +25,10 @@ fn test_sleep_task_completion() {
     _setup_logging();
     async_std::task::block_on(async {
         let task_queue = TaskQueue::new();
-        let task =
-            crate::app::sleep_task::SleepTask::new(Some(0), std::time::Duration::from_millis(100));
+        let task = crate::app::sleep_task::SleepTask::new(
+            Some(TaskId::detached(0)),
+            std::time::Duration::from_millis(100),
+        );
         let task_id = task_queue.add_task(task);
 
         let rx = task_queue._get_task(task_id).unwrap();
@@ -41,23 +47,29 @@ fn test_sleep_task_completion() {
 fn test_add_multiple_tasks() {
     let task_queue = TaskQueue::new();
 
-    let task_one =
-        crate::app::sleep_task::SleepTask::new(Some(0), std::time::Duration::from_secs(2));
+    let task_one = crate::app::sleep_task::SleepTask::new(
+        Some(TaskId::detached(0)),
+        std::time::Duration::from_secs(2),
+    );
     let task_one_id = task_queue.add_task(task_one);
 
-    let task_two =
-        crate::app::sleep_task::SleepTask::new(Some(1), std::time::Duration::from_secs(2));
+    let task_two = crate::app::sleep_task::SleepTask::new(
+        Some(TaskId::detached(1)),
+        std::time::Duration::from_secs(2),
+    );
     let task_two_id = task_queue.add_task(task_two);
 
-    assert_eq!(task_one_id, 0);
-    assert_eq!(task_two_id, 1);
+    assert_eq!(task_one_id.index(), 0);
+    assert_eq!(task_two_id.index(), 1);
 }
 
 #[test]
 fn test_poll_task() {
     let task_queue = TaskQueue::new();
-    let task =
-        crate::app::sleep_task::SleepTask::new(Some(0), std::time::Duration::from_millis(100));
+    let task = crate::app::sleep_task::SleepTask::new(
+        Some(TaskId::detached(0)),
+        std::time::Duration::from_millis(100),
+    );
     let task_id = task_queue.add_task(task);
     let poll_result = task_queue.poll_task(task_id);
     match poll_result {
@@ -77,8 +89,10 @@ fn test_poll_task() {
 fn test_remove_task() {
     _setup_logging();
     let task_queue = TaskQueue::new();
-    let task =
-        crate::app::sleep_task::SleepTask::new(Some(0), std::time::Duration::from_millis(100));
+    let task = crate::app::sleep_task::SleepTask::new(
+        Some(TaskId::detached(0)),
+        std::time::Duration::from_millis(100),
+    );
     let task_id = task_queue.add_task(task);
     let remove_result = task_queue.remove_task(task_id);
     assert!(remove_result.is_ok());
@@ -88,8 +102,10 @@ fn test_remove_task() {
 fn test_remove_polled_task() {
     _setup_logging();
     let task_queue = TaskQueue::new();
-    let task =
-        crate::app::sleep_task::SleepTask::new(Some(0), std::time::Duration::from_millis(200));
+    let task = crate::app::sleep_task::SleepTask::new(
+        Some(TaskId::detached(0)),
+        std::time::Duration::from_millis(200),
+    );
     let task_id = task_queue.add_task(task);
     let poll_result = task_queue.poll_task(task_id);
     assert!(poll_result.is_ok());
@@ -100,17 +116,37 @@ fn test_remove_polled_task() {
 #[test]
 fn test_remove_non_existent_task() {
     let task_queue = TaskQueue::new();
-    let remove_result = task_queue.remove_task(0);
+    let remove_result = task_queue.remove_task(task_queue.task_id(0));
     assert_eq!(remove_result.unwrap_err(), TaskError::NotFound);
 }
 
+#[test]
+fn test_ids_from_another_queue_are_not_found() {
+    let first = TaskQueue::new();
+    let second = TaskQueue::new();
+    let task_id = first.add_task(crate::app::stress::StressTask::new(1));
+    let other_id = second.add_task(crate::app::stress::StressTask::new(1));
+    assert_eq!(task_id.index(), other_id.index());
+    assert_ne!(task_id, other_id);
+    assert_eq!(second.status(task_id), Err(TaskError::NotFound));
+    assert_eq!(second.remove_task(task_id), Err(TaskError::NotFound));
+    assert_eq!(second.status(other_id), Ok(TaskStatus::Queued));
+    // Saved ids name no live task.
+    assert_eq!(
+        first.status(TaskId::detached(task_id.index())),
+        Err(TaskError::NotFound)
+    );
+}
+
 #[test]
 fn test_pause_and_resume() {
     _setup_logging();
     async_std::task::block_on(async {
         let task_queue = TaskQueue::new();
-        let task =
-            crate::app::sleep_task::SleepTask::new(Some(0), std::time::Duration::from_millis(500));
+        let task = crate::app::sleep_task::SleepTask::new(
+            Some(TaskId::detached(0)),
+            std::time::Duration::from_millis(500),
+        );
         let task_id = task_queue.add_task(task);
 
         let poll_result = task_queue.poll_task(task_id).unwrap();
@@ -154,3 +190,843 @@ fn test_pause_and_resume() {
         assert_eq!(poll_result, PollResult::Completed);
     });
 }
+
+#[test]
+fn test_cancelled_task_recorded_in_history() {
+    let task_queue = TaskQueue::new();
+    let task = crate::app::sleep_task::SleepTask::new(
+        Some(TaskId::detached(0)),
+        std::time::Duration::from_millis(100),
+    );
+    let task_id = task_queue.add_task(task);
+    assert!(task_queue.history().is_empty());
+
+    task_queue.poll_task(task_id).unwrap();
+    task_queue.remove_task(task_id).unwrap();
+
+    let history = task_queue.history();
+    assert_eq!(history.len(), 1);
+    assert_eq!(history[0].id, task_id);
+    assert_eq!(history[0].kind, "sleep");
+    assert_eq!(history[0].status, TaskStatus::Cancelled);
+    assert!(history[0].started_at.is_some());
+    assert!(history[0].finished_at.is_some());
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+#[test]
+fn test_subscribe_receives_transitions() {
+    let task_queue = TaskQueue::new();
+    let events = task_queue.subscribe();
+    let task = crate::app::sleep_task::SleepTask::new(
+        Some(TaskId::detached(0)),
+        std::time::Duration::from_millis(100),
+    );
+    let task_id = task_queue.add_task(task);
+    task_queue.poll_task(task_id).unwrap();
+    task_queue.remove_task(task_id).unwrap();
+
+    let statuses: Vec<TaskStatus> = events.try_iter().map(|record| record.status).collect();
+    assert_eq!(
+        statuses,
+        vec![
+            TaskStatus::Queued,
+            TaskStatus::Running,
+            TaskStatus::Cancelled
+        ]
+    );
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+#[test]
+fn test_export_history_jsonl() {
+    let task_queue = TaskQueue::new();
+    for _ in 0..2 {
+        let task = crate::app::sleep_task::SleepTask::new(None, std::time::Duration::from_secs(60));
+        let task_id = task_queue.add_task(task);
+        task_queue.remove_task(task_id).unwrap();
+    }
+    let path = std::env::temp_dir().join(format!("history_export_{}.jsonl", std::process::id()));
+    assert_eq!(task_queue.export_history_jsonl(&path).unwrap(), 2);
+
+    let exported = std::fs::read_to_string(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    let records: Vec<crate::app::history::TaskRecord> = exported
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+    // Read back, the ids no longer name this queue's tasks.
+    let history: Vec<crate::app::history::TaskRecord> = task_queue
+        .history()
+        .into_iter()
+        .map(|record| crate::app::history::TaskRecord {
+            id: TaskId::detached(record.id.index()),
+            ..record
+        })
+        .collect();
+    assert_eq!(records, history);
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+#[test]
+fn test_export_history_jsonl_includes_audit_of_spilled_records() {
+    use crate::app::audit::{AuditAction, Interface, Origin};
+
+    let spill = std::env::temp_dir().join(format!("export_spill_{}.jsonl", std::process::id()));
+    let task_queue = TaskQueue::new();
+    task_queue.set_history_file(crate::app::history_spill::HistoryFile::create(&spill).unwrap());
+    task_queue.set_history_limit(Some(4));
+    let origin = Origin::new(Interface::ControlPipe, "tester");
+    for _ in 0..10 {
+        let task = crate::app::sleep_task::SleepTask::new(None, std::time::Duration::from_secs(60));
+        let task_id = task_queue.add_task(task);
+        task_queue.audit(task_id, AuditAction::Add, &origin);
+        task_queue.remove_task(task_id).unwrap();
+    }
+    assert!(task_queue.history().len() <= 4);
+
+    let path = std::env::temp_dir().join(format!("export_audit_{}.jsonl", std::process::id()));
+    assert_eq!(task_queue.export_history_jsonl(&path).unwrap(), 10);
+    let exported = std::fs::read_to_string(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    for line in exported.lines() {
+        let line: serde_json::Value = serde_json::from_str(line).unwrap();
+        let audit = line["audit"].as_array().unwrap();
+        assert_eq!(audit.len(), 1);
+        assert_eq!(audit[0]["task_id"], line["id"]);
+    }
+}
+
+/// Completes on its first poll, producing one file.
+#[cfg(test)]
+struct ArtifactTask {
+    id: Option<TaskId>,
+}
+
+#[cfg(test)]
+impl crate::app::task_queue::Task for ArtifactTask {
+    fn id(&self) -> Result<TaskId, TaskError> {
+        self.id.ok_or(TaskError::IdUsizeIsNone)
+    }
+
+    fn set_id(&mut self, id: TaskId) {
+        self.id = Some(id);
+    }
+
+    fn poll(&mut self) -> PollResult {
+        PollResult::Completed
+    }
+
+    fn cancel(&mut self) -> Result<(), TaskError> {
+        Err(TaskError::AlreadyCompleted)
+    }
+
+    fn pause(&mut self) -> Result<(), TaskError> {
+        Err(TaskError::AlreadyCompleted)
+    }
+
+    fn resume(&mut self) -> Result<(), TaskError> {
+        Err(TaskError::AlreadyCompleted)
+    }
+
+    fn kind(&self) -> crate::app::task_queue::TaskKind {
+        crate::app::task_queue::TaskKind::Sleep
+    }
+
+    fn artifacts(&self) -> Vec<crate::app::history::Artifact> {
+        vec![crate::app::history::Artifact::File {
+            path: "result.txt".into(),
+        }]
+    }
+}
+
+#[test]
+fn test_completed_task_records_artifacts() {
+    let task_queue = TaskQueue::new();
+    let task_id = task_queue.add_task(ArtifactTask { id: None });
+    assert_eq!(task_queue.poll_task(task_id), Ok(PollResult::Completed));
+    assert_eq!(task_queue.poll_task(task_id), Ok(PollResult::Completed));
+    let history = task_queue.history();
+    assert_eq!(history.len(), 1);
+    assert_eq!(
+        history[0].artifacts,
+        vec![crate::app::history::Artifact::File {
+            path: "result.txt".into()
+        }]
+    );
+}
+
+/// Blocks inside `poll` until `release` is set, or five seconds pass.
+#[cfg(all(test, not(target_arch = "wasm32")))]
+struct BlockingPollTask {
+    id: Option<TaskId>,
+    polling: std::sync::Arc<std::sync::atomic::AtomicBool>,
+    release: std::sync::Arc<std::sync::atomic::AtomicBool>,
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+impl crate::app::task_queue::Task for BlockingPollTask {
+    fn id(&self) -> Result<TaskId, TaskError> {
+        self.id.ok_or(TaskError::IdUsizeIsNone)
+    }
+
+    fn set_id(&mut self, id: TaskId) {
+        self.id = Some(id);
+    }
+
+    fn poll(&mut self) -> PollResult {
+        use std::sync::atomic::Ordering;
+        self.polling.store(true, Ordering::SeqCst);
+        let start = std::time::Instant::now();
+        while !self.release.load(Ordering::SeqCst) && start.elapsed().as_secs() < 5 {
+            std::thread::sleep(std::time::Duration::from_millis(5));
+        }
+        PollResult::Completed
+    }
+
+    fn cancel(&mut self) -> Result<(), TaskError> {
+        Ok(())
+    }
+
+    fn pause(&mut self) -> Result<(), TaskError> {
+        Ok(())
+    }
+
+    fn resume(&mut self) -> Result<(), TaskError> {
+        Ok(())
+    }
+
+    fn kind(&self) -> crate::app::task_queue::TaskKind {
+        crate::app::task_queue::TaskKind::Sleep
+    }
+
+    fn message(&self) -> Option<String> {
+        Some("blocking".to_owned())
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+#[test]
+fn test_slow_poll_does_not_block_other_tasks() {
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::Arc;
+
+    let task_queue = Arc::new(TaskQueue::new());
+    let polling = Arc::new(AtomicBool::new(false));
+    let release = Arc::new(AtomicBool::new(false));
+    let slow_id = task_queue.add_task(BlockingPollTask {
+        id: None,
+        polling: polling.clone(),
+        release: release.clone(),
+    });
+    let poller = {
+        let task_queue = task_queue.clone();
+        std::thread::spawn(move || task_queue.poll_task(slow_id))
+    };
+    while !polling.load(Ordering::SeqCst) {
+        std::thread::yield_now();
+    }
+
+    let start = std::time::Instant::now();
+    let task = crate::app::sleep_task::SleepTask::new(None, std::time::Duration::from_secs(60));
+    let task_id = task_queue.add_task(task);
+    task_queue.poll_task(task_id).unwrap();
+    task_queue.remove_task(task_id).unwrap();
+    assert_eq!(task_queue.records().len(), 2);
+    assert!(start.elapsed() < std::time::Duration::from_secs(2));
+
+    let cached = task_queue.try_poll_task(slow_id);
+    assert_eq!(cached, Ok(PollResult::Pending(PollingData::Float(0.0))));
+    // The detail of a busy task comes without its message rather than waiting.
+    assert_eq!(task_queue.task_detail(slow_id).unwrap().message, None);
+
+    release.store(true, Ordering::SeqCst);
+    assert_eq!(poller.join().unwrap(), Ok(PollResult::Completed));
+    assert_eq!(task_queue.try_poll_task(slow_id), Ok(PollResult::Completed));
+    let detail = task_queue.task_detail(slow_id).unwrap();
+    assert_eq!(detail.record.status, TaskStatus::Completed);
+    assert_eq!(detail.progress, PollResult::Completed);
+    assert_eq!(detail.message.as_deref(), Some("blocking"));
+}
+
+#[test]
+fn test_progress_follows_transitions() {
+    let task_queue = TaskQueue::new();
+    let task = crate::app::sleep_task::SleepTask::new(None, std::time::Duration::from_secs(60));
+    let task_id = task_queue.add_task(task);
+    assert_eq!(
+        task_queue.progress(task_id),
+        Ok(PollResult::Pending(PollingData::Float(0.0)))
+    );
+
+    task_queue.poll_task(task_id).unwrap();
+    task_queue.pause_task(task_id).unwrap();
+    assert!(matches!(
+        task_queue.progress(task_id),
+        Ok(PollResult::Paused(_))
+    ));
+    task_queue.remove_task(task_id).unwrap();
+    assert_eq!(task_queue.progress(task_id), Ok(PollResult::Cancelled));
+    assert_eq!(
+        task_queue.progress(task_queue.task_id(task_id.index() + 1)),
+        Err(TaskError::NotFound)
+    );
+}
+
+#[cfg(feature = "ui")]
+#[test]
+fn test_changes_request_repaint() {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    let repaints = Arc::new(AtomicUsize::new(0));
+    let ctx = egui::Context::default();
+    {
+        let repaints = repaints.clone();
+        ctx.set_request_repaint_callback(move |_| {
+            repaints.fetch_add(1, Ordering::SeqCst);
+        });
+    }
+    let task_queue = TaskQueue::new();
+    task_queue.set_on_change(move || ctx.request_repaint());
+    assert_eq!(repaints.load(Ordering::SeqCst), 0);
+
+    let task = crate::app::sleep_task::SleepTask::new(None, std::time::Duration::from_secs(60));
+    std::thread::spawn(move || task_queue.add_task(task))
+        .join()
+        .unwrap();
+    assert!(repaints.load(Ordering::SeqCst) > 0);
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+#[test]
+fn test_history_spills_to_file_and_pages_back() {
+    let path = std::env::temp_dir().join(format!("history_spill_{}.jsonl", std::process::id()));
+    let task_queue = TaskQueue::new();
+    task_queue.set_history_file(crate::app::history_spill::HistoryFile::create(&path).unwrap());
+    task_queue.set_history_limit(Some(4));
+    for _ in 0..10 {
+        let task = crate::app::sleep_task::SleepTask::new(None, std::time::Duration::from_secs(60));
+        let task_id = task_queue.add_task(task);
+        task_queue.remove_task(task_id).unwrap();
+    }
+    assert!(task_queue.history().len() <= 4);
+
+    let ids = |records: Vec<crate::app::history::TaskRecord>| -> Vec<usize> {
+        records.iter().map(|record| record.id.index()).collect()
+    };
+    assert_eq!(
+        ids(task_queue.history_page(0, 100)),
+        (0..10).collect::<Vec<_>>()
+    );
+    // Straddles the spill file and memory.
+    assert_eq!(ids(task_queue.history_page(3, 4)), vec![3, 4, 5, 6]);
+    assert_eq!(ids(task_queue.history_page(8, 4)), vec![0, 1]);
+    assert!(task_queue.history_page(10, 4).is_empty());
+
+    drop(task_queue);
+    assert!(!path.exists());
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+#[test]
+fn test_history_retention_limits_memory_and_spill_file() {
+    use crate::app::history::{HistoryRetention, TaskRecord};
+
+    let path = std::env::temp_dir().join(format!("history_retention_{}.jsonl", std::process::id()));
+    let task_queue = TaskQueue::new();
+    task_queue.set_history_file(crate::app::history_spill::HistoryFile::create(&path).unwrap());
+    task_queue.set_history_limit(Some(4));
+    let day = 24 * 60 * 60 * 1000;
+    let now = crate::app::history::now_millis();
+    task_queue.restore_history(
+        (0..10)
+            .map(|id| TaskRecord {
+                status: TaskStatus::Completed,
+                finished_at: Some(now - (10 - id as u64) * day),
+                ..TaskRecord::new(TaskId::detached(id), "sleep")
+            })
+            .collect(),
+    );
+    let ids = |records: Vec<TaskRecord>| -> Vec<usize> {
+        records.iter().map(|record| record.id.index()).collect()
+    };
+
+    task_queue.set_history_retention(HistoryRetention {
+        max_age: Some(std::time::Duration::from_millis(6 * day + 1)),
+        ..HistoryRetention::default()
+    });
+    assert_eq!(ids(task_queue.history_page(0, 100)), vec![4, 5, 6, 7, 8, 9]);
+
+    task_queue.set_history_retention(HistoryRetention {
+        max_count: Some(2),
+        ..HistoryRetention::default()
+    });
+    assert_eq!(ids(task_queue.history_page(0, 100)), vec![8, 9]);
+
+    task_queue.clear_history().unwrap();
+    assert!(task_queue.history_page(0, 100).is_empty());
+}
+
+#[test]
+fn test_history_retention_leaves_params_out_on_disk() {
+    use crate::app::history::{Artifact, HistoryRetention, TaskRecord};
+
+    let mut record = TaskRecord::new(TaskId::detached(1), "download");
+    record.artifacts = vec![Artifact::Url {
+        url: "https://example.com/private".to_owned(),
+    }];
+    record
+        .annotations
+        .insert("commit".to_owned(), "4f1a9c2".to_owned());
+    assert_eq!(
+        *HistoryRetention::default().for_disk(&record),
+        record.clone()
+    );
+    let private = HistoryRetention {
+        exclude_params: true,
+        ..HistoryRetention::default()
+    };
+    let saved = private.for_disk(&record);
+    assert!(saved.artifacts.is_empty() && saved.annotations.is_empty());
+    assert_eq!(saved.kind, "download");
+}
+
+#[test]
+fn test_batched_add_and_cancel() {
+    let task_queue = TaskQueue::new();
+    task_queue.add_task(crate::app::stress::StressTask::new(1));
+    let ids = crate::app::stress::enqueue(&task_queue, 3);
+    assert_eq!(ids, [1, 2, 3].map(|index| task_queue.task_id(index)));
+    let new_ids: Vec<usize> = task_queue
+        .records_from(2)
+        .iter()
+        .map(|record| record.id.index())
+        .collect();
+    assert_eq!(new_ids, vec![2, 3]);
+    assert!(task_queue.records_from(ids[2].index() + 5).is_empty());
+
+    let results = task_queue.remove_tasks(&[1, 3, 7].map(|index| task_queue.task_id(index)));
+    assert_eq!(results, vec![Ok(()), Ok(()), Err(TaskError::NotFound)]);
+    assert_eq!(task_queue.poll_task(ids[2]).unwrap(), PollResult::Cancelled);
+    assert_eq!(task_queue.history().len(), 2);
+}
+
+#[test]
+fn test_batched_polls() {
+    let task_queue = TaskQueue::new();
+    let ids = crate::app::stress::enqueue(&task_queue, 3);
+    task_queue.remove_task(ids[1]).unwrap();
+
+    let results = task_queue.poll_many(&[ids[2], task_queue.task_id(9), ids[0]]);
+    assert!(matches!(results[0], Ok(PollResult::Pending(_))));
+    assert_eq!(results[1], Err(TaskError::NotFound));
+    assert!(matches!(results[2], Ok(PollResult::Pending(_))));
+    assert_eq!(task_queue.status(ids[0]), Ok(TaskStatus::Running));
+
+    let all = task_queue.poll_all();
+    let polled: Vec<TaskId> = all.iter().map(|(id, _)| *id).collect();
+    assert_eq!(polled, ids);
+    assert_eq!(all[1].1, PollResult::Cancelled);
+    task_queue.remove_tasks(&ids);
+}
+
+#[test]
+fn test_retain_ids_drops_only_finished_unheld_tasks() {
+    let task_queue = TaskQueue::new();
+    let ids = crate::app::stress::enqueue(&task_queue, 5);
+    task_queue.remove_tasks(&ids[..3]);
+    let group = task_queue.create_group(&ids[2..3]).unwrap();
+
+    // Task 0 is still held, task 2 is in a group, and tasks 3 and 4 are unfinished.
+    assert_eq!(task_queue.retain_ids(&ids[..1]), 1);
+    assert_eq!(task_queue.status(ids[1]), Err(TaskError::NotFound));
+    assert_eq!(task_queue.status(ids[0]), Ok(TaskStatus::Cancelled));
+    assert_eq!(task_queue.group_progress(group), Ok(PollResult::Cancelled));
+    assert_eq!(task_queue.retain_ids(&[]), 1);
+    assert_eq!(task_queue.records().len(), 3);
+    assert_eq!(task_queue.history().len(), 3);
+
+    // Ids are not handed out again.
+    let next = task_queue.add_task(crate::app::stress::StressTask::new(1));
+    assert_eq!(next.index(), 5);
+    task_queue.remove_tasks(&ids);
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+#[test]
+fn test_progress_granularity() {
+    use crate::app::task_queue::ProgressGranularity;
+
+    let task_queue = TaskQueue::new();
+    task_queue.set_progress_granularity(ProgressGranularity {
+        min_delta: 0.09,
+        min_interval: std::time::Duration::ZERO,
+    });
+    let progress = task_queue.subscribe_progress();
+    // Advances by 0.025 a poll.
+    let task_id = task_queue.add_task(crate::app::stress::StressTask::new(40));
+    for _ in 0..20 {
+        task_queue.poll_task(task_id).unwrap();
+    }
+    let reported: Vec<f32> = progress.try_iter().map(|event| event.progress).collect();
+    assert_eq!(reported, vec![0.025, 0.125, 0.225, 0.325, 0.425]);
+
+    task_queue
+        .set_task_progress_granularity(task_id, Some(ProgressGranularity::default()))
+        .unwrap();
+    task_queue.poll_task(task_id).unwrap();
+    task_queue.poll_task(task_id).unwrap();
+    assert_eq!(progress.try_iter().count(), 2);
+    assert_eq!(
+        task_queue.set_task_progress_granularity(task_queue.task_id(task_id.index() + 1), None),
+        Err(TaskError::NotFound)
+    );
+}
+
+#[test]
+fn test_wait_idle() {
+    let task_queue = std::sync::Arc::new(TaskQueue::new());
+    assert!(task_queue.is_idle());
+    let ids = task_queue.add_tasks((0..2).map(|_| {
+        crate::app::sleep_task::SleepTask::new(None, std::time::Duration::from_millis(50))
+    }));
+    let paused = task_queue.add_task(crate::app::sleep_task::SleepTask::new(
+        None,
+        std::time::Duration::from_secs(60),
+    ));
+    task_queue.pause_task(paused).unwrap();
+    assert!(!task_queue.is_idle());
+
+    let driver = {
+        let task_queue = task_queue.clone();
+        std::thread::spawn(move || {
+            while !task_queue.is_idle() {
+                for id in &ids {
+                    task_queue.poll_task(*id).unwrap();
+                }
+                std::thread::sleep(std::time::Duration::from_millis(10));
+            }
+        })
+    };
+    let waited = async_std::task::block_on(async_std::future::timeout(
+        std::time::Duration::from_secs(2),
+        task_queue.wait_idle(),
+    ));
+    assert!(waited.is_ok(), "Queue did not become idle in time");
+    // The paused task does not keep the queue busy.
+    assert!(task_queue.is_idle());
+    assert!(matches!(
+        task_queue.progress(paused),
+        Ok(PollResult::Paused(_))
+    ));
+    driver.join().unwrap();
+    // Already idle: resolves right away.
+    async_std::task::block_on(task_queue.wait_idle());
+}
+
+#[test]
+fn test_task_times_out() {
+    let task_queue = TaskQueue::new();
+    let timeout = std::time::Duration::from_millis(20);
+    let task_id = task_queue.add_task_with_timeout(
+        crate::app::sleep_task::SleepTask::new(None, std::time::Duration::from_secs(60)),
+        timeout,
+    );
+    let untimed = task_queue.add_task(crate::app::sleep_task::SleepTask::new(
+        None,
+        std::time::Duration::from_secs(60),
+    ));
+    assert!(task_queue.poll_task(task_id).is_ok());
+    task_queue.poll_task(untimed).unwrap();
+    std::thread::sleep(std::time::Duration::from_millis(50));
+    assert_eq!(
+        task_queue.poll_task(task_id),
+        Err(TaskError::TimedOut { timeout })
+    );
+    assert!(task_queue.poll_task(untimed).is_ok());
+    assert_eq!(task_queue.status(task_id), Ok(TaskStatus::Failed));
+    let record = task_queue.history().pop().unwrap();
+    assert!(record.timed_out);
+    assert!(record.error.is_some());
+    assert_eq!(record.status_label(), "Timed out");
+    task_queue.remove_task(untimed).unwrap();
+}
+
+#[test]
+fn test_scheduled_task_waits_for_its_start() {
+    let task_queue = TaskQueue::new();
+    let task_id = task_queue.schedule_task(
+        crate::app::sleep_task::SleepTask::new(None, std::time::Duration::from_secs(60)),
+        crate::app::executor::Instant::now() + std::time::Duration::from_millis(50),
+    );
+    assert!(task_queue.poll_task(task_id).is_ok());
+    assert_eq!(task_queue.status(task_id), Ok(TaskStatus::Scheduled));
+    assert!(task_queue.scheduled_start(task_id).unwrap().is_some());
+    assert!(!task_queue.is_idle());
+    std::thread::sleep(std::time::Duration::from_millis(80));
+    assert!(task_queue.poll_task(task_id).is_ok());
+    assert_eq!(task_queue.status(task_id), Ok(TaskStatus::Running));
+    assert_eq!(task_queue.scheduled_start(task_id), Ok(None));
+    task_queue.remove_task(task_id).unwrap();
+}
+
+#[test]
+fn test_pause_all_holds_the_whole_queue() {
+    let task_queue = TaskQueue::new();
+    let sleep = || crate::app::sleep_task::SleepTask::new(None, std::time::Duration::from_secs(60));
+    let running = task_queue.add_task(sleep());
+    let paused_alone = task_queue.add_task(sleep());
+    for &task_id in &[running, paused_alone] {
+        assert!(task_queue.poll_task(task_id).is_ok());
+    }
+    task_queue.pause_task(paused_alone).unwrap();
+
+    assert_eq!(task_queue.pause_all(), vec![running]);
+    assert!(task_queue.is_paused());
+    let added_meanwhile = task_queue.add_task(sleep());
+    assert!(task_queue.poll_task(added_meanwhile).is_ok());
+    assert_eq!(task_queue.status(running), Ok(TaskStatus::Paused));
+    assert_eq!(task_queue.status(added_meanwhile), Ok(TaskStatus::Queued));
+
+    assert_eq!(task_queue.resume_all(), vec![running]);
+    assert!(!task_queue.is_paused());
+    assert!(task_queue.poll_task(added_meanwhile).is_ok());
+    assert_eq!(task_queue.status(running), Ok(TaskStatus::Running));
+    assert_eq!(task_queue.status(paused_alone), Ok(TaskStatus::Paused));
+    assert_eq!(task_queue.status(added_meanwhile), Ok(TaskStatus::Running));
+    task_queue.remove_tasks(&[running, paused_alone, added_meanwhile]);
+}
+
+#[test]
+fn test_recurring_task_runs_again() {
+    let task_queue = TaskQueue::new();
+    let every = std::time::Duration::from_millis(50);
+    let recurrence = task_queue.add_recurring_task(
+        || crate::app::sleep_task::SleepTask::new(None, std::time::Duration::from_millis(1)),
+        every,
+    );
+    let first = task_queue.recurrence(recurrence).unwrap();
+    assert_eq!(task_queue.recurrence_of(first.run), Some(recurrence));
+    while task_queue.poll_task(first.run) != Ok(PollResult::Completed) {
+        std::thread::sleep(std::time::Duration::from_millis(1));
+    }
+    let second = task_queue.recurrence(recurrence).unwrap();
+    assert_ne!(second.run, first.run);
+    assert_eq!(task_queue.status(second.run), Ok(TaskStatus::Scheduled));
+    assert!(second.next_fire >= first.next_fire);
+    assert_eq!(task_queue.recurrence_of(first.run), None);
+
+    task_queue.stop_recurrence(recurrence).unwrap();
+    assert_eq!(task_queue.status(second.run), Ok(TaskStatus::Cancelled));
+    assert_eq!(task_queue.recurrence(recurrence), Err(TaskError::NotFound));
+    assert_eq!(
+        task_queue.stop_recurrence(recurrence),
+        Err(TaskError::NotFound)
+    );
+}
+
+#[test]
+fn test_task_meta_kept_in_record() {
+    let task_queue = TaskQueue::new();
+    let meta = crate::app::task_queue::TaskMeta {
+        created_at: 1_700_000_000_000,
+        ..crate::app::task_queue::TaskMeta::new(
+            Some("Nightly backup".to_owned()),
+            vec!["backup".to_owned()],
+        )
+    };
+    let task_id = task_queue.add_task_with_meta(
+        crate::app::sleep_task::SleepTask::new(None, std::time::Duration::from_secs(60)),
+        meta.clone(),
+    );
+    assert_eq!(task_queue.task_meta(task_id), Ok(meta));
+    task_queue.set_label(task_id, None).unwrap();
+    task_queue.remove_task(task_id).unwrap();
+    let record = task_queue.history().pop().unwrap();
+    assert_eq!(record.label, None);
+    assert_eq!(record.tags, vec!["backup".to_owned()]);
+    assert_eq!(record.created_at, 1_700_000_000_000);
+    assert_eq!(record.title(), format!("Task {}", task_id));
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+#[test]
+fn test_lifecycle_events() {
+    use crate::app::task_queue::TaskEvent;
+
+    let task_queue = TaskQueue::new();
+    let events = task_queue.subscribe_events();
+    let task_id = task_queue.add_task(crate::app::sleep_task::SleepTask::new(
+        None,
+        std::time::Duration::from_secs(60),
+    ));
+    task_queue.poll_task(task_id).unwrap();
+    task_queue.pause_task(task_id).unwrap();
+    task_queue.resume_task(task_id).unwrap();
+    task_queue.remove_task(task_id).unwrap();
+    let timeout = std::time::Duration::from_millis(1);
+    let timed = task_queue.add_task_with_timeout(
+        crate::app::sleep_task::SleepTask::new(None, std::time::Duration::from_secs(60)),
+        timeout,
+    );
+    task_queue.poll_task(timed).unwrap();
+    std::thread::sleep(std::time::Duration::from_millis(10));
+    assert!(task_queue.poll_task(timed).is_err());
+
+    let events: Vec<TaskEvent> = events
+        .try_iter()
+        .filter(|event| !matches!(event, TaskEvent::Progress { .. }))
+        .collect();
+    assert_eq!(
+        events,
+        vec![
+            TaskEvent::Started { id: task_id },
+            TaskEvent::Paused { id: task_id },
+            TaskEvent::Resumed { id: task_id },
+            TaskEvent::Cancelled { id: task_id },
+            TaskEvent::Started { id: timed },
+            TaskEvent::Failed {
+                id: timed,
+                error: TaskError::TimedOut { timeout }
+            },
+        ]
+    );
+}
+
+#[test]
+fn test_polling_data_fraction() {
+    assert_eq!(PollingData::Float(0.25).fraction(), 0.25);
+    let bytes = |done, total| PollingData::Bytes { done, total };
+    assert_eq!(bytes(512, Some(2048)).fraction(), 0.25);
+    assert_eq!(bytes(512, None).fraction(), 0.0);
+    assert_eq!(bytes(0, Some(0)).fraction(), 1.0);
+    let steps = PollingData::Steps {
+        current: 3,
+        total: 4,
+    };
+    assert_eq!(steps.fraction(), 0.75);
+    assert_eq!(steps.to_string(), "step 3 of 4");
+}
+
+/// Fails on its first poll, counting how many copies of it did.
+#[cfg(test)]
+#[derive(Clone)]
+struct FailingTask {
+    id: Option<TaskId>,
+    attempts: std::sync::Arc<std::sync::atomic::AtomicUsize>,
+}
+
+#[cfg(test)]
+impl crate::app::task_queue::Task for FailingTask {
+    fn id(&self) -> Result<TaskId, TaskError> {
+        self.id.ok_or(TaskError::IdUsizeIsNone)
+    }
+
+    fn set_id(&mut self, id: TaskId) {
+        self.id = Some(id);
+    }
+
+    fn poll(&mut self) -> PollResult {
+        self.attempts
+            .fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+        PollResult::Failed(TaskError::Failed {
+            reason: "flaky".to_owned(),
+        })
+    }
+
+    fn cancel(&mut self) -> Result<(), TaskError> {
+        Err(TaskError::AlreadyCancelled)
+    }
+
+    fn pause(&mut self) -> Result<(), TaskError> {
+        Err(TaskError::AlreadyCancelled)
+    }
+
+    fn resume(&mut self) -> Result<(), TaskError> {
+        Err(TaskError::AlreadyCancelled)
+    }
+
+    fn kind(&self) -> crate::app::task_queue::TaskKind {
+        crate::app::task_queue::TaskKind::Sleep
+    }
+}
+
+#[test]
+fn test_built_task_keeps_its_options_and_retries() {
+    use crate::app::priority::Priority;
+
+    let task_queue = TaskQueue::new();
+    let attempts = std::sync::Arc::new(std::sync::atomic::AtomicUsize::new(0));
+    let first = task_queue
+        .build_task(FailingTask {
+            id: None,
+            attempts: attempts.clone(),
+        })
+        .label("warmup")
+        .tag("nightly")
+        .priority(Priority::High)
+        .timeout(std::time::Duration::from_secs(30))
+        .retries(2)
+        .submit();
+    let mut id = first;
+    for _ in 0..3 {
+        let record = task_queue.task_detail(id).unwrap().record;
+        assert_eq!(record.label.as_deref(), Some("warmup"));
+        assert_eq!(record.tags, ["nightly"]);
+        assert_eq!(record.priority, Priority::High);
+        task_queue.poll_task(id).unwrap();
+        assert_eq!(task_queue.status(id), Ok(TaskStatus::Failed));
+        id = task_queue.task_id(id.index() + 1);
+    }
+    // Out of retries.
+    assert_eq!(task_queue.status(id), Err(TaskError::NotFound));
+    assert_eq!(attempts.load(std::sync::atomic::Ordering::SeqCst), 3);
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+#[test]
+fn test_shutdown_cancels_tasks_and_refuses_new_ones() {
+    use crate::app::sleep_task::SleepTask;
+    use std::time::Duration;
+
+    let task_queue = TaskQueue::new();
+    let running = task_queue.add_task(SleepTask::new(None, Duration::from_secs(60)));
+    let queued = task_queue.add_task(SleepTask::new(None, Duration::from_secs(60)));
+    task_queue.poll_task(running).unwrap();
+    let started = std::time::Instant::now();
+    assert!(task_queue.shutdown(Duration::from_secs(5)));
+    assert!(started.elapsed() < Duration::from_secs(5));
+    assert_eq!(task_queue.status(running), Ok(TaskStatus::Cancelled));
+    assert_eq!(task_queue.status(queued), Ok(TaskStatus::Cancelled));
+    let written: Vec<TaskStatus> = task_queue
+        .history()
+        .into_iter()
+        .map(|record| record.status)
+        .collect();
+    assert_eq!(written, vec![TaskStatus::Cancelled, TaskStatus::Cancelled]);
+
+    let late = task_queue.add_task(SleepTask::new(None, Duration::from_secs(60)));
+    assert_eq!(task_queue.status(late), Ok(TaskStatus::Cancelled));
+    assert_eq!(
+        task_queue.try_add_task(SleepTask::new(None, Duration::from_secs(60))),
+        Err(TaskError::ShuttingDown)
+    );
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+#[test]
+fn test_recurrence_added_while_shutting_down_is_cancelled() {
+    use crate::app::sleep_task::SleepTask;
+    use std::time::Duration;
+
+    let task_queue = TaskQueue::new();
+    assert!(task_queue.shutdown(Duration::from_secs(5)));
+    // Its first run is cancelled as it is added, which looks for the next one.
+    let recurrence = task_queue.add_recurring_task(
+        || SleepTask::new(None, Duration::from_secs(60)),
+        Duration::from_secs(60),
+    );
+    let first = task_queue.recurrence(recurrence).unwrap();
+    assert_eq!(task_queue.status(first.run), Ok(TaskStatus::Cancelled));
+}
@@ -0,0 +1,142 @@
+//! Laid-out text for the rows of the task list, kept in egui memory so that a row whose
+//! status and displayed progress have not changed is drawn without formatting or laying
+//! out any text.
+
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc as sync_Arc;
+
+use egui::{Color32, Galley, TextStyle, Ui};
+
+use crate::app::format;
+use crate::app::task_id::TaskId;
+use crate::app::task_queue::PollingData;
+
+/// Progress is shown to a tenth of a percent, so a row only changes in steps of that size.
+const PROGRESS_STEPS: f32 = 1000.0;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct RowKey {
+    paused: bool,
+    progress: RowProgress,
+}
+
+/// The progress as displayed: a fraction rounded to a tenth of a percent, or the counts.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum RowProgress {
+    Permille(u16),
+    Counts(PollingData),
+}
+
+/// The text of one row, ready to hand to a label and a progress bar.
+#[derive(Clone)]
+pub struct RowText {
+    pub title: sync_Arc<Galley>,
+    pub progress: sync_Arc<Galley>,
+}
+
+/// Everything that is baked into a laid-out galley besides the text itself.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct RowStyle {
+    text_color: Color32,
+    progress_color: Color32,
+    pixels_per_point: f32,
+}
+
+#[derive(Clone, Default)]
+pub struct TaskRows {
+    style: Option<RowStyle>,
+    /// With the label each row was laid out with.
+    rows: HashMap<TaskId, (RowKey, Option<String>, RowText)>,
+}
+
+impl TaskRows {
+    fn id() -> egui::Id {
+        egui::Id::new("task_rows")
+    }
+
+    /// The text for task `id`'s row, titled `label` if it has one, laid out again only
+    /// when its label, its status, its progress as shown or the visuals changed. A
+    /// fraction is shown as a percentage to a tenth of a percent; bytes and steps as
+    /// counts.
+    pub fn text(
+        ui: &Ui,
+        id: TaskId,
+        label: Option<&str>,
+        paused: bool,
+        progress: PollingData,
+    ) -> RowText {
+        let key = RowKey {
+            paused,
+            progress: match progress {
+                PollingData::Float(p) => {
+                    RowProgress::Permille((p.clamp(0.0, 1.0) * PROGRESS_STEPS).round() as u16)
+                }
+                counts => RowProgress::Counts(counts),
+            },
+        };
+        let visuals = ui.visuals();
+        let style = RowStyle {
+            text_color: visuals.text_color(),
+            progress_color: visuals
+                .override_text_color
+                .unwrap_or(visuals.selection.stroke.color),
+            pixels_per_point: ui.ctx().pixels_per_point(),
+        };
+        let cached = ui.data_mut(|data| {
+            let rows = data.get_temp_mut_or_default::<TaskRows>(Self::id());
+            if rows.style != Some(style) {
+                rows.rows.clear();
+                rows.style = Some(style);
+            }
+            match rows.rows.get(&id) {
+                Some((cached_key, cached_label, text))
+                    if *cached_key == key && cached_label.as_deref() == label =>
+                {
+                    Some(text.clone())
+                }
+                _ => None,
+            }
+        });
+        if let Some(text) = cached {
+            return text;
+        }
+        let name = match label {
+            Some(label) => label.to_owned(),
+            None => format!("Task {}", id),
+        };
+        let title = if paused {
+            format!("{} paused", name)
+        } else {
+            name
+        };
+        let progress = match key.progress {
+            RowProgress::Permille(permille) => format::percent(permille as f32 / PROGRESS_STEPS, 1),
+            RowProgress::Counts(PollingData::Steps { current, total }) => {
+                format!("Step {} of {}", current, total)
+            }
+            RowProgress::Counts(counts) => counts.to_string(),
+        };
+        let body = TextStyle::Body.resolve(ui.style());
+        let button = TextStyle::Button.resolve(ui.style());
+        let text = ui.fonts(|fonts| RowText {
+            title: fonts.layout_no_wrap(title, body, style.text_color),
+            progress: fonts.layout_no_wrap(progress, button, style.progress_color),
+        });
+        ui.data_mut(|data| {
+            data.get_temp_mut_or_default::<TaskRows>(Self::id())
+                .rows
+                .insert(id, (key, label.map(str::to_owned), text.clone()));
+        });
+        text
+    }
+
+    /// Forgets the rows of tasks that are no longer listed.
+    pub fn forget(ctx: &egui::Context, ids: &HashSet<TaskId>) {
+        ctx.data_mut(|data| {
+            let rows = &mut data.get_temp_mut_or_default::<TaskRows>(Self::id()).rows;
+            for id in ids {
+                rows.remove(id);
+            }
+        });
+    }
+}
@@ -0,0 +1,94 @@
+#[cfg(test)]
+use crate::app::task_id::TaskId;
+#[cfg(test)]
+use crate::app::task_queue::PollingData;
+#[cfg(test)]
+use crate::app::task_rows::{RowText, TaskRows};
+
+#[cfg(test)]
+fn row_text(ctx: &egui::Context, id: usize, paused: bool, progress: f32) -> RowText {
+    labelled_row_text(ctx, id, None, paused, PollingData::Float(progress))
+}
+
+#[cfg(test)]
+fn labelled_row_text(
+    ctx: &egui::Context,
+    id: usize,
+    label: Option<&str>,
+    paused: bool,
+    progress: PollingData,
+) -> RowText {
+    let mut text = None;
+    let _ = ctx.run(egui::RawInput::default(), |ctx| {
+        egui::CentralPanel::default().show(ctx, |ui| {
+            text = Some(TaskRows::text(
+                ui,
+                TaskId::detached(id),
+                label,
+                paused,
+                progress,
+            ));
+        });
+    });
+    text.unwrap()
+}
+
+#[test]
+fn test_unchanged_row_reuses_layout() {
+    let ctx = egui::Context::default();
+    let first = row_text(&ctx, 3, false, 0.4201);
+    let second = row_text(&ctx, 3, false, 0.4204);
+    assert!(std::sync::Arc::ptr_eq(&first.title, &second.title));
+    assert!(std::sync::Arc::ptr_eq(&first.progress, &second.progress));
+    assert_eq!(first.progress.text(), "42.0%");
+}
+
+#[test]
+fn test_changed_row_is_laid_out_again() {
+    let ctx = egui::Context::default();
+    let running = row_text(&ctx, 3, false, 0.42);
+    let advanced = row_text(&ctx, 3, false, 0.43);
+    assert_eq!(advanced.progress.text(), "43.0%");
+    let paused = row_text(&ctx, 3, true, 0.43);
+    assert_eq!(paused.title.text(), "Task 3 paused");
+    assert!(!std::sync::Arc::ptr_eq(&running.title, &paused.title));
+
+    TaskRows::forget(
+        &ctx,
+        &std::collections::HashSet::from([TaskId::detached(3)]),
+    );
+    let relisted = row_text(&ctx, 3, true, 0.43);
+    assert_eq!(relisted.progress.text(), "43.0%");
+}
+
+#[test]
+fn test_labelled_row() {
+    let ctx = egui::Context::default();
+    let unlabelled = row_text(&ctx, 3, true, 0.5);
+    let labelled = labelled_row_text(
+        &ctx,
+        3,
+        Some("Nightly backup"),
+        true,
+        PollingData::Float(0.5),
+    );
+    assert_eq!(labelled.title.text(), "Nightly backup paused");
+    assert!(!std::sync::Arc::ptr_eq(&unlabelled.title, &labelled.title));
+}
+
+#[test]
+fn test_counted_progress() {
+    let ctx = egui::Context::default();
+    let steps = PollingData::Steps {
+        current: 3,
+        total: 10,
+    };
+    let stepped = labelled_row_text(&ctx, 3, None, false, steps);
+    assert_eq!(stepped.progress.text(), "Step 3 of 10");
+    let bytes = PollingData::Bytes {
+        done: 512,
+        total: None,
+    };
+    let downloading = labelled_row_text(&ctx, 3, None, false, bytes);
+    assert_eq!(downloading.progress.text(), "512 bytes");
+}
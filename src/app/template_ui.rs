@@ -1,85 +1,4698 @@
+#[cfg(target_arch = "wasm32")]
+use std::collections::BTreeMap;
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::Arc as sync_Arc;
 use std::time::Duration;
+#[cfg(not(target_arch = "wasm32"))]
+use std::time::Instant;
 
+use crate::app::a11y;
+use crate::app::artifacts;
+#[cfg(not(target_arch = "wasm32"))]
+use crate::app::assets::Assets;
+use crate::app::audit::{AuditAction, AuditEntry, Interface, Origin};
+use crate::app::color_tag::ColorTag;
+#[cfg(not(target_arch = "wasm32"))]
+use crate::app::config::WatchRule;
+use crate::app::config::{open_config_file, AppConfig, ConfigWatcher, StoreBackend};
+#[cfg(not(target_arch = "wasm32"))]
+use crate::app::csv_import::{auto_mapping, ColumnMapping, CsvTable};
+#[cfg(not(target_arch = "wasm32"))]
+use crate::app::deadline::DeadlineAtRisk;
+#[cfg(not(target_arch = "wasm32"))]
+use crate::app::disk_space;
+#[cfg(not(target_arch = "wasm32"))]
+use crate::app::executor;
+use crate::app::format;
+#[cfg(not(target_arch = "wasm32"))]
+use crate::app::governor::{system_load, Governor, SystemLoad};
+use crate::app::history::{now_millis, Artifact, TaskRecord};
+#[cfg(not(target_arch = "wasm32"))]
+use crate::app::history_spill::HistoryFile;
+#[cfg(not(target_arch = "wasm32"))]
+use crate::app::job_file::{JobFile, JobFileError, JOB_FILE_EXTENSION};
+#[cfg(not(target_arch = "wasm32"))]
+use crate::app::journal::{InterruptedTask, Journal, JournalEntry};
+#[cfg(not(target_arch = "wasm32"))]
+use crate::app::launch_args::{LaunchArgs, TaskSpec};
+use crate::app::layout::{Density, Layout};
+#[cfg(not(target_arch = "wasm32"))]
+#[cfg(not(target_arch = "wasm32"))]
+use crate::app::metered::{network_cost, MeteredGuard, NetworkCost};
+#[cfg(not(target_arch = "wasm32"))]
+use crate::app::post_batch::{
+    BatchSummary, PendingBatches, PostBatchAction, POWER_COUNTDOWN_SECONDS,
+};
+use crate::app::power::{power_source, BatteryGuard, KeepAwake, PowerSource};
+use crate::app::priority::Priority;
+#[cfg(feature = "profiling")]
+use crate::app::profiler::Profiler;
+use crate::app::profiler::{profile_function, profile_scope};
+use crate::app::progress_estimate::ProgressEstimate;
+use crate::app::registry::{
+    default_params, env_pairs, DedupeKey, ParamType, RegistryError, TaskKindRegistry, TaskParams,
+};
+#[cfg(all(feature = "secrets", not(target_arch = "wasm32")))]
+use crate::app::secrets::SecretStore;
+#[cfg(not(target_arch = "wasm32"))]
+use crate::app::session::{
+    self, Session, SessionError, SessionLayout, SessionTask, SESSION_FILE_EXTENSION,
+    SESSION_VERSION,
+};
+#[cfg(not(target_arch = "wasm32"))]
+use crate::app::single_instance::InstanceServer;
 use crate::app::sleep_task::SleepTask;
-use crate::app::task_queue::{PollResult, PollingData, TaskQueue};
+use crate::app::stress::{self, STRESS_TASK_COUNT};
+use crate::app::task_group::TaskGroup;
+use crate::app::task_id::TaskId;
+#[cfg(not(target_arch = "wasm32"))]
+use crate::app::task_queue::TaskStatus;
+use crate::app::task_queue::{PollResult, PollingData, QueueStats, TaskError, TaskQueue};
+use crate::app::task_rows::TaskRows;
+#[cfg(not(target_arch = "wasm32"))]
+use crate::app::trace_export::TraceRecorder;
+#[cfg(all(feature = "self-update", not(target_arch = "wasm32")))]
+use crate::app::updater::{self, Release};
+#[cfg(not(target_arch = "wasm32"))]
+use crate::app::warm_start::{self, WarmStartReport};
+#[cfg(not(target_arch = "wasm32"))]
+use crate::app::watch_folder::{task_for_file, FolderWatcher};
+#[cfg(target_arch = "wasm32")]
+use crate::app::web_storage::{self, SavedTask};
+#[cfg(not(target_arch = "wasm32"))]
+use crate::app::window_title::window_title;
+
+/// History beyond this many records is dropped when saving to eframe storage;
+/// use the `sqlite` store to keep more.
+const EFRAME_HISTORY_LIMIT: usize = 500;
+/// Rows shown in the CSV import preview; the rest are still validated and imported.
+#[cfg(not(target_arch = "wasm32"))]
+const CSV_PREVIEW_ROWS: usize = 100;
+/// Free space below which the New Task window warns about an output destination.
+#[cfg(not(target_arch = "wasm32"))]
+const LOW_DISK_SPACE: u64 = 1024 * 1024 * 1024;
+/// Where the speed limit slider starts when a task is first limited, in KiB/s.
+#[cfg(not(target_arch = "wasm32"))]
+const DEFAULT_SPEED_LIMIT_KIB: u64 = 1024;
+#[cfg(not(target_arch = "wasm32"))]
+const SPEED_LIMIT_RANGE_KIB: std::ops::RangeInclusive<u64> = 16..=1024 * 1024;
+/// How often the power source is read and the keep-awake and battery settings applied.
+#[cfg(not(target_arch = "wasm32"))]
+const POWER_CHECK_INTERVAL: Duration = Duration::from_secs(5);
+/// Longest the UI sleeps while no task is tracked. Watched folders, forwarded launches and
+/// config changes have no event of their own and are picked up within this.
+const IDLE_REPAINT_INTERVAL: Duration = Duration::from_secs(1);
+/// How long closing the window waits for the tasks' work to stop.
+#[cfg(not(target_arch = "wasm32"))]
+const SHUTDOWN_GRACE: Duration = Duration::from_secs(3);
+/// Seconds between polls of the tracked tasks; progress bars are interpolated in between.
+const PROGRESS_POLL_INTERVAL: f64 = 0.25;
+/// Seconds between refreshes of the queue's counts and forecast.
+const STATS_INTERVAL: f64 = 1.0;
+/// Most tracked tasks polled in one frame, so a long list cannot stall the UI.
+const POLLS_PER_FRAME: usize = 2_000;
+/// Width of the task names in the task list, so the progress bars line up.
+const TASK_TITLE_WIDTH: f32 = 120.0;
+/// Width of the color tag stripe at the start of each task row.
+const COLOR_TAG_WIDTH: f32 = 4.0;
+/// Width of the priority dropdown on each task row.
+const PRIORITY_WIDTH: f32 = 70.0;
+/// Seconds a completion toast stays up unless dismissed.
+const TOAST_SECONDS: f64 = 10.0;
+/// Seconds a task scrolled to from elsewhere stays highlighted in the task list.
+const HIGHLIGHT_SECONDS: f64 = 2.0;
+/// Deadlines offered in a task's menu, in hours from now.
+const DEADLINE_HOURS: [u64; 3] = [1, 4, 24];
+/// Records per page of the History window.
+const HISTORY_PAGE_SIZE: usize = 50;
+const SQLITE_AVAILABLE: bool = cfg!(all(feature = "sqlite", not(target_arch = "wasm32")));
 
 #[derive(serde::Deserialize, serde::Serialize)]
 #[serde(default)]
 pub struct TemplateApp {
     label: String,
-    show_footer: bool,
-    show_header: bool,
+    /// Flattened, so that the flags saved before the layout had its own type still load.
+    #[serde(flatten)]
+    layout: Layout,
+    history: Vec<TaskRecord>,
+    #[serde(skip)]
+    task_queue: sync_Arc<TaskQueue>,
+    #[serde(skip)]
+    task_ids: Vec<TaskId>,
+    /// Latest poll result of each tracked task.
+    #[serde(skip)]
+    polled: HashMap<TaskId, PollResult>,
+    #[serde(skip)]
+    estimates: HashMap<TaskId, ProgressEstimate>,
+    /// egui time the last sweep over the tracked tasks started, in seconds.
+    #[serde(skip)]
+    last_poll: f64,
+    /// Index in `task_ids` of the next task to poll in the current sweep.
+    #[serde(skip)]
+    poll_cursor: usize,
+    #[serde(skip)]
+    queue_stats: QueueStats,
+    /// egui time `queue_stats` was last refreshed, in seconds.
     #[serde(skip)]
-    task_queue: TaskQueue,
+    stats_refreshed: f64,
+    /// Lowest task id not yet seen by `adopt_untracked_tasks`.
     #[serde(skip)]
-    task_ids: Vec<usize>,
+    adopted_up_to: usize,
+    /// Tasks added from outside the UI. They stay in the queue once finished, so whoever
+    /// added them can still poll them and take their results.
+    #[serde(skip)]
+    added_elsewhere: HashSet<TaskId>,
     #[serde(skip)]
     value: f32,
+    #[serde(skip)]
+    config: AppConfig,
+    #[serde(skip)]
+    config_watcher: Option<ConfigWatcher>,
+    #[serde(skip)]
+    config_error: Option<String>,
+    #[serde(skip)]
+    history_view: HistoryView,
+    #[serde(skip)]
+    registry: sync_Arc<TaskKindRegistry>,
+    #[serde(skip)]
+    show_new_task: bool,
+    #[serde(skip)]
+    new_task_kind: String,
+    #[serde(skip)]
+    new_task_params: TaskParams,
+    #[serde(skip)]
+    new_task_error: Option<String>,
+    /// The parameter the last Add was turned away for, and why, shown on that parameter
+    /// until the parameters change.
+    #[serde(skip)]
+    new_task_rejected: Option<RejectedParam>,
+    /// Minutes from Add until the task starts; it is scheduled rather than queued unless 0.
+    #[serde(skip)]
+    new_task_delay_minutes: u64,
+    /// Shown in place of the new task's id; left out when blank.
+    #[serde(skip)]
+    new_task_label: String,
+    /// Minutes between runs of the new task; 0 runs it once.
+    #[serde(skip)]
+    new_task_repeat_minutes: u64,
+    /// Agent to run the new task on; empty runs it locally.
+    #[cfg(all(feature = "remote-agent", not(target_arch = "wasm32")))]
+    #[serde(skip)]
+    new_task_agent: String,
+    /// Agent paths to fetch back once the new task completes, separated by `;`.
+    #[cfg(all(feature = "remote-agent", not(target_arch = "wasm32")))]
+    #[serde(skip)]
+    new_task_fetch: String,
+    #[cfg(all(feature = "remote-agent", not(target_arch = "wasm32")))]
+    #[serde(skip)]
+    agent_clients:
+        std::collections::HashMap<String, sync_Arc<crate::app::remote_agent::AgentClient>>,
+    #[cfg(not(target_arch = "wasm32"))]
+    #[serde(skip)]
+    instance: Option<InstanceServer>,
+    /// From `--name`; shown in the window title.
+    #[cfg(not(target_arch = "wasm32"))]
+    #[serde(skip)]
+    instance_name: Option<String>,
+    /// The window title as last set, so it is only set again when it changes.
+    #[cfg(not(target_arch = "wasm32"))]
+    #[serde(skip)]
+    window_title: String,
+    #[cfg(not(target_arch = "wasm32"))]
+    #[serde(skip)]
+    pending_links: Vec<TaskSpec>,
+    #[cfg(not(target_arch = "wasm32"))]
+    #[serde(skip)]
+    link_error: Option<String>,
+    #[cfg(all(feature = "otel", not(target_arch = "wasm32")))]
+    #[serde(skip)]
+    otel: Option<crate::app::otel::OtelExporter>,
+    #[cfg(all(feature = "lan-sync", not(target_arch = "wasm32")))]
+    #[serde(skip)]
+    lan_sync: Option<crate::app::lan_sync::LanSync>,
+    #[cfg(all(feature = "lan-sync", not(target_arch = "wasm32")))]
+    #[serde(skip)]
+    show_remote_queues: bool,
+    #[cfg(not(target_arch = "wasm32"))]
+    #[serde(skip)]
+    folder_watcher: Option<FolderWatcher>,
+    #[cfg(not(target_arch = "wasm32"))]
+    #[serde(skip)]
+    watch_drafts: WatchDrafts,
+    #[cfg(not(target_arch = "wasm32"))]
+    #[serde(skip)]
+    power: PowerState,
+    #[cfg(all(feature = "self-update", not(target_arch = "wasm32")))]
+    #[serde(skip)]
+    update: UpdateStatus,
+    #[cfg(not(target_arch = "wasm32"))]
+    #[serde(skip)]
+    warm_start: WarmStartStatus,
+    #[cfg(all(feature = "global-hotkey", not(target_arch = "wasm32")))]
+    #[serde(skip)]
+    pause_hotkey: Option<crate::app::hotkey::PauseHotkey>,
+    #[cfg(all(feature = "secrets", not(target_arch = "wasm32")))]
+    #[serde(skip)]
+    secrets: Option<sync_Arc<SecretStore>>,
+    #[cfg(all(feature = "secrets", not(target_arch = "wasm32")))]
+    #[serde(skip)]
+    secret_drafts: SecretDrafts,
+    #[cfg(not(target_arch = "wasm32"))]
+    #[serde(skip)]
+    job_dialog: Option<JobDialog>,
+    #[cfg(not(target_arch = "wasm32"))]
+    #[serde(skip)]
+    csv_import: Option<CsvImport>,
+    /// How to create again each tracked task that was added through the registry, by task
+    /// id, for File → Save session.
+    #[cfg(not(target_arch = "wasm32"))]
+    #[serde(skip)]
+    task_specs: HashMap<TaskId, TaskSpec>,
+    /// Shared with the queue; `None` for instances started with `--new-instance`, which
+    /// would otherwise overwrite the first instance's.
+    #[cfg(not(target_arch = "wasm32"))]
+    #[serde(skip)]
+    journal: Option<sync_Arc<Journal>>,
+    /// Tasks the last run did not get to finish, offered for restarting until dismissed.
+    #[cfg(not(target_arch = "wasm32"))]
+    #[serde(skip)]
+    interrupted: Vec<InterruptedTask>,
+    #[cfg(not(target_arch = "wasm32"))]
+    #[serde(skip)]
+    interrupted_error: Option<String>,
+    /// Job file and CSV batches still running, and what to do when each finishes.
+    #[cfg(not(target_arch = "wasm32"))]
+    #[serde(skip)]
+    post_batches: PendingBatches,
+    /// A finished batch's shutdown or sleep, waiting out its countdown.
+    #[cfg(not(target_arch = "wasm32"))]
+    #[serde(skip)]
+    power_countdown: Option<PowerCountdown>,
+    /// Outcome of the last File menu action that has nothing else to show it in.
+    #[cfg(not(target_arch = "wasm32"))]
+    #[serde(skip)]
+    file_notice: Option<String>,
+    /// Status changes of this session's tasks, for File → Export trace.
+    #[cfg(not(target_arch = "wasm32"))]
+    #[serde(skip)]
+    trace: Option<TraceRecorder>,
+    /// Set while the Profiler window is open.
+    #[cfg(feature = "profiling")]
+    #[serde(skip)]
+    profiler: Option<Profiler>,
+    /// Tasks that finished with a file to show, newest last.
+    #[serde(skip)]
+    toasts: Vec<CompletionToast>,
+    /// The latest change worth reading out, shown under the task count.
+    #[serde(skip)]
+    announcement: String,
+    /// Color tag of each tracked task that has one, as set in its record.
+    #[serde(skip)]
+    color_tags: HashMap<TaskId, ColorTag>,
+    /// Label of each tracked task that has one, as set in its record.
+    #[serde(skip)]
+    labels: HashMap<TaskId, String>,
+    /// Tracked tasks that failed, listed with their error until retried or dismissed.
+    #[serde(skip)]
+    failures: HashSet<TaskId>,
+    /// How many tasks are ahead of each queued one, while a limit keeps tasks waiting.
+    #[serde(skip)]
+    ahead_in_line: HashMap<TaskId, usize>,
+    /// Priority of each tracked task that is not normal, as set in its record.
+    #[serde(skip)]
+    priorities: HashMap<TaskId, Priority>,
+    /// The first tracked task with each key, of those added through the registry, so the
+    /// New task window can point out a task it is about to add again.
+    #[serde(skip)]
+    dedupe_keys: HashMap<DedupeKey, TaskId>,
+    /// Scroll the task list to this task on the next frame.
+    #[serde(skip)]
+    scroll_to_task: Option<TaskId>,
+    /// The task scrolled to last, and when, to highlight it for a moment.
+    #[serde(skip)]
+    highlighted_task: Option<(TaskId, f64)>,
+    /// Groups whose tasks are listed under their row; the rest show only the group's row.
+    #[serde(skip)]
+    expanded_groups: HashSet<usize>,
+    /// The group whose row is being dragged to another group's place.
+    #[serde(skip)]
+    dragged_group: Option<usize>,
+    /// Tasks found to be at risk of missing their deadline, to escalate as configured.
+    #[cfg(not(target_arch = "wasm32"))]
+    #[serde(skip)]
+    deadline_events: Option<std::sync::mpsc::Receiver<DeadlineAtRisk>>,
+    /// Write the app's storage on the next frame rather than at the next autosave, e.g.
+    /// so cleared history does not linger on disk.
+    #[serde(skip)]
+    save_soon: bool,
+    /// How to create again each task added from the New task window, by task id.
+    #[cfg(target_arch = "wasm32")]
+    #[serde(skip)]
+    saved_tasks: BTreeMap<TaskId, SavedTask>,
+    /// TOML being edited in Settings; the web build has no config file to open instead.
+    #[cfg(target_arch = "wasm32")]
+    #[serde(skip)]
+    config_draft: Option<String>,
+}
+
+/// A row of the task list.
+#[derive(Clone, Copy)]
+enum ListRow {
+    Task(TaskId),
+    /// Stands for the group's tasks, which follow it when it is expanded.
+    Group(usize),
+}
+
+/// A task that completed with a file, offered to the user until dismissed or expired.
+struct CompletionToast {
+    task_id: TaskId,
+    kind: String,
+    path: PathBuf,
+    /// `egui::InputState::time` when the task finished.
+    shown_at: f64,
+}
+
+/// A parameter of the New task window that the registry or the task turned away.
+struct RejectedParam {
+    name: String,
+    message: String,
+    /// The parameters as they were, so the error goes once any of them is edited.
+    params: TaskParams,
+}
+
+/// The page of history shown in the History window. Records are fetched from the queue
+/// only when the page changes, since older pages may have to be read from disk.
+#[derive(Default)]
+struct HistoryView {
+    page: usize,
+    records: Option<Vec<TaskRecord>>,
+    /// Filters the audit trail; see [`AuditEntry::matches`].
+    audit_query: String,
+    /// Settings → Clear history was pressed and awaits confirmation.
+    confirm_clear: bool,
+}
+
+/// The result of opening a job file, shown until the user closes it.
+#[cfg(not(target_arch = "wasm32"))]
+struct JobDialog {
+    file_name: String,
+    outcome: Result<Vec<TaskId>, JobFileError>,
+    /// Whether the batch is running; tasks from a job without `auto_start` wait paused.
+    started: bool,
+    after: PostBatchAction,
+}
+
+/// A CSV file being mapped to a task kind, previewed and validated before it is enqueued.
+#[cfg(not(target_arch = "wasm32"))]
+struct CsvImport {
+    file_name: String,
+    table: CsvTable,
+    kind: String,
+    mapping: ColumnMapping,
+    /// One entry per row, recomputed whenever the kind or mapping changes.
+    checked: Vec<Result<TaskParams, String>>,
+    after: PostBatchAction,
+}
+
+/// A shutdown or sleep that happens at `deadline`, an `egui::InputState::time`, unless
+/// cancelled.
+#[cfg(not(target_arch = "wasm32"))]
+struct PowerCountdown {
+    batch: BatchSummary,
+    deadline: f64,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+#[derive(Default)]
+struct PowerState {
+    source: PowerSource,
+    checked_at: Option<Instant>,
+    /// Set while tasks run with `keep_awake` on; an error is not retried until they stop.
+    keep_awake: Option<Result<KeepAwake, String>>,
+    guard: BatteryGuard,
+    /// Only read while the governor is on.
+    load: SystemLoad,
+    governor: Governor,
+    /// Only checked while `pause_on_metered` is on, on a thread of its own as asking can
+    /// take a while.
+    network: NetworkCost,
+    network_check: Option<std::sync::mpsc::Receiver<NetworkCost>>,
+    metered: MeteredGuard,
+}
+
+#[cfg(all(feature = "self-update", not(target_arch = "wasm32")))]
+#[derive(Default)]
+enum UpdateStatus {
+    #[default]
+    Idle,
+    Checking(std::sync::mpsc::Receiver<Result<Option<(Release, String)>, String>>),
+    UpToDate,
+    Available {
+        release: Release,
+        sha256: String,
+    },
+    /// The binary is being fetched by task `task_id`; `events` reports when it finishes.
+    Downloading {
+        release: Release,
+        sha256: String,
+        task_id: TaskId,
+        events: std::sync::mpsc::Receiver<TaskRecord>,
+    },
+    Staged(semver::Version),
+    Failed(String),
+}
+
+/// Debug → Warm-start benchmark, run on a thread of its own since it can take a while.
+#[cfg(not(target_arch = "wasm32"))]
+#[derive(Default)]
+enum WarmStartStatus {
+    #[default]
+    Idle,
+    Running(std::sync::mpsc::Receiver<Result<WarmStartReport, String>>),
+    Done(Result<WarmStartReport, String>),
+}
+
+/// The Secrets section of the Settings window. Cleared as soon as a secret is saved, so a
+/// typed password does not linger.
+#[cfg(all(feature = "secrets", not(target_arch = "wasm32")))]
+#[derive(Default)]
+struct SecretDrafts {
+    passphrase: String,
+    name: String,
+    value: String,
+    error: Option<String>,
+}
+
+/// Watch-folder settings as edited in the Settings window, before they are saved.
+#[cfg(not(target_arch = "wasm32"))]
+#[derive(Default)]
+struct WatchDrafts {
+    folder: String,
+    rules: Vec<WatchRuleDraft>,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+#[derive(Default)]
+struct WatchRuleDraft {
+    extensions: String,
+    kind: String,
+    params: String,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl WatchDrafts {
+    fn from_config(config: &AppConfig) -> Self {
+        WatchDrafts {
+            folder: config
+                .watch_folder
+                .as_ref()
+                .map(|folder| folder.display().to_string())
+                .unwrap_or_default(),
+            rules: config
+                .watch_rules
+                .iter()
+                .map(|rule| WatchRuleDraft {
+                    extensions: rule.extensions.join(", "),
+                    kind: rule.kind.clone(),
+                    params: rule
+                        .params
+                        .iter()
+                        .map(|(name, value)| format!("{}={}", name, value))
+                        .collect::<Vec<_>>()
+                        .join("; "),
+                })
+                .collect(),
+        }
+    }
+
+    fn apply_to(&self, config: &mut AppConfig) {
+        let folder = self.folder.trim();
+        config.watch_folder = (!folder.is_empty()).then(|| folder.into());
+        config.watch_rules = self
+            .rules
+            .iter()
+            .filter(|rule| !rule.kind.trim().is_empty())
+            .map(|rule| WatchRule {
+                extensions: rule
+                    .extensions
+                    .split(',')
+                    .map(|extension| extension.trim().to_owned())
+                    .filter(|extension| !extension.is_empty())
+                    .collect(),
+                kind: rule.kind.trim().to_owned(),
+                params: rule
+                    .params
+                    .split(';')
+                    .filter_map(|pair| pair.split_once('='))
+                    .map(|(name, value)| (name.trim().to_owned(), value.trim().to_owned()))
+                    .collect(),
+            })
+            .collect();
+    }
+}
+
+impl Default for TemplateApp {
+    fn default() -> Self {
+        Self {
+            label: "Task Queue UI".to_owned(),
+            layout: Layout::default(),
+            history: Vec::new(),
+            task_queue: sync_Arc::new(TaskQueue::new()),
+            task_ids: Vec::new(),
+            polled: HashMap::new(),
+            estimates: HashMap::new(),
+            last_poll: 0.0,
+            poll_cursor: 0,
+            queue_stats: QueueStats::default(),
+            stats_refreshed: f64::NEG_INFINITY,
+            adopted_up_to: 0,
+            added_elsewhere: HashSet::new(),
+            value: 1.0,
+            config: AppConfig::default(),
+            config_watcher: None,
+            config_error: None,
+            history_view: HistoryView::default(),
+            registry: sync_Arc::new(TaskKindRegistry::default()),
+            show_new_task: false,
+            new_task_kind: String::new(),
+            new_task_params: TaskParams::new(),
+            new_task_error: None,
+            new_task_rejected: None,
+            new_task_delay_minutes: 0,
+            new_task_repeat_minutes: 0,
+            new_task_label: String::new(),
+            #[cfg(all(feature = "remote-agent", not(target_arch = "wasm32")))]
+            new_task_agent: String::new(),
+            #[cfg(all(feature = "remote-agent", not(target_arch = "wasm32")))]
+            new_task_fetch: String::new(),
+            #[cfg(all(feature = "remote-agent", not(target_arch = "wasm32")))]
+            agent_clients: std::collections::HashMap::new(),
+            #[cfg(not(target_arch = "wasm32"))]
+            instance: None,
+            #[cfg(not(target_arch = "wasm32"))]
+            instance_name: None,
+            #[cfg(not(target_arch = "wasm32"))]
+            window_title: String::new(),
+            #[cfg(not(target_arch = "wasm32"))]
+            pending_links: Vec::new(),
+            #[cfg(not(target_arch = "wasm32"))]
+            link_error: None,
+            #[cfg(all(feature = "otel", not(target_arch = "wasm32")))]
+            otel: None,
+            #[cfg(all(feature = "lan-sync", not(target_arch = "wasm32")))]
+            lan_sync: None,
+            #[cfg(all(feature = "lan-sync", not(target_arch = "wasm32")))]
+            show_remote_queues: false,
+            #[cfg(not(target_arch = "wasm32"))]
+            folder_watcher: None,
+            #[cfg(not(target_arch = "wasm32"))]
+            watch_drafts: WatchDrafts::default(),
+            #[cfg(not(target_arch = "wasm32"))]
+            power: PowerState::default(),
+            #[cfg(all(feature = "self-update", not(target_arch = "wasm32")))]
+            update: UpdateStatus::default(),
+            #[cfg(not(target_arch = "wasm32"))]
+            warm_start: WarmStartStatus::default(),
+            #[cfg(all(feature = "global-hotkey", not(target_arch = "wasm32")))]
+            pause_hotkey: None,
+            #[cfg(all(feature = "secrets", not(target_arch = "wasm32")))]
+            secrets: None,
+            #[cfg(all(feature = "secrets", not(target_arch = "wasm32")))]
+            secret_drafts: SecretDrafts::default(),
+            #[cfg(not(target_arch = "wasm32"))]
+            job_dialog: None,
+            #[cfg(not(target_arch = "wasm32"))]
+            csv_import: None,
+            #[cfg(not(target_arch = "wasm32"))]
+            task_specs: HashMap::new(),
+            #[cfg(not(target_arch = "wasm32"))]
+            journal: None,
+            #[cfg(not(target_arch = "wasm32"))]
+            interrupted: Vec::new(),
+            #[cfg(not(target_arch = "wasm32"))]
+            interrupted_error: None,
+            #[cfg(not(target_arch = "wasm32"))]
+            post_batches: PendingBatches::default(),
+            #[cfg(not(target_arch = "wasm32"))]
+            power_countdown: None,
+            #[cfg(not(target_arch = "wasm32"))]
+            file_notice: None,
+            #[cfg(not(target_arch = "wasm32"))]
+            trace: None,
+            #[cfg(feature = "profiling")]
+            profiler: None,
+            toasts: Vec::new(),
+            announcement: String::new(),
+            color_tags: HashMap::new(),
+            labels: HashMap::new(),
+            failures: HashSet::new(),
+            ahead_in_line: HashMap::new(),
+            priorities: HashMap::new(),
+            dedupe_keys: HashMap::new(),
+            scroll_to_task: None,
+            highlighted_task: None,
+            expanded_groups: HashSet::new(),
+            dragged_group: None,
+            #[cfg(not(target_arch = "wasm32"))]
+            deadline_events: None,
+            save_soon: false,
+            #[cfg(target_arch = "wasm32")]
+            saved_tasks: BTreeMap::new(),
+            #[cfg(target_arch = "wasm32")]
+            config_draft: None,
+        }
+    }
 }
 
-impl Default for TemplateApp {
-    fn default() -> Self {
-        Self {
-            label: "Task Queue UI".to_owned(),
-            show_footer: false,
-            show_header: true,
-            task_queue: TaskQueue::new(),
-            task_ids: Vec::new(),
-            value: 1.0,
+impl TemplateApp {
+    pub fn new(cc: &eframe::CreationContext<'_>) -> Self {
+        let mut app: TemplateApp = match cc.storage {
+            Some(storage) => eframe::get_value(storage, eframe::APP_KEY).unwrap_or_default(),
+            None => Default::default(),
+        };
+        #[cfg(not(target_arch = "wasm32"))]
+        format::NumberFormat::set_current(format::NumberFormat::from_env());
+        app.init_config(&cc.egui_ctx);
+        app.layout.density.apply(&cc.egui_ctx);
+        #[cfg(not(target_arch = "wasm32"))]
+        if let Some(fonts) = Assets::from_config(&app.config).fonts() {
+            cc.egui_ctx.set_fonts(fonts);
+        }
+        app.init_task_queue();
+        let ctx = cc.egui_ctx.clone();
+        app.task_queue.set_on_change(move || ctx.request_repaint());
+        #[cfg(all(feature = "secrets", not(target_arch = "wasm32")))]
+        app.init_secrets();
+        app.init_registry();
+        #[cfg(target_arch = "wasm32")]
+        app.restore_saved_tasks();
+        app.init_integrations();
+        app
+    }
+
+    /// Enqueues the tasks given on the command line and, for the primary instance,
+    /// starts accepting arguments forwarded by later launches. Unless this is a second
+    /// instance, looks for tasks the last run left interrupted first.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn with_launch(mut self, launch: &LaunchArgs, instance: Option<InstanceServer>) -> Self {
+        if !launch.new_instance {
+            self.open_journal();
+            self.restore_queue_state();
+        }
+        self.enqueue_launch_tasks(launch);
+        self.instance = instance;
+        self.instance_name = launch.name.clone();
+        self
+    }
+
+    /// Notes in the queue's audit log that the user running the app did `action` to task
+    /// `task_id` through `interface`.
+    fn audit(&self, task_id: TaskId, action: AuditAction, interface: Interface) {
+        self.task_queue
+            .audit(task_id, action, &Origin::local(interface));
+    }
+
+    /// Puts the panels, windows and task list filter back as they were on the first run.
+    fn reset_layout(&mut self, ctx: &egui::Context) {
+        self.layout = Layout::default();
+        self.layout.density.apply(ctx);
+        // Window positions are areas; their sizes and the panels' are widget data.
+        ctx.memory_mut(|memory| {
+            memory.reset_areas();
+            memory.data.clear();
+        });
+    }
+
+    /// Creates a task through the registry and tracks it, remembering how so that a saved
+    /// session can create it again.
+    #[cfg(not(target_arch = "wasm32"))]
+    fn enqueue(&mut self, spec: &TaskSpec) -> Result<TaskId, RegistryError> {
+        let task = self.registry.create(&spec.kind, &spec.params)?;
+        Ok(self.track_spec(self.task_queue.add_task(task), spec))
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    fn track_spec(&mut self, task_id: TaskId, spec: &TaskSpec) -> TaskId {
+        self.task_ids.push(task_id);
+        self.remember_spec(task_id, spec.clone());
+        task_id
+    }
+
+    /// Keeps how task `task_id` was created, for saved sessions and, through the journal,
+    /// for restarting it should the app not get to finish it.
+    #[cfg(not(target_arch = "wasm32"))]
+    fn remember_spec(&mut self, task_id: TaskId, spec: TaskSpec) {
+        if let Some(journal) = &self.journal {
+            journal.write(&JournalEntry::Spec {
+                id: task_id,
+                spec: spec.clone(),
+            });
+        }
+        self.remember_dedupe_key(task_id, &spec.kind, &spec.params);
+        self.task_specs.insert(task_id, spec);
+    }
+
+    fn remember_dedupe_key(&mut self, task_id: TaskId, kind: &str, params: &TaskParams) {
+        if let Some(key) = self.registry.dedupe_key(kind, params) {
+            self.dedupe_keys.entry(key).or_insert(task_id);
+        }
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    fn enqueue_launch_tasks(&mut self, launch: &LaunchArgs) {
+        for spec in &launch.tasks {
+            match self.enqueue(spec) {
+                Ok(task_id) => self.audit(task_id, AuditAction::Add, Interface::CommandLine),
+                Err(e) => log::error!("Cannot enqueue '{}' from arguments: {}", spec.kind, e),
+            }
+        }
+        for annotation in &launch.annotations {
+            let change = [(annotation.key.clone(), annotation.value.clone())];
+            if let Err(e) = self.task_queue.annotate(
+                self.task_queue.task_id(annotation.task_id),
+                change.into_iter().collect(),
+            ) {
+                log::error!("Cannot annotate task {}: {}", annotation.task_id, e);
+            }
+        }
+        self.pending_links.extend(launch.link_tasks.iter().cloned());
+        for path in &launch.job_files {
+            self.open_job_file(path);
+        }
+    }
+
+    /// Follows `ids` as a batch that does `after` once finished, grouped in the task list.
+    #[cfg(not(target_arch = "wasm32"))]
+    fn add_batch(&mut self, ids: &[TaskId], after: PostBatchAction) {
+        self.post_batches.add(ids, after);
+        if ids.len() > 1 {
+            if let Err(e) = self.task_queue.create_group(ids) {
+                log::error!("Cannot group tasks {:?}: {}", ids, e);
+            }
+        }
+    }
+
+    /// Enqueues the batch in a job file, paused unless the file asks to start it.
+    ///
+    /// Nothing is enqueued if any task in the file is invalid.
+    #[cfg(not(target_arch = "wasm32"))]
+    fn open_job_file(&mut self, path: &Path) {
+        let loaded = JobFile::load(path).and_then(|job| {
+            let tasks = job.create_tasks(&self.registry)?;
+            Ok((job, tasks))
+        });
+        let (outcome, started) = match loaded {
+            Ok((job, tasks)) => {
+                let auto_start = job.auto_start;
+                let mut ids = Vec::with_capacity(tasks.len());
+                for (task, spec) in tasks.into_iter().zip(&job.tasks) {
+                    let task_id = self.task_queue.add_task(task);
+                    self.audit(task_id, AuditAction::Add, Interface::Ui);
+                    if !auto_start {
+                        if let Err(e) = self.task_queue.pause_task(task_id) {
+                            log::warn!("Cannot hold task {} from job file: {}", task_id, e);
+                        }
+                    }
+                    ids.push(self.track_spec(task_id, spec));
+                }
+                log::info!("Loaded {} tasks from {}", ids.len(), path.display());
+                self.add_batch(&ids, PostBatchAction::Nothing);
+                (Ok(ids), auto_start)
+            }
+            Err(e) => {
+                log::error!("Cannot open job file {}: {}", path.display(), e);
+                (Err(e), false)
+            }
+        };
+        self.job_dialog = Some(JobDialog {
+            file_name: path.file_name().map_or_else(
+                || path.display().to_string(),
+                |name| name.to_string_lossy().into_owned(),
+            ),
+            outcome,
+            started,
+            after: PostBatchAction::Nothing,
+        });
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    fn pick_job_file(&mut self) {
+        if let Some(path) = rfd::FileDialog::new()
+            .add_filter("Task queue job", &[JOB_FILE_EXTENSION])
+            .pick_file()
+        {
+            self.open_job_file(&path);
+        }
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    fn pick_csv_file(&mut self) {
+        let Some(path) = rfd::FileDialog::new()
+            .add_filter("CSV", &["csv"])
+            .pick_file()
+        else {
+            return;
+        };
+        let table = match CsvTable::load(&path) {
+            Ok(table) => table,
+            Err(e) => {
+                self.file_notice = Some(format!("Cannot import {}: {}", path.display(), e));
+                return;
+            }
+        };
+        let Some(info) = self.registry.kinds().first() else {
+            return;
+        };
+        let mapping = auto_mapping(&table.headers, &info.params);
+        let checked = table.validate(&self.registry, &info.name, &mapping);
+        self.csv_import = Some(CsvImport {
+            file_name: path.file_name().map_or_else(
+                || path.display().to_string(),
+                |name| name.to_string_lossy().into_owned(),
+            ),
+            kind: info.name.clone(),
+            table,
+            mapping,
+            checked,
+            after: PostBatchAction::Nothing,
+        });
+    }
+
+    /// Returns false once the user closes the dialog.
+    #[cfg(not(target_arch = "wasm32"))]
+    fn ui_csv_import(&mut self, ui: &mut egui::Ui) -> bool {
+        let Some(import) = &mut self.csv_import else {
+            return false;
+        };
+        let mut changed = false;
+        egui::ComboBox::new("csv_import_kind", "Kind")
+            .selected_text(&import.kind)
+            .show_ui(ui, |ui| {
+                for info in self.registry.kinds() {
+                    if ui
+                        .selectable_label(info.name == import.kind, &info.name)
+                        .clicked()
+                    {
+                        import.kind = info.name.clone();
+                        import.mapping = auto_mapping(&import.table.headers, &info.params);
+                        changed = true;
+                    }
+                }
+            });
+        let params: Vec<String> = self
+            .registry
+            .get(&import.kind)
+            .map(|info| info.params.iter().map(|spec| spec.name.clone()).collect())
+            .unwrap_or_default();
+
+        egui::ScrollArea::both().max_height(400.0).show(ui, |ui| {
+            egui::Grid::new("csv_import_preview")
+                .striped(true)
+                .show(ui, |ui| {
+                    ui.label("Row");
+                    for (column, header) in import.table.headers.iter().enumerate() {
+                        let target = &mut import.mapping[column];
+                        egui::ComboBox::from_id_source(("csv_column", column))
+                            .selected_text(target.as_deref().unwrap_or("(ignored)"))
+                            .show_ui(ui, |ui| {
+                                changed |= ui.selectable_value(target, None, "(ignored)").changed();
+                                for name in &params {
+                                    changed |= ui
+                                        .selectable_value(target, Some(name.clone()), name)
+                                        .changed();
+                                }
+                            })
+                            .response
+                            .on_hover_text(header);
+                    }
+                    ui.label("Status");
+                    ui.end_row();
+
+                    ui.label("");
+                    for header in &import.table.headers {
+                        ui.strong(header);
+                    }
+                    ui.label("");
+                    ui.end_row();
+
+                    for (index, (row, checked)) in import
+                        .table
+                        .rows
+                        .iter()
+                        .zip(&import.checked)
+                        .take(CSV_PREVIEW_ROWS)
+                        .enumerate()
+                    {
+                        ui.label((index + 1).to_string());
+                        for column in 0..import.table.headers.len() {
+                            ui.label(row.get(column).map_or("", String::as_str));
+                        }
+                        match checked {
+                            Ok(_) => ui.label("OK"),
+                            Err(e) => ui.colored_label(egui::Color32::RED, e),
+                        };
+                        ui.end_row();
+                    }
+                });
+        });
+        if changed {
+            import.checked = import
+                .table
+                .validate(&self.registry, &import.kind, &import.mapping);
+        }
+
+        let rows = import.checked.len();
+        let valid = import
+            .checked
+            .iter()
+            .filter(|checked| checked.is_ok())
+            .count();
+        if rows > CSV_PREVIEW_ROWS {
+            ui.label(format!(
+                "Showing the first {} of {} rows.",
+                CSV_PREVIEW_ROWS, rows
+            ));
+        }
+        if valid < rows {
+            ui.colored_label(
+                egui::Color32::RED,
+                format!(
+                    "{} of {} rows are invalid and will be skipped.",
+                    rows - valid,
+                    rows
+                ),
+            );
+        }
+        ui_post_batch_action(ui, "csv_import_after", &mut import.after);
+        let mut keep_open = true;
+        let mut added = Vec::new();
+        let mut batch = None;
+        ui.horizontal(|ui| {
+            if ui
+                .add_enabled(
+                    valid > 0,
+                    egui::Button::new(format!("Enqueue {} tasks", format::count(valid))),
+                )
+                .clicked()
+            {
+                let mut ids = Vec::with_capacity(valid);
+                for params in import.checked.iter().flatten() {
+                    let spec = TaskSpec {
+                        kind: import.kind.clone(),
+                        params: params.clone(),
+                    };
+                    match self.registry.create(&spec.kind, &spec.params) {
+                        Ok(task) => {
+                            let task_id = self.task_queue.add_task(task);
+                            self.task_queue.audit(
+                                task_id,
+                                AuditAction::Add,
+                                &Origin::local(Interface::Ui),
+                            );
+                            self.task_ids.push(task_id);
+                            added.push((task_id, spec));
+                            ids.push(task_id);
+                        }
+                        Err(e) => {
+                            log::error!("Cannot enqueue row from {}: {}", import.file_name, e)
+                        }
+                    }
+                }
+                log::info!("Imported {} tasks from {}", valid, import.file_name);
+                batch = Some((ids, import.after.clone()));
+                keep_open = false;
+            }
+            if ui.button("Cancel").clicked() {
+                keep_open = false;
+            }
+        });
+        for (task_id, spec) in added {
+            self.remember_spec(task_id, spec);
+        }
+        if let Some((ids, after)) = batch {
+            self.add_batch(&ids, after);
+        }
+        keep_open
+    }
+
+    /// The unfinished tasks that can be created again, the batches they are in and the
+    /// windows around them.
+    #[cfg(not(target_arch = "wasm32"))]
+    fn session(&self, ctx: &egui::Context) -> Session {
+        let (tasks, groups) = self.unfinished_tasks();
+        Session {
+            version: SESSION_VERSION,
+            tasks,
+            groups,
+            layout: SessionLayout {
+                show_header: self.layout.show_header,
+                show_footer: self.layout.show_footer,
+                show_settings: self.layout.show_settings,
+                show_history: self.layout.show_history,
+                show_new_task: self.show_new_task,
+                new_task_kind: self.new_task_kind.clone(),
+                new_task_params: self.new_task_params.clone(),
+                egui_memory: ctx
+                    .memory(|memory| serde_json::to_value(memory))
+                    .map_err(|e| log::warn!("Cannot save the window layout: {}", e))
+                    .ok(),
+            },
+        }
+    }
+
+    /// The unfinished tasks that can be created again, and the batches they are in as
+    /// indices into them.
+    #[cfg(not(target_arch = "wasm32"))]
+    fn unfinished_tasks(&self) -> (Vec<SessionTask>, Vec<Vec<usize>>) {
+        let mut tasks = Vec::new();
+        let mut indices = HashMap::new();
+        for task_id in &self.task_ids {
+            let Some(spec) = self.task_specs.get(task_id) else {
+                continue;
+            };
+            let Ok(detail) = self.task_queue.task_detail(*task_id) else {
+                continue;
+            };
+            if detail.record.status.is_terminal() {
+                continue;
+            }
+            indices.insert(*task_id, tasks.len());
+            tasks.push(SessionTask {
+                spec: spec.clone(),
+                paused: detail.record.status == TaskStatus::Paused,
+                color_tag: detail.record.color_tag,
+                priority: detail.record.priority,
+                deadline: detail.record.deadline,
+                label: detail.record.label,
+            });
+        }
+        // In the queue's order of the groups, so that restoring them keeps it.
+        let ranks: HashMap<usize, usize> = self
+            .task_queue
+            .groups()
+            .iter()
+            .enumerate()
+            .map(|(rank, group)| (group.id, rank))
+            .collect();
+        let mut batches = self.post_batches.groups();
+        batches.sort_by_key(|ids| {
+            ids.first()
+                .and_then(|&id| self.task_queue.group_of(id))
+                .and_then(|group| ranks.get(&group).copied())
+                .unwrap_or(usize::MAX)
+        });
+        let groups = batches
+            .into_iter()
+            .map(|ids| {
+                ids.iter()
+                    .filter_map(|id| indices.get(id).copied())
+                    .collect()
+            })
+            .filter(|group: &Vec<usize>| !group.is_empty())
+            .collect();
+        (tasks, groups)
+    }
+
+    /// Saves the unfinished tasks for the next start and empties the journal, once.
+    #[cfg(not(target_arch = "wasm32"))]
+    fn save_unfinished_on_exit(&mut self) {
+        // Only the instance that keeps the journal restores tasks on start, so only it
+        // saves them.
+        if let Some(journal) = self.journal.take() {
+            self.save_queue_state();
+            journal.close();
+        }
+    }
+
+    /// Saves the unfinished tasks for the next start, if the config asks to.
+    #[cfg(not(target_arch = "wasm32"))]
+    fn save_queue_state(&self) {
+        let Some(path) = session::queue_state_path() else {
+            return;
+        };
+        let (tasks, groups) = self.unfinished_tasks();
+        if !self.config.restore_queue || tasks.is_empty() {
+            return;
+        }
+        let session = Session {
+            version: SESSION_VERSION,
+            tasks,
+            groups,
+            layout: SessionLayout::default(),
+        };
+        let saved = path
+            .parent()
+            .map_or(Ok(()), |dir| {
+                std::fs::create_dir_all(dir).map_err(|e| SessionError::Io(e.to_string()))
+            })
+            .and_then(|()| session.save(&path));
+        match saved {
+            Ok(()) => log::info!("Saved {} unfinished tasks", session.tasks.len()),
+            Err(e) => log::error!("Cannot save unfinished tasks to {}: {}", path.display(), e),
+        }
+    }
+
+    /// Adds the tasks the last run saved on exit, if the config asks to, and deletes the
+    /// file so that they are only added once.
+    #[cfg(not(target_arch = "wasm32"))]
+    fn restore_queue_state(&mut self) {
+        let Some(path) = session::queue_state_path().filter(|path| path.exists()) else {
+            return;
+        };
+        let loaded = Session::load(&path);
+        if let Err(e) = std::fs::remove_file(&path) {
+            log::error!("Cannot remove {}: {}", path.display(), e);
+        }
+        if !self.config.restore_queue {
+            return;
+        }
+        let session = match loaded {
+            Ok(session) => session,
+            Err(e) => {
+                log::error!("Cannot restore unfinished tasks: {}", e);
+                return;
+            }
+        };
+        let (added, errors) = self.add_session_tasks(&session.tasks, &session.groups);
+        self.announcement = format!(
+            "Restored {} unfinished tasks from the last run.",
+            format::count(added)
+        );
+        if !errors.is_empty() {
+            log::error!("Cannot restore unfinished tasks: {}", errors.join("; "));
+        }
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    fn save_session(&mut self, ctx: &egui::Context) {
+        let Some(path) = rfd::FileDialog::new()
+            .add_filter("Task queue session", &[SESSION_FILE_EXTENSION])
+            .set_file_name(format!("session.{}", SESSION_FILE_EXTENSION))
+            .save_file()
+        else {
+            return;
+        };
+        let session = self.session(ctx);
+        self.file_notice = Some(match session.save(&path) {
+            Ok(()) => format!(
+                "Saved {} tasks to {}",
+                format::count(session.tasks.len()),
+                path.display()
+            ),
+            Err(e) => format!("Cannot save the session to {}: {}", path.display(), e),
+        });
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    fn pick_session_file(&mut self, ctx: &egui::Context) {
+        let Some(path) = rfd::FileDialog::new()
+            .add_filter("Task queue session", &[SESSION_FILE_EXTENSION])
+            .pick_file()
+        else {
+            return;
+        };
+        self.file_notice = Some(match Session::load(&path) {
+            Ok(session) => self.open_session(ctx, session),
+            Err(e) => format!("Cannot open {}: {}", path.display(), e),
+        });
+    }
+
+    /// Restores a saved session's windows and adds its tasks, which start over, alongside
+    /// those already in the queue. Returns what happened, for the user.
+    #[cfg(not(target_arch = "wasm32"))]
+    fn open_session(&mut self, ctx: &egui::Context, session: Session) -> String {
+        let (added, errors) = self.add_session_tasks(&session.tasks, &session.groups);
+        let layout = session.layout;
+        self.layout.show_header = layout.show_header;
+        self.layout.show_footer = layout.show_footer;
+        self.layout.show_settings = layout.show_settings;
+        self.layout.show_history = layout.show_history;
+        self.show_new_task = layout.show_new_task;
+        self.new_task_kind = layout.new_task_kind;
+        self.new_task_params = layout.new_task_params;
+        if let Some(memory) = layout.egui_memory {
+            match serde_json::from_value(memory) {
+                Ok(memory) => ctx.memory_mut(|current| *current = memory),
+                Err(e) => log::warn!("Cannot restore the window layout: {}", e),
+            }
+        }
+
+        if errors.is_empty() {
+            format!("Opened the session with {} tasks.", format::count(added))
+        } else {
+            log::error!("Cannot restore session tasks: {}", errors.join("; "));
+            format!(
+                "Opened the session with {} tasks; {} could not be created: {}",
+                format::count(added),
+                format::count(errors.len()),
+                errors.join("; ")
+            )
+        }
+    }
+
+    /// Adds the session's tasks, which start over, and groups them as they were. Returns
+    /// how many were added and why the others could not be.
+    #[cfg(not(target_arch = "wasm32"))]
+    fn add_session_tasks(
+        &mut self,
+        tasks: &[SessionTask],
+        groups: &[Vec<usize>],
+    ) -> (usize, Vec<String>) {
+        let mut ids = Vec::with_capacity(tasks.len());
+        let mut errors = Vec::new();
+        for saved in tasks {
+            match self.enqueue(&saved.spec) {
+                Ok(task_id) => {
+                    self.audit(task_id, AuditAction::Add, Interface::Ui);
+                    if saved.paused {
+                        if let Err(e) = self.task_queue.pause_task(task_id) {
+                            log::warn!("Cannot pause task {} from session: {}", task_id, e);
+                        }
+                    }
+                    if saved.color_tag.is_some() {
+                        self.set_color_tag(task_id, saved.color_tag);
+                    }
+                    if !saved.priority.is_normal() {
+                        self.set_priority(task_id, saved.priority);
+                    }
+                    if saved.deadline.is_some() {
+                        self.set_deadline(task_id, saved.deadline);
+                    }
+                    if saved.label.is_some() {
+                        self.set_label(task_id, saved.label.clone());
+                    }
+                    ids.push(Some(task_id));
+                }
+                Err(e) => {
+                    errors.push(format!("{}: {}", saved.spec.kind, e));
+                    ids.push(None);
+                }
+            }
+        }
+        for group in groups {
+            let group: Vec<TaskId> = group
+                .iter()
+                .filter_map(|&index| ids.get(index).copied().flatten())
+                .collect();
+            self.add_batch(&group, PostBatchAction::Nothing);
+        }
+        (ids.iter().flatten().count(), errors)
+    }
+
+    /// Saves the history as JSON Lines for analytics tools.
+    #[cfg(not(target_arch = "wasm32"))]
+    fn export_history(&mut self) {
+        let Some(path) = rfd::FileDialog::new()
+            .add_filter("JSON Lines", &["jsonl"])
+            .set_file_name("history.jsonl")
+            .save_file()
+        else {
+            return;
+        };
+        self.file_notice = Some(match self.task_queue.export_history_jsonl(&path) {
+            Ok(count) => format!(
+                "Exported {} records to {}",
+                format::count(count),
+                path.display()
+            ),
+            Err(e) => format!("Cannot export history to {}: {}", path.display(), e),
+        });
+    }
+
+    /// Saves the upcoming scheduled and recurring runs as a calendar.
+    #[cfg(not(target_arch = "wasm32"))]
+    fn export_schedule(&mut self) {
+        let Some(path) = rfd::FileDialog::new()
+            .add_filter("iCalendar", &["ics"])
+            .set_file_name("schedule.ics")
+            .save_file()
+        else {
+            return;
+        };
+        self.file_notice = Some(match self.task_queue.export_schedule_ics(&path) {
+            Ok(count) => format!(
+                "Exported {} planned runs to {}",
+                format::count(count),
+                path.display()
+            ),
+            Err(e) => format!("Cannot export schedule to {}: {}", path.display(), e),
+        });
+    }
+
+    /// Saves this session's task status changes as a Chrome trace.
+    #[cfg(not(target_arch = "wasm32"))]
+    fn export_trace(&mut self) {
+        let Some(trace) = &self.trace else {
+            return;
+        };
+        let Some(path) = rfd::FileDialog::new()
+            .add_filter("Chrome trace", &["json"])
+            .set_file_name("trace.json")
+            .save_file()
+        else {
+            return;
+        };
+        self.file_notice = Some(match trace.export(&path) {
+            Ok(count) => format!(
+                "Exported {} trace events to {}",
+                format::count(count),
+                path.display()
+            ),
+            Err(e) => format!("Cannot export the trace to {}: {}", path.display(), e),
+        });
+    }
+
+    /// Returns false once the user closes the dialog.
+    #[cfg(not(target_arch = "wasm32"))]
+    fn ui_job_dialog(&mut self, ui: &mut egui::Ui) -> bool {
+        let Some(dialog) = &mut self.job_dialog else {
+            return false;
+        };
+        let mut keep_open = true;
+        match &dialog.outcome {
+            Ok(ids) => {
+                ui.label(format!(
+                    "Added {} tasks from {}.",
+                    ids.len(),
+                    dialog.file_name
+                ));
+                if dialog.started {
+                    ui.label("The batch is running.");
+                } else {
+                    ui.label("The tasks are paused until you start them.");
+                }
+                if let Some(&batch) = ids.first() {
+                    if ui_post_batch_action(ui, "job_dialog_after", &mut dialog.after)
+                        && !self.post_batches.set_action(batch, dialog.after.clone())
+                    {
+                        ui.label("The batch has already finished.");
+                    }
+                }
+                ui.horizontal(|ui| {
+                    if !dialog.started && ui.button("Start now").clicked() {
+                        for &task_id in ids {
+                            match self.task_queue.resume_task(task_id) {
+                                Ok(()) => self.task_queue.audit(
+                                    task_id,
+                                    AuditAction::Resume,
+                                    &Origin::local(Interface::Ui),
+                                ),
+                                Err(e) => log::warn!("Cannot start task {}: {}", task_id, e),
+                            }
+                        }
+                        dialog.started = true;
+                    }
+                    if ui.button("Close").clicked() {
+                        keep_open = false;
+                    }
+                });
+            }
+            Err(e) => {
+                ui.label(format!("{} was not loaded:", dialog.file_name));
+                match e {
+                    JobFileError::Invalid(errors) => {
+                        for error in errors {
+                            ui.colored_label(egui::Color32::RED, error);
+                        }
+                    }
+                    e => {
+                        ui.colored_label(egui::Color32::RED, e.to_string());
+                    }
+                }
+                if ui.button("Close").clicked() {
+                    keep_open = false;
+                }
+            }
+        }
+        keep_open
+    }
+
+    /// Asks before enqueueing a task from a `taskqueue://` link, since any web page can open one.
+    #[cfg(not(target_arch = "wasm32"))]
+    fn ui_confirm_link(&mut self, ui: &mut egui::Ui) {
+        let Some(spec) = self.pending_links.first().cloned() else {
+            return;
+        };
+        ui.label(format!("A link wants to add a '{}' task:", spec.kind));
+        egui::Grid::new("link_task_params")
+            .num_columns(2)
+            .striped(true)
+            .show(ui, |ui| {
+                for (name, value) in &spec.params {
+                    ui.label(name);
+                    ui.label(value);
+                    ui.end_row();
+                }
+            });
+        if self.pending_links.len() > 1 {
+            ui.label(format!(
+                "{} more waiting",
+                format::count(self.pending_links.len() - 1)
+            ));
+        }
+        ui.horizontal(|ui| {
+            if ui.button("Add").clicked() {
+                match self.enqueue(&spec) {
+                    Ok(task_id) => {
+                        self.audit(task_id, AuditAction::Add, Interface::Ui);
+                        self.pending_links.remove(0);
+                        self.link_error = None;
+                    }
+                    Err(e) => self.link_error = Some(e.to_string()),
+                }
+            }
+            if ui.button("Discard").clicked() {
+                self.pending_links.remove(0);
+                self.link_error = None;
+            }
+        });
+        if let Some(e) = &self.link_error {
+            ui.colored_label(egui::Color32::RED, e);
+        }
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    fn receive_forwarded_args(&mut self, frame: &mut eframe::Frame) {
+        let mut forwarded = Vec::new();
+        if let Some(instance) = &self.instance {
+            while let Some(args) = instance.try_recv() {
+                forwarded.push(args);
+            }
+        }
+        for args in forwarded {
+            match LaunchArgs::parse(&args) {
+                Ok(launch) => self.enqueue_launch_tasks(&launch),
+                Err(e) => log::error!("Ignoring forwarded arguments: {}", e),
+            }
+            frame.set_minimized(false);
+            frame.focus();
+        }
+    }
+
+    /// Shows the tracked tasks' aggregate progress in the window title.
+    #[cfg(not(target_arch = "wasm32"))]
+    fn update_window_title(&mut self, frame: &mut eframe::Frame) {
+        let mut running = 0;
+        let mut progress = 0.0;
+        for result in self.polled.values() {
+            if let PollResult::Pending(data) = result {
+                running += 1;
+                progress += data.fraction();
+            }
+        }
+        let title = window_title(
+            self.instance_name.as_deref(),
+            running,
+            progress / running.max(1) as f32,
+        );
+        if title != self.window_title {
+            frame.set_window_title(&title);
+            self.window_title = title;
+        }
+    }
+
+    fn refresh_stats(&mut self, ctx: &egui::Context) {
+        let now = ctx.input(|i| i.time);
+        if now - self.stats_refreshed >= STATS_INTERVAL {
+            self.queue_stats = self.task_queue.stats();
+            self.stats_refreshed = now;
+        }
+    }
+
+    /// When everything queued and running should be done, e.g. "Everything done in about
+    /// 4m 5s (not counting 1 paused)".
+    fn forecast_text(&self) -> Option<String> {
+        let stats = &self.queue_stats;
+        let finish = stats.estimated_finish_time?;
+        let mut text = format!(
+            "Everything done in about {}",
+            format::duration_millis(now_millis(), finish)
+        );
+        let mut left_out = Vec::new();
+        if stats.paused > 0 {
+            left_out.push(format!("{} paused", format::count(stats.paused)));
+        }
+        if stats.unestimated > 0 {
+            left_out.push(format!(
+                "{} without an estimate",
+                format::count(stats.unestimated)
+            ));
+        }
+        if !left_out.is_empty() {
+            text.push_str(&format!(" (not counting {})", left_out.join(", ")));
+        }
+        Some(text)
+    }
+
+    /// Starts the optional services that follow the queue's events.
+    fn init_integrations(&mut self) {
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            self.trace = Some(TraceRecorder::start(self.task_queue.subscribe()));
+            self.deadline_events = Some(self.task_queue.subscribe_deadlines());
+        }
+        #[cfg(all(feature = "self-update", not(target_arch = "wasm32")))]
+        if self.config.update.check_on_start {
+            self.check_for_update();
+        }
+        #[cfg(all(feature = "lan-sync", not(target_arch = "wasm32")))]
+        if let Some(lan_sync) = &self.config.lan_sync {
+            match crate::app::lan_sync::LanSync::start(lan_sync, self.task_queue.clone()) {
+                Ok(lan_sync) => self.lan_sync = Some(lan_sync),
+                Err(e) => log::error!("Cannot start LAN sync: {}", e),
+            }
+        }
+        #[cfg(all(feature = "email", not(target_arch = "wasm32")))]
+        if let Some(smtp) = self.config.smtp.as_ref().filter(|smtp| smtp.batch_summary) {
+            let mut smtp = smtp.clone();
+            smtp.password = self.resolve_password(smtp.password);
+            crate::app::email::spawn_batch_mailer(&smtp, self.task_queue.subscribe());
+        }
+        #[cfg(not(target_arch = "wasm32"))]
+        if !self.config.webhooks.is_empty() {
+            log::info!(
+                "Sending task events to {} webhooks",
+                self.config.webhooks.len()
+            );
+            crate::app::webhooks::spawn_dispatcher(
+                self.config.webhooks.clone(),
+                self.task_queue.subscribe(),
+            );
+        }
+        #[cfg(all(feature = "mqtt", not(target_arch = "wasm32")))]
+        if let Some(mqtt) = &self.config.mqtt {
+            log::info!(
+                "Publishing task events to MQTT broker {}:{}",
+                mqtt.host,
+                mqtt.port
+            );
+            let mut mqtt = mqtt.clone();
+            mqtt.password = self.resolve_password(mqtt.password);
+            crate::app::mqtt::spawn_publisher(&mqtt, self.task_queue.subscribe());
+        }
+        #[cfg(all(feature = "otel", not(target_arch = "wasm32")))]
+        if let Some(otel) = &self.config.otel {
+            match crate::app::otel::OtelExporter::start(otel, self.task_queue.subscribe()) {
+                Ok(exporter) => {
+                    log::info!("Exporting task spans and metrics to {}", otel.endpoint);
+                    self.otel = Some(exporter);
+                }
+                Err(e) => log::error!("Failed to start OTLP export: {}", e),
+            }
+        }
+        #[cfg(all(feature = "global-hotkey", not(target_arch = "wasm32")))]
+        if let Some(spec) = &self.config.pause_hotkey {
+            match crate::app::hotkey::PauseHotkey::register(spec, self.task_queue.clone()) {
+                Ok(hotkey) => {
+                    log::info!("Pausing and resuming all tasks with {}", spec);
+                    self.pause_hotkey = Some(hotkey);
+                }
+                Err(e) => {
+                    log::error!("{}", e);
+                    self.config_error = Some(e.to_string());
+                }
+            }
+        }
+        #[cfg(windows)]
+        {
+            let handler = crate::app::control::ControlHandler::new(
+                self.task_queue.clone(),
+                self.registry.clone(),
+                Origin::local(Interface::ControlPipe),
+            );
+            if let Err(e) = crate::app::control_pipe::serve(handler) {
+                log::error!("Failed to open the control pipe: {}", e);
+            }
+        }
+    }
+
+    /// Starts tracking tasks added from outside the UI, e.g. over a control channel.
+    /// Only tasks added since the last call are looked at.
+    fn adopt_untracked_tasks(&mut self) {
+        profile_function!();
+        let records = self.task_queue.records_from(self.adopted_up_to);
+        let Some(last) = records.last() else {
+            return;
+        };
+        let first_unseen = self.adopted_up_to;
+        self.adopted_up_to = last.id.index() + 1;
+        // Whatever the UI added itself since the last call is at the end of `task_ids`.
+        let added_here: HashSet<TaskId> = self
+            .task_ids
+            .iter()
+            .rev()
+            .take_while(|task_id| task_id.index() >= first_unseen)
+            .copied()
+            .collect();
+        for record in records {
+            if added_here.contains(&record.id) {
+                continue;
+            }
+            self.added_elsewhere.insert(record.id);
+            if !record.status.is_terminal() {
+                if let Some(label) = record.label {
+                    self.labels.insert(record.id, label);
+                }
+                self.task_ids.push(record.id);
+            }
+        }
+    }
+
+    /// Polls the tracked tasks in sweeps, one started every `PROGRESS_POLL_INTERVAL` and
+    /// spread over as many frames as `POLLS_PER_FRAME` requires. Returns the tasks that
+    /// finished.
+    fn poll_tracked_tasks(&mut self, now: f64) -> HashSet<TaskId> {
+        profile_function!();
+        let mut finished = HashSet::new();
+        if self.poll_cursor >= self.task_ids.len() {
+            if now - self.last_poll < PROGRESS_POLL_INTERVAL {
+                return finished;
+            }
+            self.last_poll = now;
+            self.poll_cursor = 0;
+        }
+        let end = (self.poll_cursor + POLLS_PER_FRAME).min(self.task_ids.len());
+        let task_ids = self.task_ids[self.poll_cursor..end].to_vec();
+        let results = self.task_queue.poll_many(&task_ids);
+        for (task_id, result) in task_ids.into_iter().zip(results) {
+            let Ok(result) = result else {
+                continue;
+            };
+            if self.poll_tracked_task(task_id, result, now) {
+                finished.insert(task_id);
+            }
+        }
+        self.poll_cursor = end;
+        finished
+    }
+
+    /// Keeps a tracked task's poll result for drawing. Returns whether it finished.
+    fn poll_tracked_task(&mut self, task_id: TaskId, result: PollResult, now: f64) -> bool {
+        let finished = match &result {
+            PollResult::Pending(data) => {
+                let p = data.fraction();
+                self.estimates
+                    .entry(task_id)
+                    .and_modify(|estimate| estimate.report(p, now))
+                    .or_insert_with(|| ProgressEstimate::new(p, now));
+                false
+            }
+            PollResult::Paused(_) => {
+                self.estimates.remove(&task_id);
+                false
+            }
+            PollResult::Completed | PollResult::Cancelled => true,
+            // Kept in the list with its error until it is retried or dismissed.
+            PollResult::Failed(e) => {
+                if self.failures.insert(task_id) {
+                    self.estimates.remove(&task_id);
+                    self.announcement = format!("Task {} failed: {}", task_id, e);
+                    if let Some(batch) = self.post_batches.finished(task_id, false) {
+                        self.after_batch(batch, now);
+                    }
+                }
+                false
+            }
+        };
+        self.polled.insert(task_id, result);
+        finished
+    }
+
+    /// Stops tracking `finished`, keeping the current sweep's place in the list.
+    fn untrack(&mut self, ctx: &egui::Context, finished: &HashSet<TaskId>) {
+        if finished.is_empty() {
+            return;
+        }
+        profile_function!();
+        log::debug!("{} tasks finished, filtering", finished.len());
+        let cursor = self.poll_cursor;
+        let mut index = 0;
+        let mut removed_before_cursor = 0;
+        self.task_ids.retain(|task_id| {
+            let keep = !finished.contains(task_id);
+            if !keep && index < cursor {
+                removed_before_cursor += 1;
+            }
+            index += 1;
+            keep
+        });
+        self.poll_cursor -= removed_before_cursor;
+        let now = ctx.input(|i| i.time);
+        self.announce_finished(finished);
+        self.dedupe_keys
+            .retain(|_, task_id| !finished.contains(task_id));
+        #[cfg(not(target_arch = "wasm32"))]
+        self.power.metered.forget(finished);
+        for task_id in finished {
+            self.polled.remove(task_id);
+            self.estimates.remove(task_id);
+            self.color_tags.remove(task_id);
+            self.labels.remove(task_id);
+            self.priorities.remove(task_id);
+            self.failures.remove(task_id);
+            #[cfg(not(target_arch = "wasm32"))]
+            self.task_specs.remove(task_id);
+            let Ok(detail) = self.task_queue.task_detail(*task_id) else {
+                continue;
+            };
+            let completed = detail.record.status == TaskStatus::Completed;
+            if let Some(batch) = self.post_batches.finished(*task_id, completed) {
+                self.after_batch(batch, now);
+            }
+            if !completed {
+                continue;
+            }
+            if let Some(path) = artifacts::first_file(&detail.record.artifacts) {
+                self.toasts.push(CompletionToast {
+                    task_id: *task_id,
+                    kind: detail.record.kind.clone(),
+                    path: path.to_owned(),
+                    shown_at: now,
+                });
+            }
+        }
+        TaskRows::forget(ctx, finished);
+        // The list held the last ids of the finished tasks the UI added; the ones added
+        // elsewhere are left for their clients.
+        let held: Vec<TaskId> = self
+            .task_ids
+            .iter()
+            .chain(&self.added_elsewhere)
+            .copied()
+            .collect();
+        self.task_queue.retain_ids(&held);
+    }
+
+    /// Sets the announcement to how the `finished` tasks ended, or how many there were.
+    fn announce_finished(&mut self, finished: &HashSet<TaskId>) {
+        let mut ids = finished.iter();
+        self.announcement = match (ids.next(), ids.next()) {
+            (Some(&task_id), None) => match self.task_queue.task_detail(task_id) {
+                Ok(detail) if detail.record.label.is_some() => {
+                    format!("{} {}", detail.record.title(), detail.record.status_label())
+                }
+                Ok(detail) => format!(
+                    "{} task {} {}",
+                    detail.record.kind,
+                    task_id,
+                    detail.record.status_label()
+                ),
+                Err(_) => format!("Task {} finished", task_id),
+            },
+            _ => format!("{} tasks finished", format::count(finished.len())),
+        };
+    }
+
+    /// One line of the task list: its name, its progress and the buttons for its state.
+    fn ui_task_row(&mut self, ui: &mut egui::Ui, task_id: TaskId, result: PollResult, now: f64) {
+        let (paused, data) = match result {
+            PollResult::Pending(data) => (false, data),
+            PollResult::Paused(data) => (true, data),
+            PollResult::Completed | PollResult::Cancelled | PollResult::Failed(_) => return,
+        };
+        // The bar moves on smoothly between polls; counts are shown as last polled.
+        let p = match (paused, self.estimates.get_mut(&task_id)) {
+            (false, Some(estimate)) => estimate.at(now),
+            _ => data.fraction(),
+        };
+        let shown = match data {
+            PollingData::Float(_) => PollingData::Float(p),
+            counts => counts,
+        };
+        let label = self.labels.get(&task_id).map(String::as_str);
+        let text = TaskRows::text(ui, task_id, label, paused, shown);
+        let starts_at = self.task_queue.scheduled_start(task_id).ok().flatten();
+        let ahead = self.ahead_in_line.get(&task_id).copied();
+        let status = match (paused, starts_at, ahead) {
+            (true, _, _) => "paused",
+            (false, Some(_), _) => "scheduled",
+            (false, None, Some(_)) => "queued",
+            (false, None, None) => "running",
+        };
+        let row = ui.horizontal(|ui| {
+            let (stripe, _) = ui.allocate_exact_size(
+                egui::vec2(COLOR_TAG_WIDTH, ui.spacing().interact_size.y),
+                egui::Sense::hover(),
+            );
+            if let Some(tag) = self.color_tags.get(&task_id) {
+                ui.painter().rect_filled(stripe, 1.0, color32(tag.rgb()));
+            }
+            let title = ui.add_sized(
+                [TASK_TITLE_WIDTH, ui.spacing().interact_size.y],
+                egui::Label::new(text.title).sense(egui::Sense::click()),
+            );
+            a11y::name(&title, format!("Task {}, {}", task_id, status));
+            title
+                .on_hover_ui(|ui| self.ui_task_detail(ui, task_id))
+                .context_menu(|ui| {
+                    self.ui_task_detail(ui, task_id);
+                    self.ui_color_tag(ui, task_id);
+                    #[cfg(not(target_arch = "wasm32"))]
+                    self.ui_speed_limit(ui, task_id);
+                    #[cfg(not(target_arch = "wasm32"))]
+                    self.ui_metered_override(ui, task_id);
+                    self.ui_deadline(ui, task_id);
+                    self.ui_recurrence(ui, task_id);
+                });
+            self.ui_priority(ui, task_id);
+            if self.task_queue.deadline_at_risk(task_id).unwrap_or(false) {
+                let warning = ui
+                    .colored_label(ui.visuals().warn_fg_color, "⚠")
+                    .on_hover_text("Likely to miss its deadline");
+                a11y::name(&warning, format!("Task {} may miss its deadline", task_id));
+            }
+            if let Some(recurrence) = self
+                .task_queue
+                .recurrence_of(task_id)
+                .and_then(|id| self.task_queue.recurrence(id).ok())
+            {
+                let repeats = ui.label("🔁").on_hover_text(format!(
+                    "Repeats every {}; next run in {}",
+                    format::duration(recurrence.every),
+                    format::duration_millis(now_millis(), recurrence.next_fire)
+                ));
+                a11y::name(&repeats, format!("Task {} repeats", task_id));
+            }
+            ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                let cancel = ui.button("Cancel");
+                a11y::name(&cancel, format!("Cancel task {}", task_id));
+                if cancel.clicked() {
+                    if let Err(r) = self.task_queue.remove_task(task_id) {
+                        log::error!("Task {} cancellation error: {:?}", task_id, r);
+                    } else {
+                        log::debug!("Task {} cancelled", task_id);
+                        self.audit(task_id, AuditAction::Cancel, Interface::Ui);
+                        self.polled.remove(&task_id);
+                    }
+                }
+                if paused {
+                    let resume = ui.button("Resume");
+                    a11y::name(&resume, format!("Resume task {}", task_id));
+                    if resume.clicked() {
+                        if let Err(r) = self.task_queue.resume_task(task_id) {
+                            log::error!("Task {} resume error: {:?}", task_id, r);
+                        } else {
+                            log::debug!("Task {} resumed", task_id);
+                            self.audit(task_id, AuditAction::Resume, Interface::Ui);
+                            self.polled.remove(&task_id);
+                        }
+                    }
+                } else {
+                    let pause = ui.button("Pause");
+                    a11y::name(&pause, format!("Pause task {}", task_id));
+                    if pause.clicked() {
+                        if let Err(r) = self.task_queue.pause_task(task_id) {
+                            log::error!("Task {} pause error: {:?}", task_id, r);
+                        } else {
+                            log::debug!("Task {} paused", task_id);
+                            self.audit(task_id, AuditAction::Pause, Interface::Ui);
+                            self.polled.remove(&task_id);
+                        }
+                    }
+                }
+                if let Some(starts_at) = starts_at {
+                    let countdown = ui.label(format!(
+                        "Starts in {}",
+                        format::duration_millis(now_millis(), starts_at)
+                    ));
+                    a11y::name(&countdown, format!("Task {}, {}", task_id, status));
+                    return;
+                }
+                if let Some(ahead) = ahead {
+                    let place = ui.label(match ahead {
+                        0 => "Next to start".to_owned(),
+                        ahead => format!("Waiting, {} ahead", format::count(ahead)),
+                    });
+                    a11y::name(&place, format!("Task {}, {}", task_id, status));
+                    return;
+                }
+                let bar = ui.add(
+                    egui::ProgressBar::new(p)
+                        .fill(egui::Color32::DARK_GREEN)
+                        .text(text.progress),
+                );
+                a11y::progress(&bar, format!("Task {} progress, {}", task_id, status), p);
+            });
+        });
+        if let Some((highlighted, since)) = self.highlighted_task {
+            if highlighted == task_id && now - since < HIGHLIGHT_SECONDS {
+                ui.painter().rect_stroke(
+                    row.response.rect.expand(1.0),
+                    2.0,
+                    ui.visuals().selection.stroke,
+                );
+            }
+        }
+    }
+
+    /// The line of a task that failed: its name, its error and buttons to retry or dismiss
+    /// it. Returns whether it is to be taken off the list.
+    fn ui_failed_row(&mut self, ui: &mut egui::Ui, task_id: TaskId, error: &TaskError) -> bool {
+        let title = self
+            .labels
+            .get(&task_id)
+            .map_or_else(|| format!("Task {}", task_id), Clone::clone);
+        let mut dismissed = false;
+        ui.horizontal(|ui| {
+            let (stripe, _) = ui.allocate_exact_size(
+                egui::vec2(COLOR_TAG_WIDTH, ui.spacing().interact_size.y),
+                egui::Sense::hover(),
+            );
+            if let Some(tag) = self.color_tags.get(&task_id) {
+                ui.painter().rect_filled(stripe, 1.0, color32(tag.rgb()));
+            }
+            let title = ui.add_sized(
+                [TASK_TITLE_WIDTH, ui.spacing().interact_size.y],
+                egui::Label::new(title),
+            );
+            a11y::name(&title, format!("Task {}, failed", task_id));
+            title.on_hover_ui(|ui| self.ui_task_detail(ui, task_id));
+            ui.colored_label(ui.visuals().error_fg_color, error.to_string());
+            ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                let dismiss = ui.button("Dismiss");
+                a11y::name(&dismiss, format!("Dismiss task {}", task_id));
+                dismissed |= dismiss.clicked();
+                #[cfg(not(target_arch = "wasm32"))]
+                {
+                    let spec = self.task_specs.get(&task_id).cloned();
+                    let retry = ui
+                        .add_enabled(spec.is_some(), egui::Button::new("Retry"))
+                        .on_disabled_hover_text("Not known how this task was created");
+                    a11y::name(&retry, format!("Retry task {}", task_id));
+                    if let Some(spec) = spec.filter(|_| retry.clicked()) {
+                        match self.enqueue(&spec) {
+                            Ok(retried) => {
+                                log::debug!("Task {} retried as task {}", task_id, retried);
+                                self.audit(retried, AuditAction::Add, Interface::Ui);
+                                let label = self.labels.get(&task_id).cloned();
+                                if label.is_some() {
+                                    self.set_label(retried, label);
+                                }
+                                dismissed = true;
+                            }
+                            Err(e) => log::error!("Cannot retry task {}: {}", task_id, e),
+                        }
+                    }
+                }
+            });
+        });
+        dismissed
+    }
+
+    /// The rows of the task list when some tasks are filtered out or grouped: the groups,
+    /// in their order, each in place of the first tracked task of a group and followed by
+    /// its tracked tasks if expanded.
+    fn list_rows(&self, groups: &[TaskGroup]) -> Vec<ListRow> {
+        let shown = |task_id: &TaskId| {
+            self.layout
+                .color_filter
+                .map_or(true, |filter| self.color_tags.get(task_id) == Some(&filter))
+        };
+        let group_of: HashMap<TaskId, &TaskGroup> = groups
+            .iter()
+            .flat_map(|group| group.tasks.iter().map(move |&task_id| (task_id, group)))
+            .collect();
+        let tracked: HashSet<TaskId> = self.task_ids.iter().copied().collect();
+        let mut in_order = groups.iter().filter(|group| {
+            group
+                .tasks
+                .iter()
+                .any(|task_id| tracked.contains(task_id) && shown(task_id))
+        });
+        let mut listed_groups = HashSet::new();
+        let mut rows = Vec::with_capacity(self.task_ids.len());
+        for task_id in self.task_ids.iter().filter(|task_id| shown(task_id)) {
+            let Some(group) = group_of.get(task_id) else {
+                rows.push(ListRow::Task(*task_id));
+                continue;
+            };
+            if !listed_groups.insert(group.id) {
+                continue;
+            }
+            let Some(group) = in_order.next() else {
+                continue;
+            };
+            rows.push(ListRow::Group(group.id));
+            if self.expanded_groups.contains(&group.id) {
+                rows.extend(
+                    group
+                        .tasks
+                        .iter()
+                        .filter(|task_id| tracked.contains(task_id) && shown(task_id))
+                        .map(|&task_id| ListRow::Task(task_id)),
+                );
+            }
+        }
+        rows
+    }
+
+    /// A group's row: one progress bar for all its tasks, which the arrow shows or hides,
+    /// and buttons acting on all of them. Dropping another group's handle on it moves that
+    /// group to its place in line.
+    fn ui_group_row(&mut self, ui: &mut egui::Ui, group_id: usize) {
+        let Ok(result) = self.task_queue.group_progress(group_id) else {
+            return;
+        };
+        let (paused, p) = match result {
+            PollResult::Pending(data) => (false, data.fraction()),
+            PollResult::Paused(data) => (true, data.fraction()),
+            PollResult::Completed => (false, 1.0),
+            PollResult::Cancelled | PollResult::Failed(_) => (false, 0.0),
+        };
+        let expanded = self.expanded_groups.contains(&group_id);
+        let row = ui.horizontal(|ui| {
+            let handle = ui.add_sized(
+                [COLOR_TAG_WIDTH, ui.spacing().interact_size.y],
+                egui::Label::new("☰").sense(egui::Sense::drag()),
+            );
+            a11y::name(&handle, format!("Move group {}", group_id));
+            if handle.drag_started() {
+                self.dragged_group = Some(group_id);
+            }
+            if handle.dragged() {
+                ui.ctx().set_cursor_icon(egui::CursorIcon::Grabbing);
+            } else if handle.hovered() {
+                ui.ctx().set_cursor_icon(egui::CursorIcon::Grab);
+            }
+            let toggle = ui.add_sized(
+                [TASK_TITLE_WIDTH, ui.spacing().interact_size.y],
+                egui::Button::new(format!(
+                    "{} Group {}",
+                    if expanded { "⏷" } else { "⏵" },
+                    group_id
+                ))
+                .frame(false),
+            );
+            a11y::name(
+                &toggle,
+                format!(
+                    "Group {}, {}",
+                    group_id,
+                    if expanded { "expanded" } else { "collapsed" }
+                ),
+            );
+            if toggle.clicked() && !self.expanded_groups.remove(&group_id) {
+                self.expanded_groups.insert(group_id);
+            }
+            ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                let cancel = ui.button("Cancel all");
+                a11y::name(&cancel, format!("Cancel group {}", group_id));
+                if cancel.clicked() {
+                    self.group_action(group_id, AuditAction::Cancel);
+                }
+                let (label, action) = if paused {
+                    ("Resume all", AuditAction::Resume)
+                } else {
+                    ("Pause all", AuditAction::Pause)
+                };
+                let button = ui.button(label);
+                a11y::name(&button, format!("{} group {}", action, group_id));
+                if button.clicked() {
+                    self.group_action(group_id, action);
+                }
+                let bar = ui.add(
+                    egui::ProgressBar::new(p)
+                        .fill(egui::Color32::DARK_GREEN)
+                        .show_percentage(),
+                );
+                a11y::progress(&bar, format!("Group {} progress", group_id), p);
+            });
+        });
+        let Some(dragged) = self.dragged_group.filter(|&dragged| dragged != group_id) else {
+            return;
+        };
+        let pointer = ui.input(|i| i.pointer.interact_pos());
+        if !pointer.map_or(false, |pos| row.response.rect.y_range().contains(&pos.y)) {
+            return;
+        }
+        ui.painter()
+            .rect_stroke(row.response.rect, 2.0, ui.visuals().selection.stroke);
+        if ui.input(|i| i.pointer.any_released()) {
+            self.move_group(dragged, group_id);
+        }
+    }
+
+    /// Moves the group to the place in line of the group `onto`, which moves up or down one.
+    fn move_group(&mut self, group_id: usize, onto: usize) {
+        let groups = self.task_queue.groups();
+        let Some(position) = groups.iter().position(|group| group.id == onto) else {
+            return;
+        };
+        if let Err(e) = self.task_queue.move_group(group_id, position) {
+            log::error!("Cannot move group {}: {}", group_id, e);
+        }
+    }
+
+    /// Pauses or resumes the whole queue, auditing each tracked task it paused or resumed.
+    fn ui_queue_pause(&mut self, ui: &mut egui::Ui) {
+        let paused = self.task_queue.is_paused();
+        ui.horizontal(|ui| {
+            let (label, action) = if paused {
+                ("Resume queue", AuditAction::Resume)
+            } else {
+                ("Pause queue", AuditAction::Pause)
+            };
+            if ui.button(label).clicked() {
+                let task_ids = if paused {
+                    self.task_queue.resume_all()
+                } else {
+                    self.task_queue.pause_all()
+                };
+                for task_id in task_ids {
+                    if self.polled.remove(&task_id).is_some() {
+                        self.audit(task_id, action, Interface::Ui);
+                    }
+                }
+            }
+            if paused {
+                ui.weak("Queue paused: no task starts until it is resumed.");
+            }
+        });
+    }
+
+    /// Pauses, resumes or cancels every task in the group, auditing it for each tracked one.
+    fn group_action(&mut self, group_id: usize, action: AuditAction) {
+        let Ok(group) = self.task_queue.group(group_id) else {
+            return;
+        };
+        let result = match action {
+            AuditAction::Pause => self.task_queue.pause_group(group_id),
+            AuditAction::Resume => self.task_queue.resume_group(group_id),
+            AuditAction::Cancel => self.task_queue.cancel_group(group_id),
+            AuditAction::Add => return,
+        };
+        if let Err(e) = result {
+            log::error!("Group {} {} error: {}", group_id, action, e);
+        }
+        for task_id in group.tasks {
+            if self.polled.remove(&task_id).is_some() {
+                self.audit(task_id, action, Interface::Ui);
+            }
+        }
+    }
+
+    /// Tooltip of a task row. The detail is only fetched while the tooltip is shown.
+    fn ui_task_detail(&self, ui: &mut egui::Ui, task_id: TaskId) {
+        let detail = match self.task_queue.task_detail(task_id) {
+            Ok(detail) => detail,
+            Err(e) => {
+                ui.label(e.to_string());
+                return;
+            }
+        };
+        ui.label(format!(
+            "{} task, {}",
+            detail.record.kind, detail.record.status
+        ));
+        ui.label(format!("{} priority", detail.record.priority));
+        if !detail.record.tags.is_empty() {
+            ui.label(format!("Tags: {}", detail.record.tags.join(", ")));
+        }
+        if let Some(deadline) = detail.record.deadline {
+            let now = now_millis();
+            if deadline > now {
+                ui.label(format!("Due in {}", format::duration_millis(now, deadline)));
+            } else {
+                ui.label(format!(
+                    "Due {} ago",
+                    format::duration_millis(deadline, now)
+                ));
+            }
+        }
+        if let Some(started_at) = detail.record.started_at {
+            let elapsed = format::duration_millis(started_at, now_millis());
+            ui.label(format!("Started {} ago", elapsed));
+        }
+        if let Some(message) = detail.message {
+            ui.label(message);
+        }
+        if let Some(resources) = detail.resources {
+            ui.label(format!("CPU time {}", format::duration(resources.cpu_time)));
+            if let Some(io_bytes) = resources.io_bytes {
+                ui.label(format!("{} of I/O", format::bytes(io_bytes)));
+            }
+        }
+        #[cfg(not(target_arch = "wasm32"))]
+        if let Some(limit) = detail.speed_limit.and_then(|limit| limit.get()) {
+            ui.label(format!("Limited to {}/s", format::bytes(limit)));
+        }
+        if !detail.record.annotations.is_empty() {
+            ui.separator();
+            egui::Grid::new(("task_annotations", task_id))
+                .num_columns(2)
+                .show(ui, |ui| {
+                    for (key, value) in &detail.record.annotations {
+                        ui.weak(key);
+                        ui.label(value);
+                        ui.end_row();
+                    }
+                });
+        }
+    }
+
+    /// The palette to tag a task with, and a button to clear its tag.
+    fn ui_color_tag(&mut self, ui: &mut egui::Ui, task_id: TaskId) {
+        ui.separator();
+        let current = self.color_tags.get(&task_id).copied();
+        ui.horizontal(|ui| {
+            ui.label("Color");
+            for tag in ColorTag::ALL {
+                let stroke = if current == Some(tag) {
+                    ui.visuals().widgets.active.fg_stroke
+                } else {
+                    egui::Stroke::NONE
+                };
+                let swatch = ui
+                    .add(
+                        egui::Button::new("")
+                            .fill(color32(tag.rgb()))
+                            .stroke(stroke)
+                            .min_size(egui::vec2(16.0, 16.0)),
+                    )
+                    .on_hover_text(tag.to_string());
+                a11y::name(&swatch, format!("Tag task {} {}", task_id, tag));
+                if swatch.clicked() {
+                    self.set_color_tag(task_id, Some(tag));
+                    ui.close_menu();
+                }
+            }
+            if current.is_some() && ui.button("None").clicked() {
+                self.set_color_tag(task_id, None);
+                ui.close_menu();
+            }
+        });
+    }
+
+    fn set_label(&mut self, task_id: TaskId, label: Option<String>) {
+        if let Err(e) = self.task_queue.set_label(task_id, label.clone()) {
+            log::error!("Cannot label task {}: {}", task_id, e);
+            return;
+        }
+        match label {
+            Some(label) => self.labels.insert(task_id, label),
+            None => self.labels.remove(&task_id),
+        };
+    }
+
+    fn set_color_tag(&mut self, task_id: TaskId, tag: Option<ColorTag>) {
+        if let Err(e) = self.task_queue.set_color_tag(task_id, tag) {
+            log::error!("Cannot tag task {}: {}", task_id, e);
+            return;
+        }
+        match tag {
+            Some(tag) => self.color_tags.insert(task_id, tag),
+            None => self.color_tags.remove(&task_id),
+        };
+    }
+
+    /// Adds the task described in the New task window to run every `every`, returning the
+    /// id of its first run.
+    fn add_recurring_task(&mut self, every: Duration) -> TaskId {
+        let registry = self.registry.clone();
+        let kind = self.new_task_kind.clone();
+        let params = self.new_task_params.clone();
+        let recurrence = self.task_queue.add_recurring_task(
+            move || {
+                registry
+                    .create(&kind, &params)
+                    .expect("Panicked at recurring task: Parameters accepted once turned away")
+            },
+            every,
+        );
+        self.task_queue
+            .recurrence(recurrence)
+            .expect("Panicked at add_recurring_task: Recurrence just added not found")
+            .run
+    }
+
+    /// A button to stop adding runs of the task, if it is the latest run of a recurrence.
+    fn ui_recurrence(&mut self, ui: &mut egui::Ui, task_id: TaskId) {
+        let Some(recurrence) = self.task_queue.recurrence_of(task_id) else {
+            return;
+        };
+        ui.separator();
+        if ui.button("Stop repeating").clicked() {
+            if let Err(e) = self.task_queue.stop_recurrence(recurrence) {
+                log::error!("Cannot stop recurrence {}: {}", recurrence, e);
+            }
+            ui.close_menu();
+        }
+    }
+
+    /// Buttons to give the task a deadline some hours from now, or take it away.
+    fn ui_deadline(&mut self, ui: &mut egui::Ui, task_id: TaskId) {
+        ui.separator();
+        ui.horizontal(|ui| {
+            ui.label("Due in");
+            for hours in DEADLINE_HOURS {
+                if ui.button(format!("{} h", hours)).clicked() {
+                    self.set_deadline(task_id, Some(now_millis() + hours * 3_600_000));
+                    ui.close_menu();
+                }
+            }
+            if ui.button("No deadline").clicked() {
+                self.set_deadline(task_id, None);
+                ui.close_menu();
+            }
+        });
+    }
+
+    fn set_deadline(&mut self, task_id: TaskId, deadline: Option<u64>) {
+        if let Err(e) = self.task_queue.set_deadline(task_id, deadline) {
+            log::error!("Cannot set the deadline of task {}: {}", task_id, e);
+        }
+    }
+
+    /// Tells of the tasks found to be at risk of missing their deadline, by notification
+    /// if configured, and shows the priority the queue may have raised them to.
+    #[cfg(not(target_arch = "wasm32"))]
+    fn escalate_deadlines(&mut self) {
+        let Some(events) = &self.deadline_events else {
+            return;
+        };
+        let events: Vec<DeadlineAtRisk> = events.try_iter().collect();
+        for event in events {
+            log::info!("Task {} may miss its deadline", event.id);
+            if event.priority.is_normal() {
+                self.priorities.remove(&event.id);
+            } else {
+                self.priorities.insert(event.id, event.priority);
+            }
+            self.announcement = format!("Task {} may miss its deadline", event.id);
+            if !self.config.deadlines.notify {
+                continue;
+            }
+            let body = format!(
+                "Task #{} looks like finishing {} after its deadline",
+                event.id,
+                format::duration_millis(event.deadline, event.predicted_finish)
+            );
+            if let Err(e) = notify_rust::Notification::new()
+                .appname("Task Queue")
+                .summary("Deadline at risk")
+                .body(&body)
+                .show()
+            {
+                log::error!("Cannot show notification: {}", e);
+            }
+        }
+    }
+
+    /// The task's priority, as a dropdown to change it while the task is still queued.
+    fn ui_priority(&mut self, ui: &mut egui::Ui, task_id: TaskId) {
+        let current = self.priorities.get(&task_id).copied().unwrap_or_default();
+        let queued = matches!(
+            self.task_queue.status(task_id),
+            Ok(TaskStatus::Scheduled | TaskStatus::Queued)
+        );
+        let text = egui::RichText::new(current.to_string()).small();
+        let text = match current.rgb() {
+            Some(rgb) => text.color(color32(rgb)),
+            None => text,
+        };
+        let mut chosen = current;
+        let response = ui
+            .add_enabled_ui(queued, |ui| {
+                egui::ComboBox::from_id_source(("task_priority", task_id))
+                    .width(PRIORITY_WIDTH)
+                    .selected_text(text)
+                    .show_ui(ui, |ui| {
+                        for priority in Priority::ALL.into_iter().rev() {
+                            ui.selectable_value(&mut chosen, priority, priority.to_string());
+                        }
+                    })
+            })
+            .inner
+            .response;
+        a11y::name(&response, format!("Task {} priority, {}", task_id, current));
+        response.on_disabled_hover_text("Only queued tasks can be reprioritized");
+        if chosen != current {
+            self.set_priority(task_id, chosen);
+        }
+    }
+
+    fn set_priority(&mut self, task_id: TaskId, priority: Priority) {
+        if let Err(e) = self.task_queue.set_priority(task_id, priority) {
+            log::error!("Cannot reprioritize task {}: {}", task_id, e);
+            return;
+        }
+        if priority.is_normal() {
+            self.priorities.remove(&task_id);
+        } else {
+            self.priorities.insert(task_id, priority);
+        }
+    }
+
+    /// Picks the color whose tasks are listed, once any task is tagged.
+    fn ui_color_filter(&mut self, ui: &mut egui::Ui) {
+        if self.color_tags.is_empty() && self.layout.color_filter.is_none() {
+            return;
+        }
+        egui::ComboBox::from_label("Color")
+            .selected_text(
+                self.layout
+                    .color_filter
+                    .map_or("All".to_owned(), |tag| tag.to_string()),
+            )
+            .show_ui(ui, |ui| {
+                ui.selectable_value(&mut self.layout.color_filter, None, "All");
+                for tag in ColorTag::ALL {
+                    let text = egui::RichText::new(tag.to_string()).color(color32(tag.rgb()));
+                    ui.selectable_value(&mut self.layout.color_filter, Some(tag), text);
+                }
+            });
+    }
+
+    /// Adjusts a throttleable task's own speed limit while it runs. The config's global
+    /// bandwidth cap still applies on top of it.
+    #[cfg(not(target_arch = "wasm32"))]
+    fn ui_speed_limit(&self, ui: &mut egui::Ui, task_id: TaskId) {
+        let Some(speed_limit) = self
+            .task_queue
+            .task_detail(task_id)
+            .ok()
+            .and_then(|detail| detail.speed_limit)
+        else {
+            return;
+        };
+        ui.separator();
+        let current = speed_limit.get();
+        let mut limited = current.is_some();
+        let mut kib = current.map_or(DEFAULT_SPEED_LIMIT_KIB, |bytes| bytes / 1024);
+        let mut changed = ui.checkbox(&mut limited, "Limit speed").changed();
+        changed |= ui
+            .add_enabled(
+                limited,
+                egui::Slider::new(&mut kib, SPEED_LIMIT_RANGE_KIB)
+                    .logarithmic(true)
+                    .suffix(" KiB/s"),
+            )
+            .changed();
+        if changed {
+            speed_limit.set(limited.then(|| kib.max(1) * 1024));
+        }
+    }
+
+    /// Carries out a finished batch's action, or starts the countdown to a shutdown or sleep.
+    #[cfg(not(target_arch = "wasm32"))]
+    fn after_batch(&mut self, batch: BatchSummary, now: f64) {
+        log::info!("Batch finished: {}", batch);
+        if batch.action.needs_countdown() {
+            self.power_countdown = Some(PowerCountdown {
+                batch,
+                deadline: now + POWER_COUNTDOWN_SECONDS,
+            });
+        } else if let Err(e) = batch.action.run(&batch) {
+            log::error!("{}", e);
+            self.file_notice = Some(e);
+        }
+    }
+
+    /// Counts down to a finished batch's shutdown or sleep, which the user can cancel or
+    /// bring forward.
+    #[cfg(not(target_arch = "wasm32"))]
+    fn ui_power_countdown(&mut self, ctx: &egui::Context) {
+        let Some(countdown) = &self.power_countdown else {
+            return;
+        };
+        let remaining = countdown.deadline - ctx.input(|i| i.time);
+        let mut now = remaining <= 0.0;
+        let mut cancelled = false;
+        egui::Window::new("Batch finished")
+            .collapsible(false)
+            .anchor(egui::Align2::CENTER_CENTER, [0.0, 0.0])
+            .show(ctx, |ui| {
+                ui.label(countdown.batch.to_string());
+                ui.strong(format!(
+                    "{} in {:.0} seconds.",
+                    countdown.batch.action,
+                    remaining.max(0.0).ceil()
+                ));
+                ui.horizontal(|ui| {
+                    now |= ui.button("Now").clicked();
+                    cancelled = ui.button("Cancel").clicked();
+                });
+            });
+        if cancelled {
+            log::info!("{} cancelled", countdown.batch.action);
+            self.power_countdown = None;
+        } else if now {
+            if let Err(e) = countdown.batch.action.run(&countdown.batch) {
+                log::error!("{}", e);
+                self.file_notice = Some(e);
+            }
+            self.power_countdown = None;
+        } else {
+            ctx.request_repaint_after(Duration::from_millis(250));
+        }
+    }
+
+    /// Completion toasts in the bottom right corner, newest at the bottom.
+    fn ui_toasts(&mut self, ctx: &egui::Context) {
+        let now = ctx.input(|i| i.time);
+        self.toasts
+            .retain(|toast| now - toast.shown_at < TOAST_SECONDS);
+        let mut dismissed = HashSet::new();
+        let mut offset = -8.0;
+        for toast in self.toasts.iter().rev() {
+            let response = egui::Area::new(egui::Id::new(("completion_toast", toast.task_id)))
+                .anchor(egui::Align2::RIGHT_BOTTOM, [-8.0, offset])
+                .show(ctx, |ui| {
+                    egui::Frame::popup(ui.style()).show(ui, |ui| {
+                        ui.label(format!("{} task #{} finished", toast.kind, toast.task_id));
+                        ui.label(toast.path.display().to_string());
+                        ui.horizontal(|ui| {
+                            let acted = ui_file_actions(ui, &toast.path);
+                            if acted || ui.button("Dismiss").clicked() {
+                                dismissed.insert(toast.task_id);
+                            }
+                        });
+                    });
+                })
+                .response;
+            offset -= response.rect.height() + 8.0;
+        }
+        self.toasts
+            .retain(|toast| !dismissed.contains(&toast.task_id));
+    }
+
+    #[cfg(all(feature = "secrets", not(target_arch = "wasm32")))]
+    fn init_secrets(&mut self) {
+        match self.config.resolved_secrets_file() {
+            Ok(path) => {
+                let secrets = SecretStore::open(&path);
+                log::info!("Keeping secrets in: {}", secrets.describe());
+                self.secrets = Some(sync_Arc::new(secrets));
+            }
+            Err(e) => log::error!("Cannot locate the secrets file: {}", e),
+        }
+    }
+
+    /// `password` with any secret references in it filled in. A reference that cannot be
+    /// resolved is logged and leaves no password.
+    #[cfg(all(any(feature = "email", feature = "mqtt"), not(target_arch = "wasm32")))]
+    fn resolve_password(&self, password: Option<String>) -> Option<String> {
+        #[cfg(feature = "secrets")]
+        if let (Some(secrets), Some(password)) = (&self.secrets, &password) {
+            return secrets
+                .resolve(password)
+                .map_err(|e| log::error!("Cannot resolve password: {}", e))
+                .ok();
+        }
+        password
+    }
+
+    fn start_stress_test(&mut self) {
+        let task_ids = stress::enqueue(&self.task_queue, STRESS_TASK_COUNT);
+        log::info!("Stress test: added {} tasks", task_ids.len());
+        self.task_ids.extend(task_ids);
+    }
+
+    fn init_registry(&mut self) {
+        #[allow(unused_mut)]
+        let mut registry = TaskKindRegistry::with_plugins(&self.config);
+        #[cfg(all(feature = "secrets", not(target_arch = "wasm32")))]
+        if let Some(secrets) = &self.secrets {
+            registry.set_secrets(secrets.clone());
+        }
+        if let Some(info) = registry.kinds().first() {
+            self.new_task_kind = info.name.clone();
+            self.new_task_params = default_params(info);
+        }
+        self.registry = sync_Arc::new(registry);
+    }
+
+    fn ui_new_task(&mut self, ui: &mut egui::Ui) {
+        let mut selected = None;
+        egui::ComboBox::from_label("Kind")
+            .selected_text(&self.new_task_kind)
+            .show_ui(ui, |ui| {
+                for info in self.registry.kinds() {
+                    if ui
+                        .selectable_label(info.name == self.new_task_kind, &info.name)
+                        .clicked()
+                    {
+                        selected = Some((info.name.clone(), default_params(info)));
+                    }
+                }
+            });
+        if let Some((kind, params)) = selected {
+            self.new_task_kind = kind;
+            self.new_task_params = params;
+            self.new_task_error = None;
+        }
+        if let Some(info) = self.registry.get(&self.new_task_kind) {
+            let invalid = match &self.new_task_rejected {
+                Some(rejected) if rejected.params == self.new_task_params => Some((
+                    rejected.name.clone(),
+                    rejected.message.clone(),
+                    ui.visuals().error_fg_color,
+                )),
+                _ => match self
+                    .registry
+                    .validate(&self.new_task_kind, &self.new_task_params)
+                {
+                    Err(RegistryError::InvalidParam { name, message }) => {
+                        Some((name, message, ui.visuals().warn_fg_color))
+                    }
+                    _ => None,
+                },
+            };
+            egui::Grid::new("new_task_params")
+                .num_columns(2)
+                .show(ui, |ui| {
+                    for spec in &info.params {
+                        let label = if spec.required {
+                            ui.label(format!("{} *", spec.name))
+                        } else {
+                            ui.label(&spec.name)
+                        };
+                        if let Some(description) = &spec.description {
+                            label.on_hover_text(description);
+                        }
+                        let value = self.new_task_params.entry(spec.name.clone()).or_default();
+                        let cell = ui.scope(|ui| match spec.param_type {
+                            ParamType::Bool => {
+                                let mut checked = value == "true";
+                                if ui.checkbox(&mut checked, "").changed() {
+                                    *value = checked.to_string();
+                                }
+                            }
+                            ParamType::String if !spec.choices.is_empty() => {
+                                egui::ComboBox::from_id_source(("new_task_param", &spec.name))
+                                    .selected_text(value.as_str())
+                                    .show_ui(ui, |ui| {
+                                        for choice in &spec.choices {
+                                            ui.selectable_value(value, choice.clone(), choice);
+                                        }
+                                    });
+                            }
+                            ParamType::String | ParamType::Number => {
+                                ui.text_edit_singleline(value);
+                            }
+                            ParamType::Env => ui_env_editor(ui, &spec.name, value),
+                            ParamType::Text => {
+                                ui.add(egui::TextEdit::multiline(value).desired_rows(3));
+                            }
+                        });
+                        ui.end_row();
+                        if let Some((_, message, color)) =
+                            invalid.as_ref().filter(|(name, _, _)| *name == spec.name)
+                        {
+                            ui.painter().rect_stroke(
+                                cell.response.rect.expand(2.0),
+                                2.0,
+                                egui::Stroke::new(1.0, *color),
+                            );
+                            ui.label("");
+                            ui.colored_label(*color, message);
+                            ui.end_row();
+                        }
+                        #[cfg(not(target_arch = "wasm32"))]
+                        if spec.output && !value.trim().is_empty() {
+                            ui.label("");
+                            ui_free_space(ui, Path::new(value.trim()));
+                            ui.end_row();
+                        }
+                    }
+                });
+            // Errors with a parameter to point at are shown on it instead.
+            if let Err(e @ RegistryError::UnknownKind(_)) = self
+                .registry
+                .validate(&self.new_task_kind, &self.new_task_params)
+            {
+                ui.colored_label(ui.visuals().warn_fg_color, e.to_string());
+            }
+        }
+        #[cfg(all(feature = "remote-agent", not(target_arch = "wasm32")))]
+        self.ui_new_task_agent(ui);
+        let duplicate = self
+            .registry
+            .dedupe_key(&self.new_task_kind, &self.new_task_params)
+            .and_then(|key| self.dedupe_keys.get(&key).copied());
+        if let Some(task_id) = duplicate {
+            ui.horizontal(|ui| {
+                ui.colored_label(
+                    ui.visuals().warn_fg_color,
+                    format!("Task #{} has the same parameters", task_id),
+                );
+                if ui.button("Go to task").clicked() {
+                    self.scroll_to_task = Some(task_id);
+                    self.show_new_task = false;
+                }
+            });
+        }
+        ui.horizontal(|ui| {
+            ui.label("Label");
+            ui.text_edit_singleline(&mut self.new_task_label)
+                .on_hover_text("Shown in the task list in place of the task's number");
+        });
+        ui.horizontal(|ui| {
+            let repeats = self.new_task_repeat_minutes != 0;
+            ui.add_enabled_ui(!repeats, |ui| {
+                ui.label("Start in");
+                ui.add(
+                    egui::DragValue::new(&mut self.new_task_delay_minutes)
+                        .clamp_range(0..=7 * 24 * 60)
+                        .suffix(" min"),
+                )
+                .on_hover_text("0 starts the task as soon as there is room for it");
+            });
+            ui.label("Repeat every");
+            ui.add(
+                egui::DragValue::new(&mut self.new_task_repeat_minutes)
+                    .clamp_range(0..=7 * 24 * 60)
+                    .suffix(" min"),
+            )
+            .on_hover_text("0 runs the task once; otherwise the first run starts right away");
+        });
+        let add = if duplicate.is_some() {
+            "Add anyway"
+        } else {
+            "Add"
+        };
+        if ui.button(add).clicked() {
+            #[cfg(all(feature = "remote-agent", not(target_arch = "wasm32")))]
+            if !self.new_task_agent.is_empty() {
+                self.add_remote_task();
+                return;
+            }
+            match self
+                .registry
+                .create(&self.new_task_kind, &self.new_task_params)
+            {
+                Ok(task) => {
+                    let task_id = match (self.new_task_repeat_minutes, self.new_task_delay_minutes)
+                    {
+                        (0, 0) => self.task_queue.add_task(task),
+                        (0, minutes) => self.task_queue.schedule_task(
+                            task,
+                            crate::app::executor::Instant::now()
+                                + Duration::from_secs(minutes * 60),
+                        ),
+                        (minutes, _) => self.add_recurring_task(Duration::from_secs(minutes * 60)),
+                    };
+                    let label = self.new_task_label.trim();
+                    if !label.is_empty() {
+                        self.set_label(task_id, Some(label.to_owned()));
+                    }
+                    self.audit(task_id, AuditAction::Add, Interface::Ui);
+                    self.task_ids.push(task_id);
+                    self.new_task_error = None;
+                    self.new_task_rejected = None;
+                    #[cfg(target_arch = "wasm32")]
+                    {
+                        let saved = SavedTask {
+                            kind: self.new_task_kind.clone(),
+                            params: self.new_task_params.clone(),
+                        };
+                        self.remember_dedupe_key(task_id, &saved.kind, &saved.params);
+                        self.saved_tasks.insert(task_id, saved);
+                    }
+                    #[cfg(not(target_arch = "wasm32"))]
+                    self.remember_spec(
+                        task_id,
+                        TaskSpec {
+                            kind: self.new_task_kind.clone(),
+                            params: self.new_task_params.clone(),
+                        },
+                    );
+                }
+                Err(RegistryError::InvalidParam { name, message }) => {
+                    self.new_task_error = None;
+                    self.new_task_rejected = Some(RejectedParam {
+                        name,
+                        message,
+                        params: self.new_task_params.clone(),
+                    });
+                }
+                Err(e) => self.new_task_error = Some(e.to_string()),
+            }
+        }
+        if let Some(e) = &self.new_task_error {
+            ui.colored_label(egui::Color32::RED, e);
+        }
+    }
+
+    #[cfg(all(feature = "remote-agent", not(target_arch = "wasm32")))]
+    fn ui_new_task_agent(&mut self, ui: &mut egui::Ui) {
+        if self.config.remote_agents.is_empty() {
+            return;
+        }
+        let selected_text = if self.new_task_agent.is_empty() {
+            "This machine"
+        } else {
+            &self.new_task_agent
+        };
+        egui::ComboBox::from_label("Run on")
+            .selected_text(selected_text.to_owned())
+            .show_ui(ui, |ui| {
+                ui.selectable_value(&mut self.new_task_agent, String::new(), "This machine");
+                for agent in &self.config.remote_agents {
+                    ui.selectable_value(&mut self.new_task_agent, agent.name.clone(), &agent.name);
+                }
+            });
+        if !self.new_task_agent.is_empty() {
+            ui.horizontal(|ui| {
+                ui.label("Fetch files");
+                ui.text_edit_singleline(&mut self.new_task_fetch)
+                    .on_hover_text(
+                        "Paths on the agent to copy back when the task completes, separated by ';'",
+                    );
+            });
+        }
+    }
+
+    /// Dispatches the New task window's task to the selected agent, connecting if needed.
+    #[cfg(all(feature = "remote-agent", not(target_arch = "wasm32")))]
+    fn add_remote_task(&mut self) {
+        use crate::app::remote_agent::AgentClient;
+
+        let Some(config) = self
+            .config
+            .remote_agents
+            .iter()
+            .find(|agent| agent.name == self.new_task_agent)
+        else {
+            self.new_task_error = Some(format!("Unknown agent: {}", self.new_task_agent));
+            return;
+        };
+        let client = match self.agent_clients.get(&config.name) {
+            Some(client) if client.is_connected() => client.clone(),
+            _ => match AgentClient::connect(config) {
+                Ok(client) => {
+                    self.agent_clients
+                        .insert(config.name.clone(), client.clone());
+                    client
+                }
+                Err(e) => {
+                    self.new_task_error = Some(e.to_string());
+                    return;
+                }
+            },
+        };
+        let fetch = self
+            .new_task_fetch
+            .split(';')
+            .map(str::trim)
+            .filter(|path| !path.is_empty())
+            .map(str::to_owned)
+            .collect();
+        let task = client.task(&self.new_task_kind, self.new_task_params.clone(), fetch);
+        let task_id = self.task_queue.add_task(task);
+        self.audit(task_id, AuditAction::Add, Interface::Ui);
+        self.task_ids.push(task_id);
+        self.new_task_error = None;
+    }
+
+    fn uses_eframe_history(&self) -> bool {
+        self.config.store == StoreBackend::Eframe || !SQLITE_AVAILABLE
+    }
+
+    fn init_task_queue(&mut self) {
+        let history = std::mem::take(&mut self.history);
+        if self.uses_eframe_history() {
+            if self.config.store == StoreBackend::Sqlite {
+                log::warn!("The sqlite store requires the `sqlite` feature, using eframe storage");
+            }
+            #[cfg(not(target_arch = "wasm32"))]
+            self.open_history_file();
+            self.task_queue.restore_history(history);
+        } else {
+            self.open_sqlite_store();
+        }
+        #[cfg(not(target_arch = "wasm32"))]
+        self.task_queue
+            .set_history_limit(self.config.history_memory_limit);
+        self.task_queue
+            .set_history_retention(self.config.history_retention());
+        self.task_queue
+            .set_progress_granularity(self.config.progress_granularity());
+        self.task_queue
+            .set_boost_at_risk(self.config.deadlines.boost_priority);
+        self.task_queue.set_start_rate(self.config.start_rate);
+    }
+
+    /// Gives the queue somewhere to move history that exceeds `history_memory_limit`.
+    #[cfg(not(target_arch = "wasm32"))]
+    fn open_history_file(&mut self) {
+        let Some(path) = HistoryFile::default_path() else {
+            log::warn!("No cache directory, history is kept in memory");
+            return;
+        };
+        match HistoryFile::create(&path) {
+            Ok(file) => self.task_queue.set_history_file(file),
+            Err(e) => log::error!("Cannot create {}: {}", path.display(), e),
+        }
+    }
+
+    /// Moves the tasks the last run left interrupted to the history, to be offered for
+    /// restarting, and has the queue journal this run's.
+    #[cfg(not(target_arch = "wasm32"))]
+    fn open_journal(&mut self) {
+        let Some(path) = Journal::default_path() else {
+            log::warn!("No data directory, interrupted tasks cannot be recovered");
+            return;
+        };
+        let (journal, interrupted) = match Journal::open(&path) {
+            Ok(opened) => opened,
+            Err(e) => {
+                log::error!("Cannot open {}: {}", path.display(), e);
+                return;
+            }
+        };
+        if !interrupted.is_empty() {
+            log::warn!(
+                "{} tasks were interrupted when the app last stopped",
+                interrupted.len()
+            );
+            let now = now_millis();
+            self.task_queue
+                .restore_interrupted(interrupted.iter().map(|task| task.record(now)).collect());
+        }
+        let journal = sync_Arc::new(journal);
+        self.task_queue.set_journal(journal.clone());
+        self.journal = Some(journal);
+        self.interrupted = interrupted;
+    }
+
+    /// Lists the interrupted tasks, each with a button to add it again.
+    #[cfg(not(target_arch = "wasm32"))]
+    fn ui_interrupted(&mut self, ui: &mut egui::Ui) {
+        ui.label(format!(
+            "{} tasks were still running when the app last stopped.",
+            format::count(self.interrupted.len())
+        ));
+        let mut restart = Vec::new();
+        egui::ScrollArea::vertical()
+            .max_height(240.0)
+            .show(ui, |ui| {
+                for (index, task) in self.interrupted.iter().enumerate() {
+                    ui.horizontal(|ui| {
+                        ui.label(format!("#{} {}", task.id, task.kind));
+                        let button =
+                            ui.add_enabled(task.spec.is_some(), egui::Button::new("Restart"));
+                        if button
+                            .on_disabled_hover_text("This task cannot be created again")
+                            .clicked()
+                        {
+                            restart.push(index);
+                        }
+                    });
+                }
+            });
+        ui.horizontal(|ui| {
+            if ui.button("Restart all").clicked() {
+                restart = (0..self.interrupted.len()).collect();
+            }
+            if ui.button("Dismiss").clicked() {
+                self.interrupted.clear();
+                self.interrupted_error = None;
+            }
+        });
+        if let Some(e) = &self.interrupted_error {
+            ui.colored_label(ui.visuals().error_fg_color, e);
+        }
+        self.restart_interrupted(&restart);
+    }
+
+    /// Adds the interrupted tasks at `indices` again, as new tasks that start over.
+    #[cfg(not(target_arch = "wasm32"))]
+    fn restart_interrupted(&mut self, indices: &[usize]) {
+        if indices.is_empty() {
+            return;
+        }
+        let mut errors = Vec::new();
+        let mut kept = Vec::new();
+        for (index, task) in std::mem::take(&mut self.interrupted)
+            .into_iter()
+            .enumerate()
+        {
+            let spec = match &task.spec {
+                Some(spec) if indices.contains(&index) => spec.clone(),
+                _ => {
+                    kept.push(task);
+                    continue;
+                }
+            };
+            match self.enqueue(&spec) {
+                Ok(task_id) => self.audit(task_id, AuditAction::Add, Interface::Ui),
+                Err(e) => {
+                    errors.push(format!("#{}: {}", task.id, e));
+                    kept.push(task);
+                }
+            }
+        }
+        self.interrupted = kept;
+        self.interrupted_error = (!errors.is_empty()).then(|| errors.join("; "));
+    }
+
+    #[cfg(all(feature = "sqlite", not(target_arch = "wasm32")))]
+    fn open_sqlite_store(&mut self) {
+        use crate::app::store::sqlite::SqliteStore;
+
+        let opened = self
+            .config
+            .resolved_sqlite_path()
+            .map_err(|e| e.to_string())
+            .and_then(|path| SqliteStore::open(&path).map_err(|e| e.to_string()));
+        match opened {
+            Ok(store) => self.task_queue = sync_Arc::new(TaskQueue::with_store(Box::new(store))),
+            Err(e) => {
+                log::error!("Failed to open sqlite store: {}", e);
+                self.config_error = Some(e);
+            }
+        }
+    }
+
+    #[cfg(not(all(feature = "sqlite", not(target_arch = "wasm32"))))]
+    fn open_sqlite_store(&mut self) {}
+
+    #[cfg(not(target_arch = "wasm32"))]
+    fn init_config(&mut self, ctx: &egui::Context) {
+        match AppConfig::default_path() {
+            Ok(path) => {
+                match AppConfig::load_or_default(&path) {
+                    Ok(config) => self.config = config,
+                    Err(e) => {
+                        log::error!("Failed to load {}: {}", path.display(), e);
+                        self.config_error = Some(e.to_string());
+                    }
+                }
+                self.config_watcher = Some(ConfigWatcher::new(path));
+            }
+            Err(e) => {
+                log::error!("{}", e);
+                self.config_error = Some(e.to_string());
+            }
+        }
+        self.apply_config(ctx);
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    fn init_config(&mut self, ctx: &egui::Context) {
+        match web_storage::load_config() {
+            Ok(config) => self.config = config,
+            Err(e) => {
+                log::error!("Failed to load the saved config: {}", e);
+                self.config_error = Some(e.to_string());
+            }
+        }
+        self.apply_config(ctx);
+    }
+
+    /// Lets the config be edited as TOML and saves it to localStorage.
+    #[cfg(target_arch = "wasm32")]
+    fn ui_config_editor(&mut self, ui: &mut egui::Ui) {
+        let Some(draft) = &mut self.config_draft else {
+            if ui.button("Edit config").clicked() {
+                match self.config.to_toml_string() {
+                    Ok(toml) => self.config_draft = Some(toml),
+                    Err(e) => self.config_error = Some(e.to_string()),
+                }
+            }
+            return;
+        };
+        ui.add(
+            egui::TextEdit::multiline(draft)
+                .code_editor()
+                .desired_width(f32::INFINITY),
+        );
+        let (save, cancel) = ui
+            .horizontal(|ui| (ui.button("Save").clicked(), ui.button("Cancel").clicked()))
+            .inner;
+        if save {
+            let saved = AppConfig::from_toml_str(draft)
+                .and_then(|config| web_storage::save_config(&config).map(|()| config));
+            match saved {
+                Ok(config) => {
+                    log::info!("Config saved");
+                    self.config = config;
+                    self.config_error = None;
+                    self.config_draft = None;
+                    self.apply_config(ui.ctx());
+                }
+                Err(e) => self.config_error = Some(e.to_string()),
+            }
+        } else if cancel {
+            self.config_draft = None;
+        }
+    }
+
+    /// Enqueues the tasks that were unfinished when the page was last saved.
+    #[cfg(target_arch = "wasm32")]
+    fn restore_saved_tasks(&mut self) {
+        for saved in web_storage::load_tasks() {
+            match self.registry.create(&saved.kind, &saved.params) {
+                Ok(task) => {
+                    let task_id = self.task_queue.add_task(task);
+                    self.task_ids.push(task_id);
+                    self.remember_dedupe_key(task_id, &saved.kind, &saved.params);
+                    self.saved_tasks.insert(task_id, saved);
+                }
+                Err(e) => log::error!("Cannot restore a '{}' task: {}", saved.kind, e),
+            }
+        }
+    }
+
+    /// Saves the tasks from the New task window that are still queued, running or paused.
+    #[cfg(target_arch = "wasm32")]
+    fn save_unfinished_tasks(&mut self) {
+        let queue = &self.task_queue;
+        self.saved_tasks.retain(|id, _| {
+            matches!(
+                queue.progress(*id),
+                Ok(PollResult::Pending(_) | PollResult::Paused(_))
+            )
+        });
+        let tasks: Vec<SavedTask> = self.saved_tasks.values().cloned().collect();
+        web_storage::save_tasks(&tasks);
+    }
+
+    fn apply_config(&mut self, ctx: &egui::Context) {
+        ctx.set_visuals(self.config.theme.visuals());
+        self.config.apply_globals();
+        #[cfg(not(target_arch = "wasm32"))]
+        if self.power.governor.is_throttled() {
+            executor::set_worker_limit(
+                self.power
+                    .governor
+                    .concurrency(self.config.concurrency, &self.config.governor),
+            );
+        }
+        self.task_queue
+            .set_progress_granularity(self.config.progress_granularity());
+        self.task_queue
+            .set_boost_at_risk(self.config.deadlines.boost_priority);
+        self.task_queue.set_start_rate(self.config.start_rate);
+        #[cfg(not(target_arch = "wasm32"))]
+        self.task_queue
+            .set_history_limit(self.config.history_memory_limit);
+        self.task_queue
+            .set_history_retention(self.config.history_retention());
+        #[cfg(debug_assertions)]
+        {
+            if self.config.chaos.is_some() {
+                log::warn!("Chaos mode is on: tasks will stall, fail and pause at random");
+            }
+            self.task_queue.set_chaos(self.config.chaos.clone());
+        }
+        #[cfg(not(target_arch = "wasm32"))]
+        self.apply_watch_folder();
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    fn apply_watch_folder(&mut self) {
+        self.watch_drafts = WatchDrafts::from_config(&self.config);
+        let folder = self.config.watch_folder.as_deref();
+        if self.folder_watcher.as_ref().map(FolderWatcher::folder) == folder {
+            return;
+        }
+        self.folder_watcher = folder.and_then(|folder| match FolderWatcher::new(folder) {
+            Ok(watcher) => {
+                log::info!("Watching {} for new files", folder.display());
+                Some(watcher)
+            }
+            Err(e) => {
+                log::error!("Cannot watch {}: {}", folder.display(), e);
+                self.config_error = Some(format!("Cannot watch {}: {}", folder.display(), e));
+                None
+            }
+        });
+    }
+
+    /// Enqueues a task for each file that arrived in the watch folder and matches a rule.
+    #[cfg(not(target_arch = "wasm32"))]
+    fn process_watch_folder(&mut self) {
+        while let Some(path) = self
+            .folder_watcher
+            .as_ref()
+            .and_then(FolderWatcher::try_recv)
+        {
+            let Some(spec) = task_for_file(&self.config.watch_rules, &path) else {
+                log::debug!("No watch rule for {}", path.display());
+                continue;
+            };
+            match self.enqueue(&spec) {
+                Ok(task_id) => {
+                    log::info!(
+                        "Added {} task {} for {}",
+                        spec.kind,
+                        task_id,
+                        path.display()
+                    );
+                }
+                Err(e) => log::error!("Watch rule for {} failed: {}", path.display(), e),
+            }
+        }
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    fn ui_watch_rules(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            ui.label("Watch folder");
+            ui.text_edit_singleline(&mut self.watch_drafts.folder);
+        });
+        let mut remove = None;
+        egui::Grid::new("watch_rules_grid")
+            .num_columns(4)
+            .striped(true)
+            .show(ui, |ui| {
+                ui.label("Extensions");
+                ui.label("Kind");
+                ui.label("Params");
+                ui.end_row();
+                for (index, rule) in self.watch_drafts.rules.iter_mut().enumerate() {
+                    ui.text_edit_singleline(&mut rule.extensions)
+                        .on_hover_text("Comma-separated, e.g. jpg, png");
+                    ui.text_edit_singleline(&mut rule.kind);
+                    ui.text_edit_singleline(&mut rule.params)
+                        .on_hover_text("key=value pairs separated by ';'; {path} is the new file");
+                    if ui.button("Remove").clicked() {
+                        remove = Some(index);
+                    }
+                    ui.end_row();
+                }
+            });
+        if let Some(index) = remove {
+            self.watch_drafts.rules.remove(index);
+        }
+        ui.horizontal(|ui| {
+            if ui.button("Add rule").clicked() {
+                self.watch_drafts.rules.push(WatchRuleDraft::default());
+            }
+            if ui.button("Save rules").clicked() {
+                self.save_watch_rules();
+            }
+        });
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    fn save_watch_rules(&mut self) {
+        let mut config = self.config.clone();
+        self.watch_drafts.apply_to(&mut config);
+        self.save_config(&config);
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    fn save_config(&mut self, config: &AppConfig) {
+        let Some(path) = self.config_watcher.as_ref().map(|w| w.path().to_owned()) else {
+            return;
+        };
+        // The config watcher picks the saved file up and applies it on the next frame.
+        if let Err(e) = config.save(&path) {
+            log::error!("Failed to save config: {}", e);
+            self.config_error = Some(e.to_string());
+        }
+    }
+
+    /// Adds and deletes secrets; their values are never shown.
+    #[cfg(all(feature = "secrets", not(target_arch = "wasm32")))]
+    fn ui_secrets(&mut self, ui: &mut egui::Ui) {
+        let Some(secrets) = self.secrets.clone() else {
+            return;
+        };
+        let drafts = &mut self.secret_drafts;
+        ui.label("Secrets")
+            .on_hover_text("Refer to a secret in task parameters or passwords as ${secret:NAME}");
+        ui.label(secrets.describe());
+        if secrets.is_locked() {
+            ui.horizontal(|ui| {
+                ui.add(egui::TextEdit::singleline(&mut drafts.passphrase).password(true));
+                if ui.button("Unlock").clicked() {
+                    drafts.error = secrets
+                        .unlock(&drafts.passphrase)
+                        .err()
+                        .map(|e| e.to_string());
+                    drafts.passphrase.clear();
+                }
+            });
+        } else {
+            for name in secrets.names() {
+                ui.horizontal(|ui| {
+                    ui.label(&name);
+                    if ui.small_button("Delete").clicked() {
+                        drafts.error = secrets.delete(&name).err().map(|e| e.to_string());
+                    }
+                });
+            }
+            ui.horizontal(|ui| {
+                ui.add(
+                    egui::TextEdit::singleline(&mut drafts.name)
+                        .hint_text("Name")
+                        .desired_width(100.0),
+                );
+                ui.add(
+                    egui::TextEdit::singleline(&mut drafts.value)
+                        .hint_text("Value")
+                        .password(true),
+                );
+                let name = drafts.name.trim();
+                if ui
+                    .add_enabled(!name.is_empty(), egui::Button::new("Save"))
+                    .clicked()
+                {
+                    drafts.error = secrets
+                        .set(name, &drafts.value)
+                        .err()
+                        .map(|e| e.to_string());
+                    if drafts.error.is_none() {
+                        drafts.name.clear();
+                    }
+                    drafts.value.clear();
+                }
+            });
+        }
+        if let Some(e) = &drafts.error {
+            ui.colored_label(egui::Color32::RED, e);
+        }
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    fn ui_power_settings(&mut self, ui: &mut egui::Ui) {
+        let mut power = self.config.power.clone();
+        let mut changed = ui
+            .checkbox(
+                &mut power.keep_awake,
+                "Keep the computer awake while tasks run",
+            )
+            .changed();
+        changed |= ui
+            .checkbox(&mut power.pause_on_battery, "Pause heavy tasks on battery")
+            .on_hover_text(
+                "Pauses tasks of the kinds in heavy_kinds in the config file, or all tasks \
+                 when none are listed, and resumes them on AC power",
+            )
+            .changed();
+        let mut network = self.config.network.clone();
+        changed |= ui
+            .checkbox(
+                &mut network.pause_on_metered,
+                "Pause network tasks on metered connections",
+            )
+            .on_hover_text(
+                "Pauses tasks of the kinds in network_kinds in the config file while the \
+                 connection is metered or roaming, and resumes them once it is not. \
+                 A task's menu can let it run anyway.",
+            )
+            .changed();
+        let mut governor = self.config.governor.clone();
+        changed |= ui
+            .checkbox(
+                &mut governor.enabled,
+                "Slow down while the computer is busy or hot",
+            )
+            .on_hover_text(format!(
+                "Runs at most {} tasks at once{} while the CPU load or temperature is high, \
+                 as set in the [governor] table of the config file",
+                governor.throttled_concurrency.max(1),
+                if governor.pause_low_priority {
+                    " and pauses low-priority tasks"
+                } else {
+                    ""
+                }
+            ))
+            .changed();
+        if changed {
+            let mut config = self.config.clone();
+            config.power = power;
+            config.network = network;
+            config.governor = governor;
+            self.save_config(&config);
+        }
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    fn ui_restore_settings(&mut self, ui: &mut egui::Ui) {
+        let mut config = self.config.clone();
+        if ui
+            .checkbox(
+                &mut config.restore_queue,
+                "Keep unfinished tasks for the next start",
+            )
+            .on_hover_text("They are added again when the app next starts, and start over")
+            .changed()
+        {
+            self.save_config(&config);
+        }
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    fn ui_deadline_settings(&mut self, ui: &mut egui::Ui) {
+        let mut deadlines = self.config.deadlines.clone();
+        let mut changed = ui
+            .checkbox(
+                &mut deadlines.boost_priority,
+                "Raise the priority of tasks likely to miss their deadline",
+            )
+            .changed();
+        changed |= ui
+            .checkbox(
+                &mut deadlines.notify,
+                "Notify when a task is likely to miss its deadline",
+            )
+            .changed();
+        if changed {
+            let mut config = self.config.clone();
+            config.deadlines = deadlines;
+            self.save_config(&config);
+        }
+    }
+
+    /// Reads the power source every few seconds, keeps the computer awake while tasks
+    /// run, and pauses or resumes heavy tasks as it goes on and off battery.
+    #[cfg(not(target_arch = "wasm32"))]
+    fn manage_power(&mut self) {
+        if self
+            .power
+            .checked_at
+            .map_or(false, |at| at.elapsed() < POWER_CHECK_INTERVAL)
+        {
+            return;
+        }
+        self.power.checked_at = Some(Instant::now());
+        self.power.source = power_source();
+        let records = self.task_queue.records();
+
+        let running = records
+            .iter()
+            .any(|record| record.status == TaskStatus::Running);
+        if self.config.power.keep_awake && running {
+            if self.power.keep_awake.is_none() {
+                let held = KeepAwake::acquire();
+                if let Err(e) = &held {
+                    log::warn!("{}", e);
+                }
+                self.power.keep_awake = Some(held);
+            }
+        } else {
+            self.power.keep_awake = None;
+        }
+
+        let on_battery = matches!(self.power.source, PowerSource::Battery { .. });
+        if self.config.power.pause_on_battery && on_battery {
+            let heavy_kinds = &self.config.power.heavy_kinds;
+            for task_id in self.power.guard.tasks_to_pause(&records, heavy_kinds) {
+                match self.task_queue.pause_task(task_id) {
+                    Ok(()) => log::info!("Paused task {} while on battery", task_id),
+                    Err(e) => log::warn!("Cannot pause task {} on battery: {:?}", task_id, e),
+                }
+            }
+        } else {
+            for task_id in self.power.guard.tasks_to_resume() {
+                match self.task_queue.resume_task(task_id) {
+                    Ok(()) => log::info!("Resumed task {} paused on battery", task_id),
+                    Err(e) => log::debug!("Not resuming task {}: {:?}", task_id, e),
+                }
+            }
+        }
+
+        self.manage_governor(&records);
+        self.manage_metered(&records);
+    }
+
+    /// With `pause_on_metered` on, pauses network tasks while the connection is metered
+    /// or roaming, except those let through by hand, and resumes them once it is not.
+    #[cfg(not(target_arch = "wasm32"))]
+    fn manage_metered(&mut self, records: &[TaskRecord]) {
+        let config = &self.config.network;
+        let power = &mut self.power;
+        if config.pause_on_metered {
+            if let Some(check) = &power.network_check {
+                match check.try_recv() {
+                    Ok(cost) => {
+                        power.network = cost;
+                        power.network_check = None;
+                    }
+                    Err(std::sync::mpsc::TryRecvError::Empty) => {}
+                    Err(std::sync::mpsc::TryRecvError::Disconnected) => power.network_check = None,
+                }
+            }
+            if power.network_check.is_none() {
+                let (sender, receiver) = std::sync::mpsc::channel();
+                std::thread::spawn(move || {
+                    let _ = sender.send(network_cost());
+                });
+                power.network_check = Some(receiver);
+            }
+        } else {
+            power.network = NetworkCost::Unknown;
+            power.network_check = None;
+        }
+        if config.pause_on_metered && power.network.is_costly() {
+            for task_id in power.metered.tasks_to_pause(records, &config.network_kinds) {
+                match self.task_queue.pause_task(task_id) {
+                    Ok(()) => log::info!("Paused task {}: {}", task_id, power.network),
+                    Err(e) => log::warn!(
+                        "Cannot pause task {} on a metered connection: {:?}",
+                        task_id,
+                        e
+                    ),
+                }
+            }
+        } else {
+            for task_id in power.metered.tasks_to_resume() {
+                match self.task_queue.resume_task(task_id) {
+                    Ok(()) => log::info!("Resumed task {} paused on a metered connection", task_id),
+                    Err(e) => log::debug!("Not resuming task {}: {:?}", task_id, e),
+                }
+            }
+        }
+    }
+
+    /// Lets a network task run on a metered connection, resuming it if it was paused for
+    /// one. Only offered while `pause_on_metered` is on.
+    #[cfg(not(target_arch = "wasm32"))]
+    fn ui_metered_override(&mut self, ui: &mut egui::Ui, task_id: TaskId) {
+        let network = &self.config.network;
+        if !network.pause_on_metered {
+            return;
+        }
+        let Ok(detail) = self.task_queue.task_detail(task_id) else {
+            return;
+        };
+        if !network.network_kinds.contains(&detail.record.kind) {
+            return;
+        }
+        ui.separator();
+        let mut allowed = self.power.metered.is_allowed(task_id);
+        if ui
+            .checkbox(&mut allowed, "Run on metered connections")
+            .changed()
+            && self.power.metered.allow(task_id, allowed)
+        {
+            match self.task_queue.resume_task(task_id) {
+                Ok(()) => {
+                    self.audit(task_id, AuditAction::Resume, Interface::Ui);
+                    self.polled.remove(&task_id);
+                }
+                Err(e) => log::error!("Task {} resume error: {:?}", task_id, e),
+            }
+        }
+    }
+
+    /// With the governor on, reads the system load along with the power source, and
+    /// while the machine is busy or hot runs fewer tasks at once and pauses low-priority
+    /// ones.
+    #[cfg(not(target_arch = "wasm32"))]
+    fn manage_governor(&mut self, records: &[TaskRecord]) {
+        let config = &self.config.governor;
+        // Turning the governor off lets go as if the machine had cooled down.
+        self.power.load = if config.enabled {
+            system_load()
+        } else {
+            SystemLoad::default()
+        };
+        let governor = &mut self.power.governor;
+        if governor.update(&self.power.load, config) {
+            let limit = governor.concurrency(self.config.concurrency, config);
+            executor::set_worker_limit(limit);
+            if governor.is_throttled() {
+                log::info!(
+                    "{}: running at most {} tasks at once",
+                    self.power.load,
+                    format::count(limit.unwrap_or(1))
+                );
+            } else {
+                log::info!("{}: no longer throttled", self.power.load);
+            }
+        }
+        if governor.is_throttled() && config.pause_low_priority {
+            for task_id in governor.tasks_to_pause(records) {
+                match self.task_queue.pause_task(task_id) {
+                    Ok(()) => log::info!("Paused task {} while throttled", task_id),
+                    Err(e) => log::warn!("Cannot pause task {} to throttle: {:?}", task_id, e),
+                }
+            }
+        } else {
+            for task_id in governor.tasks_to_resume() {
+                match self.task_queue.resume_task(task_id) {
+                    Ok(()) => log::info!("Resumed task {} paused while throttled", task_id),
+                    Err(e) => log::debug!("Not resuming task {}: {:?}", task_id, e),
+                }
+            }
+        }
+    }
+
+    #[cfg(all(feature = "self-update", not(target_arch = "wasm32")))]
+    fn check_for_update(&mut self) {
+        if matches!(
+            self.update,
+            UpdateStatus::Checking(_) | UpdateStatus::Downloading { .. }
+        ) {
+            return;
+        }
+        let (sender, receiver) = std::sync::mpsc::channel();
+        let config = self.config.update.clone();
+        std::thread::spawn(move || {
+            let _ = sender.send(updater::check_for_update(&config).map_err(|e| e.to_string()));
+        });
+        self.update = UpdateStatus::Checking(receiver);
+    }
+
+    /// Queues the update binary as an ordinary download task.
+    #[cfg(all(feature = "self-update", not(target_arch = "wasm32")))]
+    fn download_update(&mut self, release: Release, sha256: String) {
+        let path = match updater::download_path() {
+            Ok(path) => path,
+            Err(e) => {
+                self.update = UpdateStatus::Failed(e.to_string());
+                return;
+            }
+        };
+        // Subscribe first so the task's final event cannot be missed.
+        let events = self.task_queue.subscribe();
+        let task =
+            crate::app::download_task::DownloadTask::new(None, release.binary_url.clone(), path);
+        let task_id = self.task_queue.add_task(task);
+        self.task_ids.push(task_id);
+        self.update = UpdateStatus::Downloading {
+            release,
+            sha256,
+            task_id,
+            events,
+        };
+    }
+
+    /// Moves the update along as the check answers and the download finishes.
+    #[cfg(all(feature = "self-update", not(target_arch = "wasm32")))]
+    fn poll_update(&mut self) {
+        self.update = match std::mem::take(&mut self.update) {
+            UpdateStatus::Checking(receiver) => match receiver.try_recv() {
+                Ok(Ok(Some((release, sha256)))) => {
+                    log::info!("Version {} is available", release.version);
+                    UpdateStatus::Available { release, sha256 }
+                }
+                Ok(Ok(None)) => UpdateStatus::UpToDate,
+                Ok(Err(e)) => {
+                    log::warn!("{}", e);
+                    UpdateStatus::Failed(e)
+                }
+                Err(std::sync::mpsc::TryRecvError::Empty) => UpdateStatus::Checking(receiver),
+                Err(std::sync::mpsc::TryRecvError::Disconnected) => {
+                    UpdateStatus::Failed("Update check stopped unexpectedly".to_owned())
+                }
+            },
+            UpdateStatus::Downloading {
+                release,
+                sha256,
+                task_id,
+                events,
+            } => {
+                let finished = events
+                    .try_iter()
+                    .find(|record| record.id == task_id && record.status.is_terminal());
+                match finished {
+                    Some(record) if record.status == TaskStatus::Completed => {
+                        match updater::stage(&release.version, &sha256) {
+                            Ok(()) => {
+                                log::info!(
+                                    "Version {} will be installed on next launch",
+                                    release.version
+                                );
+                                UpdateStatus::Staged(release.version)
+                            }
+                            Err(e) => {
+                                log::error!("{}", e);
+                                UpdateStatus::Failed(e.to_string())
+                            }
+                        }
+                    }
+                    Some(_) => UpdateStatus::Failed("The update download was cancelled".to_owned()),
+                    None => UpdateStatus::Downloading {
+                        release,
+                        sha256,
+                        task_id,
+                        events,
+                    },
+                }
+            }
+            status => status,
+        };
+    }
+
+    #[cfg(all(feature = "self-update", not(target_arch = "wasm32")))]
+    fn ui_update_status(&mut self, ui: &mut egui::Ui) {
+        let mut download = None;
+        match &self.update {
+            UpdateStatus::Idle => {}
+            UpdateStatus::Checking(_) => {
+                ui.label("Checking for updates…");
+            }
+            UpdateStatus::UpToDate => {
+                ui.label(format!(
+                    "Version {} is up to date",
+                    updater::current_version()
+                ));
+            }
+            UpdateStatus::Available { release, sha256 } => {
+                ui.horizontal(|ui| {
+                    ui.label(format!("Version {} is available", release.version));
+                    if ui.button("Download").clicked() {
+                        download = Some((release.clone(), sha256.clone()));
+                    }
+                });
+            }
+            UpdateStatus::Downloading { release, .. } => {
+                ui.label(format!("Downloading version {}…", release.version));
+            }
+            UpdateStatus::Staged(version) => {
+                ui.label(format!(
+                    "Version {} will be installed on next launch",
+                    version
+                ));
+            }
+            UpdateStatus::Failed(e) => {
+                ui.colored_label(egui::Color32::RED, e);
+            }
+        }
+        if let Some((release, sha256)) = download {
+            self.download_update(release, sha256);
         }
     }
-}
 
-impl TemplateApp {
-    pub fn new(cc: &eframe::CreationContext<'_>) -> Self {
-        if let Some(storage) = cc.storage {
-            return eframe::get_value(storage, eframe::APP_KEY).unwrap_or_default();
+    #[cfg(not(target_arch = "wasm32"))]
+    fn ui_power_status(&self, ui: &mut egui::Ui) {
+        let status = match &self.power.keep_awake {
+            Some(Ok(_)) => format!("{} · keeping awake", self.power.source),
+            _ => self.power.source.to_string(),
+        };
+        let label = ui.label(status);
+        if let Some(Err(e)) = &self.power.keep_awake {
+            label.on_hover_text(e);
+        }
+        if self.config.network.pause_on_metered {
+            if self.power.network.is_costly() {
+                ui.colored_label(
+                    ui.visuals().warn_fg_color,
+                    format!("{} · network tasks paused", self.power.network),
+                );
+            } else {
+                ui.label(self.power.network.to_string());
+            }
+        }
+        if self.config.governor.enabled {
+            if self.power.governor.is_throttled() {
+                ui.colored_label(
+                    ui.visuals().warn_fg_color,
+                    format!("{} · throttled", self.power.load),
+                );
+            } else {
+                ui.label(self.power.load.to_string());
+            }
+        }
+    }
+
+    fn reload_config_if_changed(&mut self, ctx: &egui::Context) {
+        let reloaded = match self.config_watcher.as_mut() {
+            Some(watcher) => watcher.poll_changed(),
+            None => None,
+        };
+        match reloaded {
+            Some(Ok(config)) => {
+                log::info!("Config reloaded");
+                self.config = config;
+                self.config_error = None;
+                self.apply_config(ctx);
+            }
+            Some(Err(e)) => {
+                log::error!("Config reload failed: {}", e);
+                self.config_error = Some(e.to_string());
+            }
+            None => {}
+        }
+    }
+
+    fn ui_settings(&mut self, ui: &mut egui::Ui) {
+        egui::Grid::new("settings_grid")
+            .num_columns(2)
+            .striped(true)
+            .show(ui, |ui| {
+                ui.label("Max concurrent tasks");
+                ui.label(match self.config.concurrency {
+                    Some(n) => n.to_string(),
+                    None => "unlimited".to_owned(),
+                });
+                ui.end_row();
+                ui.label("Max task starts");
+                ui.label(match self.config.start_rate {
+                    Some(n) => format!("{}/s", n),
+                    None => "unlimited".to_owned(),
+                });
+                ui.end_row();
+                ui.label("Theme");
+                ui.label(format!("{:?}", self.config.theme));
+                ui.end_row();
+                ui.label("Bandwidth cap");
+                ui.label(match self.config.bandwidth_cap {
+                    Some(bytes) => format!("{}/s", format::bytes(bytes)),
+                    None => "uncapped".to_owned(),
+                });
+                ui.end_row();
+                ui.label("Log level");
+                ui.label(&self.config.log_level);
+                ui.end_row();
+                ui.label("Progress reported every");
+                ui.label(format!(
+                    "{}, {}",
+                    format::percent(self.config.progress_min_delta, 1),
+                    format::duration(Duration::from_millis(self.config.progress_min_interval_ms))
+                ));
+                ui.end_row();
+                ui.label("Store");
+                ui.label(format!("{:?}", self.config.store));
+                ui.end_row();
+                #[cfg(all(feature = "global-hotkey", not(target_arch = "wasm32")))]
+                {
+                    ui.label("Pause hotkey");
+                    ui.label(self.config.pause_hotkey.as_deref().unwrap_or("off"));
+                    ui.end_row();
+                }
+                #[cfg(all(feature = "otel", not(target_arch = "wasm32")))]
+                {
+                    ui.label("OTLP endpoint");
+                    ui.label(match &self.config.otel {
+                        Some(otel) => otel.endpoint.as_str(),
+                        None => "off",
+                    });
+                    ui.end_row();
+                }
+                for (kind, dir) in &self.config.output_dirs {
+                    ui.label(format!("Output dir ({})", kind));
+                    ui.label(dir.display().to_string());
+                    ui.end_row();
+                }
+            });
+        ui.separator();
+        if let Some(watcher) = &self.config_watcher {
+            ui.label(format!("Config file: {}", watcher.path().display()));
+            if ui.button("Open config").clicked() {
+                if let Err(e) = open_config_file(watcher.path()) {
+                    log::error!("Failed to open config: {}", e);
+                    self.config_error = Some(e.to_string());
+                }
+            }
+        }
+        #[cfg(target_arch = "wasm32")]
+        self.ui_config_editor(ui);
+        #[cfg(not(target_arch = "wasm32"))]
+        if ui
+            .button("Handle taskqueue:// links")
+            .on_hover_text("Register this app as the browser handler for taskqueue:// links")
+            .clicked()
+        {
+            match crate::app::associations::register_url_scheme() {
+                Ok(()) => log::info!("Registered the taskqueue:// URL scheme"),
+                Err(e) => {
+                    log::error!("{}", e);
+                    self.config_error = Some(e.to_string());
+                }
+            }
+        }
+        #[cfg(not(target_arch = "wasm32"))]
+        if ui
+            .button("Open .taskqueue files")
+            .on_hover_text("Register this app as the program that opens .taskqueue job files")
+            .clicked()
+        {
+            match crate::app::associations::register_job_files() {
+                Ok(()) => log::info!("Registered the .taskqueue file type"),
+                Err(e) => {
+                    log::error!("{}", e);
+                    self.config_error = Some(e.to_string());
+                }
+            }
+        }
+        ui.separator();
+        self.ui_history_settings(ui);
+        #[cfg(not(target_arch = "wasm32"))]
+        self.ui_restore_settings(ui);
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            ui.separator();
+            self.ui_power_settings(ui);
+            self.ui_deadline_settings(ui);
+            ui.separator();
+            self.ui_watch_rules(ui);
+        }
+        #[cfg(all(feature = "secrets", not(target_arch = "wasm32")))]
+        {
+            ui.separator();
+            self.ui_secrets(ui);
+        }
+        if let Some(e) = &self.config_error {
+            ui.colored_label(egui::Color32::RED, e);
+        }
+    }
+
+    /// How long history is kept and whether task parameters are saved with it, and the
+    /// button that deletes all of it.
+    fn ui_history_settings(&mut self, ui: &mut egui::Ui) {
+        let mut config = self.config.clone();
+        let mut changed = false;
+        ui.horizontal(|ui| {
+            let mut limited = config.history_max_age_days.is_some();
+            changed |= ui.checkbox(&mut limited, "Delete history after").changed();
+            let mut days = config.history_max_age_days.unwrap_or(30);
+            changed |= ui
+                .add_enabled(
+                    limited,
+                    egui::DragValue::new(&mut days)
+                        .clamp_range(1..=3650)
+                        .suffix(" days"),
+                )
+                .changed();
+            config.history_max_age_days = limited.then_some(days);
+        });
+        ui.horizontal(|ui| {
+            let mut limited = config.history_max_count.is_some();
+            changed |= ui.checkbox(&mut limited, "Keep at most").changed();
+            let mut count = config.history_max_count.unwrap_or(10_000);
+            changed |= ui
+                .add_enabled(
+                    limited,
+                    egui::DragValue::new(&mut count)
+                        .clamp_range(1..=1_000_000)
+                        .suffix(" records"),
+                )
+                .changed();
+            config.history_max_count = limited.then_some(count);
+        });
+        changed |= ui
+            .checkbox(
+                &mut config.history_exclude_params,
+                "Leave task parameters out of saved history",
+            )
+            .on_hover_text(
+                "Files, links and annotations, which may hold paths and URLs, are not \
+                 written to disk with finished tasks",
+            )
+            .changed();
+        if changed {
+            self.save_config(&config);
+        }
+        if !self.history_view.confirm_clear {
+            if ui.button("Clear history…").clicked() {
+                self.history_view.confirm_clear = true;
+            }
+            return;
+        }
+        ui.horizontal(|ui| {
+            ui.label("Delete every finished task, on disk too?");
+            if ui.button("Clear history").clicked() {
+                self.clear_history();
+                self.history_view.confirm_clear = false;
+            }
+            if ui.button("Keep").clicked() {
+                self.history_view.confirm_clear = false;
+            }
+        });
+    }
+
+    fn clear_history(&mut self) {
+        if let Err(e) = self.task_queue.clear_history() {
+            log::error!("{}", e);
+            self.config_error = Some(e);
+        }
+        self.history.clear();
+        self.history_view.records = None;
+        self.history_view.page = 0;
+        self.save_soon = true;
+        log::info!("History cleared");
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    fn start_warm_start_bench(&mut self) {
+        if matches!(self.warm_start, WarmStartStatus::Running(_)) {
+            return;
+        }
+        let (sender, receiver) = std::sync::mpsc::channel();
+        let backend = if self.uses_eframe_history() {
+            StoreBackend::Eframe
+        } else {
+            StoreBackend::Sqlite
+        };
+        std::thread::spawn(move || {
+            let report = warm_start::run(backend, warm_start::DEFAULT_TASK_COUNT);
+            let _ = sender.send(report.map_err(|e| e.to_string()));
+        });
+        self.warm_start = WarmStartStatus::Running(receiver);
+    }
+
+    /// The benchmark's progress, then its report.
+    #[cfg(not(target_arch = "wasm32"))]
+    fn ui_warm_start(&mut self, ui: &mut egui::Ui) {
+        if let WarmStartStatus::Running(receiver) = &self.warm_start {
+            match receiver.try_recv() {
+                Ok(result) => self.warm_start = WarmStartStatus::Done(result),
+                Err(std::sync::mpsc::TryRecvError::Empty) => {}
+                Err(std::sync::mpsc::TryRecvError::Disconnected) => {
+                    self.warm_start =
+                        WarmStartStatus::Done(Err("Benchmark stopped unexpectedly".to_owned()));
+                }
+            }
+        }
+        match &self.warm_start {
+            WarmStartStatus::Idle => {}
+            WarmStartStatus::Running(_) => {
+                ui.horizontal(|ui| {
+                    ui.spinner();
+                    ui.label(format!(
+                        "Measuring {} rounds…",
+                        format::count(warm_start::ROUNDS)
+                    ));
+                });
+            }
+            WarmStartStatus::Done(Ok(report)) => {
+                ui.monospace(report.to_string());
+                if ui.button("Copy").clicked() {
+                    ui.output_mut(|o| o.copied_text = report.to_string());
+                }
+            }
+            WarmStartStatus::Done(Err(e)) => {
+                ui.colored_label(ui.visuals().error_fg_color, e);
+            }
+        }
+    }
+
+    /// Read-only view of the queues mirrored from other instances on the LAN.
+    #[cfg(all(feature = "lan-sync", not(target_arch = "wasm32")))]
+    fn ui_remote_queues(&self, ui: &mut egui::Ui) {
+        let peers = self
+            .lan_sync
+            .as_ref()
+            .map(|lan_sync| lan_sync.peers())
+            .unwrap_or_default();
+        if peers.is_empty() {
+            ui.label("No other instances found on the network.");
+        }
+        for peer in peers {
+            let state = if peer.connected {
+                ""
+            } else {
+                " (disconnected)"
+            };
+            egui::CollapsingHeader::new(format!("{}{}", peer.name, state))
+                .id_source(&peer.name)
+                .default_open(true)
+                .show(ui, |ui| {
+                    egui::Grid::new(("remote_tasks", &peer.name))
+                        .num_columns(3)
+                        .striped(true)
+                        .show(ui, |ui| {
+                            for record in peer.tasks.values().rev() {
+                                ui.label(format!("#{}", record.id));
+                                ui.label(&record.kind);
+                                ui.label(record.status.to_string());
+                                ui.end_row();
+                            }
+                        });
+                });
         }
+    }
 
-        Default::default()
+    fn ui_history(&mut self, ui: &mut egui::Ui) {
+        let view = &mut self.history_view;
+        let task_queue = &self.task_queue;
+        let records = view.records.get_or_insert_with(|| {
+            task_queue.history_page(view.page * HISTORY_PAGE_SIZE, HISTORY_PAGE_SIZE)
+        });
+        let full_page = records.len() == HISTORY_PAGE_SIZE;
+        egui::Grid::new("history_grid")
+            .num_columns(5)
+            .striped(true)
+            .show(ui, |ui| {
+                for record in records.iter().rev() {
+                    let file = artifacts::first_file(&record.artifacts);
+                    let mut id = egui::RichText::new(match &record.label {
+                        Some(label) => format!("#{} {}", record.id, label),
+                        None => format!("#{}", record.id),
+                    });
+                    if let Some(tag) = record.color_tag {
+                        id = id.color(color32(tag.rgb()));
+                    }
+                    for text in [id, egui::RichText::new(&record.kind)] {
+                        let response = ui.add(egui::Label::new(text).sense(egui::Sense::click()));
+                        if let Some(path) = file {
+                            response.context_menu(|ui| {
+                                if ui_file_actions(ui, path) {
+                                    ui.close_menu();
+                                }
+                            });
+                        }
+                    }
+                    ui.label(record.status_label());
+                    match (record.started_at, record.finished_at) {
+                        (Some(started), Some(finished)) => {
+                            ui.label(format::duration_millis(started, finished))
+                        }
+                        _ => ui.label(""),
+                    };
+                    if record.artifacts.is_empty() {
+                        ui.label("");
+                    } else {
+                        ui_artifacts(ui, &record.artifacts);
+                    }
+                    ui.end_row();
+                }
+            });
+        if records.is_empty() {
+            ui.label("No finished tasks.");
+        }
+        ui.horizontal(|ui| {
+            if ui
+                .add_enabled(view.page > 0, egui::Button::new("Newer"))
+                .clicked()
+            {
+                view.page -= 1;
+                view.records = None;
+            }
+            ui.label(format!("Page {}", view.page + 1));
+            if ui
+                .add_enabled(full_page, egui::Button::new("Older"))
+                .clicked()
+            {
+                view.page += 1;
+                view.records = None;
+            }
+            if ui.button("Refresh").clicked() {
+                view.records = None;
+            }
+        });
+        egui::CollapsingHeader::new("Audit trail").show(ui, |ui| {
+            ui_audit_trail(ui, task_queue, &mut view.audit_query);
+        });
     }
 
     fn ui_menubar(&mut self, ui: &mut egui::Ui) {
         egui::menu::bar(ui, |ui| {
             ui.separator();
+            #[cfg(not(target_arch = "wasm32"))]
+            ui.menu_button("File", |ui| {
+                if ui.button("Open job file…").clicked() {
+                    ui.close_menu();
+                    self.pick_job_file();
+                }
+                if ui.button("Import CSV…").clicked() {
+                    ui.close_menu();
+                    self.pick_csv_file();
+                }
+                ui.separator();
+                if ui.button("Open session…").clicked() {
+                    ui.close_menu();
+                    self.pick_session_file(ui.ctx());
+                }
+                if ui.button("Save session…").clicked() {
+                    ui.close_menu();
+                    self.save_session(ui.ctx());
+                }
+                ui.separator();
+                if ui.button("Export history…").clicked() {
+                    ui.close_menu();
+                    self.export_history();
+                }
+                if ui
+                    .button("Export schedule…")
+                    .on_hover_text("Upcoming scheduled and recurring runs, for calendar apps")
+                    .clicked()
+                {
+                    ui.close_menu();
+                    self.export_schedule();
+                }
+                if ui
+                    .button("Export trace…")
+                    .on_hover_text("For chrome://tracing or Perfetto")
+                    .clicked()
+                {
+                    ui.close_menu();
+                    self.export_trace();
+                }
+            });
             ui.menu_button("Options", |ui| {
-                ui.checkbox(&mut self.show_header, "Show header");
-                ui.checkbox(&mut self.show_footer, "Show footer");
+                ui.checkbox(&mut self.layout.show_header, "Show header");
+                ui.checkbox(&mut self.layout.show_footer, "Show footer");
+                ui.menu_button("Density", |ui| {
+                    for density in Density::ALL {
+                        let choice =
+                            ui.radio_value(&mut self.layout.density, density, density.to_string());
+                        if choice.clicked() {
+                            density.apply(ui.ctx());
+                            ui.close_menu();
+                        }
+                    }
+                });
+                if ui
+                    .button("Reset layout")
+                    .on_hover_text("Default panels, window positions and sizes, and filter")
+                    .clicked()
+                {
+                    self.reset_layout(ui.ctx());
+                    ui.close_menu();
+                }
+                if ui.button("Settings…").clicked() {
+                    self.layout.show_settings = true;
+                    ui.close_menu();
+                }
+                if ui.button("History…").clicked() {
+                    self.layout.show_history = true;
+                    self.history_view.records = None;
+                    ui.close_menu();
+                }
+                if ui
+                    .button("Stress test")
+                    .on_hover_text(format!(
+                        "Adds {} placeholder tasks",
+                        format::count(STRESS_TASK_COUNT)
+                    ))
+                    .clicked()
+                {
+                    self.start_stress_test();
+                    ui.close_menu();
+                }
+                #[cfg(all(feature = "lan-sync", not(target_arch = "wasm32")))]
+                if self.lan_sync.is_some() && ui.button("Remote queues…").clicked() {
+                    self.show_remote_queues = true;
+                    ui.close_menu();
+                }
+                #[cfg(all(feature = "self-update", not(target_arch = "wasm32")))]
+                if ui.button("Check for updates").clicked() {
+                    self.check_for_update();
+                    ui.close_menu();
+                }
+            });
+            #[cfg(any(feature = "profiling", not(target_arch = "wasm32")))]
+            ui.menu_button("Debug", |ui| {
+                #[cfg(feature = "profiling")]
+                {
+                    let mut profiling = self.profiler.is_some();
+                    if ui.checkbox(&mut profiling, "Profiler").changed() {
+                        self.profiler = profiling.then(Profiler::start);
+                        ui.close_menu();
+                    }
+                }
+                #[cfg(not(target_arch = "wasm32"))]
+                if ui
+                    .button("Warm-start benchmark")
+                    .on_hover_text(format!(
+                        "Times adding, snapshotting and restoring {} tasks with the configured store",
+                        format::count(warm_start::DEFAULT_TASK_COUNT)
+                    ))
+                    .clicked()
+                {
+                    self.start_warm_start_bench();
+                    ui.close_menu();
+                }
             });
             ui.separator();
         });
     }
 }
 
-impl eframe::App for TemplateApp {
-    fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
-        egui::TopBottomPanel::top("header_panel").show_animated(ctx, self.show_header, |ui| {
-            TemplateApp::ui_menubar(self, ui);
-            ui.separator();
-            ui.horizontal(|ui| {
-                ui.spacing_mut().item_spacing.x = 0.0;
-                ui.label("Task Queue UI");
+/// Paints a tag or priority colour.
+fn color32([r, g, b]: [u8; 3]) -> egui::Color32 {
+    egui::Color32::from_rgb(r, g, b)
+}
+
+/// Chooses what happens when a batch finishes. Returns true if the choice changed.
+#[cfg(not(target_arch = "wasm32"))]
+fn ui_post_batch_action(ui: &mut egui::Ui, id_source: &str, action: &mut PostBatchAction) -> bool {
+    let mut changed = false;
+    ui.horizontal(|ui| {
+        ui.label("When the batch finishes:");
+        egui::ComboBox::from_id_source(id_source)
+            .selected_text(action.to_string())
+            .show_ui(ui, |ui| {
+                for choice in PostBatchAction::choices() {
+                    if ui
+                        .selectable_label(action.same_kind(&choice), choice.to_string())
+                        .clicked()
+                        && !action.same_kind(&choice)
+                    {
+                        *action = choice;
+                        changed = true;
+                    }
+                }
             });
+    });
+    match action {
+        PostBatchAction::RunCommand(line) => {
+            changed |= ui
+                .add(egui::TextEdit::singleline(line).hint_text("program \"first arg\" …"))
+                .changed();
+        }
+        PostBatchAction::OpenFolder(path) => {
+            let mut text = path.display().to_string();
+            if ui.text_edit_singleline(&mut text).changed() {
+                *path = PathBuf::from(text);
+                changed = true;
+            }
+        }
+        _ => {}
+    }
+    changed
+}
+
+/// Who added, cancelled, paused or resumed which task, newest first, narrowed by `query`.
+fn ui_audit_trail(ui: &mut egui::Ui, task_queue: &TaskQueue, query: &mut String) {
+    ui.horizontal(|ui| {
+        ui.label("Filter");
+        ui.text_edit_singleline(query)
+            .on_hover_text("A task id, action, interface or user");
+    });
+    let entries: Vec<AuditEntry> = task_queue
+        .audit_log(None)
+        .into_iter()
+        .rev()
+        .filter(|entry| entry.matches(query))
+        .take(HISTORY_PAGE_SIZE)
+        .collect();
+    if entries.is_empty() {
+        ui.label("Nothing recorded.");
+        return;
+    }
+    let now = now_millis();
+    egui::Grid::new("audit_grid")
+        .num_columns(5)
+        .striped(true)
+        .show(ui, |ui| {
+            for entry in entries {
+                ui.label(format!("#{}", entry.task_id));
+                ui.label(entry.action.to_string());
+                ui.label(entry.origin.interface.to_string());
+                ui.label(&entry.origin.principal);
+                ui.label(format!("{} ago", format::duration_millis(entry.at, now)));
+                ui.end_row();
+            }
         });
-        egui::TopBottomPanel::bottom("footer_panel").show_animated(ctx, self.show_footer, |ui| {
-            ui.with_layout(egui::Layout::bottom_up(egui::Align::LEFT), |ui| {
-                egui::warn_if_debug_build(ui);
+}
+
+/// The free space where a task will write its output, in the warning colour when it is
+/// running low.
+#[cfg(not(target_arch = "wasm32"))]
+fn ui_free_space(ui: &mut egui::Ui, path: &Path) {
+    match disk_space::available_space(path) {
+        Ok(available) if available < LOW_DISK_SPACE => {
+            ui.colored_label(
+                ui.visuals().warn_fg_color,
+                format!("Only {} free", format::bytes(available)),
+            );
+        }
+        Ok(available) => {
+            ui.weak(format!("{} free", format::bytes(available)));
+        }
+        Err(e) => {
+            ui.colored_label(
+                ui.visuals().warn_fg_color,
+                format!("Cannot read the free space: {}", e),
+            );
+        }
+    }
+}
+
+/// Edits a [`ParamType::Env`] value as rows of name and value.
+fn ui_env_editor(ui: &mut egui::Ui, param: &str, value: &mut String) {
+    let mut pairs = env_pairs(value);
+    let mut changed = false;
+    let mut removed = None;
+    ui.vertical(|ui| {
+        for (index, (name, val)) in pairs.iter_mut().enumerate() {
+            ui.horizontal(|ui| {
+                changed |= ui
+                    .add(
+                        egui::TextEdit::singleline(name)
+                            .desired_width(100.0)
+                            .hint_text("NAME"),
+                    )
+                    .changed();
+                ui.label("=");
+                changed |= ui
+                    .add(
+                        egui::TextEdit::singleline(val)
+                            .desired_width(140.0)
+                            .hint_text("value"),
+                    )
+                    .changed();
+                if ui.small_button("−").on_hover_text("Remove").clicked() {
+                    removed = Some(index);
+                }
+            });
+        }
+        if ui
+            .small_button("+")
+            .on_hover_text(format!("Add to {}", param))
+            .clicked()
+        {
+            pairs.push((String::new(), String::new()));
+            changed = true;
+        }
+    });
+    if let Some(index) = removed {
+        pairs.remove(index);
+        changed = true;
+    }
+    if changed {
+        *value = pairs
+            .iter()
+            .map(|(name, val)| format!("{}={}", name, val))
+            .collect::<Vec<_>>()
+            .join("\n");
+    }
+}
+
+/// A menu of what a finished task produced, with an action for each.
+fn ui_artifacts(ui: &mut egui::Ui, artifacts: &[Artifact]) {
+    ui.menu_button(
+        format!("{} artifacts", format::count(artifacts.len())),
+        |ui| {
+            for artifact in artifacts {
+                ui.menu_button(artifact.label(), |ui| {
+                    match artifact {
+                        Artifact::File { path } => {
+                            if ui_file_actions(ui, path) {
+                                ui.close_menu();
+                            }
+                        }
+                        Artifact::Url { url } => {
+                            if ui.button("Open").clicked() {
+                                ui.close_menu();
+                                if let Err(e) = artifacts::open(artifact) {
+                                    log::error!("Cannot open {}: {}", url, e);
+                                }
+                            }
+                        }
+                        Artifact::Text { .. } => {}
+                    }
+                    let copy = match artifact {
+                        Artifact::File { .. } => "Copy path",
+                        Artifact::Url { .. } => "Copy URL",
+                        Artifact::Text { .. } => "Copy text",
+                    };
+                    if ui.button(copy).clicked() {
+                        ui.output_mut(|output| output.copied_text = artifact.copy_text());
+                        ui.close_menu();
+                    }
+                });
+            }
+        },
+    );
+}
+
+/// "Open file" and "Open containing folder" buttons for `path`. Returns whether either
+/// was clicked.
+fn ui_file_actions(ui: &mut egui::Ui, path: &Path) -> bool {
+    let open_file = ui.button("Open file").clicked();
+    let open_folder = ui.button("Open containing folder").clicked();
+    let result = if open_file {
+        artifacts::open_path(path)
+    } else if open_folder {
+        artifacts::open_containing_folder(path)
+    } else {
+        return false;
+    };
+    if let Err(e) = result {
+        log::error!("Cannot open {}: {}", path.display(), e);
+    }
+    true
+}
+
+impl eframe::App for TemplateApp {
+    fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+        #[cfg(feature = "profiling")]
+        if let Some(profiler) = &self.profiler {
+            profiler.new_frame();
+        }
+        profile_function!();
+        self.reload_config_if_changed(ctx);
+        if std::mem::take(&mut self.save_soon) {
+            if let Some(storage) = _frame.storage_mut() {
+                eframe::App::save(self, storage);
+                storage.flush();
+            }
+        }
+        self.adopt_untracked_tasks();
+        self.refresh_stats(ctx);
+        #[cfg(not(target_arch = "wasm32"))]
+        self.process_watch_folder();
+        #[cfg(not(target_arch = "wasm32"))]
+        self.receive_forwarded_args(_frame);
+        #[cfg(not(target_arch = "wasm32"))]
+        self.manage_power();
+        #[cfg(not(target_arch = "wasm32"))]
+        self.escalate_deadlines();
+        #[cfg(not(target_arch = "wasm32"))]
+        self.update_window_title(_frame);
+        #[cfg(all(feature = "self-update", not(target_arch = "wasm32")))]
+        self.poll_update();
+
+        #[cfg(feature = "profiling")]
+        if let Some(profiler) = &mut self.profiler {
+            let mut open = true;
+            egui::Window::new("Profiler")
+                .open(&mut open)
+                .default_height(400.0)
+                .show(ctx, |ui| profiler.ui(ui));
+            if !open {
+                self.profiler = None;
+            }
+        }
+
+        #[cfg(not(target_arch = "wasm32"))]
+        if !matches!(self.warm_start, WarmStartStatus::Idle) {
+            let mut open = true;
+            egui::Window::new("Warm-start benchmark")
+                .open(&mut open)
+                .show(ctx, |ui| self.ui_warm_start(ui));
+            if !open {
+                self.warm_start = WarmStartStatus::Idle;
+            }
+        }
+
+        let mut show_settings = self.layout.show_settings;
+        egui::Window::new("Settings")
+            .open(&mut show_settings)
+            .show(ctx, |ui| self.ui_settings(ui));
+        self.layout.show_settings = show_settings;
+
+        #[cfg(all(feature = "lan-sync", not(target_arch = "wasm32")))]
+        {
+            let mut show_remote_queues = self.show_remote_queues;
+            egui::Window::new("Remote queues")
+                .open(&mut show_remote_queues)
+                .show(ctx, |ui| self.ui_remote_queues(ui));
+            self.show_remote_queues = show_remote_queues;
+        }
+
+        let mut show_history = self.layout.show_history;
+        egui::Window::new("History")
+            .open(&mut show_history)
+            .show(ctx, |ui| self.ui_history(ui));
+        self.layout.show_history = show_history;
+
+        let mut show_new_task = self.show_new_task;
+        egui::Window::new("New task")
+            .open(&mut show_new_task)
+            .show(ctx, |ui| self.ui_new_task(ui));
+        self.show_new_task = show_new_task;
+
+        #[cfg(not(target_arch = "wasm32"))]
+        if !self.interrupted.is_empty() {
+            egui::Window::new("Interrupted tasks")
+                .collapsible(false)
+                .show(ctx, |ui| self.ui_interrupted(ui));
+        }
+
+        #[cfg(not(target_arch = "wasm32"))]
+        if !self.pending_links.is_empty() {
+            egui::Window::new("Add task from link?")
+                .collapsible(false)
+                .show(ctx, |ui| self.ui_confirm_link(ui));
+        }
+
+        #[cfg(not(target_arch = "wasm32"))]
+        if self.job_dialog.is_some() {
+            let mut keep_open = true;
+            egui::Window::new("Job file")
+                .collapsible(false)
+                .show(ctx, |ui| keep_open = self.ui_job_dialog(ui));
+            if !keep_open {
+                self.job_dialog = None;
+            }
+        }
+
+        #[cfg(not(target_arch = "wasm32"))]
+        if let Some(import) = &self.csv_import {
+            let mut keep_open = true;
+            egui::Window::new(format!("Import {}", import.file_name))
+                .id(egui::Id::new("csv_import"))
+                .collapsible(false)
+                .show(ctx, |ui| keep_open = self.ui_csv_import(ui));
+            if !keep_open {
+                self.csv_import = None;
+            }
+        }
+
+        #[cfg(not(target_arch = "wasm32"))]
+        if let Some(notice) = self.file_notice.clone() {
+            let mut keep_open = true;
+            egui::Window::new("File")
+                .collapsible(false)
+                .show(ctx, |ui| {
+                    ui.label(notice);
+                    if ui.button("Close").clicked() {
+                        keep_open = false;
+                    }
+                });
+            if !keep_open {
+                self.file_notice = None;
+            }
+        }
+        #[cfg(not(target_arch = "wasm32"))]
+        self.ui_power_countdown(ctx);
+        self.ui_toasts(ctx);
+
+        egui::TopBottomPanel::top("header_panel").show_animated(
+            ctx,
+            self.layout.show_header,
+            |ui| {
+                TemplateApp::ui_menubar(self, ui);
+                ui.separator();
                 ui.horizontal(|ui| {
                     ui.spacing_mut().item_spacing.x = 0.0;
-                    ui.label("powered by ");
-                    ui.hyperlink_to("egui", "https://github.com/emilk/egui");
-                    ui.label(" and ");
-                    ui.hyperlink_to(
-                        "eframe",
-                        "https://github.com/emilk/egui/tree/master/crates/eframe",
-                    );
-                    ui.label(".");
+                    ui.label("Task Queue UI");
                 });
-            });
-        });
+            },
+        );
+        egui::TopBottomPanel::bottom("footer_panel").show_animated(
+            ctx,
+            self.layout.show_footer,
+            |ui| {
+                ui.with_layout(egui::Layout::bottom_up(egui::Align::LEFT), |ui| {
+                    #[cfg(not(target_arch = "wasm32"))]
+                    self.ui_power_status(ui);
+                    if let Some(forecast) = self.forecast_text() {
+                        ui.label(forecast);
+                    }
+                    #[cfg(all(feature = "self-update", not(target_arch = "wasm32")))]
+                    self.ui_update_status(ui);
+                    egui::warn_if_debug_build(ui);
+                    ui.horizontal(|ui| {
+                        ui.spacing_mut().item_spacing.x = 0.0;
+                        ui.label("powered by ");
+                        ui.hyperlink_to("egui", "https://github.com/emilk/egui");
+                        ui.label(" and ");
+                        ui.hyperlink_to(
+                            "eframe",
+                            "https://github.com/emilk/egui/tree/master/crates/eframe",
+                        );
+                        ui.label(".");
+                    });
+                });
+            },
+        );
 
         egui::CentralPanel::default().show(ctx, |ui| {
-            if !self.show_header {
+            if !self.layout.show_header {
                 TemplateApp::ui_menubar(self, ui);
             }
             ui.separator();
@@ -92,146 +4705,172 @@ impl eframe::App for TemplateApp {
                 if ui.button("Add task").clicked() {
                     let task = SleepTask::new(None, Duration::from_secs(self.value.ceil() as u64));
                     let task_id = self.task_queue.add_task(task);
+                    self.audit(task_id, AuditAction::Add, Interface::Ui);
                     self.task_ids.push(task_id);
                 }
+                if ui.button("New task…").clicked() {
+                    self.show_new_task = true;
+                }
             });
             ui.separator();
 
             ui.heading(format!(
                 "Currently tracking {} tasks...",
-                self.task_ids.len()
+                format::count(self.task_ids.len())
             ));
+            if let Some(forecast) = self.forecast_text() {
+                ui.weak(forecast);
+            }
+            let announcement = ui.label(&self.announcement);
+            a11y::live(&announcement);
             ui.separator();
 
-            egui::ScrollArea::vertical()
+            let now = ui.input(|i| i.time);
+            let mut finished = self.poll_tracked_tasks(now);
+            self.ui_queue_pause(ui);
+            if !self.task_ids.is_empty() && ui.button("Cancel all tasks").clicked() {
+                let results = self.task_queue.remove_tasks(&self.task_ids);
+                for (&task_id, result) in self.task_ids.iter().zip(results) {
+                    match result {
+                        Ok(()) => self.audit(task_id, AuditAction::Cancel, Interface::Ui),
+                        Err(r) => log::error!("Task {} cancellation error: {:?}", task_id, r),
+                    }
+                }
+                finished.extend(self.task_ids.iter().copied());
+            }
+            self.ui_color_filter(ui);
+            profile_scope!("task list");
+            // A task being scrolled to is shown whatever its color.
+            if let Some(task_id) = self.scroll_to_task {
+                if self.layout.color_filter.is_some()
+                    && self.color_tags.get(&task_id) != self.layout.color_filter.as_ref()
+                {
+                    self.layout.color_filter = None;
+                }
+            }
+            let groups = self.task_queue.groups();
+            // A task being scrolled to is shown even if its group is collapsed.
+            if let Some(task_id) = self.scroll_to_task {
+                if let Some(group) = groups.iter().find(|group| group.tasks.contains(&task_id)) {
+                    self.expanded_groups.insert(group.id);
+                }
+            }
+            // Only built while filtering or grouping; otherwise rows index `task_ids`
+            // directly.
+            let listed: Option<Vec<ListRow>> = (self.layout.color_filter.is_some()
+                || !groups.is_empty())
+            .then(|| self.list_rows(&groups));
+            let row_count = listed.as_ref().map_or(self.task_ids.len(), Vec::len);
+            self.ahead_in_line.clear();
+            if self.task_queue.concurrency().is_some() || self.task_queue.start_rate().is_some() {
+                let line = self.task_queue.queued_order().into_iter();
+                self.ahead_in_line
+                    .extend(line.enumerate().map(|(ahead, task_id)| (task_id, ahead)));
+            }
+            let row_height = ui.spacing().interact_size.y;
+            let mut scroll_area = egui::ScrollArea::vertical()
                 .drag_to_scroll(true)
                 .max_height(_frame.info().window_info.size.y - 100.0)
-                .auto_shrink([false, true])
-                .show(ui, |ui| {
-                    if !self.task_ids.is_empty() {
-                        self.task_ids
-                            .retain(|task_id| match self.task_queue.poll_task(*task_id) {
-                                Ok(PollResult::Completed) => {
-                                    log::debug!("Task {} completed, filtering", task_id);
-                                    false
-                                }
-                                Ok(PollResult::Cancelled) => {
-                                    log::debug!("Task {} cancelled", task_id);
-                                    false
-                                }
-                                _ => true,
-                            });
-                        if ui.button("Cancel all tasks").clicked() {
-                            for task_id in &self.task_ids {
-                                if let Err(r) = self.task_queue.remove_task(*task_id) {
-                                    log::error!("Task {} cancellation error: {:?}", task_id, r);
-                                } else {
-                                    log::debug!("Task {} cancelled", task_id);
-                                };
+                .auto_shrink([false, true]);
+            if let Some(task_id) = self.scroll_to_task.take() {
+                let index = match &listed {
+                    Some(listed) => listed
+                        .iter()
+                        .position(|row| matches!(row, ListRow::Task(id) if *id == task_id)),
+                    None => self.task_ids.iter().position(|&id| id == task_id),
+                };
+                if let Some(index) = index {
+                    let row_spacing = row_height + ui.spacing().item_spacing.y;
+                    scroll_area = scroll_area.vertical_scroll_offset(index as f32 * row_spacing);
+                    self.highlighted_task = Some((task_id, now));
+                }
+            }
+            scroll_area.show_rows(ui, row_height, row_count, |ui, rows| {
+                for index in rows {
+                    let task_id = match listed
+                        .as_ref()
+                        .map_or(ListRow::Task(self.task_ids[index]), |listed| listed[index])
+                    {
+                        ListRow::Task(task_id) => task_id,
+                        ListRow::Group(group_id) => {
+                            self.ui_group_row(ui, group_id);
+                            continue;
+                        }
+                    };
+                    // Scrolled into view before its turn in the sweep.
+                    if !self.polled.contains_key(&task_id) {
+                        if let Ok(result) = self.task_queue.try_poll_task(task_id) {
+                            if self.poll_tracked_task(task_id, result, now) {
+                                finished.insert(task_id);
                             }
-                            self.task_ids.clear();
-                        }
-                        for task_id in &mut self.task_ids {
-                            if let Ok(poll_result) = self.task_queue.poll_task(*task_id) {
-                                match poll_result {
-                                    PollResult::Pending(progress) => match progress {
-                                        PollingData::Float(p) => {
-                                            log::debug!("Task {} progress: {}", task_id, p);
-                                            ui.group(|ui| {
-                                                ui.label(format!("Task {}", task_id));
-                                                ui.add(
-                                                    egui::ProgressBar::new(p)
-                                                        .desired_width(
-                                                            _frame.info().window_info.size.x - 36.0,
-                                                        )
-                                                        .fill(egui::Color32::DARK_GREEN),
-                                                );
-                                                if ui.button("Pause").clicked() {
-                                                    if let Err(r) =
-                                                        self.task_queue.pause_task(*task_id)
-                                                    {
-                                                        log::error!(
-                                                            "Task {} pause error: {:?}",
-                                                            task_id,
-                                                            r
-                                                        );
-                                                    } else {
-                                                        log::debug!("Task {} paused", task_id);
-                                                    }
-                                                }
-                                                if ui.button("Cancel").clicked() {
-                                                    if let Err(r) =
-                                                        self.task_queue.remove_task(*task_id)
-                                                    {
-                                                        log::error!(
-                                                            "Task {} cancellation error: {:?}",
-                                                            task_id,
-                                                            r
-                                                        );
-                                                    } else {
-                                                        log::debug!("Task {} cancelled", task_id);
-                                                    }
-                                                }
-                                            });
-                                        }
-                                    },
-                                    PollResult::Completed => {
-                                        log::debug!("Task {} completed", task_id);
-                                    }
-                                    PollResult::Cancelled => {
-                                        log::debug!("Task {} cancelled", task_id);
-                                    }
-                                    PollResult::Paused(progress) => match progress {
-                                        PollingData::Float(p) => {
-                                            log::debug!("Task {} paused at {}", task_id, p);
-                                            ui.group(|ui| {
-                                                ui.label(format!("Task {} paused", task_id));
-                                                ui.add(
-                                                    egui::ProgressBar::new(p)
-                                                        .desired_width(
-                                                            _frame.info().window_info.size.x - 36.0,
-                                                        )
-                                                        .fill(egui::Color32::DARK_GREEN),
-                                                );
-                                                if ui.button("Resume").clicked() {
-                                                    if let Err(r) =
-                                                        self.task_queue.resume_task(*task_id)
-                                                    {
-                                                        log::error!(
-                                                            "Task {} resume error: {:?}",
-                                                            task_id,
-                                                            r
-                                                        );
-                                                    } else {
-                                                        log::debug!("Task {} resumed", task_id);
-                                                    }
-                                                }
-                                                if ui.button("Cancel").clicked() {
-                                                    if let Err(r) =
-                                                        self.task_queue.remove_task(*task_id)
-                                                    {
-                                                        log::error!(
-                                                            "Task {} cancellation error: {:?}",
-                                                            task_id,
-                                                            r
-                                                        );
-                                                    } else {
-                                                        log::debug!("Task {} cancelled", task_id);
-                                                    }
-                                                }
-                                            });
-                                        }
-                                    },
-                                }
+                        }
+                    }
+                    match self.polled.get(&task_id).cloned() {
+                        Some(PollResult::Failed(e)) => {
+                            let dismissed = self.ui_failed_row(ui, task_id, &e);
+                            if dismissed {
+                                finished.insert(task_id);
                             }
                         }
+                        Some(result) => self.ui_task_row(ui, task_id, result, now),
+                        None => {}
                     }
-                });
+                }
+            });
+            self.untrack(ctx, &finished);
         });
-        ctx.request_repaint_after(Duration::from_millis(16));
+        // Dropped by now, on a group's row or not.
+        if !ctx.input(|i| i.pointer.any_down()) {
+            self.dragged_group = None;
+        }
+        // Progress bars need a steady frame rate; otherwise only the queue's events and
+        // the background sources checked at the top of `update` need waking up for.
+        if self.task_ids.is_empty() {
+            ctx.request_repaint_after(IDLE_REPAINT_INTERVAL);
+        } else {
+            ctx.request_repaint_after(Duration::from_millis(16));
+        }
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    fn on_close_event(&mut self) -> bool {
+        // Saved before the queue stops the tasks, while they are still unfinished.
+        self.save_unfinished_on_exit();
+        if !self.task_queue.shutdown(SHUTDOWN_GRACE) {
+            log::warn!(
+                "Tasks still stopping after {}, closing anyway",
+                format::duration(SHUTDOWN_GRACE)
+            );
+        }
+        true
+    }
+
+    fn on_exit(&mut self, _gl: Option<&eframe::glow::Context>) {
+        // Also here for when the window is closed without a close event.
+        #[cfg(not(target_arch = "wasm32"))]
+        self.save_unfinished_on_exit();
+        #[cfg(all(feature = "otel", not(target_arch = "wasm32")))]
+        if let Some(otel) = &self.otel {
+            otel.shutdown();
+        }
+        #[cfg(all(feature = "lan-sync", not(target_arch = "wasm32")))]
+        if let Some(lan_sync) = &self.lan_sync {
+            lan_sync.shutdown();
+        }
     }
 
     fn save(&mut self, storage: &mut dyn eframe::Storage) {
+        if self.uses_eframe_history() {
+            self.history = self.task_queue.history_page(0, EFRAME_HISTORY_LIMIT);
+            let retention = self.config.history_retention();
+            if retention.exclude_params {
+                for record in &mut self.history {
+                    *record = retention.for_disk(record).into_owned();
+                }
+            }
+        }
+        #[cfg(target_arch = "wasm32")]
+        self.save_unfinished_tasks();
         eframe::set_value(storage, eframe::APP_KEY, self);
     }
 }
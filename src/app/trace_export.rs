@@ -0,0 +1,132 @@
+//! A record of every task's status changes during the session, exported as Chrome
+//! trace-event JSON for chrome://tracing or Perfetto.
+//!
+//! Each task gets a row of its own, named after it, on which the time it spent queued,
+//! running and paused appear as consecutive slices, ending in an instant event for how it
+//! finished. Tasks still unfinished at export time end their last slice there.
+
+use std::collections::BTreeMap;
+use std::io::Write;
+use std::path::Path;
+use std::sync::mpsc::Receiver;
+use std::sync::{Arc as sync_Arc, Mutex as sync_Mutex};
+
+use serde_json::{json, Value};
+
+use crate::app::history::{now_millis, TaskRecord};
+use crate::app::task_id::TaskId;
+use crate::app::task_queue::TaskStatus;
+
+/// Events kept per session; later ones are dropped, so a long stress test cannot use up
+/// the memory.
+const MAX_EVENTS: usize = 1_000_000;
+
+/// A task reaching a status.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TraceEvent {
+    pub id: TaskId,
+    pub kind: String,
+    pub status: TaskStatus,
+    /// Unix timestamp in milliseconds.
+    pub at: u64,
+}
+
+#[derive(Clone, Default)]
+pub struct TraceRecorder {
+    events: sync_Arc<sync_Mutex<Vec<TraceEvent>>>,
+}
+
+impl TraceRecorder {
+    /// Records each record received on `records`, stamped with the time it arrives.
+    pub fn start(records: Receiver<TaskRecord>) -> Self {
+        let recorder = TraceRecorder::default();
+        let events = recorder.clone();
+        std::thread::spawn(move || {
+            for record in records {
+                events.record(&record, now_millis());
+            }
+        });
+        recorder
+    }
+
+    pub fn record(&self, record: &TaskRecord, at: u64) {
+        let mut events = self
+            .events
+            .lock()
+            .expect("Panicked at record: Trace mutex poisoned");
+        if events.len() >= MAX_EVENTS {
+            return;
+        }
+        events.push(TraceEvent {
+            id: record.id,
+            kind: record.kind.clone(),
+            status: record.status.clone(),
+            at,
+        });
+        if events.len() == MAX_EVENTS {
+            log::warn!("Trace is full, later task events are not recorded");
+        }
+    }
+
+    /// Writes the trace so far to `path` and returns the number of events in it.
+    pub fn export(&self, path: &Path) -> std::io::Result<usize> {
+        let trace = {
+            let events = self
+                .events
+                .lock()
+                .expect("Panicked at export: Trace mutex poisoned");
+            chrome_trace(&events, now_millis())
+        };
+        let count = trace["traceEvents"].as_array().map_or(0, Vec::len);
+        let mut writer = std::io::BufWriter::new(std::fs::File::create(path)?);
+        serde_json::to_writer(&mut writer, &trace)?;
+        writer.flush()?;
+        Ok(count)
+    }
+}
+
+/// The trace-event document for `events`, with unfinished slices ending at `end`.
+/// Timestamps are in microseconds since the first event.
+pub fn chrome_trace(events: &[TraceEvent], end: u64) -> Value {
+    let origin = events.iter().map(|event| event.at).min().unwrap_or(end);
+    let micros = |at: u64| at.saturating_sub(origin) * 1000;
+    let mut by_task: BTreeMap<TaskId, Vec<&TraceEvent>> = BTreeMap::new();
+    for event in events {
+        by_task.entry(event.id).or_default().push(event);
+    }
+    let mut trace = Vec::new();
+    for (id, task_events) in by_task {
+        trace.push(json!({
+            "name": "thread_name",
+            "ph": "M",
+            "pid": 1,
+            "tid": id,
+            "args": { "name": format!("{} {}", task_events[0].kind, id) },
+        }));
+        for (i, event) in task_events.iter().enumerate() {
+            if event.status.is_terminal() {
+                trace.push(json!({
+                    "name": event.status.to_string(),
+                    "cat": event.kind,
+                    "ph": "i",
+                    "s": "t",
+                    "ts": micros(event.at),
+                    "pid": 1,
+                    "tid": id,
+                }));
+                break;
+            }
+            let until = task_events.get(i + 1).map_or(end, |next| next.at);
+            trace.push(json!({
+                "name": event.status.to_string(),
+                "cat": event.kind,
+                "ph": "X",
+                "ts": micros(event.at),
+                "dur": micros(until) - micros(event.at),
+                "pid": 1,
+                "tid": id,
+            }));
+        }
+    }
+    json!({ "traceEvents": trace, "displayTimeUnit": "ms" })
+}
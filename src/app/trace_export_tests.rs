@@ -0,0 +1,70 @@
+#[cfg(test)]
+use crate::app::history::TaskRecord;
+#[cfg(test)]
+use crate::app::task_id::TaskId;
+#[cfg(test)]
+use crate::app::task_queue::TaskStatus;
+#[cfg(test)]
+use crate::app::trace_export::{chrome_trace, TraceRecorder};
+
+#[cfg(test)]
+fn record(id: usize, status: TaskStatus) -> TaskRecord {
+    TaskRecord {
+        status,
+        ..TaskRecord::new(TaskId::detached(id), "sleep")
+    }
+}
+
+#[test]
+fn test_chrome_trace_slices() {
+    let recorder = TraceRecorder::default();
+    recorder.record(&record(1, TaskStatus::Queued), 1_000);
+    recorder.record(&record(2, TaskStatus::Queued), 1_001);
+    recorder.record(&record(1, TaskStatus::Running), 1_002);
+    recorder.record(&record(1, TaskStatus::Paused), 1_010);
+    recorder.record(&record(1, TaskStatus::Running), 1_015);
+    recorder.record(&record(1, TaskStatus::Completed), 1_020);
+
+    let path = std::env::temp_dir().join(format!("trace_export_{}.json", std::process::id()));
+    assert_eq!(recorder.export(&path).unwrap(), 8);
+    let trace: serde_json::Value =
+        serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+    let _ = std::fs::remove_file(&path);
+    let events = trace["traceEvents"].as_array().unwrap();
+
+    let slices: Vec<(u64, &str, &str, u64, u64)> = events
+        .iter()
+        .filter(|event| event["ph"] != "M")
+        .map(|event| {
+            (
+                event["tid"].as_u64().unwrap(),
+                event["ph"].as_str().unwrap(),
+                event["name"].as_str().unwrap(),
+                event["ts"].as_u64().unwrap(),
+                event["dur"].as_u64().unwrap_or(0),
+            )
+        })
+        .collect();
+    assert_eq!(
+        slices[..5],
+        [
+            (1, "X", "queued", 0, 2_000),
+            (1, "X", "running", 2_000, 8_000),
+            (1, "X", "paused", 10_000, 5_000),
+            (1, "X", "running", 15_000, 5_000),
+            (1, "i", "completed", 20_000, 0),
+        ]
+    );
+    // Task 2 is still queued, up to the time of the export.
+    assert_eq!(slices[5].0, 2);
+    assert_eq!(slices[5].2, "queued");
+    assert!(slices[5].4 > 0);
+
+    assert_eq!(events[0]["args"]["name"], "sleep 1");
+}
+
+#[test]
+fn test_chrome_trace_empty() {
+    let trace = chrome_trace(&[], 5);
+    assert_eq!(trace["traceEvents"].as_array().unwrap().len(), 0);
+}
@@ -0,0 +1,267 @@
+//! Checks GitHub releases for a newer build and installs it on the next launch.
+//!
+//! The binary itself is fetched by a [`DownloadTask`](crate::app::download_task::DownloadTask)
+//! in the queue. Once it completes, [`stage`] checks it against the release's published
+//! SHA-256 and marks it pending; [`apply_staged_update`] swaps it in at the next start,
+//! before the window opens.
+
+use std::fmt::{Display, Formatter, Result as FmtResult};
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+use semver::Version;
+use sha2::{Digest, Sha256};
+
+use crate::app::config::UpdateConfig;
+
+const USER_AGENT: &str = concat!("functional_rust_ui_demo/", env!("CARGO_PKG_VERSION"));
+/// Target of the update download, inside the staging directory.
+const STAGED_BINARY: &str = "staged-binary";
+const PENDING_FILE: &str = "pending.json";
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum UpdateError {
+    Http(String),
+    Parse(String),
+    MissingAsset(String),
+    Checksum { expected: String, actual: String },
+    Io(String),
+    NoDataDir,
+}
+
+impl Display for UpdateError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        match self {
+            UpdateError::Http(e) => write!(f, "Update check failed: {}", e),
+            UpdateError::Parse(e) => write!(f, "Unexpected release data: {}", e),
+            UpdateError::MissingAsset(name) => {
+                write!(f, "The latest release has no asset named {}", name)
+            }
+            UpdateError::Checksum { expected, actual } => write!(
+                f,
+                "Downloaded update has SHA-256 {} but the release lists {}",
+                actual, expected
+            ),
+            UpdateError::Io(e) => write!(f, "Update I/O error: {}", e),
+            UpdateError::NoDataDir => write!(f, "No data directory to stage updates in"),
+        }
+    }
+}
+
+/// A release newer than the running build.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Release {
+    pub version: Version,
+    pub binary_url: String,
+    pub checksum_url: String,
+}
+
+#[derive(serde::Deserialize)]
+struct GithubRelease {
+    tag_name: String,
+    #[serde(default)]
+    draft: bool,
+    #[serde(default)]
+    prerelease: bool,
+    #[serde(default)]
+    assets: Vec<GithubAsset>,
+}
+
+#[derive(serde::Deserialize)]
+struct GithubAsset {
+    name: String,
+    browser_download_url: String,
+}
+
+/// Written next to the staged binary once its checksum matched.
+#[derive(serde::Deserialize, serde::Serialize)]
+struct PendingUpdate {
+    version: String,
+    sha256: String,
+}
+
+pub fn current_version() -> Version {
+    Version::parse(env!("CARGO_PKG_VERSION")).expect("CARGO_PKG_VERSION is valid semver")
+}
+
+pub fn default_asset_name() -> String {
+    format!(
+        "functional_rust_ui_demo-{}-{}{}",
+        std::env::consts::OS,
+        std::env::consts::ARCH,
+        std::env::consts::EXE_SUFFIX
+    )
+}
+
+/// Asks GitHub for the latest release and returns it, with the binary's SHA-256, if it
+/// is newer than this build.
+pub fn check_for_update(config: &UpdateConfig) -> Result<Option<(Release, String)>, UpdateError> {
+    let url = format!(
+        "https://api.github.com/repos/{}/releases/latest",
+        config.repository
+    );
+    let body = get_text(&url)?;
+    let asset_name = config.asset_name.clone().unwrap_or_else(default_asset_name);
+    match newer_release(&body, &current_version(), &asset_name)? {
+        Some(release) => {
+            let sha256 = parse_checksum(&get_text(&release.checksum_url)?)?;
+            Ok(Some((release, sha256)))
+        }
+        None => Ok(None),
+    }
+}
+
+/// Parses a GitHub release as JSON, ignoring drafts, pre-releases and anything not newer
+/// than `current`. The binary and its `.sha256` must both be attached.
+pub fn newer_release(
+    json: &str,
+    current: &Version,
+    asset_name: &str,
+) -> Result<Option<Release>, UpdateError> {
+    let release: GithubRelease =
+        serde_json::from_str(json).map_err(|e| UpdateError::Parse(e.to_string()))?;
+    if release.draft || release.prerelease {
+        return Ok(None);
+    }
+    let version = Version::parse(release.tag_name.trim_start_matches('v'))
+        .map_err(|e| UpdateError::Parse(format!("tag {}: {}", release.tag_name, e)))?;
+    if version <= *current {
+        return Ok(None);
+    }
+    let asset_url = |name: &str| {
+        release
+            .assets
+            .iter()
+            .find(|asset| asset.name == name)
+            .map(|asset| asset.browser_download_url.clone())
+            .ok_or_else(|| UpdateError::MissingAsset(name.to_owned()))
+    };
+    Ok(Some(Release {
+        binary_url: asset_url(asset_name)?,
+        checksum_url: asset_url(&format!("{}.sha256", asset_name))?,
+        version,
+    }))
+}
+
+/// The digest from `sha256sum` output, i.e. the first word, lowercased.
+pub fn parse_checksum(text: &str) -> Result<String, UpdateError> {
+    let digest = text.split_whitespace().next().unwrap_or_default();
+    if digest.len() != 64 || !digest.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(UpdateError::Parse(format!("'{}' is not a SHA-256", digest)));
+    }
+    Ok(digest.to_ascii_lowercase())
+}
+
+fn get_text(url: &str) -> Result<String, UpdateError> {
+    reqwest::blocking::Client::builder()
+        .user_agent(USER_AGENT)
+        .build()
+        .and_then(|client| client.get(url).send())
+        .and_then(|response| response.error_for_status())
+        .and_then(|response| response.text())
+        .map_err(|e| UpdateError::Http(e.to_string()))
+}
+
+pub fn sha256_file(path: &Path) -> std::io::Result<String> {
+    let mut file = std::fs::File::open(path)?;
+    let mut hasher = Sha256::new();
+    let mut buffer = vec![0; 64 * 1024];
+    loop {
+        let read = file.read(&mut buffer)?;
+        if read == 0 {
+            break;
+        }
+        hasher.update(&buffer[..read]);
+    }
+    Ok(hasher
+        .finalize()
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect())
+}
+
+fn staging_dir() -> Result<PathBuf, UpdateError> {
+    directories_next::ProjectDirs::from("net", "xthreen", "functional_rust_ui_demo")
+        .map(|dirs| dirs.data_dir().join("update"))
+        .ok_or(UpdateError::NoDataDir)
+}
+
+/// Where the update download should be saved; the staging directory is created if needed.
+pub fn download_path() -> Result<PathBuf, UpdateError> {
+    let dir = staging_dir()?;
+    std::fs::create_dir_all(&dir).map_err(|e| UpdateError::Io(e.to_string()))?;
+    Ok(dir.join(STAGED_BINARY))
+}
+
+/// Verifies the finished download against `sha256` and marks it for installation on the
+/// next launch. A download that does not match is deleted.
+pub fn stage(version: &Version, sha256: &str) -> Result<(), UpdateError> {
+    let dir = staging_dir()?;
+    stage_in(&dir, version, sha256)
+}
+
+pub fn stage_in(dir: &Path, version: &Version, sha256: &str) -> Result<(), UpdateError> {
+    let binary = dir.join(STAGED_BINARY);
+    verify(&binary, sha256)?;
+    let pending = PendingUpdate {
+        version: version.to_string(),
+        sha256: sha256.to_owned(),
+    };
+    let json = serde_json::to_string(&pending).map_err(|e| UpdateError::Io(e.to_string()))?;
+    std::fs::write(dir.join(PENDING_FILE), json).map_err(|e| UpdateError::Io(e.to_string()))
+}
+
+fn verify(binary: &Path, expected: &str) -> Result<(), UpdateError> {
+    let actual = sha256_file(binary).map_err(|e| UpdateError::Io(e.to_string()))?;
+    if actual != expected {
+        let _ = std::fs::remove_file(binary);
+        return Err(UpdateError::Checksum {
+            expected: expected.to_owned(),
+            actual,
+        });
+    }
+    Ok(())
+}
+
+/// Replaces the running executable with a staged update, returning the new version.
+///
+/// Call it at startup; the caller should then relaunch the executable so the new
+/// version runs. The staged binary is checked again, in case it changed since staging.
+pub fn apply_staged_update() -> Result<Option<String>, UpdateError> {
+    let exe = std::env::current_exe().map_err(|e| UpdateError::Io(e.to_string()))?;
+    // Left behind by the previous swap on Windows, where a running executable can only be renamed.
+    let _ = std::fs::remove_file(sibling(&exe, "old"));
+    let dir = staging_dir()?;
+    let Ok(json) = std::fs::read_to_string(dir.join(PENDING_FILE)) else {
+        return Ok(None);
+    };
+    let _ = std::fs::remove_file(dir.join(PENDING_FILE));
+    let pending: PendingUpdate =
+        serde_json::from_str(&json).map_err(|e| UpdateError::Parse(e.to_string()))?;
+    let binary = dir.join(STAGED_BINARY);
+    verify(&binary, &pending.sha256)?;
+    replace_exe(&binary, &exe).map_err(|e| UpdateError::Io(e.to_string()))?;
+    let _ = std::fs::remove_file(&binary);
+    Ok(Some(pending.version))
+}
+
+fn sibling(exe: &Path, extension: &str) -> PathBuf {
+    let mut name = exe.file_name().unwrap_or_default().to_owned();
+    name.push(".");
+    name.push(extension);
+    exe.with_file_name(name)
+}
+
+/// Copies `binary` next to `exe` first, so the final rename stays on one file system.
+fn replace_exe(binary: &Path, exe: &Path) -> std::io::Result<()> {
+    let new = sibling(exe, "new");
+    std::fs::copy(binary, &new)?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        std::fs::set_permissions(&new, std::fs::Permissions::from_mode(0o755))?;
+    }
+    #[cfg(windows)]
+    std::fs::rename(exe, sibling(exe, "old"))?;
+    std::fs::rename(&new, exe)
+}
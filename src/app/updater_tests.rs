@@ -0,0 +1,65 @@
+#[cfg(test)]
+use crate::app::updater::{newer_release, parse_checksum, sha256_file, stage_in, UpdateError};
+#[cfg(test)]
+use semver::Version;
+
+#[cfg(test)]
+const RELEASE_JSON: &str = r#"{
+    "tag_name": "v1.2.0",
+    "draft": false,
+    "prerelease": false,
+    "assets": [
+        {"name": "app-linux", "browser_download_url": "https://example.com/app-linux"},
+        {"name": "app-linux.sha256", "browser_download_url": "https://example.com/app-linux.sha256"}
+    ]
+}"#;
+
+#[test]
+fn test_newer_release() {
+    let release = newer_release(RELEASE_JSON, &Version::new(1, 1, 9), "app-linux")
+        .unwrap()
+        .unwrap();
+    assert_eq!(release.version, Version::new(1, 2, 0));
+    assert_eq!(release.binary_url, "https://example.com/app-linux");
+    assert_eq!(release.checksum_url, "https://example.com/app-linux.sha256");
+
+    assert_eq!(
+        newer_release(RELEASE_JSON, &Version::new(1, 2, 0), "app-linux"),
+        Ok(None)
+    );
+    assert_eq!(
+        newer_release(RELEASE_JSON, &Version::new(0, 1, 0), "app-windows.exe"),
+        Err(UpdateError::MissingAsset("app-windows.exe".to_owned()))
+    );
+    let prerelease = RELEASE_JSON.replace(r#""prerelease": false"#, r#""prerelease": true"#);
+    assert_eq!(
+        newer_release(&prerelease, &Version::new(0, 1, 0), "app-linux"),
+        Ok(None)
+    );
+}
+
+#[test]
+fn test_stage_verifies_checksum() {
+    let digest = "2cf24dba5fb0a30e26e83b2ac5b9e29e1b161e5c1fa7425e73043362938b9824";
+    assert_eq!(
+        parse_checksum(&format!("{}  app-linux\n", digest.to_uppercase())).unwrap(),
+        digest
+    );
+    assert!(parse_checksum("not-a-digest app-linux").is_err());
+
+    let dir = std::env::temp_dir().join(format!("update_stage_{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let binary = dir.join("staged-binary");
+    std::fs::write(&binary, "hello").unwrap();
+    assert_eq!(sha256_file(&binary).unwrap(), digest);
+    stage_in(&dir, &Version::new(1, 2, 0), digest).unwrap();
+    assert!(dir.join("pending.json").exists());
+
+    let wrong = "0".repeat(64);
+    assert!(matches!(
+        stage_in(&dir, &Version::new(1, 2, 0), &wrong),
+        Err(UpdateError::Checksum { .. })
+    ));
+    assert!(!binary.exists(), "a mismatching download is deleted");
+    std::fs::remove_dir_all(&dir).unwrap();
+}
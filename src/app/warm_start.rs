@@ -0,0 +1,197 @@
+//! How long a start with a big persisted queue takes: adding synthetic tasks, taking the
+//! snapshot saved on exit and restoring it into a fresh queue, each timed cold over
+//! several rounds with the configured store backend.
+
+use std::fmt::{Display, Formatter, Result as FmtResult};
+use std::time::{Duration, Instant};
+
+use crate::app::config::StoreBackend;
+use crate::app::format::{self, NumberFormat};
+use crate::app::history::TaskRecord;
+#[cfg(feature = "sqlite")]
+use crate::app::store::StoreError;
+use crate::app::stress;
+use crate::app::task_queue::TaskQueue;
+
+/// Tasks per round when the command line does not say.
+pub const DEFAULT_TASK_COUNT: usize = 1_000;
+/// Each round starts from a fresh queue, and store, so every sample is a cold one.
+pub const ROUNDS: usize = 10;
+/// Reported for each phase, as (label, percentile).
+const PERCENTILES: [(&str, f64); 4] = [("p50", 0.5), ("p90", 0.9), ("p99", 0.99), ("max", 1.0)];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Phase {
+    /// Adding the tasks, which the sqlite store also writes to disk one by one.
+    Add,
+    /// Serializing the queue's records, as the app's storage does on exit.
+    Snapshot,
+    /// From the snapshot, or the database, back to a queue ready to use.
+    Restore,
+}
+
+impl Display for Phase {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        match self {
+            Phase::Add => write!(f, "add"),
+            Phase::Snapshot => write!(f, "snapshot"),
+            Phase::Restore => write!(f, "restore"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum WarmStartError {
+    #[cfg(feature = "sqlite")]
+    Store(StoreError),
+    Snapshot(String),
+}
+
+impl Display for WarmStartError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        match self {
+            #[cfg(feature = "sqlite")]
+            WarmStartError::Store(e) => write!(f, "{}", e),
+            WarmStartError::Snapshot(e) => write!(f, "Snapshot failed: {}", e),
+        }
+    }
+}
+
+/// One phase's time in every round.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PhaseTimes {
+    pub phase: Phase,
+    pub samples: Vec<Duration>,
+}
+
+impl PhaseTimes {
+    /// The nearest-rank percentile, `p` from 0 to 1; zero without samples.
+    pub fn percentile(&self, p: f64) -> Duration {
+        let mut sorted = self.samples.clone();
+        sorted.sort();
+        let rank = (p.clamp(0.0, 1.0) * sorted.len() as f64).ceil() as usize;
+        sorted
+            .get(rank.saturating_sub(1))
+            .copied()
+            .unwrap_or_default()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct WarmStartReport {
+    pub backend: StoreBackend,
+    pub tasks: usize,
+    pub phases: Vec<PhaseTimes>,
+}
+
+impl Display for WarmStartReport {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        let backend = match self.backend {
+            StoreBackend::Eframe => "app storage",
+            StoreBackend::Sqlite => "sqlite store",
+        };
+        writeln!(
+            f,
+            "Warm start of {} tasks with the {}, {} rounds",
+            format::count(self.tasks),
+            backend,
+            ROUNDS
+        )?;
+        write!(f, "{:<10}", "")?;
+        for (label, _) in PERCENTILES {
+            write!(f, "{:>12}", label)?;
+        }
+        writeln!(f)?;
+        let number = NumberFormat::current();
+        for times in &self.phases {
+            write!(f, "{:<10}", times.phase.to_string())?;
+            for (_, p) in PERCENTILES {
+                let millis = times.percentile(p).as_secs_f64() * 1000.0;
+                write!(f, "{:>12}", format!("{} ms", number.decimal(millis, 1)))?;
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+/// Runs [`ROUNDS`] rounds of `tasks` stress tasks with `backend`. The sqlite store works on
+/// a scratch database in the temporary directory, never the app's own.
+pub fn run(backend: StoreBackend, tasks: usize) -> Result<WarmStartReport, WarmStartError> {
+    let mut phases: Vec<PhaseTimes> = [Phase::Add, Phase::Snapshot, Phase::Restore]
+        .into_iter()
+        .map(|phase| PhaseTimes {
+            phase,
+            samples: Vec::with_capacity(ROUNDS),
+        })
+        .collect();
+    for _ in 0..ROUNDS {
+        let times = match backend {
+            #[cfg(feature = "sqlite")]
+            StoreBackend::Sqlite => sqlite_round(tasks)?,
+            _ => app_storage_round(tasks)?,
+        };
+        for (phase, time) in phases.iter_mut().zip(times) {
+            phase.samples.push(time);
+        }
+    }
+    #[cfg(not(feature = "sqlite"))]
+    let backend = StoreBackend::Eframe;
+    Ok(WarmStartReport {
+        backend,
+        tasks,
+        phases,
+    })
+}
+
+fn snapshot(queue: &TaskQueue) -> Result<Vec<u8>, WarmStartError> {
+    serde_json::to_vec(&queue.records()).map_err(|e| WarmStartError::Snapshot(e.to_string()))
+}
+
+/// The queue lives in memory and its records are restored from the snapshot.
+fn app_storage_round(tasks: usize) -> Result<[Duration; 3], WarmStartError> {
+    let started = Instant::now();
+    let queue = TaskQueue::new();
+    stress::enqueue(&queue, tasks);
+    let add = started.elapsed();
+
+    let started = Instant::now();
+    let saved = snapshot(&queue)?;
+    let snapshot = started.elapsed();
+    drop(queue);
+
+    let started = Instant::now();
+    let records: Vec<TaskRecord> =
+        serde_json::from_slice(&saved).map_err(|e| WarmStartError::Snapshot(e.to_string()))?;
+    TaskQueue::new().restore_history(records);
+    Ok([add, snapshot, started.elapsed()])
+}
+
+/// The queue writes through to a new database, which is then opened again the way the
+/// app opens it on launch.
+#[cfg(feature = "sqlite")]
+fn sqlite_round(tasks: usize) -> Result<[Duration; 3], WarmStartError> {
+    use crate::app::store::sqlite::SqliteStore;
+
+    let path = std::env::temp_dir().join(format!("warm-start-{}.sqlite", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+    let result = (|| {
+        let started = Instant::now();
+        let store = SqliteStore::open(&path).map_err(WarmStartError::Store)?;
+        let queue = TaskQueue::with_store(Box::new(store));
+        stress::enqueue(&queue, tasks);
+        let add = started.elapsed();
+
+        let started = Instant::now();
+        snapshot(&queue)?;
+        let snapshot = started.elapsed();
+        drop(queue);
+
+        let started = Instant::now();
+        let store = SqliteStore::open(&path).map_err(WarmStartError::Store)?;
+        TaskQueue::with_store(Box::new(store));
+        Ok([add, snapshot, started.elapsed()])
+    })();
+    let _ = std::fs::remove_file(&path);
+    result
+}
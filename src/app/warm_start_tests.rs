@@ -0,0 +1,48 @@
+#[cfg(test)]
+use std::time::Duration;
+
+#[cfg(test)]
+use crate::app::config::StoreBackend;
+#[cfg(test)]
+use crate::app::warm_start::{self, Phase, PhaseTimes, ROUNDS};
+
+#[test]
+fn test_percentiles_use_the_nearest_rank() {
+    let times = PhaseTimes {
+        phase: Phase::Add,
+        samples: (1..=10).rev().map(Duration::from_millis).collect(),
+    };
+    assert_eq!(times.percentile(0.5), Duration::from_millis(5));
+    assert_eq!(times.percentile(0.9), Duration::from_millis(9));
+    assert_eq!(times.percentile(0.99), Duration::from_millis(10));
+    assert_eq!(times.percentile(0.0), Duration::from_millis(1));
+    let empty = PhaseTimes {
+        phase: Phase::Add,
+        samples: Vec::new(),
+    };
+    assert_eq!(empty.percentile(0.5), Duration::ZERO);
+}
+
+#[test]
+fn test_report_times_every_phase_of_every_round() {
+    let report = warm_start::run(StoreBackend::Eframe, 50).unwrap();
+    assert_eq!(report.tasks, 50);
+    let phases: Vec<Phase> = report.phases.iter().map(|times| times.phase).collect();
+    assert_eq!(phases, [Phase::Add, Phase::Snapshot, Phase::Restore]);
+    assert!(report
+        .phases
+        .iter()
+        .all(|times| times.samples.len() == ROUNDS));
+    let text = report.to_string();
+    assert!(text.contains("50 tasks"));
+    assert!(text.contains("restore"));
+}
+
+#[cfg(feature = "sqlite")]
+#[test]
+fn test_sqlite_rounds_clean_up_their_database() {
+    let report = warm_start::run(StoreBackend::Sqlite, 5).unwrap();
+    assert_eq!(report.backend, StoreBackend::Sqlite);
+    let scratch = std::env::temp_dir().join(format!("warm-start-{}.sqlite", std::process::id()));
+    assert!(!scratch.exists());
+}
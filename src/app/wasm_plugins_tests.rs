@@ -0,0 +1,96 @@
+#[cfg(test)]
+use crate::app::plugins::wasm::load_wasm_plugins;
+#[cfg(test)]
+use crate::app::plugins::PluginError;
+#[cfg(test)]
+use crate::app::registry::{TaskKindRegistry, TaskParams};
+#[cfg(test)]
+use crate::app::task_queue::{PollResult, TaskKind, TaskQueue};
+
+/// Echoes its parameters through a temp file and checks that HTTP outside the allow-list
+/// is refused.
+#[cfg(test)]
+const ECHO_PLUGIN: &str = r#"
+(module
+  (import "host" "progress" (func $progress (param f32)))
+  (import "host" "checkpoint" (func $checkpoint (result i32)))
+  (import "host" "set_error" (func $set_error (param i32 i32)))
+  (import "host" "temp_write" (func $temp_write (param i32 i32 i32 i32) (result i32)))
+  (import "host" "temp_read" (func $temp_read (param i32 i32 i32 i32) (result i32)))
+  (import "host" "http_get" (func $http_get (param i32 i32 i32 i32) (result i32)))
+  (memory (export "memory") 1)
+  (data (i32.const 0) "echo")
+  (data (i32.const 16) "[{\"name\": \"text\", \"type\": \"string\", \"default\": \"hi\"}]")
+  (data (i32.const 128) "out.txt")
+  (data (i32.const 144) "http://example.com/")
+  (data (i32.const 176) "http_get was not refused")
+  (func (export "plugin_abi_version") (result i32) (i32.const ABI_VERSION))
+  (func (export "plugin_name") (result i64) (i64.const 4))
+  (func (export "plugin_param_schema") (result i64)
+    (i64.or (i64.shl (i64.const 16) (i64.const 32)) (i64.const 53)))
+  (func (export "alloc") (param i32) (result i32) (i32.const 1024))
+  (func (export "run") (param $ptr i32) (param $len i32) (result i32)
+    (call $progress (f32.const 0.5))
+    (if (call $checkpoint) (then (return (i32.const 0))))
+    (drop (call $temp_write (i32.const 128) (i32.const 7) (local.get $ptr) (local.get $len)))
+    (if (i32.ne
+          (call $temp_read (i32.const 128) (i32.const 7) (i32.const 2048) (i32.const 256))
+          (local.get $len))
+      (then (return (i32.const 2))))
+    (if (i32.ne
+          (call $http_get (i32.const 144) (i32.const 19) (i32.const 4096) (i32.const 16))
+          (i32.const -1))
+      (then
+        (call $set_error (i32.const 176) (i32.const 24))
+        (return (i32.const 3))))
+    (i32.const 0)))
+"#;
+
+#[cfg(test)]
+fn plugin_dir(name: &str, abi_version: u32) -> std::path::PathBuf {
+    let dir = std::env::temp_dir().join(format!("{}_{}", name, std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(
+        dir.join("echo.wasm"),
+        ECHO_PLUGIN.replace("ABI_VERSION", &abi_version.to_string()),
+    )
+    .unwrap();
+    dir
+}
+
+#[test]
+fn test_wasm_plugin_runs_in_sandbox() {
+    let dir = plugin_dir("wasm_plugin_run", crate::app::plugins::PLUGIN_ABI_VERSION);
+    let mut registry = TaskKindRegistry::default();
+    let errors = load_wasm_plugins(&dir, &mut registry, &[]);
+    assert!(errors.is_empty(), "{:?}", errors);
+    assert_eq!(registry.get("echo").unwrap().params[0].name, "text");
+
+    let task_queue = TaskQueue::new();
+    let task = registry.create("echo", &TaskParams::new()).unwrap();
+    assert_eq!(task.kind(), TaskKind::Plugin("echo".to_owned()));
+    let task_id = task_queue.add_task(task);
+    let mut result = task_queue.poll_task(task_id).unwrap();
+    for _ in 0..200 {
+        if result == PollResult::Completed || result == PollResult::Cancelled {
+            break;
+        }
+        std::thread::sleep(std::time::Duration::from_millis(10));
+        result = task_queue.poll_task(task_id).unwrap();
+    }
+    assert_eq!(result, PollResult::Completed);
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn test_wasm_plugin_abi_mismatch() {
+    let dir = plugin_dir(
+        "wasm_plugin_abi",
+        crate::app::plugins::PLUGIN_ABI_VERSION + 1,
+    );
+    let mut registry = TaskKindRegistry::default();
+    let errors = load_wasm_plugins(&dir, &mut registry, &[]);
+    assert!(matches!(errors[..], [PluginError::AbiMismatch { .. }]));
+    assert!(registry.get("echo").is_none());
+    std::fs::remove_dir_all(dir).unwrap();
+}
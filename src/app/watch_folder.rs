@@ -0,0 +1,78 @@
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver};
+
+use notify::event::{CreateKind, ModifyKind, RenameMode};
+use notify::{Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+
+use crate::app::config::WatchRule;
+use crate::app::launch_args::TaskSpec;
+
+const PATH_PLACEHOLDER: &str = "{path}";
+
+/// Reports files created in, or moved into, a folder.
+pub struct FolderWatcher {
+    folder: PathBuf,
+    receiver: Receiver<PathBuf>,
+    // Dropping the watcher stops the notifications.
+    _watcher: RecommendedWatcher,
+}
+
+impl FolderWatcher {
+    pub fn new(folder: &Path) -> notify::Result<Self> {
+        let (sender, receiver) = mpsc::channel();
+        let mut watcher =
+            notify::recommended_watcher(move |event: notify::Result<Event>| match event {
+                Ok(event) if is_arrival(&event.kind) => {
+                    for path in event.paths {
+                        let _ = sender.send(path);
+                    }
+                }
+                Ok(_) => {}
+                Err(e) => log::warn!("Watch folder error: {}", e),
+            })?;
+        watcher.watch(folder, RecursiveMode::NonRecursive)?;
+        Ok(FolderWatcher {
+            folder: folder.to_owned(),
+            receiver,
+            _watcher: watcher,
+        })
+    }
+
+    pub fn folder(&self) -> &Path {
+        &self.folder
+    }
+
+    /// Returns the next file that arrived since the last call, if any.
+    pub fn try_recv(&self) -> Option<PathBuf> {
+        self.receiver.try_iter().find(|path| path.is_file())
+    }
+}
+
+fn is_arrival(kind: &EventKind) -> bool {
+    matches!(
+        kind,
+        EventKind::Create(CreateKind::File | CreateKind::Any)
+            | EventKind::Modify(ModifyKind::Name(RenameMode::To))
+    )
+}
+
+/// The task the first matching rule makes for `path`, with `{path}` filled in.
+pub fn task_for_file(rules: &[WatchRule], path: &Path) -> Option<TaskSpec> {
+    let extension = path.extension()?.to_str()?;
+    let rule = rules.iter().find(|rule| {
+        rule.extensions.iter().any(|candidate| {
+            candidate
+                .trim_start_matches('.')
+                .eq_ignore_ascii_case(extension)
+        })
+    })?;
+    let path = path.display().to_string();
+    Some(TaskSpec {
+        kind: rule.kind.clone(),
+        params: rule
+            .params
+            .iter()
+            .map(|(name, value)| (name.clone(), value.replace(PATH_PLACEHOLDER, &path)))
+            .collect(),
+    })
+}
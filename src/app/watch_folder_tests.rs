@@ -0,0 +1,56 @@
+#[cfg(test)]
+use std::path::Path;
+
+#[cfg(test)]
+use crate::app::config::WatchRule;
+#[cfg(test)]
+use crate::app::watch_folder::{task_for_file, FolderWatcher};
+
+#[cfg(test)]
+fn rules() -> Vec<WatchRule> {
+    vec![
+        WatchRule {
+            extensions: vec!["zip".to_owned()],
+            kind: "extract".to_owned(),
+            params: [("archive".to_owned(), "{path}".to_owned())].into(),
+        },
+        WatchRule {
+            extensions: vec!["jpg".to_owned(), ".PNG".to_owned()],
+            kind: "convert".to_owned(),
+            params: [("format".to_owned(), "webp".to_owned())].into(),
+        },
+    ]
+}
+
+#[test]
+fn test_task_for_file() {
+    let spec = task_for_file(&rules(), Path::new("/in/a.zip")).unwrap();
+    assert_eq!(spec.kind, "extract");
+    assert_eq!(spec.params["archive"], "/in/a.zip");
+
+    let spec = task_for_file(&rules(), Path::new("/in/B.png")).unwrap();
+    assert_eq!(spec.kind, "convert");
+    assert_eq!(spec.params["format"], "webp");
+
+    assert!(task_for_file(&rules(), Path::new("/in/notes.txt")).is_none());
+    assert!(task_for_file(&rules(), Path::new("/in/README")).is_none());
+}
+
+#[test]
+fn test_watcher_reports_new_files() {
+    let dir = std::env::temp_dir().join(format!("watch_folder_test_{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let watcher = FolderWatcher::new(&dir).unwrap();
+    std::fs::write(dir.join("new.zip"), b"zip").unwrap();
+
+    let mut arrived = None;
+    for _ in 0..100 {
+        arrived = watcher.try_recv();
+        if arrived.is_some() {
+            break;
+        }
+        std::thread::sleep(std::time::Duration::from_millis(20));
+    }
+    assert_eq!(arrived, Some(dir.join("new.zip")));
+    std::fs::remove_dir_all(dir).unwrap();
+}
@@ -0,0 +1,77 @@
+//! The config and unfinished tasks of the web build, kept in the browser's localStorage
+//! so that reloading the page restores them.
+//!
+//! eframe already saves the app state there, but the config has no file to live in on
+//! the web, and tasks are not part of the app state. Tasks are saved as the kind and
+//! parameters they were created from and start over when restored; progress is not kept.
+
+use crate::app::config::{AppConfig, ConfigError};
+use crate::app::registry::TaskParams;
+
+const CONFIG_KEY: &str = "functional_rust_ui_demo.config";
+const TASKS_KEY: &str = "functional_rust_ui_demo.tasks";
+
+/// What is needed to create a task again through the registry.
+#[derive(Debug, Clone, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct SavedTask {
+    pub kind: String,
+    #[serde(default)]
+    pub params: TaskParams,
+}
+
+fn local_storage() -> Result<web_sys::Storage, ConfigError> {
+    web_sys::window()
+        .and_then(|window| window.local_storage().ok().flatten())
+        .ok_or_else(|| ConfigError::Io("localStorage is not available".to_owned()))
+}
+
+fn read(key: &str) -> Result<Option<String>, ConfigError> {
+    local_storage()?
+        .get_item(key)
+        .map_err(|e| ConfigError::Io(format!("Cannot read {}: {:?}", key, e)))
+}
+
+fn write(key: &str, value: &str) -> Result<(), ConfigError> {
+    local_storage()?
+        .set_item(key, value)
+        .map_err(|e| ConfigError::Io(format!("Cannot write {}: {:?}", key, e)))
+}
+
+/// The saved config, or the defaults if none was saved yet.
+pub fn load_config() -> Result<AppConfig, ConfigError> {
+    match read(CONFIG_KEY)? {
+        Some(toml) => AppConfig::from_toml_str(&toml),
+        None => Ok(AppConfig::default()),
+    }
+}
+
+/// Saves the config as TOML, the same as `config.toml` on desktop.
+pub fn save_config(config: &AppConfig) -> Result<(), ConfigError> {
+    write(CONFIG_KEY, &config.to_toml_string()?)
+}
+
+/// The tasks that were unfinished when the page was last saved. A corrupt entry is
+/// logged and treated as empty.
+pub fn load_tasks() -> Vec<SavedTask> {
+    let json = match read(TASKS_KEY) {
+        Ok(Some(json)) => json,
+        Ok(None) => return Vec::new(),
+        Err(e) => {
+            log::error!("{}", e);
+            return Vec::new();
+        }
+    };
+    serde_json::from_str(&json).unwrap_or_else(|e| {
+        log::error!("Cannot restore the saved tasks: {}", e);
+        Vec::new()
+    })
+}
+
+pub fn save_tasks(tasks: &[SavedTask]) {
+    let result = serde_json::to_string(tasks)
+        .map_err(|e| ConfigError::Parse(e.to_string()))
+        .and_then(|json| write(TASKS_KEY, &json));
+    if let Err(e) = result {
+        log::error!("Cannot save the unfinished tasks: {}", e);
+    }
+}
@@ -0,0 +1,150 @@
+use std::sync::mpsc::{Receiver, RecvTimeoutError};
+use std::time::{Duration, Instant};
+
+use reqwest::blocking::Client;
+use reqwest::header::CONTENT_TYPE;
+use reqwest::StatusCode;
+
+use crate::app::config::WebhookConfig;
+use crate::app::history::TaskRecord;
+
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+const RETRY_BASE_DELAY: Duration = Duration::from_secs(2);
+const MAX_RETRY_DELAY: Duration = Duration::from_secs(300);
+
+/// A payload waiting to be (re)sent to one webhook.
+struct Delivery {
+    hook: usize,
+    body: String,
+    attempt: u32,
+    due: Instant,
+}
+
+enum Failure {
+    Retry(String),
+    GiveUp(String),
+}
+
+/// Fills the placeholders in `template`, or serializes the whole record when there is none.
+///
+/// String values are JSON-escaped, so they can be placed inside quotes in the template.
+pub fn render_payload(template: Option<&str>, record: &TaskRecord) -> String {
+    let Some(template) = template else {
+        return serde_json::to_string(record).unwrap_or_default();
+    };
+    let duration_secs = match (record.started_at, record.finished_at) {
+        (Some(started), Some(finished)) => {
+            format!("{:.1}", finished.saturating_sub(started) as f64 / 1000.0)
+        }
+        _ => "0".to_owned(),
+    };
+    template
+        .replace("{{id}}", &record.id.to_string())
+        .replace("{{kind}}", &escape(&record.kind))
+        .replace("{{status}}", &record.status.to_string())
+        .replace("{{duration_secs}}", &duration_secs)
+}
+
+fn escape(value: &str) -> String {
+    let quoted = serde_json::to_string(value).unwrap_or_default();
+    quoted[1..quoted.len() - 1].to_owned()
+}
+
+/// How long to wait before retry number `attempt` (starting at 1).
+pub fn retry_delay(attempt: u32) -> Duration {
+    RETRY_BASE_DELAY
+        .checked_mul(1 << attempt.saturating_sub(1).min(16))
+        .map_or(MAX_RETRY_DELAY, |delay| delay.min(MAX_RETRY_DELAY))
+}
+
+/// POSTs to `hooks` as matching records arrive on `events`, retrying failed deliveries
+/// with exponential backoff. Pending retries are still attempted after the queue drops
+/// its end of the channel; the thread exits once they are done.
+pub fn spawn_dispatcher(hooks: Vec<WebhookConfig>, events: Receiver<TaskRecord>) {
+    std::thread::spawn(move || {
+        let client = match Client::builder().timeout(REQUEST_TIMEOUT).build() {
+            Ok(client) => client,
+            Err(e) => {
+                log::error!("Cannot create webhook client: {}", e);
+                return;
+            }
+        };
+        let mut pending: Vec<Delivery> = Vec::new();
+        let mut closed = false;
+        loop {
+            let next_due = pending.iter().map(|delivery| delivery.due).min();
+            let received = match (closed, next_due) {
+                (true, None) => return,
+                (true, Some(due)) => {
+                    std::thread::sleep(due.saturating_duration_since(Instant::now()));
+                    Err(RecvTimeoutError::Timeout)
+                }
+                (false, None) => events.recv().map_err(|_| RecvTimeoutError::Disconnected),
+                (false, Some(due)) => {
+                    events.recv_timeout(due.saturating_duration_since(Instant::now()))
+                }
+            };
+            match received {
+                Ok(record) => {
+                    for (index, hook) in hooks.iter().enumerate() {
+                        if hook.on.contains(&record.status) {
+                            pending.push(Delivery {
+                                hook: index,
+                                body: render_payload(hook.payload.as_deref(), &record),
+                                attempt: 0,
+                                due: Instant::now(),
+                            });
+                        }
+                    }
+                }
+                Err(RecvTimeoutError::Timeout) => {}
+                Err(RecvTimeoutError::Disconnected) => closed = true,
+            }
+
+            let now = Instant::now();
+            let (due, waiting): (Vec<_>, Vec<_>) =
+                pending.drain(..).partition(|delivery| delivery.due <= now);
+            pending = waiting;
+            for mut delivery in due {
+                let hook = &hooks[delivery.hook];
+                match send(&client, hook, &delivery.body) {
+                    Ok(()) => log::debug!("Webhook delivered to {}", hook.url),
+                    Err(Failure::Retry(e)) if delivery.attempt < hook.max_retries => {
+                        delivery.attempt += 1;
+                        let delay = retry_delay(delivery.attempt);
+                        log::warn!(
+                            "Webhook to {} failed ({}), retrying in {:?}",
+                            hook.url,
+                            e,
+                            delay
+                        );
+                        delivery.due = Instant::now() + delay;
+                        pending.push(delivery);
+                    }
+                    Err(Failure::Retry(e) | Failure::GiveUp(e)) => {
+                        log::error!("Webhook to {} dropped: {}", hook.url, e)
+                    }
+                }
+            }
+        }
+    });
+}
+
+fn send(client: &Client, hook: &WebhookConfig, body: &str) -> Result<(), Failure> {
+    let mut request = client
+        .post(&hook.url)
+        .header(CONTENT_TYPE, "application/json")
+        .body(body.to_owned());
+    for (name, value) in &hook.headers {
+        request = request.header(name, value);
+    }
+    let response = request.send().map_err(|e| Failure::Retry(e.to_string()))?;
+    let status = response.status();
+    if status.is_success() {
+        Ok(())
+    } else if status.is_server_error() || status == StatusCode::TOO_MANY_REQUESTS {
+        Err(Failure::Retry(status.to_string()))
+    } else {
+        Err(Failure::GiveUp(status.to_string()))
+    }
+}
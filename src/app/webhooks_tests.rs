@@ -0,0 +1,48 @@
+#[cfg(test)]
+use std::time::Duration;
+
+#[cfg(test)]
+use crate::app::config::AppConfig;
+#[cfg(test)]
+use crate::app::history::TaskRecord;
+#[cfg(test)]
+use crate::app::task_id::TaskId;
+#[cfg(test)]
+use crate::app::task_queue::TaskStatus;
+#[cfg(test)]
+use crate::app::webhooks::{render_payload, retry_delay};
+
+#[test]
+fn test_render_payload() {
+    let mut record = TaskRecord::new(TaskId::detached(7), "say \"hi\"");
+    record.status = TaskStatus::Completed;
+    record.started_at = Some(1_000);
+    record.finished_at = Some(3_500);
+    let body = render_payload(
+        Some(r#"{"text": "{{kind}} task {{id}} is {{status}} after {{duration_secs}}s"}"#),
+        &record,
+    );
+    let body: serde_json::Value = serde_json::from_str(&body).unwrap();
+    assert_eq!(body["text"], "say \"hi\" task 7 is completed after 2.5s");
+
+    let raw: TaskRecord = serde_json::from_str(&render_payload(None, &record)).unwrap();
+    assert_eq!(raw, record);
+}
+
+#[test]
+fn test_webhook_config_and_backoff() {
+    let config = AppConfig::from_toml_str(
+        r#"
+        [[webhooks]]
+        url = "http://localhost:9000/hook"
+        on = ["Completed"]
+        "#,
+    )
+    .unwrap();
+    assert_eq!(config.webhooks[0].on, vec![TaskStatus::Completed]);
+    assert_eq!(config.webhooks[0].max_retries, 5);
+
+    assert_eq!(retry_delay(1), Duration::from_secs(2));
+    assert_eq!(retry_delay(3), Duration::from_secs(8));
+    assert_eq!(retry_delay(40), Duration::from_secs(300));
+}
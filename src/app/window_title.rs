@@ -0,0 +1,21 @@
+//! The window title: the instance's name, led by how far along its tasks are while any
+//! run, so that several instances can be told apart and checked at a glance on a taskbar.
+
+use crate::app::format;
+
+pub const APP_NAME: &str = "Functional Rust UI Demo";
+
+/// E.g. "3 running, 42% — render box", or just "render box" when nothing runs. `progress`
+/// is the running tasks' mean progress fraction; unnamed instances go by [`APP_NAME`].
+pub fn window_title(name: Option<&str>, running: usize, progress: f32) -> String {
+    let name = name.unwrap_or(APP_NAME);
+    if running == 0 {
+        return name.to_owned();
+    }
+    format!(
+        "{} running, {} — {}",
+        format::count(running),
+        format::percent(progress.clamp(0.0, 1.0), 0),
+        name
+    )
+}
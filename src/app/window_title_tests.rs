@@ -0,0 +1,25 @@
+#[cfg(test)]
+use crate::app::launch_args::LaunchArgs;
+#[cfg(test)]
+use crate::app::window_title::{window_title, APP_NAME};
+
+#[test]
+fn test_window_title() {
+    assert_eq!(window_title(None, 0, 0.0), APP_NAME);
+    assert_eq!(window_title(Some("render box"), 0, 0.5), "render box");
+    assert_eq!(
+        window_title(Some("render box"), 3, 0.424),
+        "3 running, 42% — render box"
+    );
+}
+
+#[test]
+fn test_name_argument() {
+    let args: Vec<String> = ["--name", "render box", "sleep"]
+        .iter()
+        .map(|arg| arg.to_string())
+        .collect();
+    let launch = LaunchArgs::parse(&args).unwrap();
+    assert_eq!(launch.name.as_deref(), Some("render box"));
+    assert_eq!(launch.tasks.len(), 1);
+}
@@ -1,4 +1,26 @@
 #![warn(clippy::all, rust_2018_idioms)]
+// puffin's scope macros expand to std items newer than our MSRV; puffin itself needs
+// Rust 1.76, so the `profiling` feature does too.
+#![cfg_attr(feature = "profiling", allow(clippy::incompatible_msrv))]
 
 mod app;
+pub mod queue;
+#[cfg(all(feature = "ui", not(target_arch = "wasm32")))]
+pub use crate::app::assets;
+#[cfg(all(feature = "plugins", not(target_arch = "wasm32")))]
+pub use crate::app::plugins::api as plugin_api;
+#[cfg(all(feature = "remote-agent", not(target_arch = "wasm32")))]
+pub use crate::app::remote_agent;
+/// For the benches, which load the queue the way the stress test does.
+#[doc(hidden)]
+pub use crate::app::stress;
+#[cfg(feature = "ui")]
 pub use crate::app::template_ui::TemplateApp;
+#[cfg(all(feature = "self-update", not(target_arch = "wasm32")))]
+pub use crate::app::updater;
+#[cfg(not(target_arch = "wasm32"))]
+pub use crate::app::{
+    config::AppConfig, launch_args, launch_args::LaunchArgs, rpc_stdio, single_instance,
+    warm_start, window_title,
+};
+pub use crate::queue::TaskQueue;
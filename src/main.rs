@@ -1,39 +1,133 @@
 #![warn(clippy::all, rust_2018_idioms)]
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")] // hide console window on Windows in release
 
-fn load_icon(path: &str) -> eframe::IconData {
-    let (icon_rgba, icon_width, icon_height) = {
-        let icon = image::open(path).expect("Failed to load icon").into_rgba8();
-        let (width, height) = icon.dimensions();
-        let rgba = icon.into_raw();
-        (rgba, width, height)
-    };
-
-    eframe::IconData {
-        rgba: icon_rgba,
-        width: icon_width,
-        height: icon_height,
-    }
-}
+#[cfg(not(target_arch = "wasm32"))]
+use functional_rust_ui_demo::assets::Assets;
+#[cfg(not(target_arch = "wasm32"))]
+use functional_rust_ui_demo::launch_args::{self, LaunchArgs, LaunchArgsError};
+#[cfg(all(feature = "remote-agent", not(target_arch = "wasm32")))]
+use functional_rust_ui_demo::remote_agent;
+#[cfg(not(target_arch = "wasm32"))]
+use functional_rust_ui_demo::rpc_stdio;
+#[cfg(not(target_arch = "wasm32"))]
+use functional_rust_ui_demo::single_instance::{self, InstanceRole};
+#[cfg(all(feature = "self-update", not(target_arch = "wasm32")))]
+use functional_rust_ui_demo::updater;
+#[cfg(not(target_arch = "wasm32"))]
+use functional_rust_ui_demo::warm_start;
+#[cfg(not(target_arch = "wasm32"))]
+use functional_rust_ui_demo::window_title::window_title;
+#[cfg(not(target_arch = "wasm32"))]
+use functional_rust_ui_demo::AppConfig;
 
 // When compiling natively:
 #[cfg(not(target_arch = "wasm32"))]
 fn main() -> eframe::Result<()> {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let launch = match LaunchArgs::parse(&args) {
+        Ok(launch) => launch,
+        Err(LaunchArgsError::Info(text)) => {
+            print!("{}", text);
+            return Ok(());
+        }
+        Err(e) => {
+            eprintln!("{}", e);
+            std::process::exit(2);
+        }
+    };
+    if let Some(shell) = launch.completions {
+        launch_args::write_completions(shell, &mut std::io::stdout());
+        return Ok(());
+    }
+    launch.apply_config_overrides();
+
+    if let Some(tasks) = launch.bench_warm_start {
+        let config = AppConfig::default_path()
+            .and_then(|path| AppConfig::load_or_default(&path))
+            .unwrap_or_else(|e| {
+                eprintln!("{}, using the default config", e);
+                AppConfig::default()
+            });
+        match warm_start::run(config.store, tasks) {
+            Ok(report) => print!("{}", report),
+            Err(e) => {
+                eprintln!("{}", e);
+                std::process::exit(1);
+            }
+        }
+        return Ok(());
+    }
+
+    if launch.rpc_stdio {
+        // stdout carries the protocol, so logs go to stderr.
+        tracing_subscriber::fmt()
+            .with_writer(std::io::stderr)
+            .init();
+        if let Err(e) = rpc_stdio::run() {
+            log::error!("JSON-RPC session ended: {}", e);
+            std::process::exit(1);
+        }
+        return Ok(());
+    }
+
+    if launch.agent {
+        tracing_subscriber::fmt::init();
+        #[cfg(feature = "remote-agent")]
+        let result = remote_agent::run_agent().map_err(|e| e.to_string());
+        #[cfg(not(feature = "remote-agent"))]
+        let result: Result<(), String> =
+            Err("--agent needs a build with the `remote-agent` feature".to_owned());
+        if let Err(e) = result {
+            log::error!("Agent stopped: {}", e);
+            std::process::exit(1);
+        }
+        return Ok(());
+    }
+
     // Log to stdout (if you run with `RUST_LOG=debug`).
     tracing_subscriber::fmt::init();
+    #[cfg(feature = "self-update")]
+    match updater::apply_staged_update() {
+        Ok(Some(version)) => {
+            log::info!("Updated to version {}, restarting", version);
+            match std::env::current_exe()
+                .and_then(|exe| std::process::Command::new(exe).args(&args).spawn())
+            {
+                Ok(_) => return Ok(()),
+                // This process still runs the old version; the new one starts next time.
+                Err(e) => log::error!("Cannot restart after updating: {}", e),
+            }
+        }
+        Ok(None) => {}
+        Err(e) => log::error!("Cannot install the downloaded update: {}", e),
+    }
+    let instance = if launch.new_instance {
+        None
+    } else {
+        match single_instance::acquire(&args) {
+            Ok(InstanceRole::Primary(server)) => Some(server),
+            Ok(InstanceRole::Forwarded) => return Ok(()),
+            Err(e) => {
+                log::warn!("Single-instance guard unavailable: {}", e);
+                None
+            }
+        }
+    };
 
     let native_options = eframe::NativeOptions {
-        icon_data: Some(load_icon("assets/tesseract-logo-houndstoothed-alpha.ico")),
-        initial_window_size: Some([960.0, 480.0].into()),
+        icon_data: Assets::from_default_config().window_icon(),
+        initial_window_size: Some(launch.window_size.unwrap_or([960.0, 480.0]).into()),
         min_window_size: Some([768.0, 480.0].into()),
         transparent: true,
         centered: true,
         ..Default::default()
     };
     eframe::run_native(
-        "Functional Rust UI Demo",
+        &window_title(launch.name.as_deref(), 0, 0.0),
         native_options,
-        Box::new(|cc| Box::new(functional_rust_ui_demo::TemplateApp::new(cc))),
+        Box::new(move |cc| {
+            Box::new(functional_rust_ui_demo::TemplateApp::new(cc).with_launch(&launch, instance))
+        }),
     )
 }
 
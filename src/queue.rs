@@ -0,0 +1,52 @@
+//! The task queue on its own, for programs that run tasks without the UI. Nothing here
+//! depends on egui: a program adds [`Task`]s to a [`TaskQueue`], polls them as often as
+//! it likes, and reads their status, records and history. Without the default `ui`
+//! feature, egui and the app are not built at all.
+//!
+//! ```
+//! use std::time::Duration;
+//!
+//! use functional_rust_ui_demo::queue::{PollResult, SleepTask, TaskQueue, TaskStatus};
+//!
+//! // Run at most two tasks at once; the rest wait their turn by priority.
+//! let queue = TaskQueue::with_concurrency(2);
+//! let id = queue.add_task(SleepTask::new(None, Duration::from_millis(10)));
+//! while !matches!(queue.poll_task(id)?, PollResult::Completed) {
+//!     std::thread::sleep(Duration::from_millis(5));
+//! }
+//! assert_eq!(queue.status(id)?, TaskStatus::Completed);
+//! # Ok::<(), functional_rust_ui_demo::queue::TaskError>(())
+//! ```
+//!
+//! Polling drives a task; a program that would rather not poll in a loop can wait for
+//! changes with [`TaskQueue::subscribe`] or [`TaskQueue::set_on_change`] and poll then.
+//! A task that pushes its progress through its [`ProgressSender`] is not asked for it on
+//! every poll, so polling often costs little.
+//!
+//! CPU-heavy work such as hashing or compression belongs in a [`BlockingTask`], which runs
+//! its closure away from the async workers, or in a [`JobTask`] when the closure should
+//! report progress and honour pause and cancel. Either takes `cpu_bound()` to run on a
+//! CPU pool with a thread per core instead of the runtime's blocking pool. Work cut into
+//! short steps can also be a [`ChunkedTask`], which needs no thread of its own.
+
+#[cfg(not(target_arch = "wasm32"))]
+pub use crate::app::blocking_task::BlockingTask;
+pub use crate::app::cancellation::CancellationToken;
+pub use crate::app::chunked_task::{ChunkedTask, Step, StepFn};
+pub use crate::app::color_tag::ColorTag;
+pub use crate::app::deadline::DeadlineAtRisk;
+pub use crate::app::executor::{set_worker_limit, worker_limit};
+pub use crate::app::history::{Artifact, HistoryRetention, TaskRecord};
+#[cfg(not(target_arch = "wasm32"))]
+pub use crate::app::job_task::{JobContext, JobTask};
+pub use crate::app::priority::Priority;
+pub use crate::app::progress_channel::ProgressSender;
+pub use crate::app::sleep_task::SleepTask;
+#[cfg(all(feature = "sqlite", not(target_arch = "wasm32")))]
+pub use crate::app::store::{sqlite::SqliteStore, QueueStore, StoreError};
+pub use crate::app::task_group::TaskGroup;
+pub use crate::app::task_id::TaskId;
+pub use crate::app::task_queue::{
+    PollResult, PollingData, ProgressEvent, ProgressGranularity, QueueStats, RecurrenceDetail,
+    Task, TaskBuilder, TaskDetail, TaskError, TaskEvent, TaskKind, TaskMeta, TaskQueue, TaskStatus,
+};